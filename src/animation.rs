@@ -0,0 +1,201 @@
+use shipyard::{Get, UniqueView, UniqueViewMut, View, World};
+
+use crate::{
+    chunked::ChunkedMapGrid,
+    components::{Coord, FieldOfView},
+    gamesym::GameSym,
+    player::PlayerId,
+    render,
+    ui::Options,
+};
+use ruggrogue::{
+    util::{Color, Position, Size},
+    TileGrid,
+};
+
+/// Frames that a pop-up number stays on a row before floating up to the next one.
+const FRAMES_PER_ROW: u32 = 5;
+
+/// Frames that a pop-up number stays on screen in total.
+const POPUP_FRAMES: u32 = 15;
+
+/// Frames that a banner stays on screen.
+const BANNER_FRAMES: u32 = 90;
+
+/// Rows above the player that a banner is shown at.
+const BANNER_ROWS_ABOVE: i32 = 3;
+
+/// A short piece of text floating upwards from a map position.
+struct PopUp {
+    pos: Position,
+    text: String,
+    fg: Color,
+    age: u32,
+}
+
+impl PopUp {
+    /// Map position and size of the area the pop-up currently covers.
+    fn rect(&self) -> (Position, Size) {
+        let len = self.text.chars().count() as i32;
+
+        (
+            Position {
+                x: self.pos.x - (len - 1) / 2,
+                y: self.pos.y - (self.age / FRAMES_PER_ROW) as i32,
+            },
+            Size {
+                w: len as u32,
+                h: 1,
+            },
+        )
+    }
+}
+
+/// A line of text held still over the map for a while, e.g. announcing a new depth.
+struct Banner {
+    pos: Position,
+    text: String,
+    age: u32,
+}
+
+impl Banner {
+    /// Map position and size of the area the banner covers, with a space of padding on each side.
+    fn rect(&self) -> (Position, Size) {
+        let len = self.text.chars().count() as i32 + 2;
+
+        (
+            Position {
+                x: self.pos.x - (len - 1) / 2,
+                y: self.pos.y,
+            },
+            Size {
+                w: len as u32,
+                h: 1,
+            },
+        )
+    }
+}
+
+/// Queue of short-lived animations drawn over the map, advanced a frame at a time.
+///
+/// Animations are purely cosmetic, so they're never saved.
+pub struct Animations {
+    popups: Vec<PopUp>,
+    banner: Option<Banner>,
+}
+
+impl Animations {
+    pub fn new() -> Self {
+        Self {
+            popups: Vec::new(),
+            banner: None,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.popups.is_empty() && self.banner.is_none()
+    }
+
+    pub fn clear(&mut self) {
+        self.popups.clear();
+        self.banner = None;
+    }
+
+    /// Advance all animations by a frame, dropping finished ones.
+    ///
+    /// Returns the map areas covered before advancing, which need to be redrawn.
+    pub fn tick(&mut self) -> Vec<(Position, Size)> {
+        let mut dirty_rects = self.popups.iter().map(PopUp::rect).collect::<Vec<_>>();
+
+        for popup in self.popups.iter_mut() {
+            popup.age += 1;
+        }
+        self.popups.retain(|p| p.age < POPUP_FRAMES);
+
+        if let Some(banner) = &mut self.banner {
+            dirty_rects.push(banner.rect());
+            banner.age += 1;
+            if banner.age >= BANNER_FRAMES {
+                self.banner = None;
+            }
+        }
+
+        dirty_rects
+    }
+}
+
+/// Show a banner over the map a few rows above the player, replacing any banner already shown.
+pub fn show_banner(world: &World, text: String) {
+    let pos = {
+        let (player_id, coords) = world.borrow::<(UniqueView<PlayerId>, View<Coord>)>();
+        let Coord(player_pos) = coords.get(player_id.0);
+
+        Position {
+            x: player_pos.x,
+            y: player_pos.y - BANNER_ROWS_ABOVE,
+        }
+    };
+
+    world.borrow::<UniqueViewMut<Animations>>().banner = Some(Banner { pos, text, age: 0 });
+}
+
+/// Float a number up from a map position, if pop-up numbers are enabled and the player can see it.
+///
+/// Positive amounts are shown as heals and negative amounts as damage.
+pub fn pop_up_number(world: &World, pos: Position, amount: i32) {
+    if amount == 0 || !world.borrow::<UniqueView<Options>>().popup_numbers {
+        return;
+    }
+
+    let (player_id, fovs) = world.borrow::<(UniqueView<PlayerId>, View<FieldOfView>)>();
+
+    if fovs.get(player_id.0).get(pos.into()) {
+        world
+            .borrow::<UniqueViewMut<Animations>>()
+            .popups
+            .push(PopUp {
+                pos,
+                text: format!("{:+}", amount),
+                fg: if amount > 0 { Color::GREEN } else { Color::RED },
+                age: 0,
+            });
+    }
+}
+
+/// Draw pop-up numbers and the banner, if any, over the map.
+pub fn draw_popups(chunked_map_grid: &ChunkedMapGrid, world: &World, grid: &mut TileGrid<GameSym>) {
+    let animations = world.borrow::<UniqueView<Animations>>();
+    let options = world.borrow::<UniqueView<Options>>();
+
+    if let Some(banner) = &animations.banner {
+        let (pos, size) = banner.rect();
+        let padded = format!(" {} ", banner.text);
+
+        for (i, ch) in padded.chars().take(size.w as usize).enumerate() {
+            let map_pos = Position {
+                x: pos.x + i as i32,
+                y: pos.y,
+            };
+
+            if let Some(grid_pos) = chunked_map_grid.map_to_grid_pos(world, map_pos) {
+                grid.put_char_color(grid_pos, ch, Color::YELLOW, Color::BLACK);
+            }
+        }
+    }
+
+    for popup in animations.popups.iter() {
+        let (pos, _) = popup.rect();
+        let fg = render::palette_color(&options, popup.fg);
+
+        for (i, ch) in popup.text.chars().enumerate() {
+            let map_pos = Position {
+                x: pos.x + i as i32,
+                y: pos.y,
+            };
+
+            if let Some(grid_pos) = chunked_map_grid.map_to_grid_pos(world, map_pos) {
+                grid.put_char_color(grid_pos, ch, fg, Color::BLACK);
+            }
+        }
+    }
+}
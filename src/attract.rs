@@ -0,0 +1,218 @@
+use rand::{seq::SliceRandom, Rng};
+use shipyard::{AllStoragesViewMut, Get, UniqueView, UniqueViewMut, View, World};
+
+use crate::{
+    animation::Animations,
+    chest,
+    chunked::Camera,
+    components::Coord,
+    experience::{self, Difficulty},
+    game_loop::{self, PlayerAction},
+    journal::Journal,
+    map::{self, Map},
+    message::Messages,
+    player::{self, PlayerAlive, PlayerId},
+    quest::Quests,
+    spawn,
+    stats::RunStats,
+    vision, GameSeed, TurnCount,
+};
+
+/// Deepest level that the title screen might show; deeper levels have nastier monsters to watch.
+const MAX_DEPTH: i32 = 8;
+
+/// Turns the bot spends on a level before a new one is generated.
+const TURNS_PER_LEVEL: u32 = 300;
+
+/// Frames between the bot's turns, so that it wanders slowly enough to watch.
+const FRAMES_PER_TURN: u32 = 6;
+
+/// A bot that wanders a procedurally generated level behind the title screen.
+///
+/// The level is played out in the main world, which holds no game while the title screen is shown.
+/// It's never saved and doesn't count towards the player's statistics or leave bones.
+pub struct Attract {
+    active: bool,
+    /// Seed of the game to be played, kept aside while levels are generated from random seeds.
+    saved_seed: u64,
+    target: Option<(i32, i32)>,
+    turns: u32,
+    frames: u32,
+}
+
+impl Attract {
+    pub fn new() -> Self {
+        Self {
+            active: false,
+            saved_seed: 0,
+            target: None,
+            turns: 0,
+            frames: 0,
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+}
+
+/// Start the bot off on a new level, if it isn't already wandering one.
+pub fn start(world: &World) {
+    {
+        let mut attract = world.borrow::<UniqueViewMut<Attract>>();
+
+        if attract.active {
+            return;
+        }
+        attract.active = true;
+        attract.saved_seed = world.borrow::<UniqueView<GameSeed>>().0;
+    }
+
+    new_level(world);
+}
+
+/// Clear away the level the bot was wandering, leaving the world ready for a real game.
+pub fn stop(world: &World) {
+    let saved_seed = {
+        let mut attract = world.borrow::<UniqueViewMut<Attract>>();
+
+        if !attract.active {
+            return;
+        }
+        attract.active = false;
+        attract.saved_seed
+    };
+
+    world.run(player::remove_coords_from_players);
+    world.run(chest::remove_coords_from_chests);
+    world.run(spawn::despawn_coord_entities);
+    world.borrow::<UniqueViewMut<Animations>>().clear();
+    world.borrow::<UniqueViewMut<GameSeed>>().0 = saved_seed;
+}
+
+/// Generate a random level with a fresh player in it for the bot to wander.
+fn new_level(world: &World) {
+    world.run(player::remove_coords_from_players);
+    world.run(chest::remove_coords_from_chests);
+    world.run(spawn::despawn_coord_entities);
+
+    world.borrow::<UniqueViewMut<Messages>>().reset();
+    world.borrow::<UniqueViewMut<Journal>>().reset();
+    world.borrow::<UniqueViewMut<Quests>>().reset();
+    world.borrow::<UniqueViewMut<Animations>>().clear();
+    world.borrow::<UniqueViewMut<Map>>().clear();
+    world.borrow::<UniqueViewMut<PlayerAlive>>().0 = true;
+    world.borrow::<UniqueViewMut<TurnCount>>().0 = 1;
+    world.borrow::<UniqueViewMut<GameSeed>>().0 = rand::random();
+    world.borrow::<UniqueViewMut<Map>>().depth = rand::thread_rng().gen_range(1..=MAX_DEPTH);
+
+    // Replace the old player with a fresh one.
+    {
+        let player_id = world.borrow::<UniqueView<PlayerId>>().0;
+        spawn::despawn_entity(&mut world.borrow::<AllStoragesViewMut>(), player_id);
+        let new_player_id = world.run(spawn::spawn_player);
+        world.borrow::<UniqueViewMut<PlayerId>>().0 = new_player_id;
+    }
+
+    chest::despawn_chests(world);
+    world.borrow::<UniqueViewMut<RunStats>>().reset(1);
+
+    // Replace the old difficulty tracker with a fresh one at the default settings.
+    {
+        let difficulty_id = world.borrow::<UniqueView<Difficulty>>().id;
+        spawn::despawn_entity(&mut world.borrow::<AllStoragesViewMut>(), difficulty_id);
+        let new_difficulty = Difficulty::new(world.run(spawn::spawn_difficulty));
+        world
+            .borrow::<UniqueViewMut<Difficulty>>()
+            .replace(new_difficulty);
+    }
+
+    // The victory present only turns up on the final level, which is never picked here.
+    world.run(map::generate_rooms_and_corridors);
+    world.run(player::add_coords_to_players);
+    world.run(map::place_player_in_first_room);
+    spawn::fill_rooms_with_spawns(world);
+    world.run(experience::calc_exp_for_next_depth);
+    world.run(vision::recalculate_fields_of_view);
+
+    {
+        let mut attract = world.borrow::<UniqueViewMut<Attract>>();
+
+        attract.target = None;
+        attract.turns = 0;
+        attract.frames = 0;
+    }
+
+    follow_player(world);
+}
+
+fn follow_player(world: &World) {
+    let player_id = world.borrow::<UniqueView<PlayerId>>().0;
+    let player_pos = world.borrow::<View<Coord>>().get(player_id).0;
+
+    world.borrow::<UniqueViewMut<Camera>>().0 = player_pos;
+}
+
+/// Next step the bot should take towards a room it wants to visit, picking a new room once it
+/// reaches the old one or can't find its way there.
+fn next_step(world: &World) -> Option<(i32, i32)> {
+    let map = world.borrow::<UniqueView<Map>>();
+    let player_id = world.borrow::<UniqueView<PlayerId>>().0;
+    let (px, py): (i32, i32) = world.borrow::<View<Coord>>().get(player_id).0.into();
+    let mut attract = world.borrow::<UniqueViewMut<Attract>>();
+
+    for _ in 0..2 {
+        let target = match attract.target {
+            Some(target) if target != (px, py) => target,
+            _ => {
+                let room = map.rooms.choose(&mut rand::thread_rng())?;
+                room.center()
+            }
+        };
+
+        if let Some((x, y)) = ruggrogue::find_path(&*map, (px, py), target, 0, true).nth(1) {
+            attract.target = Some(target);
+            return Some((x - px, y - py));
+        }
+        attract.target = None;
+    }
+
+    None
+}
+
+/// Let the bot take a turn every so often, followed by everything else on the level.
+///
+/// Returns true if anything on the level may have changed.
+pub fn tick(world: &World) -> bool {
+    {
+        let mut attract = world.borrow::<UniqueViewMut<Attract>>();
+
+        if !attract.active {
+            return false;
+        }
+        attract.frames += 1;
+        if attract.frames < FRAMES_PER_TURN {
+            return false;
+        }
+        attract.frames = 0;
+        attract.turns += 1;
+    }
+
+    if let Some((dx, dy)) = next_step(world) {
+        game_loop::advance_turn(world, PlayerAction::Move(dx, dy));
+    }
+
+    // Nothing shows pop-ups or banners here, so don't let them pile up.
+    world.borrow::<UniqueViewMut<Animations>>().clear();
+    world.borrow::<UniqueViewMut<Map>>().take_changed_tiles();
+
+    let turns = world.borrow::<UniqueView<Attract>>().turns;
+
+    if !world.run(player::player_is_alive) || turns >= TURNS_PER_LEVEL {
+        new_level(world);
+    } else {
+        follow_player(world);
+    }
+
+    true
+}
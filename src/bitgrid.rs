@@ -68,3 +68,191 @@ impl BitGrid {
         }
     }
 }
+
+/// Set operations and fills for analyzing and carving out areas of maps.
+impl BitGrid {
+    /// Count the elements that are true.
+    pub fn count_ones(&self) -> usize {
+        self.bv.count_ones()
+    }
+
+    /// Set every element of the rectangle with its top-left corner at x and y to value.
+    ///
+    /// Parts of the rectangle outside of the grid are ignored.
+    pub fn fill_rect(&mut self, x: i32, y: i32, width: i32, height: i32, value: bool) {
+        let min_x = x.max(0);
+        let min_y = y.max(0);
+        let max_x = x.saturating_add(width).min(self.width);
+        let max_y = y.saturating_add(height).min(self.height);
+
+        for y in min_y..max_y {
+            if min_x < max_x {
+                let start = self.index(min_x, y);
+                let end = self.index(max_x, y);
+                self.bv[start..end].set_all(value);
+            }
+        }
+    }
+
+    /// Set every element that is true in the other grid.
+    ///
+    /// Panics if the grids are of different sizes.
+    pub fn union_with(&mut self, other: &BitGrid) {
+        assert!(self.width == other.width && self.height == other.height);
+        self.bv |= other.bv.iter().by_val();
+    }
+
+    /// Clear every element that is true in the other grid.
+    ///
+    /// Panics if the grids are of different sizes.
+    pub fn difference_with(&mut self, other: &BitGrid) {
+        assert!(self.width == other.width && self.height == other.height);
+        self.bv &= other.bv.iter().by_val().map(|b| !b);
+    }
+
+    /// Set every element reachable from x and y by stepping through elements for which
+    /// `passable` returns true, returning how many elements were set.
+    ///
+    /// Elements that are already true are treated as already filled, so they are neither set nor
+    /// stepped through.  Steps are only taken diagonally if `diagonals` is true.  Nothing is set
+    /// if the starting element is out of bounds, already true or not passable.
+    pub fn flood_fill<F>(&mut self, x: i32, y: i32, diagonals: bool, mut passable: F) -> usize
+    where
+        F: FnMut(i32, i32) -> bool,
+    {
+        const STEPS: [(i32, i32); 8] = [
+            (0, -1),
+            (1, 0),
+            (0, 1),
+            (-1, 0),
+            (1, -1),
+            (1, 1),
+            (-1, 1),
+            (-1, -1),
+        ];
+        let steps = if diagonals { &STEPS[..] } else { &STEPS[..4] };
+        let mut filled = 0;
+        let mut stack = Vec::new();
+
+        if x < 0 || y < 0 || x >= self.width || y >= self.height {
+            return 0;
+        }
+        if self.get_bit(x, y) || !passable(x, y) {
+            return 0;
+        }
+
+        self.set_bit(x, y, true);
+        filled += 1;
+        stack.push((x, y));
+
+        while let Some((x, y)) = stack.pop() {
+            for (dx, dy) in steps {
+                let (nx, ny) = (x + dx, y + dy);
+
+                if nx >= 0
+                    && ny >= 0
+                    && nx < self.width
+                    && ny < self.height
+                    && !self.get_bit(nx, ny)
+                    && passable(nx, ny)
+                {
+                    self.set_bit(nx, ny, true);
+                    filled += 1;
+                    stack.push((nx, ny));
+                }
+            }
+        }
+
+        filled
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 7x5 grid walled all around, with a wall down the middle that has a gap at the top:
+    ///
+    /// ```text
+    /// #######
+    /// #.....#
+    /// #..#..#
+    /// #..#..#
+    /// #######
+    /// ```
+    fn walls() -> BitGrid {
+        let mut walls = BitGrid::new(7, 5);
+
+        walls.fill_rect(0, 0, 7, 5, true);
+        walls.fill_rect(1, 1, 5, 3, false);
+        walls.fill_rect(3, 2, 1, 2, true);
+        walls
+    }
+
+    #[test]
+    fn flood_fill_stays_within_walls() {
+        let walls = walls();
+        let mut filled = BitGrid::new(7, 5);
+
+        assert_eq!(walls.count_ones(), 7 * 5 - 13);
+        assert_eq!(
+            filled.flood_fill(1, 3, false, |x, y| !walls.get_bit(x, y)),
+            13
+        );
+        assert!(filled.get_bit(5, 3));
+        assert!(!filled.get_bit(3, 2));
+        assert!(!filled.get_bit(0, 0));
+
+        // Filling again from inside the filled area finds nothing new.
+        assert_eq!(
+            filled.flood_fill(5, 1, true, |x, y| !walls.get_bit(x, y)),
+            0
+        );
+
+        // Closing the gap cuts the right side off, even stepping diagonally.
+        let mut walls = walls;
+        let mut filled = BitGrid::new(7, 5);
+
+        walls.set_bit(3, 1, true);
+        assert_eq!(
+            filled.flood_fill(1, 3, true, |x, y| !walls.get_bit(x, y)),
+            6
+        );
+        assert!(!filled.get_bit(4, 1));
+
+        // Starting out of bounds or in a wall fills nothing.
+        assert_eq!(filled.flood_fill(-1, 0, false, |_, _| true), 0);
+        assert_eq!(
+            filled.flood_fill(0, 0, false, |x, y| !walls.get_bit(x, y)),
+            0
+        );
+    }
+
+    #[test]
+    fn set_operation_counts() {
+        let mut a = BitGrid::new(6, 6);
+        let mut b = BitGrid::new(6, 6);
+
+        // Two 4x4 squares overlapping in a 2x2 square.
+        a.fill_rect(0, 0, 4, 4, true);
+        b.fill_rect(2, 2, 4, 4, true);
+
+        let mut union = BitGrid::new(6, 6);
+        union.union_with(&a);
+        union.union_with(&b);
+        assert_eq!(union.count_ones(), 16 + 16 - 4);
+
+        assert!(union.get_bit(0, 0) && union.get_bit(5, 5));
+        assert!(!union.get_bit(5, 0) && !union.get_bit(0, 5));
+
+        a.difference_with(&b);
+        assert_eq!(a.count_ones(), 16 - 4);
+        assert!(!a.get_bit(3, 3));
+        assert!(a.get_bit(0, 0));
+
+        // Rectangles hanging off of the grid are cut down to size.
+        let mut clipped = BitGrid::new(6, 6);
+        clipped.fill_rect(-2, 4, 4, 10, true);
+        assert_eq!(clipped.count_ones(), 2 * 2);
+    }
+}
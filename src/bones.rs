@@ -0,0 +1,218 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use shipyard::{EntityId, Get, UniqueView, UniqueViewMut, View, World};
+use std::fs;
+
+#[cfg(target_os = "emscripten")]
+use crate::ruggrogue_sync_idbfs;
+use crate::{
+    components::{
+        CombatBonus, Coord, EquipSlot, Equipment, Experience, Gravestone, Inventory, Name,
+        NameSource, Renderable, Weight,
+    },
+    gamesym::GameSym,
+    map::Map,
+    npc::GiftItem,
+    paths,
+    player::PlayerId,
+    rng::RngStream,
+    GameSeed,
+};
+
+pub(crate) const BONES_FILENAME: &str = "bones.json";
+
+/// A piece of equipment that the player had with them when they died.
+#[derive(Deserialize, Serialize)]
+pub struct BonesItem {
+    pub name: String,
+    /// What the name is made from; bones files from older versions only have the name itself.
+    #[serde(default)]
+    pub source: Option<NameSource>,
+    pub sym: GameSym,
+    pub fg: (u8, u8, u8),
+    pub weapon: bool,
+    pub attack: f32,
+    pub defense: f32,
+    #[serde(default)]
+    pub weight: i32,
+}
+
+/// Who died where, and with what.
+#[derive(Deserialize, Serialize)]
+pub struct Remains {
+    pub name: String,
+    pub level: i32,
+    pub pos: (i32, i32),
+    pub items: Vec<BonesItem>,
+    /// Other items that the player carried, left out if they can't be spawned again by name.
+    #[serde(default)]
+    pub pack: Vec<GiftItem>,
+}
+
+/// Contents of the bones file; the map is borrowed when writing and owned when reading.
+#[derive(Deserialize, Serialize)]
+struct BonesFile<M> {
+    #[serde(flatten)]
+    remains: Remains,
+    map: M,
+}
+
+/// Everything the player carries, including equipment and the contents of any containers.
+fn carried_items(world: &World, holder_id: EntityId) -> Vec<EntityId> {
+    let equipments = world.borrow::<View<Equipment>>();
+    let inventories = world.borrow::<View<Inventory>>();
+    let mut pending = Vec::new();
+    let mut carried = Vec::new();
+
+    if let Ok(equipment) = equipments.try_get(holder_id) {
+        pending.extend(equipment.weapon);
+        pending.extend(equipment.armor);
+    }
+    if let Ok(inventory) = inventories.try_get(holder_id) {
+        pending.extend(inventory.items.iter().copied());
+    }
+
+    while let Some(id) = pending.pop() {
+        if let Ok(inventory) = inventories.try_get(id) {
+            pending.extend(inventory.items.iter().copied());
+        }
+        carried.push(id);
+    }
+
+    carried
+}
+
+/// Write the player's death level and everything they carried to the bones file for future games
+/// to find in their grave.
+pub fn write_bones(world: &World) {
+    let player_id = world.borrow::<UniqueView<PlayerId>>().0;
+    let carried = carried_items(world, player_id);
+    let map = world.borrow::<UniqueView<Map>>();
+    let combat_bonuses = world.borrow::<View<CombatBonus>>();
+    let coords = world.borrow::<View<Coord>>();
+    let equip_slots = world.borrow::<View<EquipSlot>>();
+    let exps = world.borrow::<View<Experience>>();
+    let names = world.borrow::<View<Name>>();
+    let name_sources = world.borrow::<View<NameSource>>();
+    let renderables = world.borrow::<View<Renderable>>();
+    let weights = world.borrow::<View<Weight>>();
+    let items = carried
+        .iter()
+        .filter(|&&id| equip_slots.contains(id))
+        .map(|&id| {
+            let render = renderables.get(id);
+            let bonus = combat_bonuses.get(id);
+
+            BonesItem {
+                name: names.get(id).0.clone(),
+                source: name_sources.try_get(id).ok().cloned(),
+                sym: render.sym,
+                fg: (render.fg.r, render.fg.g, render.fg.b),
+                weapon: matches!(equip_slots.get(id), EquipSlot::Weapon),
+                attack: bonus.attack,
+                defense: bonus.defense,
+                weight: weights.try_get(id).map_or(0, |w| w.0),
+            }
+        })
+        .collect();
+    let pack = carried
+        .iter()
+        .filter_map(|&id| {
+            GiftItem::ALL.iter().copied().find(|gift| {
+                name_sources
+                    .try_get(id)
+                    .is_ok_and(|source| source.key() == gift.name())
+            })
+        })
+        .collect();
+    let bones = BonesFile {
+        remains: Remains {
+            name: name_sources
+                .try_get(player_id)
+                .map_or_else(|_| names.get(player_id).0.clone(), |s| s.key().to_string()),
+            level: exps.get(player_id).level,
+            pos: coords.get(player_id).0.into(),
+            items,
+            pack,
+        },
+        map: &*map,
+    };
+
+    match serde_json::to_string(&bones) {
+        Ok(contents) => {
+            let path = paths::data_file(BONES_FILENAME);
+
+            if let Err(e) = fs::write(&path, contents) {
+                eprintln!("Warning: Failed to write {}: {}", path.display(), e);
+            }
+        }
+        Err(e) => eprintln!("Warning: Failed to serialize bones: {}", e),
+    }
+
+    #[cfg(target_os = "emscripten")]
+    unsafe {
+        ruggrogue_sync_idbfs();
+    }
+}
+
+/// Occasionally replace the freshly-generated level with the one in the bones file if it was left
+/// at the current depth, returning the remains to be spawned in it along with their grave.  The
+/// bones file is used up when this happens.
+pub fn take_bones(world: &World) -> Option<Remains> {
+    let path = paths::data_file(BONES_FILENAME);
+    let contents = fs::read_to_string(&path).ok()?;
+    let bones = match serde_json::from_str::<BonesFile<Map>>(&contents) {
+        Ok(bones) => bones,
+        Err(e) => {
+            eprintln!("Warning: Failed to parse {}: {}", path.display(), e);
+            return None;
+        }
+    };
+    let mut map = world.borrow::<UniqueViewMut<Map>>();
+
+    if bones.map.depth != map.depth
+        || bones.map.width != map.width
+        || bones.map.height != map.height
+        || bones.map.rooms.is_empty()
+    {
+        return None;
+    }
+
+    let mut rng = world
+        .borrow::<UniqueView<GameSeed>>()
+        .fork(RngStream::Bones)
+        .mix_i32(map.depth)
+        .rng();
+
+    if !rng.gen_ratio(1, 3) {
+        return None;
+    }
+
+    if let Err(e) = fs::remove_file(&path) {
+        eprintln!("Warning: Failed to remove {}: {}", path.display(), e);
+    }
+
+    #[cfg(target_os = "emscripten")]
+    unsafe {
+        ruggrogue_sync_idbfs();
+    }
+
+    let mut bones_map = bones.map;
+
+    bones_map.seen.zero_out_bits();
+    map.replace(bones_map);
+
+    Some(bones.remains)
+}
+
+/// Find the grave of an earlier player at the given position.
+pub fn grave_at(world: &World, pos: (i32, i32)) -> Option<EntityId> {
+    let map = world.borrow::<UniqueView<Map>>();
+    let gravestones = world.borrow::<View<Gravestone>>();
+
+    let grave = map
+        .iter_entities_at(pos.0, pos.1)
+        .find(|id| gravestones.contains(*id));
+
+    grave
+}
@@ -0,0 +1,85 @@
+use shipyard::{
+    AllStoragesViewMut, EntitiesView, EntityId, IntoIter, Shiperator, UniqueView, UniqueViewMut,
+    View, ViewMut, World,
+};
+
+use crate::{
+    components::{Chest, Coord},
+    map::{Map, Tile},
+    spawn,
+};
+
+/// Take the coordinates off of the storage chest so that it survives the current map being
+/// despawned, to be put back with [place_chest].
+pub fn remove_coords_from_chests(mut coords: ViewMut<Coord>, chests: View<Chest>) {
+    let chest_ids = chests.iter().with_id().map(|(id, _)| id);
+
+    for id in chest_ids {
+        coords.remove(id);
+    }
+}
+
+/// Find the storage chest at the given position.
+pub fn chest_at(world: &World, pos: (i32, i32)) -> Option<EntityId> {
+    let map = world.borrow::<UniqueView<Map>>();
+    let chests = world.borrow::<View<Chest>>();
+
+    let chest = map
+        .iter_entities_at(pos.0, pos.1)
+        .find(|id| chests.contains(*id));
+
+    chest
+}
+
+/// Despawn the storage chest along with everything stored in it, e.g. when starting a fresh game.
+pub fn despawn_chests(world: &World) {
+    let chest_ids = world
+        .borrow::<View<Chest>>()
+        .iter()
+        .with_id()
+        .map(|(id, _)| id)
+        .collect::<Vec<EntityId>>();
+
+    for id in chest_ids {
+        spawn::despawn_entity(&mut world.borrow::<AllStoragesViewMut>(), id);
+    }
+}
+
+/// Find a spot for the storage chest in the first room, out of the way of the center lines that
+/// corridors run along.
+fn free_chest_pos(map: &Map) -> Option<(i32, i32)> {
+    map.rooms.first().and_then(|room| {
+        let (cx, cy) = room.center();
+
+        room.iter_xy().find(|&(x, y)| {
+            x != cx
+                && y != cy
+                && matches!(map.get_tile(x, y), Tile::Floor)
+                && map.iter_entities_at(x, y).next().is_none()
+        })
+    })
+}
+
+/// Put the storage chest in the first room of the current map, spawning it if it doesn't exist yet.
+///
+/// The chest is left off of the map for this depth if there's no room for it.
+pub fn place_chest(world: &World) {
+    let existing = world
+        .borrow::<View<Chest>>()
+        .iter()
+        .with_id()
+        .map(|(id, _)| id)
+        .next();
+    let chest_id = existing.unwrap_or_else(|| spawn::spawn_chest(world));
+    let pos = free_chest_pos(&world.borrow::<UniqueView<Map>>());
+
+    if let Some(pos) = pos {
+        let entities = world.borrow::<EntitiesView>();
+        let mut coords = world.borrow::<ViewMut<Coord>>();
+
+        world
+            .borrow::<UniqueViewMut<Map>>()
+            .place_entity(chest_id, pos, true);
+        entities.add_component(&mut coords, Coord(pos.into()), chest_id);
+    }
+}
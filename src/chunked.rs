@@ -1,14 +1,90 @@
-use shipyard::{Get, UniqueView, View, World};
-
-use crate::{components::FieldOfView, gamesym::GameSym, map::Map, player::PlayerId, ui::Options};
+use serde::{Deserialize, Serialize};
+use shipyard::{Get, UniqueView, UniqueViewMut, View, World};
+
+use crate::{
+    components::FieldOfView,
+    diagnostics::Diagnostics,
+    gamesym::GameSym,
+    map::Map,
+    player::PlayerId,
+    render,
+    ui::{self, Options},
+};
 use ruggrogue::{
     util::{Color, Position, Size},
     Symbol, TileGrid, Tileset,
 };
+use std::collections::HashMap;
 
 pub const CHUNK_TILE_WIDTH: i32 = 8;
 pub const CHUNK_TILE_HEIGHT: i32 = 8;
 
+/// Most map chunks that [ChunkedMapGrid::draw] will work out from scratch in a single call.
+///
+/// Any more dirty chunks than this are left for later calls, so that a screen full of new chunks
+/// is filled in over a few frames instead of stalling a single frame.
+const CHUNKS_PER_FRAME: usize = 12;
+
+/// How map tiles that are known but outside of the player's field of view are drawn.
+#[derive(Clone, Copy, Default, Deserialize, PartialEq, Eq, Serialize)]
+pub enum FogStyle {
+    /// Dim gray, like an old terminal roguelike.
+    #[default]
+    Gray,
+    /// Washed-out but still recognizable colors.
+    Desaturated,
+    /// Dim blue tint, as though seen through memory.
+    Memory,
+    /// Not drawn at all.
+    Hidden,
+}
+
+impl FogStyle {
+    pub const ALL: [FogStyle; 4] = [
+        FogStyle::Gray,
+        FogStyle::Desaturated,
+        FogStyle::Memory,
+        FogStyle::Hidden,
+    ];
+
+    /// Locale key of the name of the fog style.
+    pub fn name_key(self) -> &'static str {
+        match self {
+            FogStyle::Gray => "options.fog.gray",
+            FogStyle::Desaturated => "options.fog.desaturated",
+            FogStyle::Memory => "options.fog.memory",
+            FogStyle::Hidden => "options.fog.hidden",
+        }
+    }
+
+    /// Color to draw an out-of-view tile with, or None if it shouldn't be drawn.
+    pub fn apply(self, color: Color) -> Option<Color> {
+        let (r, g, b) = (color.r as i32, color.g as i32, color.b as i32);
+        let luma = (r * 30 + g * 59 + b * 11) / 100;
+
+        match self {
+            FogStyle::Gray => {
+                let v = (luma / 2) as u8;
+                Some(Color { r: v, g: v, b: v })
+            }
+            FogStyle::Desaturated => {
+                let fade = |c: i32| ((c + luma) * 3 / 10) as u8;
+                Some(Color {
+                    r: fade(r),
+                    g: fade(g),
+                    b: fade(b),
+                })
+            }
+            FogStyle::Memory => Some(Color {
+                r: (luma / 4) as u8,
+                g: (luma * 3 / 8) as u8,
+                b: (luma * 5 / 8 + 40).min(255) as u8,
+            }),
+            FogStyle::Hidden => None,
+        }
+    }
+}
+
 pub struct Camera(pub Position);
 
 impl Camera {
@@ -20,9 +96,58 @@ impl Camera {
 #[derive(Copy, Clone)]
 struct ScreenChunk {
     dirty: bool,
+    /// True if the grid cells of the screen chunk show something other than its map chunk, so
+    /// they should be blanked if it can't be drawn right away.
+    stale: bool,
     map_chunk: Position,
 }
 
+#[derive(Copy, Clone)]
+enum CellGlyph {
+    Sym(GameSym),
+    Char(char),
+}
+
+/// How a single map tile looks on the grid.
+#[derive(Copy, Clone)]
+struct ChunkCell {
+    glyph: CellGlyph,
+    fg: Color,
+    bg: Color,
+}
+
+impl ChunkCell {
+    const BLANK: ChunkCell = ChunkCell {
+        glyph: CellGlyph::Char(' '),
+        fg: Color::WHITE,
+        bg: Color::BLACK,
+    };
+}
+
+/// Cells of map chunks that have already been worked out, in row-major order.
+///
+/// A map chunk scrolling back onto the screen can be copied straight onto the grid from here.
+/// Buffers of chunks that are invalidated are pooled so that filling the screen again, e.g. after
+/// changing levels, doesn't need to allocate.
+#[derive(Default)]
+struct ChunkCache {
+    chunks: HashMap<(i32, i32), Vec<ChunkCell>>,
+    pool: Vec<Vec<ChunkCell>>,
+}
+
+impl ChunkCache {
+    fn invalidate(&mut self, map_chunk: (i32, i32)) {
+        if let Some(cells) = self.chunks.remove(&map_chunk) {
+            self.pool.push(cells);
+        }
+    }
+
+    fn invalidate_all(&mut self) {
+        self.pool
+            .extend(self.chunks.drain().map(|(_, cells)| cells));
+    }
+}
+
 /// State and functions to support selective drawing of chunks of a map onto a TileGrid.
 ///
 /// The TileGrid retains its drawn contents, parts of which are only redrawn if they are assigned
@@ -35,13 +160,24 @@ struct ScreenChunk {
 /// TileGrid.
 ///
 /// A ChunkedMapGrid is centered on the map position of the camera.
+///
+/// The cells of map chunks are cached, so map chunks that scroll off the screen and back on again
+/// are only worked out again if they were marked dirty in the meantime.  Only a few uncached
+/// chunks are worked out per frame, nearest to the camera first; modes should keep updating while
+/// [ChunkedMapGrid::has_pending_chunks] is true so that the rest get drawn.
 pub struct ChunkedMapGrid {
     screen_chunks: Vec<ScreenChunk>,
     chunks_across: i32,
     chunks_down: i32,
     tile_size: Size,
+    map_zoom: u32,
     screen_size: Size,
     dirty_rects: Vec<(Position, Size)>,
+    cache: ChunkCache,
+    /// Depth of the map that the cache and screen chunks were drawn from.
+    depth: i32,
+    /// Indexes of dirty screen chunks in the order that they're drawn, kept to reuse its memory.
+    draw_order: Vec<usize>,
 }
 
 impl ChunkedMapGrid {
@@ -52,8 +188,12 @@ impl ChunkedMapGrid {
             chunks_across: 0,
             chunks_down: 0,
             tile_size: Size { w: 0, h: 0 },
+            map_zoom: 1,
             screen_size: Size { w: 0, h: 0 },
             dirty_rects: Vec::new(),
+            cache: ChunkCache::default(),
+            depth: 0,
+            draw_order: Vec::new(),
         }
     }
 
@@ -74,6 +214,26 @@ impl ChunkedMapGrid {
         }
     }
 
+    /// Map zoom used when the grid was last prepared, after fitting it to the screen if needed.
+    pub fn map_zoom(&self) -> u32 {
+        self.map_zoom
+    }
+
+    /// Largest map zoom at which the player's whole field of view fits in a screen area of the
+    /// given size in pixels, or 1 if even that doesn't fit.
+    fn fit_map_zoom(world: &World, tile_w: u32, tile_h: u32, size: Size) -> u32 {
+        let player_id = world.borrow::<UniqueView<PlayerId>>();
+        let fov_tiles = world
+            .borrow::<View<FieldOfView>>()
+            .try_get(player_id.0)
+            .map_or(1, |fov| 2 * fov.range as u32 + 1);
+
+        (1..=ui::MAX_MAP_ZOOM)
+            .rev()
+            .find(|z| z * tile_w * fov_tiles <= size.w && z * tile_h * fov_tiles <= size.h)
+            .unwrap_or(1)
+    }
+
     /// Set up the grid to show chunks of the map centered about the camera at the given position
     /// and size on screen.
     pub fn prepare_grid<Y: Symbol>(
@@ -87,11 +247,22 @@ impl ChunkedMapGrid {
         let Options {
             tileset: map_tileset_index,
             map_zoom,
+            map_zoom_fit,
             ..
         } = *world.borrow::<UniqueView<Options>>();
         let map_tileset = &tilesets
             .get(map_tileset_index as usize)
             .unwrap_or(&tilesets[0]);
+        let map_zoom = if map_zoom_fit {
+            Self::fit_map_zoom(
+                world,
+                map_tileset.tile_width(),
+                map_tileset.tile_height(),
+                size,
+            )
+        } else {
+            map_zoom
+        };
         let tile_px_w = map_zoom * map_tileset.tile_width();
         let tile_px_h = map_zoom * map_tileset.tile_height();
         let chunk_px_w = CHUNK_TILE_WIDTH * tile_px_w as i32;
@@ -110,11 +281,13 @@ impl ChunkedMapGrid {
         if new_chunks_across != self.chunks_across || new_chunks_down != self.chunks_down {
             for screen_chunk in self.screen_chunks.iter_mut().take(new_len) {
                 screen_chunk.dirty = true;
+                screen_chunk.stale = true;
             }
             self.screen_chunks.resize(
                 new_len,
                 ScreenChunk {
                     dirty: true,
+                    stale: true,
                     map_chunk: Position { x: 0, y: 0 },
                 },
             );
@@ -125,6 +298,7 @@ impl ChunkedMapGrid {
 
         self.tile_size.w = tile_px_w;
         self.tile_size.h = tile_px_h;
+        self.map_zoom = map_zoom;
         self.screen_size = size;
 
         grid.resize(Size {
@@ -146,14 +320,23 @@ impl ChunkedMapGrid {
     }
 
     /// Mark all screen chunks as dirty so that they will be redrawn the next time that
-    /// [ChunkedMapGrid::draw] is called.
+    /// [ChunkedMapGrid::draw] is called, and forget all cached map chunks.
     pub fn mark_all_dirty(&mut self) {
         self.dirty_rects.clear();
+        self.cache.invalidate_all();
         for screen_chunk in self.screen_chunks.iter_mut() {
             screen_chunk.dirty = true;
         }
     }
 
+    /// True if some screen chunks still need to be drawn by later calls to
+    /// [ChunkedMapGrid::draw].
+    pub fn has_pending_chunks(&self) -> bool {
+        self.screen_chunks
+            .iter()
+            .any(|screen_chunk| screen_chunk.dirty)
+    }
+
     /// Convert a map position into a grid position.
     pub fn map_to_grid_pos(&self, world: &World, map_pos: Position) -> Option<Position> {
         let top_left_chunk = self.screen_top_left_map_chunk(world);
@@ -233,6 +416,7 @@ impl ChunkedMapGrid {
                 if new_map_chunk != screen_chunk.map_chunk {
                     screen_chunk.map_chunk = new_map_chunk;
                     screen_chunk.dirty = true;
+                    screen_chunk.stale = true;
                 }
             }
         }
@@ -246,6 +430,8 @@ impl ChunkedMapGrid {
 
             for dirty_chunk_y in start_chunk_y..=end_chunk_y {
                 for dirty_chunk_x in start_chunk_x..=end_chunk_x {
+                    self.cache.invalidate((dirty_chunk_x, dirty_chunk_y));
+
                     let dirty_chunk_x = (dirty_chunk_x
                         + (dirty_chunk_x.abs() + self.chunks_across - 1) / self.chunks_across
                             * self.chunks_across)
@@ -262,49 +448,142 @@ impl ChunkedMapGrid {
         }
 
         let map = world.borrow::<UniqueView<Map>>();
+        let options = world.borrow::<UniqueView<Options>>();
         let fovs = world.borrow::<View<FieldOfView>>();
         let player_fov = {
             let player_id = world.borrow::<UniqueView<PlayerId>>();
             fovs.get(player_id.0)
         };
 
-        // Draw dirty grids and unflag them.
-        for screen_chunk in self.screen_chunks.iter_mut() {
-            if screen_chunk.dirty {
-                for (tx, ty, tile) in map.iter_bounds(
-                    screen_chunk.map_chunk.x * CHUNK_TILE_WIDTH,
-                    screen_chunk.map_chunk.y * CHUNK_TILE_HEIGHT,
-                    (screen_chunk.map_chunk.x + 1) * CHUNK_TILE_WIDTH - 1,
-                    (screen_chunk.map_chunk.y + 1) * CHUNK_TILE_HEIGHT - 1,
-                ) {
-                    if let Some((sym, color)) = tile {
-                        let color = if player_fov.get((tx, ty)) {
-                            color
-                        } else {
-                            let v =
-                                ((color.r as i32 * 30 + color.g as i32 * 59 + color.b as i32 * 11)
-                                    / 200) as u8;
-                            Color { r: v, g: v, b: v }
-                        };
-
-                        grid.put_sym_color_raw(
-                            (tx - top_left_tile_x, ty - top_left_tile_y),
-                            sym,
-                            color,
-                            Color::BLACK,
-                        );
-                    } else {
-                        grid.put_char_color_raw(
-                            (tx - top_left_tile_x, ty - top_left_tile_y),
-                            ' ',
-                            Color::WHITE,
-                            Color::BLACK,
+        let mut diagnostics = world.borrow::<UniqueViewMut<Diagnostics>>();
+
+        // Nothing drawn from the previous level is worth showing on a new one.
+        if map.depth != self.depth {
+            self.depth = map.depth;
+            self.cache.invalidate_all();
+            for screen_chunk in self.screen_chunks.iter_mut() {
+                screen_chunk.dirty = true;
+                screen_chunk.stale = true;
+            }
+        }
+
+        // Draw dirty chunks nearest to the camera first, since that's where the player is looking.
+        self.draw_order.clear();
+        self.draw_order.extend(
+            self.screen_chunks
+                .iter()
+                .enumerate()
+                .filter(|(_, screen_chunk)| screen_chunk.dirty)
+                .map(|(index, _)| index),
+        );
+        let screen_chunks = &self.screen_chunks;
+        self.draw_order.sort_by_key(|&index| {
+            let map_chunk = screen_chunks[index].map_chunk;
+
+            (map_chunk.x - camera_chunk_x)
+                .abs()
+                .max((map_chunk.y - camera_chunk_y).abs())
+        });
+
+        let mut budget = CHUNKS_PER_FRAME;
+
+        // Draw dirty grids and unflag them, leaving uncached chunks past the budget for later.
+        for &index in self.draw_order.iter() {
+            let screen_chunk = &mut self.screen_chunks[index];
+            let map_chunk = screen_chunk.map_chunk;
+            let key = (map_chunk.x, map_chunk.y);
+            let grid_pos = Position {
+                x: map_chunk.x * CHUNK_TILE_WIDTH - top_left_tile_x,
+                y: map_chunk.y * CHUNK_TILE_HEIGHT - top_left_tile_y,
+            };
+
+            if !self.cache.chunks.contains_key(&key) {
+                if budget == 0 {
+                    if screen_chunk.stale {
+                        put_chunk_cells(
+                            grid,
+                            grid_pos,
+                            &[ChunkCell::BLANK; (CHUNK_TILE_WIDTH * CHUNK_TILE_HEIGHT) as usize],
                         );
+                        screen_chunk.stale = false;
                     }
+                    continue;
                 }
 
-                screen_chunk.dirty = false;
+                let mut cells = self.cache.pool.pop().unwrap_or_default();
+
+                fill_chunk_cells(&mut cells, &map, &options, player_fov, map_chunk);
+                self.cache.chunks.insert(key, cells);
+                diagnostics.chunks_drawn += 1;
+                budget -= 1;
             }
+
+            put_chunk_cells(grid, grid_pos, &self.cache.chunks[&key]);
+            screen_chunk.dirty = false;
+            screen_chunk.stale = false;
+        }
+    }
+}
+
+/// Work out how each tile of a map chunk should look, replacing the contents of `cells`.
+fn fill_chunk_cells(
+    cells: &mut Vec<ChunkCell>,
+    map: &Map,
+    options: &Options,
+    player_fov: &FieldOfView,
+    map_chunk: Position,
+) {
+    cells.clear();
+
+    for (tx, ty, tile) in map.iter_bounds(
+        map_chunk.x * CHUNK_TILE_WIDTH,
+        map_chunk.y * CHUNK_TILE_HEIGHT,
+        (map_chunk.x + 1) * CHUNK_TILE_WIDTH - 1,
+        (map_chunk.y + 1) * CHUNK_TILE_HEIGHT - 1,
+    ) {
+        let in_view = player_fov.get((tx, ty));
+        let tile = tile.filter(|_| in_view || options.fog_style != FogStyle::Hidden);
+
+        if let Some((sym, color)) = tile {
+            // Out of view, show the items that the player remembers being here.
+            let (sym, color) = match map.remembered_at(tx, ty) {
+                Some(remembered) if !in_view => (remembered.sym, remembered.fg),
+                _ => (sym, color),
+            };
+            let color = render::palette_color(options, color);
+            let bg = render::palette_color(options, map.tile_bg(tx, ty, in_view));
+            let color = if in_view {
+                color
+            } else {
+                options.fog_style.apply(color).unwrap_or(Color::BLACK)
+            };
+            let glyph = match sym.distinct_char() {
+                Some(ch) if options.distinct_glyphs => CellGlyph::Char(ch),
+                _ => CellGlyph::Sym(sym),
+            };
+
+            cells.push(ChunkCell {
+                glyph,
+                fg: color,
+                bg,
+            });
+        } else {
+            cells.push(ChunkCell::BLANK);
+        }
+    }
+}
+
+/// Put the cells of a chunk onto the grid with its top-left corner at the given grid position.
+fn put_chunk_cells(grid: &mut TileGrid<GameSym>, grid_pos: Position, cells: &[ChunkCell]) {
+    for (i, cell) in cells.iter().enumerate() {
+        let pos = (
+            grid_pos.x + i as i32 % CHUNK_TILE_WIDTH,
+            grid_pos.y + i as i32 / CHUNK_TILE_WIDTH,
+        );
+
+        match cell.glyph {
+            CellGlyph::Sym(sym) => grid.put_sym_color_raw(pos, sym, cell.fg, cell.bg),
+            CellGlyph::Char(ch) => grid.put_char_color_raw(pos, ch, cell.fg, cell.bg),
         }
     }
 }
@@ -1,14 +1,29 @@
 use serde::{Deserialize, Serialize};
 use shipyard::EntityId;
 
-use crate::{bitgrid::BitGrid, gamesym::GameSym, player::AutoRun};
+use crate::{
+    bitgrid::BitGrid,
+    gamesym::GameSym,
+    locale::Locale,
+    player::{AutoRun, ItemBatch},
+};
 use ruggrogue::util::{Color, Position};
 
+/// Turn on which an item last came into an inventory, for sorting the most recent items first.
+#[derive(Deserialize, Serialize)]
+pub struct Acquired(pub u64);
+
 #[derive(Deserialize, Serialize)]
 pub struct AreaOfEffect {
     pub radius: i32,
 }
 
+/// Too frightened to fight, so monsters flee and the player can't attack, for a number of turns.
+#[derive(Deserialize, Serialize)]
+pub struct Afraid {
+    pub turns: i32,
+}
+
 #[derive(Deserialize, Serialize)]
 pub struct Asleep {
     pub sleepiness: i32,
@@ -18,6 +33,21 @@ pub struct Asleep {
 #[derive(Deserialize, Serialize)]
 pub struct BlocksTile;
 
+/// A boulder that moves along when pushed by walking into it.
+#[derive(Deserialize, Serialize)]
+pub struct Boulder;
+
+/// Fights for another faction for a number of turns.
+#[derive(Deserialize, Serialize)]
+pub struct Charmed {
+    pub faction: Faction,
+    pub turns: i32,
+}
+
+/// A storage chest whose contents stay with the player from one depth and game to the next.
+#[derive(Deserialize, Serialize)]
+pub struct Chest;
+
 #[derive(Deserialize, Serialize)]
 pub struct CombatBonus {
     pub attack: f32,
@@ -32,19 +62,74 @@ pub struct CombatStats {
     pub defense: f32,
 }
 
+/// Moves go in random directions half of the time for a number of turns.
+#[derive(Deserialize, Serialize)]
+pub struct Confused {
+    pub turns: i32,
+}
+
 #[derive(Deserialize, Serialize)]
 pub struct Consumable;
 
+/// An item that holds other items in an Inventory of its own.
+#[derive(Deserialize, Serialize)]
+pub struct Container {
+    /// Most items that can fit inside.
+    pub capacity: usize,
+}
+
 #[derive(Deserialize, Serialize)]
 pub struct Coord(pub Position);
 
+/// A cracked section of wall that can be dug out with a [Digger].
+#[derive(Deserialize, Serialize)]
+pub struct Diggable;
+
+/// An item that lets whoever carries it dig through [Diggable] walls.
+#[derive(Deserialize, Serialize)]
+pub struct Digger;
+
+/// Uses left in a piece of equipment before it breaks.
+#[derive(Deserialize, Serialize)]
+pub struct Durability {
+    pub current: i32,
+    pub max: i32,
+}
+
+/// Damage dealt to an entity by each entity that hurt it, used to share out experience when it dies.
+#[derive(Deserialize, Serialize)]
+pub struct DamageLog(pub Vec<(EntityId, u64)>);
+
+/// Flavor text shown when examining an item or monster, looked up in the locale catalog as it's
+/// shown so that it's always in the chosen language.
+#[derive(Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum Description {
+    /// A catalog key, or text from a data file or an older save file that's shown as it is.
+    Text(String),
+    /// The gravestone of someone who fell at the given level.
+    Grave { who: String, level: i32 },
+}
+
+impl Description {
+    pub fn text(&self, locale: &Locale) -> String {
+        match self {
+            Description::Text(key) => locale.get(key).to_string(),
+            Description::Grave { who, level } => locale.format(
+                "desc.grave",
+                &[("who", &locale.name(who)), ("level", level)],
+            ),
+        }
+    }
+}
+
 impl Coord {
     pub fn dist(&self, other: &Coord) -> i32 {
         std::cmp::max((other.0.x - self.0.x).abs(), (other.0.y - self.0.y).abs())
     }
 }
 
-#[derive(Deserialize, Serialize)]
+#[derive(Clone, Copy, Deserialize, Serialize)]
 pub enum EquipSlot {
     Weapon,
     Armor,
@@ -64,12 +149,28 @@ pub struct Experience {
     pub base: u64,
 }
 
+/// Which side an entity fights on, for entities that don't fight on the usual side for what they
+/// are; see [crate::faction::faction_of].  Entities of different factions are hostile.
+#[derive(Clone, Copy, Default, Deserialize, PartialEq, Serialize)]
+pub enum Faction {
+    /// The player, their pets and anything charmed by them.
+    Player,
+    /// Monsters that hunt the player.
+    #[default]
+    Monsters,
+    /// Creatures that attack anything that comes near, including monsters.
+    Wildlife,
+}
+
 #[derive(Deserialize, Serialize)]
 pub struct FieldOfView {
     pub tiles: BitGrid,
     pub range: i32,
     pub center: (i32, i32),
     pub dirty: bool,
+    /// Center and range that the tiles were last calculated for, if they're still valid.
+    #[serde(skip)]
+    calculated_for: Option<((i32, i32), i32)>,
 }
 
 impl FieldOfView {
@@ -83,9 +184,29 @@ impl FieldOfView {
             range,
             center: (0, 0),
             dirty: true,
+            calculated_for: None,
         }
     }
 
+    /// Check if the tiles need to be calculated again for the given center, which is only the case
+    /// if the field of view is dirty and its center or range has changed since it was last
+    /// calculated.
+    pub fn needs_recalculation(&self, center: (i32, i32)) -> bool {
+        self.dirty && self.calculated_for != Some((center, self.range))
+    }
+
+    /// Record that the tiles were just calculated for the current center and range.
+    pub fn mark_calculated(&mut self) {
+        self.calculated_for = Some((self.center, self.range));
+        self.dirty = false;
+    }
+
+    /// Force the tiles to be calculated again, e.g. when the map they were calculated on changes.
+    pub fn invalidate(&mut self) {
+        self.calculated_for = None;
+        self.dirty = true;
+    }
+
     #[inline]
     fn offset_xy(&self, (x, y): (i32, i32)) -> (i32, i32) {
         (
@@ -124,12 +245,25 @@ impl FieldOfView {
     }
 }
 
+/// An item that the player has marked to keep pinned to the top of their inventory.
+#[derive(Deserialize, Serialize)]
+pub struct Favorite;
+
+/// A harmless character that trades places with the player instead of being attacked.
+#[derive(Deserialize, Serialize)]
+pub struct Friendly;
+
 #[derive(Deserialize, Serialize)]
 pub struct GivesExperience(pub u64);
 
+/// The grave of a player from an earlier game, holding everything they carried when they died.
+#[derive(Deserialize, Serialize)]
+pub struct Gravestone;
+
 pub enum HurtBy {
     Someone(EntityId),
     Starvation,
+    Burning,
 }
 
 #[derive(Deserialize, Serialize)]
@@ -137,6 +271,10 @@ pub struct InflictsDamage {
     pub damage: i32,
 }
 
+/// Which of the player's two equipment sets an equippable item belongs to.
+#[derive(Deserialize, Serialize)]
+pub struct InLoadout(pub [bool; 2]);
+
 #[derive(Deserialize, Serialize)]
 pub struct InflictsSleep {
     pub sleepiness: i32,
@@ -150,21 +288,99 @@ pub struct Inventory {
 #[derive(Deserialize, Serialize)]
 pub struct Item;
 
+/// An item that unlocks a single locked door, and is used up doing so.
+#[derive(Deserialize, Serialize)]
+pub struct Key;
+
+/// A monster that drops the key to the vault on its level when it dies.
+#[derive(Deserialize, Serialize)]
+pub struct KeyCarrier;
+
+/// A favorite item that can't be dropped until it's unlocked, to avoid dropping it by accident.
+#[derive(Deserialize, Serialize)]
+pub struct Locked;
+
+/// Where a monster last saw the player, which it heads towards for a while after losing sight of
+/// them.
+#[derive(Deserialize, Serialize)]
+pub struct LastSeenPlayer {
+    pub pos: Position,
+    pub turns_left: i32,
+}
+
 #[derive(Deserialize, Serialize)]
 pub struct Monster;
 
+/// Name of an entity in the chosen language, made from its [NameSource].
 #[derive(Deserialize, Serialize)]
 pub struct Name(pub String);
 
+/// What the [Name] of an entity is made from, which stays the same in every language.
+///
+/// Anything that tells entities apart by name should use this instead of their [Name], which is
+/// made again whenever the language changes.
+#[derive(Clone, Deserialize, PartialEq, Serialize)]
+pub enum NameSource {
+    /// A name looked up with [crate::locale::Locale::name], e.g. "Health Potion".
+    Plain(String),
+    /// A piece of equipment named after its total bonus and quality tier, if it has one.
+    Equipment {
+        bonus: i32,
+        quality: String,
+        name: String,
+    },
+    /// The gravestone of someone, who is named as they were known.
+    Grave(String),
+    /// The ghost of someone that haunts their grave.
+    Ghost(String),
+    /// A pile of gold coins.
+    Gold(u32),
+}
+
+impl NameSource {
+    /// The untranslated name at the heart of this, e.g. the kind of monster or item.
+    pub fn key(&self) -> &str {
+        match self {
+            NameSource::Plain(name) | NameSource::Equipment { name, .. } => name,
+            NameSource::Grave(who) => who,
+            NameSource::Ghost(_) => "Ghost",
+            NameSource::Gold(_) => "Gold",
+        }
+    }
+}
+
+/// A friendly, non-combatant character that can be talked to.
+#[derive(Deserialize, Serialize)]
+pub struct Npc {
+    /// ID of the NPC definition holding the dialog tree.
+    pub def_id: String,
+    /// True once the NPC has handed over an item.
+    pub gifted: bool,
+}
+
 #[derive(Deserialize, Serialize)]
 pub struct Nutrition(pub i32);
 
+/// A companion of the player that follows them around, fights monsters and comes along when they
+/// descend.
+#[derive(Deserialize, Serialize)]
+pub struct Pet;
+
 #[derive(Deserialize, Serialize)]
 pub struct Player {
     #[serde(skip)]
     pub auto_run: Option<AutoRun>,
+    #[serde(skip)]
+    pub item_batch: Option<ItemBatch>,
+    /// Which of the two equipment sets is worn right now.
+    #[serde(default)]
+    pub active_loadout: usize,
 }
 
+/// A lost item that an NPC asked for in the quest with the given ID.
+#[derive(Deserialize, Serialize)]
+pub struct QuestItem(pub u32);
+
 #[derive(Deserialize, Serialize)]
 pub struct ProvidesHealing {
     pub heal_amount: i32,
@@ -188,11 +404,22 @@ pub struct Renderable {
     pub bg: Color,
 }
 
+/// An item that restores the durability of whatever its user has equipped in a slot.
+#[derive(Deserialize, Serialize)]
+pub struct Repairs(pub EquipSlot);
+
+/// Name of the script to run on each target when this item is used.
+#[derive(Deserialize, Serialize)]
+pub struct ScriptedUse(pub String);
+
 #[derive(Deserialize, Serialize)]
 pub struct Stomach {
     pub fullness: i32,
     pub max_fullness: i32,
     pub sub_hp: i32,
+    /// Extra fullness to lose on the next hunger tick, e.g. from moving while burdened.
+    #[serde(default)]
+    pub strain: i32,
 }
 
 #[derive(Deserialize, Serialize)]
@@ -204,3 +431,7 @@ pub struct Tally {
 
 #[derive(Deserialize, Serialize)]
 pub struct Victory;
+
+/// How heavy an item is, counting towards the carrying capacity of whoever holds it.
+#[derive(Deserialize, Serialize)]
+pub struct Weight(pub i32);
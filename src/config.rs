@@ -0,0 +1,111 @@
+use serde::{Deserialize, Serialize};
+use shipyard::{UniqueView, World};
+use std::fs;
+
+#[cfg(target_os = "emscripten")]
+use crate::ruggrogue_sync_idbfs;
+use crate::{
+    locale::LanguageList,
+    paths,
+    tilesets::TilesetList,
+    ui::{self, Options},
+};
+use ruggrogue::Error;
+
+pub(crate) const CONFIG_FILENAME: &str = "config.json";
+
+/// Everything stored in the config file.  Sections are optional so that older config files can
+/// still be read as new settings are added.
+#[derive(Default, Deserialize, Serialize)]
+struct Config {
+    #[serde(default)]
+    options: Option<Options>,
+    /// Code of the chosen language, e.g. "en".
+    #[serde(default)]
+    language: Option<String>,
+}
+
+/// Load options from the config file, falling back to the given defaults for anything that is
+/// missing or no longer valid, e.g. a tileset that was removed.
+pub fn load_options(
+    tileset_list: &TilesetList,
+    language_list: &LanguageList,
+    defaults: Options,
+) -> Options {
+    let path = paths::config_file(CONFIG_FILENAME);
+    let contents = match fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(e) => {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                eprintln!("Warning: Failed to read {}: {}", path.display(), e);
+            }
+            return defaults;
+        }
+    };
+    let (mut options, language) = match serde_json::from_str::<Config>(&contents) {
+        Ok(Config {
+            options: Some(options),
+            language,
+        }) => (options, language),
+        Ok(_) => return defaults,
+        Err(e) => {
+            eprintln!("Warning: Failed to parse {}: {}", path.display(), e);
+            return defaults;
+        }
+    };
+
+    if options.tileset as usize >= tileset_list.len() {
+        options.tileset = defaults.tileset;
+    }
+    if !tileset_list.is_font(options.font) {
+        options.font = defaults.font;
+    }
+    if !(1..=ui::MAX_MAP_ZOOM).contains(&options.map_zoom) {
+        options.map_zoom = defaults.map_zoom;
+    }
+    if !(1..=2).contains(&options.text_zoom) {
+        options.text_zoom = defaults.text_zoom;
+    }
+    if let Some(language) = language {
+        options.language = language_list
+            .index_of(&language)
+            .unwrap_or(defaults.language);
+    } else if options.language as usize >= language_list.len() {
+        options.language = defaults.language;
+    }
+    if !ui::MSG_LINES_CHOICES.contains(&options.msg_lines) {
+        options.msg_lines = defaults.msg_lines;
+    }
+    if !ui::AUTOSAVE_TURNS_CHOICES.contains(&options.autosave_turns) {
+        options.autosave_turns = defaults.autosave_turns;
+    }
+    options.difficulty = options.difficulty.validated();
+
+    options
+}
+
+/// Write the current options out to the config file.
+pub fn save_options(world: &World) -> Result<(), Error> {
+    let options = *world.borrow::<UniqueView<Options>>();
+    let config = Config {
+        options: Some(options),
+        language: world
+            .borrow::<UniqueView<LanguageList>>()
+            .code(options.language)
+            .map(String::from),
+    };
+    let contents = serde_json::to_string_pretty(&config)
+        .map_err(|e| Error::new("Failed to serialize options", e))?;
+
+    let path = paths::config_file(CONFIG_FILENAME);
+
+    fs::write(&path, contents)
+        .map_err(|e| Error::new(format!("Failed to write {}", path.display()), e))?;
+
+    #[cfg(target_os = "emscripten")]
+    unsafe {
+        ruggrogue_sync_idbfs();
+    }
+
+    Ok(())
+}
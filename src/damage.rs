@@ -1,25 +1,100 @@
-use rand::{Rng, SeedableRng};
-use rand_xoshiro::Xoshiro128PlusPlus as GameRng;
+use rand::Rng;
 use shipyard::{
     AllStoragesViewMut, EntitiesView, EntityId, Get, IntoIter, Shiperator, UniqueView,
     UniqueViewMut, View, ViewMut, World,
 };
-use std::hash::Hasher;
-use wyhash::WyHash;
 
 use crate::{
+    attract::Attract,
+    bones,
     components::{
-        Asleep, BlocksTile, CombatBonus, CombatStats, Coord, Equipment, Experience,
-        GivesExperience, HurtBy, Name, Tally,
+        Asleep, BlocksTile, CombatBonus, CombatStats, Coord, DamageLog, EquipSlot, Equipment,
+        Experience, GivesExperience, HurtBy, KeyCarrier, Monster, Name, NameSource, Pet, Tally,
     },
-    magicnum,
-    map::Map,
-    message::Messages,
+    durability,
+    event::{self, GameEvent},
+    experience, faction,
+    locale::Locale,
+    map::{Map, TileFlags},
+    message::{Messages, MsgCategory},
     player::{PlayerAlive, PlayerId},
-    saveload, spawn, GameSeed, TurnCount,
+    rng::RngStream,
+    saveload, spawn, stats,
+    tutorial::Tutorial,
+    GameSeed, TurnCount,
 };
 
-pub fn melee_attack(world: &World, attacker: EntityId, defender: EntityId) {
+/// Note damage dealt by an attacker in the damage log of the defender.
+pub fn log_damage(
+    entities: &EntitiesView,
+    damage_logs: &mut ViewMut<DamageLog>,
+    attacker: EntityId,
+    defender: EntityId,
+    damage: i32,
+) {
+    if damage <= 0 || attacker == defender {
+        return;
+    }
+
+    if let Ok(damage_log) = damage_logs.try_get(defender) {
+        if let Some(entry) = damage_log.0.iter_mut().find(|(id, _)| *id == attacker) {
+            entry.1 += damage as u64;
+        } else {
+            damage_log.0.push((attacker, damage as u64));
+        }
+    } else {
+        entities.add_component(
+            &mut *damage_logs,
+            DamageLog(vec![(attacker, damage as u64)]),
+            defender,
+        );
+    }
+}
+
+/// Sum the attack and defense bonuses of whatever the given entity has equipped.
+pub fn equipment_bonus(
+    id: EntityId,
+    equipments: &View<Equipment>,
+    combat_bonuses: &View<CombatBonus>,
+) -> (f32, f32) {
+    equipments.try_get(id).map_or((0.0, 0.0), |equip| {
+        equip
+            .weapon
+            .iter()
+            .chain(equip.armor.iter())
+            .filter_map(|&e| combat_bonuses.try_get(e).ok())
+            .fold((0.0, 0.0), |(attack, defense), b| {
+                (attack + b.attack, defense + b.defense)
+            })
+    })
+}
+
+/// Damage dealt by a hit before it's fluctuated and rounded.
+pub fn base_damage(attack_value: f32, defense_value: f32) -> f32 {
+    // Attack is twice defense most of the time.
+    if attack_value >= defense_value * 2.0 {
+        attack_value - defense_value
+    } else {
+        attack_value * (0.25 + (0.125 * attack_value / defense_value.max(1.0)).min(0.25))
+    }
+}
+
+/// Have the attacker attack the defender, returning true if the attack landed.
+///
+/// A landed attack wears down the attacker's weapon and the defender's armor.
+pub fn melee_attack(world: &World, attacker: EntityId, defender: EntityId) -> bool {
+    let landed = resolve_melee_attack(world, attacker, defender);
+
+    if landed {
+        durability::wear_equipment(world, attacker, EquipSlot::Weapon);
+        durability::wear_equipment(world, defender, EquipSlot::Armor);
+    }
+
+    landed
+}
+
+fn resolve_melee_attack(world: &World, attacker: EntityId, defender: EntityId) -> bool {
+    let locale = world.borrow::<UniqueView<Locale>>();
     let mut msgs = world.borrow::<UniqueViewMut<Messages>>();
     let entities = world.borrow::<EntitiesView>();
     let asleeps = world.borrow::<View<Asleep>>();
@@ -32,51 +107,35 @@ pub fn melee_attack(world: &World, attacker: EntityId, defender: EntityId) {
     let def_name = &names.get(defender).0;
     let mut rng = {
         let coords = world.borrow::<View<Coord>>();
-        let mut hasher = WyHash::with_seed(magicnum::MELEE_ATTACK);
-        hasher.write_u64(world.borrow::<UniqueView<GameSeed>>().0);
-        hasher.write_u64(world.borrow::<UniqueView<TurnCount>>().0);
+        let mut fork = world
+            .borrow::<UniqueView<GameSeed>>()
+            .fork(RngStream::Combat);
+        fork.mix_u64(world.borrow::<UniqueView<TurnCount>>().0);
         if let Ok(attacker_coord) = coords.try_get(attacker) {
-            hasher.write_i32(attacker_coord.0.x);
-            hasher.write_i32(attacker_coord.0.y);
+            fork.mix_i32(attacker_coord.0.x).mix_i32(attacker_coord.0.y);
         }
         if let Ok(defender_coord) = coords.try_get(defender) {
-            hasher.write_i32(defender_coord.0.x);
-            hasher.write_i32(defender_coord.0.y);
+            fork.mix_i32(defender_coord.0.x).mix_i32(defender_coord.0.y);
         }
-        GameRng::seed_from_u64(hasher.finish())
+        fork.rng()
     };
 
     if !asleeps.contains(defender) && rng.gen_ratio(1, 10) {
-        msgs.add(format!("{} misses {}.", att_name, def_name));
-        return;
+        msgs.add_as(
+            MsgCategory::Combat,
+            locale.format(
+                "msg.misses",
+                &[("attacker", att_name), ("defender", def_name)],
+            ),
+        );
+        return false;
     }
 
     let attack_value = combat_stats.get(attacker).attack
-        + equipments.try_get(attacker).map_or(0.0, |equip| {
-            equip
-                .weapon
-                .iter()
-                .chain(equip.armor.iter())
-                .filter_map(|&e| combat_bonuses.try_get(e).ok())
-                .map(|b| b.attack)
-                .sum()
-        });
+        + equipment_bonus(attacker, &equipments, &combat_bonuses).0;
     let defense_value = combat_stats.get(defender).defense
-        + equipments.try_get(defender).map_or(0.0, |equip| {
-            equip
-                .weapon
-                .iter()
-                .chain(equip.armor.iter())
-                .filter_map(|&e| combat_bonuses.try_get(e).ok())
-                .map(|b| b.defense)
-                .sum()
-        });
-    // Attack is twice defense most of the time.
-    let mut damage = if attack_value >= defense_value * 2.0 {
-        attack_value - defense_value
-    } else {
-        attack_value * (0.25 + (0.125 * attack_value / defense_value.max(1.0)).min(0.25))
-    };
+        + equipment_bonus(defender, &equipments, &combat_bonuses).1;
+    let mut damage = base_damage(attack_value, defense_value);
 
     // Fluctuate damage by a random amount.
     let mut suffix = '!';
@@ -99,36 +158,60 @@ pub fn melee_attack(world: &World, attacker: EntityId, defender: EntityId) {
         };
 
     if damage > 0 {
+        let mut damage_logs = world.borrow::<ViewMut<DamageLog>>();
         let mut tallies = world.borrow::<ViewMut<Tally>>();
 
         (&mut combat_stats).get(defender).hp -= damage;
+        event::emit_damage(world, defender, damage);
+        if let Ok(def_coord) = world.borrow::<View<Coord>>().try_get(defender) {
+            world.borrow::<UniqueViewMut<Map>>().set_flags(
+                def_coord.0.x,
+                def_coord.0.y,
+                TileFlags::BLOOD,
+                true,
+            );
+        }
         entities.add_component(&mut hurt_bys, HurtBy::Someone(attacker), defender);
+        log_damage(&entities, &mut damage_logs, attacker, defender, damage);
         if let Ok(att_tally) = (&mut tallies).try_get(attacker) {
             att_tally.damage_dealt += damage.max(0) as u64;
         }
         if let Ok(def_tally) = (&mut tallies).try_get(defender) {
             def_tally.damage_taken += damage.max(0) as u64;
         }
-        msgs.add(format!(
-            "{} hits {} for {} hp{}",
-            att_name, def_name, damage, suffix
-        ));
+        msgs.add_as(
+            MsgCategory::Combat,
+            locale.format(
+                "msg.hits",
+                &[
+                    ("attacker", att_name),
+                    ("defender", def_name),
+                    ("damage", &damage),
+                    ("suffix", &suffix),
+                ],
+            ),
+        );
     } else {
-        msgs.add(format!(
-            "{} hits {}, but does no damage.",
-            att_name, def_name
-        ));
+        msgs.add_as(
+            MsgCategory::Combat,
+            locale.format(
+                "msg.hits_no_damage",
+                &[("attacker", att_name), ("defender", def_name)],
+            ),
+        );
     }
+
+    true
 }
 
 /// Check for dead entities, do any special handling for them and delete them.
-pub fn handle_dead_entities(mut all_storages: AllStoragesViewMut) {
+pub fn handle_dead_entities(world: &World) {
     loop {
         let mut entities = [EntityId::dead(); 10];
         let mut num_entities = 0;
 
         // Fill buffer with dead entities.
-        all_storages.run(|combat_stats: View<CombatStats>| {
+        world.run(|combat_stats: View<CombatStats>| {
             for ((id, _), entity) in combat_stats
                 .iter()
                 .with_id()
@@ -142,49 +225,122 @@ pub fn handle_dead_entities(mut all_storages: AllStoragesViewMut) {
         });
 
         for &entity in entities.iter().take(num_entities) {
-            all_storages.run(|mut msgs: UniqueViewMut<Messages>, names: View<Name>| {
-                msgs.add(format!("{} dies!", &names.get(entity).0));
-            });
+            let died = world.run(
+                |coords: View<Coord>,
+                 hurt_bys: View<HurtBy>,
+                 monsters: View<Monster>,
+                 names: View<Name>,
+                 name_sources: View<NameSource>,
+                 pets: View<Pet>| GameEvent::EntityDied {
+                    entity,
+                    name: names.get(entity).0.clone(),
+                    key: name_sources.get(entity).key().to_string(),
+                    pos: coords.get(entity).0.into(),
+                    pet: pets.contains(entity),
+                    monster: monsters.contains(entity),
+                    killer: match hurt_bys.try_get(entity) {
+                        Ok(&HurtBy::Someone(killer)) => Some(killer),
+                        _ => None,
+                    },
+                },
+            );
+            event::emit(world, died);
+
+            // Only those who were hostile to the dead entity earn experience from it, so e.g. monsters
+            // fighting among themselves or the player turning on a charmed ally earns nothing.
+            let earners = {
+                let damage_logs = world.borrow::<View<DamageLog>>();
+                let hurt_bys = world.borrow::<View<HurtBy>>();
+                let mut earners = damage_logs.try_get(entity).map_or_else(
+                    |_| Vec::new(),
+                    |log| log.0.iter().map(|(id, _)| *id).collect(),
+                );
 
-            all_storages.run(
-                |mut exps: ViewMut<Experience>,
+                if let Ok(&HurtBy::Someone(killer)) = hurt_bys.try_get(entity) {
+                    earners.push(killer);
+                }
+                earners
+            };
+            let earners = earners
+                .into_iter()
+                .filter(|&id| faction::is_hostile(world, id, entity))
+                .collect::<Vec<_>>();
+
+            world.run(
+                |damage_logs: View<DamageLog>,
+                 mut exps: ViewMut<Experience>,
                  gives_exps: View<GivesExperience>,
                  hurt_bys: View<HurtBy>,
                  mut tallies: ViewMut<Tally>| {
-                    if let Ok(&HurtBy::Someone(receiver)) = hurt_bys.try_get(entity) {
-                        // Credit kill to whoever last hurt this entity.
+                    let killer = match hurt_bys.try_get(entity) {
+                        Ok(&HurtBy::Someone(receiver)) => Some(receiver),
+                        _ => None,
+                    };
+
+                    // Credit kill to whoever last hurt this entity.
+                    if let Some(receiver) = killer {
                         if let Ok(receiver_tally) = (&mut tallies).try_get(receiver) {
                             receiver_tally.kills += 1;
                         }
+                    }
 
-                        // Give experience to whoever last hurt this entity.
-                        if let Ok(receiver_exp) = (&mut exps).try_get(receiver) {
-                            if let Ok(gives_exp) = gives_exps.try_get(entity) {
-                                receiver_exp.exp += gives_exp.0;
+                    // Share experience between the killer and everyone else who hurt this entity.
+                    if let Ok(gives_exp) = gives_exps.try_get(entity) {
+                        let damage_log = damage_logs.try_get(entity).ok();
+
+                        for (id, share) in experience::share_exp(gives_exp.0, killer, damage_log) {
+                            if !earners.contains(&id) {
+                                continue;
+                            }
+                            if let Ok(exp) = (&mut exps).try_get(id) {
+                                exp.exp += share;
                             }
                         }
                     }
                 },
             );
 
-            if entity == all_storages.borrow::<UniqueView<PlayerId>>().0 {
+            if entity == world.borrow::<UniqueView<PlayerId>>().0 {
                 // The player has died.
-                all_storages.run(
-                    |mut msgs: UniqueViewMut<Messages>,
-                     mut player_alive: UniqueViewMut<PlayerAlive>| {
-                        msgs.add("Press SPACE to continue...".into());
-                        player_alive.0 = false;
-                    },
-                );
+                world.borrow::<UniqueViewMut<PlayerAlive>>().0 = false;
 
-                saveload::delete_save_file();
+                // Dying in the tutorial or behind the title screen leaves any real save, bones
+                // and records alone.
+                if !world.borrow::<UniqueView<Tutorial>>().is_active()
+                    && !world.borrow::<UniqueView<Attract>>().is_active()
+                {
+                    saveload::delete_save_file();
+                    bones::write_bones(world);
+                    world.run(stats::record_defeat);
+                }
 
                 // Don't handle any more dead entities.
                 num_entities = 0;
                 break;
             } else {
+                // Monsters drop loot where they die.
+                let monster_drop = {
+                    let (coords, monsters, name_sources) =
+                        world.borrow::<(View<Coord>, View<Monster>, View<NameSource>)>();
+
+                    if monsters.contains(entity) {
+                        Some((
+                            name_sources.get(entity).key().to_string(),
+                            coords.get(entity).0.into(),
+                        ))
+                    } else {
+                        None
+                    }
+                };
+                if let Some((name, pos)) = monster_drop {
+                    spawn::spawn_monster_drops(world, &name, pos);
+                    if world.borrow::<View<KeyCarrier>>().contains(entity) {
+                        spawn::spawn_key(world, pos);
+                    }
+                }
+
                 // Remove dead entity from the map.
-                all_storages.run(
+                world.run(
                     |mut map: UniqueViewMut<Map>,
                      blocks_tile: View<BlocksTile>,
                      coords: View<Coord>| {
@@ -196,8 +352,13 @@ pub fn handle_dead_entities(mut all_storages: AllStoragesViewMut) {
                     },
                 );
 
+                // Forget about the dead entity in the damage logs of everyone it hurt.
+                for damage_log in (&mut world.borrow::<ViewMut<DamageLog>>()).iter() {
+                    damage_log.0.retain(|(id, _)| *id != entity);
+                }
+
                 // Delete the dead entity.
-                spawn::despawn_entity(&mut all_storages, entity);
+                spawn::despawn_entity(&mut world.borrow::<AllStoragesViewMut>(), entity);
             }
         }
 
@@ -0,0 +1,150 @@
+use shipyard::{UniqueView, UniqueViewMut, World};
+use std::time::Duration;
+
+use crate::{
+    gamekey::{self, GameKey},
+    gamesym::GameSym,
+    modes::{GameHooks, ModeStack},
+    ui::{self, Options},
+};
+use ruggrogue::{
+    util::{Color, Size},
+    FrameTiming, InputBuffer, InputEvent, KeyMods, RunControl, TileGrid, TileGridLayer, Tileset,
+};
+
+/// Frames that take at least this long to update and display are logged.
+const SLOW_FRAME: Duration = Duration::from_millis(100);
+
+const OVERLAY_SIZE: Size = Size { w: 20, h: 7 };
+
+/// Frame timing and rendering statistics that can be shown over everything else on screen.
+pub struct Diagnostics {
+    shown: bool,
+    /// True if the overlay layer sits on top of the layers of the mode stack.
+    layer_attached: bool,
+    /// Map chunks worked out from scratch during the current update, not counting cached ones.
+    pub chunks_drawn: u32,
+}
+
+impl Diagnostics {
+    pub fn new() -> Self {
+        Self {
+            shown: false,
+            layer_attached: false,
+            chunks_drawn: 0,
+        }
+    }
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+fn draw_overlay(grid: &mut TileGrid<GameSym>, timing: &FrameTiming, chunks_drawn: u32) {
+    let fg = Color::WHITE;
+    let bg = Color::BLACK;
+    let lines = [
+        format!("Frame:   {:6.1} ms", millis(timing.frame_time)),
+        format!("Update:  {:6.1} ms", millis(timing.update_time)),
+        format!("Display: {:6.1} ms", millis(timing.display_time)),
+        format!("Uploads: {:6}", timing.texture_uploads),
+        format!("Chunks:  {:6}", chunks_drawn),
+    ];
+
+    grid.draw_box((0, 0), (grid.width(), grid.height()), fg, bg);
+    for (i, line) in lines.iter().enumerate() {
+        grid.print_color((1, 1 + i as i32), line, true, Color::YELLOW, bg);
+    }
+}
+
+/// Update the mode stack with the diagnostics overlay layer kept out of its way, then draw the
+/// overlay in the top-right corner of the screen on top of everything else if it's shown.
+///
+/// The debug key toggles the overlay if the mode stack leaves it unhandled.  Slow frames are
+/// logged whether the overlay is shown or not.
+pub fn update(
+    world: &World,
+    mode_stack: &mut ModeStack,
+    inputs: &mut InputBuffer,
+    layers: &mut Vec<TileGridLayer<GameSym>>,
+    tilesets: &[Tileset<GameSym>],
+    window_size: Size,
+    timing: &FrameTiming,
+) -> RunControl {
+    let overlay = {
+        let mut diagnostics = world.borrow::<UniqueViewMut<Diagnostics>>();
+
+        diagnostics.chunks_drawn = 0;
+        if diagnostics.layer_attached {
+            diagnostics.layer_attached = false;
+            layers.pop()
+        } else {
+            None
+        }
+    };
+
+    if timing.frame_time >= SLOW_FRAME {
+        eprintln!(
+            "Warning: Slow frame: {:.1} ms (update {:.1} ms, display {:.1} ms, {} uploads)",
+            millis(timing.frame_time),
+            millis(timing.update_time),
+            millis(timing.display_time),
+            timing.texture_uploads,
+        );
+    }
+
+    let run_control =
+        mode_stack.update(world, inputs, layers, tilesets, window_size, &mut GameHooks);
+    let mut diagnostics = world.borrow::<UniqueViewMut<Diagnostics>>();
+
+    if let Some(InputEvent::Press(keycode)) = inputs.get_input() {
+        if let GameKey::Diagnostics =
+            gamekey::from_keycode(keycode, inputs.get_mods(KeyMods::SHIFT))
+        {
+            diagnostics.shown = !diagnostics.shown;
+        }
+    }
+
+    if diagnostics.shown
+        && !layers.is_empty()
+        && !matches!(run_control, RunControl::Quit | RunControl::Error(_))
+    {
+        let Options {
+            font, text_zoom, ..
+        } = *world.borrow::<UniqueView<Options>>();
+        let mut layer = overlay.unwrap_or(TileGridLayer {
+            draw_behind: true,
+            grids: Vec::new(),
+        });
+
+        if layer.grids.is_empty() {
+            layer
+                .grids
+                .push(TileGrid::new(OVERLAY_SIZE, tilesets, font as usize));
+            layer.grids[0].view.clear_color = None;
+        }
+
+        let grid = &mut layer.grids[0];
+        let tileset = &tilesets[font as usize];
+        let px_size = Size {
+            w: OVERLAY_SIZE.w * tileset.tile_width() * text_zoom,
+            h: OVERLAY_SIZE.h * tileset.tile_height() * text_zoom,
+        };
+
+        grid.set_tileset(tilesets, font as usize);
+        grid.view_centered(
+            tilesets,
+            text_zoom,
+            (window_size.w.saturating_sub(px_size.w) as i32, 0).into(),
+            px_size,
+        );
+        grid.view.zoom = text_zoom;
+        grid.view.bg_alpha = ui::OVERLAY_BG_ALPHA;
+        draw_overlay(grid, timing, diagnostics.chunks_drawn);
+
+        layers.push(layer);
+        diagnostics.layer_attached = true;
+    }
+
+    run_control
+}
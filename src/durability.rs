@@ -0,0 +1,124 @@
+use shipyard::{
+    AllStoragesViewMut, EntityId, Get, UniqueView, UniqueViewMut, View, ViewMut, World,
+};
+
+use crate::{
+    components::{Durability, EquipSlot, Equipment, Name},
+    locale::Locale,
+    message::{Messages, MsgCategory},
+    spawn,
+    ui::Options,
+};
+use ruggrogue::util::Color;
+
+/// Uses that freshly-spawned weapons and armor can take before they break.
+pub const MAX_DURABILITY: i32 = 150;
+
+/// Equipment worn down to this fraction of its durability or less is warned about.
+const BADLY_WORN_FRACTION: f32 = 0.25;
+
+fn equipped_in_slot(world: &World, holder_id: EntityId, slot: EquipSlot) -> Option<EntityId> {
+    world
+        .borrow::<View<Equipment>>()
+        .try_get(holder_id)
+        .ok()
+        .and_then(|equipment| match slot {
+            EquipSlot::Weapon => equipment.weapon,
+            EquipSlot::Armor => equipment.armor,
+        })
+}
+
+/// Wear down whatever the holder has equipped in the given slot by one use, breaking it if that
+/// was the last use it had left.  Does nothing if durability is turned off in the options.
+pub fn wear_equipment(world: &World, holder_id: EntityId, slot: EquipSlot) {
+    if !world.borrow::<UniqueView<Options>>().durability {
+        return;
+    }
+
+    let item_id = match equipped_in_slot(world, holder_id, slot) {
+        Some(item_id) => item_id,
+        None => return,
+    };
+    let (current, max) = {
+        let mut durabilities = world.borrow::<ViewMut<Durability>>();
+
+        match (&mut durabilities).try_get(item_id) {
+            Ok(durability) => {
+                durability.current = (durability.current - 1).max(0);
+                (durability.current, durability.max)
+            }
+            Err(_) => return,
+        }
+    };
+
+    if current > 0 && current != (max as f32 * BADLY_WORN_FRACTION) as i32 {
+        return;
+    }
+
+    world.run(
+        |locale: UniqueView<Locale>, mut msgs: UniqueViewMut<Messages>, names: View<Name>| {
+            let who = &names.get(holder_id).0;
+            let item = &names.get(item_id).0;
+
+            if current > 0 {
+                msgs.add_as(
+                    MsgCategory::Warning,
+                    locale.format("msg.equipment_worn", &[("who", who), ("item", item)]),
+                );
+            } else {
+                msgs.add_as(
+                    MsgCategory::Warning,
+                    locale.format("msg.equipment_breaks", &[("who", who), ("item", item)]),
+                );
+            }
+        },
+    );
+
+    if current == 0 {
+        {
+            let mut equipments = world.borrow::<ViewMut<Equipment>>();
+            let equipment = (&mut equipments).get(holder_id);
+
+            match slot {
+                EquipSlot::Weapon => equipment.weapon = None,
+                EquipSlot::Armor => equipment.armor = None,
+            }
+        }
+        spawn::despawn_entity(&mut world.borrow::<AllStoragesViewMut>(), item_id);
+    }
+}
+
+/// Restore whatever the holder has equipped in the given slot to full durability, returning the
+/// repaired item, or `None` if there was nothing there that needed repairing.
+pub fn repair_equipment(world: &World, holder_id: EntityId, slot: EquipSlot) -> Option<EntityId> {
+    let item_id = equipped_in_slot(world, holder_id, slot)?;
+    let mut durabilities = world.borrow::<ViewMut<Durability>>();
+    let durability = (&mut durabilities).try_get(item_id).ok()?;
+
+    if durability.current < durability.max {
+        durability.current = durability.max;
+        Some(item_id)
+    } else {
+        None
+    }
+}
+
+/// A short note on how worn an item is and a color to show it in, or `None` if it's in good shape
+/// or durability is turned off in the options.
+pub fn wear_suffix(world: &World, item_id: EntityId) -> Option<(&'static str, Color)> {
+    if !world.borrow::<UniqueView<Options>>().durability {
+        return None;
+    }
+
+    let durabilities = world.borrow::<View<Durability>>();
+    let durability = durabilities.try_get(item_id).ok()?;
+    let fraction = durability.current as f32 / durability.max.max(1) as f32;
+
+    if fraction <= BADLY_WORN_FRACTION {
+        Some((" (battered)", Color::ORANGE))
+    } else if fraction <= 0.5 {
+        Some((" (worn)", Color::YELLOW))
+    } else {
+        None
+    }
+}
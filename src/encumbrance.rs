@@ -0,0 +1,85 @@
+use shipyard::{EntityId, Get, UniqueView, UniqueViewMut, View, ViewMut, World};
+
+use crate::{
+    components::{Equipment, Experience, Inventory, Stomach, Weight},
+    locale::Locale,
+    message::{Messages, MsgCategory},
+    player::PlayerId,
+};
+
+/// Extra fullness lost for each step taken while burdened.
+const BURDENED_HUNGER_PER_MOVE: i32 = 2;
+
+/// Total weight that can be carried without being burdened at the given level.
+pub fn carry_capacity(level: i32) -> i32 {
+    30 + 5 * level
+}
+
+/// Weight of an item along with anything inside of it.
+fn item_weight(inventories: &View<Inventory>, weights: &View<Weight>, item_id: EntityId) -> i32 {
+    let contents_weight = inventories.try_get(item_id).map_or(0, |inv| {
+        inv.items
+            .iter()
+            .map(|id| item_weight(inventories, weights, *id))
+            .sum()
+    });
+
+    weights.try_get(item_id).map_or(0, |w| w.0) + contents_weight
+}
+
+/// Total weight of everything an entity is holding, both in its inventory and equipped.
+pub fn carried_weight(world: &World, holder_id: EntityId) -> i32 {
+    let (equipments, inventories, weights) =
+        world.borrow::<(View<Equipment>, View<Inventory>, View<Weight>)>();
+    let weight_of = |id: EntityId| item_weight(&inventories, &weights, id);
+    let inventory_weight = inventories
+        .try_get(holder_id)
+        .map_or(0, |inv| inv.items.iter().map(|id| weight_of(*id)).sum());
+    let equipment_weight = equipments.try_get(holder_id).map_or(0, |equip| {
+        equip.weapon.map_or(0, weight_of) + equip.armor.map_or(0, weight_of)
+    });
+
+    inventory_weight + equipment_weight
+}
+
+/// Carried weight and carrying capacity of an entity, or None if it has no level to base a
+/// capacity on.
+pub fn weight_and_capacity(world: &World, holder_id: EntityId) -> Option<(i32, i32)> {
+    let level = world
+        .borrow::<View<Experience>>()
+        .try_get(holder_id)
+        .ok()?
+        .level;
+
+    Some((carried_weight(world, holder_id), carry_capacity(level)))
+}
+
+pub fn is_burdened(world: &World, holder_id: EntityId) -> bool {
+    matches!(weight_and_capacity(world, holder_id), Some((weight, capacity)) if weight > capacity)
+}
+
+/// Let the player know if picking up or dropping something changed whether they're burdened.
+pub fn report_player_burden_change(world: &World, was_burdened: bool) {
+    let player_id = world.borrow::<UniqueView<PlayerId>>().0;
+    let now_burdened = is_burdened(world, player_id);
+
+    if now_burdened != was_burdened {
+        let locale = world.borrow::<UniqueView<Locale>>();
+        let mut msgs = world.borrow::<UniqueViewMut<Messages>>();
+
+        if now_burdened {
+            msgs.add_as(MsgCategory::Warning, locale.get("msg.burdened").into());
+        } else {
+            msgs.add_as(MsgCategory::Item, locale.get("msg.unburdened").into());
+        }
+    }
+}
+
+/// Make a burdened entity that just moved a step hungrier on the next hunger tick.
+pub fn strain_from_move(world: &World, mover_id: EntityId) {
+    if is_burdened(world, mover_id) {
+        if let Ok(stomach) = (&mut world.borrow::<ViewMut<Stomach>>()).try_get(mover_id) {
+            stomach.strain += BURDENED_HUNGER_PER_MOVE;
+        }
+    }
+}
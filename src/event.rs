@@ -0,0 +1,162 @@
+use shipyard::{EntityId, Get, UniqueView, UniqueViewMut, View, World};
+
+use crate::{
+    animation::{self, Animations},
+    components::{Coord, FieldOfView},
+    hints::{self, Hint},
+    locale::Locale,
+    message::{Messages, MsgCategory},
+    player::PlayerId,
+    quest, sound,
+    stats::RunStats,
+};
+use ruggrogue::util::Position;
+
+/// Something notable that happened in the game, for systems that react to it without the system
+/// that made it happen having to know about them.
+///
+/// Events describe what happened at the time, since the entities involved may be gone by the
+/// time the event is handled.
+pub enum GameEvent {
+    /// An entity lost hit points; healing is a negative amount.
+    DamageDealt { pos: Position, amount: i32 },
+    /// An entity used an item, possibly aimed at a target position.
+    ItemUsed {
+        user: EntityId,
+        target: Option<(i32, i32)>,
+    },
+    /// An entity died, killed by whoever last hurt it, if anyone.
+    EntityDied {
+        entity: EntityId,
+        /// Name to show, in the chosen language.
+        name: String,
+        /// Name to record, the same in every language.
+        key: String,
+        pos: (i32, i32),
+        pet: bool,
+        monster: bool,
+        killer: Option<EntityId>,
+    },
+    /// The player arrived at a new depth.
+    LevelChanged { depth: i32 },
+}
+
+/// Events that have happened since they were last dispatched.
+///
+/// Events are handled as soon as the step of the turn that caused them is over, so they're never
+/// saved.
+#[derive(Default)]
+pub struct GameEvents(Vec<GameEvent>);
+
+/// Queue up an event to be handled the next time events are dispatched.
+pub fn emit(world: &World, event: GameEvent) {
+    world.borrow::<UniqueViewMut<GameEvents>>().0.push(event);
+}
+
+/// Queue up damage dealt to an entity where it stands; healing is a negative amount.
+pub fn emit_damage(world: &World, entity: EntityId, amount: i32) {
+    let pos = world
+        .borrow::<View<Coord>>()
+        .try_get(entity)
+        .map(|coord| coord.0);
+
+    if let Ok(pos) = pos {
+        emit(world, GameEvent::DamageDealt { pos, amount });
+    }
+}
+
+/// Hand every queued event to each system that listens for it, in the order they happened.
+pub fn dispatch(world: &World) {
+    let events = std::mem::take(&mut world.borrow::<UniqueViewMut<GameEvents>>().0);
+
+    for event in events {
+        announce(world, &event);
+        animate(world, &event);
+        record(world, &event);
+    }
+}
+
+/// Tell the player about events they can see or hear.
+fn announce(world: &World, event: &GameEvent) {
+    if let GameEvent::EntityDied {
+        entity,
+        name,
+        pos,
+        pet,
+        ..
+    } = event
+    {
+        let locale = world.borrow::<UniqueView<Locale>>();
+        let mut msgs = world.borrow::<UniqueViewMut<Messages>>();
+        let player_id = world.borrow::<UniqueView<PlayerId>>();
+        let (coords, fovs) = world.borrow::<(View<Coord>, View<FieldOfView>)>();
+        let player_pos = coords.get(player_id.0).0.into();
+        let player_fov = fovs.get(player_id.0);
+
+        // Deaths out of sight can still be heard, but the player always learns of the death of
+        // their pet.
+        if *pet {
+            msgs.add_as(
+                MsgCategory::Combat,
+                locale.format("msg.pet_dies", &[("who", name)]),
+            );
+        } else if *entity == player_id.0 || player_fov.get(*pos) {
+            msgs.add_as(
+                MsgCategory::Combat,
+                locale.format("msg.dies", &[("who", name)]),
+            );
+        } else if let Some(cue) =
+            sound::cue(&locale, player_pos, player_fov, *pos, "sound.death_cry")
+        {
+            msgs.add(cue);
+        }
+
+        if *entity == player_id.0 {
+            msgs.add(locale.get("msg.press_space").into());
+        }
+    }
+}
+
+/// Show events over the map.
+fn animate(world: &World, event: &GameEvent) {
+    match event {
+        GameEvent::DamageDealt { pos, amount } => {
+            animation::pop_up_number(world, *pos, -amount);
+        }
+        GameEvent::LevelChanged { depth } => {
+            world.borrow::<UniqueViewMut<Animations>>().clear();
+
+            let banner = world
+                .borrow::<UniqueView<Locale>>()
+                .format("feeling.depth_banner", &[("depth", depth)]);
+            animation::show_banner(world, banner);
+        }
+        GameEvent::ItemUsed { .. } | GameEvent::EntityDied { .. } => {}
+    }
+}
+
+/// Keep track of what the player has done for statistics, quests and hints.
+fn record(world: &World, event: &GameEvent) {
+    let player_id = world.borrow::<UniqueView<PlayerId>>().0;
+
+    match event {
+        GameEvent::EntityDied {
+            key,
+            monster: true,
+            killer: Some(killer),
+            ..
+        } if *killer == player_id => {
+            world.borrow::<UniqueViewMut<RunStats>>().add_kill(key);
+            quest::record_kill(world, key);
+        }
+        // Someone who has aimed an item already knows how to.
+        GameEvent::ItemUsed {
+            user,
+            target: Some(_),
+            ..
+        } if *user == player_id => {
+            hints::mark_shown(world, Hint::Target);
+        }
+        _ => {}
+    }
+}
@@ -3,11 +3,100 @@ use serde::{Deserialize, Serialize};
 use shipyard::{EntityId, Get, IntoIter, Shiperator, UniqueView, UniqueViewMut, View, ViewMut};
 
 use crate::{
-    components::{CombatStats, Experience, GivesExperience, Monster, Name, Player},
+    components::{CombatStats, DamageLog, Experience, GivesExperience, Monster, Name, Pet, Player},
     message::Messages,
     player::PlayerId,
 };
 
+/// Difficulty presets that can be picked when starting a new game.
+#[derive(Clone, Copy, Deserialize, PartialEq, Serialize)]
+pub enum DifficultyPreset {
+    Easy,
+    Normal,
+    Hard,
+    Custom,
+}
+
+impl DifficultyPreset {
+    pub const ALL: [DifficultyPreset; 4] = [
+        DifficultyPreset::Easy,
+        DifficultyPreset::Normal,
+        DifficultyPreset::Hard,
+        DifficultyPreset::Custom,
+    ];
+
+    /// Key of the name of the preset in the locale catalogs.
+    pub fn name_key(&self) -> &'static str {
+        match self {
+            DifficultyPreset::Easy => "difficulty.easy",
+            DifficultyPreset::Normal => "difficulty.normal",
+            DifficultyPreset::Hard => "difficulty.hard",
+            DifficultyPreset::Custom => "difficulty.custom",
+        }
+    }
+}
+
+/// How hard a game is, chosen when it starts and kept through New Game Plus.
+///
+/// Rates are percentages of their usual values.
+#[derive(Clone, Copy, Deserialize, Serialize)]
+pub struct DifficultySettings {
+    pub preset: DifficultyPreset,
+    /// Levels added to monsters as they spawn; negative to take levels away.
+    pub monster_levels: i32,
+    /// How likely rooms are to hold monsters.
+    pub spawn_density: u32,
+    pub hunger_rate: u32,
+    pub regen_rate: u32,
+}
+
+impl DifficultySettings {
+    pub const MONSTER_LEVELS_RANGE: (i32, i32) = (-3, 3);
+    pub const RATE_RANGE: (u32, u32) = (25, 300);
+    pub const RATE_STEP: u32 = 25;
+
+    /// Settings for the given preset; custom settings start out the same as normal ones.
+    pub fn from_preset(preset: DifficultyPreset) -> Self {
+        let (monster_levels, spawn_density, hunger_rate, regen_rate) = match preset {
+            DifficultyPreset::Easy => (-1, 75, 75, 150),
+            DifficultyPreset::Normal | DifficultyPreset::Custom => (0, 100, 100, 100),
+            DifficultyPreset::Hard => (1, 125, 125, 75),
+        };
+
+        Self {
+            preset,
+            monster_levels,
+            spawn_density,
+            hunger_rate,
+            regen_rate,
+        }
+    }
+
+    /// Check that custom values are in range, falling back to normal settings if they aren't.
+    pub fn validated(self) -> Self {
+        let levels = Self::MONSTER_LEVELS_RANGE;
+        let rates = Self::RATE_RANGE;
+        let rate_ok = |rate: u32| rate >= rates.0 && rate <= rates.1;
+
+        if self.monster_levels >= levels.0
+            && self.monster_levels <= levels.1
+            && rate_ok(self.spawn_density)
+            && rate_ok(self.hunger_rate)
+            && rate_ok(self.regen_rate)
+        {
+            self
+        } else {
+            Self::default()
+        }
+    }
+}
+
+impl Default for DifficultySettings {
+    fn default() -> Self {
+        Self::from_preset(DifficultyPreset::Normal)
+    }
+}
+
 /// Tracking state that counts total amount of experience points that could be gained at the time
 /// of entering a new dungeon depth, used to determine the approximate level that monsters and
 /// items on the current dungeon floor should be based around.
@@ -15,6 +104,9 @@ use crate::{
 pub struct Difficulty {
     pub id: EntityId,
     exp_for_next_depth: u64,
+    /// Saves from before difficulty settings existed were played on normal.
+    #[serde(default)]
+    pub settings: DifficultySettings,
 }
 
 impl Difficulty {
@@ -26,6 +118,7 @@ impl Difficulty {
         Self {
             id,
             exp_for_next_depth: 0,
+            settings: DifficultySettings::default(),
         }
     }
 
@@ -33,6 +126,7 @@ impl Difficulty {
     pub fn replace(&mut self, replacement: Self) {
         self.id = replacement.id;
         self.exp_for_next_depth = replacement.exp_for_next_depth;
+        self.settings = replacement.settings;
     }
 
     /// Get the level tracked by difficulty with a fractional part based on experience.
@@ -93,6 +187,47 @@ pub fn redeem_exp_for_next_depth(
     difficulty.exp_for_next_depth = 0;
 }
 
+/// One in this many experience points of a dead entity goes to whoever dealt the killing blow; the
+/// rest is shared out between everyone who hurt it, in proportion to the damage they dealt.
+const KILL_EXP_DIVISOR: u64 = 4;
+
+/// Share out experience given by a dead entity between its killer and everyone who hurt it.
+///
+/// Experience is only given out on death, so the total given never exceeds what [Difficulty]
+/// counts, even for monsters that are hurt but never killed.
+pub fn share_exp(
+    exp: u64,
+    killer: Option<EntityId>,
+    damage_log: Option<&DamageLog>,
+) -> Vec<(EntityId, u64)> {
+    let dealt = damage_log.map_or(&[][..], |log| &log.0[..]);
+    let total_damage: u64 = dealt.iter().map(|(_, damage)| damage).sum();
+
+    if total_damage == 0 {
+        return killer.map(|k| vec![(k, exp)]).unwrap_or_default();
+    }
+
+    let kill_bonus = if killer.is_some() {
+        exp / KILL_EXP_DIVISOR
+    } else {
+        0
+    };
+    let mut shares = dealt
+        .iter()
+        .map(|&(id, damage)| (id, (exp - kill_bonus) * damage / total_damage))
+        .collect::<Vec<_>>();
+    // The killer gets whatever's left over from rounding down the shares, along with the bonus.
+    let leftover = exp - shares.iter().map(|(_, share)| share).sum::<u64>();
+
+    if let Some(killer) = killer {
+        shares.push((killer, leftover));
+    } else if let Some(top) = shares.iter_mut().max_by_key(|(_, share)| *share) {
+        top.1 += leftover;
+    }
+
+    shares
+}
+
 /// The factor around which all combat stats are scaled around.
 fn level_factor(level: i32) -> f32 {
     (1.0 + (level - 1) as f32 * 0.1).max(0.1)
@@ -122,6 +257,11 @@ pub fn calc_monster_defense(level: i32) -> f32 {
     level_factor(level) * 4.0
 }
 
+/// Work out the level of a monster from its attack, for monsters that don't track experience.
+pub fn estimate_monster_level(attack: f32) -> i32 {
+    (((attack / 8.0 - 1.0) * 10.0).round() as i32 + 1).max(1)
+}
+
 pub fn calc_monster_exp(level: i32) -> u64 {
     (level_factor(level) * 10.0).ceil() as u64
 }
@@ -140,6 +280,7 @@ pub fn gain_levels(
     mut combat_stats: ViewMut<CombatStats>,
     mut exps: ViewMut<Experience>,
     names: View<Name>,
+    pets: View<Pet>,
     players: View<Player>,
 ) {
     for (id, exp) in (&mut exps).iter().with_id() {
@@ -171,7 +312,7 @@ pub fn gain_levels(
                     stats.attack = new_attack;
                     stats.defense = new_defense;
 
-                    if id == player_id.0 {
+                    if id == player_id.0 || pets.contains(id) {
                         msgs.add(format!("{} is now level {}!", &names.get(id).0, exp.level));
                     }
                 }
@@ -0,0 +1,67 @@
+use shipyard::{EntityId, Get, UniqueView, View, World};
+use std::collections::HashMap;
+
+use crate::{
+    components::{Charmed, CombatStats, Coord, Faction, FieldOfView, Monster, Pet, Player},
+    map::Map,
+};
+
+/// The faction an entity fights for, or `None` for entities that stay out of fights entirely.
+///
+/// Charms take priority, followed by an explicit [Faction] component.  Otherwise the player and
+/// their pets fight for the player, and monsters fight for the monsters.
+pub fn faction_of(world: &World, who: EntityId) -> Option<Faction> {
+    if let Ok(charmed) = world.borrow::<View<Charmed>>().try_get(who) {
+        Some(charmed.faction)
+    } else if let Ok(faction) = world.borrow::<View<Faction>>().try_get(who) {
+        Some(*faction)
+    } else if world.borrow::<View<Player>>().contains(who)
+        || world.borrow::<View<Pet>>().contains(who)
+    {
+        Some(Faction::Player)
+    } else if world.borrow::<View<Monster>>().contains(who) {
+        Some(Faction::Monsters)
+    } else {
+        None
+    }
+}
+
+/// True if the two entities fight for different factions.
+pub fn is_hostile(world: &World, a: EntityId, b: EntityId) -> bool {
+    matches!(
+        (faction_of(world, a), faction_of(world, b)),
+        (Some(a_faction), Some(b_faction)) if a_faction != b_faction
+    )
+}
+
+/// Find the closest hostile entity that each of the given entities can see.
+///
+/// Only the part of the map that each entity can see is searched, and factions are looked up only
+/// once per entity for all of the given entities, which matters when there are hundreds of
+/// monsters on a level.
+pub fn nearest_visible_foes(world: &World, whos: &[EntityId]) -> Vec<Option<EntityId>> {
+    let map = world.borrow::<UniqueView<Map>>();
+    let combat_stats = world.borrow::<View<CombatStats>>();
+    let coords = world.borrow::<View<Coord>>();
+    let fovs = world.borrow::<View<FieldOfView>>();
+    let mut factions: HashMap<EntityId, Option<Faction>> = HashMap::new();
+    let mut faction_of = |id| *factions.entry(id).or_insert_with(|| faction_of(world, id));
+
+    whos.iter()
+        .map(|&who| {
+            let who_faction = faction_of(who)?;
+            let (who_coord, who_fov) = match (coords.try_get(who), fovs.try_get(who)) {
+                (Ok(coord), Ok(fov)) => (coord, fov),
+                _ => return None,
+            };
+
+            map.nearest_entity(who_coord.0.into(), who_fov.range, |id, pos| {
+                id != who
+                    && combat_stats.contains(id)
+                    && who_fov.get(pos)
+                    && matches!(faction_of(id), Some(faction) if faction != who_faction)
+            })
+            .map(|(id, _)| id)
+        })
+        .collect()
+}
@@ -0,0 +1,83 @@
+use shipyard::{IntoIter, Shiperator, UniqueView, UniqueViewMut, View, World};
+
+use crate::{
+    components::{CombatBonus, Coord, Experience, GivesExperience, Item, Monster, Victory},
+    experience::{self, Difficulty},
+    locale::Locale,
+    map::Map,
+    message::Messages,
+    BaseEquipmentLevel,
+};
+
+/// Monsters and equipment this many levels above the usual for the depth stand out.
+const OUT_OF_DEPTH_LEVELS: i32 = 3;
+
+/// Size up the level that was just generated and tell the player how it feels on arrival.
+///
+/// Feelings are based on how crowded the level is, whether any monsters or equipment are well
+/// above the usual level for the depth, and whether it holds a vault or the present.
+pub fn announce_level_feeling(world: &World) {
+    let keys = {
+        let map = world.borrow::<UniqueView<Map>>();
+        let difficulty = world.borrow::<UniqueView<Difficulty>>();
+        let base_equipment_level = world.borrow::<UniqueView<BaseEquipmentLevel>>().0;
+        let combat_bonuses = world.borrow::<View<CombatBonus>>();
+        let coords = world.borrow::<View<Coord>>();
+        let exps = world.borrow::<View<Experience>>();
+        let gives_exps = world.borrow::<View<GivesExperience>>();
+        let items = world.borrow::<View<Item>>();
+        let monsters = world.borrow::<View<Monster>>();
+        let victories = world.borrow::<View<Victory>>();
+        let level = difficulty.as_f32(&exps).round() as i32;
+        let strong_exp = experience::calc_monster_exp(level + OUT_OF_DEPTH_LEVELS);
+        let fine_level = level + OUT_OF_DEPTH_LEVELS + base_equipment_level;
+        let in_vault =
+            |coord: &Coord| matches!(map.vault, Some(v) if v.contains(coord.0.x, coord.0.y));
+        let mut keys = Vec::new();
+
+        if (&victories, &coords).iter().next().is_some() {
+            keys.push("feeling.present");
+        }
+
+        let num_monsters = (&monsters, &coords).iter().count();
+        let strong_monster = (&monsters, &coords, &gives_exps)
+            .iter()
+            .into_iter()
+            .any(|(_, _, gives_exp)| gives_exp.0 >= strong_exp);
+
+        if strong_monster {
+            keys.push("feeling.strong_monster");
+        } else if num_monsters * 2 >= map.rooms.len() * 3 {
+            keys.push("feeling.crowded");
+        } else if num_monsters * 4 <= map.rooms.len() {
+            keys.push("feeling.quiet");
+        }
+
+        if map.vault.is_some() {
+            keys.push("feeling.vault");
+        }
+
+        let fine_equipment =
+            (&items, &coords, &combat_bonuses)
+                .iter()
+                .into_iter()
+                .any(|(_, coord, bonus)| {
+                    !in_vault(coord)
+                        && (bonus.attack >= experience::calc_weapon_attack(fine_level)
+                            || bonus.defense >= experience::calc_armor_defense(fine_level))
+                });
+
+        if fine_equipment {
+            keys.push("feeling.treasure");
+        }
+
+        keys
+    };
+
+    let locale = world.borrow::<UniqueView<Locale>>();
+    let mut msgs = world.borrow::<UniqueViewMut<Messages>>();
+
+    for key in keys {
+        msgs.add(locale.get(key).into());
+    }
+}
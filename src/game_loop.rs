@@ -0,0 +1,175 @@
+use shipyard::{EntityId, UniqueView, UniqueViewMut, World};
+use std::error::Error;
+
+use crate::{
+    damage, event, experience, hunger, item,
+    map::{self, Map},
+    message::Messages,
+    monster, pet,
+    player::{self, PlayerId, PlayerInputResult},
+    status, terrain, vision, TurnCount,
+};
+
+/// Something the player can do, carried out by [advance_turn].
+///
+/// Anything driving the game can pick these, whether it's the dungeon mode reacting to input and
+/// menus, a replay or a bot, without going through the modes and input handling.
+pub enum PlayerAction {
+    /// Step in a direction, attacking, swapping with or interacting with whatever is there.
+    Move(i32, i32),
+    PickUpItems(Vec<EntityId>),
+    DropItem(EntityId),
+    DropItems(Vec<EntityId>),
+    EquipItem(EntityId),
+    RemoveEquipment(EntityId),
+    DropEquipment(EntityId),
+    /// Use an item, aimed at a target position if it needs one.
+    UseItem(EntityId, Option<(i32, i32)>),
+    /// Head towards a known position on the map, one step at a time.
+    TravelTo((i32, i32)),
+    /// Take the downstairs to the next level, which doesn't take a turn.
+    Descend,
+    /// Fall through a hole in the floor to the next level, which doesn't take a turn.
+    FallDown,
+    /// The player already acted, e.g. through [player::player_input], and used up their turn.
+    TurnTaken,
+}
+
+/// What came of a call to [advance_turn].
+pub struct TurnOutcome {
+    /// True if the player's action used up their turn, giving everything else a turn too.
+    pub time_passed: bool,
+    /// True if the player's action won the game.
+    pub victory: bool,
+    /// The victory item the player tried to use if the game couldn't be saved before winning, and
+    /// why; the item is left unused so that winning can be tried again.
+    pub unsaved_victory: Option<(EntityId, Box<dyn Error>)>,
+    /// True if the player survived the turn.
+    pub player_alive: bool,
+    /// True if the player ended up on a different level.
+    pub depth_changed: bool,
+}
+
+/// Carry out an action for the player and, if it took their turn, give everything else their
+/// turn after it.
+///
+/// Only the game world is changed: events raised along the way are dispatched, but nothing is
+/// drawn and nothing is saved besides the game just before it's won, leaving the rest up to the
+/// caller.
+pub fn advance_turn(world: &World, action: PlayerAction) -> TurnOutcome {
+    let player_id = world.borrow::<UniqueView<PlayerId>>().0;
+    let old_depth = world.borrow::<UniqueView<Map>>().depth;
+    let mut victory = false;
+    let mut unsaved_victory = None;
+    let time_passed = match action {
+        PlayerAction::Move(dx, dy) => matches!(
+            player::try_move_player(world, dx, dy, false),
+            PlayerInputResult::TurnDone
+        ),
+        PlayerAction::PickUpItems(item_ids) => {
+            player::player_pick_up_items(world, &item_ids);
+            true
+        }
+        PlayerAction::DropItem(item_id) => {
+            player::player_drop_item(world, item_id);
+            true
+        }
+        PlayerAction::DropItems(item_ids) => {
+            player::player_drop_items(world, &item_ids);
+            true
+        }
+        PlayerAction::EquipItem(item_id) => {
+            item::equip_item(world, player_id, item_id);
+            true
+        }
+        PlayerAction::RemoveEquipment(item_id) => {
+            item::remove_equipment(world, player_id, item_id);
+            true
+        }
+        PlayerAction::DropEquipment(item_id) => {
+            item::drop_equipment(world, player_id, item_id);
+            true
+        }
+        PlayerAction::UseItem(item_id, target) => {
+            // Nothing else gets a turn once the game is won, or while winning it is held off.
+            match item::use_item(world, player_id, item_id, target) {
+                Ok(won) => victory = won,
+                Err(e) => unsaved_victory = Some((item_id, e)),
+            }
+            !victory && unsaved_victory.is_none()
+        }
+        PlayerAction::TravelTo(dest) => matches!(
+            player::player_travel_to(world, dest),
+            PlayerInputResult::TurnDone
+        ),
+        PlayerAction::Descend => {
+            player::player_do_descend(world);
+            false
+        }
+        PlayerAction::FallDown => {
+            player::player_fall_down(world);
+            false
+        }
+        PlayerAction::TurnTaken => true,
+    };
+
+    if time_passed {
+        finish_turn(world);
+    }
+
+    // Handle anything that happened outside of a turn, e.g. descending.
+    event::dispatch(world);
+
+    if cfg!(debug_assertions) {
+        world.run(map::check_entity_positions);
+    }
+
+    TurnOutcome {
+        time_passed,
+        victory,
+        unsaved_victory,
+        player_alive: world.run(player::player_is_alive),
+        depth_changed: world.borrow::<UniqueView<Map>>().depth != old_depth,
+    }
+}
+
+/// Let everything else act after the player has used up their turn, stopping early if the player
+/// dies along the way.
+fn finish_turn(world: &World) {
+    damage::handle_dead_entities(world);
+    event::dispatch(world);
+    world.run(experience::gain_levels);
+    world.run(vision::recalculate_fields_of_view);
+    world.run(monster::enqueue_monster_turns);
+
+    if !world.run(player::player_is_alive) {
+        return;
+    }
+
+    pet::do_pet_turns(world);
+    monster::do_monster_turns(world);
+    damage::handle_dead_entities(world);
+    event::dispatch(world);
+    world.run(experience::gain_levels);
+    world.run(vision::recalculate_fields_of_view);
+
+    if !world.run(player::player_is_alive) {
+        return;
+    }
+
+    world.run(hunger::tick_hunger);
+    terrain::tick_terrain(world);
+    world.run(status::tick_statuses);
+    damage::handle_dead_entities(world);
+    event::dispatch(world);
+    world.run(experience::gain_levels);
+    world.run(vision::recalculate_fields_of_view);
+
+    if !world.run(player::player_is_alive) {
+        return;
+    }
+
+    world.run(damage::clear_hurt_bys);
+    world.borrow::<UniqueViewMut<TurnCount>>().0 += 1;
+    world.borrow::<UniqueViewMut<Messages>>().separator();
+}
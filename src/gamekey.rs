@@ -1,5 +1,7 @@
 use sdl2::keyboard::Keycode;
 
+use ruggrogue::MenuNav;
+
 pub enum GameKey {
     Unmapped,
     Up,
@@ -25,6 +27,25 @@ pub enum GameKey {
     RemoveItem,
     UseItem,
     DropItem,
+    TakeNote,
+    RecentMessages,
+    Journal,
+    QuestLog,
+    Bestiary,
+    Interact,
+    DoAgain,
+    RecordMacro,
+    PlayMacro,
+    SwapLoadout,
+    LookList,
+    Inspect,
+    SelectAll,
+    Filter,
+    ZoomIn,
+    ZoomOut,
+    Diagnostics,
+    Screenshot,
+    ExportMap,
 }
 
 pub fn from_keycode(key: Keycode, shift: bool) -> GameKey {
@@ -59,6 +80,54 @@ pub fn from_keycode(key: Keycode, shift: bool) -> GameKey {
         Keycode::R => GameKey::RemoveItem,
         Keycode::A => GameKey::UseItem,
         Keycode::D => GameKey::DropItem,
+        Keycode::F2 => GameKey::TakeNote,
+        Keycode::F3 => GameKey::Diagnostics,
+        Keycode::F12 => {
+            if shift {
+                GameKey::ExportMap
+            } else {
+                GameKey::Screenshot
+            }
+        }
+        Keycode::M => GameKey::RecentMessages,
+        Keycode::Q => {
+            if shift {
+                GameKey::QuestLog
+            } else {
+                GameKey::Journal
+            }
+        }
+        Keycode::X => GameKey::LookList,
+        Keycode::Backslash => GameKey::Bestiary,
+        Keycode::Semicolon => GameKey::Inspect,
+        Keycode::F => GameKey::Interact,
+        Keycode::Backquote => {
+            if shift {
+                GameKey::RecordMacro
+            } else {
+                GameKey::DoAgain
+            }
+        }
+        Keycode::P => GameKey::PlayMacro,
+        Keycode::Z => GameKey::SwapLoadout,
+        Keycode::Asterisk | Keycode::KpMultiply => GameKey::SelectAll,
+        Keycode::Num8 if shift => GameKey::SelectAll,
+        Keycode::Slash | Keycode::KpDivide => GameKey::Filter,
+        Keycode::Equals | Keycode::Plus | Keycode::KpPlus => GameKey::ZoomIn,
+        Keycode::Minus | Keycode::KpMinus => GameKey::ZoomOut,
         _ => GameKey::Unmapped,
     }
 }
+
+/// The menu cursor movement that a game key stands for, if any.
+pub fn menu_nav(key: &GameKey) -> Option<MenuNav> {
+    match key {
+        GameKey::Up => Some(MenuNav::Up),
+        GameKey::Down => Some(MenuNav::Down),
+        GameKey::PageUp => Some(MenuNav::PageUp),
+        GameKey::PageDown => Some(MenuNav::PageDown),
+        GameKey::Home => Some(MenuNav::Home),
+        GameKey::End => Some(MenuNav::End),
+        _ => None,
+    }
+}
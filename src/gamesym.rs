@@ -1,7 +1,12 @@
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, path::PathBuf};
+use std::{collections::HashMap, fs, path::PathBuf};
 
-use ruggrogue::{Symbol, TilesetInfo};
+use ruggrogue::{DynSym, Symbol, SymbolRegistry, TilesetInfo};
+
+const SYMBOLS_PATH: &str = "assets/symbols.json";
+
+/// ID given to dynamic symbols loaded by a name that isn't registered, drawn as a fallback.
+pub const UNKNOWN_DYN_ID: u32 = u32::MAX;
 
 #[derive(Copy, Clone, Eq, PartialEq, Hash, Deserialize, Serialize)]
 pub enum GameSym {
@@ -24,6 +29,11 @@ pub enum GameSym {
     WallNesw,
     WallOther,
     DownStairs,
+    ShallowWater,
+    DeepWater,
+    Lava,
+    Chasm,
+    LockedDoor,
     Player,
     Ration,
     HealthPotion,
@@ -51,6 +61,17 @@ pub enum GameSym {
     ArmyHelmet,
     FlakJacket,
     Present,
+    Bag,
+    Gold,
+    Pickaxe,
+    Whetstone,
+    RepairKit,
+    Key,
+    Trinket,
+    Boulder,
+    CrackedWall,
+    Chest,
+    Gravestone,
     Blob,
     Bat,
     Crab,
@@ -76,6 +97,12 @@ pub enum GameSym {
     Dweller,
     LittleHelper,
     BigHelper,
+    Dog,
+    /// A symbol registered by name in the [SymbolRegistry], identified by its [DynSym::id].
+    ///
+    /// IDs depend on which symbols happen to be registered, so save files keep the names of the
+    /// registered symbols to match them up again when loading.
+    Dyn(u32),
 }
 
 impl Symbol for GameSym {
@@ -102,6 +129,11 @@ impl Symbol for GameSym {
             WallNesw => '╬',
             WallOther => '#',
             DownStairs => '>',
+            ShallowWater => '~',
+            DeepWater => '~',
+            Lava => '~',
+            Chasm => ':',
+            LockedDoor => '+',
             Player => '@',
             Ration => '%',
             HealthPotion => '!',
@@ -129,6 +161,17 @@ impl Symbol for GameSym {
             ArmyHelmet => '[',
             FlakJacket => '[',
             Present => '$',
+            Bag => '(',
+            Gold => '*',
+            Pickaxe => '(',
+            Whetstone => '(',
+            RepairKit => '(',
+            Key => '-',
+            Trinket => '"',
+            Boulder => '0',
+            CrackedWall => '#',
+            Chest => '=',
+            Gravestone => '|',
             Blob => 'b',
             Bat => 'B',
             Crab => 'c',
@@ -154,11 +197,67 @@ impl Symbol for GameSym {
             Dweller => 'D',
             LittleHelper => 'h',
             BigHelper => 'H',
+            Dog => 'd',
+            Dyn(_) => '?',
+        }
+    }
+
+    fn dyn_sym(self) -> Option<DynSym> {
+        match self {
+            GameSym::Dyn(id) => Some(DynSym::from_id(id)),
+            _ => None,
         }
     }
 }
 
-pub fn urizen_tileset_info() -> TilesetInfo<GameSym> {
+impl GameSym {
+    /// A character that sets this symbol apart from others that would otherwise only differ from
+    /// it by color, or `None` for dynamic symbols.
+    pub fn distinct_char(self) -> Option<char> {
+        use GameSym::*;
+
+        match self {
+            MagicMissileScroll => Some('~'),
+            FireballScroll => Some('*'),
+            SleepScroll => Some('='),
+            DeepWater => Some('='),
+            Lava => Some('^'),
+            Pickaxe => Some('/'),
+            Whetstone => Some('{'),
+            RepairKit => Some('}'),
+            Ghost => Some('p'),
+            Dyn(_) => None,
+            _ => Some(self.text_fallback()),
+        }
+    }
+}
+
+/// Load named symbols and their fallback characters from the symbols file, if it exists.
+///
+/// The symbols file is a JSON object mapping symbol names to single-character strings.
+pub fn load_symbol_registry() -> SymbolRegistry {
+    let mut registry = SymbolRegistry::new();
+
+    if let Ok(contents) = fs::read_to_string(SYMBOLS_PATH) {
+        match serde_json::from_str::<HashMap<String, char>>(&contents) {
+            Ok(symbols) => {
+                let mut names = symbols.keys().collect::<Vec<_>>();
+
+                // Register in name order so symbol IDs are stable between runs.
+                names.sort();
+                for name in names {
+                    registry.register(name, symbols[name]);
+                }
+            }
+            Err(e) => eprintln!("Warning: Failed to load {}: {}", SYMBOLS_PATH, e),
+        }
+    }
+
+    registry
+}
+
+/// Map of characters to glyph positions in the Urizen tileset image.
+pub fn urizen_font_map() -> HashMap<char, (i32, i32)> {
     let mut font_map: HashMap<char, (i32, i32)> = HashMap::new();
     {
         for (i, ch) in ('A'..='T').enumerate() {
@@ -217,6 +316,11 @@ pub fn urizen_tileset_info() -> TilesetInfo<GameSym> {
         font_map.insert(';', (18, 47));
     }
 
+    font_map
+}
+
+/// Map of game symbols to tile positions in the Urizen tileset image.
+pub fn urizen_symbol_map() -> HashMap<GameSym, (i32, i32)> {
     let mut symbol_map: HashMap<GameSym, (i32, i32)> = HashMap::new();
     {
         use GameSym::*;
@@ -240,6 +344,8 @@ pub fn urizen_tileset_info() -> TilesetInfo<GameSym> {
         symbol_map.insert(WallNesw, (1, 0));
         symbol_map.insert(WallOther, (1, 0));
         symbol_map.insert(DownStairs, (10, 0));
+        // Water, lava, chasms and locked doors have no tiles of their own, so they fall back to
+        // their text characters.
         symbol_map.insert(Player, (29, 0));
         symbol_map.insert(Ration, (5, 16));
         symbol_map.insert(HealthPotion, (29, 19));
@@ -267,6 +373,8 @@ pub fn urizen_tileset_info() -> TilesetInfo<GameSym> {
         symbol_map.insert(ArmyHelmet, (33, 43));
         symbol_map.insert(FlakJacket, (34, 43));
         symbol_map.insert(Present, (27, 30));
+        // Bag, Gold, Pickaxe, Whetstone, RepairKit, Key, Trinket, Boulder, CrackedWall, Chest and
+        // Gravestone have no tiles of their own, so they fall back to their text characters.
         symbol_map.insert(Blob, (39, 10));
         symbol_map.insert(Bat, (8, 13));
         symbol_map.insert(Crab, (7, 13));
@@ -292,14 +400,20 @@ pub fn urizen_tileset_info() -> TilesetInfo<GameSym> {
         symbol_map.insert(Dweller, (1, 41));
         symbol_map.insert(LittleHelper, (26, 30));
         symbol_map.insert(BigHelper, (25, 30));
+        // Dog has no tile of its own either, so it falls back to its text character.
     }
 
+    symbol_map
+}
+
+pub fn urizen_tileset_info() -> TilesetInfo<GameSym> {
     TilesetInfo::<GameSym> {
         image_path: PathBuf::from("assets/urizen/urizen-onebit-tileset-mono.png"),
         tile_size: (12, 12).into(),
         tile_start: (1, 1).into(),
         tile_gap: (1, 1).into(),
-        font_map,
-        symbol_map,
+        font_map: urizen_font_map(),
+        symbol_map: urizen_symbol_map(),
+        dyn_symbol_map: HashMap::new(),
     }
 }
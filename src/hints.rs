@@ -0,0 +1,115 @@
+use serde::{Deserialize, Serialize};
+use shipyard::{Get, UniqueView, UniqueViewMut, View, World};
+use std::collections::BTreeSet;
+
+use crate::{
+    components::{CombatStats, Inventory, Ranged},
+    hunger,
+    locale::Locale,
+    message::Messages,
+    player::PlayerId,
+    stats,
+    ui::Options,
+};
+use ruggrogue::Error;
+
+/// A tip about how to play that is shown to the player when it first becomes useful.
+#[derive(Clone, Copy, Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Hint {
+    /// Pick up items with the pick up key; repeated until the player picks something up.
+    PickUp,
+    /// Rest to heal when low on hit points.
+    Rest,
+    /// Aim items that need a target.
+    Target,
+    /// Eat when getting hungry.
+    Eat,
+}
+
+impl Hint {
+    fn text_key(self) -> &'static str {
+        match self {
+            Hint::PickUp => "hint.pick_up",
+            Hint::Rest => "hint.rest",
+            Hint::Target => "hint.target",
+            Hint::Eat => "hint.eat",
+        }
+    }
+}
+
+/// Hints that the player has already been given, across all games.
+///
+/// This is kept in the profile instead of the save file, so that new games don't repeat hints
+/// that the player has seen before.
+pub struct Hints {
+    shown: BTreeSet<Hint>,
+}
+
+impl Hints {
+    /// Hints shown so far according to the profile.
+    pub fn load() -> Self {
+        Self {
+            shown: stats::load_profile().hints_shown,
+        }
+    }
+
+    /// True if the given hint should be shown to the player.
+    pub fn wants(&self, options: &Options, hint: Hint) -> bool {
+        options.hints && !self.shown.contains(&hint)
+    }
+}
+
+/// Forget all hints that have been shown so they'll be shown again, returning an error if that
+/// couldn't be written to the profile.
+pub fn reset(world: &World) -> Result<(), Error> {
+    world.borrow::<UniqueViewMut<Hints>>().shown.clear();
+    stats::forget_hints_shown(world);
+    stats::check_profile_saved(world)
+}
+
+/// Note that the player has been given a hint so that it isn't given again.
+pub fn mark_shown(world: &World, hint: Hint) {
+    if world.borrow::<UniqueViewMut<Hints>>().shown.insert(hint) {
+        stats::record_hint_shown(world, hint);
+    }
+}
+
+/// Give the player hints for the situation they're in after a turn, each only once.
+pub fn update(world: &World) {
+    let wanted = {
+        let hints = world.borrow::<UniqueView<Hints>>();
+        let options = world.borrow::<UniqueView<Options>>();
+        let player_id = world.borrow::<UniqueView<PlayerId>>().0;
+        let (combat_stats, inventories, ranged) =
+            world.borrow::<(View<CombatStats>, View<Inventory>, View<Ranged>)>();
+
+        [
+            (Hint::Rest, {
+                let stats = combat_stats.get(player_id);
+                stats.hp * 3 <= stats.max_hp
+            }),
+            (
+                Hint::Target,
+                inventories
+                    .try_get(player_id)
+                    .is_ok_and(|inv| inv.items.iter().any(|&id| ranged.contains(id))),
+            ),
+            (Hint::Eat, hunger::is_hungry(world, player_id)),
+        ]
+        .iter()
+        .filter(|(hint, applies)| *applies && hints.wants(&options, *hint))
+        .map(|(hint, _)| *hint)
+        .collect::<Vec<_>>()
+    };
+
+    for hint in wanted {
+        {
+            let locale = world.borrow::<UniqueView<Locale>>();
+
+            world
+                .borrow::<UniqueViewMut<Messages>>()
+                .add(locale.get(hint.text_key()).into());
+        }
+        mark_shown(world, hint);
+    }
+}
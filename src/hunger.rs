@@ -5,8 +5,11 @@ use shipyard::{
 
 use crate::{
     components::{CombatStats, HurtBy, Name, Player, Stomach, Tally},
-    message::Messages,
+    experience::Difficulty,
+    locale::Locale,
+    message::{Messages, MsgCategory},
     player::PlayerId,
+    TurnCount,
 };
 use ruggrogue::util::Color;
 
@@ -29,14 +32,15 @@ enum HungerState {
 }
 
 impl HungerState {
-    fn reduced_to(&self) -> &'static str {
+    /// Locale key of the message announcing that hunger has changed to this state.
+    fn reduced_to_key(&self) -> &'static str {
         match self {
-            HungerState::Starving => "starving!",
-            HungerState::Famished => "famished!",
-            HungerState::VeryHungry => "getting very hungry.",
-            HungerState::Hungry => "getting hungry.",
-            HungerState::Normal => "no longer full.",
-            HungerState::Full => "full.",
+            HungerState::Starving => "msg.hunger_starving",
+            HungerState::Famished => "msg.hunger_famished",
+            HungerState::VeryHungry => "msg.hunger_very_hungry",
+            HungerState::Hungry => "msg.hunger_hungry",
+            HungerState::Normal => "msg.hunger_normal",
+            HungerState::Full => "msg.hunger_full",
         }
     }
 
@@ -78,6 +82,17 @@ impl From<i32> for HungerState {
     }
 }
 
+/// True if the given entity has a stomach that is hungry or worse.
+pub fn is_hungry(world: &World, entity_id: EntityId) -> bool {
+    match world.borrow::<View<Stomach>>().try_get(entity_id) {
+        Ok(stomach) => !matches!(
+            HungerState::from(stomach.fullness),
+            HungerState::Normal | HungerState::Full
+        ),
+        Err(_) => false,
+    }
+}
+
 /// Checks if the given entity can or cannot regenerate with a specific reason.
 #[allow(clippy::question_mark)]
 pub fn can_regen(world: &World, entity_id: EntityId) -> CanRegenResult {
@@ -107,29 +122,43 @@ pub fn can_regen(world: &World, entity_id: EntityId) -> CanRegenResult {
     CanRegenResult::CanRegen
 }
 
-/// Get a description for the player's hunger level to show in the UI, with foreground and
-/// background colors.
+/// Get the locale key of a description for the player's hunger level to show in the UI, with
+/// foreground and background colors.
 pub fn player_hunger_label(
     player_id: UniqueView<PlayerId>,
     stomachs: View<Stomach>,
 ) -> (&'static str, Color, Color) {
     if let Ok(stomach) = stomachs.try_get(player_id.0) {
         match HungerState::from(stomach.fullness) {
-            HungerState::Starving => ("Starving", Color::BLACK, Color::ORANGE),
-            HungerState::Famished => ("Famished", Color::ORANGE, Color::BLACK),
-            HungerState::VeryHungry => ("Very Hungry", Color::YELLOW, Color::BLACK),
-            HungerState::Hungry => ("Hungry", Color::YELLOW, Color::BLACK),
-            HungerState::Normal => ("Normal", Color::GRAY, Color::BLACK),
-            HungerState::Full => ("Full", Color::GREEN, Color::BLACK),
+            HungerState::Starving => ("hunger.starving", Color::BLACK, Color::ORANGE),
+            HungerState::Famished => ("hunger.famished", Color::ORANGE, Color::BLACK),
+            HungerState::VeryHungry => ("hunger.very_hungry", Color::YELLOW, Color::BLACK),
+            HungerState::Hungry => ("hunger.hungry", Color::YELLOW, Color::BLACK),
+            HungerState::Normal => ("hunger.normal", Color::GRAY, Color::BLACK),
+            HungerState::Full => ("hunger.full", Color::GREEN, Color::BLACK),
         }
     } else {
         ("", Color::WHITE, Color::BLACK)
     }
 }
 
+/// Spread a rate given as a percentage evenly over turns, returning the whole amount due this turn.
+fn per_turn_share(turn: u64, rate: u32) -> i32 {
+    let rate = u64::from(rate);
+
+    (turn * rate / 100 - turn.saturating_sub(1) * rate / 100) as i32
+}
+
 /// Perform per-turn hunger effects like emptying stomachs, regeneration and starvation.
+///
+/// Hunger and regeneration rates are scaled by the difficulty settings of the game.
 pub fn tick_hunger(
     mut msgs: UniqueViewMut<Messages>,
+    (difficulty, locale, turn_count): (
+        UniqueView<Difficulty>,
+        UniqueView<Locale>,
+        UniqueView<TurnCount>,
+    ),
     player_id: UniqueView<PlayerId>,
     entities: EntitiesView,
     mut combat_stats: ViewMut<CombatStats>,
@@ -139,19 +168,24 @@ pub fn tick_hunger(
     mut stomachs: ViewMut<Stomach>,
     mut tallies: ViewMut<Tally>,
 ) {
+    let hunger = per_turn_share(turn_count.0, difficulty.settings.hunger_rate);
+    let regen_rate = difficulty.settings.regen_rate.max(1) as i32;
+
     for (id, stomach) in (&mut stomachs).iter().with_id() {
         let name = names.get(id);
+        let strain = std::mem::take(&mut stomach.strain);
 
         if stomach.fullness > 0 {
             let old_hunger = HungerState::from(stomach.fullness);
-            stomach.fullness -= 1;
+            stomach.fullness = (stomach.fullness - hunger - strain).max(0);
 
             if let Ok(stats) = (&mut combat_stats).try_get(id) {
                 if stats.hp > 0 {
                     // Regenerate hit points if below max and stomach allows it.
                     if stats.hp < stats.max_hp && stomach.fullness > 0 {
-                        if let Some(regen_turns) =
-                            HungerState::from(stomach.fullness).turns_to_regen_to_max_hp()
+                        if let Some(regen_turns) = HungerState::from(stomach.fullness)
+                            .turns_to_regen_to_max_hp()
+                            .map(|turns| turns * 100 / regen_rate)
                         {
                             // Regeneration costs extra hunger.
                             stomach.fullness -= 1;
@@ -178,7 +212,10 @@ pub fn tick_hunger(
 
                 // Tell the player when their hunger state changes.
                 if id == player_id.0 {
-                    msgs.add(format!("{} is {}", &name.0, new_hunger.reduced_to()));
+                    msgs.add_as(
+                        MsgCategory::Warning,
+                        locale.format(new_hunger.reduced_to_key(), &[("who", &name.0)]),
+                    );
                 }
             }
         }
@@ -208,7 +245,10 @@ pub fn tick_hunger(
 
                         // Tell the player when they take damage from starvation.
                         if id == player_id.0 {
-                            msgs.add(format!("{} aches with hunger!", &name.0));
+                            msgs.add_as(
+                                MsgCategory::Warning,
+                                locale.format("msg.hunger_aches", &[("who", &name.0)]),
+                            );
                         }
                     }
                 }
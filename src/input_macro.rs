@@ -0,0 +1,163 @@
+use sdl2::keyboard::Keycode;
+use shipyard::{UniqueView, UniqueViewMut, World};
+
+use crate::{
+    locale::Locale,
+    message::{Messages, MsgCategory},
+    player,
+};
+use ruggrogue::{InputBuffer, InputEvent};
+
+/// Inputs kept around to be replayed, either the last action by the do-again key or a macro
+/// recorded by the player.
+#[derive(Default)]
+pub struct InputMacros {
+    /// Inputs of the action that is still being carried out, e.g. through a menu.
+    current_action: Vec<InputEvent>,
+    /// Inputs of the last action that was carried out in full.
+    last_action: Vec<InputEvent>,
+    /// Actions gathered so far for a macro that is being recorded.
+    recording: Option<Vec<InputEvent>>,
+    /// The last macro that was recorded.
+    saved_macro: Vec<InputEvent>,
+}
+
+fn add_msg(world: &World, category: MsgCategory, key: &str) {
+    let msg = world.borrow::<UniqueView<Locale>>().get(key).to_string();
+
+    world
+        .borrow::<UniqueViewMut<Messages>>()
+        .add_as(category, msg);
+}
+
+/// Release any key left pressed at the end of a list of inputs, so that replaying it doesn't
+/// leave a modifier key held down.
+fn with_releases(inputs: &[InputEvent]) -> Vec<InputEvent> {
+    let mut held: Vec<Keycode> = Vec::new();
+
+    for input in inputs {
+        match *input {
+            InputEvent::Press(keycode) if !held.contains(&keycode) => held.push(keycode),
+            InputEvent::Release(keycode) => held.retain(|k| *k != keycode),
+            _ => {}
+        }
+    }
+
+    inputs
+        .iter()
+        .copied()
+        .chain(held.into_iter().map(InputEvent::Release))
+        .collect()
+}
+
+fn start_replay(world: &World, inputs: &mut InputBuffer, replay: Vec<InputEvent>) {
+    // Only warnings from here on should stop the replay.
+    world.borrow::<UniqueViewMut<Messages>>().take_warned();
+    inputs.replay(with_releases(&replay));
+}
+
+/// Gather the inputs of the action that the dungeon just handled.  Once the action is complete,
+/// i.e. it took a turn or changed levels, its inputs become the last action, and are added to any
+/// macro being recorded.  Actions that were backed out of without doing anything are forgotten.
+pub fn end_action(world: &World, inputs: &mut InputBuffer, complete: bool) {
+    let mut input_macros = world.borrow::<UniqueViewMut<InputMacros>>();
+    let recorded = inputs.take_recorded().into_iter().filter(|input| {
+        matches!(
+            input,
+            InputEvent::Press(_) | InputEvent::Release(_) | InputEvent::Text(_)
+        )
+    });
+
+    input_macros.current_action.extend(recorded);
+
+    if complete {
+        let action = std::mem::take(&mut input_macros.current_action);
+
+        // Leftover key releases on their own don't make an action.
+        if action.iter().any(|i| matches!(i, InputEvent::Press(_))) {
+            if let Some(recording) = &mut input_macros.recording {
+                recording.extend(action.iter().copied());
+            }
+            input_macros.last_action = action;
+        }
+    } else {
+        input_macros.current_action.clear();
+    }
+
+    if !world.run(player::player_is_alive) {
+        inputs.stop_replay();
+    }
+
+    // Don't let replayed inputs interrupt resting, auto-running or picking up several items.
+    inputs.hold_replay(
+        world.run(player::player_is_auto_running) || world.run(player::player_has_item_batch),
+    );
+}
+
+/// Replay the inputs of the last complete action.
+pub fn do_again(world: &World, inputs: &mut InputBuffer) {
+    let last_action = world
+        .borrow::<UniqueView<InputMacros>>()
+        .last_action
+        .clone();
+
+    if last_action.is_empty() {
+        add_msg(world, MsgCategory::Warning, "msg.macro_nothing_to_repeat");
+    } else {
+        start_replay(world, inputs, last_action);
+    }
+}
+
+/// Start recording a macro, or stop and save the one being recorded.
+pub fn toggle_recording(world: &World) {
+    let recording = world
+        .borrow::<UniqueViewMut<InputMacros>>()
+        .recording
+        .take();
+
+    match recording {
+        Some(recording) => {
+            if recording.is_empty() {
+                add_msg(world, MsgCategory::System, "msg.macro_empty");
+            } else {
+                world.borrow::<UniqueViewMut<InputMacros>>().saved_macro = recording;
+                add_msg(world, MsgCategory::System, "msg.macro_saved");
+            }
+        }
+        None => {
+            world.borrow::<UniqueViewMut<InputMacros>>().recording = Some(Vec::new());
+            add_msg(world, MsgCategory::System, "msg.macro_recording");
+        }
+    }
+}
+
+/// Replay the last macro that was recorded.
+pub fn play_macro(world: &World, inputs: &mut InputBuffer) {
+    let (recording, saved_macro) = {
+        let input_macros = world.borrow::<UniqueView<InputMacros>>();
+
+        (
+            input_macros.recording.is_some(),
+            input_macros.saved_macro.clone(),
+        )
+    };
+
+    if recording {
+        add_msg(world, MsgCategory::Warning, "msg.macro_busy");
+    } else if saved_macro.is_empty() {
+        add_msg(world, MsgCategory::Warning, "msg.macro_none");
+    } else {
+        start_replay(world, inputs, saved_macro);
+    }
+}
+
+/// Stop replaying inputs as soon as a warning shows up, since the replay probably isn't doing what
+/// the player expected anymore.
+pub fn check_replay(world: &World, inputs: &mut InputBuffer) {
+    let warned = world.borrow::<UniqueViewMut<Messages>>().take_warned();
+
+    if warned && inputs.is_replaying() {
+        inputs.stop_replay();
+        add_msg(world, MsgCategory::System, "msg.macro_stopped");
+    }
+}
@@ -0,0 +1,160 @@
+use shipyard::{EntityId, Get, UniqueView, UniqueViewMut, View, World};
+
+use crate::{
+    components::{
+        Afraid, Asleep, CombatBonus, CombatStats, Confused, Coord, Description, Equipment,
+        Experience, Faction, FieldOfView, Monster, Name, Pet,
+    },
+    damage, experience, faction,
+    locale::Locale,
+    map::Map,
+    message::{Messages, MsgCategory},
+    player::{PlayerId, PlayerInputResult},
+};
+
+const TEXT_WIDTH: usize = 40;
+
+/// Monsters in the player's field of view, nearest first.
+fn visible_monsters(world: &World) -> Vec<EntityId> {
+    let (map, player_id, coords, fovs, monsters) = world.borrow::<(
+        UniqueView<Map>,
+        UniqueView<PlayerId>,
+        View<Coord>,
+        View<FieldOfView>,
+        View<Monster>,
+    )>();
+    let player_coord = coords.get(player_id.0);
+    let mut visible = fovs
+        .get(player_id.0)
+        .iter()
+        .flat_map(|(x, y)| map.iter_entities_at(x, y))
+        .filter(|&id| monsters.contains(id))
+        .collect::<Vec<_>>();
+
+    visible.sort_by_key(|&id| player_coord.dist(coords.get(id)));
+    visible
+}
+
+/// Pick the monster to take a closer look at: the only one in view, or one chosen by the player
+/// if there are several.
+pub fn player_inspect(world: &World) -> PlayerInputResult {
+    match visible_monsters(world).as_slice() {
+        [] => {
+            let msg = world
+                .borrow::<UniqueView<Locale>>()
+                .get("msg.nothing_to_inspect")
+                .to_string();
+
+            world
+                .borrow::<UniqueViewMut<Messages>>()
+                .add_as(MsgCategory::Warning, msg);
+            PlayerInputResult::NoResult
+        }
+        [monster] => PlayerInputResult::InspectMonster(*monster),
+        _ => PlayerInputResult::PickInspectTarget,
+    }
+}
+
+/// The monster in view at the given position, if any.
+pub fn monster_at(world: &World, x: i32, y: i32) -> Option<EntityId> {
+    visible_monsters(world).into_iter().find(|&id| {
+        let pos = world.borrow::<View<Coord>>().get(id).0;
+        pos.x == x && pos.y == y
+    })
+}
+
+/// Lines of a short summary of a monster, estimating how a fight with the player would go.
+pub fn monster_summary(world: &World, monster: EntityId) -> Vec<String> {
+    let player_id = world.borrow::<UniqueView<PlayerId>>().0;
+    let combat_bonuses = world.borrow::<View<CombatBonus>>();
+    let combat_stats = world.borrow::<View<CombatStats>>();
+    let equipments = world.borrow::<View<Equipment>>();
+    let stats = combat_stats.get(monster);
+    let level = world
+        .borrow::<View<Experience>>()
+        .try_get(monster)
+        .map_or_else(
+            |_| experience::estimate_monster_level(stats.attack),
+            |e| e.level,
+        );
+    let player_stats = combat_stats.get(player_id);
+    let player_bonus = damage::equipment_bonus(player_id, &equipments, &combat_bonuses);
+    let monster_bonus = damage::equipment_bonus(monster, &equipments, &combat_bonuses);
+    let attack = stats.attack + monster_bonus.0;
+    let defense = stats.defense + monster_bonus.1;
+    let locale = world.borrow::<UniqueView<Locale>>();
+    let mut lines = vec![
+        locale.format(
+            "inspect.level",
+            &[
+                ("name", &world.borrow::<View<Name>>().get(monster).0),
+                ("level", &level),
+            ],
+        ),
+        locale.format(
+            "inspect.hp",
+            &[("hp", &stats.hp), ("max_hp", &stats.max_hp)],
+        ),
+        locale.format(
+            "inspect.stats",
+            &[
+                ("attack", &format!("{:.0}", attack)),
+                ("defense", &format!("{:.0}", defense)),
+            ],
+        ),
+        locale.format(
+            "inspect.hits_you",
+            &[(
+                "damage",
+                &format!(
+                    "{:.1}",
+                    damage::base_damage(attack, player_stats.defense + player_bonus.1)
+                ),
+            )],
+        ),
+        locale.format(
+            "inspect.you_hit",
+            &[(
+                "damage",
+                &format!(
+                    "{:.1}",
+                    damage::base_damage(player_stats.attack + player_bonus.0, defense)
+                ),
+            )],
+        ),
+    ];
+
+    let mut statuses = Vec::new();
+    if world.borrow::<View<Pet>>().contains(monster) {
+        statuses.push("inspect.pet");
+    } else if faction::faction_of(world, monster) == Some(Faction::Player) {
+        statuses.push("inspect.charmed");
+    } else if faction::faction_of(world, monster) == Some(Faction::Wildlife) {
+        statuses.push("inspect.wild");
+    }
+    if world.borrow::<View<Asleep>>().contains(monster) {
+        statuses.push("inspect.asleep");
+    }
+    if world.borrow::<View<Afraid>>().contains(monster) {
+        statuses.push("inspect.afraid");
+    }
+    if world.borrow::<View<Confused>>().contains(monster) {
+        statuses.push("inspect.confused");
+    }
+    if !statuses.is_empty() {
+        let statuses = statuses
+            .iter()
+            .map(|key| locale.get(key))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        lines.push(locale.format("inspect.status", &[("statuses", &statuses)]));
+    }
+
+    if let Ok(desc) = world.borrow::<View<Description>>().try_get(monster) {
+        lines.push(String::new());
+        lines.extend(ruggrogue::word_wrap(&desc.text(&locale), TEXT_WIDTH).map(String::from));
+    }
+
+    lines
+}
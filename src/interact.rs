@@ -0,0 +1,146 @@
+use shipyard::{Get, UniqueView, View, World};
+
+use crate::{
+    bones, chest,
+    components::{Coord, Item, Name, Npc},
+    locale::Locale,
+    map::{Map, Tile},
+    obstacle,
+    player::{self, PlayerId, PlayerInputResult},
+    sound,
+};
+
+/// The eight directions around the player, in the order they're listed in the interact menu.
+const DIRECTIONS: [(i32, i32); 8] = [
+    (0, -1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+    (0, 1),
+    (-1, 1),
+    (-1, 0),
+    (-1, -1),
+];
+
+/// Something the player can do with their own tile or one next to it.
+#[derive(Clone, Copy)]
+pub enum Interaction {
+    Descend,
+    PickUp,
+    Talk((i32, i32)),
+    OpenChest((i32, i32)),
+    Unlock((i32, i32)),
+    Dig((i32, i32)),
+    Push((i32, i32)),
+}
+
+/// List every interaction available to the player right now, each with a menu label.
+pub fn available_interactions(world: &World) -> Vec<(Interaction, String)> {
+    let player_id = world.borrow::<UniqueView<PlayerId>>().0;
+    let (px, py): (i32, i32) = world.borrow::<View<Coord>>().get(player_id).0.into();
+    let locale = world.borrow::<UniqueView<Locale>>();
+    let mut interactions = Vec::new();
+
+    {
+        let map = world.borrow::<UniqueView<Map>>();
+        let items = world.borrow::<View<Item>>();
+
+        if matches!(map.get_tile(px, py), Tile::DownStairs) {
+            interactions.push((
+                Interaction::Descend,
+                locale.get("interact.descend").to_string(),
+            ));
+        }
+        if map.iter_entities_at(px, py).any(|id| items.contains(id)) {
+            interactions.push((
+                Interaction::PickUp,
+                locale.get("interact.pick_up").to_string(),
+            ));
+        }
+    }
+
+    let has_key = obstacle::carried_key(world, player_id).is_some();
+    let has_digger = obstacle::carries_digger(world, player_id);
+
+    for &(dx, dy) in DIRECTIONS.iter() {
+        let pos = (px + dx, py + dy);
+        let dir = locale.get(sound::direction(dx, dy));
+        let npc = {
+            let map = world.borrow::<UniqueView<Map>>();
+            let npcs = world.borrow::<View<Npc>>();
+
+            let npc = map
+                .iter_entities_at(pos.0, pos.1)
+                .find(|id| npcs.contains(*id));
+
+            npc
+        };
+        let locked_door = {
+            let map = world.borrow::<UniqueView<Map>>();
+
+            !map.wall_or_oob(pos.0, pos.1) && matches!(map.get_tile(pos.0, pos.1), Tile::LockedDoor)
+        };
+        let names = world.borrow::<View<Name>>();
+
+        if let Some(npc_id) = npc {
+            interactions.push((
+                Interaction::Talk((dx, dy)),
+                locale.format(
+                    "interact.talk",
+                    &[("who", &names.get(npc_id).0), ("dir", &dir)],
+                ),
+            ));
+        }
+        if let Some(chest_id) = chest::chest_at(world, pos).or_else(|| bones::grave_at(world, pos))
+        {
+            interactions.push((
+                Interaction::OpenChest((dx, dy)),
+                locale.format(
+                    "interact.open",
+                    &[("what", &names.get(chest_id).0), ("dir", &dir)],
+                ),
+            ));
+        }
+        if locked_door && has_key {
+            interactions.push((
+                Interaction::Unlock((dx, dy)),
+                locale.format("interact.unlock", &[("dir", &dir)]),
+            ));
+        }
+        if let Some(wall_id) = obstacle::diggable_at(world, pos) {
+            if has_digger {
+                interactions.push((
+                    Interaction::Dig((dx, dy)),
+                    locale.format(
+                        "interact.dig",
+                        &[("what", &names.get(wall_id).0), ("dir", &dir)],
+                    ),
+                ));
+            }
+        }
+        if let Some(boulder_id) = obstacle::boulder_at(world, pos) {
+            interactions.push((
+                Interaction::Push((dx, dy)),
+                locale.format(
+                    "interact.push",
+                    &[("what", &names.get(boulder_id).0), ("dir", &dir)],
+                ),
+            ));
+        }
+    }
+
+    interactions
+}
+
+/// Carry out a chosen interaction the same way as the key or move that it stands for.
+pub fn interact(world: &World, interaction: Interaction) -> PlayerInputResult {
+    match interaction {
+        Interaction::Descend => PlayerInputResult::TryDescend,
+        Interaction::PickUp => PlayerInputResult::ShowPickUpMenu,
+        Interaction::Talk((dx, dy))
+        | Interaction::OpenChest((dx, dy))
+        | Interaction::Unlock((dx, dy))
+        | Interaction::Dig((dx, dy))
+        | Interaction::Push((dx, dy)) => player::try_move_player(world, dx, dy, false),
+    }
+}
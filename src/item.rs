@@ -2,27 +2,55 @@ use shipyard::{
     AllStoragesViewMut, EntitiesView, EntityId, Get, Remove, UniqueView, UniqueViewMut, View,
     ViewMut, World,
 };
-use std::cmp::Ordering;
+use std::{cmp::Ordering, error::Error};
 
 use crate::{
     components::*,
-    map::Map,
-    message::Messages,
+    damage, durability,
+    event::{self, GameEvent},
+    locale::Locale,
+    map::{Map, TileFlags},
+    message::{Messages, MsgCategory},
     player::{self, PlayerId},
-    saveload, Wins,
+    saveload, script, sound, spawn, stats, TurnCount, Wins,
 };
 use ruggrogue::FovShape;
 
-pub struct PickUpHint(pub bool);
+/// Orders that an inventory can be sorted in.  Favorite items come first in any order.
+#[derive(Clone, Copy)]
+pub enum SortOrder {
+    /// Grouped by kind of item, with the best equipment first.
+    Type,
+    Name,
+    /// Most recently acquired first.
+    Recent,
+}
+
+impl SortOrder {
+    pub const ALL: [SortOrder; 3] = [SortOrder::Type, SortOrder::Name, SortOrder::Recent];
+
+    /// Locale key of the name of the sort order.
+    pub fn name_key(&self) -> &'static str {
+        match self {
+            SortOrder::Type => "inventory.sort.type",
+            SortOrder::Name => "inventory.sort.name",
+            SortOrder::Recent => "inventory.sort.recent",
+        }
+    }
+}
 
 pub fn add_item_to_map(world: &World, item_id: EntityId, pos: (i32, i32)) {
-    let (mut map, entities, mut coords, mut render_on_floors) = world.borrow::<(
+    let (mut map, entities, mut coords, mut in_loadouts, mut render_on_floors) = world.borrow::<(
         UniqueViewMut<Map>,
         EntitiesView,
         ViewMut<Coord>,
+        ViewMut<InLoadout>,
         ViewMut<RenderOnFloor>,
     )>();
 
+    // Items left behind drop out of the equipment sets.
+    in_loadouts.remove(item_id);
+
     entities.add_component(
         (&mut coords, &mut render_on_floors),
         (Coord(pos.into()), RenderOnFloor {}),
@@ -39,11 +67,24 @@ pub fn remove_item_from_map(world: &World, item_id: EntityId) {
     Remove::<(Coord, RenderOnFloor)>::remove((&mut coords, &mut render_on_floors), item_id);
 }
 
+/// Add an item to the top of an inventory, below any favorite items.
 pub fn add_item_to_inventory(world: &World, picker_id: EntityId, item_id: EntityId) {
-    let mut inventories = world.borrow::<ViewMut<Inventory>>();
+    let turn_count = world.borrow::<UniqueView<TurnCount>>().0;
+    let (entities, mut acquireds, favorites, mut inventories) = world.borrow::<(
+        EntitiesView,
+        ViewMut<Acquired>,
+        View<Favorite>,
+        ViewMut<Inventory>,
+    )>();
     let picker_inv = (&mut inventories).get(picker_id);
-
-    picker_inv.items.insert(0, item_id);
+    let insert_pos = picker_inv
+        .items
+        .iter()
+        .position(|id| !favorites.contains(*id))
+        .unwrap_or(picker_inv.items.len());
+
+    picker_inv.items.insert(insert_pos, item_id);
+    entities.add_component(&mut acquireds, Acquired(turn_count), item_id);
 }
 
 pub fn remove_item_from_inventory(world: &World, holder_id: EntityId, item_id: EntityId) {
@@ -55,6 +96,164 @@ pub fn remove_item_from_inventory(world: &World, holder_id: EntityId, item_id: E
     }
 }
 
+/// Despawn every consumable item held by the holder, including those inside containers they hold.
+/// Returns how many were despawned.
+pub fn discard_consumables(world: &World, holder_id: EntityId) -> usize {
+    let (consumable_ids, container_ids) = {
+        let consumables = world.borrow::<View<Consumable>>();
+        let inventories = world.borrow::<View<Inventory>>();
+        let items = &inventories.get(holder_id).items;
+
+        (
+            items
+                .iter()
+                .filter(|id| consumables.contains(**id))
+                .copied()
+                .collect::<Vec<_>>(),
+            items
+                .iter()
+                .filter(|id| inventories.contains(**id))
+                .copied()
+                .collect::<Vec<_>>(),
+        )
+    };
+    let mut count = consumable_ids.len();
+
+    for item_id in consumable_ids {
+        remove_item_from_inventory(world, holder_id, item_id);
+        spawn::despawn_entity(&mut world.borrow::<AllStoragesViewMut>(), item_id);
+    }
+    for container_id in container_ids {
+        count += discard_consumables(world, container_id);
+    }
+
+    count
+}
+
+/// Move items from the map into the inventory of the picker, skipping any that are no longer under
+/// them.  Returns the items that were actually picked up.
+pub fn pick_up_items(world: &World, picker_id: EntityId, item_ids: &[EntityId]) -> Vec<EntityId> {
+    let picked = {
+        let coords = world.borrow::<View<Coord>>();
+        let picker_pos = coords.get(picker_id).0;
+
+        item_ids
+            .iter()
+            .filter(|id| matches!(coords.try_get(**id), Ok(c) if c.0 == picker_pos))
+            .copied()
+            .collect::<Vec<_>>()
+    };
+
+    for item_id in &picked {
+        remove_item_from_map(world, *item_id);
+        add_item_to_inventory(world, picker_id, *item_id);
+    }
+
+    picked
+}
+
+/// Move items from the inventory of the dropper onto the map where they stand, skipping any that
+/// are no longer held.  Returns the items that were actually dropped.
+pub fn drop_items(world: &World, dropper_id: EntityId, item_ids: &[EntityId]) -> Vec<EntityId> {
+    let dropper_pos: (i32, i32) = world.borrow::<View<Coord>>().get(dropper_id).0.into();
+    let dropped = {
+        let inventories = world.borrow::<View<Inventory>>();
+        let dropper_inv = inventories.get(dropper_id);
+
+        item_ids
+            .iter()
+            .filter(|id| dropper_inv.items.contains(id))
+            .copied()
+            .collect::<Vec<_>>()
+    };
+
+    for item_id in &dropped {
+        remove_item_from_inventory(world, dropper_id, *item_id);
+        add_item_to_map(world, *item_id, dropper_pos);
+    }
+
+    dropped
+}
+
+/// Move an item from a holder's inventory into a container in that inventory, returning false if
+/// the container is full.
+pub fn put_in_container(
+    world: &World,
+    holder_id: EntityId,
+    container_id: EntityId,
+    item_id: EntityId,
+) -> bool {
+    let capacity = world.borrow::<View<Container>>().get(container_id).capacity;
+
+    if world
+        .borrow::<View<Inventory>>()
+        .get(container_id)
+        .items
+        .len()
+        >= capacity
+    {
+        world.run(
+            |locale: UniqueView<Locale>, mut msgs: UniqueViewMut<Messages>, names: View<Name>| {
+                msgs.add_as(
+                    MsgCategory::Warning,
+                    locale.format(
+                        "msg.container_full",
+                        &[("container", &names.get(container_id).0)],
+                    ),
+                );
+            },
+        );
+        return false;
+    }
+
+    remove_item_from_inventory(world, holder_id, item_id);
+    add_item_to_inventory(world, container_id, item_id);
+    world.borrow::<ViewMut<InLoadout>>().remove(item_id);
+    world.run(
+        |locale: UniqueView<Locale>, mut msgs: UniqueViewMut<Messages>, names: View<Name>| {
+            msgs.add_as(
+                MsgCategory::Item,
+                locale.format(
+                    "msg.puts_in",
+                    &[
+                        ("who", &names.get(holder_id).0),
+                        ("item", &names.get(item_id).0),
+                        ("container", &names.get(container_id).0),
+                    ],
+                ),
+            );
+        },
+    );
+
+    true
+}
+
+/// Move an item out of a container and into the inventory of the one holding the container.
+pub fn take_from_container(
+    world: &World,
+    holder_id: EntityId,
+    container_id: EntityId,
+    item_id: EntityId,
+) {
+    remove_item_from_inventory(world, container_id, item_id);
+    add_item_to_inventory(world, holder_id, item_id);
+    world.run(
+        |locale: UniqueView<Locale>, mut msgs: UniqueViewMut<Messages>, names: View<Name>| {
+            msgs.add_as(
+                MsgCategory::Item,
+                locale.format(
+                    "msg.takes_out",
+                    &[
+                        ("who", &names.get(holder_id).0),
+                        ("item", &names.get(item_id).0),
+                        ("container", &names.get(container_id).0),
+                    ],
+                ),
+            );
+        },
+    );
+}
+
 fn unequip_item(world: &World, unequipper_id: EntityId, item_id: EntityId) {
     let mut equipments = world.borrow::<ViewMut<Equipment>>();
     let equipment = (&mut equipments).get(unequipper_id);
@@ -70,14 +269,20 @@ pub fn remove_equipment(world: &World, remover_id: EntityId, item_id: EntityId)
         unequip_item(world, remover_id, item_id);
         add_item_to_inventory(world, remover_id, item_id);
 
+        let locale = world.borrow::<UniqueView<Locale>>();
         let mut msgs = world.borrow::<UniqueViewMut<Messages>>();
         let names = world.borrow::<View<Name>>();
 
-        msgs.add(format!(
-            "{} removes {}.",
-            &names.get(remover_id).0,
-            &names.get(item_id).0
-        ));
+        msgs.add_as(
+            MsgCategory::Item,
+            locale.format(
+                "msg.removes",
+                &[
+                    ("who", &names.get(remover_id).0),
+                    ("item", &names.get(item_id).0),
+                ],
+            ),
+        );
     } else {
         // Remover has no inventory, so attempt dropping the equipment instead.
         drop_equipment(world, remover_id, item_id);
@@ -93,14 +298,20 @@ pub fn drop_equipment(world: &World, dropper_id: EntityId, item_id: EntityId) {
     unequip_item(world, dropper_id, item_id);
     add_item_to_map(world, item_id, dropper_pos);
 
+    let locale = world.borrow::<UniqueView<Locale>>();
     let mut msgs = world.borrow::<UniqueViewMut<Messages>>();
     let names = world.borrow::<View<Name>>();
 
-    msgs.add(format!(
-        "{} drops {}.",
-        &names.get(dropper_id).0,
-        &names.get(item_id).0
-    ));
+    msgs.add_as(
+        MsgCategory::Item,
+        locale.format(
+            "msg.drops",
+            &[
+                ("who", &names.get(dropper_id).0),
+                ("item", &names.get(item_id).0),
+            ],
+        ),
+    );
 }
 
 pub fn equip_item(world: &World, equipper_id: EntityId, item_id: EntityId) {
@@ -127,19 +338,72 @@ pub fn equip_item(world: &World, equipper_id: EntityId, item_id: EntityId) {
         remove_item_from_inventory(world, equipper_id, item_id);
     }
 
+    let locale = world.borrow::<UniqueView<Locale>>();
     let mut msgs = world.borrow::<UniqueViewMut<Messages>>();
     let names = world.borrow::<View<Name>>();
 
-    msgs.add(format!(
-        "{} equips {}.",
-        &names.get(equipper_id).0,
-        &names.get(item_id).0
-    ));
+    msgs.add_as(
+        MsgCategory::Item,
+        locale.format(
+            "msg.equips",
+            &[
+                ("who", &names.get(equipper_id).0),
+                ("item", &names.get(item_id).0),
+            ],
+        ),
+    );
+}
+
+/// How an item would change a holder's combat bonus if it replaced what's equipped in its slot.
+pub struct EquipComparison {
+    pub slot: EquipSlot,
+    /// Attack bonus difference for weapons, defense bonus difference for armor.
+    pub delta: f32,
+    pub equipped: Option<EntityId>,
+}
+
+/// Compare an equippable item against what the holder has equipped in the same slot, or None if
+/// the item can't be equipped or is already equipped.
+pub fn compare_to_equipped(
+    world: &World,
+    holder_id: EntityId,
+    item_id: EntityId,
+) -> Option<EquipComparison> {
+    let combat_bonuses = world.borrow::<View<CombatBonus>>();
+    let equipments = world.borrow::<View<Equipment>>();
+    let slot = *world.borrow::<View<EquipSlot>>().try_get(item_id).ok()?;
+    let bonus = combat_bonuses.try_get(item_id).ok()?;
+    let equipped = equipments
+        .try_get(holder_id)
+        .ok()
+        .and_then(|equip| match slot {
+            EquipSlot::Weapon => equip.weapon,
+            EquipSlot::Armor => equip.armor,
+        });
+
+    if equipped == Some(item_id) {
+        return None;
+    }
+
+    let current = equipped.and_then(|id| combat_bonuses.try_get(id).ok());
+    let delta = match slot {
+        EquipSlot::Weapon => bonus.attack - current.map_or(0.0, |c| c.attack),
+        EquipSlot::Armor => bonus.defense - current.map_or(0.0, |c| c.defense),
+    };
+
+    Some(EquipComparison {
+        slot,
+        delta,
+        equipped,
+    })
 }
 
-pub fn sort_inventory(world: &World, holder: EntityId) {
+pub fn sort_inventory(world: &World, holder: EntityId, order: SortOrder) {
+    let acquireds = world.borrow::<View<Acquired>>();
+    let favorites = world.borrow::<View<Favorite>>();
     let aoes = world.borrow::<View<AreaOfEffect>>();
     let combat_bonuses = world.borrow::<View<CombatBonus>>();
+    let containers = world.borrow::<View<Container>>();
     let inflicts_damages = world.borrow::<View<InflictsDamage>>();
     let inflicts_sleeps = world.borrow::<View<InflictsSleep>>();
     let names = world.borrow::<View<Name>>();
@@ -147,7 +411,7 @@ pub fn sort_inventory(world: &World, holder: EntityId) {
     let nutritions = world.borrow::<View<Nutrition>>();
     let rangeds = world.borrow::<View<Ranged>>();
     let victories = world.borrow::<View<Victory>>();
-    let item_order = |&a: &EntityId, &b: &EntityId| -> Ordering {
+    let type_order = |&a: &EntityId, &b: &EntityId| -> Ordering {
         // Present
         {
             let a_is_victory = victories.contains(a);
@@ -162,6 +426,20 @@ pub fn sort_inventory(world: &World, holder: EntityId) {
             }
         }
 
+        // Bag
+        {
+            let a_is_bag = containers.contains(a);
+            let b_is_bag = containers.contains(b);
+
+            if a_is_bag && b_is_bag {
+                return Ordering::Equal;
+            } else if a_is_bag {
+                return Ordering::Less;
+            } else if b_is_bag {
+                return Ordering::Greater;
+            }
+        }
+
         // Ration
         {
             let a_is_ration = nutritions.contains(a);
@@ -281,32 +559,107 @@ pub fn sort_inventory(world: &World, holder: EntityId) {
         names.get(a).0.cmp(&names.get(b).0)
     };
 
+    let name_order = |a: &EntityId, b: &EntityId| names.get(*a).0.cmp(&names.get(*b).0);
+    let recent_order = |a: &EntityId, b: &EntityId| {
+        let acquired = |id: EntityId| acquireds.try_get(id).map_or(0, |a| a.0);
+        acquired(*b)
+            .cmp(&acquired(*a))
+            .then_with(|| name_order(a, b))
+    };
+
     let mut inventories = world.borrow::<ViewMut<Inventory>>();
     let holder_inv = (&mut inventories).get(holder);
 
-    holder_inv.items.sort_unstable_by(item_order);
+    holder_inv.items.sort_by(|a, b| {
+        favorites
+            .contains(*b)
+            .cmp(&favorites.contains(*a))
+            .then_with(|| match order {
+                SortOrder::Type => type_order(a, b),
+                SortOrder::Name => name_order(a, b),
+                SortOrder::Recent => recent_order(a, b),
+            })
+    });
+}
+
+/// Move favorite items to the top of an inventory, keeping the order of everything else.
+fn pin_favorites(world: &World, holder: EntityId) {
+    let (favorites, mut inventories) = world.borrow::<(View<Favorite>, ViewMut<Inventory>)>();
+
+    (&mut inventories)
+        .get(holder)
+        .items
+        .sort_by_key(|id| !favorites.contains(*id));
+}
+
+/// Mark or unmark an item in a holder's inventory as a favorite, pinning favorites to the top.
+/// Unmarking an item also unlocks it.
+pub fn toggle_favorite(world: &World, holder: EntityId, item_id: EntityId) {
+    {
+        let (entities, mut favorites, mut lockeds) =
+            world.borrow::<(EntitiesView, ViewMut<Favorite>, ViewMut<Locked>)>();
+
+        if favorites.contains(item_id) {
+            favorites.remove(item_id);
+            lockeds.remove(item_id);
+        } else {
+            entities.add_component(&mut favorites, Favorite {}, item_id);
+        }
+    }
+
+    pin_favorites(world, holder);
+}
+
+/// Lock or unlock an item so that it can't be dropped by accident.
+pub fn toggle_locked(world: &World, item_id: EntityId) {
+    let (entities, mut lockeds) = world.borrow::<(EntitiesView, ViewMut<Locked>)>();
+
+    if lockeds.contains(item_id) {
+        lockeds.remove(item_id);
+    } else {
+        entities.add_component(&mut lockeds, Locked {}, item_id);
+    }
+}
+
+/// Win the game by using up the victory item.
+pub fn claim_victory(world: &World, user_id: EntityId, item_id: EntityId) {
+    remove_item_from_inventory(world, user_id, item_id);
+    world.borrow::<AllStoragesViewMut>().delete(item_id);
+    world.borrow::<UniqueViewMut<Wins>>().0 += 1;
+    world.run(stats::record_victory);
 }
 
 /// Returns true if the game should end after the item is used.
+///
+/// Using the victory item saves the game first, returning the error without using the item if the
+/// game couldn't be saved, so that the caller can try again or go ahead with [claim_victory].
 pub fn use_item(
     world: &World,
     user_id: EntityId,
     item_id: EntityId,
     target: Option<(i32, i32)>,
-) -> bool {
+) -> Result<bool, Box<dyn Error>> {
+    let mut script_targets = Vec::new();
+    let mut marks = Vec::new();
+
     if world.borrow::<View<Player>>().contains(user_id)
         && world.borrow::<View<Victory>>().contains(item_id)
     {
         // Auto-save the game before the victory item is deleted in case an AppQuit causes the game
         // to terminate outside of standard gameplay.
-        if let Err(e) = saveload::save_game(world) {
-            eprintln!("Warning: saveload::save_game: {}", e);
-        }
-        remove_item_from_inventory(world, user_id, item_id);
-        world.borrow::<AllStoragesViewMut>().delete(item_id);
-        world.borrow::<UniqueViewMut<Wins>>().0 += 1;
-        return true;
+        saveload::save_game(world)?;
+        claim_victory(world, user_id, item_id);
+        return Ok(true);
     } else {
+        event::emit(
+            world,
+            GameEvent::ItemUsed {
+                user: user_id,
+                target,
+            },
+        );
+
+        let locale = world.borrow::<UniqueView<Locale>>();
         let map = world.borrow::<UniqueView<Map>>();
         let mut msgs = world.borrow::<UniqueViewMut<Messages>>();
         let entities = world.borrow::<EntitiesView>();
@@ -314,6 +667,7 @@ pub fn use_item(
         let mut asleeps = world.borrow::<ViewMut<Asleep>>();
         let mut combat_stats = world.borrow::<ViewMut<CombatStats>>();
         let coords = world.borrow::<View<Coord>>();
+        let mut damage_logs = world.borrow::<ViewMut<DamageLog>>();
         let mut hurt_bys = world.borrow::<ViewMut<HurtBy>>();
         let inflicts_damages = world.borrow::<View<InflictsDamage>>();
         let inflicts_sleeps = world.borrow::<View<InflictsSleep>>();
@@ -327,18 +681,33 @@ pub fn use_item(
 
         let center = target.unwrap_or_else(|| coords.get(user_id).0.into());
         let radius = aoes.try_get(item_id).map_or(0, |aoe| aoe.radius);
-        let targets = ruggrogue::field_of_view(&*map, center, radius, FovShape::CirclePlus)
+        let blast = ruggrogue::field_of_view(&*map, center, radius, FovShape::CirclePlus)
             .filter(|(_, _, symmetric)| *symmetric)
-            .flat_map(|(x, y, _)| map.iter_entities_at(x, y))
+            .map(|(x, y, _)| (x, y))
+            .collect::<Vec<_>>();
+        let targets = map
+            .iter_entities_within(center, radius)
+            .filter(|(_, pos)| blast.contains(pos))
+            .map(|(id, _)| id)
             .filter(|id| monsters.contains(*id) || players.contains(*id));
         let user_name = &names.get(user_id).0;
         let item_name = &names.get(item_id).0;
 
-        msgs.add(format!("{} uses {}.", user_name, item_name));
+        msgs.add_as(
+            MsgCategory::Item,
+            locale.format("msg.uses", &[("who", user_name), ("item", item_name)]),
+        );
+
+        // Damaging blasts scorch everything they reach.
+        if aoes.contains(item_id) && inflicts_damages.contains(item_id) {
+            marks.extend(blast.iter().map(|&pos| (pos, TileFlags::SCORCH)));
+        }
 
         for target_id in targets {
             let target_name = &names.get(target_id).0;
 
+            script_targets.push(target_id);
+
             if let Ok(stomach) = (&mut stomachs).try_get(target_id) {
                 if let Ok(nutrition) = nutritions.try_get(item_id) {
                     stomach.fullness = (stomach.fullness + nutrition.0).min(stomach.max_fullness);
@@ -348,35 +717,65 @@ pub fn use_item(
             if let Ok(stats) = (&mut combat_stats).try_get(target_id) {
                 if let Ok(ProvidesHealing { heal_amount }) = provides_healings.try_get(item_id) {
                     if stats.hp < stats.max_hp {
-                        stats.hp = (stats.hp + heal_amount).min(stats.max_hp);
-                        msgs.add(format!(
-                            "{} heals {} for {} hp.",
-                            item_name, target_name, heal_amount,
-                        ));
+                        let healed = (stats.hp + heal_amount).min(stats.max_hp) - stats.hp;
+
+                        stats.hp += healed;
+                        event::emit_damage(world, target_id, -healed);
+                        msgs.add_as(
+                            MsgCategory::Item,
+                            locale.format(
+                                "msg.item_heals",
+                                &[
+                                    ("item", item_name),
+                                    ("target", target_name),
+                                    ("amount", heal_amount),
+                                ],
+                            ),
+                        );
                     } else {
                         let amount = 2;
                         stats.hp += amount;
                         stats.max_hp += amount;
-                        msgs.add(format!(
-                            "{} grants {} max hp to {}.",
-                            item_name, amount, target_name,
-                        ));
+                        event::emit_damage(world, target_id, -amount);
+                        msgs.add_as(
+                            MsgCategory::Item,
+                            locale.format(
+                                "msg.item_max_hp",
+                                &[
+                                    ("item", item_name),
+                                    ("target", target_name),
+                                    ("amount", &amount),
+                                ],
+                            ),
+                        );
                     }
                 }
 
                 if let Ok(InflictsDamage { damage }) = inflicts_damages.try_get(item_id) {
+                    if *damage > 0 {
+                        marks.push((coords.get(target_id).0.into(), TileFlags::BLOOD));
+                    }
                     stats.hp -= damage;
+                    event::emit_damage(world, target_id, *damage);
                     entities.add_component(&mut hurt_bys, HurtBy::Someone(user_id), target_id);
+                    damage::log_damage(&entities, &mut damage_logs, user_id, target_id, *damage);
                     if let Ok(user_tally) = (&mut tallies).try_get(user_id) {
                         user_tally.damage_dealt += *damage.max(&0) as u64;
                     }
                     if let Ok(target_tally) = (&mut tallies).try_get(target_id) {
                         target_tally.damage_taken += *damage.max(&0) as u64;
                     }
-                    msgs.add(format!(
-                        "{} hits {} for {} hp.",
-                        item_name, target_name, damage,
-                    ));
+                    msgs.add_as(
+                        MsgCategory::Combat,
+                        locale.format(
+                            "msg.item_hits",
+                            &[
+                                ("item", item_name),
+                                ("target", target_name),
+                                ("damage", damage),
+                            ],
+                        ),
+                    );
                 }
 
                 if let Ok(InflictsSleep { sleepiness }) = inflicts_sleeps.try_get(item_id) {
@@ -388,18 +787,67 @@ pub fn use_item(
                         },
                         target_id,
                     );
-                    msgs.add(format!("{} sends {} to sleep.", item_name, target_name));
+                    msgs.add_as(
+                        MsgCategory::Combat,
+                        locale.format(
+                            "msg.item_sleep",
+                            &[("item", item_name), ("target", target_name)],
+                        ),
+                    );
                 }
             }
         }
     }
 
+    {
+        let mut map = world.borrow::<UniqueViewMut<Map>>();
+
+        for ((x, y), flags) in marks {
+            map.set_flags(x, y, flags, true);
+        }
+    }
+
+    let repairs = world
+        .borrow::<View<Repairs>>()
+        .try_get(item_id)
+        .map(|repairs| repairs.0);
+
+    if let Ok(slot) = repairs {
+        let repaired = durability::repair_equipment(world, user_id, slot);
+        let locale = world.borrow::<UniqueView<Locale>>();
+        let mut msgs = world.borrow::<UniqueViewMut<Messages>>();
+        let names = world.borrow::<View<Name>>();
+        let item_name = &names.get(item_id).0;
+
+        msgs.add_as(
+            MsgCategory::Item,
+            match repaired {
+                Some(repaired_id) => locale.format(
+                    "msg.item_repairs",
+                    &[("item", item_name), ("target", &names.get(repaired_id).0)],
+                ),
+                None => locale.format("msg.item_repairs_nothing", &[("item", item_name)]),
+            },
+        );
+    }
+
+    let script = world
+        .borrow::<View<ScriptedUse>>()
+        .try_get(item_id)
+        .map(|scripted_use| scripted_use.0.clone());
+
+    if let Ok(script) = script {
+        for target_id in script_targets {
+            script::run_script(world, &script, user_id, target_id);
+        }
+    }
+
     if world.borrow::<View<Consumable>>().contains(item_id) {
         remove_item_from_inventory(world, user_id, item_id);
         world.borrow::<AllStoragesViewMut>().delete(item_id);
     }
 
-    false
+    Ok(false)
 }
 
 pub fn is_asleep(world: &World, who: EntityId) -> bool {
@@ -410,7 +858,8 @@ pub fn handle_sleep_turn(world: &World, who: EntityId) {
     let mut asleeps = world.borrow::<ViewMut<Asleep>>();
 
     if let Ok(mut asleep) = (&mut asleeps).try_get(who) {
-        let (mut msgs, player_id, combat_stats, coords, fovs, names) = world.borrow::<(
+        let (locale, mut msgs, player_id, combat_stats, coords, fovs, names) = world.borrow::<(
+            UniqueView<Locale>,
             UniqueViewMut<Messages>,
             UniqueView<PlayerId>,
             View<CombatStats>,
@@ -444,7 +893,23 @@ pub fn handle_sleep_turn(world: &World, who: EntityId) {
 
             asleeps.remove(who);
             if show_msg {
-                msgs.add(format!("{} wakes up.", names.get(who).0));
+                msgs.add_as(
+                    MsgCategory::Combat,
+                    locale.format("msg.wakes_up", &[("who", &names.get(who).0)]),
+                );
+            } else if let Ok(who_coord) = coords.try_get(who) {
+                let player_pos = coords.get(player_id.0).0.into();
+                let player_fov = fovs.get(player_id.0);
+
+                if let Some(cue) = sound::cue(
+                    &locale,
+                    player_pos,
+                    player_fov,
+                    who_coord.0.into(),
+                    "sound.stirring",
+                ) {
+                    msgs.add(cue);
+                }
             }
         }
     }
@@ -0,0 +1,63 @@
+use serde::{Deserialize, Serialize};
+
+use crate::npc::GiftItem;
+
+/// A quest handed out by an NPC, tracked in the journal.
+#[derive(Deserialize, Serialize)]
+pub struct JournalEntry {
+    pub id: String,
+    pub giver: String,
+    pub text: String,
+    /// Dungeon depth where the quest was given.
+    pub depth: i32,
+    /// Dungeon depth where the quest was completed, if it has been.
+    pub completed_depth: Option<i32>,
+    pub reward: Option<GiftItem>,
+    pub reward_claimed: bool,
+}
+
+/// Active and completed quests of the current game.
+#[derive(Default, Deserialize, Serialize)]
+pub struct Journal {
+    entries: Vec<JournalEntry>,
+}
+
+impl Journal {
+    pub fn replace(&mut self, replacement: Self) {
+        self.entries = replacement.entries;
+    }
+
+    pub fn reset(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Add a new quest, returning false if a quest with the same ID was already given.
+    pub fn add_quest(&mut self, entry: JournalEntry) -> bool {
+        if self.entries.iter().any(|e| e.id == entry.id) {
+            false
+        } else {
+            self.entries.push(entry);
+            true
+        }
+    }
+
+    /// Mark an active quest as completed, returning it if it was active.
+    pub fn complete_quest(&mut self, id: &str, depth: i32) -> Option<&mut JournalEntry> {
+        let entry = self
+            .entries
+            .iter_mut()
+            .find(|e| e.id == id && e.completed_depth.is_none())?;
+
+        entry.completed_depth = Some(depth);
+
+        Some(entry)
+    }
+
+    pub fn active(&self) -> impl Iterator<Item = &JournalEntry> {
+        self.entries.iter().filter(|e| e.completed_depth.is_none())
+    }
+
+    pub fn completed(&self) -> impl Iterator<Item = &JournalEntry> {
+        self.entries.iter().filter(|e| e.completed_depth.is_some())
+    }
+}
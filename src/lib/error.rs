@@ -0,0 +1,39 @@
+use std::{error, fmt};
+
+/// Something that went wrong, along with what was being done when it happened, e.g. "Failed to
+/// save game" with the underlying I/O error as its source.
+#[derive(Debug)]
+pub struct Error {
+    context: String,
+    source: Box<dyn error::Error>,
+}
+
+impl Error {
+    pub fn new<S, E>(context: S, source: E) -> Self
+    where
+        S: Into<String>,
+        E: Into<Box<dyn error::Error>>,
+    {
+        Self {
+            context: context.into(),
+            source: source.into(),
+        }
+    }
+
+    /// What was being done when the error happened.
+    pub fn context(&self) -> &str {
+        &self.context
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(f, "{}: {}", self.context, self.source)
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        Some(self.source.as_ref())
+    }
+}
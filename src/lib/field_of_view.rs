@@ -11,26 +11,105 @@ pub enum FovShape {
     CirclePlus,
 }
 
+/// Algorithm used by [field_of_view] to decide which tiles are in view.
+#[derive(Clone, Copy, Eq, PartialEq)]
+pub enum FovAlgorithm {
+    /// Shadowcasting from tile centers with diamond-shaped walls.  Fast, but not every tile in
+    /// view can see the starting position back; such tiles are marked as not symmetric.
+    Shadowcast,
+    /// Shadowcasting that only shows tiles that can see the starting position back, plus any
+    /// opaque tiles in view so that walls still show up.  Use this for fair ranged combat.
+    Symmetric,
+    /// A tile is in view if a line from anywhere in the starting tile reaches anywhere in it
+    /// without passing through an opaque tile, approximated with lines between the centers,
+    /// corners and edge midpoints of each tile.  Sees the most around corners.  Symmetric.
+    Permissive,
+    /// A tile is in view if a digital line, i.e. a straight line made of tiles, connects it with
+    /// the starting position without passing through an opaque tile.  Symmetric.
+    Digital,
+}
+
+/// Offsets of the points in each tile that lines are drawn between for [FovAlgorithm::Permissive],
+/// in halves of a tile from the tile center.
+const PERMISSIVE_SAMPLES: [i32; 3] = [-1, 0, 1];
+
+/// Sight between a low and high angle: ((low_dy, low_dx), (high_dy, high_dx)).
+type Sight = ((i32, i32), (i32, i32));
+
+/// How x and y in an octant map to real x and y offsets, and whether the octant includes its edges:
+/// (real_x_from_x, real_x_from_y, real_y_from_x, real_y_from_y, include_edges).
+const OCTANT_DATA: [(i32, i32, i32, i32, bool); 8] = [
+    (1, 0, 0, 1, true),
+    (0, 1, 1, 0, false),
+    (0, -1, 1, 0, true),
+    (-1, 0, 0, 1, false),
+    (-1, 0, 0, -1, true),
+    (0, -1, -1, 0, false),
+    (0, 1, -1, 0, true),
+    (1, 0, 0, -1, false),
+];
+
+/// Working buffers of a field of view calculation.
+///
+/// Keeping these around and passing them to [field_of_view_reusing] avoids allocating new ones
+/// for every calculation, which adds up when many fields of view are calculated each turn.
+#[derive(Default)]
+pub struct FovBuffers {
+    sights_even: Vec<Sight>,
+    sights_odd: Vec<Sight>,
+    blockers: Vec<(i32, i32)>,
+}
+
 /// A map-like trait that can be sent into [field_of_view] to calculate a field of view.
 pub trait ViewableField {
     /// Returns `true` if the tile at the given coordinates is opaque.
     fn is_opaque(&self, x: i32, y: i32) -> bool;
 }
 
+/// Returns `true` if the line segment from `p` to `q`, in halves of a tile, passes through the
+/// interior of the tile at `(tx, ty)`; merely touching its edges or corners doesn't count.
+fn segment_crosses_tile(p: (i32, i32), q: (i32, i32), (tx, ty): (i32, i32)) -> bool {
+    // Range of the segment parameter within the tile, exclusive.
+    let mut low = f64::NEG_INFINITY;
+    let mut high = f64::INFINITY;
+
+    for &(start, delta, min, max) in &[
+        (p.0, q.0 - p.0, 2 * tx - 1, 2 * tx + 1),
+        (p.1, q.1 - p.1, 2 * ty - 1, 2 * ty + 1),
+    ] {
+        if delta == 0 {
+            if start <= min || start >= max {
+                return false;
+            }
+        } else {
+            let t1 = (min - start) as f64 / delta as f64;
+            let t2 = (max - start) as f64 / delta as f64;
+
+            low = low.max(t1.min(t2));
+            high = high.min(t1.max(t2));
+        }
+    }
+
+    low < high && low < 1.0 && high > 0.0
+}
+
 /// Iterator returned by [field_of_view] that iterates over each tile in the field of view.
 ///
 /// Each call to [FovIter::next] returns `x`, `y` and `symmetric`, the last of which is `true` if
-/// the starting position and tile in question are in each other's fields of view.
+/// the starting position and tile in question are in each other's fields of view.  Only
+/// [FovAlgorithm::Shadowcast] ever returns tiles that aren't symmetric, apart from opaque tiles
+/// returned by [FovAlgorithm::Symmetric].
 pub struct FovIter<'a, T: BoundedMap + ViewableField> {
     map: &'a T,
     start_pos: (i32, i32),
     range: i32,
     fov_shape: FovShape,
+    algorithm: FovAlgorithm,
 
     bounds: (i32, i32, i32, i32), // min_x, min_y, max_x, max_y
+    clip: (i32, i32, i32, i32),   // ^, within bounds
     max_dist2: i32,
-    sights_even: Vec<((i32, i32), (i32, i32))>, // ((low_dy, low_dx), (high_dy, high_dx))
-    sights_odd: Vec<((i32, i32), (i32, i32))>,  // ^
+    buffers: FovBuffers,
     low_y: i32,
     high_y: i32,
     low_sight_angle: Option<(i32, i32)>,
@@ -39,9 +118,178 @@ pub struct FovIter<'a, T: BoundedMap + ViewableField> {
     x: Option<i32>,
     s: Option<usize>,
     y: Option<i32>,
+
+    scan: Option<(i32, i32)>,
 }
 
 impl<T: BoundedMap + ViewableField> FovIter<'_, T> {
+    /// Only visit tiles within the given `(min_x, min_y, max_x, max_y)` rectangle, inclusive,
+    /// skipping the work for any part of the field of view that lies completely outside of it.
+    ///
+    /// Tiles outside of the rectangle still block sight as usual.
+    pub fn clip(mut self, (min_x, min_y, max_x, max_y): (i32, i32, i32, i32)) -> Self {
+        self.clip = (
+            min_x.max(self.bounds.0),
+            min_y.max(self.bounds.1),
+            max_x.min(self.bounds.2),
+            max_y.min(self.bounds.3),
+        );
+        self
+    }
+
+    /// Pick the algorithm used to decide which tiles are in view; [FovAlgorithm::Shadowcast] is
+    /// used if none is picked.
+    pub fn algorithm(mut self, algorithm: FovAlgorithm) -> Self {
+        self.algorithm = algorithm;
+        self
+    }
+
+    /// Take back the working buffers of the calculation to reuse them with
+    /// [field_of_view_reusing].
+    pub fn into_buffers(self) -> FovBuffers {
+        self.buffers
+    }
+
+    /// Returns `true` if the tile at the given position is within map bounds and opaque.
+    fn opaque_at(&self, x: i32, y: i32) -> bool {
+        let (min_x, min_y, max_x, max_y) = self.bounds;
+
+        x >= min_x && x <= max_x && y >= min_y && y <= max_y && self.map.is_opaque(x, y)
+    }
+
+    /// Returns `true` if a digital line connects the starting position and the tile at the given
+    /// offset from it without passing through an opaque tile, not counting either end.
+    ///
+    /// Digital lines are those whose tiles along the minor axis are `(i * minor + eps) / major`
+    /// for some `eps` in `0..major`; reversing one gives another, so this is symmetric.
+    fn digital_line_of_sight(&self, dx: i32, dy: i32) -> bool {
+        let (ax, ay) = (dx.abs(), dy.abs());
+        let (major, minor) = (ax.max(ay), ax.min(ay));
+
+        (0..major.max(1)).any(|eps| {
+            (1..major).all(|i| {
+                let j = (i * minor + eps) / major;
+                let (ox, oy) = if ax >= ay { (i, j) } else { (j, i) };
+
+                !self.opaque_at(
+                    self.start_pos.0 + ox * dx.signum(),
+                    self.start_pos.1 + oy * dy.signum(),
+                )
+            })
+        })
+    }
+
+    /// Returns `true` if a line between sample points of the starting tile and the tile at the
+    /// given offset from it avoids the interior of every opaque tile, not counting either end.
+    ///
+    /// Both tiles use the same sample points, so this is symmetric.
+    fn permissive_line_of_sight(&mut self, dx: i32, dy: i32) -> bool {
+        let (sx, sy) = self.start_pos;
+        let (tx, ty) = (sx + dx, sy + dy);
+        let mut blockers = std::mem::take(&mut self.buffers.blockers);
+
+        // Only opaque tiles in the rectangle spanned by both ends can get in the way.
+        blockers.clear();
+        for y in sy.min(ty)..=sy.max(ty) {
+            for x in sx.min(tx)..=sx.max(tx) {
+                if (x, y) != (sx, sy) && (x, y) != (tx, ty) && self.opaque_at(x, y) {
+                    blockers.push((x, y));
+                }
+            }
+        }
+
+        let samples = |x: i32, y: i32| {
+            PERMISSIVE_SAMPLES.iter().flat_map(move |&oy| {
+                PERMISSIVE_SAMPLES
+                    .iter()
+                    .map(move |&ox| (2 * x + ox, 2 * y + oy))
+            })
+        };
+        let visible = blockers.is_empty()
+            || samples(sx, sy).any(|p| {
+                samples(tx, ty).any(|q| blockers.iter().all(|&b| !segment_crosses_tile(p, q, b)))
+            });
+
+        self.buffers.blockers = blockers;
+        visible
+    }
+
+    /// Step through each tile in range, checking each one for a line of sight individually, for
+    /// the algorithms that aren't based on shadowcasting.
+    fn next_scanned(&mut self) -> Option<(i32, i32, bool)> {
+        let (min_x, min_y, max_x, max_y) = self.clip;
+        let left = (self.start_pos.0 - self.range).max(min_x);
+        let top = (self.start_pos.1 - self.range).max(min_y);
+        let right = (self.start_pos.0 + self.range).min(max_x);
+        let bottom = (self.start_pos.1 + self.range).min(max_y);
+
+        loop {
+            let (x, y) = match self.scan {
+                None => (left, top),
+                Some((x, y)) if x < right => (x + 1, y),
+                Some((_, y)) => (left, y + 1),
+            };
+
+            if left > right || y > bottom {
+                self.scan = Some((right, bottom));
+                return None;
+            }
+
+            self.scan = Some((x, y));
+
+            let (dx, dy) = (x - self.start_pos.0, y - self.start_pos.1);
+            let in_shape = match self.fov_shape {
+                FovShape::Square => true,
+                FovShape::Circle | FovShape::CirclePlus => dx * dx + dy * dy <= self.max_dist2,
+            };
+            let visible = in_shape
+                && match self.algorithm {
+                    FovAlgorithm::Digital => self.digital_line_of_sight(dx, dy),
+                    _ => self.permissive_line_of_sight(dx, dy),
+                };
+
+            if visible {
+                return Some((x, y, true));
+            }
+        }
+    }
+
+    /// Returns the next tile in view according to shadowcasting.
+    fn next_shadowcast(&mut self) -> Option<(i32, i32, bool)> {
+        let mut item;
+
+        loop {
+            item = self.advance();
+
+            if let Some((x, y, _)) = item {
+                if x >= self.clip.0 && x <= self.clip.2 && y >= self.clip.1 && y <= self.clip.3 {
+                    // Valid position.
+                    break;
+                }
+            } else if self.octant.unwrap() >= 8 {
+                // The field of view has been completely iterated over.
+                break;
+            }
+        }
+
+        item
+    }
+
+    /// Returns `true` if the real area covered by an octant lies completely outside of the clip
+    /// rectangle.
+    fn octant_clipped(&self, octant: i32) -> bool {
+        let (rx_x, rx_y, ry_x, ry_y, _) = OCTANT_DATA[octant as usize];
+        let (sx, sy) = self.start_pos;
+        let (ex, ey) = (sx + self.range * rx_x, sy + self.range * ry_x);
+        let (cx, cy) = (ex + self.range * rx_y, ey + self.range * ry_y);
+        let (min_x, min_y, max_x, max_y) = self.clip;
+
+        sx.max(ex).max(cx) < min_x
+            || sx.min(ex).min(cx) > max_x
+            || sy.max(ey).max(cy) < min_y
+            || sy.min(ey).min(cy) > max_y
+    }
+
     /// Advance one step through the field of view calculation.  Returns `x`, `y` and `symmetric` if
     /// this step contains a tile in the field of view.
     ///
@@ -53,11 +301,11 @@ impl<T: BoundedMap + ViewableField> FovIter<'_, T> {
         let mut out_symmetric = false;
 
         if self.octant.is_none() {
-            // Exit early if field of view doesn't intersect map.
-            if self.start_pos.0 + self.range < self.bounds.0
-                || self.start_pos.0 - self.range > self.bounds.2
-                || self.start_pos.1 + self.range < self.bounds.1
-                || self.start_pos.1 - self.range > self.bounds.3
+            // Exit early if field of view doesn't intersect the clipped map.
+            if self.start_pos.0 + self.range < self.clip.0
+                || self.start_pos.0 - self.range > self.clip.2
+                || self.start_pos.1 + self.range < self.clip.1
+                || self.start_pos.1 - self.range > self.clip.3
             {
                 self.octant = Some(8);
             } else {
@@ -74,9 +322,15 @@ impl<T: BoundedMap + ViewableField> FovIter<'_, T> {
             self.octant = Some(octant + 1);
         } else if octant < 8 {
             if self.x.is_none() {
+                // Skip octants that can't show any tiles within the clip rectangle.
+                if self.octant_clipped(octant) {
+                    self.octant = Some(octant + 1);
+                    return None;
+                }
+
                 // Kick off with sight of the full octant.
-                self.sights_odd.clear();
-                self.sights_odd.push(((0, 1), (1, 1)));
+                self.buffers.sights_odd.clear();
+                self.buffers.sights_odd.push(((0, 1), (1, 1)));
                 self.x = Some(1);
             }
 
@@ -87,9 +341,9 @@ impl<T: BoundedMap + ViewableField> FovIter<'_, T> {
 
                 // Flip between using even and odd sights for current and next.
                 let (current, next) = if x % 2 == 0 {
-                    (&mut self.sights_even, &mut self.sights_odd)
+                    (&mut self.buffers.sights_even, &mut self.buffers.sights_odd)
                 } else {
-                    (&mut self.sights_odd, &mut self.sights_even)
+                    (&mut self.buffers.sights_odd, &mut self.buffers.sights_even)
                 };
 
                 if self.s.is_none() {
@@ -121,23 +375,13 @@ impl<T: BoundedMap + ViewableField> FovIter<'_, T> {
                     };
 
                     if in_shape && y <= self.high_y {
-                        let octant_data = [
-                            (1, 0, 0, 1, true),
-                            (0, 1, 1, 0, false),
-                            (0, -1, 1, 0, true),
-                            (-1, 0, 0, 1, false),
-                            (-1, 0, 0, -1, true),
-                            (0, -1, -1, 0, false),
-                            (0, 1, -1, 0, true),
-                            (1, 0, 0, -1, false),
-                        ];
                         let (
                             real_x_from_x,
                             real_x_from_y,
                             real_y_from_x,
                             real_y_from_y,
                             include_edges,
-                        ) = octant_data[octant as usize];
+                        ) = OCTANT_DATA[octant as usize];
 
                         let real_x = self.start_pos.0 + x * real_x_from_x + y * real_x_from_y;
                         let real_y = self.start_pos.1 + x * real_y_from_x + y * real_y_from_y;
@@ -201,27 +445,17 @@ impl<T: BoundedMap + ViewableField> Iterator for FovIter<'_, T> {
     /// Returns the next `(x, y, symmetric)` tuple, where `symmetric` means that the starting
     /// position and tile in question are in each other's fields of view.
     fn next(&mut self) -> Option<Self::Item> {
-        let mut item;
-
-        loop {
-            item = self.advance();
+        match self.algorithm {
+            FovAlgorithm::Shadowcast => self.next_shadowcast(),
+            FovAlgorithm::Symmetric => loop {
+                let item = self.next_shadowcast()?;
 
-            if let Some((x, y, _)) = item {
-                if x >= self.bounds.0
-                    && x <= self.bounds.2
-                    && y >= self.bounds.1
-                    && y <= self.bounds.3
-                {
-                    // Valid position.
-                    break;
+                if item.2 || self.map.is_opaque(item.0, item.1) {
+                    return Some(item);
                 }
-            } else if self.octant.unwrap() >= 8 {
-                // The field of view has been completely iterated over.
-                break;
-            }
+            },
+            FovAlgorithm::Permissive | FovAlgorithm::Digital => self.next_scanned(),
         }
-
-        item
     }
 }
 
@@ -237,6 +471,27 @@ pub fn field_of_view<T>(
     range: i32,
     fov_shape: FovShape,
 ) -> FovIter<'_, T>
+where
+    T: BoundedMap + ViewableField,
+{
+    let buffers = FovBuffers {
+        sights_even: Vec::with_capacity(range.max(0) as usize),
+        sights_odd: Vec::with_capacity(range.max(0) as usize),
+        blockers: Vec::new(),
+    };
+
+    field_of_view_reusing(map, start_pos, range, fov_shape, buffers)
+}
+
+/// Like [field_of_view], but working in buffers left over from an earlier calculation, which can
+/// be taken back afterwards with [FovIter::into_buffers].
+pub fn field_of_view_reusing<T>(
+    map: &'_ T,
+    start_pos: (i32, i32),
+    range: i32,
+    fov_shape: FovShape,
+    buffers: FovBuffers,
+) -> FovIter<'_, T>
 where
     T: BoundedMap + ViewableField,
 {
@@ -253,11 +508,12 @@ where
         start_pos,
         range,
         fov_shape,
+        algorithm: FovAlgorithm::Shadowcast,
 
         bounds: map.bounds(),
+        clip: map.bounds(),
         max_dist2,
-        sights_even: Vec::with_capacity(range as usize),
-        sights_odd: Vec::with_capacity(range as usize),
+        buffers,
         low_y: 0,
         high_y: 0,
         low_sight_angle: None,
@@ -266,5 +522,7 @@ where
         x: None,
         s: None,
         y: None,
+
+        scan: None,
     }
 }
@@ -1,12 +1,31 @@
-use sdl2::{event::Event, keyboard::Keycode};
+use sdl2::{
+    event::{Event, WindowEvent},
+    keyboard::Keycode,
+    mouse::MouseButton,
+};
 use std::collections::VecDeque;
 
+use crate::util::Position;
+
+/// Mouse ID that SDL gives to mouse events synthesized from touch input.
+const TOUCH_MOUSE_ID: u32 = u32::MAX;
+
 /// Input events buffered by and emitted from an [InputBuffer].
 #[derive(Clone, Copy)]
 pub enum InputEvent {
     AppQuit,
     Press(Keycode),
     Release(Keycode),
+    /// A character of text typed by the user, separate from the key presses that produced it, only
+    /// buffered while text is accepted; see [InputBuffer::accept_text].
+    Text(char),
+    /// The window lost keyboard focus.
+    FocusLost,
+    /// A left click or finger tap at a pixel position in the window; `touch` is true for taps.
+    Tap {
+        pos: Position,
+        touch: bool,
+    },
 }
 
 bitflags! {
@@ -50,11 +69,20 @@ bitflags! {
 /// [InputBuffer::more_inputs].
 ///
 /// As long as handle_event is called, inputs will be buffered.  If these buffered inputs aren't
-/// needed, calling [InputBuffer::flush_all_inputs] will clear them all.
+/// needed, calling [InputBuffer::flush_all_inputs] will clear them all.  Typed text is only buffered
+/// while [InputBuffer::accept_text] allows it.
+///
+/// Every prepared input is also recorded; call [InputBuffer::take_recorded] to collect them.
+/// Recorded inputs can be fed back in with [InputBuffer::replay]; they're prepared whenever no
+/// other input is waiting, and a real key press stops the replay so that the user can take over.
 pub struct InputBuffer {
     buffer: VecDeque<InputEvent>,
     current_input: Option<InputEvent>,
     keymods: KeyMods,
+    recorded: Vec<InputEvent>,
+    replay: VecDeque<InputEvent>,
+    replay_held: bool,
+    text_accepted: bool,
 }
 
 impl Default for InputBuffer {
@@ -70,6 +98,10 @@ impl InputBuffer {
             buffer: VecDeque::new(),
             current_input: None,
             keymods: KeyMods::empty(),
+            recorded: Vec::new(),
+            replay: VecDeque::new(),
+            replay_held: false,
+            text_accepted: false,
         }
     }
 
@@ -82,19 +114,51 @@ impl InputBuffer {
             Event::KeyUp {
                 keycode: Some(key), ..
             } => self.buffer.push_back(InputEvent::Release(*key)),
+            Event::TextInput { text, .. } if self.text_accepted => {
+                for c in text.chars() {
+                    self.buffer.push_back(InputEvent::Text(c));
+                }
+            }
+            Event::MouseButtonDown {
+                which,
+                mouse_btn: MouseButton::Left,
+                x,
+                y,
+                ..
+            } => self.buffer.push_back(InputEvent::Tap {
+                pos: Position { x: *x, y: *y },
+                touch: *which == TOUCH_MOUSE_ID,
+            }),
             Event::Quit { .. } => self.buffer.push_back(InputEvent::AppQuit),
+            Event::Window {
+                win_event: WindowEvent::FocusLost,
+                ..
+            } => {
+                // Key releases won't arrive while unfocused, so don't leave modifiers held down.
+                self.keymods = KeyMods::empty();
+                self.buffer.push_back(InputEvent::FocusLost);
+            }
             _ => {}
         }
     }
 
-    /// If no event is prepared, set current input event to the next one in the buffer.
-    /// If an event is already prepared, do nothing.
+    /// If no event is prepared, set current input event to the next one in the buffer, or the next
+    /// replayed event if the buffer is empty.  If an event is already prepared, do nothing.
     pub fn prepare_input(&mut self) {
-        if self.current_input.is_none() && !self.buffer.is_empty() {
-            self.current_input = self.buffer.pop_front();
+        if self.current_input.is_none() {
+            if let Some(input) = self.buffer.pop_front() {
+                if matches!(input, InputEvent::Press(_) | InputEvent::AppQuit) {
+                    self.replay.clear();
+                }
+                self.current_input = Some(input);
+            } else if !self.replay_held {
+                self.current_input = self.replay.pop_front();
+            }
 
-            // Track modifier keys.
             if let Some(input) = self.current_input {
+                self.recorded.push(input);
+
+                // Track modifier keys.
                 match input {
                     InputEvent::Press(keycode) => match keycode {
                         Keycode::LShift => self.keymods |= KeyMods::LSHIFT,
@@ -130,6 +194,50 @@ impl InputBuffer {
         !(self.keymods & mods).is_empty()
     }
 
+    /// Replace the current input event with another, e.g. to turn a tap on an on-screen button
+    /// into the key press that it stands for.  Does nothing if no event is prepared.
+    pub fn replace_input(&mut self, input: InputEvent) {
+        if self.current_input.is_some() {
+            self.current_input = Some(input);
+            if let Some(last) = self.recorded.last_mut() {
+                *last = input;
+            }
+        }
+    }
+
+    /// Take all of the input events prepared since the last call.
+    pub fn take_recorded(&mut self) -> Vec<InputEvent> {
+        std::mem::take(&mut self.recorded)
+    }
+
+    /// Queue up input events to be prepared as if they had just arrived, replacing any replay that
+    /// was already in progress.
+    pub fn replay<I: IntoIterator<Item = InputEvent>>(&mut self, inputs: I) {
+        self.replay = inputs.into_iter().collect();
+    }
+
+    /// Drop any input events left to be replayed.
+    pub fn stop_replay(&mut self) {
+        self.replay.clear();
+    }
+
+    /// Returns true if there are input events left to be replayed.
+    pub fn is_replaying(&self) -> bool {
+        !self.replay.is_empty()
+    }
+
+    /// Keep replayed input events from being prepared while `held` is true, e.g. so they don't
+    /// interrupt something that stops on any key press.
+    pub fn hold_replay(&mut self, held: bool) {
+        self.replay_held = held;
+    }
+
+    /// Buffer typed text as [InputEvent::Text] events only while `accepted` is true, e.g. while text
+    /// is being entered, so that it can't reach anything that only wants key presses.
+    pub fn accept_text(&mut self, accepted: bool) {
+        self.text_accepted = accepted;
+    }
+
     /// Clear the current input event.
     pub fn clear_input(&mut self) {
         self.current_input = None;
@@ -138,11 +246,13 @@ impl InputBuffer {
     /// Clear all buffered input events.
     pub fn flush_all_inputs(&mut self) {
         self.buffer.clear();
+        self.replay.clear();
         self.current_input = None;
     }
 
-    /// Returns true if there are more input events buffered beyond the current input.
+    /// Returns true if there are more input events buffered or ready to be replayed beyond the
+    /// current input.
     pub fn more_inputs(&self) -> bool {
-        !self.buffer.is_empty()
+        !self.buffer.is_empty() || (!self.replay_held && !self.replay.is_empty())
     }
 }
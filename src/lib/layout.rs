@@ -0,0 +1,257 @@
+use crate::{
+    tilegrid::{Symbol, TileGrid, Tileset},
+    util::{Position, Size},
+};
+
+/// The point of an [Area] that something laid out in it sticks to.
+///
+/// Anything that doesn't fit in its area is clipped on the side opposite the anchor, so e.g. a
+/// grid anchored to the top of an area that's too short for it shows its top rows.
+#[derive(Clone, Copy, Eq, PartialEq)]
+pub enum Anchor {
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+impl Anchor {
+    /// Horizontal and vertical alignment, where 0 is left/top, 1 is center and 2 is right/bottom.
+    fn alignment(self) -> (u32, u32) {
+        match self {
+            Anchor::TopLeft => (0, 0),
+            Anchor::Top => (1, 0),
+            Anchor::TopRight => (2, 0),
+            Anchor::Left => (0, 1),
+            Anchor::Center => (1, 1),
+            Anchor::Right => (2, 1),
+            Anchor::BottomLeft => (0, 2),
+            Anchor::Bottom => (1, 2),
+            Anchor::BottomRight => (2, 2),
+        }
+    }
+
+    /// Offset and clip offset along one axis of something `len` pixels long in a space `space`
+    /// pixels long with the given alignment.
+    fn align_axis(align: u32, len: u32, space: u32) -> (i32, i32) {
+        if len <= space {
+            (((space - len) * align / 2) as i32, 0)
+        } else {
+            (0, -(((len - space) * align / 2) as i32))
+        }
+    }
+
+    /// Position of the top-left corner of something of `size` pixels in an area anchored here,
+    /// along with how far it has to be shifted within the area to clip it on the correct sides.
+    pub(crate) fn place(self, size: Size, area: Area) -> (Area, Position) {
+        let (align_x, align_y) = self.alignment();
+        let (x, dx) = Self::align_axis(align_x, size.w, area.size.w);
+        let (y, dy) = Self::align_axis(align_y, size.h, area.size.h);
+
+        (
+            Area {
+                pos: Position {
+                    x: area.pos.x + x,
+                    y: area.pos.y + y,
+                },
+                size: Size {
+                    w: size.w.min(area.size.w),
+                    h: size.h.min(area.size.h),
+                },
+            },
+            Position { x: dx, y: dy },
+        )
+    }
+}
+
+/// How long a [Layout] wants to be along one axis, in cells.
+#[derive(Clone, Copy, Eq, PartialEq)]
+pub enum Extent {
+    /// An exact number of cells.
+    Cells(u32),
+    /// A percentage of the cells that fit in the area being laid out in.
+    Percent(u32),
+    /// All of the cells that fit in the area being laid out in.
+    Fill,
+}
+
+impl Extent {
+    fn cells(self, area_cells: u32) -> u32 {
+        match self {
+            Extent::Cells(cells) => cells,
+            Extent::Percent(percent) => area_cells * percent.min(100) / 100,
+            Extent::Fill => area_cells,
+        }
+    }
+}
+
+/// A rectangular area of the window in pixels.
+#[derive(Clone, Copy, Eq, PartialEq)]
+pub struct Area {
+    pub pos: Position,
+    pub size: Size,
+}
+
+impl Area {
+    pub fn new(pos: Position, size: Size) -> Self {
+        Self { pos, size }
+    }
+
+    /// An area covering a window of the given size.
+    pub fn window(window_size: Size) -> Self {
+        Self {
+            pos: Position { x: 0, y: 0 },
+            size: window_size,
+        }
+    }
+
+    /// Split off the bottom `h` pixels of the area, returning the rest and the bottom.
+    pub fn split_bottom(self, h: u32) -> (Area, Area) {
+        let h = h.min(self.size.h);
+        let top_h = self.size.h - h;
+
+        (
+            Area {
+                pos: self.pos,
+                size: Size {
+                    w: self.size.w,
+                    h: top_h,
+                },
+            },
+            Area {
+                pos: Position {
+                    x: self.pos.x,
+                    y: self.pos.y + top_h as i32,
+                },
+                size: Size { w: self.size.w, h },
+            },
+        )
+    }
+}
+
+/// Declarative size and placement of a TileGrid within an [Area] of the window.
+///
+/// The size of the grid in cells is worked out in this order:
+///
+/// 1. The width and height [Extent]s.
+/// 2. The maximum size.
+/// 3. The size of the area, less any space left for other grids, if the layout is fitted to it.
+/// 4. The minimum size.
+///
+/// The grid is then placed at its [Anchor] within the area, and clipped if it's still too big.
+#[derive(Clone, Copy)]
+pub struct Layout {
+    width: Extent,
+    height: Extent,
+    min_size: Size,
+    max_size: Size,
+    fit_width: Option<u32>,
+    fit_height: Option<u32>,
+    anchor: Anchor,
+}
+
+impl Layout {
+    /// A layout of the given width and height centered in its area.
+    pub fn new(width: Extent, height: Extent) -> Self {
+        Self {
+            width,
+            height,
+            min_size: Size { w: 0, h: 0 },
+            max_size: Size {
+                w: u32::MAX,
+                h: u32::MAX,
+            },
+            fit_width: None,
+            fit_height: None,
+            anchor: Anchor::Center,
+        }
+    }
+
+    pub fn with_anchor(mut self, anchor: Anchor) -> Self {
+        self.anchor = anchor;
+        self
+    }
+
+    /// Never shrink below the given size in cells, even if that means being clipped.
+    pub fn with_min_size(mut self, min_size: Size) -> Self {
+        self.min_size = min_size;
+        self
+    }
+
+    /// Never grow past the given size in cells.
+    pub fn with_max_size(mut self, max_size: Size) -> Self {
+        self.max_size = max_size;
+        self
+    }
+
+    /// Shrink to fit within the width of the area being laid out in with `reserved` columns to
+    /// spare, e.g. for other grids beside it, down to the minimum width.
+    pub fn fitted_width(mut self, reserved: u32) -> Self {
+        self.fit_width = Some(reserved);
+        self
+    }
+
+    /// Shrink to fit within the height of the area being laid out in with `reserved` rows to
+    /// spare, e.g. for other grids above or below it, down to the minimum height.
+    pub fn fitted_height(mut self, reserved: u32) -> Self {
+        self.fit_height = Some(reserved);
+        self
+    }
+
+    /// Size in cells of a grid with this layout in an area of `area_size` pixels, where each cell
+    /// is `cell_size` pixels.
+    pub fn grid_size(&self, area_size: Size, cell_size: Size) -> Size {
+        let area_w = area_size.w / cell_size.w.max(1);
+        let area_h = area_size.h / cell_size.h.max(1);
+        let mut w = self.width.cells(area_w).min(self.max_size.w);
+        let mut h = self.height.cells(area_h).min(self.max_size.h);
+
+        if let Some(reserved) = self.fit_width {
+            w = w.min(area_w.saturating_sub(reserved));
+        }
+        if let Some(reserved) = self.fit_height {
+            h = h.min(area_h.saturating_sub(reserved));
+        }
+
+        Size {
+            w: w.max(self.min_size.w),
+            h: h.max(self.min_size.h),
+        }
+    }
+
+    /// The part of `area` in pixels that a grid with this layout would cover.
+    pub fn area_in(&self, area: Area, cell_size: Size) -> Area {
+        let grid_size = self.grid_size(area.size, cell_size);
+        let px_size = Size {
+            w: grid_size.w * cell_size.w,
+            h: grid_size.h * cell_size.h,
+        };
+
+        self.anchor.place(px_size, area).0
+    }
+
+    /// Resize the grid according to the layout and set up its view to place it within `area`
+    /// at the given zoom level.
+    pub fn apply<Y: Symbol>(
+        &self,
+        grid: &mut TileGrid<Y>,
+        tilesets: &[Tileset<Y>],
+        zoom: u32,
+        area: Area,
+    ) {
+        let tileset = &tilesets[grid.tileset()];
+        let cell_size = Size {
+            w: tileset.tile_width() * zoom,
+            h: tileset.tile_height() * zoom,
+        };
+
+        grid.resize(self.grid_size(area.size, cell_size));
+        grid.view_anchored(tilesets, zoom, self.anchor, area.pos, area.size);
+        grid.view.zoom = zoom;
+    }
+}
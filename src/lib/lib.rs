@@ -1,20 +1,36 @@
 #[macro_use]
 extern crate bitflags;
 
+mod error;
 mod field_of_view;
 mod input_buffer;
+mod layout;
+mod menu;
+mod mode_stack;
 mod path_find;
 mod run;
 mod tilegrid;
 pub mod util;
 mod word_wrap;
 
-pub use field_of_view::{field_of_view, FovIter, FovShape, ViewableField};
+pub use error::Error;
+pub use field_of_view::{
+    field_of_view, field_of_view_reusing, FovAlgorithm, FovBuffers, FovIter, FovShape,
+    ViewableField,
+};
 pub use input_buffer::{InputBuffer, InputEvent, KeyMods};
-pub use path_find::{find_path, AStarIter, PathableMap};
-pub use run::{run, RunControl, RunSettings};
-pub use tilegrid::{Symbol, TileGrid, TileGridLayer, TileGridView, Tileset, TilesetInfo};
-pub use word_wrap::word_wrap;
+pub use layout::{Anchor, Area, Extent, Layout};
+pub use menu::{Menu, MenuItem, MenuNav};
+pub use mode_stack::{Mode, ModeControl, ModeResult, ModeStack, ModeStackHooks, ModeUpdate};
+pub use path_find::{
+    find_path, find_path_reusing, AStarIter, DijkstraMap, PathBuffers, PathStats, PathableMap,
+    CARDINAL_COST, DIAGONAL_COST,
+};
+pub use run::{run, FramePacing, FrameRequests, FrameTiming, RenderPath, RunControl, RunSettings};
+pub use tilegrid::{
+    DynSym, Symbol, SymbolRegistry, TileGrid, TileGridLayer, TileGridView, Tileset, TilesetInfo,
+};
+pub use word_wrap::{word_wrap, word_wrap_styled, WrapOptions};
 
 /// A trait for a map that has minimum and maximum coordinate bounds.
 pub trait BoundedMap {
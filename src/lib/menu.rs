@@ -0,0 +1,306 @@
+use sdl2::keyboard::Keycode;
+
+use crate::{
+    tilegrid::{Symbol, TileGrid},
+    util::{Color, Position},
+};
+
+/// Cursor movement for a [Menu], translated from whatever keys a game binds to them.
+#[derive(Clone, Copy, Eq, PartialEq)]
+pub enum MenuNav {
+    /// Move up one entry, wrapping around to the bottom of the menu.
+    Up,
+    /// Move down one entry, wrapping around to the top of the menu.
+    Down,
+    /// Move up a page of items.
+    PageUp,
+    /// Move down a page of items.
+    PageDown,
+    /// Move to the first item.
+    Home,
+    /// Move to the last item.
+    End,
+}
+
+/// An entry in a [Menu].
+pub struct MenuItem<T> {
+    /// What the entry stands for, handed back when it's chosen.
+    pub value: T,
+    /// Text shown for the entry.
+    pub label: String,
+    /// Key that jumps straight to the entry, if any.
+    pub hotkey: Option<Keycode>,
+}
+
+impl<T> MenuItem<T> {
+    pub fn new<S: Into<String>>(value: T, label: S) -> Self {
+        Self {
+            value,
+            label: label.into(),
+            hotkey: None,
+        }
+    }
+
+    /// Let the given key jump straight to the entry.
+    pub fn with_hotkey(mut self, hotkey: Keycode) -> Self {
+        self.hotkey = Some(hotkey);
+        self
+    }
+}
+
+/// A vertical menu that tracks which of its entries is selected and how far its list is scrolled.
+///
+/// The entries of a menu are split into a list of items that scrolls if it's too tall to be shown
+/// at once, and a footer of entries below it that stays put, e.g. a cancel button.  Moving up and
+/// down wraps around through both of them, while page navigation only moves through the items.
+///
+/// The selection is a single index that counts through the items and then the footer entries, so
+/// it can be remembered and handed back to a new menu to restore the cursor position.
+pub struct Menu<T> {
+    items: Vec<MenuItem<T>>,
+    footer: Vec<MenuItem<T>>,
+    selection: usize,
+}
+
+impl<T> Menu<T> {
+    pub fn new(items: Vec<MenuItem<T>>) -> Self {
+        Self {
+            items,
+            footer: Vec::new(),
+            selection: 0,
+        }
+    }
+
+    /// Add entries below the items that stay put while the items scroll.
+    pub fn with_footer(mut self, footer: Vec<MenuItem<T>>) -> Self {
+        self.footer = footer;
+        self
+    }
+
+    /// Start the menu with the given item selected, e.g. one that was remembered from before.
+    ///
+    /// The selection is kept within the items, so a remembered footer entry selects the last item.
+    pub fn with_selection(mut self, selection: usize) -> Self {
+        self.selection = selection.min(self.items.len().saturating_sub(1));
+        self
+    }
+
+    /// The items of the menu, not including the footer.
+    pub fn items(&self) -> &[MenuItem<T>] {
+        &self.items
+    }
+
+    /// Insert an item at the given index, keeping the same entry selected.
+    pub fn insert_item(&mut self, index: usize, item: MenuItem<T>) {
+        let index = index.min(self.items.len());
+
+        self.items.insert(index, item);
+        if self.selection >= index && self.num_entries() > 1 {
+            self.selection += 1;
+        }
+    }
+
+    /// Remove items that don't satisfy the predicate, keeping the selection in range.
+    pub fn retain_items<F: FnMut(&MenuItem<T>) -> bool>(&mut self, f: F) {
+        let in_footer = self.in_footer();
+        let footer_selection = self.selection.saturating_sub(self.items.len());
+
+        self.items.retain(f);
+        self.selection = if in_footer {
+            self.items.len() + footer_selection
+        } else {
+            self.selection.min(self.num_entries().saturating_sub(1))
+        };
+    }
+
+    /// Replace the label of every entry, e.g. after the language was changed.
+    pub fn relabel<F: FnMut(&T) -> String>(&mut self, mut f: F) {
+        for entry in self.items.iter_mut().chain(self.footer.iter_mut()) {
+            entry.label = f(&entry.value);
+        }
+    }
+
+    /// The number of entries in the menu, including the footer.
+    pub fn num_entries(&self) -> usize {
+        self.items.len() + self.footer.len()
+    }
+
+    /// Index of the selected entry, counting through the items and then the footer entries.
+    pub fn selection(&self) -> usize {
+        self.selection
+    }
+
+    /// Select the entry at the given index, counting through the items and then the footer.
+    pub fn select(&mut self, selection: usize) {
+        self.selection = selection.min(self.num_entries().saturating_sub(1));
+    }
+
+    /// Select the first entry whose value satisfies the predicate, returning true if one was found.
+    pub fn select_where<F: FnMut(&T) -> bool>(&mut self, mut f: F) -> bool {
+        let found = self.entries().position(|entry| f(&entry.value));
+
+        if let Some(pos) = found {
+            self.selection = pos;
+        }
+
+        found.is_some()
+    }
+
+    /// True if the selection is in the footer instead of the items.
+    pub fn in_footer(&self) -> bool {
+        self.selection >= self.items.len()
+    }
+
+    /// The selected entry, if the menu has any entries.
+    pub fn selected(&self) -> Option<&MenuItem<T>> {
+        self.entries().nth(self.selection)
+    }
+
+    /// Move the selection as requested, where a page is `page_height` items tall.
+    pub fn navigate(&mut self, nav: MenuNav, page_height: i32) {
+        let num_entries = self.num_entries();
+        let last_item = self.items.len().saturating_sub(1);
+        let page_height = page_height.max(1) as usize;
+
+        if num_entries == 0 {
+            return;
+        }
+
+        match nav {
+            MenuNav::Up => self.selection = (self.selection + num_entries - 1) % num_entries,
+            MenuNav::Down => self.selection = (self.selection + 1) % num_entries,
+            MenuNav::PageUp if !self.in_footer() => {
+                self.selection = self.selection.saturating_sub(page_height);
+            }
+            MenuNav::PageDown if !self.in_footer() => {
+                self.selection = (self.selection + page_height).min(last_item);
+            }
+            MenuNav::Home if !self.items.is_empty() => self.selection = 0,
+            MenuNav::End if !self.items.is_empty() => self.selection = last_item,
+            _ => {}
+        }
+    }
+
+    /// Select the entry with the given hotkey, returning true if there was one.
+    pub fn select_hotkey(&mut self, key: Keycode) -> bool {
+        let found = self.entries().position(|entry| entry.hotkey == Some(key));
+
+        if let Some(pos) = found {
+            self.selection = pos;
+        }
+
+        found.is_some()
+    }
+
+    /// The index of the first item shown in a list `list_height` rows tall, which keeps the
+    /// selected item near the middle of the list where possible.
+    pub fn list_offset(&self, list_height: i32) -> i32 {
+        let selection = self.selection.min(self.items.len().saturating_sub(1)) as i32;
+
+        (selection - (list_height - 1) / 2)
+            .min(self.items.len() as i32 - list_height)
+            .max(0)
+    }
+
+    /// Draw the items that fit in a list `list_height` rows tall starting at `pos`, along with a
+    /// scroll bar along the right edge of the grid if they don't all fit.
+    ///
+    /// `draw_item` is called with the grid, the position, the index and the item for each item
+    /// shown, as well as the background color to show whether it's selected.
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_list<Y, F>(
+        &self,
+        grid: &mut TileGrid<Y>,
+        pos: Position,
+        list_height: i32,
+        fg: Color,
+        bg: Color,
+        selected_bg: Color,
+        mut draw_item: F,
+    ) where
+        Y: Symbol,
+        F: FnMut(&mut TileGrid<Y>, Position, usize, &MenuItem<T>, Color),
+    {
+        let list_offset = self.list_offset(list_height);
+
+        if self.items.len() as i32 > list_height {
+            grid.draw_bar(
+                true,
+                (grid.width() as i32 - 1, pos.y),
+                list_height,
+                list_offset,
+                list_height,
+                self.items.len() as i32,
+                fg,
+                bg,
+            );
+        }
+
+        for (i, item) in self
+            .items
+            .iter()
+            .enumerate()
+            .skip(list_offset as usize)
+            .take(list_height.max(0) as usize)
+        {
+            let item_pos = Position {
+                x: pos.x,
+                y: pos.y + i as i32 - list_offset,
+            };
+            let item_bg = if i == self.selection { selected_bg } else { bg };
+
+            draw_item(grid, item_pos, i, item, item_bg);
+        }
+    }
+
+    /// Draw the labels of the items that fit in a list `list_height` rows tall starting at `pos`.
+    pub fn draw_labels<Y: Symbol>(
+        &self,
+        grid: &mut TileGrid<Y>,
+        pos: Position,
+        list_height: i32,
+        fg: Color,
+        bg: Color,
+        selected_bg: Color,
+    ) {
+        self.draw_list(
+            grid,
+            pos,
+            list_height,
+            fg,
+            bg,
+            selected_bg,
+            |grid, item_pos, _, item, item_bg| {
+                grid.print_color(item_pos, &item.label, true, fg, item_bg);
+            },
+        );
+    }
+
+    /// Draw the labels of the footer entries on consecutive rows starting at `pos`.
+    pub fn draw_footer<Y: Symbol>(
+        &self,
+        grid: &mut TileGrid<Y>,
+        pos: Position,
+        fg: Color,
+        bg: Color,
+        selected_bg: Color,
+    ) {
+        for (i, entry) in self.footer.iter().enumerate() {
+            grid.print_color(
+                (pos.x, pos.y + i as i32),
+                &entry.label,
+                true,
+                fg,
+                if self.items.len() + i == self.selection {
+                    selected_bg
+                } else {
+                    bg
+                },
+            );
+        }
+    }
+
+    fn entries(&self) -> impl Iterator<Item = &MenuItem<T>> {
+        self.items.iter().chain(self.footer.iter())
+    }
+}
@@ -0,0 +1,482 @@
+//! The mode stack is the central coordinator of the parts of a game that the player sees and
+//! interacts with.  The game parts, or "modes", are the high-level building blocks of the game,
+//! e.g. title screen, main gameplay screen, inventory menu, dialog boxes, etc.  By placing and
+//! changing these modes on the "mode stack", the top-most mode has its update logic performed,
+//! while all the modes on the stack perform their drawing from the bottom-up.
+//!
+//! To add a new mode, create a mode struct and implement [Mode] for it.  Modes are generic over a
+//! context type `C` that is handed to every mode method, e.g. the world that holds the game data,
+//! and the [Symbol] type `Y` of the TileGrids that the modes draw into.
+//!
+//! `update` for the new mode should perform update logic and return a two-tuple of [ModeControl]
+//! and [ModeUpdate].  The [ModeControl] controls stack manipulation which should most often be
+//! [ModeControl::Stay] to keep the stack as-is.  When using [ModeControl::Pop], return it with a
+//! boxed [ModeResult] of any type; the mode underneath receives it in its next `update` and can
+//! downcast it back to find out what happened.  Meanwhile, the [ModeUpdate] determines how the
+//! next `update` should be handled; see its documentation for the possible values and their
+//! effects.
+//!
+//! `draw` for the new mode should draw whatever the mode wants to show.  Modes underneath this
+//! mode on the stack will be drawn before this one, while modes on top will be drawn afterwards,
+//! so it's possible to have e.g. an inventory menu mode draw itself smaller than the screen, so
+//! the main gameplay mode underneath can be seen behind it.
+
+use std::{
+    any::{Any, TypeId},
+    time::Duration,
+};
+
+use crate::{
+    error::Error,
+    input_buffer::InputBuffer,
+    run::RunControl,
+    tilegrid::{Symbol, TileGrid, TileGridLayer, Tileset},
+    util::{Color, Position, Size},
+};
+
+/// Data returned by a mode when it's popped from the stack, to be downcast by the mode underneath.
+pub type ModeResult = Box<dyn Any>;
+
+/// A mode that can be placed on a [ModeStack].
+pub trait Mode<C, Y: Symbol>: Any {
+    /// Create or adjust the TileGrids of the mode before it's updated or drawn.
+    fn prepare_grids(
+        &mut self,
+        ctx: &C,
+        grids: &mut Vec<TileGrid<Y>>,
+        tilesets: &[Tileset<Y>],
+        window_size: Size,
+    );
+
+    /// Perform update logic for the mode when it's on top of the stack.  `pop_result` holds the
+    /// result of a mode that was just popped off the top of this one, if any.
+    fn update(
+        &mut self,
+        ctx: &C,
+        inputs: &mut InputBuffer,
+        grids: &[TileGrid<Y>],
+        pop_result: &Option<ModeResult>,
+    ) -> (ModeControl<C, Y>, ModeUpdate);
+
+    /// Draw the mode into its TileGrids; `active` is true if the mode is on top of the stack.
+    fn draw(&mut self, ctx: &C, grids: &mut [TileGrid<Y>], active: bool);
+
+    /// Should the mode draw modes behind it in the stack?
+    fn draw_behind(&self) -> bool {
+        true
+    }
+
+    /// Is the mode taking typed text while it's on top of the stack?  Text typed while this is false
+    /// isn't buffered at all.
+    fn wants_text(&self) -> bool {
+        false
+    }
+}
+
+/// Mode stack manipulation values to be returned from an `update` call.
+pub enum ModeControl<C, Y: Symbol> {
+    /// Keep the stack as-is.
+    Stay,
+    /// Replace the current mode on the stack with a new mode.
+    Switch(Box<dyn Mode<C, Y>>),
+    /// Push a new mode on top of the current mode on the stack.
+    Push(Box<dyn Mode<C, Y>>),
+    /// Pop the current mode from the stack, with a corresponding result.
+    Pop(ModeResult),
+    /// Clear the whole stack, while returning a corresponding result.
+    Terminate(ModeResult),
+    /// Report an error to the player, keeping the current mode on the stack.  The mode from
+    /// [ModeStackHooks::error_mode] is pushed on top to show it, and the current mode receives its
+    /// result when it's popped, e.g. to retry whatever failed.
+    Report(Error),
+}
+
+/// Desired behavior for the next update, to be returned from an `update` call.
+pub enum ModeUpdate {
+    /// Run the next update immediately, without waiting for the next frame.
+    Immediate,
+    /// Wait a frame before the next update; this will likely draw the mode for a frame.
+    Update,
+    /// Wait for an input event before the next update; this will likely draw the mode before
+    /// waiting.
+    WaitForEvent,
+    /// Like [ModeUpdate::WaitForEvent], but also update once the given duration passes without an
+    /// event, e.g. for slow animations.
+    WaitForEventTimeout(Duration),
+}
+
+/// Game-specific extras for [ModeStack::update] to weave in around the modes, e.g. on-screen
+/// controls that stay above every mode.  Every method does nothing by default, and `()` can be
+/// used when there's nothing extra to do.
+pub trait ModeStackHooks<C, Y: Symbol> {
+    /// Create or adjust the TileGrids of the overlay layer that sits above all modes.
+    fn prepare_overlay_grids(
+        &mut self,
+        _ctx: &C,
+        _grids: &mut Vec<TileGrid<Y>>,
+        _tilesets: &[Tileset<Y>],
+        _window_size: Size,
+    ) {
+    }
+
+    /// Called just before the top mode is updated, e.g. to translate inputs for it.
+    ///
+    /// Returning an error shows it with [ModeStackHooks::error_mode] as if the top mode had
+    /// reported it, except that the top mode isn't updated, and the result of the error mode goes
+    /// to [ModeStackHooks::report_done] instead of the top mode.
+    fn before_update(
+        &mut self,
+        _ctx: &C,
+        _inputs: &mut InputBuffer,
+        _overlay: &[TileGrid<Y>],
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Called with the result of the error mode that showed an error returned by
+    /// [ModeStackHooks::before_update], e.g. to retry whatever failed.  Returning an error shows
+    /// that in turn.
+    fn report_done(&mut self, _ctx: &C, _result: ModeResult) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Called just after the top mode is updated.
+    fn after_update(&mut self, _ctx: &C, _inputs: &mut InputBuffer) {}
+
+    /// Draw the overlay layer after all the modes have been drawn.
+    fn draw_overlay(&mut self, _ctx: &C, _overlay: &mut [TileGrid<Y>]) {}
+
+    /// Create a mode to show an error reported through [ModeControl::Report].  Returning the error
+    /// instead stops the mode stack, handing the error out through [RunControl::Error].
+    fn error_mode(&mut self, _ctx: &C, error: Error) -> Result<Box<dyn Mode<C, Y>>, Error> {
+        Err(error)
+    }
+
+    /// Should changes to the top of the stack be animated?
+    fn transitions(&self, _ctx: &C) -> bool {
+        false
+    }
+}
+
+impl<C, Y: Symbol> ModeStackHooks<C, Y> for () {}
+
+/// Number of frames that a transition between modes takes.
+const TRANSITION_FRAMES: u32 = 8;
+
+/// Pixels below its final position that a mode drawn over others starts sliding in from.
+const TRANSITION_SLIDE: i32 = 24;
+
+/// An animation of the top of the mode stack changing, shown over a few frames.
+#[derive(Clone, Copy)]
+enum Transition {
+    /// A mode was pushed or switched in: menus slide and fade in while the modes behind them dim,
+    /// and other modes fade in from black.
+    In(u32),
+    /// A mode was popped: the mode revealed underneath brightens back up.
+    Out(u32),
+}
+
+/// The mode stack proper.  Create one of these with an initial mode, then call [ModeStack::update]
+/// at the appropriate point in the surrounding code; the mode stack and the modes it holds will
+/// handle everything else.
+pub struct ModeStack<C, Y: Symbol> {
+    stack: Vec<Box<dyn Mode<C, Y>>>,
+    pop_result: Option<ModeResult>,
+    /// Stack depth to return to once an error returned by the hooks has been shown, along with the
+    /// pop result that the top mode was due before then.
+    hook_report: Option<(usize, Option<ModeResult>)>,
+    /// Result of showing an error returned by the hooks, to be handed back to them.
+    hook_result: Option<ModeResult>,
+    transition: Option<Transition>,
+    /// Positions and color mods of grids as the modes left them, before the transition changed
+    /// them, as (layer index, grid index, position, color mod).
+    transition_restore: Vec<(usize, usize, Position, Color)>,
+}
+
+impl<C: 'static, Y: Symbol + 'static> ModeStack<C, Y> {
+    /// Create a new mode stack.
+    pub fn new(stack: Vec<Box<dyn Mode<C, Y>>>) -> Self {
+        Self {
+            stack,
+            pop_result: None,
+            hook_report: None,
+            hook_result: None,
+            transition: None,
+            transition_restore: Vec::new(),
+        }
+    }
+
+    /// True if a mode of the given type is somewhere in the stack.
+    pub fn contains<M: Mode<C, Y>>(&self) -> bool {
+        self.stack
+            .iter()
+            .any(|mode| (**mode).type_id() == TypeId::of::<M>())
+    }
+
+    /// Perform update logic for the top mode of the stack, and then drawing logic for all modes.
+    ///
+    /// This also converts [ModeUpdate] values into [RunControl] values to control the behavior of
+    /// the next update.
+    ///
+    /// The overlay of the hooks gets a layer of its own above those of the modes, which is set
+    /// aside while the modes are updated so that each mode still lines up with its layer.
+    pub fn update<H: ModeStackHooks<C, Y>>(
+        &mut self,
+        ctx: &C,
+        inputs: &mut InputBuffer,
+        layers: &mut Vec<TileGridLayer<Y>>,
+        tilesets: &[Tileset<Y>],
+        window_size: Size,
+        hooks: &mut H,
+    ) -> RunControl {
+        let mut overlay_layer = if !layers.is_empty() && layers.len() > self.stack.len() {
+            layers.pop().unwrap()
+        } else {
+            TileGridLayer {
+                draw_behind: true,
+                grids: Vec::new(),
+            }
+        };
+
+        let run_control = self.update_modes(
+            ctx,
+            inputs,
+            layers,
+            &mut overlay_layer.grids,
+            tilesets,
+            window_size,
+            hooks,
+        );
+
+        if !self.stack.is_empty() {
+            layers.push(overlay_layer);
+        }
+
+        run_control
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn update_modes<H: ModeStackHooks<C, Y>>(
+        &mut self,
+        ctx: &C,
+        inputs: &mut InputBuffer,
+        layers: &mut Vec<TileGridLayer<Y>>,
+        overlay_grids: &mut Vec<TileGrid<Y>>,
+        tilesets: &[Tileset<Y>],
+        window_size: Size,
+        hooks: &mut H,
+    ) -> RunControl {
+        self.undo_transition(layers);
+
+        if !self.stack.is_empty() && layers.is_empty() {
+            // Initialize a layer for each mode in the stack.
+            // There will always be a layer for each mode, even if it doesn't use it.
+            for mode in &self.stack {
+                layers.push(TileGridLayer {
+                    draw_behind: mode.draw_behind(),
+                    grids: Vec::new(),
+                });
+            }
+        }
+
+        while !self.stack.is_empty() {
+            // Prepare grids for modes, starting from the lowest visible mode.
+            let prepare_grids_from = self
+                .stack
+                .iter()
+                .rposition(|mode| !mode.draw_behind())
+                .unwrap_or(0);
+
+            for (i, mode) in self.stack.iter_mut().enumerate().skip(prepare_grids_from) {
+                mode.prepare_grids(ctx, &mut layers[i].grids, tilesets, window_size);
+            }
+            hooks.prepare_overlay_grids(ctx, overlay_grids, tilesets, window_size);
+
+            let hooked = match self.hook_result.take() {
+                Some(result) => hooks.report_done(ctx, result),
+                None => Ok(()),
+            }
+            .and_then(|()| hooks.before_update(ctx, inputs, overlay_grids));
+
+            // Update the top mode, unless the hooks have an error to show over it first.
+            let (mode_control, mode_update) = match hooked {
+                Ok(()) => {
+                    let top_mode = self.stack.last_mut().unwrap();
+                    let top_layer = layers.last().unwrap();
+                    let updated =
+                        top_mode.update(ctx, inputs, top_layer.grids.as_slice(), &self.pop_result);
+
+                    hooks.after_update(ctx, inputs);
+                    self.pop_result = None;
+                    updated
+                }
+                Err(error) => {
+                    self.hook_report = Some((self.stack.len(), self.pop_result.take()));
+                    (ModeControl::Report(error), ModeUpdate::Immediate)
+                }
+            };
+
+            // Show reported errors in a mode pushed on top of the mode that reported them.
+            let mode_control = match mode_control {
+                ModeControl::Report(error) => match hooks.error_mode(ctx, error) {
+                    Ok(mode) => ModeControl::Push(mode),
+                    Err(error) => return RunControl::Error(error),
+                },
+                mode_control => mode_control,
+            };
+
+            // Animate changes to the top of the stack if wanted.
+            let transitions = hooks.transitions(ctx);
+
+            self.transition = match mode_control {
+                ModeControl::Stay => self.transition,
+                ModeControl::Switch(_) | ModeControl::Push(_) if transitions => {
+                    Some(Transition::In(0))
+                }
+                ModeControl::Pop(_) if transitions => Some(Transition::Out(0)),
+                _ => None,
+            };
+
+            // Control the stack as requested by the top mode update logic.
+            match mode_control {
+                ModeControl::Stay => {}
+                ModeControl::Switch(mode) => {
+                    self.stack.pop();
+                    layers.pop();
+                    layers.push(TileGridLayer {
+                        draw_behind: mode.draw_behind(),
+                        grids: Vec::new(),
+                    });
+                    self.stack.push(mode);
+                }
+                ModeControl::Push(mode) => {
+                    layers.push(TileGridLayer {
+                        draw_behind: mode.draw_behind(),
+                        grids: Vec::new(),
+                    });
+                    self.stack.push(mode);
+                }
+                ModeControl::Pop(mode_result) => {
+                    self.pop_result = Some(mode_result);
+                    self.stack.pop();
+                    layers.pop();
+
+                    // Hand the result of showing an error from the hooks back to them, and give the
+                    // top mode back whatever it was due before then.
+                    if matches!(self.hook_report, Some((depth, _)) if depth == self.stack.len()) {
+                        let (_, pop_result) = self.hook_report.take().unwrap();
+
+                        self.hook_result = std::mem::replace(&mut self.pop_result, pop_result);
+                    }
+                }
+                ModeControl::Terminate(mode_result) => {
+                    self.pop_result = Some(mode_result);
+                    self.hook_report = None;
+                    self.stack.clear();
+                    layers.clear();
+                }
+                ModeControl::Report(_) => unreachable!(),
+            }
+
+            // Only take typed text for a mode on top that's waiting for it.
+            inputs.accept_text(self.stack.last().is_some_and(|mode| mode.wants_text()));
+
+            // Draw modes in the stack from the bottom-up.
+            if !self.stack.is_empty() && !matches!(mode_update, ModeUpdate::Immediate) {
+                let draw_from = self
+                    .stack
+                    .iter()
+                    .rposition(|mode| !mode.draw_behind())
+                    .unwrap_or(0);
+                let top = self.stack.len().saturating_sub(1);
+
+                // Draw non-top modes with `active` set to `false`.
+                for (i, mode) in self.stack.iter_mut().enumerate().skip(draw_from) {
+                    mode.draw(ctx, &mut layers[i].grids[..], false);
+                }
+
+                // Draw top mode with `active` set to `true`.
+                self.stack[top].draw(ctx, &mut layers[top].grids[..], true);
+
+                hooks.draw_overlay(ctx, &mut overlay_grids[..]);
+
+                // Keep updating until the transition is done, even if the mode would wait.
+                if self.apply_transition(layers) {
+                    if let ModeUpdate::WaitForEvent | ModeUpdate::WaitForEventTimeout(_) =
+                        mode_update
+                    {
+                        return RunControl::Update;
+                    }
+                }
+            }
+
+            match mode_update {
+                ModeUpdate::Immediate => (),
+                ModeUpdate::Update => return RunControl::Update,
+                ModeUpdate::WaitForEvent => return RunControl::WaitForEvent,
+                ModeUpdate::WaitForEventTimeout(timeout) => {
+                    return RunControl::WaitForEventTimeout(timeout)
+                }
+            }
+        }
+
+        RunControl::Quit
+    }
+
+    /// Put grids back the way their modes left them before the last transition frame changed them.
+    fn undo_transition(&mut self, layers: &mut [TileGridLayer<Y>]) {
+        for (layer, grid, pos, color_mod) in self.transition_restore.drain(..) {
+            if let Some(grid) = layers.get_mut(layer).and_then(|l| l.grids.get_mut(grid)) {
+                grid.view.pos = pos;
+                grid.view.color_mod = color_mod;
+            }
+        }
+    }
+
+    /// Change the grids of the modes on the stack to show the current frame of the transition,
+    /// returning true if the transition has more frames to show.
+    fn apply_transition(&mut self, layers: &mut [TileGridLayer<Y>]) -> bool {
+        let (frame, entering) = match self.transition {
+            Some(Transition::In(frame)) => (frame, true),
+            Some(Transition::Out(frame)) => (frame, false),
+            None => return false,
+        };
+        let top = self.stack.len() - 1;
+        let amount = (255 * (frame + 1) / TRANSITION_FRAMES) as u8;
+        let remaining = (TRANSITION_FRAMES - frame - 1) as i32;
+        let overlay = self.stack[top].draw_behind();
+
+        for (l, layer) in layers.iter_mut().enumerate().take(top + 1) {
+            for (g, grid) in layer.grids.iter_mut().enumerate() {
+                let view = &mut grid.view;
+                let color_mod = view.color_mod;
+
+                self.transition_restore.push((l, g, view.pos, color_mod));
+
+                if l == top {
+                    if entering {
+                        view.color_mod = Color::BLACK.blend(color_mod, amount);
+                        if overlay {
+                            view.pos.y += TRANSITION_SLIDE * remaining / TRANSITION_FRAMES as i32;
+                        }
+                    } else {
+                        view.color_mod = Color::GRAY.blend(color_mod, amount);
+                    }
+                } else if entering && overlay {
+                    view.color_mod = Color::WHITE.blend(color_mod, amount);
+                }
+            }
+        }
+
+        self.transition = if frame + 1 < TRANSITION_FRAMES {
+            Some(match self.transition {
+                Some(Transition::In(_)) => Transition::In(frame + 1),
+                _ => Transition::Out(frame + 1),
+            })
+        } else {
+            None
+        };
+
+        self.transition.is_some()
+    }
+}
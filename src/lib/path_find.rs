@@ -11,9 +11,34 @@ pub trait PathableMap {
     fn is_blocked(&self, x: i32, y: i32) -> bool;
 }
 
+/// Statistics about a path search, for profiling.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PathStats {
+    /// Positions taken off the frontier and expanded.
+    pub expanded: u32,
+    /// Positions put onto the frontier.
+    pub queued: u32,
+    /// True if the search ran out of its expansion budget before reaching the destination.
+    pub out_of_budget: bool,
+}
+
+/// Working storage of a path search.
+///
+/// Keeping these around and passing them to [find_path_reusing] avoids allocating new ones for
+/// every search, which adds up when many monsters look for paths each turn.
+#[derive(Default)]
+pub struct PathBuffers {
+    // (priority, (x, y))
+    frontier: BinaryHeap<(Reverse<i32>, (i32, i32))>,
+    // ((x, y), cost)
+    cost_so_far: HashMap<(i32, i32), i32>,
+    came_from: HashMap<(i32, i32), (i32, i32)>,
+    stats: PathStats,
+}
+
 /// Iterator that steps through each tile in a path found by [find_path].
 pub struct AStarIter {
-    came_from: HashMap<(i32, i32), (i32, i32)>,
+    buffers: PathBuffers,
     current_pos: Option<(i32, i32)>,
     pub fallback: bool,
 }
@@ -24,6 +49,16 @@ impl AStarIter {
     pub fn is_fallback(&self) -> bool {
         self.fallback
     }
+
+    /// Statistics about the search that found the path.
+    pub fn stats(&self) -> PathStats {
+        self.buffers.stats
+    }
+
+    /// Take back the working storage of the search to reuse it with [find_path_reusing].
+    pub fn into_buffers(self) -> PathBuffers {
+        self.buffers
+    }
 }
 
 impl Iterator for AStarIter {
@@ -31,7 +66,7 @@ impl Iterator for AStarIter {
 
     fn next(&mut self) -> Option<Self::Item> {
         if let Some(current_pos) = self.current_pos {
-            self.current_pos = self.came_from.get(&current_pos).copied();
+            self.current_pos = self.buffers.came_from.get(&current_pos).copied();
             Some(current_pos)
         } else {
             None
@@ -50,23 +85,184 @@ const ADJACENT_TILES: [(i32, i32); 8] = [
     (1, 1),
 ];
 
+/// Cost of moving to a cardinally adjacent tile in a [DijkstraMap].
+pub const CARDINAL_COST: i32 = 100;
+
+/// Cost of moving to a diagonally adjacent tile in a [DijkstraMap].
+pub const DIAGONAL_COST: i32 = 141;
+
+/// A map of the cost of the cheapest path from each tile to the nearest of a set of source tiles,
+/// also known as a distance field.
+///
+/// Stepping "downhill" from any tile leads to the nearest source, so one DijkstraMap can stand in
+/// for a path search per seeker, e.g. for every monster approaching the player, auto-explore
+/// heading to unexplored tiles or fleeing with [DijkstraMap::fleeing].
+///
+/// Costs follow [CARDINAL_COST] and [DIAGONAL_COST]; sources may be blocked, but paths never
+/// cross other blocked tiles.
+pub struct DijkstraMap {
+    bounds: (i32, i32, i32, i32),
+    costs: Vec<i32>,
+}
+
+impl DijkstraMap {
+    /// Build a DijkstraMap whose sources all start with a cost of zero.
+    ///
+    /// If `max_cost` is given, tiles that would cost more than that to reach are left unreached,
+    /// which saves time when only the area around the sources matters.
+    pub fn new<T, I>(map: &T, sources: I, max_cost: Option<i32>) -> Self
+    where
+        T: BoundedMap + PathableMap,
+        I: IntoIterator<Item = (i32, i32)>,
+    {
+        Self::with_source_costs(map, sources.into_iter().map(|pos| (pos, 0)), max_cost)
+    }
+
+    /// Build a DijkstraMap whose sources start with individual costs, e.g. to make some
+    /// destinations more attractive than others; costs may be negative.
+    pub fn with_source_costs<T, I>(map: &T, sources: I, max_cost: Option<i32>) -> Self
+    where
+        T: BoundedMap + PathableMap,
+        I: IntoIterator<Item = ((i32, i32), i32)>,
+    {
+        let bounds = map.bounds();
+        let width = (bounds.2 - bounds.0 + 1).max(0) as usize;
+        let height = (bounds.3 - bounds.1 + 1).max(0) as usize;
+        let mut dijkstra_map = Self {
+            bounds,
+            costs: vec![i32::MAX; width * height],
+        };
+        // (cost, (x, y))
+        let mut frontier: BinaryHeap<(Reverse<i32>, (i32, i32))> = BinaryHeap::new();
+
+        for (pos, cost) in sources {
+            if let Some(index) = dijkstra_map.index(pos) {
+                if cost < dijkstra_map.costs[index] {
+                    dijkstra_map.costs[index] = cost;
+                    frontier.push((Reverse(cost), pos));
+                }
+            }
+        }
+
+        while let Some((Reverse(current_cost), current)) = frontier.pop() {
+            // Skip stale entries for tiles that have since been reached more cheaply.
+            if current_cost > dijkstra_map.costs[dijkstra_map.index(current).unwrap()] {
+                continue;
+            }
+
+            for (i, (dx, dy)) in ADJACENT_TILES.iter().enumerate() {
+                let next = (current.0 + dx, current.1 + dy);
+                let next_cost = current_cost + if i >= 4 { DIAGONAL_COST } else { CARDINAL_COST };
+
+                if matches!(max_cost, Some(max_cost) if next_cost > max_cost) {
+                    continue;
+                }
+
+                if let Some(index) = dijkstra_map.index(next) {
+                    if next_cost < dijkstra_map.costs[index] && !map.is_blocked(next.0, next.1) {
+                        dijkstra_map.costs[index] = next_cost;
+                        frontier.push((Reverse(next_cost), next));
+                    }
+                }
+            }
+        }
+
+        dijkstra_map
+    }
+
+    fn index(&self, (x, y): (i32, i32)) -> Option<usize> {
+        let (min_x, min_y, max_x, max_y) = self.bounds;
+
+        if x >= min_x && x <= max_x && y >= min_y && y <= max_y {
+            Some(((y - min_y) * (max_x - min_x + 1) + (x - min_x)) as usize)
+        } else {
+            None
+        }
+    }
+
+    /// Cost of the cheapest path from the given position to the nearest source, or `None` if no
+    /// source can be reached from there.
+    pub fn get(&self, pos: (i32, i32)) -> Option<i32> {
+        self.index(pos)
+            .map(|index| self.costs[index])
+            .filter(|&cost| cost != i32::MAX)
+    }
+
+    /// The adjacent position to step to from the given position to get closer to the nearest
+    /// source, or `None` if no step gets any closer.
+    ///
+    /// Cardinal steps are preferred over diagonal steps that are just as good.
+    pub fn downhill(&self, pos: (i32, i32)) -> Option<(i32, i32)> {
+        let mut best = None;
+        let mut best_cost = self.get(pos).unwrap_or(i32::MAX);
+
+        for (dx, dy) in ADJACENT_TILES.iter() {
+            let next = (pos.0 + dx, pos.1 + dy);
+
+            if let Some(next_cost) = self.get(next) {
+                if next_cost < best_cost {
+                    best = Some(next);
+                    best_cost = next_cost;
+                }
+            }
+        }
+
+        best
+    }
+
+    /// Build a DijkstraMap for fleeing from the sources of this one.
+    ///
+    /// Simply stepping away from the sources tends to lead into dead ends, so this instead treats
+    /// every reached tile as a source whose cost is its cost here negated and scaled by
+    /// `percent / 100`.  Stepping downhill in the result heads for the far side of the map,
+    /// doubling back past the sources if it has to; percentages over 100, e.g. 120, favor open
+    /// areas over corners.
+    pub fn fleeing<T: BoundedMap + PathableMap>(&self, map: &T, percent: i32) -> Self {
+        let (min_x, min_y, max_x, _) = self.bounds;
+        let width = max_x - min_x + 1;
+        let sources = self
+            .costs
+            .iter()
+            .enumerate()
+            .filter(|(_, &cost)| cost != i32::MAX)
+            .map(|(i, &cost)| {
+                let pos = (min_x + i as i32 % width, min_y + i as i32 / width);
+
+                (pos, -(cost as i64 * percent as i64 / 100) as i32)
+            });
+
+        Self::with_source_costs(map, sources, None)
+    }
+}
+
 /// Calculate the shortest path from `start` to `dest` using the A* algorithm, returning the
 /// closest point to `dest` that is reachable from `start`, which will be equal to `dest` if a path
 /// was found.
 ///
-/// The path data are stored in `came_from` where the keys are positions and the values are the
-/// position that they came from; this means that the path is stored in reverse.
+/// The path data are stored in `came_from` of `buffers` where the keys are positions and the
+/// values are the position that they came from; this means that the path is stored in reverse.
+///
+/// If `budget` is given, give up after expanding that many positions.
 fn a_star<T: BoundedMap + PathableMap>(
     map: &T,
     start: (i32, i32),
     dest: (i32, i32),
     bound_pad: i32,
-    came_from: &mut HashMap<(i32, i32), (i32, i32)>,
+    budget: Option<u32>,
+    buffers: &mut PathBuffers,
 ) -> (i32, i32) {
-    // (priority, (x, y))
-    let mut frontier: BinaryHeap<(Reverse<i32>, (i32, i32))> = BinaryHeap::new();
-    // ((x, y), cost)
-    let mut cost_so_far: HashMap<(i32, i32), i32> = HashMap::new();
+    let PathBuffers {
+        frontier,
+        cost_so_far,
+        came_from,
+        stats,
+    } = buffers;
+
+    frontier.clear();
+    cost_so_far.clear();
+    came_from.clear();
+    *stats = PathStats::default();
+
     let (min_x, min_y, max_x, max_y) = if bound_pad == 0 {
         map.bounds()
     } else {
@@ -97,8 +293,15 @@ fn a_star<T: BoundedMap + PathableMap>(
 
     frontier.push((Reverse(0), start));
     cost_so_far.insert(start, 0);
+    stats.queued += 1;
 
     while let Some((_, current)) = frontier.pop() {
+        if matches!(budget, Some(budget) if stats.expanded >= budget) {
+            stats.out_of_budget = true;
+            break;
+        }
+        stats.expanded += 1;
+
         let current_cost = *cost_so_far.get(&current).unwrap();
         let current_dist = dist100(current, dest);
 
@@ -128,6 +331,7 @@ fn a_star<T: BoundedMap + PathableMap>(
                         frontier.push((Reverse(next_cost + dist100(next, dest)), next));
                         came_from.insert(next, current);
                         cost_so_far.insert(next, next_cost);
+                        stats.queued += 1;
                     }
                 }
             }
@@ -153,8 +357,33 @@ pub fn find_path<T: BoundedMap + PathableMap>(
     bound_pad: i32,
     fallback_closest: bool,
 ) -> AStarIter {
-    let mut came_from: HashMap<(i32, i32), (i32, i32)> = HashMap::new();
-    let closest = a_star(map, start, dest, bound_pad, &mut came_from);
+    find_path_reusing(
+        map,
+        start,
+        dest,
+        bound_pad,
+        fallback_closest,
+        None,
+        PathBuffers::default(),
+    )
+}
+
+/// Like [find_path], but working in storage left over from an earlier search, which can be taken
+/// back afterwards with [AStarIter::into_buffers].
+///
+/// If `budget` is given, the search gives up after expanding that many positions, treating the
+/// closest point to `dest` found so far as unreachable or as the fallback as with [find_path].
+pub fn find_path_reusing<T: BoundedMap + PathableMap>(
+    map: &T,
+    start: (i32, i32),
+    dest: (i32, i32),
+    bound_pad: i32,
+    fallback_closest: bool,
+    budget: Option<u32>,
+    mut buffers: PathBuffers,
+) -> AStarIter {
+    let closest = a_star(map, start, dest, bound_pad, budget, &mut buffers);
+    let came_from = &mut buffers.came_from;
 
     if closest == dest || fallback_closest {
         // Reverse the path from closest to start.
@@ -172,15 +401,158 @@ pub fn find_path<T: BoundedMap + PathableMap>(
         }
 
         AStarIter {
-            came_from,
+            buffers,
             current_pos: Some(start),
             fallback: true,
         }
     } else {
         AStarIter {
-            came_from,
+            buffers,
             current_pos: None,
             fallback: false,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A small walled map where '#' is blocked; getting from one side of the bottom row to the
+    /// other means going around the top.
+    struct TestMap(&'static [&'static str]);
+
+    const ROWS: [&str; 5] = [
+        "#########",
+        "#.......#",
+        "#.#####.#",
+        "#...#...#",
+        "#########",
+    ];
+
+    impl BoundedMap for TestMap {
+        fn bounds(&self) -> (i32, i32, i32, i32) {
+            (0, 0, self.0[0].len() as i32 - 1, self.0.len() as i32 - 1)
+        }
+    }
+
+    impl PathableMap for TestMap {
+        fn is_blocked(&self, x: i32, y: i32) -> bool {
+            self.0[y as usize].as_bytes()[x as usize] == b'#'
+        }
+    }
+
+    #[test]
+    fn budget_too_small_finds_no_path() {
+        let map = TestMap(&ROWS);
+        let path = find_path_reusing(
+            &map,
+            (1, 3),
+            (7, 3),
+            0,
+            false,
+            Some(3),
+            PathBuffers::default(),
+        );
+        let stats = path.stats();
+
+        assert!(stats.out_of_budget);
+        assert_eq!(stats.expanded, 3);
+        assert_eq!(path.count(), 0);
+    }
+
+    #[test]
+    fn budgeted_and_reused_searches_match_find_path() {
+        let map = TestMap(&ROWS);
+        let mut buffers = PathBuffers::default();
+
+        for &(start, dest) in [((1, 3), (7, 3)), ((3, 3), (5, 3)), ((7, 1), (1, 3))].iter() {
+            let expected = find_path(&map, start, dest, 0, false).collect::<Vec<_>>();
+            let mut path = find_path_reusing(&map, start, dest, 0, false, Some(1000), buffers);
+            let stats = path.stats();
+
+            assert!(!stats.out_of_budget);
+            assert!(stats.expanded > 0 && stats.queued >= stats.expanded);
+            assert_eq!(expected.first(), Some(&start));
+            assert_eq!(expected.last(), Some(&dest));
+            assert_eq!((&mut path).collect::<Vec<_>>(), expected);
+            buffers = path.into_buffers();
+        }
+    }
+
+    #[test]
+    fn dijkstra_map_costs_from_multiple_sources() {
+        let map = TestMap(&ROWS);
+        let dijkstra_map = DijkstraMap::new(&map, vec![(1, 3), (7, 3)], None);
+        // Costs of tiles inside the walls of each row, with -1 for blocked tiles, meeting in the
+        // middle of the top row.
+        let expected = [
+            (1, vec![200, 241, 341, 441, 341, 241, 200]),
+            (2, vec![100, -1, -1, -1, -1, -1, 100]),
+            (3, vec![0, 100, 200, -1, 200, 100, 0]),
+        ];
+
+        for (y, costs) in expected.iter() {
+            for (x, &cost) in (1..).zip(costs.iter()) {
+                let cost = Some(cost).filter(|&c| c >= 0);
+
+                assert_eq!(dijkstra_map.get((x, *y)), cost, "at ({}, {})", x, y);
+            }
+        }
+        assert_eq!(dijkstra_map.get((0, 0)), None);
+        assert_eq!(dijkstra_map.get((-1, 3)), None);
+
+        // Going no further than the cost limit.
+        let limited = DijkstraMap::new(&map, vec![(1, 3)], Some(250));
+
+        assert_eq!(limited.get((2, 1)), Some(241));
+        assert_eq!(limited.get((3, 1)), None);
+        assert_eq!(limited.get((7, 3)), None);
+    }
+
+    #[test]
+    fn dijkstra_map_downhill_steps() {
+        let map = TestMap(&ROWS);
+        let dijkstra_map = DijkstraMap::new(&map, vec![(1, 3), (7, 3)], None);
+
+        // Steps head for whichever source is nearest, cutting corners diagonally where they can.
+        assert_eq!(dijkstra_map.downhill((2, 1)), Some((1, 2)));
+        assert_eq!(dijkstra_map.downhill((1, 1)), Some((1, 2)));
+        assert_eq!(dijkstra_map.downhill((4, 1)), Some((3, 1)));
+        assert_eq!(dijkstra_map.downhill((3, 3)), Some((2, 3)));
+        assert_eq!(dijkstra_map.downhill((1, 3)), None);
+        assert_eq!(dijkstra_map.downhill((7, 3)), None);
+    }
+
+    #[test]
+    fn dijkstra_map_fleeing_steps() {
+        let map = TestMap(&ROWS);
+        let dijkstra_map = DijkstraMap::new(&map, vec![(1, 3)], None);
+        let fleeing = dijkstra_map.fleeing(&map, 100);
+        let mut pos = (1, 2);
+        let mut steps = Vec::new();
+
+        // Fleeing from a single source goes all the way around to the far end of the bottom row.
+        while let Some(next) = fleeing.downhill(pos) {
+            assert!(dijkstra_map.get(next) > dijkstra_map.get(pos));
+            steps.push(next);
+            pos = next;
+        }
+
+        assert_eq!(
+            steps,
+            vec![
+                (2, 1),
+                (3, 1),
+                (4, 1),
+                (5, 1),
+                (6, 1),
+                (7, 2),
+                (6, 3),
+                (5, 3),
+            ],
+        );
+        assert_eq!(dijkstra_map.get(pos), Some(1023));
+        assert_eq!(fleeing.get(pos), Some(-1023));
+    }
+}
@@ -1,13 +1,22 @@
 use sdl2::{
     event::{Event, WindowEvent},
-    pixels::Color as Sdl2Color,
+    image::SaveSurface,
+    pixels::{Color as Sdl2Color, PixelFormatEnum},
     rect::Rect,
+    render::{Texture, TextureCreator, WindowCanvas},
+    surface::Surface,
+    video::WindowContext,
+    EventPump,
+};
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
 };
-use std::time::{Duration, Instant};
 
 use crate::{
+    error::Error,
     input_buffer::InputBuffer,
-    tilegrid::{Symbol, TileGridLayer, Tileset, TilesetInfo},
+    tilegrid::{Symbol, SymbolRegistry, TileGrid, TileGridLayer, Tileset, TilesetInfo},
     util::Size,
 };
 
@@ -15,10 +24,92 @@ use crate::{
 pub enum RunControl {
     /// Wait for an event before calling `update` again.
     WaitForEvent,
+    /// Wait for an event or for the given duration to pass, whichever comes first, before calling
+    /// `update` again.  Useful for coarse animations that don't need every frame.
+    WaitForEventTimeout(Duration),
     /// Call `update` again next frame.
     Update,
     /// Quit the run loop.
     Quit,
+    /// Quit the run loop because of an error that couldn't be dealt with any other way, which
+    /// [run] then returns.
+    Error(Error),
+}
+
+/// How TileGrids get their tiles onto the screen.
+#[derive(Clone, Copy, Eq, PartialEq)]
+pub enum RenderPath {
+    /// Blit tiles onto surfaces in software, then upload them to textures.
+    Software,
+    /// Copy tiles from tileset textures onto render target textures on the GPU, which is faster
+    /// for large windows and high zoom levels.  Falls back to software rendering if render
+    /// targets aren't supported.
+    Gpu,
+}
+
+/// How the main loop of [run] passes the time between frames.
+#[derive(Clone, Copy, Eq, PartialEq)]
+pub enum FramePacing {
+    /// Sleep until the next frame is due, handling any events that arrive in the meantime then.
+    Fixed,
+    /// Wait for events until the next frame is due, waking up to handle them as soon as they
+    /// arrive instead of sleeping through them.
+    Adaptive,
+}
+
+/// How long the parts of a frame took, handed to the `update` callback of [run] for the most
+/// recently displayed frame.
+///
+/// Time spent waiting for events or sleeping between frames isn't counted.
+#[derive(Clone, Copy, Default)]
+pub struct FrameTiming {
+    /// Time taken by the whole frame, i.e. updating and displaying.
+    pub frame_time: Duration,
+    /// Time taken by calls to `update`.
+    pub update_time: Duration,
+    /// Time taken to display the TileGrids and present them on screen.
+    pub display_time: Duration,
+    /// Number of TileGrids whose textures had to be updated to be displayed.
+    pub texture_uploads: u32,
+}
+
+/// Images that the `update` callback of [run] asks to be saved, and how earlier requests went.
+pub struct FrameRequests<'b, 'r, Y: Symbol> {
+    /// Save the contents of the window as a PNG image at this path once the frame is drawn.
+    pub screenshot: Option<PathBuf>,
+    /// Render each TileGrid in full and save it as a PNG image at its path; the grids don't need
+    /// to be shown on screen.
+    pub grid_images: Vec<(TileGrid<'b, 'r, Y>, PathBuf)>,
+    /// Paths of images saved since the last update, each with an error message if saving failed.
+    ///
+    /// The `update` callback should take these out to report them.
+    pub saved_images: Vec<(PathBuf, Result<(), String>)>,
+}
+
+impl<Y: Symbol> FrameRequests<'_, '_, Y> {
+    fn new() -> Self {
+        Self {
+            screenshot: None,
+            grid_images: Vec::new(),
+            saved_images: Vec::new(),
+        }
+    }
+}
+
+/// Read the pixels drawn onto the canvas so far and save them as a PNG image.
+fn save_screenshot(canvas: &WindowCanvas, path: &Path) -> Result<(), String> {
+    let (w, h) = canvas.output_size()?;
+    let format = PixelFormatEnum::ARGB8888;
+    let mut pixels = canvas.read_pixels(None, format)?;
+    let surface = Surface::from_data(
+        &mut pixels,
+        w,
+        h,
+        w * format.byte_size_per_pixel() as u32,
+        format,
+    )?;
+
+    surface.save(path)
 }
 
 /// Window and event loop settings for [run].
@@ -31,21 +122,77 @@ pub struct RunSettings<Y: Symbol> {
     pub min_window_size: Size,
     /// Frames per second.
     pub fps: u32,
+    /// Frames per second while the window is minimized or doesn't have focus.
+    pub background_fps: u32,
     /// Tilesets to draw TileGrids with.
     pub tileset_infos: Vec<TilesetInfo<Y>>,
+    /// Dynamic symbols to resolve in the dynamic symbol maps of the tilesets.
+    pub symbol_registry: SymbolRegistry,
+    /// How TileGrids are rendered.
+    pub render_path: RenderPath,
+    /// Synchronize presenting frames with the refresh rate of the display to avoid tearing.
+    pub vsync: bool,
+    /// How to pass the time between frames.
+    pub frame_pacing: FramePacing,
+}
+
+/// Pass the time remaining until the next frame is due.
+///
+/// Returns an event that arrived early with adaptive pacing, which should be handled first next
+/// frame.
+fn wait_for_next_frame(
+    frame_pacing: FramePacing,
+    remaining: Duration,
+    event_pump: &mut EventPump,
+) -> Option<Event> {
+    match frame_pacing {
+        FramePacing::Fixed => {
+            std::thread::sleep(remaining);
+            None
+        }
+        FramePacing::Adaptive => {
+            let remaining_ms = remaining.as_millis().min(u32::MAX as u128) as u32;
+            event_pump.wait_event_timeout(remaining_ms)
+        }
+    }
+}
+
+/// Create the atlas textures of the tilesets for rendering on the GPU.
+fn create_atlases<'r, Y: Symbol>(
+    tilesets: &[Tileset<Y>],
+    texture_creator: &'r TextureCreator<WindowContext>,
+) -> Vec<Texture<'r>> {
+    tilesets
+        .iter()
+        .map(|t| t.create_atlas(texture_creator))
+        .collect()
 }
 
 /// Create a window and run a main event loop that calls `update` repeatedly.
 ///
-/// `update` should return a [RunControl] enum variant to control the loop behavior.
-pub fn run<U, Y>(settings: RunSettings<Y>, mut update: U)
+/// `update` should return a [RunControl] enum variant to control the loop behavior.  It can also
+/// ask for screenshots and images of TileGrids to be saved through [FrameRequests].
+///
+/// Returns an error if the window couldn't be set up, or if `update` returned one through
+/// [RunControl::Error].
+pub fn run<U, Y>(settings: RunSettings<Y>, mut update: U) -> Result<(), Error>
 where
-    U: FnMut(&mut InputBuffer, &mut Vec<TileGridLayer<Y>>, &[Tileset<Y>], Size) -> RunControl,
+    U: FnMut(
+        &mut InputBuffer,
+        &mut Vec<TileGridLayer<Y>>,
+        &[Tileset<Y>],
+        Size,
+        &FrameTiming,
+        &mut FrameRequests<Y>,
+    ) -> RunControl,
     Y: Symbol,
 {
-    let sdl_context = sdl2::init().unwrap();
-    let video_subsystem = sdl_context.video().unwrap();
-    let _image_context = sdl2::image::init(sdl2::image::InitFlag::PNG).unwrap();
+    let sdl_context = sdl2::init().map_err(|e| Error::new("Failed to initialize SDL", e))?;
+    let video_subsystem = sdl_context
+        .video()
+        .map_err(|e| Error::new("Failed to initialize video", e))?;
+    let _image_context = sdl2::image::init(sdl2::image::InitFlag::PNG)
+        .map_err(|e| Error::new("Failed to initialize SDL_image", e))?;
 
     assert!(settings.window_size.w > 0 && settings.window_size.w <= i32::MAX as u32);
     assert!(settings.window_size.h > 0 && settings.window_size.h <= i32::MAX as u32);
@@ -63,37 +210,67 @@ where
         .resizable()
         .position_centered()
         .build()
-        .unwrap();
+        .map_err(|e| Error::new("Failed to create window", e))?;
 
     window
         .set_minimum_size(settings.window_size.w, settings.window_size.h)
         .unwrap();
 
-    let mut canvas = window.into_canvas().build().unwrap();
+    let mut canvas_builder = window.into_canvas();
+    if settings.vsync {
+        canvas_builder = canvas_builder.present_vsync();
+    }
+    let mut canvas = canvas_builder
+        .build()
+        .map_err(|e| Error::new("Failed to create renderer", e))?;
     let texture_creator = canvas.texture_creator();
-    let mut event_pump = sdl_context.event_pump().unwrap();
+    let mut event_pump = sdl_context
+        .event_pump()
+        .map_err(|e| Error::new("Failed to get event pump", e))?;
 
     assert!(!settings.tileset_infos.is_empty());
 
     let mut tilesets = Vec::with_capacity(settings.tileset_infos.len());
     for tileset_info in settings.tileset_infos {
-        tilesets.push(Tileset::new(tileset_info));
+        tilesets.push(Tileset::new(tileset_info, &settings.symbol_registry));
     }
 
+    let gpu_render = match settings.render_path {
+        RenderPath::Gpu => canvas.render_target_supported(),
+        RenderPath::Software => false,
+    };
+    let mut atlases = if gpu_render {
+        create_atlases(&tilesets, &texture_creator)
+    } else {
+        Vec::new()
+    };
+
     let mut window_size = canvas.output_size().unwrap();
     let mut window_rect = Rect::new(0, 0, window_size.0, window_size.1);
     let mut layers: Vec<TileGridLayer<Y>> = Vec::new();
     let mut inputs = InputBuffer::new();
+    let mut requests = FrameRequests::new();
 
     let mut mouse_shown = true;
+    let mut minimized = false;
+    let mut focused = true;
     let mut active_update = true;
+    let mut wait_timeout: Option<Duration> = None;
     let mut done = false;
 
     assert!(settings.fps > 0);
+    assert!(settings.background_fps > 0);
 
-    let frame_time = Duration::new(0, 1_000_000_000u32 / settings.fps);
+    let foreground_frame_time = Duration::new(0, 1_000_000_000u32 / settings.fps);
+    let background_frame_time = Duration::new(0, 1_000_000_000u32 / settings.background_fps);
+    let mut frame_time = foreground_frame_time;
     let mut previous = Instant::now();
     let mut lag = frame_time; // Update once to start with.
+    let mut timing = FrameTiming::default();
+    let mut early_event: Option<Event> = None;
+    let mut redraw = true;
+    // Draw behind flag and grid count of each layer when the last frame was drawn.
+    let mut drawn_layout: Vec<(bool, usize)> = Vec::new();
 
     #[cfg(feature = "fps")]
     let mut update_count = 0;
@@ -105,13 +282,27 @@ where
     while !done {
         let mut new_mouse_shown = None;
 
-        // Wait for an event if waiting is requested.
-        let waited_event = if !active_update && !inputs.more_inputs() {
-            Some(event_pump.wait_event())
+        // Wait for an event (or a timeout) if waiting is requested.
+        let idle_wait = !active_update && !inputs.more_inputs();
+        let mut timed_out = false;
+        let waited_event = if early_event.is_some() {
+            early_event.take()
+        } else if idle_wait {
+            if let Some(timeout) = wait_timeout {
+                let timeout_ms = timeout.as_millis().min(u32::MAX as u128) as u32;
+                let event = event_pump.wait_event_timeout(timeout_ms);
+                timed_out = event.is_none();
+                event
+            } else {
+                Some(event_pump.wait_event())
+            }
         } else {
             None
         };
 
+        // While idle, only update and redraw for events that could change what's shown.
+        let mut wake = !idle_wait || timed_out;
+
         // Poll for additional events and handle all events.
         for event in waited_event.into_iter().chain(event_pump.poll_iter()) {
             match event {
@@ -120,13 +311,50 @@ where
                     ..
                 } => {
                     window_size = (w as u32, h as u32);
+                    wake = true;
+                }
+                Event::Window {
+                    win_event: WindowEvent::Minimized,
+                    ..
+                } => minimized = true,
+                Event::Window {
+                    win_event: WindowEvent::Restored | WindowEvent::Maximized,
+                    ..
+                } => {
+                    wake = true;
+                    if minimized {
+                        minimized = false;
+
+                        // Some drivers lose texture contents while minimized.
+                        for layer in layers.iter_mut() {
+                            for grid in &mut layer.grids {
+                                grid.flag_texture_reset();
+                            }
+                        }
+                    }
+                }
+                Event::Window {
+                    win_event: WindowEvent::FocusGained,
+                    ..
+                } => {
+                    focused = true;
+                    wake = true;
+                }
+                Event::Window {
+                    win_event: WindowEvent::FocusLost,
+                    ..
+                } => {
+                    focused = false;
+                    wake = true;
                 }
+                Event::Window { .. } => wake = true,
                 Event::KeyDown { .. } | Event::KeyUp { .. } => new_mouse_shown = Some(false),
                 Event::MouseMotion { .. }
                 | Event::MouseButtonDown { .. }
                 | Event::MouseButtonUp { .. }
                 | Event::MouseWheel { .. } => new_mouse_shown = Some(true),
                 Event::RenderTargetsReset { .. } => {
+                    wake = true;
                     for layer in layers.iter_mut() {
                         for grid in &mut layer.grids {
                             grid.flag_texture_reset();
@@ -134,6 +362,10 @@ where
                     }
                 }
                 Event::RenderDeviceReset { .. } => {
+                    wake = true;
+                    if gpu_render {
+                        atlases = create_atlases(&tilesets, &texture_creator);
+                    }
                     for layer in layers.iter_mut() {
                         for grid in &mut layer.grids {
                             grid.flag_texture_recreate();
@@ -143,9 +375,23 @@ where
                 _ => {}
             }
 
+            // The window contents may need to be drawn again after any change to the window.
+            if matches!(
+                event,
+                Event::Window { .. }
+                    | Event::RenderTargetsReset { .. }
+                    | Event::RenderDeviceReset { .. }
+            ) {
+                redraw = true;
+            }
+
             inputs.handle_event(&event);
         }
 
+        if inputs.more_inputs() {
+            wake = true;
+        }
+
         // Show or hide mouse cursor based on keyboard and mouse input.
         if let Some(new_mouse_shown) = new_mouse_shown {
             if mouse_shown != new_mouse_shown {
@@ -154,6 +400,18 @@ where
             }
         }
 
+        // Go back to waiting if nothing relevant happened, e.g. mouse motion.
+        if !wake {
+            continue;
+        }
+
+        // Slow down animations and updates while in the background.
+        frame_time = if minimized || !focused {
+            background_frame_time
+        } else {
+            foreground_frame_time
+        };
+
         // Guarantee minimum window dimensions, even if we have to fake it.
         if window_size.0 < settings.min_window_size.w {
             window_size.0 = settings.min_window_size.w;
@@ -164,6 +422,7 @@ where
 
         // Perform update(s).
         let start = previous;
+        let update_start = Instant::now();
         if active_update {
             let mut update_limit = 10;
             let current = Instant::now();
@@ -177,16 +436,30 @@ where
                     update_count += 1;
                 }
 
-                match update(&mut inputs, &mut layers, &tilesets[..], window_size.into()) {
+                match update(
+                    &mut inputs,
+                    &mut layers,
+                    &tilesets[..],
+                    window_size.into(),
+                    &timing,
+                    &mut requests,
+                ) {
                     RunControl::Update => lag -= frame_time,
                     RunControl::WaitForEvent => {
                         active_update = false;
+                        wait_timeout = None;
+                        lag = Duration::new(0, 0);
+                    }
+                    RunControl::WaitForEventTimeout(timeout) => {
+                        active_update = false;
+                        wait_timeout = Some(timeout);
                         lag = Duration::new(0, 0);
                     }
                     RunControl::Quit => {
                         done = true;
                         lag = Duration::new(0, 0);
                     }
+                    RunControl::Error(e) => return Err(e),
                 }
 
                 // Avoid doing too much catch-up at once.
@@ -204,13 +477,22 @@ where
             }
 
             // Update once in response to events.
-            match update(&mut inputs, &mut layers, &tilesets[..], window_size.into()) {
-                RunControl::WaitForEvent => {}
+            match update(
+                &mut inputs,
+                &mut layers,
+                &tilesets[..],
+                window_size.into(),
+                &timing,
+                &mut requests,
+            ) {
+                RunControl::WaitForEvent => wait_timeout = None,
+                RunControl::WaitForEventTimeout(timeout) => wait_timeout = Some(timeout),
                 RunControl::Update => {
                     active_update = true;
                     lag = frame_time;
                 }
                 RunControl::Quit => done = true,
+                RunControl::Error(e) => return Err(e),
             }
         }
 
@@ -219,31 +501,107 @@ where
             break;
         }
 
-        window_rect.set_width(window_size.0);
-        window_rect.set_height(window_size.1);
-        canvas.set_clip_rect(window_rect);
-        canvas.set_draw_color(Sdl2Color::BLACK);
-        canvas.clear();
+        for (mut grid, path) in requests.grid_images.drain(..) {
+            let result = grid.save_png(&mut tilesets[..], &path);
+            requests.saved_images.push((path, result));
+        }
+
+        // Screenshots need the whole window drawn, and can't be taken while it's minimized.
+        if requests.screenshot.is_some() {
+            if minimized {
+                let path = requests.screenshot.take().unwrap();
+                requests
+                    .saved_images
+                    .push((path, Err("window is minimized".to_string())));
+            } else {
+                redraw = true;
+            }
+        }
+
+        // Nothing can be seen while minimized, so don't bother rendering.
+        if minimized {
+            inputs.clear_input();
+
+            let elapsed = Instant::now().duration_since(start);
+            if elapsed < frame_time {
+                early_event = wait_for_next_frame(
+                    settings.frame_pacing,
+                    frame_time - elapsed,
+                    &mut event_pump,
+                );
+            }
+            continue;
+        }
 
-        // Display the grids, starting from the lowest visible layer.
+        let display_start = Instant::now();
+        let update_time = display_start.duration_since(update_start);
+        let mut texture_uploads = 0;
         let start_layer_draw_from = layers.iter().rposition(|l| !l.draw_behind).unwrap_or(0);
+        let layout: Vec<(bool, usize)> = layers
+            .iter()
+            .map(|l| (l.draw_behind, l.grids.len()))
+            .collect();
+
+        // Only draw the frame if it would look any different from the last one, which is still on
+        // screen otherwise.
+        if redraw
+            || layout != drawn_layout
+            || layers[start_layer_draw_from..]
+                .iter()
+                .any(|l| l.grids.iter().any(|g| g.needs_display()))
+        {
+            window_rect.set_width(window_size.0);
+            window_rect.set_height(window_size.1);
+            canvas.set_clip_rect(window_rect);
+            canvas.set_draw_color(Sdl2Color::BLACK);
+            canvas.clear();
+
+            // Display the grids, starting from the lowest visible layer.
+            for layer in &mut layers[start_layer_draw_from..] {
+                for grid in &mut layer.grids {
+                    if grid.display(
+                        &mut tilesets[..],
+                        if gpu_render {
+                            Some(&mut atlases[..])
+                        } else {
+                            None
+                        },
+                        &mut canvas,
+                        &texture_creator,
+                    ) {
+                        texture_uploads += 1;
+                    }
+                }
+            }
 
-        for layer in &mut layers[start_layer_draw_from..] {
-            for grid in &mut layer.grids {
-                grid.display(&mut tilesets[..], &mut canvas, &texture_creator);
+            // The canvas can only be read reliably before it's presented.
+            if let Some(path) = requests.screenshot.take() {
+                let result = save_screenshot(&canvas, &path);
+                requests.saved_images.push((path, result));
+            }
+
+            canvas.present();
+
+            redraw = false;
+            drawn_layout = layout;
+
+            #[cfg(feature = "fps")]
+            {
+                frame_count += 1;
             }
         }
 
-        canvas.present();
+        let display_time = Instant::now().duration_since(display_start);
+        timing = FrameTiming {
+            frame_time: update_time + display_time,
+            update_time,
+            display_time,
+            texture_uploads,
+        };
 
         // Discard any current input to make way for the next one.
         inputs.clear_input();
 
-        #[cfg(feature = "fps")]
-        {
-            frame_count += 1;
-        }
-
         // Show updates and frames per second.
         #[cfg(feature = "fps")]
         if Instant::now().duration_since(last_fps_print) >= Duration::new(1, 0) {
@@ -258,10 +616,13 @@ where
             frame_count = 0;
         }
 
-        // Sleep until the next frame is due.
+        // Wait until the next frame is due.
         let elapsed = Instant::now().duration_since(start);
         if elapsed < frame_time {
-            std::thread::sleep(frame_time - elapsed);
+            early_event =
+                wait_for_next_frame(settings.frame_pacing, frame_time - elapsed, &mut event_pump);
         }
     }
+
+    Ok(())
 }
@@ -1,14 +1,21 @@
 use sdl2::{
-    image::LoadSurface,
+    image::{LoadSurface, SaveSurface},
     pixels::{Color as Sdl2Color, PixelFormatEnum},
     rect::Rect,
     render::{BlendMode, Texture, TextureCreator, WindowCanvas},
     surface::Surface,
     video::WindowContext,
 };
-use std::{collections::HashMap, hash::Hash, path::PathBuf};
+use std::{
+    collections::HashMap,
+    hash::Hash,
+    path::{Path, PathBuf},
+};
 
-use crate::util::{Color, Position, Size};
+use crate::{
+    layout::{Anchor, Area},
+    util::{self, Color, Position, Size},
+};
 
 const U32_SIZE: usize = std::mem::size_of::<u32>();
 
@@ -18,6 +25,73 @@ pub type TileIndex = (i32, i32);
 /// Bundle of traits needed for a type to be stored as part of a cell of a [TileGrid].
 pub trait Symbol: Copy + Clone + Eq + PartialEq + Hash {
     fn text_fallback(self) -> char;
+
+    /// The dynamic symbol that this symbol stands for, if any.
+    ///
+    /// Symbols that return a [DynSym] here are drawn as that dynamic symbol instead.
+    fn dyn_sym(self) -> Option<DynSym> {
+        None
+    }
+}
+
+/// A symbol registered by name at run time in a [SymbolRegistry].
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct DynSym(u32);
+
+impl DynSym {
+    /// Numeric ID of the dynamic symbol, in registration order.
+    pub fn id(self) -> u32 {
+        self.0
+    }
+
+    /// Dynamic symbol from a numeric ID returned by [DynSym::id].
+    pub fn from_id(id: u32) -> Self {
+        Self(id)
+    }
+}
+
+/// Named dynamic symbols, each with a character to fall back on for tilesets that lack a tile for
+/// it.
+#[derive(Clone, Default)]
+pub struct SymbolRegistry {
+    ids: HashMap<String, DynSym>,
+    names: Vec<String>,
+    fallbacks: Vec<char>,
+}
+
+impl SymbolRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a named symbol, returning the existing one if the name is already registered.
+    pub fn register(&mut self, name: &str, fallback: char) -> DynSym {
+        if let Some(dsym) = self.ids.get(name) {
+            *dsym
+        } else {
+            let dsym = DynSym(self.fallbacks.len() as u32);
+
+            self.ids.insert(name.to_string(), dsym);
+            self.names.push(name.to_string());
+            self.fallbacks.push(fallback);
+            dsym
+        }
+    }
+
+    /// Look up a named symbol.
+    pub fn get(&self, name: &str) -> Option<DynSym> {
+        self.ids.get(name).copied()
+    }
+
+    /// The name that a dynamic symbol was registered under.
+    pub fn name(&self, dsym: DynSym) -> Option<&str> {
+        self.names.get(dsym.0 as usize).map(String::as_str)
+    }
+
+    /// The fallback character of a dynamic symbol.
+    pub fn fallback(&self, dsym: DynSym) -> char {
+        self.fallbacks.get(dsym.0 as usize).copied().unwrap_or('?')
+    }
 }
 
 /// Data describing a tileset that can be loaded from an image on a file system.
@@ -34,6 +108,8 @@ pub struct TilesetInfo<Y: Symbol> {
     pub font_map: HashMap<char, TileIndex>,
     /// Map of symbols to tile positions in the tile image.
     pub symbol_map: HashMap<Y, TileIndex>,
+    /// Map of names of dynamic symbols to tile positions in the tile image.
+    pub dyn_symbol_map: HashMap<String, TileIndex>,
 }
 
 impl<Y: Symbol> TilesetInfo<Y> {
@@ -69,6 +145,25 @@ impl<Y: Symbol> TilesetInfo<Y> {
 enum CellSym<Y: Symbol> {
     Char(char),
     Sym(Y),
+    Dyn(DynSym),
+    /// Right half of a double-width character whose left half is in the cell to the left.
+    WideTail(char),
+}
+
+impl<Y: Symbol> CellSym<Y> {
+    #[inline]
+    fn from_sym(sym: Y) -> Self {
+        match sym.dyn_sym() {
+            Some(dsym) => CellSym::<Y>::Dyn(dsym),
+            None => CellSym::<Y>::Sym(sym),
+        }
+    }
+
+    /// True for the left half of a double-width character.
+    #[inline]
+    fn is_wide(&self) -> bool {
+        matches!(*self, CellSym::<Y>::Char(c) if util::char_width(c) == 2)
+    }
 }
 
 /// A set of symbols mapped to positions in a tile image.
@@ -78,6 +173,7 @@ pub struct Tileset<'s, Y: Symbol> {
     surface: Surface<'s>,
     tile_size: Size,
     cellsym_map: HashMap<CellSym<Y>, Option<i32>>,
+    dyn_fallbacks: Vec<char>,
 }
 
 impl<'s, Y: Symbol> Tileset<'s, Y> {
@@ -198,11 +294,14 @@ impl<'s, Y: Symbol> Tileset<'s, Y> {
     /// Create a new tileset.  An [sdl2::image::Sdl2ImageContext] must be active at the time that
     /// this is called in order to load the tile image.
     ///
+    /// Names in the dynamic symbol map are resolved against `registry`; unregistered names are
+    /// ignored.
+    ///
     /// # Panics
     ///
     /// Panics if no tiles are mapped, the tile image cannot be loaded, or if any entry of the font
     /// map lies outside the tile image bounds.
-    pub fn new(tileset_info: TilesetInfo<Y>) -> Self {
+    pub fn new(tileset_info: TilesetInfo<Y>, registry: &SymbolRegistry) -> Self {
         assert!(
             !tileset_info.font_map.is_empty() || !tileset_info.symbol_map.is_empty(),
             "at least one tile must be mapped"
@@ -238,6 +337,17 @@ impl<'s, Y: Symbol> Tileset<'s, Y> {
             },
         );
 
+        Self::validate_tile_indexes(
+            tileset_info.dyn_symbol_map.values().copied(),
+            tileset_info.tile_size,
+            tileset_info.tile_start,
+            tileset_info.tile_gap,
+            Size {
+                w: image.width(),
+                h: image.height(),
+            },
+        );
+
         // Create a mapping from TileIndex to y positions.
         let mut tile_index_to_pos: HashMap<TileIndex, i32> = HashMap::new();
 
@@ -253,6 +363,12 @@ impl<'s, Y: Symbol> Tileset<'s, Y> {
             tile_h,
         );
 
+        Self::add_tile_index_to_pos_mappings(
+            &mut tile_index_to_pos,
+            tileset_info.dyn_symbol_map.values().copied(),
+            tile_h,
+        );
+
         let mut cellsym_map: HashMap<CellSym<Y>, Option<i32>> = HashMap::new();
 
         // Remap font map by y position instead of TileIndex.
@@ -271,6 +387,16 @@ impl<'s, Y: Symbol> Tileset<'s, Y> {
             );
         }
 
+        // Resolve dynamic symbol names and remap them by y position.
+        for (name, tile_index) in tileset_info.dyn_symbol_map {
+            if let Some(dsym) = registry.get(&name) {
+                cellsym_map.insert(
+                    CellSym::<Y>::Dyn(dsym),
+                    tile_index_to_pos.get(&tile_index).copied(),
+                );
+            }
+        }
+
         // Create a one-tile-wide surface to transfer tiles from the image onto.
         let mut surface = Surface::new(
             tile_w,
@@ -294,6 +420,7 @@ impl<'s, Y: Symbol> Tileset<'s, Y> {
             surface,
             tile_size: tileset_info.tile_size,
             cellsym_map,
+            dyn_fallbacks: registry.fallbacks.clone(),
         }
     }
 
@@ -307,8 +434,29 @@ impl<'s, Y: Symbol> Tileset<'s, Y> {
         self.tile_size.h
     }
 
-    /// Draw a tileset tile onto `dest` at `rect` with a given `color`.
-    fn draw_tile_to(&mut self, csym: CellSym<Y>, color: Color, dest: &mut Surface, rect: Rect) {
+    /// Create a texture of the tileset tiles to render TileGrids with on the GPU.
+    pub(crate) fn create_atlas<'r>(
+        &self,
+        texture_creator: &'r TextureCreator<WindowContext>,
+    ) -> Texture<'r> {
+        let mut atlas = texture_creator
+            .create_texture_from_surface(&self.surface)
+            .unwrap();
+
+        atlas.set_blend_mode(BlendMode::Blend);
+        atlas
+    }
+
+    /// Area of the tileset surface holding the tile for a cell symbol, if it has one.
+    ///
+    /// Each half of a double-width character gets its half of the tile.
+    fn tile_rect(&mut self, csym: CellSym<Y>) -> Option<Rect> {
+        let half_w = self.tile_size.w / 2;
+        let (csym, src_x, src_w) = match csym {
+            CellSym::<Y>::WideTail(c) => (CellSym::<Y>::Char(c), half_w, self.tile_size.w - half_w),
+            _ if csym.is_wide() => (csym, 0, half_w),
+            _ => (csym, 0, self.tile_size.w),
+        };
         let maybe_y: Option<i32> = match self.cellsym_map.get(&csym) {
             Some(&maybe_y) => maybe_y,
             None => match csym {
@@ -319,16 +467,38 @@ impl<'s, Y: Symbol> Tileset<'s, Y> {
                     self.cellsym_map.insert(csym, fallback_y);
                     fallback_y
                 }
-                CellSym::<Y>::Char(_) => None,
+                CellSym::<Y>::Dyn(dsym) => {
+                    // Cache fallback mapping result.
+                    let fallback_ch = CellSym::<Y>::Char(
+                        self.dyn_fallbacks
+                            .get(dsym.0 as usize)
+                            .copied()
+                            .unwrap_or('?'),
+                    );
+                    let fallback_y = self.cellsym_map.get(&fallback_ch).copied().unwrap_or(None);
+                    self.cellsym_map.insert(csym, fallback_y);
+                    fallback_y
+                }
+                CellSym::<Y>::Char(_) | CellSym::<Y>::WideTail(_) => None,
             },
         };
 
-        if let Some(y) = maybe_y {
+        maybe_y.map(|y| Rect::new(src_x as i32, y, src_w, self.tile_size.h))
+    }
+
+    /// Draw a tileset tile onto `dest` at `rect` with a given `color`.
+    ///
+    /// Each half of a double-width character draws its half of the tile stretched to fill `rect`.
+    fn draw_tile_to(&mut self, csym: CellSym<Y>, color: Color, dest: &mut Surface, rect: Rect) {
+        if let Some(tile_rect) = self.tile_rect(csym) {
             let color = Sdl2Color::RGB(color.r, color.g, color.b);
-            let tile_rect = Rect::new(0, y, self.tile_size.w, self.tile_size.h);
 
             self.surface.set_color_mod(color);
-            self.surface.blit(tile_rect, dest, rect).unwrap();
+            if tile_rect.width() == self.tile_size.w {
+                self.surface.blit(tile_rect, dest, rect).unwrap();
+            } else {
+                self.surface.blit_scaled(tile_rect, dest, rect).unwrap();
+            }
         }
     }
 }
@@ -338,6 +508,8 @@ struct Cell<Y: Symbol> {
     csym: CellSym<Y>,
     fg: Color,
     bg: Color,
+    /// Color multiplied with both fg and bg when rendered, e.g. for lighting effects.
+    tint: Color,
 }
 
 impl<Y: Symbol> Cell<Y> {
@@ -346,11 +518,13 @@ impl<Y: Symbol> Cell<Y> {
         self.csym != other.csym
             || (!matches!(self.csym, CellSym::<Y>::Char(' ')) && self.fg != other.fg)
             || self.bg != other.bg
+            || self.tint != other.tint
     }
 }
 
 const DEFAULT_FG: Color = Color::WHITE;
 const DEFAULT_BG: Color = Color::BLACK;
+const DEFAULT_TINT: Color = Color::WHITE;
 
 struct RawTileGrid<Y: Symbol> {
     size: Size,
@@ -373,6 +547,7 @@ impl<Y: Symbol> RawTileGrid<Y> {
                     csym: CellSym::<Y>::Char(' '),
                     fg: DEFAULT_FG,
                     bg: DEFAULT_BG,
+                    tint: DEFAULT_TINT,
                 };
                 (size.w * size.h) as usize
             ],
@@ -394,6 +569,7 @@ impl<Y: Symbol> RawTileGrid<Y> {
                     csym: CellSym::<Y>::Char(' '),
                     fg: DEFAULT_FG,
                     bg: DEFAULT_BG,
+                    tint: DEFAULT_TINT,
                 },
             );
         }
@@ -423,10 +599,12 @@ impl<Y: Symbol> RawTileGrid<Y> {
                 csym: CellSym::<Y>::Char(' '),
                 fg,
                 bg,
+                tint: DEFAULT_TINT,
             });
         } else {
             for cell in self.cells.iter_mut() {
                 cell.csym = CellSym::<Y>::Char(' ');
+                cell.tint = DEFAULT_TINT;
                 if let Some(fg) = fg.into() {
                     cell.fg = fg;
                 }
@@ -452,20 +630,69 @@ impl<Y: Symbol> RawTileGrid<Y> {
         (real_y * self.size.w as i32 + real_x) as usize
     }
 
+    /// Blank out the other half of a double-width character at the given position, if any, before
+    /// the cell there is overwritten.
+    fn unpair_wide(&mut self, pos: Position) {
+        let index = self.index(pos);
+        let other_x = match self.cells[index].csym {
+            CellSym::<Y>::WideTail(_) => pos.x - 1,
+            csym if csym.is_wide() => pos.x + 1,
+            _ => return,
+        };
+
+        if other_x >= 0 && other_x < self.size.w as i32 {
+            let other_index = self.index(Position {
+                x: other_x,
+                y: pos.y,
+            });
+            let other = &mut self.cells[other_index];
+
+            if matches!(other.csym, CellSym::<Y>::WideTail(_)) || other.csym.is_wide() {
+                other.csym = CellSym::<Y>::Char(' ');
+            }
+        }
+    }
+
+    /// Put a cell symbol at a position, which must be within bounds.
+    ///
+    /// A double-width character takes up the cell to its right as well, and is left out if there's
+    /// no room for it at the right edge.
     fn put_color_raw<F, B>(&mut self, pos: Position, csym: CellSym<Y>, fg: F, bg: B)
     where
         F: Into<Option<Color>> + Copy,
         B: Into<Option<Color>> + Copy,
     {
-        let index = self.index(pos);
-        let cell = &mut self.cells[index];
+        let tail_pos = Position {
+            x: pos.x + 1,
+            y: pos.y,
+        };
+        let (csym, tail) = match csym {
+            CellSym::<Y>::Char(c) if csym.is_wide() => {
+                if tail_pos.x < self.size.w as i32 {
+                    (csym, Some(CellSym::<Y>::WideTail(c)))
+                } else {
+                    (CellSym::<Y>::Char(' '), None)
+                }
+            }
+            _ => (csym, None),
+        };
 
-        cell.csym = csym;
-        if let Some(fg) = fg.into() {
-            cell.fg = fg;
+        self.unpair_wide(pos);
+        if tail.is_some() {
+            self.unpair_wide(tail_pos);
         }
-        if let Some(bg) = bg.into() {
-            cell.bg = bg;
+
+        for (pos, csym) in std::iter::once((pos, csym)).chain(tail.map(|t| (tail_pos, t))) {
+            let index = self.index(pos);
+            let cell = &mut self.cells[index];
+
+            cell.csym = csym;
+            if let Some(fg) = fg.into() {
+                cell.fg = fg;
+            }
+            if let Some(bg) = bg.into() {
+                cell.bg = bg;
+            }
         }
     }
 
@@ -496,32 +723,92 @@ impl<Y: Symbol> RawTileGrid<Y> {
         }
     }
 
+    fn set_tint(&mut self, pos: Position, tint: Color) {
+        if pos.x >= 0 && pos.y >= 0 && pos.x < self.size.w as i32 && pos.y < self.size.h as i32 {
+            let index = self.index(pos);
+
+            self.cells[index].tint = tint;
+        }
+    }
+
+    fn clear_tints(&mut self) {
+        for cell in self.cells.iter_mut() {
+            cell.tint = DEFAULT_TINT;
+        }
+    }
+
     fn print_color<F, B>(&mut self, pos: Position, s: &str, draw_space: bool, fg: F, bg: B)
     where
         F: Into<Option<Color>> + Copy,
         B: Into<Option<Color>> + Copy,
     {
-        if pos.y >= 0
-            && pos.y < self.size.h as i32
-            && pos.x < self.size.w as i32
-            && pos.x + s.len() as i32 > 0
-        {
-            for (i, c) in s
-                .chars()
-                .skip(-pos.x.min(0) as usize)
-                .take(self.size.w.saturating_sub(pos.x.max(0) as u32) as usize)
-                .filter(|c| draw_space || *c != ' ')
-                .enumerate()
-            {
-                self.put_color_raw(
-                    Position {
-                        x: pos.x + i as i32,
-                        y: pos.y,
-                    },
-                    CellSym::<Y>::Char(c),
-                    fg,
-                    bg,
-                );
+        if pos.y >= 0 && pos.y < self.size.h as i32 {
+            let mut x = pos.x;
+
+            for c in s.chars() {
+                let width = util::char_width(c) as i32;
+
+                // Stop at the right edge, leaving out a double-width character that doesn't fit.
+                if x + width > self.size.w as i32 {
+                    break;
+                }
+                if x >= 0 && (draw_space || c != ' ') {
+                    self.put_color_raw(Position { x, y: pos.y }, CellSym::<Y>::Char(c), fg, bg);
+                }
+                x += width;
+            }
+        }
+    }
+
+    /// Copy a region of cells from another grid, clipped to both grids.
+    ///
+    /// Double-width characters cut in half by the edges of the region are copied as spaces.
+    fn blit_from(
+        &mut self,
+        other: &RawTileGrid<Y>,
+        src_pos: Position,
+        size: Size,
+        dst_pos: Position,
+    ) {
+        // Clip the region so that it lies within both grids.
+        let left = 0.max(-src_pos.x).max(-dst_pos.x);
+        let top = 0.max(-src_pos.y).max(-dst_pos.y);
+        let right = (size.w as i32)
+            .min(other.size.w as i32 - src_pos.x)
+            .min(self.size.w as i32 - dst_pos.x);
+        let bottom = (size.h as i32)
+            .min(other.size.h as i32 - src_pos.y)
+            .min(self.size.h as i32 - dst_pos.y);
+
+        for y in top..bottom {
+            for x in left..right {
+                let src_cell = other.cells[other.index(Position {
+                    x: src_pos.x + x,
+                    y: src_pos.y + y,
+                })];
+                let dst = Position {
+                    x: dst_pos.x + x,
+                    y: dst_pos.y + y,
+                };
+                let csym = match src_cell.csym {
+                    // Tails are put along with the left halves of their characters.
+                    CellSym::<Y>::WideTail(_) if x > left => continue,
+                    CellSym::<Y>::WideTail(_) => CellSym::<Y>::Char(' '),
+                    csym if csym.is_wide() && x + 1 >= right => CellSym::<Y>::Char(' '),
+                    csym => csym,
+                };
+
+                self.put_color_raw(dst, csym, src_cell.fg, src_cell.bg);
+                self.set_tint(dst, src_cell.tint);
+                if csym.is_wide() {
+                    self.set_tint(
+                        Position {
+                            x: dst.x + 1,
+                            y: dst.y,
+                        },
+                        src_cell.tint,
+                    );
+                }
             }
         }
     }
@@ -678,6 +965,7 @@ impl<Y: Symbol> RawTileGrid<Y> {
 }
 
 /// Where and how a TileGrid should be displayed on screen.
+#[derive(Clone, Copy, Eq, PartialEq)]
 pub struct TileGridView {
     /// Top-left pixel position of the clipping rectangle in which the TileGrid will be displayed.
     pub pos: Position,
@@ -693,6 +981,9 @@ pub struct TileGridView {
     pub clear_color: Option<Color>,
     /// Color to multiply with the texture as it's displayed on the screen.
     pub color_mod: Color,
+    /// Opacity of cell backgrounds, letting whatever was displayed behind the TileGrid show
+    /// through when below 255; glyphs stay opaque.
+    pub bg_alpha: u8,
     /// Zoom factor of the TileGrid when displayed on screen.
     pub zoom: u32,
 }
@@ -706,7 +997,19 @@ pub struct TileGrid<'b, 'r, Y: Symbol> {
     force_render: bool,
     needs_render: bool,
     needs_upload: bool,
+    /// Area of the buffer in pixels rendered since the last upload, if only part of it needs to be
+    /// uploaded to the texture.
+    upload_rect: Option<Rect>,
+    /// Background alpha that the buffer was last rendered with.
+    rendered_bg_alpha: u8,
+    /// View and draw offset that the TileGrid was last displayed with.
+    displayed_view: Option<(TileGridView, Position)>,
     tileset_index: usize,
+    /// Pixel size of each cell in the buffer and texture, taken from the tileset unless the grid
+    /// is drawn in blocks.
+    tile_size: Size,
+    /// Pixel size of solid blocks that cells are drawn as instead of with the tileset, if any.
+    block_size: Option<Size>,
     buffer: Option<Surface<'b>>,
     texture: Option<Texture<'r>>,
     pub view: TileGridView,
@@ -728,7 +1031,12 @@ impl<'b, 'r, Y: Symbol> TileGrid<'b, 'r, Y> {
             force_render: true,
             needs_render: true,
             needs_upload: true,
+            upload_rect: None,
+            rendered_bg_alpha: 255,
+            displayed_view: None,
             tileset_index,
+            tile_size: tilesets[tileset_index].tile_size,
+            block_size: None,
             buffer: None,
             texture: None,
             view: TileGridView {
@@ -739,6 +1047,7 @@ impl<'b, 'r, Y: Symbol> TileGrid<'b, 'r, Y> {
                 visible: true,
                 clear_color: Some(Color::BLACK),
                 color_mod: Color::WHITE,
+                bg_alpha: 255,
                 zoom: 1,
             },
         }
@@ -793,6 +1102,26 @@ impl<'b, 'r, Y: Symbol> TileGrid<'b, 'r, Y> {
             self.tileset_index = new_tileset_index;
             self.force_render = true;
         }
+        self.tile_size = self
+            .block_size
+            .unwrap_or(tilesets[new_tileset_index].tile_size);
+    }
+
+    /// Draw each cell as a solid block of its background color that is `block_size` pixels big
+    /// instead of with the tileset, or go back to the tileset if None.
+    ///
+    /// Blocks can be much smaller than tiles, e.g. a single pixel per cell, which makes for a
+    /// small texture that can be zoomed up to show an overview of something much bigger than the
+    /// screen could fit in tiles.
+    pub fn set_block_size(&mut self, tilesets: &[Tileset<Y>], block_size: Option<Size>) {
+        if self.block_size != block_size {
+            self.block_size = block_size;
+            self.force_render = true;
+            self.needs_upload = true;
+            self.buffer = None;
+            self.texture = None;
+        }
+        self.tile_size = block_size.unwrap_or(tilesets[self.tileset_index].tile_size);
     }
 
     /// Prepare the TileGrid to be displayed centered within a given rectangle, possibly clipped.
@@ -803,28 +1132,86 @@ impl<'b, 'r, Y: Symbol> TileGrid<'b, 'r, Y> {
         rect_pos: Position,
         rect_size: Size,
     ) {
-        let tileset = &tilesets[self.tileset_index];
-        let px_width = self.front.size.w * tileset.tile_width() * zoom;
-        let px_height = self.front.size.h * tileset.tile_height() * zoom;
-
-        if px_width <= rect_size.w {
-            self.view.size.w = px_width;
-            self.view.pos.x = rect_pos.x + (rect_size.w - px_width) as i32 / 2;
-            self.view.dx = 0;
+        self.view_anchored(tilesets, zoom, Anchor::Center, rect_pos, rect_size);
+    }
+
+    /// Prepare the TileGrid to be displayed at the given anchor of a rectangle, clipped on the
+    /// sides away from the anchor if it doesn't fit.
+    pub fn view_anchored(
+        &mut self,
+        tilesets: &[Tileset<Y>],
+        zoom: u32,
+        anchor: Anchor,
+        rect_pos: Position,
+        rect_size: Size,
+    ) {
+        let tile_size = self
+            .block_size
+            .unwrap_or(tilesets[self.tileset_index].tile_size);
+        let px_size = Size {
+            w: self.front.size.w * tile_size.w * zoom,
+            h: self.front.size.h * tile_size.h * zoom,
+        };
+        let (area, offset) = anchor.place(px_size, Area::new(rect_pos, rect_size));
+
+        self.view.pos = area.pos;
+        self.view.size = area.size;
+        self.view.dx = offset.x;
+        self.view.dy = offset.y;
+    }
+
+    /// Scroll the view of a TileGrid that is taller than its clipping rectangle to center on the
+    /// given row as much as possible.
+    pub fn view_scroll_to_row(&mut self, row: i32) {
+        let row_px = (self.tile_size.h * self.view.zoom) as i32;
+        let grid_px = self.front.size.h as i32 * row_px;
+        let view_px = self.view.size.h as i32;
+
+        if row_px > 0 && grid_px > view_px {
+            self.view.dy = (view_px / 2 - row * row_px - row_px / 2).clamp(view_px - grid_px, 0);
+        }
+    }
+
+    /// The first and last rows of the TileGrid that are fully visible in its clipping rectangle.
+    pub fn view_visible_rows(&self) -> (i32, i32) {
+        let row_px = (self.tile_size.h * self.view.zoom) as i32;
+        let last_row = (self.front.size.h as i32 - 1).max(0);
+
+        if row_px > 0 {
+            let first = (-self.view.dy + row_px - 1).div_euclid(row_px);
+            let last = (self.view.size.h as i32 - self.view.dy).div_euclid(row_px) - 1;
+
+            (first.clamp(0, last_row), last.clamp(0, last_row))
         } else {
-            self.view.size.w = rect_size.w;
-            self.view.pos.x = rect_pos.x;
-            self.view.dx = -((px_width - rect_size.w) as i32 / 2);
+            (0, last_row)
         }
+    }
 
-        if px_height <= rect_size.h {
-            self.view.size.h = px_height;
-            self.view.pos.y = rect_pos.y + (rect_size.h - px_height) as i32 / 2;
-            self.view.dy = 0;
+    /// The cell of the TileGrid displayed at the given pixel position in the window, if any.
+    pub fn view_cell_at(&self, px: Position) -> Option<Position> {
+        let cell_w = (self.tile_size.w * self.view.zoom) as i32;
+        let cell_h = (self.tile_size.h * self.view.zoom) as i32;
+        let rel_x = px.x - self.view.pos.x;
+        let rel_y = px.y - self.view.pos.y;
+
+        if !self.view.visible
+            || cell_w <= 0
+            || cell_h <= 0
+            || rel_x < 0
+            || rel_y < 0
+            || rel_x >= self.view.size.w as i32
+            || rel_y >= self.view.size.h as i32
+        {
+            return None;
+        }
+
+        let x = (rel_x - self.view.dx).div_euclid(cell_w);
+        let y = (rel_y - self.view.dy).div_euclid(cell_h);
+
+        if x >= 0 && y >= 0 && x < self.front.size.w as i32 && y < self.front.size.h as i32 {
+            Some(Position { x, y })
         } else {
-            self.view.size.h = rect_size.h;
-            self.view.pos.y = rect_pos.y;
-            self.view.dy = -((px_height - rect_size.h) as i32 / 2);
+            None
         }
     }
 
@@ -888,7 +1275,20 @@ impl<'b, 'r, Y: Symbol> TileGrid<'b, 'r, Y> {
         B: Into<Option<Color>> + Copy,
     {
         self.front
-            .put_color(pos.into(), CellSym::<Y>::Sym(sym), fg, bg);
+            .put_color(pos.into(), CellSym::<Y>::from_sym(sym), fg, bg);
+        self.needs_render = true;
+    }
+
+    /// Put a dynamic symbol in a given position, optionally changing the foreground and/or
+    /// background colors.
+    pub fn put_dyn_sym_color<P, F, B>(&mut self, pos: P, dsym: DynSym, fg: F, bg: B)
+    where
+        P: Into<Position>,
+        F: Into<Option<Color>> + Copy,
+        B: Into<Option<Color>> + Copy,
+    {
+        self.front
+            .put_color(pos.into(), CellSym::<Y>::Dyn(dsym), fg, bg);
         self.needs_render = true;
     }
 
@@ -912,7 +1312,7 @@ impl<'b, 'r, Y: Symbol> TileGrid<'b, 'r, Y> {
         B: Into<Option<Color>> + Copy,
     {
         self.front
-            .put_color_raw(pos.into(), CellSym::<Y>::Sym(sym), fg, bg);
+            .put_color_raw(pos.into(), CellSym::<Y>::from_sym(sym), fg, bg);
         self.needs_render = true;
     }
 
@@ -927,6 +1327,23 @@ impl<'b, 'r, Y: Symbol> TileGrid<'b, 'r, Y> {
         self.needs_render = true;
     }
 
+    /// Tint the cell at a given position, multiplying its foreground and background colors by the
+    /// tint color when rendered.
+    ///
+    /// Tints stay put as cells are redrawn and are only reset by clearing the TileGrid, so effects
+    /// like lighting can be changed without redrawing the cells underneath.  Only cells whose tint
+    /// actually changes are rerendered.
+    pub fn set_tint<P: Into<Position>>(&mut self, pos: P, tint: Color) {
+        self.front.set_tint(pos.into(), tint);
+        self.needs_render = true;
+    }
+
+    /// Reset the tints of all cells without otherwise clearing the TileGrid.
+    pub fn clear_tints(&mut self) {
+        self.front.clear_tints();
+        self.needs_render = true;
+    }
+
     /// Print a string on the TileGrid starting at the given position.  If the string goes past the
     /// right edge of the TileGrid it will be truncated.
     pub fn print<P: Into<Position>>(&mut self, pos: P, s: &str) {
@@ -947,6 +1364,21 @@ impl<'b, 'r, Y: Symbol> TileGrid<'b, 'r, Y> {
         self.needs_render = true;
     }
 
+    /// Copy a rectangular region of cells from another TileGrid, placing its top-left corner at
+    /// `dst_pos`.  Any part of the region outside of either TileGrid is clipped off.
+    ///
+    /// This is handy for composing panels drawn separately into a single TileGrid.
+    pub fn blit_from<P, S, Q>(&mut self, other: &TileGrid<Y>, src_pos: P, size: S, dst_pos: Q)
+    where
+        P: Into<Position>,
+        S: Into<Size>,
+        Q: Into<Position>,
+    {
+        self.front
+            .blit_from(&other.front, src_pos.into(), size.into(), dst_pos.into());
+        self.needs_render = true;
+    }
+
     /// Draw a box on the TileGrid with the given size and position.  Any part of the box that
     /// falls outside of the TileGrid will be clipped off.
     pub fn draw_box<P, S, F, B>(&mut self, pos: P, size: S, fg: F, bg: B)
@@ -983,13 +1415,16 @@ impl<'b, 'r, Y: Symbol> TileGrid<'b, 'r, Y> {
         self.needs_render = true;
     }
 
-    fn render(&mut self, tileset: &mut Tileset<Y>, mut force: bool) -> bool {
-        let mut buffer_updated = false;
+    /// Render changed cells to the buffer, returning the area of the buffer in pixels that was
+    /// rendered to, if any.
+    fn render(&mut self, tileset: &mut Tileset<Y>, mut force: bool) -> Option<Rect> {
+        // Bounding box of rendered cells as (left, top, right, bottom), inclusive.
+        let mut dirty: Option<(i32, i32, i32, i32)> = None;
 
         assert!(self.front.size == self.back.size);
 
-        let buffer_px_w = self.front.size.w * tileset.tile_size.w;
-        let buffer_px_h = self.front.size.h * tileset.tile_size.h;
+        let buffer_px_w = self.front.size.w * self.tile_size.w;
+        let buffer_px_h = self.front.size.h * self.tile_size.h;
 
         // Reset the buffer if it isn't the correct size to render to.
         if self.buffer.is_some() {
@@ -1015,8 +1450,9 @@ impl<'b, 'r, Y: Symbol> TileGrid<'b, 'r, Y> {
         };
 
         let grid_width = self.front.size.w as i32;
-        let cell_width = tileset.tile_size.w as u32;
-        let cell_height = tileset.tile_size.h as u32;
+        let cell_width = self.tile_size.w;
+        let cell_height = self.tile_size.h;
+        let blocks = self.block_size.is_some();
 
         // Check the grid for positions to (re)render and (re)render them.
         for (i, (fcell, bcell)) in self
@@ -1028,55 +1464,56 @@ impl<'b, 'r, Y: Symbol> TileGrid<'b, 'r, Y> {
         {
             // Render cell if requested or a visible change has occurred.
             if force || fcell.visible_diff(bcell) {
+                let (cell_x, cell_y) = (i as i32 % grid_width, i as i32 / grid_width);
                 let dest_rect = Rect::new(
-                    i as i32 % grid_width * cell_width as i32,
-                    i as i32 / grid_width * cell_height as i32,
+                    cell_x * cell_width as i32,
+                    cell_y * cell_height as i32,
                     cell_width,
                     cell_height,
                 );
-                let bg_color = Sdl2Color::RGB(fcell.bg.r, fcell.bg.g, fcell.bg.b);
+                let bg = fcell.bg.multiply(fcell.tint);
+                let bg_color = Sdl2Color::RGBA(bg.r, bg.g, bg.b, self.view.bg_alpha);
 
                 buffer.fill_rect(dest_rect, bg_color).unwrap();
 
-                if !matches!(fcell.csym, CellSym::<Y>::Char(' ')) {
-                    tileset.draw_tile_to(fcell.csym, fcell.fg, buffer, dest_rect);
+                if !blocks && !matches!(fcell.csym, CellSym::<Y>::Char(' ')) {
+                    tileset.draw_tile_to(
+                        fcell.csym,
+                        fcell.fg.multiply(fcell.tint),
+                        buffer,
+                        dest_rect,
+                    );
                 }
 
-                buffer_updated = true;
+                dirty = Some(match dirty {
+                    Some((l, t, r, b)) => {
+                        (l.min(cell_x), t.min(cell_y), r.max(cell_x), b.max(cell_y))
+                    }
+                    None => (cell_x, cell_y, cell_x, cell_y),
+                });
             }
         }
 
         // Update back buffer with front buffer contents.
         self.back.cells.copy_from_slice(&self.front.cells[..]);
 
-        buffer_updated
+        dirty.map(|(l, t, r, b)| {
+            Rect::new(
+                l * cell_width as i32,
+                t * cell_height as i32,
+                (r - l + 1) as u32 * cell_width,
+                (b - t + 1) as u32 * cell_height,
+            )
+        })
     }
 
-    /// Display the TileGrid onto the screen.
-    ///
-    /// A TileGrid maintains internal buffers to track changes since the last display, so it needs
-    /// to be mutable in order to update those buffers when these changes are detected.
-    ///
-    /// # Panics
-    ///
-    /// Panics if:
-    ///
-    ///  * buffer creation fails
-    ///  * texture creation fails
-    ///  * the texture fails to be updated
-    ///  * the texture fails to be copied onto the canvas
-    pub fn display(
+    /// Render the grid contents to the buffer in software and upload it to the texture, returning
+    /// true if the texture was updated.
+    fn render_software(
         &mut self,
-        tilesets: &mut [Tileset<Y>],
-        canvas: &mut WindowCanvas,
+        tileset: &mut Tileset<Y>,
         texture_creator: &'r TextureCreator<WindowContext>,
-    ) {
-        if !self.view.visible || self.view.zoom == 0 {
-            return;
-        }
-
-        let tileset = &mut tilesets[self.tileset_index];
-
+    ) -> bool {
         // If the buffer doesn't exist yet, it will need to be fully rendered.
         if self.buffer.is_none() {
             self.force_render = true;
@@ -1084,8 +1521,11 @@ impl<'b, 'r, Y: Symbol> TileGrid<'b, 'r, Y> {
 
         // Render the drawn grid contents to the buffer.
         if self.needs_render || self.force_render {
-            if self.render(tileset, self.force_render) {
-                self.needs_upload = true;
+            if let Some(rendered_rect) = self.render(tileset, self.force_render) {
+                self.upload_rect = Some(match self.upload_rect {
+                    Some(upload_rect) => upload_rect.union(rendered_rect),
+                    None => rendered_rect,
+                });
                 self.force_render = false;
             }
             self.needs_render = false;
@@ -1099,7 +1539,7 @@ impl<'b, 'r, Y: Symbol> TileGrid<'b, 'r, Y> {
                 self.texture = Some(
                     texture_creator
                         .create_texture_streaming(
-                            PixelFormatEnum::RGB888,
+                            PixelFormatEnum::ARGB8888,
                             buffer.width(),
                             buffer.height(),
                         )
@@ -1110,7 +1550,8 @@ impl<'b, 'r, Y: Symbol> TileGrid<'b, 'r, Y> {
             }
         };
 
-        // Upload the buffer contents to the texture if needed.
+        // Upload the buffer contents to the texture if needed, limited to the rendered area if
+        // the texture is otherwise up to date.
         if self.needs_upload {
             texture
                 .update(
@@ -1120,7 +1561,194 @@ impl<'b, 'r, Y: Symbol> TileGrid<'b, 'r, Y> {
                 )
                 .unwrap();
             self.needs_upload = false;
+            self.upload_rect = None;
+            true
+        } else if let Some(upload_rect) = self.upload_rect.take() {
+            let pitch = buffer.pitch() as usize;
+            let start = upload_rect.y() as usize * pitch
+                + upload_rect.x() as usize * buffer.pixel_format_enum().byte_size_per_pixel();
+
+            texture
+                .update(upload_rect, &buffer.without_lock().unwrap()[start..], pitch)
+                .unwrap();
+            true
+        } else {
+            false
         }
+    }
+
+    /// Render the grid contents straight to the texture as a render target, copying tiles from
+    /// the atlas texture of the tileset, returning true if the texture was rendered to.
+    fn render_gpu(
+        &mut self,
+        tileset: &mut Tileset<Y>,
+        atlas: &mut Texture,
+        canvas: &mut WindowCanvas,
+        texture_creator: &'r TextureCreator<WindowContext>,
+    ) -> bool {
+        let px_w = self.front.size.w * self.tile_size.w;
+        let px_h = self.front.size.h * self.tile_size.h;
+
+        // Recreate the texture if it isn't the correct size to render to.
+        if let Some(texture) = &self.texture {
+            let query = texture.query();
+
+            if query.width != px_w || query.height != px_h {
+                self.texture = None;
+            }
+        }
+
+        let texture = match &mut self.texture {
+            Some(texture) => texture,
+            None => {
+                self.texture = Some(
+                    texture_creator
+                        .create_texture_target(PixelFormatEnum::ARGB8888, px_w, px_h)
+                        .unwrap(),
+                );
+                self.needs_upload = true;
+                self.texture.as_mut().unwrap()
+            }
+        };
+
+        // New render targets are blank and old ones may have lost their contents, so there's
+        // nothing to build on and everything needs to be rendered again.
+        if self.needs_upload {
+            self.force_render = true;
+            self.needs_upload = false;
+        }
+
+        if !self.needs_render && !self.force_render {
+            return false;
+        }
+
+        let force = self.force_render;
+        let bg_alpha = self.view.bg_alpha;
+        let grid_width = self.front.size.w as i32;
+        let cell_width = self.tile_size.w;
+        let cell_height = self.tile_size.h;
+        let blocks = self.block_size.is_some();
+        let front = &self.front;
+        let back = &self.back;
+
+        canvas
+            .with_texture_canvas(texture, |target| {
+                // Write background alpha as-is instead of blending it.
+                target.set_blend_mode(BlendMode::None);
+
+                for (i, (fcell, bcell)) in front.cells.iter().zip(back.cells.iter()).enumerate() {
+                    // Render cell if requested or a visible change has occurred.
+                    if force || fcell.visible_diff(bcell) {
+                        let dest_rect = Rect::new(
+                            i as i32 % grid_width * cell_width as i32,
+                            i as i32 / grid_width * cell_height as i32,
+                            cell_width,
+                            cell_height,
+                        );
+                        let bg = fcell.bg.multiply(fcell.tint);
+
+                        target.set_draw_color(Sdl2Color::RGBA(bg.r, bg.g, bg.b, bg_alpha));
+                        target.fill_rect(dest_rect).unwrap();
+
+                        if !blocks && !matches!(fcell.csym, CellSym::<Y>::Char(' ')) {
+                            if let Some(tile_rect) = tileset.tile_rect(fcell.csym) {
+                                let fg = fcell.fg.multiply(fcell.tint);
+
+                                atlas.set_color_mod(fg.r, fg.g, fg.b);
+                                target.copy(atlas, tile_rect, dest_rect).unwrap();
+                            }
+                        }
+                    }
+                }
+            })
+            .unwrap();
+
+        // Update back buffer with front buffer contents.
+        self.back.cells.copy_from_slice(&self.front.cells[..]);
+
+        self.force_render = false;
+        self.needs_render = false;
+
+        true
+    }
+
+    /// Render the whole grid in software and save it as a PNG image, whether or not it has ever
+    /// been displayed.
+    pub(crate) fn save_png(
+        &mut self,
+        tilesets: &mut [Tileset<Y>],
+        path: &Path,
+    ) -> Result<(), String> {
+        self.render(&mut tilesets[self.tileset_index], true);
+        self.buffer.as_ref().unwrap().save(path)
+    }
+
+    /// Check if displaying the TileGrid would show anything different from the last time it was
+    /// displayed, so that drawing the screen can be skipped if nothing has changed.
+    pub fn needs_display(&self) -> bool {
+        if self.displayed_view != Some((self.view, self.front.draw_offset)) {
+            return true;
+        }
+
+        self.view.visible
+            && (self.force_render
+                || self.needs_upload
+                || self.texture.is_none()
+                || self.rendered_bg_alpha != self.view.bg_alpha
+                || self.needs_render && self.front.cells != self.back.cells)
+    }
+
+    /// Display the TileGrid onto the screen, returning true if its texture had to be updated.
+    ///
+    /// A TileGrid maintains internal buffers to track changes since the last display, so it needs
+    /// to be mutable in order to update those buffers when these changes are detected.
+    ///
+    /// If `atlases` is given, it must hold the atlas texture of each tileset, and the TileGrid is
+    /// rendered on the GPU with them instead of in software.
+    ///
+    /// # Panics
+    ///
+    /// Panics if:
+    ///
+    ///  * buffer creation fails
+    ///  * texture creation fails
+    ///  * the texture fails to be updated or rendered to
+    ///  * the texture fails to be copied onto the canvas
+    pub fn display(
+        &mut self,
+        tilesets: &mut [Tileset<Y>],
+        atlases: Option<&mut [Texture]>,
+        canvas: &mut WindowCanvas,
+        texture_creator: &'r TextureCreator<WindowContext>,
+    ) -> bool {
+        self.displayed_view = Some((self.view, self.front.draw_offset));
+
+        if !self.view.visible || self.view.zoom == 0 {
+            return false;
+        }
+
+        let tileset = &mut tilesets[self.tileset_index];
+        let px_w = self.front.size.w * self.tile_size.w;
+        let px_h = self.front.size.h * self.tile_size.h;
+
+        // Backgrounds are rendered with their alpha, so changing it means rendering them again.
+        if self.rendered_bg_alpha != self.view.bg_alpha {
+            self.rendered_bg_alpha = self.view.bg_alpha;
+            self.force_render = true;
+        }
+
+        let texture_updated = match atlases {
+            Some(atlases) => self.render_gpu(
+                tileset,
+                &mut atlases[self.tileset_index],
+                canvas,
+                texture_creator,
+            ),
+            None => self.render_software(tileset, texture_creator),
+        };
+
+        // The texture is guaranteed to exist after rendering.
+        let texture = self.texture.as_mut().unwrap();
 
         let clip_rect = Rect::new(
             self.view.pos.x,
@@ -1135,7 +1763,12 @@ impl<'b, 'r, Y: Symbol> TileGrid<'b, 'r, Y> {
             canvas.draw_rect(clip_rect).unwrap();
         }
 
-        // Display the texture on the screen.
+        // Display the texture on the screen, only blending it if the background is translucent.
+        texture.set_blend_mode(if self.view.bg_alpha < 255 {
+            BlendMode::Blend
+        } else {
+            BlendMode::None
+        });
         texture.set_color_mod(
             self.view.color_mod.r,
             self.view.color_mod.g,
@@ -1143,14 +1776,14 @@ impl<'b, 'r, Y: Symbol> TileGrid<'b, 'r, Y> {
         );
         canvas.set_clip_rect(clip_rect);
 
-        let offset_x_px = self.front.draw_offset.x * tileset.tile_width() as i32;
-        let offset_y_px = self.front.draw_offset.y * tileset.tile_height() as i32;
+        let offset_x_px = self.front.draw_offset.x * self.tile_size.w as i32;
+        let offset_y_px = self.front.draw_offset.y * self.tile_size.h as i32;
 
         // Display bottom-right of the texture at the top-left of the destination.
         let src_x = offset_x_px;
         let src_y = offset_y_px;
-        let src_w = buffer.width() - offset_x_px as u32;
-        let src_h = buffer.height() - offset_y_px as u32;
+        let src_w = px_w - offset_x_px as u32;
+        let src_h = px_h - offset_y_px as u32;
         let dest_x = self.view.pos.x + self.view.dx;
         let dest_y = self.view.pos.y + self.view.dy;
         canvas
@@ -1171,10 +1804,10 @@ impl<'b, 'r, Y: Symbol> TileGrid<'b, 'r, Y> {
             let src_x = 0;
             let src_y = offset_y_px;
             let src_w = offset_x_px as u32;
-            let src_h = buffer.height() - offset_y_px as u32;
+            let src_h = px_h - offset_y_px as u32;
             let dest_x = self.view.pos.x
                 + self.view.dx
-                + (buffer.width() as i32 - offset_x_px) * self.view.zoom as i32;
+                + (px_w as i32 - offset_x_px) * self.view.zoom as i32;
             let dest_y = self.view.pos.y + self.view.dy;
             canvas
                 .copy(
@@ -1197,10 +1830,10 @@ impl<'b, 'r, Y: Symbol> TileGrid<'b, 'r, Y> {
                 let src_h = offset_y_px as u32;
                 let dest_x = self.view.pos.x
                     + self.view.dx
-                    + (buffer.width() as i32 - offset_x_px) * self.view.zoom as i32;
+                    + (px_w as i32 - offset_x_px) * self.view.zoom as i32;
                 let dest_y = self.view.pos.y
                     + self.view.dy
-                    + (buffer.height() as i32 - offset_y_px) * self.view.zoom as i32;
+                    + (px_h as i32 - offset_y_px) * self.view.zoom as i32;
                 canvas
                     .copy(
                         texture,
@@ -1220,12 +1853,12 @@ impl<'b, 'r, Y: Symbol> TileGrid<'b, 'r, Y> {
             // Display top-right of the texture at the bottom-left of the destination.
             let src_x = offset_x_px;
             let src_y = 0;
-            let src_w = buffer.width() - offset_x_px as u32;
+            let src_w = px_w - offset_x_px as u32;
             let src_h = offset_y_px as u32;
             let dest_x = self.view.pos.x + self.view.dx;
             let dest_y = self.view.pos.y
                 + self.view.dy
-                + (buffer.height() as i32 - offset_y_px) * self.view.zoom as i32;
+                + (px_h as i32 - offset_y_px) * self.view.zoom as i32;
             canvas
                 .copy(
                     texture,
@@ -1239,6 +1872,8 @@ impl<'b, 'r, Y: Symbol> TileGrid<'b, 'r, Y> {
                 )
                 .unwrap();
         }
+
+        texture_updated
     }
 }
 
@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+use crate::ViewableField;
+
 #[derive(Clone, Copy, Eq, PartialEq, Deserialize, Serialize)]
 pub struct Color {
     pub r: u8,
@@ -67,6 +69,57 @@ impl Color {
         g: 191,
         b: 204,
     };
+
+    /// Multiply each channel with that of another color, e.g. to light or shade this color.
+    pub fn multiply(self, other: Color) -> Color {
+        let mul = |a: u8, b: u8| (a as u16 * b as u16 / 255) as u8;
+
+        Color {
+            r: mul(self.r, other.r),
+            g: mul(self.g, other.g),
+            b: mul(self.b, other.b),
+        }
+    }
+
+    /// Mix this color with another, from all of this color at 0 to all of the other at 255.
+    pub fn blend(self, other: Color, amount: u8) -> Color {
+        let mix = |a: u8, b: u8| {
+            ((a as u16 * (255 - amount as u16) + b as u16 * amount as u16) / 255) as u8
+        };
+
+        Color {
+            r: mix(self.r, other.r),
+            g: mix(self.g, other.g),
+            b: mix(self.b, other.b),
+        }
+    }
+}
+
+/// Number of cells a character takes up when printed: 2 for double-width characters such as
+/// full-width CJK, 1 for everything else.
+pub fn char_width(c: char) -> usize {
+    let c = c as u32;
+
+    if c < 0x1100 {
+        1
+    } else if (0x1100..=0x115f).contains(&c)
+        || (0x2e80..=0xa4cf).contains(&c) && c != 0x303f
+        || (0xac00..=0xd7a3).contains(&c)
+        || (0xf900..=0xfaff).contains(&c)
+        || (0xfe30..=0xfe4f).contains(&c)
+        || (0xff00..=0xff60).contains(&c)
+        || (0xffe0..=0xffe6).contains(&c)
+        || (0x20000..=0x3fffd).contains(&c)
+    {
+        2
+    } else {
+        1
+    }
+}
+
+/// Number of cells a string takes up when printed, counting double-width characters as 2.
+pub fn str_width(s: &str) -> usize {
+    s.chars().map(char_width).sum()
 }
 
 impl From<(u8, u8, u8)> for Color {
@@ -104,3 +157,157 @@ impl From<(u32, u32)> for Size {
         Self { w, h }
     }
 }
+
+/// Iterator over the positions of a line between two points, created by [walk_line].
+pub struct LineIter {
+    x: i32,
+    y: i32,
+    dx: i32,
+    dy: i32,
+    step_x: i32,
+    step_y: i32,
+    err: i32,
+    remaining: i32,
+}
+
+impl Iterator for LineIter {
+    type Item = (i32, i32);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining < 0 {
+            return None;
+        }
+
+        let pos = (self.x, self.y);
+        let e2 = 2 * self.err;
+
+        if e2 >= self.dy {
+            self.err += self.dy;
+            self.x += self.step_x;
+        }
+        if e2 <= self.dx {
+            self.err += self.dx;
+            self.y += self.step_y;
+        }
+        self.remaining -= 1;
+
+        Some(pos)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = (self.remaining + 1).max(0) as usize;
+        (len, Some(len))
+    }
+}
+
+impl ExactSizeIterator for LineIter {}
+
+/// Walk the positions of a line from `a` to `b` using Bresenham's line algorithm, including both
+/// ends.
+///
+/// Note that walking from `b` to `a` may pass through different positions.
+pub fn walk_line(a: (i32, i32), b: (i32, i32)) -> LineIter {
+    let dx = (b.0 - a.0).abs();
+    let dy = -(b.1 - a.1).abs();
+
+    LineIter {
+        x: a.0,
+        y: a.1,
+        dx,
+        dy,
+        step_x: if a.0 < b.0 { 1 } else { -1 },
+        step_y: if a.1 < b.1 { 1 } else { -1 },
+        err: dx + dy,
+        remaining: dx.max(-dy),
+    }
+}
+
+/// Returns true if nothing opaque lies on the line from `a` to `b`, as walked by [walk_line].
+///
+/// The ends themselves may be opaque, so e.g. a wall can be seen from a position next to it.
+pub fn has_los<T: ViewableField>(map: &T, a: (i32, i32), b: (i32, i32)) -> bool {
+    walk_line(a, b)
+        .filter(|&pos| pos != a && pos != b)
+        .all(|(x, y)| !map.is_opaque(x, y))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Opaque at every listed position and nowhere else.
+    struct Walls(Vec<(i32, i32)>);
+
+    impl ViewableField for Walls {
+        fn is_opaque(&self, x: i32, y: i32) -> bool {
+            self.0.contains(&(x, y))
+        }
+    }
+
+    type Transform = fn((i32, i32)) -> (i32, i32);
+
+    #[test]
+    fn lines_are_the_same_in_every_octant() {
+        // Flipping either axis and swapping the axes reaches every octant from the first one.
+        let transforms: [Transform; 8] = [
+            |(x, y)| (x, y),
+            |(x, y)| (y, x),
+            |(x, y)| (-y, x),
+            |(x, y)| (-x, y),
+            |(x, y)| (-x, -y),
+            |(x, y)| (-y, -x),
+            |(x, y)| (y, -x),
+            |(x, y)| (x, -y),
+        ];
+
+        for dx in 0..=8 {
+            for dy in 0..=dx {
+                let line = walk_line((0, 0), (dx, dy)).collect::<Vec<_>>();
+
+                for transform in transforms.iter() {
+                    assert_eq!(
+                        walk_line((0, 0), transform((dx, dy))).collect::<Vec<_>>(),
+                        line.iter().copied().map(transform).collect::<Vec<_>>(),
+                        "line to {:?}",
+                        transform((dx, dy)),
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn lines_include_both_ends() {
+        for &(a, b) in [((0, 0), (0, 0)), ((2, 3), (7, 5)), ((4, -1), (-3, 6))].iter() {
+            let line = walk_line(a, b);
+            let len = (b.0 - a.0).abs().max((b.1 - a.1).abs()) as usize + 1;
+
+            assert_eq!(line.len(), len);
+
+            let line = line.collect::<Vec<_>>();
+
+            assert_eq!(line.len(), len);
+            assert_eq!(line.first(), Some(&a));
+            assert_eq!(line.last(), Some(&b));
+            // Each step moves to an adjacent position.
+            assert!(line
+                .windows(2)
+                .all(|w| (w[1].0 - w[0].0).abs() <= 1 && (w[1].1 - w[0].1).abs() <= 1));
+        }
+    }
+
+    #[test]
+    fn line_of_sight_is_blocked_by_opaque_positions_between_the_ends() {
+        let walls = Walls(vec![(3, 0), (5, 5)]);
+
+        assert!(!has_los(&walls, (0, 0), (6, 0)));
+        assert!(has_los(&walls, (0, 1), (6, 1)));
+        assert!(!has_los(&walls, (2, 2), (8, 8)));
+        assert!(has_los(&walls, (2, 2), (8, 4)));
+
+        // Opaque ends don't block, so walls can be seen from next to them and from each other.
+        assert!(has_los(&walls, (0, 0), (3, 0)));
+        assert!(has_los(&walls, (3, 0), (3, 0)));
+        assert!(has_los(&walls, (5, 5), (5, 0)));
+    }
+}
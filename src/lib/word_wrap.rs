@@ -113,3 +113,116 @@ pub fn word_wrap(input: &str, max_length: usize) -> impl Iterator<Item = &str> {
             .map(move |(start, end)| &line[start..end])
     })
 }
+
+/// Extra line-breaking behavior for [word_wrap_styled].
+#[derive(Clone, Copy, Default)]
+pub struct WrapOptions {
+    /// Split words that are too long for a line with a hyphen instead of just cutting them off.
+    pub hyphenate: bool,
+    /// Allow lines to break between CJK characters, which aren't separated by spaces.
+    pub break_cjk: bool,
+}
+
+/// True for characters of scripts that don't put spaces between words.
+fn is_cjk(ch: char) -> bool {
+    matches!(ch as u32,
+        0x3000..=0x30ff     // CJK punctuation, Hiragana, Katakana
+        | 0x3400..=0x4dbf   // CJK Unified Ideographs Extension A
+        | 0x4e00..=0x9fff   // CJK Unified Ideographs
+        | 0xac00..=0xd7a3   // Hangul Syllables
+        | 0xf900..=0xfaff   // CJK Compatibility Ideographs
+        | 0xff00..=0xffef   // Halfwidth and Fullwidth Forms
+    )
+}
+
+/// Word wrap text whose characters each carry a style, e.g. a color, into lines with at most
+/// `max_length` characters.
+///
+/// Breaks lines like [word_wrap], but since styles are kept per character, any inline markup can
+/// be parsed out beforehand and still apply to the right characters afterwards.  Hyphens added by
+/// hyphenation take the style of the character before them.
+pub fn word_wrap_styled<T: Copy>(
+    text: &[(char, T)],
+    max_length: usize,
+    options: WrapOptions,
+) -> Vec<Vec<(char, T)>> {
+    assert!(max_length > 0);
+
+    let hyphenate = options.hyphenate && max_length > 1;
+    let mut lines = Vec::new();
+
+    for input_line in text.split(|(ch, _)| *ch == '\n') {
+        // Split into runs of whitespace and words, the latter ending after hyphens and around
+        // CJK characters if requested.
+        let mut tokens: Vec<&[(char, T)]> = Vec::new();
+        let mut start = 0;
+
+        for i in 1..=input_line.len() {
+            let at_end = i == input_line.len();
+            let prev = input_line[i - 1].0;
+            let split = at_end || {
+                let ch = input_line[i].0;
+                prev.is_whitespace() != ch.is_whitespace()
+                    || (!ch.is_whitespace() && prev == '-')
+                    || (options.break_cjk && (is_cjk(prev) || is_cjk(ch)))
+            };
+
+            if split {
+                tokens.push(&input_line[start..i]);
+                start = i;
+            }
+        }
+
+        let mut line: Vec<(char, T)> = Vec::new();
+        let mut pending_space: &[(char, T)] = &[];
+
+        for token in tokens {
+            if token[0].0.is_whitespace() {
+                if line.is_empty() {
+                    // Keep leading whitespace for indentation, as long as it fits.
+                    line.extend(token.iter().take(max_length));
+                } else {
+                    pending_space = token;
+                }
+                continue;
+            }
+
+            if line.len() + pending_space.len() + token.len() <= max_length {
+                line.extend_from_slice(pending_space);
+                line.extend_from_slice(token);
+            } else if token.len() <= max_length {
+                lines.push(std::mem::take(&mut line));
+                line.extend_from_slice(token);
+            } else {
+                // The word can't fit on any line, so it has to be split.
+                if !line.is_empty() {
+                    lines.push(std::mem::take(&mut line));
+                }
+
+                let mut rest = token;
+                while rest.len() > max_length {
+                    let piece_len = if hyphenate {
+                        max_length - 1
+                    } else {
+                        max_length
+                    };
+                    let (piece, after) = rest.split_at(piece_len);
+
+                    line.extend_from_slice(piece);
+                    if hyphenate {
+                        line.push(('-', piece[piece_len - 1].1));
+                    }
+                    lines.push(std::mem::take(&mut line));
+                    rest = after;
+                }
+                line.extend_from_slice(rest);
+            }
+
+            pending_space = &[];
+        }
+
+        lines.push(line);
+    }
+
+    lines
+}
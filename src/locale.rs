@@ -0,0 +1,154 @@
+use serde::Deserialize;
+use std::{collections::HashMap, fmt, fs};
+
+const LOCALE_DIR: &str = "assets/locale";
+
+/// Language that every other catalog falls back on for strings that it doesn't translate.
+const BASE_LANGUAGE: &str = "en";
+
+/// Contents of a catalog file in the locale directory.
+#[derive(Deserialize)]
+struct Catalog {
+    /// Name of the language in that language, shown in the options menu.
+    language: String,
+    strings: HashMap<String, String>,
+}
+
+pub struct LanguageEntry {
+    pub code: String,
+    pub name: String,
+}
+
+/// Languages that catalogs were found for, with the base language first.
+pub struct LanguageList(pub Vec<LanguageEntry>);
+
+impl LanguageList {
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn name(&self, index: u32) -> Option<&str> {
+        self.0.get(index as usize).map(|e| e.name.as_str())
+    }
+
+    pub fn code(&self, index: u32) -> Option<&str> {
+        self.0.get(index as usize).map(|e| e.code.as_str())
+    }
+
+    pub fn index_of(&self, code: &str) -> Option<u32> {
+        self.0.iter().position(|e| e.code == code).map(|i| i as u32)
+    }
+}
+
+fn read_catalog(code: &str) -> Result<Catalog, String> {
+    let path = format!("{}/{}.json", LOCALE_DIR, code);
+
+    fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read {}: {}", path, e))
+        .and_then(|s| {
+            serde_json::from_str::<Catalog>(&s)
+                .map_err(|e| format!("Failed to parse {}: {}", path, e))
+        })
+}
+
+/// Find the catalogs in the locale directory.
+pub fn discover_languages() -> LanguageList {
+    let mut codes = match fs::read_dir(LOCALE_DIR) {
+        Ok(read_dir) => read_dir
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|p| matches!(p.extension().and_then(|e| e.to_str()), Some("json")))
+            .filter_map(|p| p.file_stem().and_then(|s| s.to_str()).map(String::from))
+            .collect::<Vec<_>>(),
+        Err(e) => {
+            eprintln!("Warning: Failed to read {}: {}", LOCALE_DIR, e);
+            Vec::new()
+        }
+    };
+
+    codes.sort_by_key(|code| (code != BASE_LANGUAGE, code.clone()));
+
+    LanguageList(
+        codes
+            .into_iter()
+            .filter_map(|code| match read_catalog(&code) {
+                Ok(catalog) => Some(LanguageEntry {
+                    code,
+                    name: catalog.language,
+                }),
+                Err(e) => {
+                    eprintln!("Warning: {}", e);
+                    None
+                }
+            })
+            .collect(),
+    )
+}
+
+/// The strings of the chosen language, filled in with those of the base language where missing.
+pub struct Locale {
+    strings: HashMap<String, String>,
+}
+
+/// Codes of the catalogs that make up a language, with the base language first.
+fn catalog_codes(languages: &LanguageList, index: u32) -> [&str; 2] {
+    let code = languages
+        .0
+        .get(index as usize)
+        .map_or(BASE_LANGUAGE, |e| e.code.as_str());
+
+    [BASE_LANGUAGE, code]
+}
+
+impl Locale {
+    pub fn load(languages: &LanguageList, index: u32) -> Self {
+        let mut strings = HashMap::new();
+
+        for code in catalog_codes(languages, index).iter() {
+            match read_catalog(code) {
+                Ok(catalog) => strings.extend(catalog.strings),
+                Err(e) => eprintln!("Warning: {}", e),
+            }
+        }
+
+        Self { strings }
+    }
+
+    /// Like [Locale::load], but fail if any catalog can't be loaded instead of making do without
+    /// it.
+    pub fn try_load(languages: &LanguageList, index: u32) -> Result<Self, String> {
+        let mut strings = HashMap::new();
+
+        for code in catalog_codes(languages, index).iter() {
+            strings.extend(read_catalog(code)?.strings);
+        }
+
+        Ok(Self { strings })
+    }
+
+    /// Look up a string, falling back on the key itself so that missing strings are obvious.
+    pub fn get<'a>(&'a self, key: &'a str) -> &'a str {
+        self.strings.get(key).map_or(key, String::as_str)
+    }
+
+    /// Look up the name of an item or obstacle, e.g. "Health Potion" as "name.health_potion",
+    /// falling back on the name itself for things added to the data files without a catalog entry.
+    pub fn name(&self, name: &str) -> String {
+        let key = format!("name.{}", name.to_lowercase().replace(' ', "_"));
+
+        self.strings
+            .get(&key)
+            .cloned()
+            .unwrap_or_else(|| name.to_string())
+    }
+
+    /// Look up a string and replace each "{name}" in it with the value of the matching argument.
+    pub fn format(&self, key: &str, args: &[(&str, &dyn fmt::Display)]) -> String {
+        let mut text = self.get(key).to_string();
+
+        for (name, value) in args {
+            text = text.replace(&format!("{{{}}}", name), &value.to_string());
+        }
+
+        text
+    }
+}
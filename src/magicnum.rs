@@ -4,4 +4,18 @@ pub const GENERATE_ROOMS_AND_CORRIDORS: u64 = 0x3fdc77fb4d7f5d2f;
 pub const SPAWN_GUARANTEED_WEAPON: u64 = 0x67caf3e7b16e9df2;
 pub const SPAWN_GUARANTEED_ARMOR: u64 = 0x74e90549dbcadfd0;
 pub const FILL_ROOM_WITH_SPAWNS: u64 = 0xd85af3d2cf6dcbc5;
+pub const SPAWN_NPC: u64 = 0x9b3e51c7a20d64f1;
+pub const TAKE_BONES: u64 = 0x51f0c3a9e4b7d826;
 pub const MELEE_ATTACK: u64 = 0x258890651a33d5d;
+pub const MONSTER_ABILITY: u64 = 0xc47a9e03d15b6f28;
+pub const MONSTER_DROPS: u64 = 0x8e2d4b6f19a07c53;
+pub const CONFUSION: u64 = 0x3b91d6e07a5c2f84;
+pub const SPAWN_FRIENDLY: u64 = 0xa6f2083d5c9e41b7;
+pub const GENERATE_TERRAIN: u64 = 0x7d14e9b2c86a3f05;
+pub const SPAWN_OBSTACLES: u64 = 0x1e6b93f0d27a58c4;
+pub const SPAWN_VAULT: u64 = 0x42c8a5e1f09d3b67;
+pub const GENERATE_QUESTS: u64 = 0xb83e0f5a7c2d9164;
+pub const PLACE_PREFABS: u64 = 0x6a0d2f93c5e8b417;
+pub const SPAWN_PREFAB_CONTENTS: u64 = 0xe51c7b082fa4d96e;
+pub const GENERATE_CORRIDORS: u64 = 0x93a6e1d84b0f725c;
+pub const GENERATE_LEVEL_SHAPE: u64 = 0x2c5f8e17b940a6d3;
@@ -1,43 +1,90 @@
+mod animation;
+mod attract;
 mod bitgrid;
+mod bones;
+mod chest;
 mod chunked;
 mod components;
+mod config;
 mod damage;
+mod diagnostics;
+mod durability;
+mod encumbrance;
+mod event;
 mod experience;
+mod faction;
+mod feeling;
+mod game_loop;
 mod gamekey;
 mod gamesym;
+mod hints;
 mod hunger;
+mod input_macro;
+mod inspect;
+mod interact;
 mod item;
+mod journal;
+mod locale;
 mod magicnum;
 mod map;
 mod menu_memory;
 mod message;
 mod modes;
 mod monster;
+mod notes;
+mod npc;
+mod obstacle;
+mod paths;
+mod pet;
 mod player;
+mod prefab;
+mod quest;
+mod recording;
 mod render;
+mod rng;
 mod saveload;
+mod screenshot;
+mod script;
+mod sound;
+mod spatial;
 mod spawn;
+mod spawn_tables;
+mod stats;
+mod status;
+mod terrain;
+mod tilesets;
+mod touch;
+mod tutorial;
 mod ui;
 mod vision;
 
 use serde::{Deserialize, Serialize};
 use shipyard::World;
-use std::{collections::HashMap, path::PathBuf};
 
 use crate::{
-    chunked::Camera,
-    experience::Difficulty,
-    gamesym::GameSym,
-    item::PickUpHint,
+    animation::Animations,
+    attract::Attract,
+    chunked::{Camera, FogStyle},
+    diagnostics::Diagnostics,
+    event::GameEvents,
+    experience::{Difficulty, DifficultySettings},
+    hints::Hints,
+    input_macro::InputMacros,
+    journal::Journal,
+    locale::Locale,
     map::Map,
     menu_memory::MenuMemory,
-    message::Messages,
-    modes::{title::TitleMode, ModeStack},
-    monster::MonsterTurns,
+    message::{Messages, MsgFilter},
+    modes::{dungeon::DungeonMode, title::TitleMode, ModeStack},
+    monster::{MonsterPaths, MonsterTurns},
     player::{PlayerAlive, PlayerId},
+    quest::Quests,
+    recording::Recorder,
+    stats::{RunStats, UnsavedProfile},
+    tutorial::Tutorial,
     ui::Options,
 };
-use ruggrogue::{RunSettings, TilesetInfo};
+use ruggrogue::{FramePacing, RenderPath, RunSettings};
 
 #[derive(Deserialize, Serialize)]
 pub struct GameSeed(u64);
@@ -57,66 +104,163 @@ extern "C" {
 }
 
 fn main() {
+    // Check a save file and exit without starting the game.
+    if std::env::args().nth(1).as_deref() == Some("--validate-save") {
+        match std::env::args().nth(2) {
+            Some(path) => {
+                if !saveload::validate_save(&path) {
+                    std::process::exit(1);
+                }
+            }
+            None => {
+                eprintln!("Usage: ruggrogue --validate-save <save file>");
+                std::process::exit(2);
+            }
+        }
+        return;
+    }
+
+    paths::init();
+
+    let spawn_tables = match spawn_tables::load_spawn_tables() {
+        Ok(spawn_tables) => spawn_tables,
+        Err(errors) => {
+            for e in errors {
+                eprintln!("Error: {}: {}", spawn_tables::SPAWN_TABLES_PATH, e);
+            }
+            std::process::exit(1);
+        }
+    };
     let world = World::new();
     let game_seed = std::env::args()
         .nth(1)
         .and_then(|arg| arg.as_str().parse().ok())
         .unwrap_or_else(rand::random);
 
-    world.add_unique(Options {
-        tileset: 2,
-        font: 0,
-        map_zoom: 1,
-        text_zoom: 1,
-    });
+    let (tileset_list, tileset_infos) = tilesets::discover_tilesets();
+    let symbol_registry = gamesym::load_symbol_registry();
+    let language_list = locale::discover_languages();
+    let options = config::load_options(
+        &tileset_list,
+        &language_list,
+        Options {
+            tileset: tileset_list.default_tileset(),
+            font: tileset_list.default_font(),
+            map_zoom: 1,
+            text_zoom: 1,
+            map_zoom_fit: false,
+            colorblind: false,
+            distinct_glyphs: false,
+            high_contrast: false,
+            fog_style: FogStyle::default(),
+            popup_numbers: true,
+            transitions: true,
+            msg_filter: MsgFilter::default(),
+            msg_dock_bottom: false,
+            msg_lines: 0,
+            autosave_turns: ui::default_autosave_turns(),
+            pause_on_focus_loss: false,
+            turn_per_item: false,
+            durability: true,
+            language: 0,
+            difficulty: DifficultySettings::default(),
+            starting_pet: false,
+            touch_controls: false,
+            record_sessions: false,
+            hints: true,
+            vsync: false,
+        },
+    );
+
+    let vsync = options.vsync;
+
+    world.add_unique(Locale::load(&language_list, options.language));
+    world.add_unique(options);
     world.add_unique(GameSeed(game_seed));
     world.add_unique(TurnCount(0));
     world.add_unique(Wins(0));
     world.add_unique(BaseEquipmentLevel(0));
+    world.add_unique(Animations::new());
+    world.add_unique(GameEvents::default());
     world.add_unique(Camera::new());
     world.add_unique(Difficulty::new(world.run(spawn::spawn_difficulty)));
     world.add_unique(MenuMemory::new());
+    world.add_unique(InputMacros::default());
     world.add_unique(Messages::new(100));
+    world.add_unique(Journal::default());
+    world.add_unique(Quests::default());
+    world.add_unique(RunStats::default());
+    world.add_unique(UnsavedProfile::default());
     world.add_unique(Map::new(80, 50));
-    world.add_unique(PickUpHint(true));
+    world.add_unique(Hints::load());
     world.add_unique(PlayerId(world.run(spawn::spawn_player)));
     world.add_unique(PlayerAlive(true));
     world.add_unique(MonsterTurns::new());
+    world.add_unique(MonsterPaths::default());
+    world.add_unique(Diagnostics::new());
+    world.add_unique(Recorder::new());
+    world.add_unique(Tutorial::new());
+    world.add_unique(Attract::new());
+    world.add_unique(tileset_list);
+    world.add_unique(language_list);
+    world.add_unique(symbol_registry.clone());
+    world.add_unique(npc::load_npc_defs());
+    world.add_unique(script::load_scripts());
+    world.add_unique(prefab::load_prefabs());
+    world.add_unique(spawn_tables);
 
-    let mut mode_stack = ModeStack::new(vec![TitleMode::new().into()]);
+    let mut mode_stack = ModeStack::new(vec![Box::new(TitleMode::new(&world))]);
 
     let settings = RunSettings {
         title: "RuggRogue".into(),
         window_size: (896, 560).into(),
         min_window_size: (640, 192).into(),
         fps: 30,
-        tileset_infos: vec![
-            TilesetInfo::<GameSym> {
-                image_path: PathBuf::from("assets/gohufont-8x14.png"),
-                tile_size: (8, 14).into(),
-                tile_start: (0, 0).into(),
-                tile_gap: (0, 0).into(),
-                font_map: TilesetInfo::<GameSym>::map_code_page_437(),
-                symbol_map: HashMap::new(),
-            },
-            TilesetInfo::<GameSym> {
-                image_path: PathBuf::from("assets/terminal-8x8.png"),
-                tile_size: (8, 8).into(),
-                tile_start: (0, 0).into(),
-                tile_gap: (0, 0).into(),
-                font_map: TilesetInfo::<GameSym>::map_code_page_437(),
-                symbol_map: HashMap::new(),
-            },
-            gamesym::urizen_tileset_info(),
-        ],
+        background_fps: 5,
+        tileset_infos,
+        symbol_registry,
+        // Software blitting is the bottleneck of the web build, so render on the GPU there.
+        render_path: if cfg!(target_os = "emscripten") {
+            RenderPath::Gpu
+        } else {
+            RenderPath::Software
+        },
+        vsync,
+        frame_pacing: FramePacing::Adaptive,
     };
 
-    ruggrogue::run(settings, |inputs, layers, tilesets, window_size| {
-        mode_stack.update(&world, inputs, layers, tilesets, window_size)
-    });
+    let result = ruggrogue::run(
+        settings,
+        |inputs, layers, tilesets, window_size, timing, requests| {
+            let run_control = diagnostics::update(
+                &world,
+                &mut mode_stack,
+                inputs,
+                layers,
+                tilesets,
+                window_size,
+                timing,
+            );
+
+            screenshot::update(
+                &world,
+                mode_stack.contains::<DungeonMode>(),
+                inputs,
+                tilesets,
+                requests,
+            );
+
+            run_control
+        },
+    );
 
     #[cfg(target_os = "emscripten")]
     unsafe {
         ruggrogue_sync_idbfs();
     }
+
+    if let Err(e) = result {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
 }
@@ -1,17 +1,19 @@
-use rand::{seq::IteratorRandom, Rng, SeedableRng};
-use rand_xoshiro::Xoshiro128PlusPlus as GameRng;
+use bitflags::bitflags;
+use rand::{seq::IteratorRandom, Rng};
 use serde::{Deserialize, Serialize};
-use shipyard::{EntityId, Get, UniqueView, UniqueViewMut, View, ViewMut, World};
-use std::{collections::HashMap, hash::Hasher};
-use wyhash::WyHash;
+use shipyard::{EntitiesView, EntityId, Get, UniqueView, UniqueViewMut, View, ViewMut, World};
+use std::collections::HashMap;
 
 use crate::{
     bitgrid::BitGrid,
     components::{Coord, Experience, FieldOfView, Item, Monster, Name, Player},
     experience::Difficulty,
     gamesym::GameSym,
-    magicnum,
+    locale::Locale,
     player::PlayerId,
+    prefab::{self, PrefabMark, Prefabs},
+    rng::RngStream,
+    spatial::SpatialIndex,
     GameSeed,
 };
 use ruggrogue::util::Color;
@@ -24,6 +26,24 @@ pub enum Tile {
     Wall,
     #[serde(rename = "D")]
     DownStairs,
+    #[serde(rename = "S")]
+    ShallowWater,
+    #[serde(rename = "P")]
+    DeepWater,
+    #[serde(rename = "L")]
+    Lava,
+    #[serde(rename = "C")]
+    Chasm,
+    #[serde(rename = "+")]
+    LockedDoor,
+}
+
+impl Tile {
+    /// True for tiles that can be entered but are dangerous or awkward to be in, which monsters and
+    /// pathfinding steer clear of.
+    pub fn is_hazard(&self) -> bool {
+        matches!(self, Tile::DeepWater | Tile::Lava | Tile::Chasm)
+    }
 }
 
 impl std::fmt::Display for Tile {
@@ -35,11 +55,37 @@ impl std::fmt::Display for Tile {
                 Tile::Floor => "Floor",
                 Tile::Wall => "Wall",
                 Tile::DownStairs => "Down Stairs",
+                Tile::ShallowWater => "Shallow Water",
+                Tile::DeepWater => "Deep Water",
+                Tile::Lava => "Lava",
+                Tile::Chasm => "Chasm",
+                Tile::LockedDoor => "Locked Door",
             }
         )
     }
 }
 
+bitflags! {
+    /// Marks left on a tile by things that happened on it, shown as tints of its background.
+    pub struct TileFlags: u8 {
+        /// Something was hurt here.
+        const BLOOD = 0b0001;
+        /// A fireball went off here.
+        const SCORCH = 0b0010;
+        /// Cobwebs cover this tile.
+        const WEB = 0b0100;
+        /// The player last saw an item lying here.
+        const ITEM_SEEN = 0b1000;
+    }
+}
+
+/// How an item looked when the player last saw it, drawn in its place while it's out of view.
+#[derive(Clone, Copy, Eq, PartialEq, Deserialize, Serialize)]
+pub struct Remembered {
+    pub sym: GameSym,
+    pub fg: Color,
+}
+
 #[derive(Clone, Copy, Deserialize, Serialize)]
 pub struct Rect {
     pub x1: i32,
@@ -69,6 +115,10 @@ impl Rect {
             && other.y1 - margin <= self.y2
     }
 
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        x >= self.x1 && x <= self.x2 && y >= self.y1 && y <= self.y2
+    }
+
     pub fn center(&self) -> (i32, i32) {
         (
             (self.x2 - self.x1) / 2 + self.x1,
@@ -90,16 +140,33 @@ pub struct Map {
     pub height: i32,
     #[serde(with = "crate::saveload::run_length_encoded")]
     tiles: Vec<Tile>,
+    /// [TileFlags] bits for each tile, which may be empty for maps saved before flags existed.
+    #[serde(default, with = "crate::saveload::run_length_encoded")]
+    flags: Vec<u8>,
     pub rooms: Vec<Rect>,
+    /// Tiles that make up the playable part of the map, or the whole map if this is None.  Tiles
+    /// outside of it are solid rock that is never shown or seen through.
+    #[serde(default)]
+    pub playable: Option<BitGrid>,
+    /// A locked room full of loot that's kept apart from the other rooms.
+    #[serde(default)]
+    pub vault: Option<Rect>,
     pub seen: BitGrid,
+    /// Items the player last saw at explored positions.
+    #[serde(default, with = "crate::saveload::pos_map")]
+    remembered: HashMap<(i32, i32), Remembered>,
 
-    // (x, y) -> (blocking_entity_count, entities_here)
+    /// Spots of prefabs stamped into the map that need something spawned at them.
     #[serde(skip)]
-    tile_entities: HashMap<(i32, i32), (i32, Vec<EntityId>)>,
+    pub prefab_marks: Vec<((i32, i32), PrefabMark)>,
 
-    // zero-length non-zero-capacity vectors for reuse in tile_entities
+    /// Entities at each position, filled in as entities are placed, e.g. after loading.
     #[serde(skip)]
-    empty_entity_vecs: Vec<Vec<EntityId>>,
+    spatial: SpatialIndex,
+
+    // tiles changed by [Map::change_tile] that haven't been redrawn yet
+    #[serde(skip)]
+    changed_tiles: Vec<(i32, i32)>,
 }
 
 impl Map {
@@ -111,10 +178,15 @@ impl Map {
             width,
             height,
             tiles: vec![Tile::Floor; (width * height) as usize],
+            flags: vec![0; (width * height) as usize],
             rooms: Vec::new(),
+            playable: None,
+            vault: None,
             seen: BitGrid::new(width, height),
-            tile_entities: HashMap::new(),
-            empty_entity_vecs: Vec::new(),
+            remembered: HashMap::new(),
+            prefab_marks: Vec::new(),
+            spatial: SpatialIndex::new(width, height),
+            changed_tiles: Vec::new(),
         }
     }
 
@@ -123,19 +195,74 @@ impl Map {
         self.width = replacement.width;
         self.height = replacement.height;
         self.tiles = replacement.tiles;
+        self.flags = replacement.flags;
         self.rooms = replacement.rooms;
+        self.playable = replacement.playable;
+        self.vault = replacement.vault;
         self.seen = replacement.seen;
-        self.tile_entities = replacement.tile_entities;
-        self.empty_entity_vecs = replacement.empty_entity_vecs;
+        self.remembered = replacement.remembered;
+        self.prefab_marks = replacement.prefab_marks;
+        self.spatial = replacement.spatial;
+        self.changed_tiles = replacement.changed_tiles;
     }
 
     pub fn clear(&mut self) {
         self.tiles.clear();
         self.tiles
             .resize((self.width * self.height) as usize, Tile::Floor);
+        self.flags.clear();
+        self.flags.resize((self.width * self.height) as usize, 0);
         self.rooms.clear();
+        self.playable = None;
+        self.vault = None;
         self.seen.zero_out_bits();
-        self.tile_entities.clear();
+        self.remembered.clear();
+        self.prefab_marks.clear();
+        self.spatial.reset(self.width, self.height);
+        self.changed_tiles.clear();
+    }
+
+    /// Change the size of the map, clearing it in the process.
+    pub fn resize(&mut self, width: i32, height: i32) {
+        assert!(width > 0 && height > 0);
+
+        self.width = width;
+        self.height = height;
+        self.seen = BitGrid::new(width, height);
+        self.clear();
+    }
+
+    /// True if a position is inside of the playable part of the map.
+    #[inline]
+    pub fn is_playable(&self, x: i32, y: i32) -> bool {
+        if let Some(playable) = &self.playable {
+            playable.get_bit(x, y)
+        } else {
+            x >= 0 && y >= 0 && x < self.width && y < self.height
+        }
+    }
+
+    /// The smallest rectangle that holds the playable part of the map.
+    pub fn playable_rect(&self) -> Rect {
+        let mut rect = Rect {
+            x1: self.width - 1,
+            y1: self.height - 1,
+            x2: 0,
+            y2: 0,
+        };
+
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if self.is_playable(x, y) {
+                    rect.x1 = rect.x1.min(x);
+                    rect.y1 = rect.y1.min(y);
+                    rect.x2 = rect.x2.max(x);
+                    rect.y2 = rect.y2.max(y);
+                }
+            }
+        }
+
+        rect
     }
 
     #[inline]
@@ -154,6 +281,97 @@ impl Map {
         self.tiles[idx] = tile;
     }
 
+    /// Change a tile during play, keeping track of it so that it can be redrawn; see
+    /// [Map::take_changed_tiles].
+    pub fn change_tile(&mut self, x: i32, y: i32, tile: Tile) {
+        self.set_tile(x, y, tile);
+        self.changed_tiles.push((x, y));
+    }
+
+    #[inline]
+    pub fn get_flags(&self, x: i32, y: i32) -> TileFlags {
+        self.flags
+            .get(self.index(x, y))
+            .map_or(TileFlags::empty(), |&bits| {
+                TileFlags::from_bits_truncate(bits)
+            })
+    }
+
+    /// Add or remove flags of a tile, keeping track of it to be redrawn if they changed, like
+    /// [Map::change_tile].
+    pub fn set_flags(&mut self, x: i32, y: i32, flags: TileFlags, value: bool) {
+        let idx = self.index(x, y);
+
+        if self.flags.len() != self.tiles.len() {
+            self.flags.resize(self.tiles.len(), 0);
+        }
+
+        let mut new_flags = TileFlags::from_bits_truncate(self.flags[idx]);
+
+        new_flags.set(flags, value);
+        if new_flags.bits() != self.flags[idx] {
+            self.flags[idx] = new_flags.bits();
+            self.changed_tiles.push((x, y));
+        }
+    }
+
+    /// The item the player remembers seeing at a position, if any.
+    pub fn remembered_at(&self, x: i32, y: i32) -> Option<&Remembered> {
+        self.remembered.get(&(x, y))
+    }
+
+    /// Remember how an item at a position looked, or forget about it if there's nothing there,
+    /// keeping track of it to be redrawn if that changed.
+    /// Change the symbol of every remembered item, e.g. to match up dynamic symbols after loading.
+    pub fn map_remembered_syms<F: Fn(GameSym) -> GameSym>(&mut self, f: F) {
+        for remembered in self.remembered.values_mut() {
+            remembered.sym = f(remembered.sym);
+        }
+    }
+
+    pub fn remember(&mut self, x: i32, y: i32, remembered: Option<Remembered>) {
+        let old = match remembered {
+            Some(remembered) => self.remembered.insert((x, y), remembered),
+            None => self.remembered.remove(&(x, y)),
+        };
+
+        if old != remembered {
+            self.changed_tiles.push((x, y));
+        }
+    }
+
+    /// Background color of a tile, tinted by the marks left on it.  Remembered items only show up
+    /// on tiles that are out of view.
+    pub fn tile_bg(&self, x: i32, y: i32, in_view: bool) -> Color {
+        let flags = self.get_flags(x, y);
+
+        if !in_view && flags.contains(TileFlags::ITEM_SEEN) {
+            Color { r: 0, g: 30, b: 70 }
+        } else if flags.contains(TileFlags::BLOOD) {
+            Color { r: 70, g: 0, b: 0 }
+        } else if flags.contains(TileFlags::SCORCH) {
+            Color {
+                r: 45,
+                g: 35,
+                b: 25,
+            }
+        } else if flags.contains(TileFlags::WEB) {
+            Color {
+                r: 50,
+                g: 50,
+                b: 60,
+            }
+        } else {
+            Color::BLACK
+        }
+    }
+
+    /// Take the positions of tiles changed by [Map::change_tile] since the last time this was
+    /// called.
+    pub fn take_changed_tiles(&mut self) -> Vec<(i32, i32)> {
+        std::mem::take(&mut self.changed_tiles)
+    }
+
     pub fn set_rect(&mut self, rect: &Rect, tile: Tile) {
         assert!(rect.x1 >= 0 && rect.x2 < self.width);
         assert!(rect.y1 >= 0 && rect.y2 < self.height);
@@ -198,6 +416,13 @@ impl Map {
             || matches!(self.get_tile(x, y), Tile::Wall)
     }
 
+    /// True if a wall, a locked door or a blocking entity prevents moving into a tile, unlike
+    /// [ruggrogue::PathableMap::is_blocked], which also avoids hazards.
+    pub fn is_impassable(&self, x: i32, y: i32) -> bool {
+        matches!(self.get_tile(x, y), &Tile::Wall | &Tile::LockedDoor)
+            || self.spatial.is_blocked((x, y))
+    }
+
     #[allow(clippy::many_single_char_names)]
     fn wall_sym(&self, x: i32, y: i32) -> GameSym {
         let n = self.wall_or_oob(x, y - 1);
@@ -247,6 +472,76 @@ impl Map {
         }
     }
 
+    /// Symbol and color of a map tile, whether or not it's been seen.
+    pub fn tile_appearance(&self, x: i32, y: i32) -> (GameSym, Color) {
+        match self.get_tile(x, y) {
+            Tile::Floor => (
+                GameSym::Floor,
+                Color {
+                    r: 102,
+                    g: 102,
+                    b: 102,
+                },
+            ),
+            Tile::Wall => (
+                self.wall_sym(x, y),
+                Color {
+                    r: 134,
+                    g: 77,
+                    b: 20,
+                },
+            ),
+            Tile::DownStairs => (
+                GameSym::DownStairs,
+                Color {
+                    r: 255,
+                    g: 255,
+                    b: 0,
+                },
+            ),
+            Tile::ShallowWater => (
+                GameSym::ShallowWater,
+                Color {
+                    r: 90,
+                    g: 160,
+                    b: 255,
+                },
+            ),
+            Tile::DeepWater => (
+                GameSym::DeepWater,
+                Color {
+                    r: 30,
+                    g: 60,
+                    b: 210,
+                },
+            ),
+            Tile::Lava => (
+                GameSym::Lava,
+                Color {
+                    r: 255,
+                    g: 80,
+                    b: 0,
+                },
+            ),
+            Tile::Chasm => (
+                GameSym::Chasm,
+                Color {
+                    r: 70,
+                    g: 70,
+                    b: 90,
+                },
+            ),
+            Tile::LockedDoor => (
+                GameSym::LockedDoor,
+                Color {
+                    r: 230,
+                    g: 190,
+                    b: 60,
+                },
+            ),
+        }
+    }
+
     pub fn iter_bounds(
         &self,
         x1: i32,
@@ -262,37 +557,8 @@ impl Map {
             std::iter::repeat(y).zip(xs)
         })
         .map(move |(y, x)| {
-            if self.seen.get_bit(x, y) {
-                (
-                    x,
-                    y,
-                    Some(match self.get_tile(x, y) {
-                        Tile::Floor => (
-                            GameSym::Floor,
-                            Color {
-                                r: 102,
-                                g: 102,
-                                b: 102,
-                            },
-                        ),
-                        Tile::Wall => (
-                            self.wall_sym(x, y),
-                            Color {
-                                r: 134,
-                                g: 77,
-                                b: 20,
-                            },
-                        ),
-                        Tile::DownStairs => (
-                            GameSym::DownStairs,
-                            Color {
-                                r: 255,
-                                g: 255,
-                                b: 0,
-                            },
-                        ),
-                    }),
-                )
+            if self.seen.get_bit(x, y) && self.is_playable(x, y) {
+                (x, y, Some(self.tile_appearance(x, y)))
             } else {
                 (x, y, None)
             }
@@ -300,36 +566,16 @@ impl Map {
     }
 
     pub fn place_entity(&mut self, entity: EntityId, pos: (i32, i32), blocks: bool) {
-        if let Some((block_count, entities_here)) = self.tile_entities.get_mut(&pos) {
-            if blocks {
-                *block_count += 1;
-            }
-            entities_here.push(entity);
-        } else {
-            let mut entities_here = self.empty_entity_vecs.pop().unwrap_or_default();
-
-            entities_here.push(entity);
-            self.tile_entities
-                .insert(pos, (if blocks { 1 } else { 0 }, entities_here));
+        // A map that was just loaded has yet to index any entities.
+        if !self.spatial.fits(self.width, self.height) {
+            self.spatial.reset(self.width, self.height);
         }
+
+        self.spatial.insert(entity, pos, blocks);
     }
 
     pub fn remove_entity(&mut self, entity: EntityId, pos: (i32, i32), blocks: bool) {
-        if self.tile_entities.get(&pos).unwrap().1.len() == 1 {
-            let (_, mut entities_here) = self.tile_entities.remove(&pos).unwrap();
-
-            assert!(entities_here[0] == entity);
-            entities_here.clear();
-            self.empty_entity_vecs.push(entities_here);
-        } else {
-            let (block_count, entities_here) = self.tile_entities.get_mut(&pos).unwrap();
-            let idx = entities_here.iter().position(|e| *e == entity).unwrap();
-
-            entities_here.remove(idx);
-            if blocks {
-                *block_count -= 1;
-            }
-        }
+        self.spatial.remove(entity, pos, blocks);
     }
 
     pub fn move_entity(
@@ -339,25 +585,46 @@ impl Map {
         new_pos: (i32, i32),
         blocks: bool,
     ) {
-        if self.tile_entities.get(&old_pos).unwrap().1.len() == 1
-            && !self.tile_entities.contains_key(&new_pos)
-        {
-            let data = self.tile_entities.remove(&old_pos).unwrap();
+        self.spatial.move_entity(entity, old_pos, new_pos, blocks);
+    }
 
-            assert!(data.1[0] == entity);
-            self.tile_entities.insert(new_pos, data);
-        } else {
-            self.remove_entity(entity, old_pos, blocks);
-            self.place_entity(entity, new_pos, blocks);
-        }
+    /// Trade the positions of two entities, e.g. when the player displaces a friendly.
+    pub fn swap_entities(
+        &mut self,
+        (a, a_pos, a_blocks): (EntityId, (i32, i32), bool),
+        (b, b_pos, b_blocks): (EntityId, (i32, i32), bool),
+    ) {
+        self.remove_entity(a, a_pos, a_blocks);
+        self.remove_entity(b, b_pos, b_blocks);
+        self.place_entity(a, b_pos, a_blocks);
+        self.place_entity(b, a_pos, b_blocks);
     }
 
     pub fn iter_entities_at(&self, x: i32, y: i32) -> impl Iterator<Item = EntityId> + '_ {
-        self.tile_entities
-            .get(&(x, y))
-            .map(|(_, es)| es.iter().copied())
-            .into_iter()
-            .flatten()
+        self.spatial.entities_at((x, y))
+    }
+
+    /// Every entity within `radius` tiles of a position along with where it is.
+    pub fn iter_entities_within(
+        &self,
+        center: (i32, i32),
+        radius: i32,
+    ) -> impl Iterator<Item = (EntityId, (i32, i32))> + '_ {
+        self.spatial.within_radius(center, radius)
+    }
+
+    /// The closest entity within `max_radius` tiles of a position that satisfies the predicate,
+    /// along with where it is; see [SpatialIndex::nearest].
+    pub fn nearest_entity<F>(
+        &self,
+        center: (i32, i32),
+        max_radius: i32,
+        pred: F,
+    ) -> Option<(EntityId, (i32, i32))>
+    where
+        F: FnMut(EntityId, (i32, i32)) -> bool,
+    {
+        self.spatial.nearest(center, max_radius, pred)
     }
 
     /// Describe a position on the map from the perspective of the player.
@@ -373,6 +640,8 @@ impl Map {
         omit_player: bool,
         omit_boring_tiles: bool,
     ) -> (String, bool) {
+        let locale = world.borrow::<UniqueView<Locale>>();
+
         if self.seen.get_bit(x, y) {
             let in_player_fov = {
                 let player_id = world.borrow::<UniqueView<PlayerId>>();
@@ -409,7 +678,9 @@ impl Map {
                         let more_items_count = items_at_pos.count();
 
                         if more_items_count > 0 {
-                            desc_vec.push(format!("{} items", more_items_count + 1));
+                            desc_vec.push(
+                                locale.format("map.items", &[("count", &(more_items_count + 1))]),
+                            );
                         } else {
                             desc_vec.push(names.get(item).0.clone());
                         }
@@ -421,16 +692,16 @@ impl Map {
                         || !omit_boring_tiles
                         || !matches!(tile, Tile::Floor | Tile::Wall)
                     {
-                        desc_vec.push(tile.to_string());
+                        desc_vec.push(locale.name(&tile.to_string()));
                     }
                 }
 
                 (desc_vec.join(", "), false)
             } else {
-                (self.get_tile(x, y).to_string(), true)
+                (locale.name(&self.get_tile(x, y).to_string()), true)
             }
         } else {
-            ("nothing".to_string(), true)
+            (locale.get("map.nothing").to_string(), true)
         }
     }
 }
@@ -443,17 +714,309 @@ impl ruggrogue::BoundedMap for Map {
 
 impl ruggrogue::ViewableField for Map {
     fn is_opaque(&self, x: i32, y: i32) -> bool {
-        matches!(self.get_tile(x, y), Tile::Wall)
+        !self.is_playable(x, y) || matches!(self.get_tile(x, y), Tile::Wall | Tile::LockedDoor)
     }
 }
 
 impl ruggrogue::PathableMap for Map {
+    /// Hazards count as blocked so that monsters and paths steer clear of them; see
+    /// [Map::is_impassable] for whether something can physically move into a tile.
     fn is_blocked(&self, x: i32, y: i32) -> bool {
-        matches!(self.get_tile(x, y), &Tile::Wall)
-            || self
-                .tile_entities
-                .get(&(x, y))
-                .map_or(false, |(block_count, _)| *block_count > 0)
+        self.get_tile(x, y).is_hazard() || self.is_impassable(x, y)
+    }
+}
+
+/// Fill the middle of some rooms with pools of water or lava, or chasms down to the next depth.
+///
+/// Features leave a ring of floor around the edge of their room so that they never cut off the way
+/// through it, and the first and last rooms are left alone to keep the arrival point and the way
+/// down clear.
+fn add_terrain_features<R: Rng>(map: &mut Map, rng: &mut R, allow_chasms: bool) {
+    for i in 1..map.rooms.len().saturating_sub(1) {
+        if !rng.gen_ratio(1, 5) {
+            continue;
+        }
+
+        let room = map.rooms[i];
+        let max_w = room.x2 - room.x1 - 1;
+        let max_h = room.y2 - room.y1 - 1;
+
+        if max_w < 2 || max_h < 2 {
+            continue;
+        }
+
+        let w = rng.gen_range(2..=max_w);
+        let h = rng.gen_range(2..=max_h);
+        let x = rng.gen_range(room.x1 + 1..=room.x2 - w);
+        let y = rng.gen_range(room.y1 + 1..=room.y2 - h);
+        let feature = Rect::new(x, y, w, h);
+
+        match rng.gen_range(0..4) {
+            0 if map.depth >= 3 => map.set_rect(&feature, Tile::Lava),
+            1 if map.depth >= 2 && allow_chasms => map.set_rect(&feature, Tile::Chasm),
+            _ => {
+                // Water is shallow around the edges, and small pools are shallow throughout.
+                map.set_rect(&feature, Tile::ShallowWater);
+                if w > 2 && h > 2 {
+                    map.set_rect(&Rect::new(x + 1, y + 1, w - 2, h - 2), Tile::DeepWater);
+                }
+            }
+        }
+    }
+}
+
+/// Try to carve out a small vault in solid rock, sealed off by a locked door that opens onto floor
+/// outside of it.  No vault is added if a spot can't be found for one.
+fn add_vault<R: Rng>(map: &mut Map, rng: &mut R) {
+    const ATTEMPTS: usize = 50;
+
+    for _ in 0..ATTEMPTS {
+        let w = rng.gen_range(2..=4);
+        let h = rng.gen_range(2..=4);
+        // Keep the walls of the vault off of the edges of the map.
+        let x = rng.gen_range(2..=map.width - w - 2);
+        let y = rng.gen_range(2..=map.height - h - 2);
+        let walls = Rect::new(x - 1, y - 1, w + 2, h + 2);
+
+        if walls
+            .iter_xy()
+            .any(|(wx, wy)| !map.is_playable(wx, wy) || !matches!(map.get_tile(wx, wy), Tile::Wall))
+        {
+            continue;
+        }
+
+        // The door goes in a side wall, but not a corner, with floor right outside of it.
+        let door = walls
+            .iter_xy()
+            .filter_map(|(wx, wy)| {
+                let outside = if wy == walls.y1 || wy == walls.y2 {
+                    if wx == walls.x1 || wx == walls.x2 {
+                        return None;
+                    }
+                    (wx, if wy == walls.y1 { wy - 1 } else { wy + 1 })
+                } else if wx == walls.x1 {
+                    (wx - 1, wy)
+                } else if wx == walls.x2 {
+                    (wx + 1, wy)
+                } else {
+                    return None;
+                };
+
+                if matches!(map.get_tile(outside.0, outside.1), Tile::Floor) {
+                    Some((wx, wy))
+                } else {
+                    None
+                }
+            })
+            .choose(rng);
+
+        if let Some((door_x, door_y)) = door {
+            let vault = Rect::new(x, y, w, h);
+
+            map.set_rect(&vault, Tile::Floor);
+            map.set_tile(door_x, door_y, Tile::LockedDoor);
+            map.vault = Some(vault);
+            return;
+        }
+    }
+}
+
+/// Hang cobwebs in the corners of some rooms, other than the first.
+fn add_webs<R: Rng>(map: &mut Map, rng: &mut R) {
+    for i in 1..map.rooms.len() {
+        if !rng.gen_ratio(1, 4) {
+            continue;
+        }
+
+        let room = map.rooms[i];
+        let (x, dx) = if rng.gen() {
+            (room.x1, 1)
+        } else {
+            (room.x2, -1)
+        };
+        let (y, dy) = if rng.gen() {
+            (room.y1, 1)
+        } else {
+            (room.y2, -1)
+        };
+
+        for &(wx, wy) in &[(x, y), (x + dx, y), (x, y + dy)] {
+            if matches!(map.get_tile(wx, wy), Tile::Floor) {
+                let idx = map.index(wx, wy);
+                map.flags[idx] |= TileFlags::WEB.bits();
+            }
+        }
+    }
+}
+
+/// Width and height of the map at a depth.  Levels grow larger the deeper they go, while the level
+/// with the present on it is a small arena.
+pub fn level_size(depth: i32, has_way_down: bool) -> (i32, i32) {
+    if !has_way_down {
+        return (48, 32);
+    }
+
+    let growth = (depth - 1).max(0) / 4;
+
+    ((80 + 8 * growth).min(120), (50 + 4 * growth).min(70))
+}
+
+/// The outline of the playable part of a level.
+#[derive(Clone, Copy)]
+enum LevelShape {
+    /// The whole map.
+    Rectangle,
+    /// An oval touching the middle of each edge of the map.
+    Oval,
+    /// A wide band across the middle of the map crossed by a tall one.
+    Cross,
+}
+
+impl LevelShape {
+    fn pick<R: Rng>(rng: &mut R, depth: i32, has_way_down: bool) -> Self {
+        if !has_way_down {
+            return LevelShape::Oval;
+        }
+        if depth <= 1 {
+            return LevelShape::Rectangle;
+        }
+
+        match rng.gen_range(0..4) {
+            0 => LevelShape::Oval,
+            1 => LevelShape::Cross,
+            _ => LevelShape::Rectangle,
+        }
+    }
+
+    /// Tiles of a map of the given size inside of the shape, or None if it covers the whole map.
+    fn mask(self, width: i32, height: i32) -> Option<BitGrid> {
+        let mut mask = BitGrid::new(width, height);
+
+        match self {
+            LevelShape::Rectangle => return None,
+            LevelShape::Oval => {
+                // Scale everything up by two to measure from the center of each tile.
+                let (rx, ry) = (width as i64, height as i64);
+
+                for y in 0..height {
+                    for x in 0..width {
+                        let dx = (2 * x + 1) as i64 - rx;
+                        let dy = (2 * y + 1) as i64 - ry;
+
+                        if dx * dx * ry * ry + dy * dy * rx * rx <= rx * rx * ry * ry {
+                            mask.set_bit(x, y, true);
+                        }
+                    }
+                }
+            }
+            LevelShape::Cross => {
+                mask.fill_rect(0, height / 5, width, height - 2 * (height / 5), true);
+                mask.fill_rect(width / 4, 0, width - 2 * (width / 4), height, true);
+            }
+        }
+
+        Some(mask)
+    }
+}
+
+/// How the corridors between rooms of a level are dug out.
+#[derive(Clone, Copy)]
+enum CorridorStyle {
+    /// Straight lines with a single bend.
+    Straight,
+    /// Tunnels that wander about on their way to the other room.
+    Winding,
+    /// Like straight corridors, but two tiles wide.
+    Wide,
+}
+
+impl CorridorStyle {
+    fn pick<R: Rng>(rng: &mut R, depth: i32) -> Self {
+        // The first level always has plain corridors to ease new players in.
+        if depth <= 1 {
+            return CorridorStyle::Straight;
+        }
+
+        match rng.gen_range(0..4) {
+            0 => CorridorStyle::Winding,
+            1 => CorridorStyle::Wide,
+            _ => CorridorStyle::Straight,
+        }
+    }
+}
+
+/// Dig a tunnel from one point to another that staggers about randomly, but leans towards where
+/// it's going.  Tunnels that wander for too long are finished off with a straight corridor.
+fn dig_winding_tunnel<R: Rng>(map: &mut Map, rng: &mut R, from: (i32, i32), to: (i32, i32)) {
+    let (mut x, mut y) = from;
+    let max_steps = ((to.0 - x).abs() + (to.1 - y).abs()) * 4;
+
+    map.set_tile(x, y, Tile::Floor);
+
+    for _ in 0..max_steps {
+        if (x, y) == to {
+            return;
+        }
+
+        let (dx, dy) = if rng.gen_ratio(2, 3) {
+            // Step towards the destination along either axis that gets closer to it.
+            if to.0 != x && (to.1 == y || rng.gen::<bool>()) {
+                ((to.0 - x).signum(), 0)
+            } else {
+                (0, (to.1 - y).signum())
+            }
+        } else {
+            [(-1, 0), (1, 0), (0, -1), (0, 1)][rng.gen_range(0..4)]
+        };
+
+        // Keep a solid wall around the edges of the map.
+        x = (x + dx).max(1).min(map.width - 2);
+        y = (y + dy).max(1).min(map.height - 2);
+        map.set_tile(x, y, Tile::Floor);
+    }
+
+    map.set_hline(x, to.0, y, Tile::Floor);
+    map.set_vline(y, to.1, to.0, Tile::Floor);
+}
+
+/// Connect the centers of two rooms with a corridor of the given style.
+fn connect_rooms<R: Rng>(
+    map: &mut Map,
+    rng: &mut R,
+    style: CorridorStyle,
+    r1: usize,
+    r2: usize,
+    h_then_v: bool,
+) {
+    let (r1x, r1y) = map.rooms[r1].center();
+    let (r2x, r2y) = map.rooms[r2].center();
+
+    match style {
+        CorridorStyle::Straight | CorridorStyle::Wide => {
+            // Wide corridors are a second straight corridor right next to the first, kept off of
+            // the edges of the map.
+            let offsets: &[i32] = if matches!(style, CorridorStyle::Wide) {
+                &[0, 1]
+            } else {
+                &[0]
+            };
+
+            for offset in offsets {
+                let y1 = (r1y + offset).min(map.height - 2);
+                let y2 = (r2y + offset).min(map.height - 2);
+                let x1 = (r1x + offset).min(map.width - 2);
+                let x2 = (r2x + offset).min(map.width - 2);
+
+                if h_then_v {
+                    map.set_hline(r2x, r1x, y2, Tile::Floor);
+                    map.set_vline(r2y, r1y, x1, Tile::Floor);
+                } else {
+                    map.set_vline(r2y, r1y, x2, Tile::Floor);
+                    map.set_hline(r2x, r1x, y1, Tile::Floor);
+                }
+            }
+        }
+        CorridorStyle::Winding => dig_winding_tunnel(map, rng, (r2x, r2y), (r1x, r1y)),
     }
 }
 
@@ -462,45 +1025,75 @@ pub fn generate_rooms_and_corridors(
     difficulty: UniqueView<Difficulty>,
     game_seed: UniqueView<GameSeed>,
     mut map: UniqueViewMut<Map>,
+    prefabs: UniqueView<Prefabs>,
     exps: View<Experience>,
 ) -> Option<(i32, i32)> {
-    {
-        let w = map.width;
-        let h = map.height;
-        map.set_rect(&Rect::new(0, 0, w, h), Tile::Wall);
-    }
+    // The level with the present on it has no chasms to skip past it.
+    let has_way_down = exps.get(difficulty.id).level < 25;
 
-    let mut rng = {
-        let mut hasher = WyHash::with_seed(magicnum::GENERATE_ROOMS_AND_CORRIDORS);
-        hasher.write_u64(game_seed.0);
-        hasher.write_i32(map.depth);
-        GameRng::seed_from_u64(hasher.finish())
-    };
+    generate_map(&mut map, &game_seed, &prefabs, has_way_down)
+}
 
-    for _ in 0..30 {
+/// Lay out the rooms, corridors and terrain of a cleared map at its current depth, following only
+/// from the game seed and the given prefabs.
+///
+/// If `has_way_down` is false, no down stairs are placed, and the position where they would have
+/// gone is returned instead for the victory item.
+pub fn generate_map(
+    map: &mut Map,
+    game_seed: &GameSeed,
+    prefabs: &Prefabs,
+    has_way_down: bool,
+) -> Option<(i32, i32)> {
+    let (w, h) = level_size(map.depth, has_way_down);
+
+    map.resize(w, h);
+    map.set_rect(&Rect::new(0, 0, w, h), Tile::Wall);
+    map.playable = LevelShape::pick(
+        &mut game_seed
+            .fork(RngStream::LevelShape)
+            .mix_i32(map.depth)
+            .rng(),
+        map.depth,
+        has_way_down,
+    )
+    .mask(w, h);
+
+    let mut rng = game_seed.fork(RngStream::MapGen).mix_i32(map.depth).rng();
+    // Shaped levels turn down more rooms, so make up for it with more tries.
+    let attempts = if map.playable.is_some() { 100 } else { 30 };
+
+    for _ in 0..attempts {
         let w: i32 = rng.gen_range(6i32..15i32);
         let h: i32 = rng.gen_range(6i32..11i32);
         let x: i32 = rng.gen_range(1i32..map.width - w - 1);
         let y: i32 = rng.gen_range(1i32..map.height - h - 1);
         let new_room = Rect::new(x, y, w, h);
 
-        if !map.rooms.iter().any(|r| new_room.intersects(r, 1)) {
+        if Rect::new(x - 1, y - 1, w + 2, h + 2)
+            .iter_xy()
+            .all(|(rx, ry)| map.is_playable(rx, ry))
+            && !map.rooms.iter().any(|r| new_room.intersects(r, 1))
+        {
             map.set_rect(&new_room, Tile::Floor);
             map.rooms.push(new_room);
         }
     }
 
-    let connect_rooms = |map: &mut UniqueViewMut<Map>, r1: usize, r2: usize, h_then_v: bool| {
-        let (r1x, r1y) = map.rooms[r1].center();
-        let (r2x, r2y) = map.rooms[r2].center();
-        if h_then_v {
-            map.set_hline(r2x, r1x, r2y, Tile::Floor);
-            map.set_vline(r2y, r1y, r1x, Tile::Floor);
-        } else {
-            map.set_vline(r2y, r1y, r2x, Tile::Floor);
-            map.set_hline(r2x, r1x, r1y, Tile::Floor);
-        }
-    };
+    // Make sure there's somewhere to start, even if no rooms fit the shape of the level.
+    if map.rooms.is_empty() {
+        let new_room = Rect::new(w / 2 - 3, h / 2 - 3, 6, 6);
+
+        map.set_rect(&new_room, Tile::Floor);
+        map.rooms.push(new_room);
+    }
+
+    // Corridor styles come from a stream of their own so that room layouts stay the same.
+    let mut corridor_rng = game_seed
+        .fork(RngStream::Corridors)
+        .mix_i32(map.depth)
+        .rng();
+    let style = CorridorStyle::pick(&mut corridor_rng, map.depth);
     let mut connected: Vec<usize> = Vec::new();
     let mut disconnected: Vec<usize> = Vec::new();
 
@@ -529,7 +1122,9 @@ pub fn generate_rooms_and_corridors(
 
         // Connect the closest connected and disconnected rooms together.
         connect_rooms(
-            &mut map,
+            map,
+            &mut corridor_rng,
+            style,
             connected[closest_connected],
             disconnected[closest_disconnected],
             rng.gen::<bool>(),
@@ -551,13 +1146,48 @@ pub fn generate_rooms_and_corridors(
         .chunks_exact(2)
         .zip(&extra_corridors)
     {
-        connect_rooms(&mut map, extra_rooms[0], extra_rooms[1], *extra_corridor);
+        connect_rooms(
+            map,
+            &mut corridor_rng,
+            style,
+            extra_rooms[0],
+            extra_rooms[1],
+            *extra_corridor,
+        );
+    }
+
+    let mut terrain_rng = game_seed.fork(RngStream::Terrain).mix_i32(map.depth).rng();
+
+    add_terrain_features(map, &mut terrain_rng, has_way_down);
+
+    let mut prefab_rng = game_seed.fork(RngStream::Prefabs).mix_i32(map.depth).rng();
+
+    prefab::add_prefabs(map, &mut prefab_rng, prefabs, has_way_down);
+    if map.depth >= 2 && terrain_rng.gen_ratio(1, 3) {
+        add_vault(map, &mut terrain_rng);
+    }
+    add_webs(map, &mut terrain_rng);
+
+    // Corridors may stray outside of the shape of the level, so widen it to take them in, along
+    // with the walls around everything that isn't solid rock.
+    if let Some(mut playable) = map.playable.take() {
+        let mut dug = BitGrid::new(w, h);
+
+        for y in 0..h {
+            for x in 0..w {
+                if !matches!(map.get_tile(x, y), Tile::Wall) {
+                    dug.fill_rect(x - 1, y - 1, 3, 3, true);
+                }
+            }
+        }
+        playable.union_with(&dug);
+        map.playable = Some(playable);
     }
 
     if let Some(last_room) = map.rooms.last() {
         let (center_x, center_y) = last_room.center();
 
-        if exps.get(difficulty.id).level < 25 {
+        if has_way_down {
             map.set_tile(center_x, center_y, Tile::DownStairs);
             None
         } else {
@@ -568,6 +1198,28 @@ pub fn generate_rooms_and_corridors(
     }
 }
 
+/// Panic if the entities at each position of the map don't match where they think they are, to
+/// catch anything that moves, adds or removes an entity without telling the map.
+pub fn check_entity_positions(map: UniqueView<Map>, entities: EntitiesView, coords: View<Coord>) {
+    map.spatial.check_consistency();
+
+    for y in 0..map.height {
+        for x in 0..map.width {
+            for id in map.iter_entities_at(x, y) {
+                assert!(entities.is_alive(id), "dead entity at ({}, {})", x, y);
+                assert!(
+                    coords
+                        .try_get(id)
+                        .is_ok_and(|coord| coord.0 == (x, y).into()),
+                    "entity at ({}, {}) thinks it's somewhere else",
+                    x,
+                    y,
+                );
+            }
+        }
+    }
+}
+
 pub fn place_player_in_first_room(
     mut map: UniqueViewMut<Map>,
     player_id: UniqueView<PlayerId>,
@@ -579,3 +1231,256 @@ pub fn place_player_in_first_room(
     map.place_entity(player_id.0, room_center, false);
     player_coord.0 = room_center.into();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Seeds checked on every test run, across the early depths where terrain, prefabs, corridor
+    /// styles and level shapes all start to appear, and a few deeper ones where levels grow larger.
+    const SEEDS: u64 = 20;
+    /// Enough seeds to turn up rare layouts, checked by the ignored `map_sweep` test.
+    const SWEEP_SEEDS: u64 = 2000;
+    const DEPTHS: [i32; 5] = [1, 2, 3, 5, 9];
+
+    fn generate(prefabs: &Prefabs, seed: u64, depth: i32, has_way_down: bool) -> Map {
+        let mut map = Map::new(80, 50);
+
+        map.depth = depth;
+        generate_map(&mut map, &GameSeed(seed), prefabs, has_way_down);
+        map
+    }
+
+    /// Tiles that can be walked across without a key or stepping into a hazard.
+    fn is_safe(map: &Map, x: i32, y: i32) -> bool {
+        !map.wall_or_oob(x, y)
+            && !matches!(map.get_tile(x, y), Tile::LockedDoor)
+            && !map.get_tile(x, y).is_hazard()
+    }
+
+    /// Flood fill safe tiles from the center of the first room.
+    fn reachable(map: &Map) -> BitGrid {
+        let mut reached = BitGrid::new(map.width, map.height);
+        let mut stack = vec![map.rooms[0].center()];
+
+        reached.set_bit(stack[0].0, stack[0].1, true);
+        while let Some((x, y)) = stack.pop() {
+            for (nx, ny) in [(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)]
+                .iter()
+                .copied()
+            {
+                if is_safe(map, nx, ny) && !reached.get_bit(nx, ny) {
+                    reached.set_bit(nx, ny, true);
+                    stack.push((nx, ny));
+                }
+            }
+        }
+
+        reached
+    }
+
+    fn for_each_map<F: FnMut(u64, &Map)>(seeds: u64, mut f: F) {
+        let prefabs = prefab::load_prefabs();
+
+        for seed in 0..seeds {
+            for &depth in DEPTHS.iter() {
+                f(seed, &generate(&prefabs, seed, depth, true));
+            }
+        }
+    }
+
+    fn check_rooms_connected(seed: u64, map: &Map) {
+        let reached = reachable(map);
+
+        assert!(!map.rooms.is_empty(), "seed {} has no rooms", seed);
+        for room in &map.rooms {
+            assert!(
+                room.iter_xy().any(|(x, y)| reached.get_bit(x, y)),
+                "seed {} depth {}: room at ({}, {}) is cut off",
+                seed,
+                map.depth,
+                room.x1,
+                room.y1,
+            );
+        }
+        for y in 0..map.height {
+            for x in 0..map.width {
+                let in_vault = map.vault.iter().any(|v| v.contains(x, y));
+
+                assert!(
+                    in_vault || !is_safe(map, x, y) || reached.get_bit(x, y),
+                    "seed {} depth {}: ({}, {}) is cut off",
+                    seed,
+                    map.depth,
+                    x,
+                    y,
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn all_rooms_are_connected() {
+        for_each_map(SEEDS, check_rooms_connected);
+    }
+
+    fn check_open_tiles_playable(seed: u64, map: &Map) {
+        assert_eq!(
+            (map.width, map.height),
+            level_size(map.depth, true),
+            "seed {} depth {}",
+            seed,
+            map.depth,
+        );
+        for y in 0..map.height {
+            for x in 0..map.width {
+                let walled_in = (y - 1..=y + 1)
+                    .flat_map(|ny| (x - 1..=x + 1).map(move |nx| (nx, ny)))
+                    .all(|(nx, ny)| map.wall_or_oob(nx, ny));
+
+                assert!(
+                    walled_in || map.is_playable(x, y),
+                    "seed {} depth {}: ({}, {}) is outside of the playable area",
+                    seed,
+                    map.depth,
+                    x,
+                    y,
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn open_tiles_are_playable() {
+        for_each_map(SEEDS, check_open_tiles_playable);
+    }
+
+    fn check_stairs_reachable(seed: u64, map: &Map) {
+        let reached = reachable(map);
+        let stairs = (0..map.height)
+            .flat_map(|y| (0..map.width).map(move |x| (x, y)))
+            .filter(|&(x, y)| matches!(map.get_tile(x, y), Tile::DownStairs))
+            .collect::<Vec<_>>();
+
+        assert_eq!(stairs.len(), 1, "seed {} depth {}", seed, map.depth);
+        assert!(
+            reached.get_bit(stairs[0].0, stairs[0].1),
+            "seed {} depth {}: stairs can't be reached",
+            seed,
+            map.depth,
+        );
+    }
+
+    #[test]
+    fn stairs_are_reachable() {
+        for_each_map(SEEDS, check_stairs_reachable);
+    }
+
+    #[test]
+    fn victory_level_has_no_way_down() {
+        let prefabs = prefab::load_prefabs();
+
+        for seed in 0..SEEDS {
+            let mut map = Map::new(80, 50);
+
+            map.depth = 12;
+            let victory_pos =
+                generate_map(&mut map, &GameSeed(seed), &prefabs, false).expect("victory position");
+
+            assert_eq!((map.width, map.height), level_size(map.depth, false));
+            assert!(map.rooms.len() >= 2, "seed {} has too few rooms", seed);
+            assert!(reachable(&map).get_bit(victory_pos.0, victory_pos.1));
+            assert!(!map
+                .tiles
+                .iter()
+                .any(|t| matches!(t, Tile::DownStairs | Tile::Chasm)));
+        }
+    }
+
+    #[test]
+    fn same_seed_same_map() {
+        let prefabs = prefab::load_prefabs();
+
+        for seed in 0..SEEDS {
+            let a = generate(&prefabs, seed, 3, true);
+            let b = generate(&prefabs, seed, 3, true);
+
+            assert!(a.tiles == b.tiles, "seed {} differs between runs", seed);
+        }
+    }
+
+    fn check_tiles_round_trip(seed: u64, map: &Map) {
+        let json = serde_json::to_string(map).unwrap();
+        let loaded: Map = serde_json::from_str(&json).unwrap();
+
+        assert!(
+            loaded.tiles == map.tiles && loaded.flags == map.flags,
+            "seed {} depth {}",
+            seed,
+            map.depth
+        );
+        assert_eq!(loaded.rooms.len(), map.rooms.len());
+        assert_eq!(
+            loaded.vault.map(|v| (v.x1, v.y1, v.x2, v.y2)),
+            map.vault.map(|v| (v.x1, v.y1, v.x2, v.y2)),
+        );
+    }
+
+    #[test]
+    fn tiles_round_trip_through_serialization() {
+        for_each_map(SEEDS, check_tiles_round_trip);
+    }
+
+    /// Check thousands of maps for the rare layouts that the default seeds miss.
+    ///
+    /// Run with `cargo test --release -- --ignored --nocapture map_sweep`.
+    #[test]
+    #[ignore]
+    fn map_sweep() {
+        for_each_map(SWEEP_SEEDS, |seed, map| {
+            check_rooms_connected(seed, map);
+            check_open_tiles_playable(seed, map);
+            check_stairs_reachable(seed, map);
+            check_tiles_round_trip(seed, map);
+        });
+    }
+
+    #[test]
+    fn maps_saved_without_flags_load_with_none() {
+        let prefabs = prefab::load_prefabs();
+        let mut map = generate(&prefabs, 1, 2, true);
+
+        map.flags.clear();
+
+        let json = serde_json::to_string(&map).unwrap();
+        let mut loaded: Map = serde_json::from_str(&json).unwrap();
+        let (x, y) = loaded.rooms[0].center();
+
+        assert!(loaded.get_flags(x, y).is_empty());
+        loaded.set_flags(x, y, TileFlags::BLOOD, true);
+        assert_eq!(loaded.flags.len(), loaded.tiles.len());
+        assert_eq!(loaded.get_flags(x, y), TileFlags::BLOOD);
+        assert_eq!(loaded.take_changed_tiles(), vec![(x, y)]);
+    }
+
+    #[test]
+    fn remembered_items_survive_saving() {
+        let prefabs = prefab::load_prefabs();
+        let mut map = generate(&prefabs, 1, 2, true);
+        let (x, y) = map.rooms[0].center();
+        let remembered = Remembered {
+            sym: GameSym::Ration,
+            fg: Color::BROWN,
+        };
+
+        map.remember(x, y, Some(remembered));
+        map.remember(x + 1, y, Some(remembered));
+        map.remember(x + 1, y, None);
+
+        let json = serde_json::to_string(&map).unwrap();
+        let loaded: Map = serde_json::from_str(&json).unwrap();
+
+        assert!(loaded.remembered_at(x, y) == Some(&remembered));
+        assert!(loaded.remembered_at(x + 1, y).is_none());
+    }
+}
@@ -3,11 +3,14 @@ use std::ops::{Index, IndexMut};
 use ruggrogue::util::Position;
 
 pub struct MenuMemory {
-    menu: [i32; 7],
+    menu: [i32; 8],
     pub pick_up_pos: Position,
+    /// Text that the inventory list was last filtered by.
+    pub inventory_filter: String,
 }
 
 impl MenuMemory {
+    /// Position in the full inventory, even when the list is filtered.
     pub const INVENTORY: usize = 0;
     pub const INVENTORY_SHORTCUT_EQUIP: usize = 1;
     pub const INVENTORY_SHORTCUT_USE: usize = 2;
@@ -15,11 +18,14 @@ impl MenuMemory {
     pub const EQUIPMENT_SHORTCUT_REMOVE: usize = 4;
     pub const EQUIPMENT_SHORTCUT_DROP: usize = 5;
     pub const PICK_UP: usize = 6;
+    /// Index into [crate::item::SortOrder::ALL] rather than a menu position.
+    pub const INVENTORY_SORT: usize = 7;
 
     pub fn new() -> Self {
         Self {
-            menu: [0; 7],
+            menu: [0; 8],
             pick_up_pos: Position { x: 0, y: 0 },
+            inventory_filter: String::new(),
         }
     }
 
@@ -28,6 +34,7 @@ impl MenuMemory {
             *m = 0;
         }
         self.pick_up_pos = Position { x: 0, y: 0 };
+        self.inventory_filter.clear();
     }
 }
 
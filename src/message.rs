@@ -1,12 +1,234 @@
 use serde::{Deserialize, Serialize};
-use std::collections::VecDeque;
+use std::{borrow::Cow, collections::VecDeque};
+
+use ruggrogue::{util::Color, WrapOptions};
+
+/// Colors that can be named in message markup.
+fn markup_color(name: &str) -> Option<Color> {
+    match name {
+        "white" => Some(Color::WHITE),
+        "gray" => Some(Color::GRAY),
+        "red" => Some(Color::RED),
+        "green" => Some(Color::GREEN),
+        "blue" => Some(Color::BLUE),
+        "yellow" => Some(Color::YELLOW),
+        "magenta" => Some(Color::MAGENTA),
+        "brown" => Some(Color::BROWN),
+        "cyan" => Some(Color::CYAN),
+        "orange" => Some(Color::ORANGE),
+        "purple" => Some(Color::PURPLE),
+        "pink" => Some(Color::PINK),
+        _ => None,
+    }
+}
+
+/// Split a message with inline markup into its plain text and the color of each of its chars,
+/// where `None` means the usual color of the message.
+///
+/// `{red}` starts coloring text red until `{/}`, and `{{` is a literal brace.  Anything else in
+/// braces is left alone.
+pub fn parse_markup(text: &str) -> (String, Vec<Option<Color>>) {
+    let mut plain = String::with_capacity(text.len());
+    let mut colors = Vec::with_capacity(text.len());
+    let mut color = None;
+    let mut rest = text;
+
+    while let Some(ch) = rest.chars().next() {
+        if ch == '{' {
+            if let Some(after) = rest.strip_prefix("{{") {
+                plain.push('{');
+                colors.push(color);
+                rest = after;
+                continue;
+            }
+
+            if let Some((tag, after)) = rest[1..].split_once('}') {
+                if tag == "/" {
+                    color = None;
+                    rest = after;
+                    continue;
+                } else if let Some(tag_color) = markup_color(tag) {
+                    color = Some(tag_color);
+                    rest = after;
+                    continue;
+                }
+            }
+        }
+
+        plain.push(ch);
+        colors.push(color);
+        rest = &rest[ch.len_utf8()..];
+    }
+
+    (plain, colors)
+}
+
+/// Word wrap text with inline markup into lines of chars paired with their colors, splitting long
+/// words with hyphens and breaking between CJK characters.
+pub fn wrap_markup(text: &str, width: usize) -> Vec<Vec<(char, Option<Color>)>> {
+    let (plain, colors) = parse_markup(text);
+    let styled = plain.chars().zip(colors).collect::<Vec<_>>();
+
+    ruggrogue::word_wrap_styled(
+        &styled,
+        width,
+        WrapOptions {
+            hyphenate: true,
+            break_cjk: true,
+        },
+    )
+}
+
+/// Remove markup from a message, for places that can only show plain text.
+pub fn strip_markup(text: &str) -> String {
+    parse_markup(text).0
+}
+
+/// The kind of event a message describes, used to color and filter messages.
+#[derive(Clone, Copy, Deserialize, Eq, PartialEq, Serialize)]
+pub enum MsgCategory {
+    Combat,
+    Item,
+    System,
+    Warning,
+}
+
+impl MsgCategory {
+    pub const ALL: [MsgCategory; 4] = [
+        MsgCategory::Combat,
+        MsgCategory::Item,
+        MsgCategory::System,
+        MsgCategory::Warning,
+    ];
+
+    /// Locale key of the name of this category.
+    pub fn name_key(self) -> &'static str {
+        match self {
+            MsgCategory::Combat => "category.combat",
+            MsgCategory::Item => "category.items",
+            MsgCategory::System => "category.system",
+            MsgCategory::Warning => "category.warnings",
+        }
+    }
+
+    /// Color of messages of this category at full brightness.
+    pub fn color(self) -> Color {
+        match self {
+            MsgCategory::Combat => Color {
+                r: 255,
+                g: 144,
+                b: 128,
+            },
+            MsgCategory::Item => Color {
+                r: 144,
+                g: 200,
+                b: 255,
+            },
+            MsgCategory::System => Color::WHITE,
+            MsgCategory::Warning => Color::YELLOW,
+        }
+    }
+
+    fn bit(self) -> u8 {
+        match self {
+            MsgCategory::Combat => 0b0001,
+            MsgCategory::Item => 0b0010,
+            MsgCategory::System => 0b0100,
+            MsgCategory::Warning => 0b1000,
+        }
+    }
+}
+
+/// A set of message categories to show.
+#[derive(Clone, Copy, Deserialize, Serialize)]
+pub struct MsgFilter(u8);
+
+impl Default for MsgFilter {
+    fn default() -> Self {
+        Self(MsgCategory::ALL.iter().fold(0, |bits, c| bits | c.bit()))
+    }
+}
+
+impl MsgFilter {
+    pub fn shows(self, category: MsgCategory) -> bool {
+        self.0 & category.bit() != 0
+    }
+
+    pub fn set(&mut self, category: MsgCategory, show: bool) {
+        if show {
+            self.0 |= category.bit();
+        } else {
+            self.0 &= !category.bit();
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(from = "SavedMessage")]
+struct Message {
+    text: String,
+    category: MsgCategory,
+    /// Number of times this message was repeated in a row.
+    count: u32,
+}
+
+fn one() -> u32 {
+    1
+}
+
+/// A message as found in a save file, which older versions of the game saved as plain text.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SavedMessage {
+    Text(String),
+    Message {
+        text: String,
+        category: MsgCategory,
+        #[serde(default = "one")]
+        count: u32,
+    },
+}
+
+impl From<SavedMessage> for Message {
+    fn from(saved: SavedMessage) -> Self {
+        match saved {
+            SavedMessage::Text(text) => Message {
+                text,
+                category: MsgCategory::System,
+                count: 1,
+            },
+            SavedMessage::Message {
+                text,
+                category,
+                count,
+            } => Message {
+                text,
+                category,
+                count,
+            },
+        }
+    }
+}
+
+impl Message {
+    fn display(&self) -> Cow<'_, str> {
+        if self.count > 1 {
+            Cow::Owned(format!("{} (x{})", self.text, self.count))
+        } else {
+            Cow::Borrowed(&self.text)
+        }
+    }
+}
 
 #[derive(Deserialize, Serialize)]
 pub struct Messages {
     capacity: u16,
-    msg_queue: VecDeque<String>,
+    msg_queue: VecDeque<Message>,
     num_highlighted: usize,
     want_separator: bool,
+    /// Set when a warning is added, until checked with [Messages::take_warned].
+    #[serde(skip)]
+    warned: bool,
 }
 
 impl Messages {
@@ -18,6 +240,7 @@ impl Messages {
             msg_queue: VecDeque::with_capacity(capacity as usize),
             num_highlighted: 0,
             want_separator: false,
+            warned: false,
         }
     }
 
@@ -31,7 +254,30 @@ impl Messages {
         self.num_highlighted = 0;
     }
 
+    /// Add a general system message.
     pub fn add(&mut self, msg: String) {
+        self.add_as(MsgCategory::System, msg);
+    }
+
+    /// Add a message of the given category.
+    ///
+    /// A message identical to the one before it in the same turn is coalesced into it instead of
+    /// being added again.
+    pub fn add_as(&mut self, category: MsgCategory, msg: String) {
+        if category == MsgCategory::Warning {
+            self.warned = true;
+        }
+
+        if !self.want_separator {
+            if let Some(last) = self.msg_queue.back_mut() {
+                if last.category == category && last.text == msg {
+                    last.count = last.count.saturating_add(1);
+                    self.num_highlighted = self.num_highlighted.max(1);
+                    return;
+                }
+            }
+        }
+
         let space_needed = if self.want_separator { 2 } else { 1 };
 
         if self.msg_queue.len() + space_needed >= self.capacity as usize {
@@ -42,11 +288,19 @@ impl Messages {
         }
 
         if self.want_separator {
-            self.msg_queue.push_back("".to_string());
+            self.msg_queue.push_back(Message {
+                text: "".to_string(),
+                category: MsgCategory::System,
+                count: 1,
+            });
             self.want_separator = false;
         }
 
-        self.msg_queue.push_back(msg);
+        self.msg_queue.push_back(Message {
+            text: msg,
+            category,
+            count: 1,
+        });
         self.num_highlighted = self.num_highlighted.saturating_add(1);
     }
 
@@ -54,16 +308,88 @@ impl Messages {
         self.want_separator = true;
     }
 
-    /// Returns an iterator over messages in reverse order, each with a highlight flag.
-    pub fn rev_iter(&self) -> impl Iterator<Item = (&str, bool)> {
+    /// Returns an iterator over messages in reverse order, each with its category and a highlight
+    /// flag.  Empty messages separate groups of messages from different turns.
+    pub fn rev_iter(&self) -> impl Iterator<Item = (Cow<'_, str>, MsgCategory, bool)> {
         self.msg_queue
             .iter()
             .rev()
             .enumerate()
-            .map(move |(i, s)| (s.as_str(), i < self.num_highlighted))
+            .map(move |(i, m)| (m.display(), m.category, i < self.num_highlighted))
+    }
+
+    /// Messages of the most recent turn as plain text, oldest first.
+    pub fn last_turn(&self) -> Vec<String> {
+        let mut msgs = self
+            .msg_queue
+            .iter()
+            .rev()
+            .take_while(|m| !m.text.is_empty())
+            .map(|m| strip_markup(&m.display()))
+            .collect::<Vec<_>>();
+
+        msgs.reverse();
+        msgs
+    }
+
+    /// Returns true if a warning was added since the last call.
+    pub fn take_warned(&mut self) -> bool {
+        std::mem::take(&mut self.warned)
     }
 
     pub fn reset_highlight(&mut self) {
         self.num_highlighted = 0;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repeated_messages_coalesce_within_a_turn_only() {
+        let mut messages = Messages::new(100);
+
+        messages.add("You hit the Bat.".to_string());
+        messages.add("You hit the Bat.".to_string());
+        messages.separator();
+        messages.add("You hit the Bat.".to_string());
+        messages.add("The Bat dies.".to_string());
+
+        assert_eq!(
+            messages.last_turn(),
+            ["You hit the Bat.".to_string(), "The Bat dies.".to_string()]
+        );
+        assert_eq!(
+            messages
+                .rev_iter()
+                .map(|(text, _, _)| text.into_owned())
+                .collect::<Vec<_>>(),
+            [
+                "The Bat dies.",
+                "You hit the Bat.",
+                "",
+                "You hit the Bat. (x2)"
+            ]
+        );
+    }
+
+    #[test]
+    fn old_plain_text_messages_still_load() {
+        let messages: Messages = serde_json::from_str(
+            r#"{"capacity":100,"msg_queue":["Hello!",{"text":"Ouch!","category":"Combat"}],"num_highlighted":0,"want_separator":false}"#,
+        )
+        .unwrap();
+        let msgs = messages
+            .rev_iter()
+            .map(|(text, category, _)| (text.into_owned(), category))
+            .collect::<Vec<_>>();
+
+        assert!(
+            msgs == [
+                ("Ouch!".to_string(), MsgCategory::Combat),
+                ("Hello!".to_string(), MsgCategory::System),
+            ]
+        );
+    }
+}
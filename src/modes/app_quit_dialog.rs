@@ -1,11 +1,11 @@
-use shipyard::World;
+use shipyard::{UniqueView, World};
 
-use crate::gamesym::GameSym;
+use crate::{gamesym::GameSym, locale::Locale};
 use ruggrogue::{util::Size, InputBuffer, TileGrid, Tileset};
 
 use super::{
     yes_no_dialog::{YesNoDialogMode, YesNoDialogModeResult},
-    ModeControl, ModeResult, ModeUpdate,
+    Mode, ModeControl, ModeResult, ModeUpdate,
 };
 
 pub enum AppQuitDialogModeResult {
@@ -17,15 +17,20 @@ pub struct AppQuitDialogMode(YesNoDialogMode);
 
 /// A yes-or-no dialog box that appears when the use requests that the app be closed.
 impl AppQuitDialogMode {
-    pub fn new() -> Self {
+    pub fn new(world: &World) -> Self {
         Self(YesNoDialogMode::new(
-            "Really quit RuggRogue?".to_string(),
+            world
+                .borrow::<UniqueView<Locale>>()
+                .get("app_quit.prompt")
+                .to_string(),
             false,
         ))
     }
+}
 
-    pub fn prepare_grids(
-        &self,
+impl Mode<World, GameSym> for AppQuitDialogMode {
+    fn prepare_grids(
+        &mut self,
         world: &World,
         grids: &mut Vec<TileGrid<GameSym>>,
         tilesets: &[Tileset<GameSym>],
@@ -34,7 +39,7 @@ impl AppQuitDialogMode {
         self.0.prepare_grids(world, grids, tilesets, window_size);
     }
 
-    pub fn update(
+    fn update(
         &mut self,
         world: &World,
         inputs: &mut InputBuffer,
@@ -42,24 +47,27 @@ impl AppQuitDialogMode {
         pop_result: &Option<ModeResult>,
     ) -> (ModeControl, ModeUpdate) {
         match self.0.update(world, inputs, grids, pop_result) {
-            (ModeControl::Pop(ModeResult::YesNoDialogModeResult(result)), mode_update) => {
-                match result {
-                    YesNoDialogModeResult::AppQuit => (ModeControl::Stay, ModeUpdate::WaitForEvent),
-                    YesNoDialogModeResult::Yes => (
-                        ModeControl::Pop(AppQuitDialogModeResult::Confirmed.into()),
+            (ModeControl::Pop(result), mode_update) => {
+                match result.downcast_ref::<YesNoDialogModeResult>() {
+                    Some(YesNoDialogModeResult::AppQuit) => {
+                        (ModeControl::Stay, ModeUpdate::WaitForEvent)
+                    }
+                    Some(YesNoDialogModeResult::Yes) => (
+                        ModeControl::Pop(Box::new(AppQuitDialogModeResult::Confirmed)),
                         mode_update,
                     ),
-                    YesNoDialogModeResult::No => (
-                        ModeControl::Pop(AppQuitDialogModeResult::Cancelled.into()),
+                    Some(YesNoDialogModeResult::No) => (
+                        ModeControl::Pop(Box::new(AppQuitDialogModeResult::Cancelled)),
                         mode_update,
                     ),
+                    None => (ModeControl::Pop(result), mode_update),
                 }
             }
             result => result,
         }
     }
 
-    pub fn draw(&self, world: &World, grids: &mut [TileGrid<GameSym>], active: bool) {
+    fn draw(&mut self, world: &World, grids: &mut [TileGrid<GameSym>], active: bool) {
         self.0.draw(world, grids, active);
     }
 }
@@ -0,0 +1,244 @@
+use shipyard::{UniqueView, World};
+
+use crate::{
+    components::Faction,
+    experience,
+    gamekey::{self, GameKey},
+    gamesym::GameSym,
+    locale::Locale,
+    spawn_tables::{SpawnEntry, SpawnTables},
+    stats::{self, RunStats},
+    ui::{self, Options},
+};
+use ruggrogue::{
+    util::{Color, Size},
+    InputBuffer, InputEvent, KeyMods, TileGrid, Tileset,
+};
+
+use super::{Mode, ModeControl, ModeResult, ModeUpdate};
+
+const TEXT_WIDTH: usize = 56;
+
+pub enum BestiaryModeResult {
+    AppQuit,
+    Done,
+}
+
+pub struct BestiaryMode {
+    lines: Vec<(String, Color)>,
+    scroll_row: i32,
+}
+
+/// Keys of things worth knowing about how a monster behaves, worked out from its spawn table
+/// entry.
+fn abilities(entry: &SpawnEntry, tables: &SpawnTables) -> Vec<&'static str> {
+    let drops = entry.drops.unwrap_or(tables.default_drops);
+    let mut abilities = Vec::new();
+
+    if entry.faction == Faction::Wildlife {
+        abilities.push("bestiary.wildlife");
+    }
+    if drops.gold_chance >= 50 {
+        abilities.push("bestiary.carries_gold");
+    }
+    if drops.consumable_chance >= 25 {
+        abilities.push("bestiary.carries_items");
+    }
+    if drops.equipment_chance >= 10 {
+        abilities.push("bestiary.carries_equipment");
+    }
+
+    abilities
+}
+
+/// Show every kind of monster, revealing its description once it's been seen, and its stats,
+/// abilities and kill counts once it's been killed, in this run or any earlier one.
+impl BestiaryMode {
+    pub fn new(world: &World) -> Self {
+        let tables = world.borrow::<UniqueView<SpawnTables>>();
+        let locale = world.borrow::<UniqueView<Locale>>();
+        let run_stats = world.borrow::<UniqueView<RunStats>>();
+        let profile = stats::current_profile(world);
+        let mut entries = Vec::new();
+        let mut discovered = 0;
+
+        for (i, entry) in tables.monsters.iter().enumerate() {
+            let run_kills = run_stats.kills_of(&entry.name);
+            let kills = run_kills + profile.kills.get(&entry.name).copied().unwrap_or(0);
+            let seen =
+                kills > 0 || run_stats.has_seen(&entry.name) || profile.seen.contains(&entry.name);
+
+            entries.push((String::new(), Color::WHITE));
+
+            if !seen {
+                entries.push(("???".to_string(), Color::GRAY));
+                continue;
+            }
+
+            discovered += 1;
+            entries.push((entry.name.clone(), entry.fg.into()));
+            for line in ruggrogue::word_wrap(&entry.desc, TEXT_WIDTH - 2) {
+                entries.push((format!("  {}", line), Color::WHITE));
+            }
+
+            if kills == 0 {
+                entries.push((
+                    format!("  {}", locale.get("bestiary.kill_to_learn")),
+                    Color::GRAY,
+                ));
+                continue;
+            }
+
+            // Monsters show up at the level of their place in the spawn table or higher.
+            let level = i as i32 + 1;
+            entries.push((
+                format!(
+                    "  {}",
+                    locale.format(
+                        "bestiary.stats",
+                        &[
+                            ("level", &level),
+                            ("hp", &experience::calc_monster_max_hp(level)),
+                            (
+                                "attack",
+                                &format!("{:.0}", experience::calc_monster_attack(level)),
+                            ),
+                            (
+                                "defense",
+                                &format!("{:.0}", experience::calc_monster_defense(level)),
+                            ),
+                            ("exp", &experience::calc_monster_exp(level)),
+                        ],
+                    )
+                ),
+                Color::LIGHT_GRAY,
+            ));
+            for ability in abilities(entry, &tables) {
+                for (j, line) in
+                    ruggrogue::word_wrap(locale.get(ability), TEXT_WIDTH - 4).enumerate()
+                {
+                    let bullet = if j == 0 { "* " } else { "  " };
+                    entries.push((format!("  {}{}", bullet, line), Color::LIGHT_GRAY));
+                }
+            }
+            entries.push((
+                format!(
+                    "  {}",
+                    locale.format(
+                        "bestiary.killed",
+                        &[("kills", &kills), ("run_kills", &run_kills)],
+                    )
+                ),
+                Color::YELLOW,
+            ));
+        }
+
+        let mut lines = vec![(
+            locale.format(
+                "bestiary.discovered",
+                &[("count", &discovered), ("total", &tables.monsters.len())],
+            ),
+            Color::YELLOW,
+        )];
+        lines.extend(entries);
+
+        Self {
+            lines,
+            scroll_row: 0,
+        }
+    }
+}
+
+impl Mode<World, GameSym> for BestiaryMode {
+    fn prepare_grids(
+        &mut self,
+        world: &World,
+        grids: &mut Vec<TileGrid<GameSym>>,
+        tilesets: &[Tileset<GameSym>],
+        window_size: Size,
+    ) {
+        let Options {
+            font, text_zoom, ..
+        } = *world.borrow::<UniqueView<Options>>();
+        let new_size = Size {
+            w: TEXT_WIDTH as u32 + 4,
+            h: self.lines.len() as u32 + 4,
+        };
+
+        if !grids.is_empty() {
+            grids[0].resize(new_size);
+        } else {
+            grids.push(TileGrid::new(new_size, tilesets, font as usize));
+            grids[0].view.clear_color = None;
+        }
+
+        grids[0].set_tileset(tilesets, font as usize);
+        grids[0].view_centered(tilesets, text_zoom, (0, 0).into(), window_size);
+        grids[0].view.zoom = text_zoom;
+    }
+
+    fn update(
+        &mut self,
+        _world: &World,
+        inputs: &mut InputBuffer,
+        grids: &[TileGrid<GameSym>],
+        _pop_result: &Option<ModeResult>,
+    ) -> (ModeControl, ModeUpdate) {
+        inputs.prepare_input();
+
+        if let Some(InputEvent::AppQuit) = inputs.get_input() {
+            return (
+                ModeControl::Pop(Box::new(BestiaryModeResult::AppQuit)),
+                ModeUpdate::Immediate,
+            );
+        } else if let Some(InputEvent::Press(keycode)) = inputs.get_input() {
+            let key = gamekey::from_keycode(keycode, inputs.get_mods(KeyMods::SHIFT));
+            if matches!(key, GameKey::Confirm | GameKey::Cancel | GameKey::Bestiary) {
+                inputs.clear_input();
+                return (
+                    ModeControl::Pop(Box::new(BestiaryModeResult::Done)),
+                    ModeUpdate::Immediate,
+                );
+            } else if let Some(grid) = grids.first() {
+                // Scroll a row at a time if the bestiary doesn't fit on screen.
+                let (first_row, last_row) = grid.view_visible_rows();
+                let half = (last_row - first_row) / 2;
+                let max_scroll = (grid.height() as i32 - 1 - half).max(half);
+
+                match key {
+                    GameKey::Up => {
+                        self.scroll_row = (self.scroll_row.min(max_scroll) - 1).max(half);
+                    }
+                    GameKey::Down => {
+                        self.scroll_row = (self.scroll_row.max(half) + 1).min(max_scroll);
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        (ModeControl::Stay, ModeUpdate::WaitForEvent)
+    }
+
+    fn draw(&mut self, world: &World, grids: &mut [TileGrid<GameSym>], active: bool) {
+        let grid = &mut grids[0];
+        let bg = Color::BLACK;
+
+        grid.view.color_mod = if active { Color::WHITE } else { Color::GRAY };
+
+        grid.draw_box((0, 0), (grid.width(), grid.height()), Color::WHITE, bg);
+        grid.print_color(
+            (2, 0),
+            world.borrow::<UniqueView<Locale>>().get("bestiary.title"),
+            true,
+            Color::YELLOW,
+            bg,
+        );
+
+        for (y, (line, fg)) in self.lines.iter().enumerate() {
+            grid.print_color((2, 2 + y as i32), line, true, *fg, bg);
+        }
+
+        ui::scroll_menu_grid(grid, self.scroll_row, Color::WHITE, bg);
+    }
+}
@@ -0,0 +1,456 @@
+use shipyard::{EntityId, Get, UniqueView, View, World};
+
+use crate::{
+    components::{Container, Inventory, Name, Renderable},
+    encumbrance,
+    gamekey::{self, GameKey},
+    gamesym::GameSym,
+    item,
+    locale::Locale,
+    player::PlayerId,
+    render,
+    ui::{self, Options},
+};
+use ruggrogue::{
+    util::{Color, Size},
+    InputBuffer, InputEvent, KeyMods, TileGrid, Tileset,
+};
+
+use super::{Mode, ModeControl, ModeResult, ModeUpdate};
+
+const CHEST_GRID: usize = 0;
+const PACK_GRID: usize = 1;
+
+pub enum ChestModeResult {
+    AppQuit,
+    Done,
+}
+
+enum SubSection {
+    Chest,
+    Pack,
+}
+
+pub struct ChestMode {
+    chest_id: EntityId,
+    subsection: SubSection,
+    chest_selection: i32,
+    pack_selection: i32,
+}
+
+/// Items in the chest, and items in the player's pack.
+fn item_lists(world: &World, chest_id: EntityId) -> (Vec<EntityId>, Vec<EntityId>) {
+    let player_id = world.borrow::<UniqueView<PlayerId>>();
+    let inventories = world.borrow::<View<Inventory>>();
+
+    (
+        inventories.get(chest_id).items.clone(),
+        inventories.get(player_id.0).items.clone(),
+    )
+}
+
+/// Show the contents of the storage chest above the player's pack, and allow items to be moved
+/// between them.
+impl ChestMode {
+    pub fn new(chest_id: EntityId) -> Self {
+        Self {
+            chest_id,
+            subsection: SubSection::Pack,
+            chest_selection: 0,
+            pack_selection: 0,
+        }
+    }
+
+    /// The height of the item list in the chest grid, whose bottom edge is shared with the pack.
+    fn chest_item_list_height(grid: &TileGrid<GameSym>) -> i32 {
+        grid.height().saturating_sub(3).max(1).min(i32::MAX as u32) as i32
+    }
+
+    /// The height of the item list in the pack grid.
+    fn pack_item_list_height(grid: &TileGrid<GameSym>) -> i32 {
+        grid.height().saturating_sub(4).max(1).min(i32::MAX as u32) as i32
+    }
+
+    /// Keep the selections within the lists after items move between them.
+    fn clamp_selections(&mut self, chest_len: usize, pack_len: usize) {
+        self.chest_selection = self.chest_selection.min(chest_len.saturating_sub(1) as i32);
+        self.pack_selection = self.pack_selection.min(pack_len.saturating_sub(1) as i32);
+    }
+
+    fn draw_item_list(
+        &self,
+        world: &World,
+        grid: &mut TileGrid<GameSym>,
+        items: &[EntityId],
+        list_height: i32,
+        selection: Option<i32>,
+    ) {
+        let fg = Color::WHITE;
+        let bg = Color::BLACK;
+        let options = world.borrow::<UniqueView<Options>>();
+        let names = world.borrow::<View<Name>>();
+        let renderables = world.borrow::<View<Renderable>>();
+        let item_x = 2;
+        let item_y = 2;
+
+        if items.is_empty() {
+            grid.print_color(
+                (item_x, item_y),
+                world
+                    .borrow::<UniqueView<Locale>>()
+                    .get("inventory.nothing"),
+                true,
+                fg,
+                if selection.is_some() {
+                    ui::SELECTED_BG
+                } else {
+                    bg
+                },
+            );
+            return;
+        }
+
+        let selected = selection.unwrap_or(0);
+        let item_offset = std::cmp::max(
+            0,
+            std::cmp::min(
+                items.len() as i32 - list_height,
+                selected - (list_height - 1) / 2,
+            ),
+        );
+
+        if items.len() as i32 > list_height {
+            grid.draw_bar(
+                true,
+                (grid.width() as i32 - 1, item_y),
+                list_height,
+                item_offset,
+                list_height,
+                items.len() as i32,
+                fg,
+                bg,
+            );
+        }
+
+        for (i, item_id) in items
+            .iter()
+            .enumerate()
+            .skip(item_offset as usize)
+            .take(list_height as usize)
+        {
+            render::put_renderable(
+                grid,
+                (item_x, item_y + i as i32 - item_offset),
+                renderables.get(*item_id),
+                &options,
+            );
+            grid.print_color(
+                (item_x + 2, item_y + i as i32 - item_offset),
+                &names.get(*item_id).0,
+                true,
+                fg,
+                if selection == Some(i as i32) {
+                    ui::SELECTED_BG
+                } else {
+                    bg
+                },
+            );
+        }
+    }
+
+    fn draw_chest(&self, world: &World, grid: &mut TileGrid<GameSym>, items: &[EntityId]) {
+        let fg = Color::WHITE;
+        let bg = Color::BLACK;
+        let title = format!(
+            "< {} ({}/{}) >",
+            world.borrow::<View<Name>>().get(self.chest_id).0,
+            items.len(),
+            world
+                .borrow::<View<Container>>()
+                .get(self.chest_id)
+                .capacity,
+        );
+
+        // Draw box with bottom edge off-grid.
+        grid.draw_box((0, 0), (grid.width(), grid.height() + 1), fg, bg);
+        grid.print_color((2, 0), &title, true, Color::YELLOW, bg);
+
+        let list_height = Self::chest_item_list_height(grid);
+        let selection = if matches!(self.subsection, SubSection::Chest) {
+            Some(self.chest_selection)
+        } else {
+            None
+        };
+
+        self.draw_item_list(world, grid, items, list_height, selection);
+    }
+
+    fn draw_pack(&self, world: &World, grid: &mut TileGrid<GameSym>, items: &[EntityId]) {
+        let fg = Color::WHITE;
+        let bg = Color::BLACK;
+
+        grid.draw_box((0, 0), (grid.width(), grid.height()), fg, bg);
+        grid.put_char_color((0, 0), '├', fg, bg);
+        grid.put_char_color((grid.width() as i32 - 1, 0), '┤', fg, bg);
+        let locale = world.borrow::<UniqueView<Locale>>();
+
+        grid.print_color(
+            (2, 0),
+            locale.get("inventory.title"),
+            true,
+            Color::YELLOW,
+            bg,
+        );
+
+        let player_id = world.borrow::<UniqueView<PlayerId>>().0;
+        if let Some((weight, capacity)) = encumbrance::weight_and_capacity(world, player_id) {
+            let weight_text = locale.format(
+                "inventory.weight",
+                &[("weight", &weight), ("capacity", &capacity)],
+            );
+            let weight_fg = if weight > capacity {
+                Color::ORANGE
+            } else {
+                Color::YELLOW
+            };
+
+            grid.print_color(
+                (
+                    grid.width() as i32 - 2 - weight_text.chars().count() as i32,
+                    0,
+                ),
+                &weight_text,
+                true,
+                weight_fg,
+                bg,
+            );
+        }
+
+        let list_height = Self::pack_item_list_height(grid);
+        let selection = if matches!(self.subsection, SubSection::Pack) {
+            Some(self.pack_selection)
+        } else {
+            None
+        };
+
+        self.draw_item_list(world, grid, items, list_height, selection);
+    }
+}
+
+impl Mode<World, GameSym> for ChestMode {
+    fn prepare_grids(
+        &mut self,
+        world: &World,
+        grids: &mut Vec<TileGrid<GameSym>>,
+        tilesets: &[Tileset<GameSym>],
+        window_size: Size,
+    ) {
+        let Options {
+            font, text_zoom, ..
+        } = *world.borrow::<UniqueView<Options>>();
+        let tileset = &tilesets.get(font as usize).unwrap_or(&tilesets[0]);
+        let (chest_items, pack_items) = item_lists(world, self.chest_id);
+        let main_width = {
+            let names = world.borrow::<View<Name>>();
+
+            chest_items
+                .iter()
+                .chain(pack_items.iter())
+                .map(|id| names.get(*id).0.chars().count() + 2)
+                .max()
+                .unwrap_or(0)
+                .max(30) as u32
+        };
+        let window_rows = window_size.h / (tileset.tile_height() * text_zoom);
+
+        // Split the rows between the chest and the pack, favoring the pack when space is short.
+        let chest_h = chest_items.len().max(1) as u32 + 3;
+        let pack_h = pack_items.len().max(1) as u32 + 4;
+        let (chest_h, pack_h) = if chest_h + pack_h <= window_rows {
+            (chest_h, pack_h)
+        } else {
+            let chest_h = chest_h.min((window_rows / 2).max(4));
+            (
+                chest_h,
+                pack_h.min(window_rows.saturating_sub(chest_h)).max(5),
+            )
+        };
+        let new_chest_size = Size {
+            w: 4 + main_width,
+            h: chest_h,
+        };
+        let new_pack_size = Size {
+            w: new_chest_size.w,
+            h: pack_h,
+        };
+
+        if !grids.is_empty() {
+            grids[CHEST_GRID].resize(new_chest_size);
+            grids[PACK_GRID].resize(new_pack_size);
+        } else {
+            grids.push(TileGrid::new(new_chest_size, tilesets, font as usize));
+            grids.push(TileGrid::new(new_pack_size, tilesets, font as usize));
+            grids[CHEST_GRID].view.clear_color = None;
+            grids[PACK_GRID].view.clear_color = None;
+            grids[CHEST_GRID].view.bg_alpha = ui::OVERLAY_BG_ALPHA;
+            grids[PACK_GRID].view.bg_alpha = ui::OVERLAY_BG_ALPHA;
+        }
+
+        let (chest_grid, grids) = grids.split_first_mut().unwrap(); // CHEST_GRID
+        let (pack_grid, _) = grids.split_first_mut().unwrap(); // PACK_GRID
+
+        // Set fonts.
+        chest_grid.set_tileset(tilesets, font as usize);
+        pack_grid.set_tileset(tilesets, font as usize);
+
+        // Calculate x and width of both grids.
+        chest_grid.view.size.w = new_chest_size.w * tileset.tile_width() * text_zoom;
+        chest_grid.view.pos.x = (window_size.w - chest_grid.view.size.w) as i32 / 2;
+        pack_grid.view.size.w = new_pack_size.w * tileset.tile_width() * text_zoom;
+        pack_grid.view.pos.x = chest_grid.view.pos.x;
+
+        // Calculate y and height of both grids, stacking the pack under the chest.
+        let combined_px_height =
+            (new_chest_size.h + new_pack_size.h) * tileset.tile_height() * text_zoom;
+        chest_grid.view.pos.y = window_size.h.saturating_sub(combined_px_height) as i32 / 2;
+        chest_grid.view.size.h = new_chest_size.h * tileset.tile_height() * text_zoom;
+        pack_grid.view.pos.y = chest_grid.view.pos.y + chest_grid.view.size.h as i32;
+        pack_grid.view.size.h = new_pack_size.h * tileset.tile_height() * text_zoom;
+
+        // Set all grids to current text zoom.
+        chest_grid.view.zoom = text_zoom;
+        pack_grid.view.zoom = text_zoom;
+    }
+
+    fn update(
+        &mut self,
+        world: &World,
+        inputs: &mut InputBuffer,
+        grids: &[TileGrid<GameSym>],
+        _pop_result: &Option<ModeResult>,
+    ) -> (ModeControl, ModeUpdate) {
+        let (chest_items, pack_items) = item_lists(world, self.chest_id);
+
+        inputs.prepare_input();
+
+        if let Some(InputEvent::AppQuit) = inputs.get_input() {
+            return (
+                ModeControl::Pop(Box::new(ChestModeResult::AppQuit)),
+                ModeUpdate::Immediate,
+            );
+        } else if let Some(InputEvent::Press(keycode)) = inputs.get_input() {
+            let shift = inputs.get_mods(KeyMods::SHIFT);
+            let (items, selection, list_height) = match self.subsection {
+                SubSection::Chest => (
+                    &chest_items,
+                    &mut self.chest_selection,
+                    grids.get(CHEST_GRID).map(Self::chest_item_list_height),
+                ),
+                SubSection::Pack => (
+                    &pack_items,
+                    &mut self.pack_selection,
+                    grids.get(PACK_GRID).map(Self::pack_item_list_height),
+                ),
+            };
+            let max_selection = (items.len() as i32 - 1).max(0);
+
+            match gamekey::from_keycode(keycode, shift) {
+                GameKey::Up => {
+                    if *selection > 0 {
+                        *selection -= 1;
+                    } else {
+                        // Wrap around to the bottom of the other list.
+                        self.subsection = match self.subsection {
+                            SubSection::Chest => {
+                                self.pack_selection = (pack_items.len() as i32 - 1).max(0);
+                                SubSection::Pack
+                            }
+                            SubSection::Pack => {
+                                self.chest_selection = (chest_items.len() as i32 - 1).max(0);
+                                SubSection::Chest
+                            }
+                        };
+                    }
+                }
+                GameKey::Down => {
+                    if *selection < max_selection {
+                        *selection += 1;
+                    } else {
+                        // Wrap around to the top of the other list.
+                        self.subsection = match self.subsection {
+                            SubSection::Chest => {
+                                self.pack_selection = 0;
+                                SubSection::Pack
+                            }
+                            SubSection::Pack => {
+                                self.chest_selection = 0;
+                                SubSection::Chest
+                            }
+                        };
+                    }
+                }
+                GameKey::PageUp => {
+                    if let Some(list_height) = list_height {
+                        *selection = selection.saturating_sub(list_height).max(0);
+                    }
+                }
+                GameKey::PageDown => {
+                    if let Some(list_height) = list_height {
+                        *selection = selection.saturating_add(list_height).min(max_selection);
+                    }
+                }
+                GameKey::Home => *selection = 0,
+                GameKey::End => *selection = max_selection,
+                GameKey::Left | GameKey::Right => {
+                    self.subsection = match self.subsection {
+                        SubSection::Chest => SubSection::Pack,
+                        SubSection::Pack => SubSection::Chest,
+                    };
+                }
+                GameKey::Confirm => {
+                    if let Some(&item_id) = items.get(*selection as usize) {
+                        let player_id = world.borrow::<UniqueView<PlayerId>>().0;
+
+                        match self.subsection {
+                            SubSection::Chest => {
+                                item::take_from_container(world, player_id, self.chest_id, item_id);
+                            }
+                            SubSection::Pack => {
+                                item::put_in_container(world, player_id, self.chest_id, item_id);
+                            }
+                        }
+
+                        let (chest_items, pack_items) = item_lists(world, self.chest_id);
+                        self.clamp_selections(chest_items.len(), pack_items.len());
+                    }
+                }
+                GameKey::Cancel => {
+                    return (
+                        ModeControl::Pop(Box::new(ChestModeResult::Done)),
+                        ModeUpdate::Immediate,
+                    );
+                }
+                _ => {}
+            }
+        }
+
+        (ModeControl::Stay, ModeUpdate::WaitForEvent)
+    }
+
+    fn draw(&mut self, world: &World, grids: &mut [TileGrid<GameSym>], active: bool) {
+        let (chest_grid, grids) = grids.split_first_mut().unwrap(); // CHEST_GRID
+        let (pack_grid, _) = grids.split_first_mut().unwrap(); // PACK_GRID
+        let (chest_items, pack_items) = item_lists(world, self.chest_id);
+
+        if active {
+            chest_grid.view.color_mod = Color::WHITE;
+            pack_grid.view.color_mod = Color::WHITE;
+        } else {
+            chest_grid.view.color_mod = Color::GRAY;
+            pack_grid.view.color_mod = Color::GRAY;
+        }
+
+        self.draw_chest(world, chest_grid, &chest_items);
+        self.draw_pack(world, pack_grid, &pack_items);
+    }
+}
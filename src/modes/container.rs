@@ -0,0 +1,354 @@
+use shipyard::{EntityId, Get, UniqueView, View, World};
+
+use crate::{
+    components::{Container, Inventory, Name, Renderable},
+    gamekey::{self, GameKey},
+    gamesym::GameSym,
+    item,
+    locale::Locale,
+    player::PlayerId,
+    render,
+    ui::{self, Options},
+};
+use ruggrogue::{
+    util::{Color, Size},
+    InputBuffer, InputEvent, KeyMods, TileGrid, Tileset,
+};
+
+use super::{Mode, ModeControl, ModeResult, ModeUpdate};
+
+const CLOSE: &str = "menu.close";
+const NOTHING: &str = "inventory.nothing";
+
+pub enum ContainerModeResult {
+    AppQuit,
+    Done,
+}
+
+enum SubSection {
+    Contents,
+    Pack,
+    Close,
+}
+
+pub struct ContainerMode {
+    container_id: EntityId,
+    subsection: SubSection,
+    selection: i32,
+}
+
+/// Items in the container, and items in the player's pack that could be put into it.
+fn item_lists(world: &World, container_id: EntityId) -> (Vec<EntityId>, Vec<EntityId>) {
+    let player_id = world.borrow::<UniqueView<PlayerId>>();
+    let containers = world.borrow::<View<Container>>();
+    let inventories = world.borrow::<View<Inventory>>();
+    let contents = inventories.get(container_id).items.clone();
+    let pack = inventories
+        .get(player_id.0)
+        .items
+        .iter()
+        .filter(|id| !containers.contains(**id))
+        .copied()
+        .collect();
+
+    (contents, pack)
+}
+
+/// Show the contents of a container in the player's inventory, and allow items to be moved into
+/// and out of it.
+impl ContainerMode {
+    pub fn new(container_id: EntityId) -> Self {
+        Self {
+            container_id,
+            subsection: SubSection::Contents,
+            selection: 0,
+        }
+    }
+
+    /// Keep the selection within the lists after items move between them.
+    fn clamp_selection(&mut self, contents_len: usize, pack_len: usize) {
+        let len = match self.subsection {
+            SubSection::Contents => contents_len,
+            SubSection::Pack => pack_len,
+            SubSection::Close => return,
+        };
+
+        if len == 0 {
+            self.subsection = if contents_len > 0 {
+                SubSection::Contents
+            } else if pack_len > 0 {
+                SubSection::Pack
+            } else {
+                SubSection::Close
+            };
+            self.selection = 0;
+        } else {
+            self.selection = self.selection.min(len as i32 - 1);
+        }
+    }
+
+    fn draw_list(
+        &self,
+        grid: &mut TileGrid<GameSym>,
+        y: i32,
+        items: &[EntityId],
+        selected: bool,
+        (options, locale, names, renderables): (&Options, &Locale, &View<Name>, &View<Renderable>),
+    ) {
+        let fg = Color::WHITE;
+        let bg = Color::BLACK;
+
+        if items.is_empty() {
+            grid.print_color((4, y), locale.get(NOTHING), true, Color::GRAY, bg);
+        }
+
+        for (i, item_id) in items.iter().enumerate() {
+            render::put_renderable(grid, (2, y + i as i32), renderables.get(*item_id), options);
+            grid.print_color(
+                (4, y + i as i32),
+                &names.get(*item_id).0,
+                true,
+                fg,
+                if selected && i as i32 == self.selection {
+                    ui::SELECTED_BG
+                } else {
+                    bg
+                },
+            );
+        }
+    }
+}
+
+impl Mode<World, GameSym> for ContainerMode {
+    fn prepare_grids(
+        &mut self,
+        world: &World,
+        grids: &mut Vec<TileGrid<GameSym>>,
+        tilesets: &[Tileset<GameSym>],
+        window_size: Size,
+    ) {
+        let Options {
+            font, text_zoom, ..
+        } = *world.borrow::<UniqueView<Options>>();
+        let (contents, pack) = item_lists(world, self.container_id);
+        let names = world.borrow::<View<Name>>();
+        let locale = world.borrow::<UniqueView<Locale>>();
+        let inner_width = contents
+            .iter()
+            .chain(pack.iter())
+            .map(|id| names.get(*id).0.chars().count() + 2)
+            .max()
+            .unwrap_or(0)
+            .max(locale.get(NOTHING).chars().count() + 2)
+            .max(locale.get("container.pack").chars().count())
+            .max(names.get(self.container_id).0.chars().count() + 12);
+        let new_size = Size {
+            w: inner_width as u32 + 4,
+            h: 11 + contents.len().max(1) as u32 + pack.len().max(1) as u32,
+        };
+
+        if !grids.is_empty() {
+            grids[0].resize(new_size);
+        } else {
+            grids.push(TileGrid::new(new_size, tilesets, font as usize));
+            grids[0].view.clear_color = None;
+        }
+
+        grids[0].set_tileset(tilesets, font as usize);
+        grids[0].view_centered(tilesets, text_zoom, (0, 0).into(), window_size);
+        grids[0].view.zoom = text_zoom;
+    }
+
+    fn update(
+        &mut self,
+        world: &World,
+        inputs: &mut InputBuffer,
+        _grids: &[TileGrid<GameSym>],
+        _pop_result: &Option<ModeResult>,
+    ) -> (ModeControl, ModeUpdate) {
+        let (contents, pack) = item_lists(world, self.container_id);
+
+        self.clamp_selection(contents.len(), pack.len());
+        inputs.prepare_input();
+
+        if let Some(InputEvent::AppQuit) = inputs.get_input() {
+            return (
+                ModeControl::Pop(Box::new(ContainerModeResult::AppQuit)),
+                ModeUpdate::Immediate,
+            );
+        } else if let Some(InputEvent::Press(keycode)) = inputs.get_input() {
+            match gamekey::from_keycode(keycode, inputs.get_mods(KeyMods::SHIFT)) {
+                GameKey::Up => match self.subsection {
+                    SubSection::Contents => {
+                        if self.selection > 0 {
+                            self.selection -= 1;
+                        } else {
+                            self.subsection = SubSection::Close;
+                        }
+                    }
+                    SubSection::Pack => {
+                        if self.selection > 0 {
+                            self.selection -= 1;
+                        } else if !contents.is_empty() {
+                            self.subsection = SubSection::Contents;
+                            self.selection = contents.len() as i32 - 1;
+                        } else {
+                            self.subsection = SubSection::Close;
+                        }
+                    }
+                    SubSection::Close => {
+                        if !pack.is_empty() {
+                            self.subsection = SubSection::Pack;
+                            self.selection = pack.len() as i32 - 1;
+                        } else if !contents.is_empty() {
+                            self.subsection = SubSection::Contents;
+                            self.selection = contents.len() as i32 - 1;
+                        }
+                    }
+                },
+                GameKey::Down => match self.subsection {
+                    SubSection::Contents => {
+                        if self.selection < contents.len() as i32 - 1 {
+                            self.selection += 1;
+                        } else if !pack.is_empty() {
+                            self.subsection = SubSection::Pack;
+                            self.selection = 0;
+                        } else {
+                            self.subsection = SubSection::Close;
+                        }
+                    }
+                    SubSection::Pack => {
+                        if self.selection < pack.len() as i32 - 1 {
+                            self.selection += 1;
+                        } else {
+                            self.subsection = SubSection::Close;
+                        }
+                    }
+                    SubSection::Close => {
+                        if !contents.is_empty() {
+                            self.subsection = SubSection::Contents;
+                            self.selection = 0;
+                        } else if !pack.is_empty() {
+                            self.subsection = SubSection::Pack;
+                            self.selection = 0;
+                        }
+                    }
+                },
+                GameKey::Confirm => {
+                    let player_id = world.borrow::<UniqueView<PlayerId>>().0;
+
+                    match self.subsection {
+                        SubSection::Contents => {
+                            let item_id = contents[self.selection as usize];
+                            item::take_from_container(world, player_id, self.container_id, item_id);
+                        }
+                        SubSection::Pack => {
+                            let item_id = pack[self.selection as usize];
+                            item::put_in_container(world, player_id, self.container_id, item_id);
+                        }
+                        SubSection::Close => {
+                            return (
+                                ModeControl::Pop(Box::new(ContainerModeResult::Done)),
+                                ModeUpdate::Immediate,
+                            );
+                        }
+                    }
+
+                    let (contents, pack) = item_lists(world, self.container_id);
+                    self.clamp_selection(contents.len(), pack.len());
+                }
+                GameKey::Cancel => {
+                    return (
+                        ModeControl::Pop(Box::new(ContainerModeResult::Done)),
+                        ModeUpdate::Immediate,
+                    );
+                }
+                _ => {}
+            }
+        }
+
+        (ModeControl::Stay, ModeUpdate::WaitForEvent)
+    }
+
+    fn draw(&mut self, world: &World, grids: &mut [TileGrid<GameSym>], active: bool) {
+        let grid = &mut grids[0];
+        let fg = Color::WHITE;
+        let bg = Color::BLACK;
+        let (contents, pack) = item_lists(world, self.container_id);
+        let options = world.borrow::<UniqueView<Options>>();
+        let locale = world.borrow::<UniqueView<Locale>>();
+        let names = world.borrow::<View<Name>>();
+        let renderables = world.borrow::<View<Renderable>>();
+        let capacity = world
+            .borrow::<View<Container>>()
+            .get(self.container_id)
+            .capacity;
+
+        grid.view.color_mod = if active { Color::WHITE } else { Color::GRAY };
+
+        grid.draw_box((0, 0), (grid.width(), grid.height()), fg, bg);
+        grid.print_color(
+            (2, 0),
+            &format!("< {} >", names.get(self.container_id).0),
+            true,
+            Color::YELLOW,
+            bg,
+        );
+
+        let contents_y = 4;
+        grid.print_color(
+            (2, 2),
+            &locale.format(
+                "container.inside",
+                &[("count", &contents.len()), ("capacity", &capacity)],
+            ),
+            true,
+            Color::LIGHT_GRAY,
+            bg,
+        );
+        self.draw_list(
+            grid,
+            contents_y,
+            &contents,
+            matches!(self.subsection, SubSection::Contents),
+            (&options, &locale, &names, &renderables),
+        );
+
+        let pack_y = contents_y + contents.len().max(1) as i32 + 3;
+        grid.print_color(
+            (2, pack_y - 2),
+            locale.get("container.pack"),
+            true,
+            Color::LIGHT_GRAY,
+            bg,
+        );
+        self.draw_list(
+            grid,
+            pack_y,
+            &pack,
+            matches!(self.subsection, SubSection::Pack),
+            (&options, &locale, &names, &renderables),
+        );
+
+        let close_y = grid.height() as i32 - 3;
+        grid.print_color(
+            (4, close_y),
+            locale.get(CLOSE),
+            true,
+            fg,
+            if matches!(self.subsection, SubSection::Close) {
+                ui::SELECTED_BG
+            } else {
+                bg
+            },
+        );
+
+        let selected_row = match self.subsection {
+            SubSection::Contents => contents_y + self.selection,
+            SubSection::Pack => pack_y + self.selection,
+            SubSection::Close => close_y,
+        };
+
+        ui::scroll_menu_grid(grid, selected_row, fg, bg);
+    }
+}
@@ -0,0 +1,292 @@
+use shipyard::{EntityId, Get, UniqueView, UniqueViewMut, View, ViewMut, World};
+
+use crate::{
+    components::{Coord, Name, Npc},
+    gamekey::{self, GameKey},
+    gamesym::GameSym,
+    journal::{Journal, JournalEntry},
+    locale::Locale,
+    map::Map,
+    message::{Messages, MsgCategory},
+    npc::{DialogEffect, DialogNode, NpcDefs},
+    player::PlayerId,
+    spawn,
+    ui::{self, Options},
+};
+use ruggrogue::{
+    util::{Color, Size},
+    InputBuffer, InputEvent, KeyMods, TileGrid, Tileset,
+};
+
+use super::{Mode, ModeControl, ModeResult, ModeUpdate};
+
+const TEXT_WIDTH: usize = 46;
+
+fn player_pos(world: &World) -> (i32, i32) {
+    let player_id = world.borrow::<UniqueView<PlayerId>>();
+    let coords = world.borrow::<View<Coord>>();
+
+    coords.get(player_id.0).0.into()
+}
+
+pub enum DialogModeResult {
+    AppQuit,
+    Done,
+}
+
+pub struct DialogMode {
+    npc_id: EntityId,
+    node: String,
+    selection: i32,
+}
+
+/// Converse with a friendly NPC by walking its dialog tree one node at a time.
+impl DialogMode {
+    pub fn new(world: &World, npc_id: EntityId) -> Self {
+        let npc_defs = world.borrow::<UniqueView<NpcDefs>>();
+        let npcs = world.borrow::<View<Npc>>();
+        let node = npc_defs
+            .get(&npcs.get(npc_id).def_id)
+            .map(|def| def.start.clone())
+            .unwrap_or_default();
+
+        Self {
+            npc_id,
+            node,
+            selection: 0,
+        }
+    }
+
+    fn with_node<T>(&self, world: &World, f: impl FnOnce(&DialogNode) -> T) -> Option<T> {
+        let npc_defs = world.borrow::<UniqueView<NpcDefs>>();
+        let npcs = world.borrow::<View<Npc>>();
+
+        npc_defs
+            .get(&npcs.get(self.npc_id).def_id)
+            .and_then(|def| def.nodes.get(&self.node))
+            .map(f)
+    }
+
+    /// Apply the effect of the chosen dialog choice and return the node to continue to, if any.
+    fn choose(&self, world: &World) -> Option<String> {
+        let npc_defs = world.borrow::<UniqueView<NpcDefs>>();
+        let choice = npc_defs
+            .get(&world.borrow::<View<Npc>>().get(self.npc_id).def_id)
+            .and_then(|def| def.nodes.get(&self.node))
+            .and_then(|node| node.choices.get(self.selection as usize))?;
+
+        match &choice.effect {
+            Some(DialogEffect::Hint { text }) => {
+                world.borrow::<UniqueViewMut<Messages>>().add(text.clone());
+            }
+            Some(DialogEffect::Item { item }) => {
+                let npc_name = world.borrow::<View<Name>>().get(self.npc_id).0.clone();
+                let gifted = world.borrow::<View<Npc>>().get(self.npc_id).gifted;
+
+                if gifted {
+                    let msg = world
+                        .borrow::<UniqueView<Locale>>()
+                        .format("dialog.nothing_more", &[("npc", &npc_name)]);
+                    world.borrow::<UniqueViewMut<Messages>>().add(msg);
+                } else {
+                    let player_pos = player_pos(world);
+
+                    (&mut world.borrow::<ViewMut<Npc>>())
+                        .get(self.npc_id)
+                        .gifted = true;
+                    spawn::spawn_gift(world, player_pos, *item);
+                    let msg = world
+                        .borrow::<UniqueView<Locale>>()
+                        .format("dialog.gift", &[("npc", &npc_name)]);
+                    world
+                        .borrow::<UniqueViewMut<Messages>>()
+                        .add_as(MsgCategory::Item, msg);
+                }
+            }
+            Some(DialogEffect::Quest { id, text, reward }) => {
+                let added = world
+                    .borrow::<UniqueViewMut<Journal>>()
+                    .add_quest(JournalEntry {
+                        id: id.clone(),
+                        giver: world.borrow::<View<Name>>().get(self.npc_id).0.clone(),
+                        text: text.clone(),
+                        depth: world.borrow::<UniqueView<Map>>().depth,
+                        completed_depth: None,
+                        reward: *reward,
+                        reward_claimed: false,
+                    });
+
+                if added {
+                    let msg = world
+                        .borrow::<UniqueView<Locale>>()
+                        .format("dialog.new_quest", &[("text", text)]);
+                    world.borrow::<UniqueViewMut<Messages>>().add(msg);
+                }
+            }
+            Some(DialogEffect::CompleteQuest { id }) => {
+                let depth = world.borrow::<UniqueView<Map>>().depth;
+                let completed = world
+                    .borrow::<UniqueViewMut<Journal>>()
+                    .complete_quest(id, depth)
+                    .map(|entry| {
+                        entry.reward_claimed = entry.reward.is_some();
+                        (entry.text.clone(), entry.reward)
+                    });
+
+                if let Some((text, reward)) = completed {
+                    let locale = world.borrow::<UniqueView<Locale>>();
+
+                    world
+                        .borrow::<UniqueViewMut<Messages>>()
+                        .add(locale.format("dialog.quest_complete", &[("text", &text)]));
+
+                    if let Some(reward) = reward {
+                        spawn::spawn_gift(world, player_pos(world), reward);
+                        world.borrow::<UniqueViewMut<Messages>>().add_as(
+                            MsgCategory::Item,
+                            locale
+                                .format("dialog.reward", &[("item", &locale.name(reward.name()))]),
+                        );
+                    }
+                }
+            }
+            None => {}
+        }
+
+        choice.next.clone()
+    }
+}
+
+impl Mode<World, GameSym> for DialogMode {
+    fn prepare_grids(
+        &mut self,
+        world: &World,
+        grids: &mut Vec<TileGrid<GameSym>>,
+        tilesets: &[Tileset<GameSym>],
+        window_size: Size,
+    ) {
+        let Options {
+            font, text_zoom, ..
+        } = *world.borrow::<UniqueView<Options>>();
+        let (text_lines, num_choices) = self
+            .with_node(world, |node| {
+                (
+                    ruggrogue::word_wrap(&node.text, TEXT_WIDTH).count(),
+                    node.choices.len(),
+                )
+            })
+            .unwrap_or((0, 0));
+        let new_grid_size = Size {
+            w: TEXT_WIDTH as u32 + 4,
+            h: (text_lines + num_choices) as u32 + 5,
+        };
+
+        if !grids.is_empty() {
+            grids[0].resize(new_grid_size);
+        } else {
+            grids.push(TileGrid::new(new_grid_size, tilesets, font as usize));
+            grids[0].view.clear_color = None;
+        }
+
+        grids[0].set_tileset(tilesets, font as usize);
+        grids[0].view_centered(tilesets, text_zoom, (0, 0).into(), window_size);
+        grids[0].view.zoom = text_zoom;
+    }
+
+    fn update(
+        &mut self,
+        world: &World,
+        inputs: &mut InputBuffer,
+        _grids: &[TileGrid<GameSym>],
+        _pop_result: &Option<ModeResult>,
+    ) -> (ModeControl, ModeUpdate) {
+        inputs.prepare_input();
+
+        let num_choices = self
+            .with_node(world, |node| node.choices.len() as i32)
+            .unwrap_or(0);
+
+        if let Some(InputEvent::AppQuit) = inputs.get_input() {
+            return (
+                ModeControl::Pop(Box::new(DialogModeResult::AppQuit)),
+                ModeUpdate::Immediate,
+            );
+        } else if let Some(InputEvent::Press(keycode)) = inputs.get_input() {
+            match gamekey::from_keycode(keycode, inputs.get_mods(KeyMods::SHIFT)) {
+                GameKey::Down if num_choices > 0 => {
+                    self.selection = (self.selection + 1) % num_choices;
+                }
+                GameKey::Up if num_choices > 0 => {
+                    self.selection = (self.selection + num_choices - 1) % num_choices;
+                }
+                GameKey::Cancel => {
+                    inputs.clear_input();
+                    return (
+                        ModeControl::Pop(Box::new(DialogModeResult::Done)),
+                        ModeUpdate::Immediate,
+                    );
+                }
+                GameKey::Confirm => {
+                    if let Some(next) = self.choose(world) {
+                        self.node = next;
+                        self.selection = 0;
+                    } else {
+                        inputs.clear_input();
+                        return (
+                            ModeControl::Pop(Box::new(DialogModeResult::Done)),
+                            ModeUpdate::Immediate,
+                        );
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        (ModeControl::Stay, ModeUpdate::WaitForEvent)
+    }
+
+    fn draw(&mut self, world: &World, grids: &mut [TileGrid<GameSym>], active: bool) {
+        let grid = &mut grids[0];
+        let fg = Color::WHITE;
+        let bg = Color::BLACK;
+        let npc_name = world.borrow::<View<Name>>().get(self.npc_id).0.clone();
+
+        grid.view.color_mod = if active { Color::WHITE } else { Color::GRAY };
+
+        grid.draw_box((0, 0), (grid.width(), grid.height()), fg, bg);
+        grid.print_color(
+            (2, 0),
+            &format!("< {} >", npc_name),
+            true,
+            Color::YELLOW,
+            bg,
+        );
+
+        let selected_row = self
+            .with_node(world, |node| {
+                let mut y = 2;
+
+                for line in ruggrogue::word_wrap(&node.text, TEXT_WIDTH) {
+                    grid.print_color((2, y), line, true, fg, bg);
+                    y += 1;
+                }
+
+                y += 1;
+
+                for (i, choice) in node.choices.iter().enumerate() {
+                    let choice_bg = if i as i32 == self.selection {
+                        ui::SELECTED_BG
+                    } else {
+                        bg
+                    };
+
+                    grid.print_color((2, y + i as i32), &choice.label, true, fg, choice_bg);
+                }
+
+                y + self.selection
+            })
+            .unwrap_or(0);
+
+        ui::scroll_menu_grid(grid, selected_row, fg, bg);
+    }
+}
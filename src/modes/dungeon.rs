@@ -1,36 +1,54 @@
-use shipyard::{Get, UniqueView, UniqueViewMut, View, World};
+use shipyard::{EntityId, Get, UniqueView, UniqueViewMut, View, World};
 
 use crate::{
+    animation::{self, Animations},
     chunked::{Camera, ChunkedMapGrid},
     components::{Coord, FieldOfView},
-    damage, experience,
+    config,
+    game_loop::{self, PlayerAction},
     gamesym::GameSym,
-    hunger, item,
+    hints, input_macro, inspect,
+    interact::{self, Interaction},
+    item,
+    locale::Locale,
     map::Map,
-    message::Messages,
-    monster,
+    message::{Messages, MsgCategory},
     player::{self, PlayerId, PlayerInputResult},
-    render, saveload, ui, vision, TurnCount,
+    recording, render, saveload, stats,
+    tutorial::{self, Tutorial},
+    ui::{self, Options},
+    TurnCount,
 };
 use ruggrogue::{
     util::{Color, Position, Size},
-    InputBuffer, TileGrid, Tileset,
+    Error, InputBuffer, TileGrid, Tileset,
 };
 
 use super::{
     app_quit_dialog::{AppQuitDialogMode, AppQuitDialogModeResult},
+    bestiary::{BestiaryMode, BestiaryModeResult},
+    chest::{ChestMode, ChestModeResult},
+    dialog::{DialogMode, DialogModeResult},
     equipment_action::EquipmentAction,
     equipment_shortcut::{EquipmentShortcutMode, EquipmentShortcutModeResult},
+    error_dialog::ErrorDialogModeResult,
     game_over::GameOverMode,
+    interact_menu::{InteractMenuMode, InteractMenuModeResult},
     inventory::{InventoryMode, InventoryModeResult},
     inventory_action::InventoryAction,
     inventory_shortcut::{InventoryShortcutMode, InventoryShortcutModeResult},
+    journal::{JournalMode, JournalModeResult},
+    message_box::{MessageBoxMode, MessageBoxModeResult},
+    note_entry::{NoteEntryMode, NoteEntryModeResult},
     options_menu::{OptionsMenuMode, OptionsMenuModeResult},
     pick_up_menu::{PickUpMenuMode, PickUpMenuModeResult},
+    progress::{ProgressMode, ProgressModeResult},
+    quest_log::{QuestLogMode, QuestLogModeResult},
+    target::{TargetMode, TargetModeResult},
     title::{self, TitleMode},
     view_map::{ViewMapMode, ViewMapModeResult},
     yes_no_dialog::{YesNoDialogMode, YesNoDialogModeResult},
-    ModeControl, ModeResult, ModeUpdate,
+    Mode, ModeControl, ModeResult, ModeUpdate,
 };
 
 pub enum DungeonModeResult {
@@ -41,16 +59,61 @@ pub struct DungeonMode {
     chunked_map_grid: ChunkedMapGrid,
     old_msg_frame_size: Size,
     redraw_msg_frame_grid: bool,
+    /// Interaction chosen from the interact menu, to be carried out in place of player input.
+    pending_interaction: Option<Interaction>,
+    /// Where to go once the game has finished saving on the way out.
+    quit_to: Option<QuitTo>,
+    /// What to try again if the player chooses to after a failure is reported.
+    retry: Option<Retry>,
 }
 
-fn app_quit_dialog(inputs: &mut InputBuffer) -> (ModeControl, ModeUpdate) {
+/// Where the player is headed when leaving the dungeon.
+#[derive(Clone, Copy)]
+enum QuitTo {
+    App,
+    Title,
+}
+
+/// Something that failed and was reported to the player, who can choose to try it again.
+#[derive(Clone, Copy)]
+enum Retry {
+    /// Saving the game along the way, e.g. upon arriving on a new level.
+    Checkpoint,
+    /// Saving the game on the way out of the dungeon.
+    Quit(QuitTo),
+    /// Saving the options to the config file.
+    Options,
+    /// Saving the game just before winning it with the victory item.
+    Victory(EntityId),
+    /// Writing lifetime statistics to the stats file, then ending the game if it was won.
+    Profile { won: bool },
+}
+
+fn app_quit_dialog(world: &World, inputs: &mut InputBuffer) -> (ModeControl, ModeUpdate) {
     inputs.clear_input();
     (
-        ModeControl::Push(AppQuitDialogMode::new().into()),
+        ModeControl::Push(Box::new(AppQuitDialogMode::new(world))),
         ModeUpdate::Immediate,
     )
 }
 
+fn quit(world: &World, inputs: &mut InputBuffer, quit_to: QuitTo) -> (ModeControl, ModeUpdate) {
+    match quit_to {
+        QuitTo::App => (
+            ModeControl::Pop(Box::new(DungeonModeResult::Done)),
+            ModeUpdate::Immediate,
+        ),
+        QuitTo::Title => {
+            title::post_game_cleanup(world, true);
+            inputs.clear_input();
+            (
+                ModeControl::Switch(Box::new(TitleMode::new(world))),
+                ModeUpdate::Immediate,
+            )
+        }
+    }
+}
+
 fn get_player_fov(player_id: UniqueView<PlayerId>, fovs: View<FieldOfView>) -> (Position, Size) {
     let player_fov = fovs.get(player_id.0);
 
@@ -78,10 +141,55 @@ impl DungeonMode {
             chunked_map_grid: ChunkedMapGrid::new(),
             old_msg_frame_size: (0, 0).into(),
             redraw_msg_frame_grid: true,
+            pending_interaction: None,
+            quit_to: None,
+            retry: None,
+        }
+    }
+
+    /// Show an error to the player, remembering what to do if they choose to retry.
+    fn report(&mut self, error: Error, retry: Retry) -> (ModeControl, ModeUpdate) {
+        self.retry = Some(retry);
+        (ModeControl::Report(error), ModeUpdate::Immediate)
+    }
+
+    /// Show the game over screen once the game is won, reporting first if the profile couldn't be
+    /// saved.
+    fn victory(&mut self, world: &World, inputs: &mut InputBuffer) -> (ModeControl, ModeUpdate) {
+        if let Err(e) = stats::check_profile_saved(world) {
+            return self.report(e, Retry::Profile { won: true });
         }
+
+        inputs.clear_input();
+        (
+            ModeControl::Switch(Box::new(GameOverMode::new())),
+            ModeUpdate::Immediate,
+        )
     }
 
-    pub fn prepare_grids(
+    /// Save the game a bit at a time behind a progress bar, and quit once it's saved.
+    fn save_and_quit(
+        &mut self,
+        world: &World,
+        inputs: &mut InputBuffer,
+        quit_to: QuitTo,
+    ) -> (ModeControl, ModeUpdate) {
+        match saveload::GameSaver::new(world) {
+            Ok(Some(saver)) => {
+                self.quit_to = Some(quit_to);
+                (
+                    ModeControl::Push(Box::new(ProgressMode::save(saver))),
+                    ModeUpdate::Immediate,
+                )
+            }
+            Ok(None) => quit(world, inputs, quit_to),
+            Err(e) => self.report(Error::new("Failed to save game", e), Retry::Quit(quit_to)),
+        }
+    }
+}
+
+impl Mode<World, GameSym> for DungeonMode {
+    fn prepare_grids(
         &mut self,
         world: &World,
         grids: &mut Vec<TileGrid<GameSym>>,
@@ -103,202 +211,363 @@ impl DungeonMode {
         self.old_msg_frame_size.h = grids[ui::MSG_FRAME_GRID].height();
     }
 
-    pub fn update(
+    fn update(
         &mut self,
         world: &World,
         inputs: &mut InputBuffer,
         _grids: &[TileGrid<GameSym>],
         pop_result: &Option<ModeResult>,
     ) -> (ModeControl, ModeUpdate) {
+        // Advance pop-up numbers, redrawing the map where they were.
+        for (pos, size) in world.borrow::<UniqueViewMut<Animations>>().tick() {
+            self.chunked_map_grid.mark_dirty(pos, size);
+        }
+
         if world.run(player::player_is_alive) {
             let old_player_fov = world.run(get_player_fov);
             let old_player_pos = world.run(get_player_pos);
             let old_depth = world.borrow::<UniqueView<Map>>().depth;
-            let time_passed = if let Some(result) = pop_result {
-                match result {
-                    ModeResult::AppQuitDialogModeResult(result) => match result {
+            let action = if let Some(result) = pop_result {
+                if let Some(result) = result.downcast_ref::<AppQuitDialogModeResult>() {
+                    match result {
                         AppQuitDialogModeResult::Confirmed => {
-                            if let Err(e) = saveload::save_game(world) {
-                                eprintln!("Warning: saveload::save_game: {}", e);
-                            }
-                            return (
-                                ModeControl::Pop(DungeonModeResult::Done.into()),
-                                ModeUpdate::Immediate,
-                            );
+                            return self.save_and_quit(world, inputs, QuitTo::App);
                         }
-                        AppQuitDialogModeResult::Cancelled => false,
-                    },
-
-                    ModeResult::YesNoDialogModeResult(result) => match result {
-                        YesNoDialogModeResult::AppQuit => return app_quit_dialog(inputs),
+                        AppQuitDialogModeResult::Cancelled => None,
+                    }
+                } else if let Some(result) = result.downcast_ref::<YesNoDialogModeResult>() {
+                    match result {
+                        YesNoDialogModeResult::AppQuit => return app_quit_dialog(world, inputs),
                         YesNoDialogModeResult::Yes => {
-                            player::player_do_descend(world);
-                            if let Err(e) = saveload::save_game(world) {
-                                eprintln!("Warning: saveload::save_game: {}", e);
+                            // Taking the way down ends the tutorial.
+                            if world.borrow::<UniqueView<Tutorial>>().is_active() {
+                                title::post_game_cleanup(world, true);
+                                inputs.clear_input();
+                                return (
+                                    ModeControl::Switch(Box::new(TitleMode::new(world))),
+                                    ModeUpdate::Immediate,
+                                );
                             }
-                            false
+                            Some(PlayerAction::Descend)
+                        }
+                        YesNoDialogModeResult::No => None,
+                    }
+                } else if let Some(result) = result.downcast_ref::<OptionsMenuModeResult>() {
+                    match result {
+                        OptionsMenuModeResult::AppQuit => return app_quit_dialog(world, inputs),
+                        OptionsMenuModeResult::Closed => {
+                            // Map colors may have changed.
+                            self.chunked_map_grid.mark_all_dirty();
+                            None
                         }
-                        YesNoDialogModeResult::No => false,
-                    },
-
-                    ModeResult::OptionsMenuModeResult(result) => match result {
-                        OptionsMenuModeResult::AppQuit => return app_quit_dialog(inputs),
-                        OptionsMenuModeResult::Closed => false,
                         OptionsMenuModeResult::ReallyQuit => {
-                            if let Err(e) = saveload::save_game(world) {
-                                eprintln!("Warning: saveload::save_game: {}", e);
-                            }
-                            title::post_game_cleanup(world, true);
-                            inputs.clear_input();
-                            return (
-                                ModeControl::Switch(TitleMode::new().into()),
-                                ModeUpdate::Immediate,
+                            return self.save_and_quit(world, inputs, QuitTo::Title);
+                        }
+                    }
+                } else if let Some(result) = result.downcast_ref::<PickUpMenuModeResult>() {
+                    match result {
+                        PickUpMenuModeResult::AppQuit => return app_quit_dialog(world, inputs),
+                        PickUpMenuModeResult::PickedItems(item_ids) => {
+                            Some(PlayerAction::PickUpItems(item_ids.clone()))
+                        }
+                        PickUpMenuModeResult::Cancelled => None,
+                    }
+                } else if let Some(result) = result.downcast_ref::<InventoryModeResult>() {
+                    match result {
+                        InventoryModeResult::AppQuit => return app_quit_dialog(world, inputs),
+                        InventoryModeResult::DoNothing => None,
+                        InventoryModeResult::RemoveEquipment(item_id) => {
+                            Some(PlayerAction::RemoveEquipment(*item_id))
+                        }
+                        InventoryModeResult::DropEquipment(item_id) => {
+                            Some(PlayerAction::DropEquipment(*item_id))
+                        }
+                        InventoryModeResult::EquipItem(item_id) => {
+                            Some(PlayerAction::EquipItem(*item_id))
+                        }
+                        InventoryModeResult::UseItem(item_id, target) => {
+                            Some(PlayerAction::UseItem(*item_id, *target))
+                        }
+                        InventoryModeResult::DropItem(item_id) => {
+                            Some(PlayerAction::DropItem(*item_id))
+                        }
+                    }
+                } else if let Some(result) = result.downcast_ref::<InventoryShortcutModeResult>() {
+                    match result {
+                        InventoryShortcutModeResult::AppQuit => {
+                            return app_quit_dialog(world, inputs)
+                        }
+                        InventoryShortcutModeResult::Cancelled => None,
+                        InventoryShortcutModeResult::EquipItem(item_id) => {
+                            Some(PlayerAction::EquipItem(*item_id))
+                        }
+                        InventoryShortcutModeResult::UseItem(item_id, target) => {
+                            Some(PlayerAction::UseItem(*item_id, *target))
+                        }
+                        InventoryShortcutModeResult::DropItems(item_ids) => {
+                            Some(PlayerAction::DropItems(item_ids.clone()))
+                        }
+                    }
+                } else if let Some(result) = result.downcast_ref::<EquipmentShortcutModeResult>() {
+                    match result {
+                        EquipmentShortcutModeResult::AppQuit => {
+                            return app_quit_dialog(world, inputs)
+                        }
+                        EquipmentShortcutModeResult::Cancelled => None,
+                        EquipmentShortcutModeResult::RemoveEquipment(item_id) => {
+                            Some(PlayerAction::RemoveEquipment(*item_id))
+                        }
+                        EquipmentShortcutModeResult::DropEquipment(item_id) => {
+                            Some(PlayerAction::DropEquipment(*item_id))
+                        }
+                    }
+                } else if let Some(result) = result.downcast_ref::<ViewMapModeResult>() {
+                    match result {
+                        ViewMapModeResult::AppQuit => return app_quit_dialog(world, inputs),
+                        ViewMapModeResult::Done => None,
+                        ViewMapModeResult::TravelTo(dest) => Some(PlayerAction::TravelTo(*dest)),
+                    }
+                } else if let Some(result) = result.downcast_ref::<ProgressModeResult>() {
+                    match (result, self.quit_to.take()) {
+                        (ProgressModeResult::Done, Some(quit_to)) => {
+                            return quit(world, inputs, quit_to)
+                        }
+                        (ProgressModeResult::Failed(e), Some(quit_to)) => {
+                            return self.report(
+                                Error::new("Failed to save game", e.clone()),
+                                Retry::Quit(quit_to),
                             );
                         }
-                    },
-
-                    ModeResult::PickUpMenuModeResult(result) => match result {
-                        PickUpMenuModeResult::AppQuit => return app_quit_dialog(inputs),
-                        PickUpMenuModeResult::PickedItem(item_id) => {
-                            player::player_pick_up_item(world, *item_id);
-                            true
+                        (_, None) => None,
+                    }
+                } else if let Some(result) = result.downcast_ref::<ErrorDialogModeResult>() {
+                    match (result, self.retry.take()) {
+                        (ErrorDialogModeResult::AppQuit, _) => {
+                            return app_quit_dialog(world, inputs)
                         }
-                        PickUpMenuModeResult::Cancelled => false,
-                    },
-
-                    ModeResult::InventoryModeResult(result) => {
-                        let player_id = world.borrow::<UniqueView<PlayerId>>().0;
-
-                        match result {
-                            InventoryModeResult::AppQuit => return app_quit_dialog(inputs),
-                            InventoryModeResult::DoNothing => false,
-                            InventoryModeResult::RemoveEquipment(item_id) => {
-                                item::remove_equipment(world, player_id, *item_id);
-                                true
-                            }
-                            InventoryModeResult::DropEquipment(item_id) => {
-                                item::drop_equipment(world, player_id, *item_id);
-                                true
+                        (ErrorDialogModeResult::Retry, Some(Retry::Checkpoint)) => {
+                            if let Err(e) = saveload::save_game(world) {
+                                return self.report(
+                                    Error::new("Failed to save game", e),
+                                    Retry::Checkpoint,
+                                );
                             }
-                            InventoryModeResult::EquipItem(item_id) => {
-                                item::equip_item(world, player_id, *item_id);
-                                true
+                            None
+                        }
+                        (ErrorDialogModeResult::Retry, Some(Retry::Quit(quit_to))) => {
+                            return self.save_and_quit(world, inputs, quit_to);
+                        }
+                        (ErrorDialogModeResult::Retry, Some(Retry::Options)) => {
+                            if let Err(e) = config::save_options(world) {
+                                return self.report(e, Retry::Options);
                             }
-                            InventoryModeResult::UseItem(item_id, target) => {
-                                if item::use_item(world, player_id, *item_id, *target) {
-                                    inputs.clear_input();
-                                    return (
-                                        ModeControl::Switch(GameOverMode::new().into()),
-                                        ModeUpdate::Immediate,
-                                    );
-                                }
-                                true
+                            None
+                        }
+                        (ErrorDialogModeResult::Retry, Some(Retry::Victory(item_id))) => {
+                            Some(PlayerAction::UseItem(item_id, None))
+                        }
+                        (ErrorDialogModeResult::Retry, Some(Retry::Profile { won })) => {
+                            if let Err(e) = stats::save_unsaved_profile(world) {
+                                return self.report(e, Retry::Profile { won });
                             }
-                            InventoryModeResult::DropItem(item_id) => {
-                                player::player_drop_item(world, *item_id);
-                                true
+                            if won {
+                                return self.victory(world, inputs);
                             }
+                            None
                         }
-                    }
-
-                    ModeResult::InventoryShortcutModeResult(result) => {
-                        let player_id = world.borrow::<UniqueView<PlayerId>>().0;
+                        (ErrorDialogModeResult::Ignore, Some(Retry::Quit(quit_to))) => {
+                            return quit(world, inputs, quit_to);
+                        }
+                        (ErrorDialogModeResult::Ignore, Some(Retry::Victory(item_id))) => {
+                            let player_id = world.borrow::<UniqueView<PlayerId>>().0;
 
-                        match result {
-                            InventoryShortcutModeResult::AppQuit => return app_quit_dialog(inputs),
-                            InventoryShortcutModeResult::Cancelled => false,
-                            InventoryShortcutModeResult::EquipItem(item_id) => {
-                                item::equip_item(world, player_id, *item_id);
-                                true
-                            }
-                            InventoryShortcutModeResult::UseItem(item_id, target) => {
-                                if item::use_item(world, player_id, *item_id, *target) {
-                                    inputs.clear_input();
-                                    return (
-                                        ModeControl::Switch(GameOverMode::new().into()),
-                                        ModeUpdate::Immediate,
-                                    );
-                                }
-                                true
-                            }
-                            InventoryShortcutModeResult::DropItem(item_id) => {
-                                player::player_drop_item(world, *item_id);
-                                true
-                            }
+                            item::claim_victory(world, player_id, item_id);
+                            return self.victory(world, inputs);
+                        }
+                        (ErrorDialogModeResult::Ignore, Some(Retry::Profile { won: true })) => {
+                            return self.victory(world, inputs);
                         }
+                        (ErrorDialogModeResult::Retry, None)
+                        | (ErrorDialogModeResult::Ignore, _) => None,
                     }
-
-                    ModeResult::EquipmentShortcutModeResult(result) => {
-                        let player_id = world.borrow::<UniqueView<PlayerId>>().0;
-
-                        match result {
-                            EquipmentShortcutModeResult::AppQuit => return app_quit_dialog(inputs),
-                            EquipmentShortcutModeResult::Cancelled => false,
-                            EquipmentShortcutModeResult::RemoveEquipment(item_id) => {
-                                item::remove_equipment(world, player_id, *item_id);
-                                true
-                            }
-                            EquipmentShortcutModeResult::DropEquipment(item_id) => {
-                                item::drop_equipment(world, player_id, *item_id);
-                                true
+                } else if let Some(result) = result.downcast_ref::<MessageBoxModeResult>() {
+                    match result {
+                        MessageBoxModeResult::AppQuit => return app_quit_dialog(world, inputs),
+                        MessageBoxModeResult::Done => None,
+                    }
+                } else if let Some(result) = result.downcast_ref::<NoteEntryModeResult>() {
+                    match result {
+                        NoteEntryModeResult::AppQuit => return app_quit_dialog(world, inputs),
+                        NoteEntryModeResult::Done => None,
+                    }
+                } else if let Some(result) = result.downcast_ref::<JournalModeResult>() {
+                    match result {
+                        JournalModeResult::AppQuit => return app_quit_dialog(world, inputs),
+                        JournalModeResult::Done => None,
+                    }
+                } else if let Some(result) = result.downcast_ref::<QuestLogModeResult>() {
+                    match result {
+                        QuestLogModeResult::AppQuit => return app_quit_dialog(world, inputs),
+                        QuestLogModeResult::Done => None,
+                    }
+                } else if let Some(result) = result.downcast_ref::<TargetModeResult>() {
+                    match result {
+                        TargetModeResult::AppQuit => return app_quit_dialog(world, inputs),
+                        TargetModeResult::Cancelled => None,
+                        TargetModeResult::Target { x, y } => {
+                            if let Some(monster) = inspect::monster_at(world, *x, *y) {
+                                inputs.clear_input();
+                                return (
+                                    ModeControl::Push(Box::new(MessageBoxMode::new(
+                                        inspect::monster_summary(world, monster),
+                                    ))),
+                                    ModeUpdate::Immediate,
+                                );
                             }
+                            let msg = world
+                                .borrow::<UniqueView<Locale>>()
+                                .get("msg.nothing_to_inspect_there")
+                                .to_string();
+
+                            world
+                                .borrow::<UniqueViewMut<Messages>>()
+                                .add_as(MsgCategory::Warning, msg);
+                            None
                         }
                     }
-
-                    ModeResult::ViewMapModeResult(result) => match result {
-                        ViewMapModeResult::AppQuit => return app_quit_dialog(inputs),
-                        ViewMapModeResult::Done => false,
-                    },
-
-                    _ => unreachable!(),
+                } else if let Some(result) = result.downcast_ref::<BestiaryModeResult>() {
+                    match result {
+                        BestiaryModeResult::AppQuit => return app_quit_dialog(world, inputs),
+                        BestiaryModeResult::Done => None,
+                    }
+                } else if let Some(result) = result.downcast_ref::<DialogModeResult>() {
+                    match result {
+                        DialogModeResult::AppQuit => return app_quit_dialog(world, inputs),
+                        DialogModeResult::Done => None,
+                    }
+                } else if let Some(result) = result.downcast_ref::<ChestModeResult>() {
+                    match result {
+                        ChestModeResult::AppQuit => return app_quit_dialog(world, inputs),
+                        ChestModeResult::Done => None,
+                    }
+                } else if let Some(result) = result.downcast_ref::<InteractMenuModeResult>() {
+                    match result {
+                        InteractMenuModeResult::AppQuit => return app_quit_dialog(world, inputs),
+                        InteractMenuModeResult::Cancelled => None,
+                        InteractMenuModeResult::Chosen(interaction) => {
+                            // Carry out the interaction in the next update as if it were input.
+                            self.pending_interaction = Some(*interaction);
+                            return (ModeControl::Stay, ModeUpdate::Immediate);
+                        }
+                    }
+                } else {
+                    unreachable!()
                 }
             } else {
-                match player::player_input(world, inputs) {
-                    PlayerInputResult::AppQuit => return app_quit_dialog(inputs),
-                    PlayerInputResult::NoResult => false,
-                    PlayerInputResult::TurnDone => true,
+                let player_result = match self.pending_interaction.take() {
+                    Some(interaction) => interact::interact(world, interaction),
+                    None => player::player_input(world, inputs),
+                };
+
+                match player_result {
+                    PlayerInputResult::AppQuit => return app_quit_dialog(world, inputs),
+                    PlayerInputResult::NoResult => None,
+                    PlayerInputResult::TurnDone => Some(PlayerAction::TurnTaken),
                     PlayerInputResult::ShowOptionsMenu => {
                         inputs.clear_input();
                         return (
-                            ModeControl::Push(OptionsMenuMode::new(true).into()),
+                            ModeControl::Push(Box::new(OptionsMenuMode::new(true))),
                             ModeUpdate::Immediate,
                         );
                     }
                     PlayerInputResult::ViewMap => {
                         inputs.clear_input();
                         return (
-                            ModeControl::Push(ViewMapMode::new(world).into()),
+                            ModeControl::Push(Box::new(ViewMapMode::new(world))),
+                            ModeUpdate::Immediate,
+                        );
+                    }
+                    PlayerInputResult::LookList => {
+                        inputs.clear_input();
+                        return (
+                            ModeControl::Push(Box::new(ViewMapMode::with_look_list(world))),
                             ModeUpdate::Immediate,
                         );
                     }
+                    PlayerInputResult::InspectMonster(monster) => {
+                        inputs.clear_input();
+                        return (
+                            ModeControl::Push(Box::new(MessageBoxMode::new(
+                                inspect::monster_summary(world, monster),
+                            ))),
+                            ModeUpdate::Immediate,
+                        );
+                    }
+                    PlayerInputResult::PickInspectTarget => {
+                        let range = world.run(
+                            |player_id: UniqueView<PlayerId>, fovs: View<FieldOfView>| {
+                                fovs.get(player_id.0).range
+                            },
+                        );
+
+                        inputs.clear_input();
+                        return (
+                            ModeControl::Push(Box::new(TargetMode::new(
+                                world,
+                                world
+                                    .borrow::<UniqueView<Locale>>()
+                                    .get("dungeon.closer_look")
+                                    .to_string(),
+                                range,
+                                0,
+                                false,
+                            ))),
+                            ModeUpdate::Immediate,
+                        );
+                    }
+                    PlayerInputResult::ZoomMap(delta) => {
+                        let old_zoom = self.chunked_map_grid.map_zoom() as i32;
+                        let new_zoom = (old_zoom + delta).clamp(1, ui::MAX_MAP_ZOOM as i32);
+
+                        {
+                            let mut options = world.borrow::<UniqueViewMut<Options>>();
+
+                            options.map_zoom = new_zoom as u32;
+                            options.map_zoom_fit = false;
+                        }
+                        if let Err(e) = config::save_options(world) {
+                            return self.report(e, Retry::Options);
+                        }
+                        None
+                    }
                     PlayerInputResult::TryDescend => {
                         if world.run(player::player_try_descend) {
                             inputs.clear_input();
                             return (
-                                ModeControl::Push(
-                                    YesNoDialogMode::new(
-                                        "Descend to the next level?".to_string(),
-                                        false,
-                                    )
-                                    .into(),
-                                ),
+                                ModeControl::Push(Box::new(YesNoDialogMode::new(
+                                    world
+                                        .borrow::<UniqueView<Locale>>()
+                                        .get("dungeon.confirm_descend")
+                                        .to_string(),
+                                    false,
+                                ))),
                                 ModeUpdate::Immediate,
                             );
                         } else {
-                            false
+                            None
                         }
                     }
                     PlayerInputResult::ShowPickUpMenu => {
                         inputs.clear_input();
                         return (
-                            ModeControl::Push(PickUpMenuMode::new(world).into()),
+                            ModeControl::Push(Box::new(PickUpMenuMode::new(world))),
                             ModeUpdate::Immediate,
                         );
                     }
                     PlayerInputResult::ShowInventory => {
                         inputs.clear_input();
                         return (
-                            ModeControl::Push(InventoryMode::new(world).into()),
+                            ModeControl::Push(Box::new(InventoryMode::new(world))),
                             ModeUpdate::Immediate,
                         );
                     }
@@ -306,52 +575,143 @@ impl DungeonMode {
                         if let Some(action) = InventoryAction::from_key(key) {
                             inputs.clear_input();
                             return (
-                                ModeControl::Push(InventoryShortcutMode::new(world, action).into()),
+                                ModeControl::Push(Box::new(InventoryShortcutMode::new(
+                                    world, action,
+                                ))),
                                 ModeUpdate::Immediate,
                             );
                         } else {
-                            false
+                            None
                         }
                     }
                     PlayerInputResult::ShowEquipmentShortcut(key) => {
                         if let Some(action) = EquipmentAction::from_key(key) {
                             inputs.clear_input();
                             return (
-                                ModeControl::Push(EquipmentShortcutMode::new(world, action).into()),
+                                ModeControl::Push(Box::new(EquipmentShortcutMode::new(
+                                    world, action,
+                                ))),
                                 ModeUpdate::Immediate,
                             );
                         } else {
-                            false
+                            None
                         }
                     }
+                    PlayerInputResult::ShowRecentMessages => {
+                        let msg = world
+                            .borrow::<UniqueView<Messages>>()
+                            .last_turn()
+                            .iter()
+                            .flat_map(|m| ruggrogue::word_wrap(m, 78).map(String::from))
+                            .collect::<Vec<_>>();
+
+                        inputs.clear_input();
+                        return (
+                            ModeControl::Push(Box::new(MessageBoxMode::new(msg))),
+                            ModeUpdate::Immediate,
+                        );
+                    }
+                    PlayerInputResult::TakeNote => {
+                        inputs.clear_input();
+                        return (
+                            ModeControl::Push(Box::new(NoteEntryMode::new())),
+                            ModeUpdate::Immediate,
+                        );
+                    }
+                    PlayerInputResult::ShowJournal => {
+                        inputs.clear_input();
+                        return (
+                            ModeControl::Push(Box::new(JournalMode::new(world))),
+                            ModeUpdate::Immediate,
+                        );
+                    }
+                    PlayerInputResult::ShowInteractMenu => {
+                        inputs.clear_input();
+                        return (
+                            ModeControl::Push(Box::new(InteractMenuMode::new(world))),
+                            ModeUpdate::Immediate,
+                        );
+                    }
+                    PlayerInputResult::DoAgain => {
+                        input_macro::do_again(world, inputs);
+                        None
+                    }
+                    PlayerInputResult::ToggleMacroRecording => {
+                        input_macro::toggle_recording(world);
+                        None
+                    }
+                    PlayerInputResult::PlayMacro => {
+                        input_macro::play_macro(world, inputs);
+                        None
+                    }
+                    PlayerInputResult::ShowQuestLog => {
+                        inputs.clear_input();
+                        return (
+                            ModeControl::Push(Box::new(QuestLogMode::new(world))),
+                            ModeUpdate::Immediate,
+                        );
+                    }
+                    PlayerInputResult::ShowBestiary => {
+                        inputs.clear_input();
+                        return (
+                            ModeControl::Push(Box::new(BestiaryMode::new(world))),
+                            ModeUpdate::Immediate,
+                        );
+                    }
+                    PlayerInputResult::FallDown => Some(PlayerAction::FallDown),
+                    PlayerInputResult::TalkTo(npc_id) => {
+                        inputs.clear_input();
+                        return (
+                            ModeControl::Push(Box::new(DialogMode::new(world, npc_id))),
+                            ModeUpdate::Immediate,
+                        );
+                    }
+                    PlayerInputResult::OpenChest(chest_id) => {
+                        inputs.clear_input();
+                        return (
+                            ModeControl::Push(Box::new(ChestMode::new(chest_id))),
+                            ModeUpdate::Immediate,
+                        );
+                    }
                 }
             };
 
-            if time_passed {
-                world.run(damage::handle_dead_entities);
-                world.run(experience::gain_levels);
-                world.run(vision::recalculate_fields_of_view);
-                world.run(monster::enqueue_monster_turns);
-
-                if world.run(player::player_is_alive) {
-                    monster::do_monster_turns(world);
-                    world.run(damage::handle_dead_entities);
-                    world.run(experience::gain_levels);
-                    world.run(vision::recalculate_fields_of_view);
+            let outcome = action.map(|action| game_loop::advance_turn(world, action));
+            let time_passed = outcome.as_ref().is_some_and(|outcome| outcome.time_passed);
+            let mut save_error = None;
 
-                    if world.run(player::player_is_alive) {
-                        world.run(hunger::tick_hunger);
-                        world.run(damage::handle_dead_entities);
-                        world.run(experience::gain_levels);
-                        world.run(vision::recalculate_fields_of_view);
+            if let Some(outcome) = &outcome {
+                if outcome.victory {
+                    return self.victory(world, inputs);
+                }
 
-                        if world.run(player::player_is_alive) {
-                            world.run(damage::clear_hurt_bys);
-                            world.borrow::<UniqueViewMut<TurnCount>>().0 += 1;
-                            world.borrow::<UniqueViewMut<Messages>>().separator();
+                // Save upon arriving on a new level, and every so often while alive.
+                if outcome.depth_changed {
+                    save_error = saveload::save_game(world).err();
+                } else if outcome.time_passed && outcome.player_alive {
+                    let autosave_turns = world.borrow::<UniqueView<Options>>().autosave_turns;
+                    let turn_count = world.borrow::<UniqueView<TurnCount>>().0;
+
+                    // An interval of zero disables autosaving.
+                    if turn_count.checked_rem(u64::from(autosave_turns)) == Some(0) {
+                        if let Err(e) = saveload::save_game(world) {
+                            save_error = Some(e);
+                            let msg = world
+                                .borrow::<UniqueView<Locale>>()
+                                .get("msg.autosave_failed")
+                                .into();
+                            world
+                                .borrow::<UniqueViewMut<Messages>>()
+                                .add_as(MsgCategory::Warning, msg);
                         }
                     }
                 }
+            }
+
+            if time_passed {
+                recording::record_turn(world);
+                tutorial::advance(world);
+                hints::update(world);
 
                 // Redraw map chunks containing the player's old and new fields of view.
                 let new_player_fov = world.run(get_player_fov);
@@ -361,8 +721,17 @@ impl DungeonMode {
                     .mark_dirty(new_player_fov.0, new_player_fov.1);
             }
 
+            world.run(stats::record_seen_monsters);
+
+            // Redraw around tiles that changed, since neighboring walls may need new symbols.
+            for (x, y) in world.borrow::<UniqueViewMut<Map>>().take_changed_tiles() {
+                self.chunked_map_grid
+                    .mark_dirty(Position { x: x - 1, y: y - 1 }, Size { w: 3, h: 3 });
+            }
+
+            let new_depth = world.borrow::<UniqueView<Map>>().depth;
+
             {
-                let new_depth = world.borrow::<UniqueView<Map>>().depth;
                 let new_player_pos = world.run(get_player_pos);
 
                 // Redraw all map chunks when changing levels.
@@ -381,17 +750,53 @@ impl DungeonMode {
                 }
             }
 
+            input_macro::end_action(world, inputs, time_passed || new_depth != old_depth);
+
+            // Only report failures once the turn has been fully dealt with.
+            if let Some(Some((item_id, e))) = outcome.map(|outcome| outcome.unsaved_victory) {
+                return self.report(
+                    Error::new("Failed to save game", e),
+                    Retry::Victory(item_id),
+                );
+            }
+            if let Some(e) = save_error {
+                return self.report(Error::new("Failed to save game", e), Retry::Checkpoint);
+            }
+            if let Err(e) = stats::check_profile_saved(world) {
+                return self.report(e, Retry::Profile { won: false });
+            }
+
             (
                 ModeControl::Stay,
-                if world.run(player::player_is_alive) && world.run(player::player_is_auto_running) {
+                if self.chunked_map_grid.has_pending_chunks()
+                    || world.run(player::player_is_alive)
+                        && (world.run(player::player_is_auto_running)
+                            || world.run(player::player_has_item_batch)
+                            || !world.borrow::<UniqueView<Animations>>().is_empty())
+                {
                     ModeUpdate::Update
                 } else {
                     ModeUpdate::WaitForEvent
                 },
             )
+        } else if let Some(result) = pop_result
+            .as_ref()
+            .and_then(|result| result.downcast_ref::<ErrorDialogModeResult>())
+        {
+            // Only the profile is written once the player is dead.
+            match (result, self.retry.take()) {
+                (ErrorDialogModeResult::AppQuit, _) => quit(world, inputs, QuitTo::App),
+                (ErrorDialogModeResult::Retry, Some(retry @ Retry::Profile { .. })) => {
+                    match stats::save_unsaved_profile(world) {
+                        Ok(()) => (ModeControl::Stay, ModeUpdate::WaitForEvent),
+                        Err(e) => self.report(e, retry),
+                    }
+                }
+                _ => (ModeControl::Stay, ModeUpdate::WaitForEvent),
+            }
         } else if player::player_is_dead_input(inputs) {
             (
-                ModeControl::Switch(GameOverMode::new().into()),
+                ModeControl::Switch(Box::new(GameOverMode::new())),
                 ModeUpdate::Immediate,
             )
         } else {
@@ -399,7 +804,7 @@ impl DungeonMode {
         }
     }
 
-    pub fn draw(&mut self, world: &World, grids: &mut [TileGrid<GameSym>], active: bool) {
+    fn draw(&mut self, world: &World, grids: &mut [TileGrid<GameSym>], active: bool) {
         let (map_grid, grids) = grids.split_first_mut().unwrap(); // ui::MAP_GRID
         let (status_grid, grids) = grids.split_first_mut().unwrap(); // ui::STATUS_GRID
         let (item_grid, grids) = grids.split_first_mut().unwrap(); // ui::ITEM_GRID
@@ -422,12 +827,17 @@ impl DungeonMode {
 
         self.chunked_map_grid.draw(world, map_grid);
         render::draw_renderables(&self.chunked_map_grid, world, map_grid);
+        animation::draw_popups(&self.chunked_map_grid, world, map_grid);
 
         if self.redraw_msg_frame_grid {
-            ui::draw_msg_frame(msg_frame_grid, false);
+            ui::draw_msg_frame(world, msg_frame_grid, false);
         }
 
         msg_grid.clear();
         ui::draw_ui(world, status_grid, item_grid, msg_grid, None);
     }
+
+    fn draw_behind(&self) -> bool {
+        false
+    }
 }
@@ -4,6 +4,8 @@ use crate::{
     components::{Name, Renderable},
     gamekey::{self, GameKey},
     gamesym::GameSym,
+    locale::Locale,
+    render,
     ui::{self, Options},
 };
 use ruggrogue::{
@@ -11,9 +13,9 @@ use ruggrogue::{
     InputBuffer, InputEvent, KeyMods, TileGrid, Tileset,
 };
 
-use super::{ModeControl, ModeResult, ModeUpdate};
+use super::{Mode, ModeControl, ModeResult, ModeUpdate};
 
-const CANCEL: &str = "[ Cancel ]";
+const CANCEL: &str = "menu.cancel";
 
 pub enum EquipmentActionModeResult {
     AppQuit,
@@ -43,17 +45,19 @@ impl EquipmentAction {
         }
     }
 
-    pub fn name(&self) -> &'static str {
+    /// Key of the name of the action in the locale catalogs.
+    pub fn name_key(&self) -> &'static str {
         match self {
-            EquipmentAction::RemoveEquipment => "Remove",
-            EquipmentAction::DropEquipment => "Drop",
+            EquipmentAction::RemoveEquipment => "equipment_action.remove",
+            EquipmentAction::DropEquipment => "equipment_action.drop",
         }
     }
 
-    fn label(&self) -> &'static str {
+    /// Key of the button label of the action in the locale catalogs.
+    fn label_key(&self) -> &'static str {
         match self {
-            EquipmentAction::RemoveEquipment => "[ Remove ]",
-            EquipmentAction::DropEquipment => "[ Drop ]",
+            EquipmentAction::RemoveEquipment => "equipment_action.remove_button",
+            EquipmentAction::DropEquipment => "equipment_action.drop_button",
         }
     }
 }
@@ -82,10 +86,14 @@ impl EquipmentActionMode {
         let selection = default_action
             .and_then(|d_act| actions.iter().position(|a| *a == d_act))
             .unwrap_or(0);
-        let item_width = world.borrow::<View<Name>>().get(item_id).0.len();
-        let inner_width = 2 + item_width
-            .max(CANCEL.len())
-            .max(actions.iter().map(|a| a.label().len()).max().unwrap_or(0));
+        let locale = world.borrow::<UniqueView<Locale>>();
+        let item_width = world.borrow::<View<Name>>().get(item_id).0.chars().count();
+        let inner_width = 2 + actions
+            .iter()
+            .map(|a| a.label_key())
+            .chain([CANCEL])
+            .map(|key| locale.get(key).chars().count())
+            .fold(item_width, usize::max);
 
         Self {
             item_id,
@@ -96,8 +104,26 @@ impl EquipmentActionMode {
         }
     }
 
-    pub fn prepare_grids(
-        &self,
+    fn confirm_action(&self) -> (ModeControl, ModeUpdate) {
+        let result = match self.subsection {
+            SubSection::Actions => match self.actions[self.selection as usize] {
+                EquipmentAction::RemoveEquipment => {
+                    EquipmentActionModeResult::RemoveEquipment(self.item_id)
+                }
+                EquipmentAction::DropEquipment => {
+                    EquipmentActionModeResult::DropEquipment(self.item_id)
+                }
+            },
+            SubSection::Cancel => EquipmentActionModeResult::Cancelled,
+        };
+
+        (ModeControl::Pop(Box::new(result)), ModeUpdate::Immediate)
+    }
+}
+
+impl Mode<World, GameSym> for EquipmentActionMode {
+    fn prepare_grids(
+        &mut self,
         world: &World,
         grids: &mut Vec<TileGrid<GameSym>>,
         tilesets: &[Tileset<GameSym>],
@@ -123,23 +149,7 @@ impl EquipmentActionMode {
         grids[0].view.zoom = text_zoom;
     }
 
-    fn confirm_action(&self) -> (ModeControl, ModeUpdate) {
-        let result = match self.subsection {
-            SubSection::Actions => match self.actions[self.selection as usize] {
-                EquipmentAction::RemoveEquipment => {
-                    EquipmentActionModeResult::RemoveEquipment(self.item_id)
-                }
-                EquipmentAction::DropEquipment => {
-                    EquipmentActionModeResult::DropEquipment(self.item_id)
-                }
-            },
-            SubSection::Cancel => EquipmentActionModeResult::Cancelled,
-        };
-
-        (ModeControl::Pop(result.into()), ModeUpdate::Immediate)
-    }
-
-    pub fn update(
+    fn update(
         &mut self,
         _world: &World,
         inputs: &mut InputBuffer,
@@ -150,7 +160,7 @@ impl EquipmentActionMode {
 
         if let Some(InputEvent::AppQuit) = inputs.get_input() {
             return (
-                ModeControl::Pop(EquipmentActionModeResult::AppQuit.into()),
+                ModeControl::Pop(Box::new(EquipmentActionModeResult::AppQuit)),
                 ModeUpdate::Immediate,
             );
         } else if let Some(InputEvent::Press(keycode)) = inputs.get_input() {
@@ -187,7 +197,7 @@ impl EquipmentActionMode {
                 },
                 GameKey::Cancel => {
                     return (
-                        ModeControl::Pop(EquipmentActionModeResult::Cancelled.into()),
+                        ModeControl::Pop(Box::new(EquipmentActionModeResult::Cancelled)),
                         ModeUpdate::Immediate,
                     )
                 }
@@ -215,7 +225,7 @@ impl EquipmentActionMode {
         (ModeControl::Stay, ModeUpdate::WaitForEvent)
     }
 
-    pub fn draw(&self, world: &World, grids: &mut [TileGrid<GameSym>], active: bool) {
+    fn draw(&mut self, world: &World, grids: &mut [TileGrid<GameSym>], active: bool) {
         let grid = &mut grids[0];
         let fg = Color::WHITE;
         let bg = Color::BLACK;
@@ -225,19 +235,22 @@ impl EquipmentActionMode {
 
         grid.draw_box((0, 0), (grid.width(), grid.height()), fg, bg);
 
+        let locale = world.borrow::<UniqueView<Locale>>();
+
         {
             let names = world.borrow::<View<Name>>();
             let renderables = world.borrow::<View<Renderable>>();
+            let options = world.borrow::<UniqueView<Options>>();
             let render = renderables.get(self.item_id);
 
-            grid.put_sym_color((2, 2), render.sym, render.fg, render.bg);
+            render::put_renderable(grid, (2, 2), render, &options);
             grid.print_color((4, 2), &names.get(self.item_id).0, true, fg, bg);
         }
 
         for (i, action) in self.actions.iter().enumerate() {
             grid.print_color(
                 (4, 4 + i as i32),
-                action.label(),
+                locale.get(action.label_key()),
                 true,
                 fg,
                 if matches!(self.subsection, SubSection::Actions) && i as i32 == self.selection {
@@ -250,7 +263,7 @@ impl EquipmentActionMode {
 
         grid.print_color(
             (4, grid.height() as i32 - 3),
-            CANCEL,
+            locale.get(CANCEL),
             true,
             fg,
             if matches!(self.subsection, SubSection::Cancel) {
@@ -259,5 +272,12 @@ impl EquipmentActionMode {
                 bg
             },
         );
+
+        let selected_row = match self.subsection {
+            SubSection::Actions => 4 + self.selection,
+            SubSection::Cancel => grid.height() as i32 - 3,
+        };
+
+        ui::scroll_menu_grid(grid, selected_row, fg, bg);
     }
 }
@@ -4,19 +4,21 @@ use crate::{
     components::{Equipment, Name, Renderable},
     gamekey::{self, GameKey},
     gamesym::GameSym,
+    locale::Locale,
     menu_memory::MenuMemory,
-    message::Messages,
+    message::{Messages, MsgCategory},
     player::PlayerId,
+    render,
     ui::{self, Options},
 };
 use ruggrogue::{
-    util::{Color, Size},
-    InputBuffer, InputEvent, KeyMods, TileGrid, Tileset,
+    util::{Color, Position, Size},
+    Area, Extent, InputBuffer, InputEvent, KeyMods, Layout, Menu, MenuItem, TileGrid, Tileset,
 };
 
-use super::{equipment_action::EquipmentAction, ModeControl, ModeResult, ModeUpdate};
+use super::{equipment_action::EquipmentAction, Mode, ModeControl, ModeResult, ModeUpdate};
 
-const CANCEL: &str = "[ Cancel ]";
+const CANCEL: &str = "menu.cancel";
 
 pub enum EquipmentShortcutModeResult {
     AppQuit,
@@ -25,19 +27,13 @@ pub enum EquipmentShortcutModeResult {
     DropEquipment(EntityId),
 }
 
-enum SubSection {
-    Items,
-    Cancel,
-}
-
 pub struct EquipmentShortcutMode {
     action: EquipmentAction,
     title: String,
     prompt: String,
-    items: Vec<EntityId>,
+    /// Equipment to choose from, followed by a cancel entry with no equipment.
+    menu: Menu<Option<EntityId>>,
     inner_width: i32,
-    subsection: SubSection,
-    selection: i32,
 }
 
 /// Show equipment for which a given action can be performed, shortcutting the inventory and
@@ -55,60 +51,42 @@ impl EquipmentShortcutMode {
             .copied()
             .chain(player_equipment.armor)
             .collect::<Vec<EntityId>>();
-        let title = format!("< {} Equipment >", action.name());
-        let prompt = format!("{} which equipment?", action.name());
-        let inner_width = title.len().max(prompt.len()).max(CANCEL.len()).max(
-            items
-                .iter()
-                .map(|it| names.get(*it).0.len() + 2)
-                .max()
-                .unwrap_or(2),
-        );
+        let locale = world.borrow::<UniqueView<Locale>>();
+        let action_name = locale.get(action.name_key());
+        let title = locale.format("equipment_shortcut.title", &[("action", &action_name)]);
+        let prompt = locale.format("equipment_shortcut.prompt", &[("action", &action_name)]);
+        let inner_width = title
+            .chars()
+            .count()
+            .max(prompt.chars().count())
+            .max(locale.get(CANCEL).chars().count())
+            .max(
+                items
+                    .iter()
+                    .map(|it| names.get(*it).0.chars().count() + 2)
+                    .max()
+                    .unwrap_or(2),
+            );
         let selection = match action {
             EquipmentAction::RemoveEquipment => menu_memory[MenuMemory::EQUIPMENT_SHORTCUT_REMOVE],
             EquipmentAction::DropEquipment => menu_memory[MenuMemory::EQUIPMENT_SHORTCUT_DROP],
         };
-        let selection = selection.min(items.len().saturating_sub(1) as i32);
+        let menu = Menu::new(
+            items
+                .iter()
+                .map(|it| MenuItem::new(Some(*it), names.get(*it).0.clone()))
+                .collect(),
+        )
+        .with_footer(vec![MenuItem::new(None, locale.get(CANCEL))])
+        .with_selection(selection.max(0) as usize);
 
         Self {
             action,
             title,
             prompt,
-            items,
+            menu,
             inner_width: inner_width as i32,
-            subsection: SubSection::Items,
-            selection,
-        }
-    }
-
-    pub fn prepare_grids(
-        &self,
-        world: &World,
-        grids: &mut Vec<TileGrid<GameSym>>,
-        tilesets: &[Tileset<GameSym>],
-        window_size: Size,
-    ) {
-        let Options {
-            font, text_zoom, ..
-        } = *world.borrow::<UniqueView<Options>>();
-        let tileset = &tilesets.get(font as usize).unwrap_or(&tilesets[0]);
-        let new_grid_size = Size {
-            w: self.inner_width as u32 + 4,
-            h: (8 + self.items.len() as u32)
-                .min(window_size.h / (tileset.tile_height() * text_zoom))
-                .max(9),
-        };
-
-        if !grids.is_empty() {
-            grids[0].resize(new_grid_size);
-        } else {
-            grids.push(TileGrid::new(new_grid_size, tilesets, font as usize));
-            grids[0].view.clear_color = None;
         }
-
-        grids[0].set_tileset(tilesets, font as usize);
-        grids[0].view_centered(tilesets, text_zoom, (0, 0).into(), window_size);
-        grids[0].view.zoom = text_zoom;
     }
 
     /// The height of the item list as an i32 for convenience.
@@ -117,9 +95,8 @@ impl EquipmentShortcutMode {
     }
 
     fn confirm_action(&self) -> (ModeControl, ModeUpdate) {
-        let item_id = self.items[self.selection as usize];
-        let result = match self.subsection {
-            SubSection::Items => match self.action {
+        let result = match self.menu.selected().and_then(|entry| entry.value) {
+            Some(item_id) => match self.action {
                 EquipmentAction::RemoveEquipment => {
                     EquipmentShortcutModeResult::RemoveEquipment(item_id)
                 }
@@ -127,27 +104,68 @@ impl EquipmentShortcutMode {
                     EquipmentShortcutModeResult::DropEquipment(item_id)
                 }
             },
-            SubSection::Cancel => EquipmentShortcutModeResult::Cancelled,
+            None => EquipmentShortcutModeResult::Cancelled,
         };
 
-        (ModeControl::Pop(result.into()), ModeUpdate::Immediate)
+        (ModeControl::Pop(Box::new(result)), ModeUpdate::Immediate)
     }
+}
 
-    pub fn update(
+impl Mode<World, GameSym> for EquipmentShortcutMode {
+    fn prepare_grids(
+        &mut self,
+        world: &World,
+        grids: &mut Vec<TileGrid<GameSym>>,
+        tilesets: &[Tileset<GameSym>],
+        window_size: Size,
+    ) {
+        let Options {
+            font, text_zoom, ..
+        } = *world.borrow::<UniqueView<Options>>();
+        let layout = Layout::new(
+            Extent::Cells(self.inner_width as u32 + 4),
+            Extent::Cells(8 + self.menu.items().len() as u32),
+        )
+        .fitted_height(0)
+        .with_min_size(Size { w: 0, h: 9 });
+
+        if grids.is_empty() {
+            // Use a bogus size; the layout will resize the grid below.
+            grids.push(TileGrid::new(Size { w: 1, h: 1 }, tilesets, font as usize));
+            grids[0].view.clear_color = None;
+        }
+
+        grids[0].set_tileset(tilesets, font as usize);
+        layout.apply(
+            &mut grids[0],
+            tilesets,
+            text_zoom,
+            Area::window(window_size),
+        );
+    }
+
+    fn update(
         &mut self,
         world: &World,
         inputs: &mut InputBuffer,
         grids: &[TileGrid<GameSym>],
         _pop_result: &Option<ModeResult>,
     ) -> (ModeControl, ModeUpdate) {
-        if self.items.is_empty() {
-            world.borrow::<UniqueViewMut<Messages>>().add(format!(
-                "You have no equipment to {}.",
-                self.action.name().to_lowercase(),
-            ));
+        if self.menu.items().is_empty() {
+            let msg = {
+                let locale = world.borrow::<UniqueView<Locale>>();
+
+                locale.format(
+                    "msg.no_equipment_to",
+                    &[("action", &locale.get(self.action.name_key()).to_lowercase())],
+                )
+            };
+            world
+                .borrow::<UniqueViewMut<Messages>>()
+                .add_as(MsgCategory::Warning, msg);
 
             (
-                ModeControl::Pop(EquipmentShortcutModeResult::Cancelled.into()),
+                ModeControl::Pop(Box::new(EquipmentShortcutModeResult::Cancelled)),
                 ModeUpdate::Immediate,
             )
         } else {
@@ -155,81 +173,30 @@ impl EquipmentShortcutMode {
 
             if let Some(InputEvent::AppQuit) = inputs.get_input() {
                 return (
-                    ModeControl::Pop(EquipmentShortcutModeResult::AppQuit.into()),
+                    ModeControl::Pop(Box::new(EquipmentShortcutModeResult::AppQuit)),
                     ModeUpdate::Immediate,
                 );
             } else if let Some(InputEvent::Press(keycode)) = inputs.get_input() {
-                match gamekey::from_keycode(keycode, inputs.get_mods(KeyMods::SHIFT)) {
-                    GameKey::Up => match self.subsection {
-                        SubSection::Items => {
-                            if self.selection > 0 {
-                                self.selection -= 1;
-                            } else {
-                                self.subsection = SubSection::Cancel;
-                            }
-                        }
-                        SubSection::Cancel => {
-                            self.subsection = SubSection::Items;
-                            self.selection = self.items.len() as i32 - 1;
-                        }
-                    },
-                    GameKey::Down => match self.subsection {
-                        SubSection::Items => {
-                            if self.selection < self.items.len() as i32 - 1 {
-                                self.selection += 1;
-                            } else {
-                                self.subsection = SubSection::Cancel;
-                            }
-                        }
-                        SubSection::Cancel => {
-                            self.subsection = SubSection::Items;
-                            self.selection = 0;
-                        }
-                    },
-                    GameKey::PageUp => {
-                        if matches!(self.subsection, SubSection::Items) {
-                            if let Some(grid) = grids.get(0) {
-                                self.selection = self
-                                    .selection
-                                    .saturating_sub(Self::item_list_height(grid))
-                                    .max(0);
-                            }
-                        }
-                    }
-                    GameKey::PageDown => {
-                        if matches!(self.subsection, SubSection::Items) {
-                            if let Some(grid) = grids.get(0) {
-                                let max_selection = (self.items.len() as i32 - 1).max(0);
+                let key = gamekey::from_keycode(keycode, inputs.get_mods(KeyMods::SHIFT));
 
-                                self.selection = self
-                                    .selection
-                                    .saturating_add(Self::item_list_height(grid))
-                                    .min(max_selection);
-                            }
-                        }
-                    }
-                    GameKey::Home => {
-                        if matches!(self.subsection, SubSection::Items) {
-                            self.selection = 0;
-                        }
-                    }
-                    GameKey::End => {
-                        if matches!(self.subsection, SubSection::Items) {
-                            self.selection = (self.items.len() as i32 - 1).max(0);
+                if let Some(nav) = gamekey::menu_nav(&key) {
+                    let page_height = grids.first().map_or(1, Self::item_list_height);
+
+                    self.menu.navigate(nav, page_height);
+                } else {
+                    match key {
+                        GameKey::Cancel => {
+                            return (
+                                ModeControl::Pop(Box::new(EquipmentShortcutModeResult::Cancelled)),
+                                ModeUpdate::Immediate,
+                            )
                         }
-                    }
-                    GameKey::Cancel => {
-                        return (
-                            ModeControl::Pop(EquipmentShortcutModeResult::Cancelled.into()),
-                            ModeUpdate::Immediate,
-                        )
-                    }
-                    GameKey::Confirm => return self.confirm_action(),
-                    key => {
-                        if let Some(action) = EquipmentAction::from_key(key) {
-                            if action == self.action && matches!(self.subsection, SubSection::Items)
-                            {
-                                return self.confirm_action();
+                        GameKey::Confirm => return self.confirm_action(),
+                        key => {
+                            if let Some(action) = EquipmentAction::from_key(key) {
+                                if action == self.action && !self.menu.in_footer() {
+                                    return self.confirm_action();
+                                }
                             }
                         }
                     }
@@ -247,7 +214,7 @@ impl EquipmentShortcutMode {
                         }
                     };
 
-                    *menu_memory = self.selection;
+                    *menu_memory = self.menu.selection() as i32;
                 }
             }
 
@@ -255,7 +222,7 @@ impl EquipmentShortcutMode {
         }
     }
 
-    pub fn draw(&self, world: &World, grids: &mut [TileGrid<GameSym>], active: bool) {
+    fn draw(&mut self, world: &World, grids: &mut [TileGrid<GameSym>], active: bool) {
         let grid = &mut grids[0];
         let width = grid.width();
         let height = grid.height();
@@ -270,67 +237,36 @@ impl EquipmentShortcutMode {
         grid.print((2, 2), &self.prompt);
 
         let list_height = Self::item_list_height(grid);
-        let list_offset = (self.selection - (list_height - 1) / 2)
-            .min(self.items.len() as i32 - list_height)
-            .max(0);
 
-        if self.items.len() as i32 > list_height {
-            grid.draw_bar(
-                true,
-                (width as i32 - 1, 4),
-                list_height,
-                list_offset,
+        {
+            let renderables = world.borrow::<View<Renderable>>();
+            let options = world.borrow::<UniqueView<Options>>();
+
+            self.menu.draw_list(
+                grid,
+                Position { x: 2, y: 4 },
                 list_height,
-                self.items.len() as i32,
                 fg,
                 bg,
+                selected_bg,
+                |grid, pos, _, item, item_bg| {
+                    if let Some(item_id) = item.value {
+                        render::put_renderable(grid, pos, renderables.get(item_id), &options);
+                        grid.print_color((pos.x + 2, pos.y), &item.label, true, fg, item_bg);
+                    }
+                },
             );
         }
 
-        {
-            let names = world.borrow::<View<Name>>();
-            let renderables = world.borrow::<View<Renderable>>();
-
-            for (i, item_id) in self
-                .items
-                .iter()
-                .enumerate()
-                .skip(list_offset as usize)
-                .take(list_height as usize)
-            {
-                let render = renderables.get(*item_id);
-
-                grid.put_sym_color(
-                    (2, 4 + i as i32 - list_offset),
-                    render.sym,
-                    render.fg,
-                    render.bg,
-                );
-
-                grid.print_color(
-                    (4, 4 + i as i32 - list_offset),
-                    &names.get(*item_id).0,
-                    true,
-                    fg,
-                    if matches!(self.subsection, SubSection::Items) && i as i32 == self.selection {
-                        selected_bg
-                    } else {
-                        bg
-                    },
-                );
-            }
-        }
-
-        grid.print_color(
-            (4, height as i32 - 3),
-            CANCEL,
-            true,
-            fg,
-            if matches!(self.subsection, SubSection::Cancel) {
-                selected_bg
-            } else {
-                bg
+        self.menu.draw_footer(
+            grid,
+            Position {
+                x: 4,
+                y: height as i32 - 3,
             },
+            fg,
+            bg,
+            selected_bg,
         );
     }
 }
@@ -0,0 +1,169 @@
+use shipyard::{UniqueView, World};
+use std::error::Error as _;
+
+use crate::{
+    gamekey::{self, GameKey},
+    gamesym::GameSym,
+    locale::Locale,
+    ui::{self, Options},
+};
+use ruggrogue::{
+    util::{Color, Size},
+    Error, InputBuffer, InputEvent, KeyMods, TileGrid, Tileset,
+};
+
+use super::{Mode, ModeControl, ModeResult, ModeUpdate};
+
+/// Widest that the text of the error is allowed to be before it's wrapped.
+const MAX_WIDTH: usize = 60;
+
+pub enum ErrorDialogModeResult {
+    AppQuit,
+    Retry,
+    Ignore,
+}
+
+pub struct ErrorDialogMode {
+    msg: Vec<String>,
+    inner_width: u32,
+    retry_selected: bool,
+}
+
+/// A dialog box that tells the player about an error reported by the mode underneath it, letting
+/// them retry whatever failed or carry on without it.
+impl ErrorDialogMode {
+    pub fn new(error: &Error) -> Self {
+        let mut msg = vec![format!("{}:", error.context()), "".to_string()];
+
+        if let Some(source) = error.source() {
+            msg.extend(ruggrogue::word_wrap(&format!("{}", source), MAX_WIDTH).map(String::from));
+        }
+
+        let inner_width = msg.iter().map(|m| m.chars().count()).max().unwrap_or(0) as u32;
+
+        Self {
+            msg,
+            inner_width,
+            retry_selected: true,
+        }
+    }
+}
+
+impl Mode<World, GameSym> for ErrorDialogMode {
+    fn prepare_grids(
+        &mut self,
+        world: &World,
+        grids: &mut Vec<TileGrid<GameSym>>,
+        tilesets: &[Tileset<GameSym>],
+        window_size: Size,
+    ) {
+        let Options {
+            font, text_zoom, ..
+        } = *world.borrow::<UniqueView<Options>>();
+        let locale = world.borrow::<UniqueView<Locale>>();
+        let buttons_width = locale.get("error.retry").chars().count()
+            + locale.get("error.ignore").chars().count()
+            + 2;
+        let new_grid_size = Size {
+            w: self.inner_width.max(buttons_width as u32) + 4,
+            h: self.msg.len() as u32 + 6,
+        };
+
+        if !grids.is_empty() {
+            grids[0].resize(new_grid_size);
+        } else {
+            grids.push(TileGrid::new(new_grid_size, tilesets, font as usize));
+            grids[0].view.clear_color = None;
+        }
+
+        grids[0].set_tileset(tilesets, font as usize);
+        grids[0].view_centered(tilesets, text_zoom, (0, 0).into(), window_size);
+        grids[0].view.zoom = text_zoom;
+    }
+
+    fn update(
+        &mut self,
+        _world: &World,
+        inputs: &mut InputBuffer,
+        _grids: &[TileGrid<GameSym>],
+        _pop_result: &Option<ModeResult>,
+    ) -> (ModeControl, ModeUpdate) {
+        inputs.prepare_input();
+
+        if let Some(InputEvent::AppQuit) = inputs.get_input() {
+            return (
+                ModeControl::Pop(Box::new(ErrorDialogModeResult::AppQuit)),
+                ModeUpdate::Immediate,
+            );
+        } else if let Some(InputEvent::Press(keycode)) = inputs.get_input() {
+            match gamekey::from_keycode(keycode, inputs.get_mods(KeyMods::SHIFT)) {
+                GameKey::Left => self.retry_selected = true,
+                GameKey::Right => self.retry_selected = false,
+                GameKey::Confirm => {
+                    let result = if self.retry_selected {
+                        ErrorDialogModeResult::Retry
+                    } else {
+                        ErrorDialogModeResult::Ignore
+                    };
+
+                    inputs.clear_input();
+                    return (ModeControl::Pop(Box::new(result)), ModeUpdate::Immediate);
+                }
+                GameKey::Cancel => {
+                    inputs.clear_input();
+                    return (
+                        ModeControl::Pop(Box::new(ErrorDialogModeResult::Ignore)),
+                        ModeUpdate::Immediate,
+                    );
+                }
+                _ => {}
+            }
+        }
+
+        (ModeControl::Stay, ModeUpdate::WaitForEvent)
+    }
+
+    fn draw(&mut self, world: &World, grids: &mut [TileGrid<GameSym>], active: bool) {
+        let grid = &mut grids[0];
+        let locale = world.borrow::<UniqueView<Locale>>();
+        let retry_str = locale.get("error.retry");
+        let ignore_str = locale.get("error.ignore");
+        let buttons_y = grid.height() as i32 - 3;
+        let retry_x = grid.width() as i32
+            - (retry_str.chars().count() + ignore_str.chars().count() + 4) as i32;
+        let ignore_x = grid.width() as i32 - ignore_str.chars().count() as i32 - 2;
+        let fg = Color::WHITE;
+        let bg = Color::BLACK;
+        let selected_bg = ui::SELECTED_BG;
+
+        grid.view.color_mod = if active { Color::WHITE } else { Color::GRAY };
+
+        grid.draw_box((0, 0), (grid.width(), grid.height()), fg, bg);
+        grid.print_color((2, 0), locale.get("error.title"), true, Color::YELLOW, bg);
+
+        for (y, msg) in self.msg.iter().enumerate() {
+            grid.print((2, 2 + y as i32), msg);
+        }
+
+        grid.print_color(
+            (retry_x, buttons_y),
+            retry_str,
+            true,
+            fg,
+            if self.retry_selected { selected_bg } else { bg },
+        );
+        grid.print_color(
+            (ignore_x, buttons_y),
+            ignore_str,
+            true,
+            fg,
+            if !self.retry_selected {
+                selected_bg
+            } else {
+                bg
+            },
+        );
+
+        ui::scroll_menu_grid(grid, buttons_y, fg, bg);
+    }
+}
@@ -1,11 +1,14 @@
 use shipyard::{Get, UniqueView, View, World};
 
 use crate::{
-    components::{CombatStats, Equipment, Experience, HurtBy, Inventory, Name, Tally},
+    components::{CombatStats, Equipment, Experience, HurtBy, Inventory, Name, NameSource, Tally},
+    experience::Difficulty,
     gamekey::{self, GameKey},
     gamesym::GameSym,
+    locale::Locale,
     map::Map,
     player::{PlayerAlive, PlayerId},
+    stats,
     ui::Options,
     TurnCount, Wins,
 };
@@ -17,9 +20,43 @@ use ruggrogue::{
 use super::{
     dungeon::DungeonMode,
     title::{self, TitleMode},
-    ModeControl, ModeResult, ModeUpdate,
+    Mode, ModeControl, ModeResult, ModeUpdate,
 };
 
+/// Keys of the labels down the left side of the summary, which the data is lined up beside.
+const LABELS: [&str; 17] = [
+    "game_over.wins",
+    "game_over.defeated_by",
+    "game_over.new_game_plus",
+    "game_over.level",
+    "game_over.experience",
+    "game_over.health",
+    "game_over.attack",
+    "game_over.defense",
+    "game_over.depth",
+    "game_over.turns",
+    "game_over.difficulty",
+    "game_over.items_carried",
+    "game_over.weapon",
+    "game_over.armor",
+    "game_over.damage_dealt",
+    "game_over.damage_taken",
+    "game_over.foes_defeated",
+];
+
+/// Width of the data shown beside the labels.
+const DATA_WIDTH: i32 = 19;
+
+/// Column where the data starts, just past the widest label in the current language.
+fn data_x(locale: &Locale) -> i32 {
+    LABELS
+        .iter()
+        .map(|l| locale.get(l).chars().count() as i32)
+        .max()
+        .unwrap_or(0)
+        + 1
+}
+
 pub enum GameOverModeResult {
     AppQuit,
 }
@@ -30,9 +67,11 @@ impl GameOverMode {
     pub fn new() -> Self {
         Self {}
     }
+}
 
-    pub fn prepare_grids(
-        &self,
+impl Mode<World, GameSym> for GameOverMode {
+    fn prepare_grids(
+        &mut self,
         world: &World,
         grids: &mut Vec<TileGrid<GameSym>>,
         tilesets: &[Tileset<GameSym>],
@@ -41,7 +80,10 @@ impl GameOverMode {
         let Options {
             font, text_zoom, ..
         } = *world.borrow::<UniqueView<Options>>();
-        let new_grid_size = Size { w: 34, h: 19 };
+        let new_grid_size = Size {
+            w: (data_x(&world.borrow::<UniqueView<Locale>>()) + DATA_WIDTH) as u32,
+            h: 19,
+        };
 
         if !grids.is_empty() {
             grids[0].resize(new_grid_size);
@@ -55,7 +97,7 @@ impl GameOverMode {
         grids[0].view.zoom = text_zoom;
     }
 
-    pub fn update(
+    fn update(
         &mut self,
         world: &World,
         inputs: &mut InputBuffer,
@@ -66,7 +108,7 @@ impl GameOverMode {
 
         if let Some(InputEvent::AppQuit) = inputs.get_input() {
             return (
-                ModeControl::Pop(GameOverModeResult::AppQuit.into()),
+                ModeControl::Pop(Box::new(GameOverModeResult::AppQuit)),
                 ModeUpdate::Immediate,
             );
         } else if let Some(InputEvent::Press(keycode)) = inputs.get_input() {
@@ -83,9 +125,9 @@ impl GameOverMode {
                 return (
                     ModeControl::Switch(if player_alive {
                         // Jump straight into new game plus.
-                        DungeonMode::new().into()
+                        Box::new(DungeonMode::new())
                     } else {
-                        TitleMode::new().into()
+                        Box::new(TitleMode::new(world))
                     }),
                     ModeUpdate::Immediate,
                 );
@@ -95,31 +137,33 @@ impl GameOverMode {
         (ModeControl::Stay, ModeUpdate::WaitForEvent)
     }
 
-    pub fn draw(&self, world: &World, grids: &mut [TileGrid<GameSym>], active: bool) {
-        const DATA_X: i32 = 15;
+    fn draw(&mut self, world: &World, grids: &mut [TileGrid<GameSym>], active: bool) {
         let grid = &mut grids[0];
+        let locale = world.borrow::<UniqueView<Locale>>();
+        let data_x = data_x(&locale);
+        let print_label = |grid: &mut TileGrid<GameSym>, y: i32, key: &str| {
+            let label = locale.get(key);
+
+            grid.print(
+                ((data_x - 1 - label.chars().count() as i32).max(0), y),
+                label,
+            );
+        };
         let data_fg = Color::YELLOW;
         let bg = Color::BLACK;
         let player_alive = world.borrow::<UniqueView<PlayerAlive>>().0;
 
         grid.view.color_mod = if active { Color::WHITE } else { Color::GRAY };
 
-        if player_alive {
-            grid.print_color(
-                (1, 0),
-                "* * *  Y O U   W I N !  * * *",
-                true,
-                Color::GREEN,
-                bg,
-            );
-        } else {
-            grid.print_color(
-                (0, 0),
-                "* * * You have been defeated * * *",
-                true,
-                Color::MAGENTA,
-                bg,
-            );
+        {
+            let (headline, headline_fg) = if player_alive {
+                (locale.get("game_over.win"), Color::GREEN)
+            } else {
+                (locale.get("game_over.defeated"), Color::MAGENTA)
+            };
+            let headline_x = (grid.width() as i32 - headline.chars().count() as i32).max(0) / 2;
+
+            grid.print_color((headline_x, 0), headline, true, headline_fg, bg);
         }
 
         let player_id = world.borrow::<UniqueView<PlayerId>>();
@@ -128,45 +172,52 @@ impl GameOverMode {
             let wins = world.borrow::<UniqueView<Wins>>().0;
 
             if wins < 2 {
-                grid.print((0, 2), "Your birthday present is saved!");
+                grid.print((0, 2), locale.get("game_over.present_saved"));
             } else {
-                grid.print((9, 2), "Wins:");
-                grid.print_color((DATA_X, 2), wins.to_string().as_str(), true, data_fg, bg);
+                print_label(grid, 2, "game_over.wins");
+                grid.print_color((data_x, 2), wins.to_string().as_str(), true, data_fg, bg);
             }
         } else {
-            let names = world.borrow::<View<Name>>();
+            let name_sources = world.borrow::<View<NameSource>>();
             let hurt_bys = world.borrow::<View<HurtBy>>();
-            let defeated_by = match hurt_bys.try_get(player_id.0) {
-                Ok(HurtBy::Someone(hurter)) => {
-                    if *hurter == player_id.0 {
-                        "an overinflated ego"
-                    } else {
-                        names.get(*hurter).0.as_str()
-                    }
-                }
-                Ok(HurtBy::Starvation) => "starvation",
-                Err(_) => "perfectly natural causes",
+            let defeated_by =
+                locale.name(&stats::defeat_cause(player_id.0, &hurt_bys, &name_sources));
+
+            print_label(grid, 2, "game_over.defeated_by");
+            grid.print_color((data_x, 2), &defeated_by, true, data_fg, bg);
+        }
+
+        // Wins are counted as soon as the present is opened, so a winning run started with one
+        // less.
+        {
+            let wins = world.borrow::<UniqueView<Wins>>().0;
+            let ng_plus = if player_alive {
+                wins.saturating_sub(1)
+            } else {
+                wins
             };
 
-            grid.print((2, 2), "Defeated by:");
-            grid.print_color((DATA_X, 2), defeated_by, true, data_fg, bg);
+            if ng_plus > 0 {
+                print_label(grid, 3, "game_over.new_game_plus");
+                grid.print_color((data_x, 3), &ng_plus.to_string(), true, data_fg, bg);
+            }
         }
 
         {
             let exps = world.borrow::<View<Experience>>();
             let player_exp = exps.get(player_id.0);
 
-            grid.print((8, 4), "Level:");
+            print_label(grid, 4, "game_over.level");
             grid.print_color(
-                (DATA_X, 4),
+                (data_x, 4),
                 player_exp.level.to_string().as_str(),
                 true,
                 data_fg,
                 bg,
             );
-            grid.print((3, 5), "Experience:");
+            print_label(grid, 5, "game_over.experience");
             grid.print_color(
-                (DATA_X, 5),
+                (data_x, 5),
                 (player_exp.base + player_exp.exp).to_string().as_str(),
                 true,
                 data_fg,
@@ -178,25 +229,25 @@ impl GameOverMode {
             let combat_stats = world.borrow::<View<CombatStats>>();
             let player_stats = combat_stats.get(player_id.0);
 
-            grid.print((7, 6), "Health:");
+            print_label(grid, 6, "game_over.health");
             grid.print_color(
-                (DATA_X, 6),
+                (data_x, 6),
                 &format!("{} / {}", player_stats.hp, player_stats.max_hp),
                 true,
                 data_fg,
                 bg,
             );
-            grid.print((7, 7), "Attack:");
+            print_label(grid, 7, "game_over.attack");
             grid.print_color(
-                (DATA_X, 7),
+                (data_x, 7),
                 &format!("{:+.0}", player_stats.attack),
                 true,
                 data_fg,
                 bg,
             );
-            grid.print((6, 8), "Defense:");
+            print_label(grid, 8, "game_over.defense");
             grid.print_color(
-                (DATA_X, 8),
+                (data_x, 8),
                 &format!("{:+.0}", player_stats.defense),
                 true,
                 data_fg,
@@ -204,18 +255,18 @@ impl GameOverMode {
             );
         }
 
-        grid.print((8, 9), "Depth:");
+        print_label(grid, 9, "game_over.depth");
         grid.print_color(
-            (DATA_X, 9),
+            (data_x, 9),
             world.borrow::<UniqueView<Map>>().depth.to_string().as_str(),
             true,
             data_fg,
             bg,
         );
 
-        grid.print((8, 10), "Turns:");
+        print_label(grid, 10, "game_over.turns");
         grid.print_color(
-            (DATA_X, 10),
+            (data_x, 10),
             world
                 .borrow::<UniqueView<TurnCount>>()
                 .0
@@ -226,9 +277,24 @@ impl GameOverMode {
             bg,
         );
 
-        grid.print((0, 12), "Items carried:");
+        print_label(grid, 11, "game_over.difficulty");
+        grid.print_color(
+            (data_x, 11),
+            locale.get(
+                world
+                    .borrow::<UniqueView<Difficulty>>()
+                    .settings
+                    .preset
+                    .name_key(),
+            ),
+            true,
+            data_fg,
+            bg,
+        );
+
+        print_label(grid, 12, "game_over.items_carried");
         grid.print_color(
-            (DATA_X, 12),
+            (data_x, 12),
             world
                 .borrow::<View<Inventory>>()
                 .get(player_id.0)
@@ -246,24 +312,24 @@ impl GameOverMode {
             let names = world.borrow::<View<Name>>();
             let player_equipment = equipments.get(player_id.0);
 
-            grid.print((7, 13), "Weapon:");
+            print_label(grid, 13, "game_over.weapon");
             grid.print_color(
-                (DATA_X, 13),
+                (data_x, 13),
                 player_equipment
                     .weapon
                     .map(|w| names.get(w).0.as_str())
-                    .unwrap_or("nothing"),
+                    .unwrap_or_else(|| locale.get("game_over.nothing")),
                 true,
                 data_fg,
                 bg,
             );
-            grid.print((8, 14), "Armor:");
+            print_label(grid, 14, "game_over.armor");
             grid.print_color(
-                (DATA_X, 14),
+                (data_x, 14),
                 player_equipment
                     .armor
                     .map(|a| names.get(a).0.as_str())
-                    .unwrap_or("nothing"),
+                    .unwrap_or_else(|| locale.get("game_over.nothing")),
                 true,
                 data_fg,
                 bg,
@@ -274,25 +340,25 @@ impl GameOverMode {
             let tallies = world.borrow::<View<Tally>>();
             let player_tally = tallies.get(player_id.0);
 
-            grid.print((1, 16), "Damage dealt:");
+            print_label(grid, 16, "game_over.damage_dealt");
             grid.print_color(
-                (DATA_X, 16),
+                (data_x, 16),
                 player_tally.damage_dealt.to_string().as_str(),
                 true,
                 data_fg,
                 bg,
             );
-            grid.print((1, 17), "Damage taken:");
+            print_label(grid, 17, "game_over.damage_taken");
             grid.print_color(
-                (DATA_X, 17),
+                (data_x, 17),
                 player_tally.damage_taken.to_string().as_str(),
                 true,
                 data_fg,
                 bg,
             );
-            grid.print((0, 18), "Foes defeated:");
+            print_label(grid, 18, "game_over.foes_defeated");
             grid.print_color(
-                (DATA_X, 18),
+                (data_x, 18),
                 player_tally.kills.to_string().as_str(),
                 true,
                 data_fg,
@@ -300,4 +366,8 @@ impl GameOverMode {
             );
         }
     }
+
+    fn draw_behind(&self) -> bool {
+        false
+    }
 }
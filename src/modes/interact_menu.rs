@@ -0,0 +1,203 @@
+use sdl2::keyboard::Keycode;
+use shipyard::{UniqueView, UniqueViewMut, World};
+
+use crate::{
+    gamekey::{self, GameKey},
+    gamesym::GameSym,
+    interact::{self, Interaction},
+    locale::Locale,
+    message::{Messages, MsgCategory},
+    ui::{self, Options},
+};
+use ruggrogue::{
+    util::{Color, Position, Size},
+    InputBuffer, InputEvent, KeyMods, Menu, MenuItem, TileGrid, Tileset,
+};
+
+use super::{Mode, ModeControl, ModeResult, ModeUpdate};
+
+const TITLE: &str = "interact.title";
+const CANCEL: &str = "menu.cancel";
+const HOTKEYS: [Keycode; 9] = [
+    Keycode::Num1,
+    Keycode::Num2,
+    Keycode::Num3,
+    Keycode::Num4,
+    Keycode::Num5,
+    Keycode::Num6,
+    Keycode::Num7,
+    Keycode::Num8,
+    Keycode::Num9,
+];
+
+pub enum InteractMenuModeResult {
+    AppQuit,
+    Cancelled,
+    Chosen(Interaction),
+}
+
+pub struct InteractMenuMode {
+    /// Interactions to choose from, followed by a cancel entry with no interaction.
+    menu: Menu<Option<Interaction>>,
+    width: i32,
+}
+
+/// List everything the player can do with their own tile and the tiles around them, so only one
+/// key has to be remembered to do any of it.
+impl InteractMenuMode {
+    pub fn new(world: &World) -> Self {
+        // The first few interactions can be chosen directly with the number keys.
+        let items = interact::available_interactions(world)
+            .into_iter()
+            .enumerate()
+            .map(|(i, (interaction, label))| match HOTKEYS.get(i) {
+                Some(hotkey) => MenuItem::new(Some(interaction), format!("{}) {}", i + 1, label))
+                    .with_hotkey(*hotkey),
+                None => MenuItem::new(Some(interaction), format!("   {}", label)),
+            })
+            .collect::<Vec<_>>();
+        let locale = world.borrow::<UniqueView<Locale>>();
+        let width = items
+            .iter()
+            .map(|item| item.label.chars().count())
+            .chain(
+                [TITLE, CANCEL]
+                    .iter()
+                    .map(|key| locale.get(key).chars().count()),
+            )
+            .max()
+            .unwrap_or(0);
+
+        Self {
+            menu: Menu::new(items).with_footer(vec![MenuItem::new(None, locale.get(CANCEL))]),
+            width: width as i32,
+        }
+    }
+}
+
+impl Mode<World, GameSym> for InteractMenuMode {
+    fn prepare_grids(
+        &mut self,
+        world: &World,
+        grids: &mut Vec<TileGrid<GameSym>>,
+        tilesets: &[Tileset<GameSym>],
+        window_size: Size,
+    ) {
+        let Options {
+            font, text_zoom, ..
+        } = *world.borrow::<UniqueView<Options>>();
+        let new_grid_size = Size {
+            w: self.width as u32 + 4,
+            h: self.menu.items().len() as u32 + 6,
+        };
+
+        if !grids.is_empty() {
+            grids[0].resize(new_grid_size);
+        } else {
+            grids.push(TileGrid::new(new_grid_size, tilesets, font as usize));
+            grids[0].view.clear_color = None;
+            grids[0].view.bg_alpha = ui::OVERLAY_BG_ALPHA;
+        }
+
+        grids[0].set_tileset(tilesets, font as usize);
+        grids[0].view_centered(tilesets, text_zoom, (0, 0).into(), window_size);
+        grids[0].view.zoom = text_zoom;
+    }
+
+    fn update(
+        &mut self,
+        world: &World,
+        inputs: &mut InputBuffer,
+        _grids: &[TileGrid<GameSym>],
+        _pop_result: &Option<ModeResult>,
+    ) -> (ModeControl, ModeUpdate) {
+        if self.menu.items().is_empty() {
+            world.run(
+                |locale: UniqueView<Locale>, mut msgs: UniqueViewMut<Messages>| {
+                    msgs.add_as(
+                        MsgCategory::Warning,
+                        locale.get("msg.nothing_to_interact_with").into(),
+                    );
+                },
+            );
+
+            return (
+                ModeControl::Pop(Box::new(InteractMenuModeResult::Cancelled)),
+                ModeUpdate::Immediate,
+            );
+        }
+
+        inputs.prepare_input();
+
+        if let Some(InputEvent::AppQuit) = inputs.get_input() {
+            return (
+                ModeControl::Pop(Box::new(InteractMenuModeResult::AppQuit)),
+                ModeUpdate::Immediate,
+            );
+        } else if let Some(InputEvent::Press(keycode)) = inputs.get_input() {
+            let shift = inputs.get_mods(KeyMods::SHIFT);
+            let key = gamekey::from_keycode(keycode, shift);
+
+            if let Some(nav) = gamekey::menu_nav(&key) {
+                self.menu.navigate(nav, self.menu.num_entries() as i32);
+            } else if (!shift && self.menu.select_hotkey(keycode))
+                || matches!(key, GameKey::Confirm)
+            {
+                let result = match self.menu.selected().and_then(|item| item.value) {
+                    Some(interaction) => InteractMenuModeResult::Chosen(interaction),
+                    None => InteractMenuModeResult::Cancelled,
+                };
+
+                inputs.clear_input();
+                return (ModeControl::Pop(Box::new(result)), ModeUpdate::Immediate);
+            } else if matches!(key, GameKey::Cancel | GameKey::Interact) {
+                inputs.clear_input();
+                return (
+                    ModeControl::Pop(Box::new(InteractMenuModeResult::Cancelled)),
+                    ModeUpdate::Immediate,
+                );
+            }
+        }
+
+        (ModeControl::Stay, ModeUpdate::WaitForEvent)
+    }
+
+    fn draw(&mut self, world: &World, grids: &mut [TileGrid<GameSym>], active: bool) {
+        let grid = &mut grids[0];
+        let fg = Color::WHITE;
+        let bg = Color::BLACK;
+        let selected_bg = ui::SELECTED_BG;
+
+        grid.view.color_mod = if active { Color::WHITE } else { Color::GRAY };
+
+        grid.draw_box((0, 0), (grid.width(), grid.height()), fg, bg);
+        grid.print_color(
+            (2, 0),
+            world.borrow::<UniqueView<Locale>>().get(TITLE),
+            true,
+            Color::YELLOW,
+            bg,
+        );
+
+        self.menu.draw_labels(
+            grid,
+            Position { x: 2, y: 2 },
+            self.menu.items().len() as i32,
+            fg,
+            bg,
+            selected_bg,
+        );
+
+        let cancel_y = grid.height() as i32 - 3;
+        self.menu
+            .draw_footer(grid, Position { x: 2, y: cancel_y }, fg, bg, selected_bg);
+
+        let selected_row = if self.menu.in_footer() {
+            cancel_y
+        } else {
+            2 + self.menu.selection() as i32
+        };
+
+        ui::scroll_menu_grid(grid, selected_row, fg, bg);
+    }
+}
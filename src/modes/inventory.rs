@@ -1,29 +1,38 @@
+use sdl2::keyboard::Keycode;
 use shipyard::{EntityId, Get, UniqueView, UniqueViewMut, View, World};
 
 use crate::{
-    components::{Equipment, Inventory, Name, Renderable},
+    components::{Equipment, Favorite, Inventory, Locked, Name, Player, Renderable},
+    durability, encumbrance,
     gamekey::{self, GameKey},
     gamesym::GameSym,
-    item,
+    item::{self, SortOrder},
+    locale::Locale,
     menu_memory::MenuMemory,
     player::PlayerId,
+    render,
     ui::{self, Options},
 };
 use ruggrogue::{
-    util::{Color, Size},
-    InputBuffer, InputEvent, KeyMods, TileGrid, Tileset,
+    util::{Color, Position, Size},
+    Anchor, Area, Extent, InputBuffer, InputEvent, KeyMods, Layout, Menu, MenuItem, MenuNav,
+    TileGrid, Tileset,
 };
 
 use super::{
+    container::{ContainerMode, ContainerModeResult},
     equipment_action::{EquipmentAction, EquipmentActionMode, EquipmentActionModeResult},
     inventory_action::{InventoryAction, InventoryActionMode, InventoryActionModeResult},
     yes_no_dialog::{YesNoDialogMode, YesNoDialogModeResult},
-    ModeControl, ModeResult, ModeUpdate,
+    Mode, ModeControl, ModeResult, ModeUpdate,
 };
 
 const EQUIP_GRID: usize = 0;
 const INV_GRID: usize = 1;
 
+/// Height of the equip grid in cells.
+const EQUIP_HEIGHT: u32 = 5;
+
 pub enum InventoryModeResult {
     AppQuit,
     DoNothing,
@@ -44,30 +53,178 @@ enum SubSection {
 pub struct InventoryMode {
     main_width: i32,
     subsection: SubSection,
-    inv_selection: i32,
+    /// Items in the player's inventory that match the filter.
+    menu: Menu<EntityId>,
+    sort_order: usize,
+    filter: String,
+    typing_filter: bool,
 }
 
+const LOCKED_SUFFIX: &str = "inventory.locked_suffix";
+const FILTER_LABEL: &str = "inventory.filter";
+const NOTHING: &str = "inventory.nothing";
+const MAX_FILTER_LEN: usize = 20;
+
 /// Show a screen with items carried by the player, and allow them to be manipulated.
 impl InventoryMode {
     pub fn new(world: &World) -> Self {
         let player_id = world.borrow::<UniqueView<PlayerId>>();
         let inventories = world.borrow::<View<Inventory>>();
-        let names = world.borrow::<View<Name>>();
         let player_inventory = inventories.get(player_id.0);
-        let inv_min_width = player_inventory
-            .items
-            .iter()
-            .map(|it| names.get(*it).0.len() + 2)
-            .max()
-            .unwrap_or(0);
-        let inv_selection = world.borrow::<UniqueView<MenuMemory>>()[MenuMemory::INVENTORY]
+        let menu_memory = world.borrow::<UniqueView<MenuMemory>>();
+        let filter = menu_memory.inventory_filter.clone();
+        let remembered_pos = menu_memory[MenuMemory::INVENTORY]
             .min(player_inventory.items.len().saturating_sub(1) as i32);
+        let remembered = player_inventory
+            .items
+            .get(remembered_pos.max(0) as usize)
+            .copied();
+        let sort_order =
+            (menu_memory[MenuMemory::INVENTORY_SORT].max(0) as usize).min(SortOrder::ALL.len() - 1);
 
         Self {
-            main_width: std::cmp::max(30, inv_min_width as i32),
+            main_width: Self::calc_main_width(world),
             subsection: SubSection::Inventory,
-            inv_selection,
+            menu: Self::item_menu(world, &filter, remembered),
+            sort_order,
+            filter,
+            typing_filter: false,
+        }
+    }
+
+    /// A menu of the items in the player's inventory whose names contain the filter text, ignoring
+    /// case, with the given item selected if it's among them.
+    fn item_menu(world: &World, filter: &str, selected: Option<EntityId>) -> Menu<EntityId> {
+        let player_id = world.borrow::<UniqueView<PlayerId>>();
+        let inventories = world.borrow::<View<Inventory>>();
+        let names = world.borrow::<View<Name>>();
+        let filter = filter.to_lowercase();
+        let mut menu = Menu::new(
+            inventories
+                .get(player_id.0)
+                .items
+                .iter()
+                .filter(|id| names.get(**id).0.to_lowercase().contains(&filter))
+                .map(|id| MenuItem::new(*id, names.get(*id).0.clone()))
+                .collect(),
+        );
+
+        if let Some(selected) = selected {
+            menu.select_where(|id| *id == selected);
+        }
+
+        menu
+    }
+
+    /// The item that the inventory cursor is on, if there are any items.
+    fn selected_item(&self) -> Option<EntityId> {
+        self.menu.selected().map(|item| item.value)
+    }
+
+    /// Remember the selected item by its position in the full inventory, along with the filter,
+    /// so that the same item is selected the next time the inventory is opened.
+    fn remember_selection(&self, world: &World) {
+        let selected = self.selected_item();
+        let player_id = world.borrow::<UniqueView<PlayerId>>();
+        let inventories = world.borrow::<View<Inventory>>();
+        let mut menu_memory = world.borrow::<UniqueViewMut<MenuMemory>>();
+
+        menu_memory[MenuMemory::INVENTORY] = selected
+            .and_then(|selected| {
+                inventories
+                    .get(player_id.0)
+                    .items
+                    .iter()
+                    .position(|id| *id == selected)
+            })
+            .unwrap_or(0) as i32;
+        menu_memory.inventory_filter = self.filter.clone();
+    }
+
+    /// Apply a changed filter or inventory, keeping the selection on the item it was on if it
+    /// still matches.
+    fn refilter(&mut self, world: &World, selected: Option<EntityId>) {
+        self.menu = Self::item_menu(world, &self.filter, selected);
+    }
+
+    /// Handle inputs while the filter is being typed, leaving any key that doesn't edit the filter
+    /// to be ignored so that typing doesn't trigger item actions.
+    fn update_filter_typing(&mut self, world: &World, inputs: &InputBuffer) {
+        let selected = self.selected_item();
+
+        match inputs.get_input() {
+            Some(InputEvent::Text(c))
+                if !c.is_control() && self.filter.chars().count() < MAX_FILTER_LEN =>
+            {
+                self.filter.push(c);
+                self.refilter(world, selected);
+            }
+            Some(InputEvent::Press(Keycode::Backspace)) => {
+                self.filter.pop();
+                self.refilter(world, selected);
+            }
+            Some(InputEvent::Press(keycode)) => {
+                match gamekey::from_keycode(keycode, inputs.get_mods(KeyMods::SHIFT)) {
+                    GameKey::Confirm => self.typing_filter = false,
+                    GameKey::Cancel => {
+                        self.typing_filter = false;
+                        self.filter.clear();
+                        self.refilter(world, selected);
+                    }
+                    _ => {}
+                }
+            }
+            _ => {}
         }
+
+        self.remember_selection(world);
+    }
+
+    /// Width needed to fit the name of every item in the player's inventory.
+    fn calc_main_width(world: &World) -> i32 {
+        let player_id = world.borrow::<UniqueView<PlayerId>>();
+        let inventories = world.borrow::<View<Inventory>>();
+        let lockeds = world.borrow::<View<Locked>>();
+        let names = world.borrow::<View<Name>>();
+        let locked_suffix_len = world
+            .borrow::<UniqueView<Locale>>()
+            .get(LOCKED_SUFFIX)
+            .chars()
+            .count();
+        let inv_min_width = inventories
+            .get(player_id.0)
+            .items
+            .iter()
+            .map(|it| {
+                let locked_len = if lockeds.contains(*it) {
+                    locked_suffix_len
+                } else {
+                    0
+                };
+                let wear_len =
+                    durability::wear_suffix(world, *it).map_or(0, |(s, _)| s.chars().count());
+                names.get(*it).0.chars().count() + 2 + locked_len + wear_len
+            })
+            .max()
+            .unwrap_or(0);
+
+        std::cmp::max(30, inv_min_width as i32)
+    }
+
+    fn sort_all_label(&self, locale: &Locale) -> String {
+        locale.format(
+            "inventory.sort_all",
+            &[(
+                "order",
+                &locale.get(SortOrder::ALL[self.sort_order].name_key()),
+            )],
+        )
+    }
+
+    /// Keep the selection on an item after the inventory was rearranged around it.
+    fn select_item(&mut self, world: &World, item_id: EntityId) {
+        self.refilter(world, Some(item_id));
+        self.remember_selection(world);
     }
 
     /// The height of the item list in the inventory grid as an i32 for convenience.
@@ -75,9 +232,300 @@ impl InventoryMode {
         grid.height().saturating_sub(6).max(1).min(i32::MAX as u32) as i32
     }
 
-    pub fn prepare_grids(
+    /// Note how worn an item is just after text printed at the given position.
+    fn draw_wear(
+        world: &World,
+        grid: &mut TileGrid<GameSym>,
+        pos: (i32, i32),
+        item_id: EntityId,
+        text: &str,
+        bg: Color,
+    ) {
+        if let Some((wear, wear_fg)) = durability::wear_suffix(world, item_id) {
+            grid.print_color(
+                (pos.0 + text.chars().count() as i32, pos.1),
+                wear,
+                true,
+                wear_fg,
+                bg,
+            );
+        }
+    }
+
+    fn draw_equip(
         &self,
         world: &World,
+        grid: &mut TileGrid<GameSym>,
+        fg: Color,
+        bg: Color,
+        selected_bg: Color,
+    ) {
+        let equipments = world.borrow::<View<Equipment>>();
+        let names = world.borrow::<View<Name>>();
+        let renderables = world.borrow::<View<Renderable>>();
+        let options = world.borrow::<UniqueView<Options>>();
+        let player_id = world.borrow::<UniqueView<PlayerId>>().0;
+        let player_equipment = equipments.get(player_id);
+        let active_loadout = world.borrow::<View<Player>>().get(player_id).active_loadout;
+        let locale = world.borrow::<UniqueView<Locale>>();
+        let weapon_label = locale.get("inventory.weapon");
+        let armor_label = locale.get("inventory.armor");
+        // Line the equipment up just past the wider of the two labels.
+        let equip_x = 3 + weapon_label
+            .chars()
+            .count()
+            .max(armor_label.chars().count()) as i32;
+        let weapon_bg = if matches!(self.subsection, SubSection::EquipWeapon) {
+            selected_bg
+        } else {
+            bg
+        };
+        let armor_bg = if matches!(self.subsection, SubSection::EquipArmor) {
+            selected_bg
+        } else {
+            bg
+        };
+
+        // Draw box with bottom edge off-grid.
+        grid.draw_box((0, 0), (grid.width(), grid.height() + 1), fg, bg);
+        grid.print_color(
+            (2, 0),
+            locale.get("inventory.equipment_title"),
+            true,
+            Color::YELLOW,
+            bg,
+        );
+
+        let set_label = locale.format("inventory.set", &[("set", &(active_loadout + 1))]);
+        grid.print_color(
+            (
+                grid.width() as i32 - 2 - set_label.chars().count() as i32,
+                0,
+            ),
+            &set_label,
+            true,
+            Color::GRAY,
+            bg,
+        );
+
+        grid.print((2, 2), weapon_label);
+        if let Some(weapon) = player_equipment.weapon {
+            let render = renderables.get(weapon);
+            render::put_renderable(grid, (equip_x, 2), render, &options);
+            grid.print_color((equip_x + 2, 2), &names.get(weapon).0, true, fg, weapon_bg);
+            Self::draw_wear(
+                world,
+                grid,
+                (equip_x + 2, 2),
+                weapon,
+                &names.get(weapon).0,
+                bg,
+            );
+        } else {
+            grid.print_color((equip_x, 2), locale.get(NOTHING), true, fg, weapon_bg);
+        }
+
+        grid.print((2, 3), armor_label);
+        if let Some(armor) = player_equipment.armor {
+            let render = renderables.get(armor);
+            render::put_renderable(grid, (equip_x, 3), render, &options);
+            grid.print_color((equip_x + 2, 3), &names.get(armor).0, true, fg, armor_bg);
+            Self::draw_wear(
+                world,
+                grid,
+                (equip_x + 2, 3),
+                armor,
+                &names.get(armor).0,
+                bg,
+            );
+        } else {
+            grid.print_color((equip_x, 3), locale.get(NOTHING), true, fg, armor_bg);
+        }
+    }
+
+    fn draw_inventory(
+        &self,
+        world: &World,
+        grid: &mut TileGrid<GameSym>,
+        fg: Color,
+        bg: Color,
+        selected_bg: Color,
+    ) {
+        grid.draw_box((0, 0), (grid.width(), grid.height()), fg, bg);
+        grid.put_char_color((0, 0), '├', fg, bg);
+        grid.put_char_color((grid.width() as i32 - 1, 0), '┤', fg, bg);
+        let locale = world.borrow::<UniqueView<Locale>>();
+
+        grid.print_color(
+            (2, 0),
+            locale.get("inventory.title"),
+            true,
+            Color::YELLOW,
+            bg,
+        );
+
+        let player_id = world.borrow::<UniqueView<PlayerId>>().0;
+        if let Some((weight, capacity)) = encumbrance::weight_and_capacity(world, player_id) {
+            let weight_text = locale.format(
+                "inventory.weight",
+                &[("weight", &weight), ("capacity", &capacity)],
+            );
+            let weight_fg = if weight > capacity {
+                Color::ORANGE
+            } else {
+                Color::YELLOW
+            };
+
+            grid.print_color(
+                (
+                    grid.width() as i32 - 2 - weight_text.chars().count() as i32,
+                    0,
+                ),
+                &weight_text,
+                true,
+                weight_fg,
+                bg,
+            );
+        }
+
+        if self.typing_filter {
+            grid.print_color(
+                (2, grid.height() as i32 - 1),
+                locale.get("inventory.filter_hint"),
+                true,
+                Color::YELLOW,
+                bg,
+            );
+        } else if matches!(self.subsection, SubSection::Inventory) {
+            if let Some((text, color)) = self
+                .selected_item()
+                .and_then(|id| ui::equip_comparison_text(world, id))
+            {
+                grid.print_color(
+                    (2, grid.height() as i32 - 1),
+                    &format!("< {} >", text),
+                    true,
+                    color,
+                    bg,
+                );
+            }
+        }
+
+        if matches!(self.subsection, SubSection::SortAll) {
+            grid.print_color(
+                (2, grid.height() as i32 - 1),
+                locale.get("inventory.sort_hint"),
+                true,
+                Color::YELLOW,
+                bg,
+            );
+        }
+
+        grid.print_color(
+            (2, 2),
+            &self.sort_all_label(&locale),
+            true,
+            fg,
+            if matches!(self.subsection, SubSection::SortAll) {
+                selected_bg
+            } else {
+                bg
+            },
+        );
+
+        if self.typing_filter || !self.filter.is_empty() {
+            let filter_label = locale.get(FILTER_LABEL);
+            let filter_x = 2 + filter_label.chars().count() as i32;
+
+            grid.print_color((2, 3), filter_label, true, Color::GRAY, bg);
+            grid.print_color((filter_x, 3), &self.filter, true, fg, bg);
+            if self.typing_filter {
+                grid.put_char_color(
+                    (filter_x + self.filter.chars().count() as i32, 3),
+                    '_',
+                    Color::YELLOW,
+                    bg,
+                );
+            }
+        }
+
+        world.run(
+            |options: UniqueView<Options>,
+             favorites: View<Favorite>,
+             lockeds: View<Locked>,
+             renderables: View<Renderable>| {
+                let item_pos = Position { x: 2, y: 4 };
+                let item_selected_bg = if matches!(self.subsection, SubSection::Inventory) {
+                    selected_bg
+                } else {
+                    bg
+                };
+
+                if self.menu.items().is_empty() {
+                    grid.print_color(
+                        item_pos,
+                        locale.get(if self.filter.is_empty() {
+                            NOTHING
+                        } else {
+                            "inventory.no_matches"
+                        }),
+                        true,
+                        fg,
+                        item_selected_bg,
+                    );
+                } else {
+                    let list_height = Self::inv_item_list_height(grid);
+
+                    self.menu.draw_list(
+                        grid,
+                        item_pos,
+                        list_height,
+                        fg,
+                        bg,
+                        item_selected_bg,
+                        |grid, pos, _, item, item_bg| {
+                            let item_id = item.value;
+                            // Favorites stand out in yellow.
+                            let item_fg = if favorites.contains(item_id) {
+                                Color::YELLOW
+                            } else {
+                                fg
+                            };
+                            let mut suffix_x = pos.x + 2 + item.label.chars().count() as i32;
+
+                            render::put_renderable(grid, pos, renderables.get(item_id), &options);
+                            grid.print_color(
+                                (pos.x + 2, pos.y),
+                                &item.label,
+                                true,
+                                item_fg,
+                                item_bg,
+                            );
+                            if lockeds.contains(item_id) {
+                                let locked_suffix = locale.get(LOCKED_SUFFIX);
+
+                                grid.print_color(
+                                    (suffix_x, pos.y),
+                                    locked_suffix,
+                                    true,
+                                    Color::GRAY,
+                                    bg,
+                                );
+                                suffix_x += locked_suffix.chars().count() as i32;
+                            }
+                            Self::draw_wear(world, grid, (suffix_x, pos.y), item_id, "", bg);
+                        },
+                    );
+                }
+            },
+        );
+    }
+}
+
+impl Mode<World, GameSym> for InventoryMode {
+    fn prepare_grids(
+        &mut self,
+        world: &World,
         grids: &mut Vec<TileGrid<GameSym>>,
         tilesets: &[Tileset<GameSym>],
         window_size: Size,
@@ -86,37 +534,37 @@ impl InventoryMode {
             font, text_zoom, ..
         } = *world.borrow::<UniqueView<Options>>();
         let tileset = &tilesets.get(font as usize).unwrap_or(&tilesets[0]);
-
-        // Equip grid on top.
-        let new_equip_size = Size {
-            w: 4 + self.main_width as u32,
-            h: 5,
+        let cell_size = Size {
+            w: tileset.tile_width() * text_zoom,
+            h: tileset.tile_height() * text_zoom,
         };
-        // Inventory grid occupies the majority center bottom-right.
+        let width = 4 + self.main_width as u32;
+
+        // Equip grid on top, clipped from the bottom if there's no room for all of it.
+        let equip_layout =
+            Layout::new(Extent::Cells(width), Extent::Cells(EQUIP_HEIGHT)).with_anchor(Anchor::Top);
+        // Inventory grid occupies the majority center bottom-right, leaving room for the equip grid
+        // above it where possible.
         let inv_len = world.run(
             |player_id: UniqueView<PlayerId>, inventories: View<Inventory>| {
                 inventories.get(player_id.0).items.len() as u32
             },
         );
-        let new_inv_size = Size {
-            w: new_equip_size.w,
-            h: (inv_len + 6)
-                .max(13)
-                .min(
-                    (window_size.h / (tileset.tile_height() * text_zoom))
-                        .saturating_sub(new_equip_size.h),
-                )
-                .max(7),
-        };
-
-        if !grids.is_empty() {
-            grids[EQUIP_GRID].resize(new_equip_size);
-            grids[INV_GRID].resize(new_inv_size);
-        } else {
-            grids.push(TileGrid::new(new_equip_size, tilesets, font as usize));
-            grids.push(TileGrid::new(new_inv_size, tilesets, font as usize));
+        let inv_size = Layout::new(Extent::Cells(width), Extent::Cells((inv_len + 6).max(13)))
+            .fitted_height(EQUIP_HEIGHT)
+            .with_min_size(Size { w: 0, h: 7 })
+            .grid_size(window_size, cell_size);
+        let inv_layout =
+            Layout::new(Extent::Cells(width), Extent::Cells(inv_size.h)).with_anchor(Anchor::Top);
+
+        if grids.is_empty() {
+            // Use bogus sizes; the layouts will resize the grids below.
+            grids.push(TileGrid::new(Size { w: 1, h: 1 }, tilesets, font as usize));
+            grids.push(TileGrid::new(Size { w: 1, h: 1 }, tilesets, font as usize));
             grids[EQUIP_GRID].view.clear_color = None;
             grids[INV_GRID].view.clear_color = None;
+            grids[EQUIP_GRID].view.bg_alpha = ui::OVERLAY_BG_ALPHA;
+            grids[INV_GRID].view.bg_alpha = ui::OVERLAY_BG_ALPHA;
         }
 
         let (equip_grid, grids) = grids.split_first_mut().unwrap(); // EQUIP_GRID
@@ -126,42 +574,20 @@ impl InventoryMode {
         equip_grid.set_tileset(tilesets, font as usize);
         inv_grid.set_tileset(tilesets, font as usize);
 
-        // Calculate equip grid x and width.
-        equip_grid.view.size.w = new_equip_size.w * tileset.tile_width() * text_zoom;
-        equip_grid.view.pos.x = (window_size.w - equip_grid.view.size.w) as i32 / 2;
-
-        // Calculate inventory grid x and width.
-        inv_grid.view.size.w = new_inv_size.w * tileset.tile_width() * text_zoom;
-        inv_grid.view.pos.x = equip_grid.view.pos.x;
-
-        // Calculate equip grid y and height.
-        let combined_px_height =
-            (new_inv_size.h + new_equip_size.h) * tileset.tile_height() * text_zoom;
-        if combined_px_height <= window_size.h {
-            equip_grid.view.pos.y = (window_size.h - combined_px_height) as i32 / 2;
-            equip_grid.view.size.h = new_equip_size.h * tileset.tile_height() * text_zoom;
-            equip_grid.view.visible = true;
-        } else if new_inv_size.h * tileset.tile_height() * text_zoom < window_size.h {
-            equip_grid.view.pos.y = 0;
-            equip_grid.view.size.h =
-                window_size.h - new_inv_size.h * tileset.tile_height() * text_zoom;
-            equip_grid.view.visible = true;
-        } else {
-            equip_grid.view.pos.y = 0;
-            equip_grid.view.size.h = 0;
-            equip_grid.view.visible = false;
-        }
-
-        // Calculate inventory grid y and height.
-        inv_grid.view.pos.y = equip_grid.view.pos.y + equip_grid.view.size.h as i32;
-        inv_grid.view.size.h = new_inv_size.h * tileset.tile_height() * text_zoom;
-
-        // Set all grids to current text zoom.
-        equip_grid.view.zoom = text_zoom;
-        inv_grid.view.zoom = text_zoom;
+        // Center both grids together, then split the space between them.
+        let combined_area = Layout::new(
+            Extent::Cells(width),
+            Extent::Cells(EQUIP_HEIGHT + inv_size.h),
+        )
+        .area_in(Area::window(window_size), cell_size);
+        let (equip_area, inv_area) = combined_area.split_bottom(inv_size.h * cell_size.h);
+
+        equip_layout.apply(equip_grid, tilesets, text_zoom, equip_area);
+        equip_grid.view.visible = equip_area.size.h > 0;
+        inv_layout.apply(inv_grid, tilesets, text_zoom, inv_area);
     }
 
-    pub fn update(
+    fn update(
         &mut self,
         world: &World,
         inputs: &mut InputBuffer,
@@ -169,55 +595,85 @@ impl InventoryMode {
         pop_result: &Option<ModeResult>,
     ) -> (ModeControl, ModeUpdate) {
         if let Some(result) = pop_result {
-            return match result {
-                ModeResult::EquipmentActionModeResult(result) => match result {
+            return if let Some(result) = result.downcast_ref::<EquipmentActionModeResult>() {
+                match result {
                     EquipmentActionModeResult::AppQuit => (
-                        ModeControl::Pop(InventoryModeResult::AppQuit.into()),
+                        ModeControl::Pop(Box::new(InventoryModeResult::AppQuit)),
                         ModeUpdate::Immediate,
                     ),
                     EquipmentActionModeResult::Cancelled => {
                         (ModeControl::Stay, ModeUpdate::WaitForEvent)
                     }
                     EquipmentActionModeResult::RemoveEquipment(item_id) => (
-                        ModeControl::Pop(InventoryModeResult::RemoveEquipment(*item_id).into()),
+                        ModeControl::Pop(Box::new(InventoryModeResult::RemoveEquipment(*item_id))),
                         ModeUpdate::Immediate,
                     ),
                     EquipmentActionModeResult::DropEquipment(item_id) => (
-                        ModeControl::Pop(InventoryModeResult::DropEquipment(*item_id).into()),
+                        ModeControl::Pop(Box::new(InventoryModeResult::DropEquipment(*item_id))),
                         ModeUpdate::Immediate,
                     ),
-                },
-
-                ModeResult::InventoryActionModeResult(result) => match result {
+                }
+            } else if let Some(result) = result.downcast_ref::<InventoryActionModeResult>() {
+                match result {
                     InventoryActionModeResult::AppQuit => (
-                        ModeControl::Pop(InventoryModeResult::AppQuit.into()),
+                        ModeControl::Pop(Box::new(InventoryModeResult::AppQuit)),
                         ModeUpdate::Immediate,
                     ),
                     InventoryActionModeResult::Cancelled => {
                         (ModeControl::Stay, ModeUpdate::WaitForEvent)
                     }
                     InventoryActionModeResult::EquipItem(item_id) => (
-                        ModeControl::Pop(InventoryModeResult::EquipItem(*item_id).into()),
+                        ModeControl::Pop(Box::new(InventoryModeResult::EquipItem(*item_id))),
                         ModeUpdate::Immediate,
                     ),
                     InventoryActionModeResult::UseItem(item_id, target) => (
-                        ModeControl::Pop(InventoryModeResult::UseItem(*item_id, *target).into()),
+                        ModeControl::Pop(Box::new(InventoryModeResult::UseItem(*item_id, *target))),
                         ModeUpdate::Immediate,
                     ),
                     InventoryActionModeResult::DropItem(item_id) => (
-                        ModeControl::Pop(InventoryModeResult::DropItem(*item_id).into()),
+                        ModeControl::Pop(Box::new(InventoryModeResult::DropItem(*item_id))),
+                        ModeUpdate::Immediate,
+                    ),
+                    InventoryActionModeResult::OpenContainer(item_id) => (
+                        ModeControl::Push(Box::new(ContainerMode::new(*item_id))),
                         ModeUpdate::Immediate,
                     ),
-                },
+                    InventoryActionModeResult::ToggleFavorite(item_id) => {
+                        let player_id = world.borrow::<UniqueView<PlayerId>>().0;
 
-                ModeResult::YesNoDialogModeResult(result) => match result {
+                        item::toggle_favorite(world, player_id, *item_id);
+                        self.select_item(world, *item_id);
+                        self.main_width = Self::calc_main_width(world);
+                        (ModeControl::Stay, ModeUpdate::WaitForEvent)
+                    }
+                    InventoryActionModeResult::ToggleLocked(item_id) => {
+                        item::toggle_locked(world, *item_id);
+                        self.main_width = Self::calc_main_width(world);
+                        (ModeControl::Stay, ModeUpdate::WaitForEvent)
+                    }
+                }
+            } else if let Some(result) = result.downcast_ref::<ContainerModeResult>() {
+                match result {
+                    ContainerModeResult::AppQuit => (
+                        ModeControl::Pop(Box::new(InventoryModeResult::AppQuit)),
+                        ModeUpdate::Immediate,
+                    ),
+                    ContainerModeResult::Done => {
+                        // Items may have moved into or out of the inventory, so start afresh.
+                        *self = InventoryMode::new(world);
+                        (ModeControl::Stay, ModeUpdate::WaitForEvent)
+                    }
+                }
+            } else if let Some(result) = result.downcast_ref::<YesNoDialogModeResult>() {
+                match result {
                     YesNoDialogModeResult::AppQuit => (
-                        ModeControl::Pop(InventoryModeResult::AppQuit.into()),
+                        ModeControl::Pop(Box::new(InventoryModeResult::AppQuit)),
                         ModeUpdate::Immediate,
                     ),
                     YesNoDialogModeResult::Yes => {
                         let player_id = world.borrow::<UniqueView<PlayerId>>();
-                        item::sort_inventory(world, player_id.0);
+                        item::sort_inventory(world, player_id.0, SortOrder::ALL[self.sort_order]);
+                        self.refilter(world, self.selected_item());
 
                         // Reset menu memory for inventory-related shortcut menus.
                         let mut menu_memory = world.borrow::<UniqueViewMut<MenuMemory>>();
@@ -228,9 +684,9 @@ impl InventoryMode {
                         (ModeControl::Stay, ModeUpdate::WaitForEvent)
                     }
                     YesNoDialogModeResult::No => (ModeControl::Stay, ModeUpdate::WaitForEvent),
-                },
-
-                _ => unreachable!(),
+                }
+            } else {
+                unreachable!()
             };
         }
 
@@ -238,25 +694,22 @@ impl InventoryMode {
 
         if let Some(InputEvent::AppQuit) = inputs.get_input() {
             (
-                ModeControl::Pop(InventoryModeResult::AppQuit.into()),
+                ModeControl::Pop(Box::new(InventoryModeResult::AppQuit)),
                 ModeUpdate::Immediate,
             )
+        } else if self.typing_filter {
+            self.update_filter_typing(world, inputs);
+            (ModeControl::Stay, ModeUpdate::WaitForEvent)
         } else if let Some(InputEvent::Press(keycode)) = inputs.get_input() {
             let player_id = world.borrow::<UniqueView<PlayerId>>();
             let equipments = world.borrow::<View<Equipment>>();
-            let inventories = world.borrow::<View<Inventory>>();
             let player_equipment = equipments.get(player_id.0);
-            let player_inv = inventories.get(player_id.0);
             let shift = inputs.get_mods(KeyMods::SHIFT);
 
             match (&self.subsection, gamekey::from_keycode(keycode, shift)) {
                 (SubSection::EquipWeapon, GameKey::Up) => {
                     self.subsection = SubSection::Inventory;
-                    self.inv_selection = if player_inv.items.is_empty() {
-                        0
-                    } else {
-                        player_inv.items.len() as i32 - 1
-                    }
+                    self.menu.navigate(MenuNav::End, 1);
                 }
                 (SubSection::EquipWeapon, GameKey::Down) => {
                     self.subsection = SubSection::EquipArmor;
@@ -265,7 +718,9 @@ impl InventoryMode {
                     if let Some(weapon) = player_equipment.weapon {
                         inputs.clear_input();
                         return (
-                            ModeControl::Push(EquipmentActionMode::new(world, weapon, None).into()),
+                            ModeControl::Push(Box::new(EquipmentActionMode::new(
+                                world, weapon, None,
+                            ))),
                             ModeUpdate::Immediate,
                         );
                     }
@@ -277,10 +732,11 @@ impl InventoryMode {
                         if let Some(equip_action) = EquipmentAction::from_key(key) {
                             inputs.clear_input();
                             return (
-                                ModeControl::Push(
-                                    EquipmentActionMode::new(world, weapon, Some(equip_action))
-                                        .into(),
-                                ),
+                                ModeControl::Push(Box::new(EquipmentActionMode::new(
+                                    world,
+                                    weapon,
+                                    Some(equip_action),
+                                ))),
                                 ModeUpdate::Immediate,
                             );
                         }
@@ -297,7 +753,9 @@ impl InventoryMode {
                     if let Some(armor) = player_equipment.armor {
                         inputs.clear_input();
                         return (
-                            ModeControl::Push(EquipmentActionMode::new(world, armor, None).into()),
+                            ModeControl::Push(Box::new(EquipmentActionMode::new(
+                                world, armor, None,
+                            ))),
                             ModeUpdate::Immediate,
                         );
                     }
@@ -309,10 +767,11 @@ impl InventoryMode {
                         if let Some(equip_action) = EquipmentAction::from_key(key) {
                             inputs.clear_input();
                             return (
-                                ModeControl::Push(
-                                    EquipmentActionMode::new(world, armor, Some(equip_action))
-                                        .into(),
-                                ),
+                                ModeControl::Push(Box::new(EquipmentActionMode::new(
+                                    world,
+                                    armor,
+                                    Some(equip_action),
+                                ))),
                                 ModeUpdate::Immediate,
                             );
                         }
@@ -324,71 +783,66 @@ impl InventoryMode {
                 }
                 (SubSection::SortAll, GameKey::Down) => {
                     self.subsection = SubSection::Inventory;
-                    self.inv_selection = 0;
+                    self.menu.navigate(MenuNav::Home, 1);
+                }
+                (SubSection::SortAll, key @ GameKey::Left)
+                | (SubSection::SortAll, key @ GameKey::Right) => {
+                    let num_orders = SortOrder::ALL.len();
+                    let step = if matches!(key, GameKey::Left) {
+                        num_orders - 1
+                    } else {
+                        1
+                    };
+
+                    self.sort_order = (self.sort_order + step) % num_orders;
+                    world.borrow::<UniqueViewMut<MenuMemory>>()[MenuMemory::INVENTORY_SORT] =
+                        self.sort_order as i32;
                 }
                 (SubSection::SortAll, GameKey::Confirm) => {
                     inputs.clear_input();
                     return (
-                        ModeControl::Push(
-                            YesNoDialogMode::new("Sort all inventory items?".to_string(), true)
-                                .into(),
-                        ),
+                        ModeControl::Push(Box::new(YesNoDialogMode::new(
+                            {
+                                let locale = world.borrow::<UniqueView<Locale>>();
+
+                                locale.format(
+                                    "inventory.confirm_sort",
+                                    &[(
+                                        "order",
+                                        &locale.get(SortOrder::ALL[self.sort_order].name_key()),
+                                    )],
+                                )
+                            },
+                            true,
+                        ))),
                         ModeUpdate::Immediate,
                     );
                 }
 
-                (SubSection::Inventory, GameKey::Up) => {
-                    if self.inv_selection > 0 {
-                        self.inv_selection -= 1;
-                    } else {
-                        self.subsection = SubSection::SortAll;
-                    }
-                }
-                (SubSection::Inventory, GameKey::Down) => {
-                    if !player_inv.items.is_empty()
-                        && self.inv_selection < player_inv.items.len() as i32 - 1
-                    {
-                        self.inv_selection += 1;
-                    } else {
-                        self.subsection = SubSection::EquipWeapon;
-                    }
+                // Moving off either end of the items leaves the menu rather than wrapping around.
+                (SubSection::Inventory, GameKey::Up) if self.menu.selection() == 0 => {
+                    self.subsection = SubSection::SortAll;
                 }
-                (SubSection::Inventory, GameKey::PageUp) => {
-                    if let Some(inv_grid) = grids.get(INV_GRID) {
-                        self.inv_selection = self
-                            .inv_selection
-                            .saturating_sub(Self::inv_item_list_height(inv_grid))
-                            .max(0);
-                    }
+                (SubSection::Inventory, GameKey::Down)
+                    if self.menu.selection() + 1 >= self.menu.num_entries() =>
+                {
+                    self.subsection = SubSection::EquipWeapon;
                 }
-                (SubSection::Inventory, GameKey::PageDown) => {
-                    if let Some(inv_grid) = grids.get(INV_GRID) {
-                        let max_selection = (player_inv.items.len() as i32 - 1).max(0);
-
-                        self.inv_selection = self
-                            .inv_selection
-                            .saturating_add(Self::inv_item_list_height(inv_grid))
-                            .min(max_selection);
+                (SubSection::Inventory, key) if gamekey::menu_nav(&key).is_some() => {
+                    if let (Some(nav), Some(inv_grid)) =
+                        (gamekey::menu_nav(&key), grids.get(INV_GRID))
+                    {
+                        self.menu
+                            .navigate(nav, Self::inv_item_list_height(inv_grid));
                     }
                 }
-                (SubSection::Inventory, GameKey::Home) => {
-                    self.inv_selection = 0;
-                }
-                (SubSection::Inventory, GameKey::End) => {
-                    self.inv_selection = (player_inv.items.len() as i32 - 1).max(0);
-                }
                 (SubSection::Inventory, GameKey::Confirm) => {
-                    if !player_inv.items.is_empty() {
+                    if let Some(item_id) = self.selected_item() {
                         inputs.clear_input();
                         return (
-                            ModeControl::Push(
-                                InventoryActionMode::new(
-                                    world,
-                                    player_inv.items[self.inv_selection as usize],
-                                    None,
-                                )
-                                .into(),
-                            ),
+                            ModeControl::Push(Box::new(InventoryActionMode::new(
+                                world, item_id, None,
+                            ))),
                             ModeUpdate::Immediate,
                         );
                     }
@@ -399,15 +853,16 @@ impl InventoryMode {
                         GameKey::EquipItem | GameKey::UseItem | GameKey::DropItem
                     ) =>
                 {
-                    if let Some(item_id) = player_inv.items.get(self.inv_selection as usize) {
+                    if let Some(item_id) = self.selected_item() {
                         if let Some(inv_action) = InventoryAction::from_key(key) {
-                            if InventoryAction::item_supports_action(world, *item_id, inv_action) {
+                            if InventoryAction::item_supports_action(world, item_id, inv_action) {
                                 inputs.clear_input();
                                 return (
-                                    ModeControl::Push(
-                                        InventoryActionMode::new(world, *item_id, Some(inv_action))
-                                            .into(),
-                                    ),
+                                    ModeControl::Push(Box::new(InventoryActionMode::new(
+                                        world,
+                                        item_id,
+                                        Some(inv_action),
+                                    ))),
                                     ModeUpdate::Immediate,
                                 );
                             }
@@ -415,16 +870,29 @@ impl InventoryMode {
                     }
                 }
 
+                // Text is only accepted from the next update on, so the text of this key press is
+                // dropped instead of starting the filter.
+                (_, GameKey::Filter) => {
+                    self.subsection = SubSection::Inventory;
+                    self.typing_filter = true;
+                }
+                // Clear the filter before leaving the inventory.
+                (_, GameKey::Cancel) if !self.filter.is_empty() => {
+                    let selected = self.selected_item();
+
+                    self.filter.clear();
+                    self.refilter(world, selected);
+                }
                 (_, GameKey::Cancel) | (_, GameKey::Inventory) => {
                     return (
-                        ModeControl::Pop(InventoryModeResult::DoNothing.into()),
+                        ModeControl::Pop(Box::new(InventoryModeResult::DoNothing)),
                         ModeUpdate::Immediate,
                     )
                 }
                 _ => {}
             }
 
-            world.borrow::<UniqueViewMut<MenuMemory>>()[MenuMemory::INVENTORY] = self.inv_selection;
+            self.remember_selection(world);
 
             (ModeControl::Stay, ModeUpdate::WaitForEvent)
         } else {
@@ -432,157 +900,7 @@ impl InventoryMode {
         }
     }
 
-    fn draw_equip(
-        &self,
-        world: &World,
-        grid: &mut TileGrid<GameSym>,
-        fg: Color,
-        bg: Color,
-        selected_bg: Color,
-    ) {
-        let equipments = world.borrow::<View<Equipment>>();
-        let names = world.borrow::<View<Name>>();
-        let renderables = world.borrow::<View<Renderable>>();
-        let player_equipment = equipments.get(world.borrow::<UniqueView<PlayerId>>().0);
-        let weapon_bg = if matches!(self.subsection, SubSection::EquipWeapon) {
-            selected_bg
-        } else {
-            bg
-        };
-        let armor_bg = if matches!(self.subsection, SubSection::EquipArmor) {
-            selected_bg
-        } else {
-            bg
-        };
-
-        // Draw box with bottom edge off-grid.
-        grid.draw_box((0, 0), (grid.width(), grid.height() + 1), fg, bg);
-        grid.print_color((2, 0), "< Equipment >", true, Color::YELLOW, bg);
-
-        grid.print((2, 2), "Weapon:");
-        if let Some(weapon) = player_equipment.weapon {
-            let render = renderables.get(weapon);
-            grid.put_sym_color((10, 2), render.sym, render.fg, render.bg);
-            grid.print_color((12, 2), &names.get(weapon).0, true, fg, weapon_bg);
-        } else {
-            grid.print_color((10, 2), "-- nothing --", true, fg, weapon_bg);
-        }
-
-        grid.print((2, 3), "Armor:");
-        if let Some(armor) = player_equipment.armor {
-            let render = renderables.get(armor);
-            grid.put_sym_color((10, 3), render.sym, render.fg, render.bg);
-            grid.print_color((12, 3), &names.get(armor).0, true, fg, armor_bg);
-        } else {
-            grid.print_color((10, 3), "-- nothing --", true, fg, armor_bg);
-        }
-    }
-
-    fn draw_inventory(
-        &self,
-        world: &World,
-        grid: &mut TileGrid<GameSym>,
-        fg: Color,
-        bg: Color,
-        selected_bg: Color,
-    ) {
-        grid.draw_box((0, 0), (grid.width(), grid.height()), fg, bg);
-        grid.put_char_color((0, 0), '├', fg, bg);
-        grid.put_char_color((grid.width() as i32 - 1, 0), '┤', fg, bg);
-        grid.print_color((2, 0), "< Inventory >", true, Color::YELLOW, bg);
-
-        grid.print_color(
-            (2, 2),
-            "[ Sort all items ]",
-            true,
-            fg,
-            if matches!(self.subsection, SubSection::SortAll) {
-                selected_bg
-            } else {
-                bg
-            },
-        );
-
-        world.run(
-            |player_id: UniqueView<PlayerId>,
-             inventories: View<Inventory>,
-             names: View<Name>,
-             renderables: View<Renderable>| {
-                let player_inv = inventories.get(player_id.0);
-                let item_x = 2;
-                let item_y = 4;
-
-                if player_inv.items.is_empty() {
-                    grid.print_color(
-                        (item_x, item_y),
-                        "-- nothing --",
-                        true,
-                        fg,
-                        if matches!(self.subsection, SubSection::Inventory) {
-                            selected_bg
-                        } else {
-                            bg
-                        },
-                    );
-                } else {
-                    let item_height = Self::inv_item_list_height(grid);
-                    let item_offset = std::cmp::max(
-                        0,
-                        std::cmp::min(
-                            player_inv.items.len() as i32 - item_height,
-                            self.inv_selection - (item_height - 1) / 2,
-                        ),
-                    );
-
-                    if player_inv.items.len() as i32 > item_height {
-                        grid.draw_bar(
-                            true,
-                            (grid.width() as i32 - 1, item_y),
-                            item_height,
-                            item_offset,
-                            item_height,
-                            player_inv.items.len() as i32,
-                            fg,
-                            bg,
-                        );
-                    }
-
-                    for (i, item_id) in player_inv
-                        .items
-                        .iter()
-                        .enumerate()
-                        .skip(item_offset as usize)
-                        .take(item_height as usize)
-                    {
-                        let render = renderables.get(*item_id);
-
-                        grid.put_sym_color(
-                            (item_x, item_y + i as i32 - item_offset),
-                            render.sym,
-                            render.fg,
-                            render.bg,
-                        );
-
-                        grid.print_color(
-                            (item_x + 2, item_y + i as i32 - item_offset),
-                            &names.get(*item_id).0,
-                            true,
-                            fg,
-                            if matches!(self.subsection, SubSection::Inventory)
-                                && i as i32 == self.inv_selection
-                            {
-                                selected_bg
-                            } else {
-                                bg
-                            },
-                        );
-                    }
-                }
-            },
-        );
-    }
-
-    pub fn draw(&self, world: &World, grids: &mut [TileGrid<GameSym>], active: bool) {
+    fn draw(&mut self, world: &World, grids: &mut [TileGrid<GameSym>], active: bool) {
         let (equip_grid, grids) = grids.split_first_mut().unwrap(); // EQUIP_GRID
         let (inv_grid, _) = grids.split_first_mut().unwrap(); // INV_GRID
         let fg = Color::WHITE;
@@ -602,4 +920,8 @@ impl InventoryMode {
         }
         self.draw_inventory(world, inv_grid, fg, bg, selected_bg);
     }
+
+    fn wants_text(&self) -> bool {
+        self.typing_filter
+    }
 }
@@ -1,9 +1,14 @@
 use shipyard::{EntityId, Get, UniqueView, View, World};
 
 use crate::{
-    components::{AreaOfEffect, Consumable, EquipSlot, Name, Ranged, Renderable, Victory},
+    components::{
+        AreaOfEffect, Consumable, Container, EquipSlot, Favorite, Locked, Name, Ranged, Renderable,
+        Victory,
+    },
     gamekey::{self, GameKey},
     gamesym::GameSym,
+    locale::Locale,
+    render,
     ui::{self, Options},
 };
 use ruggrogue::{
@@ -12,11 +17,13 @@ use ruggrogue::{
 };
 
 use super::{
+    item_detail::{ItemDetailMode, ItemDetailModeResult},
     target::{TargetMode, TargetModeResult},
-    ModeControl, ModeResult, ModeUpdate,
+    Mode, ModeControl, ModeResult, ModeUpdate,
 };
 
-const CANCEL: &str = "[ Cancel ]";
+const DESCRIBE: &str = "inventory_action.describe";
+const CANCEL: &str = "menu.cancel";
 
 pub enum InventoryActionModeResult {
     AppQuit,
@@ -24,10 +31,14 @@ pub enum InventoryActionModeResult {
     EquipItem(EntityId),
     UseItem(EntityId, Option<(i32, i32)>),
     DropItem(EntityId),
+    OpenContainer(EntityId),
+    ToggleFavorite(EntityId),
+    ToggleLocked(EntityId),
 }
 
 enum SubSection {
     Actions,
+    Describe,
     Cancel,
 }
 
@@ -37,6 +48,11 @@ pub enum InventoryAction {
     EquipItem,
     UseItem,
     DropItem,
+    OpenContainer,
+    Favorite,
+    Unfavorite,
+    Lock,
+    Unlock,
 }
 
 impl InventoryAction {
@@ -56,23 +72,43 @@ impl InventoryAction {
                 world.borrow::<View<Consumable>>().contains(item_id)
                     | world.borrow::<View<Victory>>().contains(item_id)
             }
-            InventoryAction::DropItem => true,
+            InventoryAction::DropItem => !world.borrow::<View<Locked>>().contains(item_id),
+            InventoryAction::OpenContainer => world.borrow::<View<Container>>().contains(item_id),
+            InventoryAction::Favorite => !world.borrow::<View<Favorite>>().contains(item_id),
+            InventoryAction::Unfavorite => world.borrow::<View<Favorite>>().contains(item_id),
+            InventoryAction::Lock => {
+                world.borrow::<View<Favorite>>().contains(item_id)
+                    && !world.borrow::<View<Locked>>().contains(item_id)
+            }
+            InventoryAction::Unlock => world.borrow::<View<Locked>>().contains(item_id),
         }
     }
 
-    pub fn name(&self) -> &'static str {
+    /// Key of the name of the action in the locale catalogs.
+    pub fn name_key(&self) -> &'static str {
         match self {
-            InventoryAction::EquipItem => "Equip",
-            InventoryAction::UseItem => "Apply",
-            InventoryAction::DropItem => "Drop",
+            InventoryAction::EquipItem => "inventory_action.equip",
+            InventoryAction::UseItem => "inventory_action.apply",
+            InventoryAction::DropItem => "inventory_action.drop",
+            InventoryAction::OpenContainer => "inventory_action.open",
+            InventoryAction::Favorite => "inventory_action.favorite",
+            InventoryAction::Unfavorite => "inventory_action.unfavorite",
+            InventoryAction::Lock => "inventory_action.lock",
+            InventoryAction::Unlock => "inventory_action.unlock",
         }
     }
 
-    fn label(&self) -> &'static str {
+    /// Key of the button label of the action in the locale catalogs.
+    fn label_key(&self) -> &'static str {
         match self {
-            InventoryAction::EquipItem => "[ Equip ]",
-            InventoryAction::UseItem => "[ Apply ]",
-            InventoryAction::DropItem => "[ Drop ]",
+            InventoryAction::EquipItem => "inventory_action.equip_button",
+            InventoryAction::UseItem => "inventory_action.apply_button",
+            InventoryAction::DropItem => "inventory_action.drop_button",
+            InventoryAction::OpenContainer => "inventory_action.open_button",
+            InventoryAction::Favorite => "inventory_action.favorite_button",
+            InventoryAction::Unfavorite => "inventory_action.unfavorite_button",
+            InventoryAction::Lock => "inventory_action.lock_button",
+            InventoryAction::Unlock => "inventory_action.unlock_button",
         }
     }
 }
@@ -89,9 +125,14 @@ pub struct InventoryActionMode {
 impl InventoryActionMode {
     pub fn new(world: &World, item_id: EntityId, default_action: Option<InventoryAction>) -> Self {
         let actions = [
+            InventoryAction::OpenContainer,
             InventoryAction::EquipItem,
             InventoryAction::UseItem,
             InventoryAction::DropItem,
+            InventoryAction::Favorite,
+            InventoryAction::Unfavorite,
+            InventoryAction::Lock,
+            InventoryAction::Unlock,
         ]
         .iter()
         .filter(|action| InventoryAction::item_supports_action(world, item_id, **action))
@@ -105,10 +146,14 @@ impl InventoryActionMode {
         let selection = default_action
             .and_then(|d_act| actions.iter().position(|a| *a == d_act))
             .unwrap_or(0);
-        let item_width = world.borrow::<View<Name>>().get(item_id).0.len();
-        let inner_width = 2 + item_width
-            .max(CANCEL.len())
-            .max(actions.iter().map(|a| a.label().len()).max().unwrap_or(0));
+        let locale = world.borrow::<UniqueView<Locale>>();
+        let item_width = world.borrow::<View<Name>>().get(item_id).0.chars().count();
+        let inner_width = 2 + actions
+            .iter()
+            .map(|a| a.label_key())
+            .chain([DESCRIBE, CANCEL])
+            .map(|key| locale.get(key).chars().count())
+            .fold(item_width, usize::max);
 
         Self {
             item_id,
@@ -119,33 +164,6 @@ impl InventoryActionMode {
         }
     }
 
-    pub fn prepare_grids(
-        &self,
-        world: &World,
-        grids: &mut Vec<TileGrid<GameSym>>,
-        tilesets: &[Tileset<GameSym>],
-        window_size: Size,
-    ) {
-        let Options {
-            font, text_zoom, ..
-        } = *world.borrow::<UniqueView<Options>>();
-        let new_grid_size = Size {
-            w: 4 + self.inner_width as u32,
-            h: 8 + self.actions.len() as u32,
-        };
-
-        if !grids.is_empty() {
-            grids[0].resize(new_grid_size);
-        } else {
-            grids.push(TileGrid::new(new_grid_size, tilesets, font as usize));
-            grids[0].view.clear_color = None;
-        }
-
-        grids[0].set_tileset(tilesets, font as usize);
-        grids[0].view_centered(tilesets, text_zoom, (0, 0).into(), window_size);
-        grids[0].view.zoom = text_zoom;
-    }
-
     fn confirm_action(&self, world: &World, inputs: &mut InputBuffer) -> (ModeControl, ModeUpdate) {
         let result = match self.subsection {
             SubSection::Actions => match self.actions[self.selection as usize] {
@@ -162,9 +180,9 @@ impl InventoryActionMode {
 
                         inputs.clear_input();
                         return (
-                            ModeControl::Push(
-                                TargetMode::new(world, item_name, *range, radius, true).into(),
-                            ),
+                            ModeControl::Push(Box::new(TargetMode::new(
+                                world, item_name, *range, radius, true,
+                            ))),
                             ModeUpdate::Immediate,
                         );
                     } else {
@@ -172,14 +190,59 @@ impl InventoryActionMode {
                     }
                 }
                 InventoryAction::DropItem => InventoryActionModeResult::DropItem(self.item_id),
+                InventoryAction::OpenContainer => {
+                    InventoryActionModeResult::OpenContainer(self.item_id)
+                }
+                InventoryAction::Favorite | InventoryAction::Unfavorite => {
+                    InventoryActionModeResult::ToggleFavorite(self.item_id)
+                }
+                InventoryAction::Lock | InventoryAction::Unlock => {
+                    InventoryActionModeResult::ToggleLocked(self.item_id)
+                }
             },
+            SubSection::Describe => {
+                inputs.clear_input();
+                return (
+                    ModeControl::Push(Box::new(ItemDetailMode::new(world, self.item_id))),
+                    ModeUpdate::Immediate,
+                );
+            }
             SubSection::Cancel => InventoryActionModeResult::Cancelled,
         };
 
-        (ModeControl::Pop(result.into()), ModeUpdate::Immediate)
+        (ModeControl::Pop(Box::new(result)), ModeUpdate::Immediate)
     }
+}
 
-    pub fn update(
+impl Mode<World, GameSym> for InventoryActionMode {
+    fn prepare_grids(
+        &mut self,
+        world: &World,
+        grids: &mut Vec<TileGrid<GameSym>>,
+        tilesets: &[Tileset<GameSym>],
+        window_size: Size,
+    ) {
+        let Options {
+            font, text_zoom, ..
+        } = *world.borrow::<UniqueView<Options>>();
+        let new_grid_size = Size {
+            w: 4 + self.inner_width as u32,
+            h: 9 + self.actions.len() as u32,
+        };
+
+        if !grids.is_empty() {
+            grids[0].resize(new_grid_size);
+        } else {
+            grids.push(TileGrid::new(new_grid_size, tilesets, font as usize));
+            grids[0].view.clear_color = None;
+        }
+
+        grids[0].set_tileset(tilesets, font as usize);
+        grids[0].view_centered(tilesets, text_zoom, (0, 0).into(), window_size);
+        grids[0].view.zoom = text_zoom;
+    }
+
+    fn update(
         &mut self,
         world: &World,
         inputs: &mut InputBuffer,
@@ -187,21 +250,31 @@ impl InventoryActionMode {
         pop_result: &Option<ModeResult>,
     ) -> (ModeControl, ModeUpdate) {
         if let Some(result) = pop_result {
-            return match result {
-                ModeResult::TargetModeResult(result) => match result {
+            return if let Some(result) = result.downcast_ref::<TargetModeResult>() {
+                match result {
                     TargetModeResult::AppQuit => (
-                        ModeControl::Pop(InventoryActionModeResult::AppQuit.into()),
+                        ModeControl::Pop(Box::new(InventoryActionModeResult::AppQuit)),
                         ModeUpdate::Immediate,
                     ),
                     TargetModeResult::Cancelled => (ModeControl::Stay, ModeUpdate::WaitForEvent),
                     TargetModeResult::Target { x, y } => (
-                        ModeControl::Pop(
-                            InventoryActionModeResult::UseItem(self.item_id, Some((*x, *y))).into(),
-                        ),
+                        ModeControl::Pop(Box::new(InventoryActionModeResult::UseItem(
+                            self.item_id,
+                            Some((*x, *y)),
+                        ))),
                         ModeUpdate::Immediate,
                     ),
-                },
-                _ => (ModeControl::Stay, ModeUpdate::WaitForEvent),
+                }
+            } else if let Some(result) = result.downcast_ref::<ItemDetailModeResult>() {
+                match result {
+                    ItemDetailModeResult::AppQuit => (
+                        ModeControl::Pop(Box::new(InventoryActionModeResult::AppQuit)),
+                        ModeUpdate::Immediate,
+                    ),
+                    ItemDetailModeResult::Done => (ModeControl::Stay, ModeUpdate::WaitForEvent),
+                }
+            } else {
+                (ModeControl::Stay, ModeUpdate::WaitForEvent)
             };
         }
 
@@ -209,7 +282,7 @@ impl InventoryActionMode {
 
         if let Some(InputEvent::AppQuit) = inputs.get_input() {
             return (
-                ModeControl::Pop(InventoryActionModeResult::AppQuit.into()),
+                ModeControl::Pop(Box::new(InventoryActionModeResult::AppQuit)),
                 ModeUpdate::Immediate,
             );
         } else if let Some(InputEvent::Press(keycode)) = inputs.get_input() {
@@ -219,11 +292,14 @@ impl InventoryActionMode {
                         if self.selection < self.actions.len() as i32 - 1 {
                             self.selection += 1;
                         } else {
-                            self.subsection = SubSection::Cancel;
+                            self.subsection = SubSection::Describe;
                         }
                     }
+                    SubSection::Describe => self.subsection = SubSection::Cancel,
                     SubSection::Cancel => {
-                        if !self.actions.is_empty() {
+                        if self.actions.is_empty() {
+                            self.subsection = SubSection::Describe;
+                        } else {
                             self.subsection = SubSection::Actions;
                             self.selection = 0;
                         }
@@ -237,16 +313,19 @@ impl InventoryActionMode {
                             self.subsection = SubSection::Cancel;
                         }
                     }
-                    SubSection::Cancel => {
-                        if !self.actions.is_empty() {
+                    SubSection::Describe => {
+                        if self.actions.is_empty() {
+                            self.subsection = SubSection::Cancel;
+                        } else {
                             self.subsection = SubSection::Actions;
                             self.selection = self.actions.len() as i32 - 1;
                         }
                     }
+                    SubSection::Cancel => self.subsection = SubSection::Describe,
                 },
                 GameKey::Cancel => {
                     return (
-                        ModeControl::Pop(InventoryActionModeResult::Cancelled.into()),
+                        ModeControl::Pop(Box::new(InventoryActionModeResult::Cancelled)),
                         ModeUpdate::Immediate,
                     )
                 }
@@ -273,7 +352,7 @@ impl InventoryActionMode {
         (ModeControl::Stay, ModeUpdate::WaitForEvent)
     }
 
-    pub fn draw(&self, world: &World, grids: &mut [TileGrid<GameSym>], active: bool) {
+    fn draw(&mut self, world: &World, grids: &mut [TileGrid<GameSym>], active: bool) {
         let grid = &mut grids[0];
         let fg = Color::WHITE;
         let bg = Color::BLACK;
@@ -283,17 +362,21 @@ impl InventoryActionMode {
 
         grid.draw_box((0, 0), (grid.width(), grid.height()), fg, bg);
 
-        world.run(|names: View<Name>, renderables: View<Renderable>| {
-            let render = renderables.get(self.item_id);
+        let locale = world.borrow::<UniqueView<Locale>>();
 
-            grid.put_sym_color((2, 2), render.sym, render.fg, render.bg);
-            grid.print_color((4, 2), &names.get(self.item_id).0, true, fg, bg);
-        });
+        world.run(
+            |options: UniqueView<Options>, names: View<Name>, renderables: View<Renderable>| {
+                let render = renderables.get(self.item_id);
+
+                render::put_renderable(grid, (2, 2), render, &options);
+                grid.print_color((4, 2), &names.get(self.item_id).0, true, fg, bg);
+            },
+        );
 
         for (i, action) in self.actions.iter().enumerate() {
             grid.print_color(
                 (4, 4 + i as i32),
-                action.label(),
+                locale.get(action.label_key()),
                 true,
                 fg,
                 if matches!(self.subsection, SubSection::Actions) && i as i32 == self.selection {
@@ -304,9 +387,21 @@ impl InventoryActionMode {
             );
         }
 
+        grid.print_color(
+            (4, grid.height() as i32 - 4),
+            locale.get(DESCRIBE),
+            true,
+            fg,
+            if matches!(self.subsection, SubSection::Describe) {
+                selected_bg
+            } else {
+                bg
+            },
+        );
+
         grid.print_color(
             (4, grid.height() as i32 - 3),
-            CANCEL,
+            locale.get(CANCEL),
             true,
             fg,
             if matches!(self.subsection, SubSection::Cancel) {
@@ -315,5 +410,13 @@ impl InventoryActionMode {
                 bg
             },
         );
+
+        let selected_row = match self.subsection {
+            SubSection::Actions => 4 + self.selection,
+            SubSection::Describe => grid.height() as i32 - 4,
+            SubSection::Cancel => grid.height() as i32 - 3,
+        };
+
+        ui::scroll_menu_grid(grid, selected_row, fg, bg);
     }
 }
@@ -4,45 +4,44 @@ use crate::{
     components::{AreaOfEffect, Inventory, Name, Ranged, Renderable},
     gamekey::{self, GameKey},
     gamesym::GameSym,
+    locale::Locale,
     menu_memory::MenuMemory,
-    message::Messages,
+    message::{Messages, MsgCategory},
     player::PlayerId,
+    render,
     ui::{self, Options},
 };
 use ruggrogue::{
-    util::{Color, Size},
-    InputBuffer, InputEvent, KeyMods, TileGrid, Tileset,
+    util::{Color, Position, Size},
+    Area, Extent, InputBuffer, InputEvent, KeyMods, Layout, Menu, MenuItem, TileGrid, Tileset,
 };
 
 use super::{
     inventory_action::InventoryAction,
     target::{TargetMode, TargetModeResult},
-    ModeControl, ModeResult, ModeUpdate,
+    Mode, ModeControl, ModeResult, ModeUpdate,
 };
 
-const CANCEL: &str = "[ Cancel ]";
+const CANCEL: &str = "menu.cancel";
+const MARK_HINT: &str = "menu.mark_hint";
 
 pub enum InventoryShortcutModeResult {
     AppQuit,
     Cancelled,
     EquipItem(EntityId),
     UseItem(EntityId, Option<(i32, i32)>),
-    DropItem(EntityId),
-}
-
-enum SubSection {
-    Items,
-    Cancel,
+    DropItems(Vec<EntityId>),
 }
 
 pub struct InventoryShortcutMode {
     action: InventoryAction,
     title: String,
     prompt: String,
-    items: Vec<EntityId>,
+    /// Items to choose from, followed by a cancel entry with no item.
+    menu: Menu<Option<EntityId>>,
+    /// Items marked to be acted on together, if the action allows more than one at a time.
+    marked: Option<Vec<bool>>,
     inner_width: i32,
-    subsection: SubSection,
-    selection: i32,
 }
 
 /// Show inventory items for which a given action can be performed, shortcutting the inventory and
@@ -60,61 +59,62 @@ impl InventoryShortcutMode {
             .filter(|it| InventoryAction::item_supports_action(world, **it, action))
             .copied()
             .collect::<Vec<EntityId>>();
-        let title = format!("< {} Item >", action.name());
-        let prompt = format!("{} which item?", action.name());
-        let inner_width = title.len().max(prompt.len()).max(CANCEL.len()).max(
-            items
-                .iter()
-                .map(|it| names.get(*it).0.len() + 2)
-                .max()
-                .unwrap_or(2),
-        );
+        let locale = world.borrow::<UniqueView<Locale>>();
+        let action_name = locale.get(action.name_key());
+        let title = locale.format("inventory_shortcut.title", &[("action", &action_name)]);
+        let prompt = locale.format("inventory_shortcut.prompt", &[("action", &action_name)]);
+        let marked = if matches!(action, InventoryAction::DropItem) {
+            Some(vec![false; items.len()])
+        } else {
+            None
+        };
+        let inner_width = title
+            .chars()
+            .count()
+            .max(prompt.chars().count())
+            .max(locale.get(CANCEL).chars().count())
+            .max(
+                items
+                    .iter()
+                    .map(|it| {
+                        names.get(*it).0.chars().count() + if marked.is_some() { 4 } else { 2 }
+                    })
+                    .max()
+                    .unwrap_or(2),
+            );
+        let inner_width = if marked.is_some() {
+            inner_width.max(locale.get(MARK_HINT).chars().count())
+        } else {
+            inner_width
+        };
         let selection = match action {
             InventoryAction::EquipItem => menu_memory[MenuMemory::INVENTORY_SHORTCUT_EQUIP],
             InventoryAction::UseItem => menu_memory[MenuMemory::INVENTORY_SHORTCUT_USE],
             InventoryAction::DropItem => menu_memory[MenuMemory::INVENTORY_SHORTCUT_DROP],
+            // Shortcuts are only made for actions that have a key, which the others don't.
+            InventoryAction::OpenContainer
+            | InventoryAction::Favorite
+            | InventoryAction::Unfavorite
+            | InventoryAction::Lock
+            | InventoryAction::Unlock => unreachable!(),
         };
-        let selection = selection.min(items.len().saturating_sub(1) as i32);
+        let menu = Menu::new(
+            items
+                .iter()
+                .map(|it| MenuItem::new(Some(*it), names.get(*it).0.clone()))
+                .collect(),
+        )
+        .with_footer(vec![MenuItem::new(None, locale.get(CANCEL))])
+        .with_selection(selection.max(0) as usize);
 
         Self {
             action,
             title,
             prompt,
-            items,
+            menu,
+            marked,
             inner_width: inner_width as i32,
-            subsection: SubSection::Items,
-            selection,
-        }
-    }
-
-    pub fn prepare_grids(
-        &self,
-        world: &World,
-        grids: &mut Vec<TileGrid<GameSym>>,
-        tilesets: &[Tileset<GameSym>],
-        window_size: Size,
-    ) {
-        let Options {
-            font, text_zoom, ..
-        } = *world.borrow::<UniqueView<Options>>();
-        let tileset = &tilesets.get(font as usize).unwrap_or(&tilesets[0]);
-        let new_grid_size = Size {
-            w: self.inner_width as u32 + 4,
-            h: (8 + self.items.len() as u32)
-                .min(window_size.h / (tileset.tile_height() * text_zoom))
-                .max(9),
-        };
-
-        if !grids.is_empty() {
-            grids[0].resize(new_grid_size);
-        } else {
-            grids.push(TileGrid::new(new_grid_size, tilesets, font as usize));
-            grids[0].view.clear_color = None;
         }
-
-        grids[0].set_tileset(tilesets, font as usize);
-        grids[0].view_centered(tilesets, text_zoom, (0, 0).into(), window_size);
-        grids[0].view.zoom = text_zoom;
     }
 
     /// The height of the item list as an i32 for convenience.
@@ -122,10 +122,28 @@ impl InventoryShortcutMode {
         grid.height().saturating_sub(8).max(1).min(i32::MAX as u32) as i32
     }
 
+    /// The item under the cursor, if it isn't on the cancel entry.
+    fn selected_item(&self) -> Option<EntityId> {
+        self.menu.selected().and_then(|entry| entry.value)
+    }
+
+    /// The marked items, or the item under the cursor if none are marked.
+    fn chosen_items(&self, item_id: EntityId) -> Vec<EntityId> {
+        match &self.marked {
+            Some(marked) if marked.iter().any(|m| *m) => self
+                .menu
+                .items()
+                .iter()
+                .zip(marked.iter())
+                .filter_map(|(item, m)| if *m { item.value } else { None })
+                .collect(),
+            _ => vec![item_id],
+        }
+    }
+
     fn confirm_action(&self, world: &World, inputs: &mut InputBuffer) -> (ModeControl, ModeUpdate) {
-        let item_id = self.items[self.selection as usize];
-        let result = match self.subsection {
-            SubSection::Items => match self.action {
+        let result = match self.selected_item() {
+            Some(item_id) => match self.action {
                 InventoryAction::EquipItem => InventoryShortcutModeResult::EquipItem(item_id),
                 InventoryAction::UseItem => {
                     if let Some(Ranged { range }) =
@@ -139,141 +157,159 @@ impl InventoryShortcutMode {
 
                         inputs.clear_input();
                         return (
-                            ModeControl::Push(
-                                TargetMode::new(world, item_name, *range, radius, true).into(),
-                            ),
+                            ModeControl::Push(Box::new(TargetMode::new(
+                                world, item_name, *range, radius, true,
+                            ))),
                             ModeUpdate::Immediate,
                         );
                     } else {
                         InventoryShortcutModeResult::UseItem(item_id, None)
                     }
                 }
-                InventoryAction::DropItem => InventoryShortcutModeResult::DropItem(item_id),
+                InventoryAction::DropItem => {
+                    InventoryShortcutModeResult::DropItems(self.chosen_items(item_id))
+                }
+                InventoryAction::OpenContainer
+                | InventoryAction::Favorite
+                | InventoryAction::Unfavorite
+                | InventoryAction::Lock
+                | InventoryAction::Unlock => unreachable!(),
             },
-            SubSection::Cancel => InventoryShortcutModeResult::Cancelled,
+            None => InventoryShortcutModeResult::Cancelled,
         };
 
-        (ModeControl::Pop(result.into()), ModeUpdate::Immediate)
+        (ModeControl::Pop(Box::new(result)), ModeUpdate::Immediate)
+    }
+}
+
+impl Mode<World, GameSym> for InventoryShortcutMode {
+    fn prepare_grids(
+        &mut self,
+        world: &World,
+        grids: &mut Vec<TileGrid<GameSym>>,
+        tilesets: &[Tileset<GameSym>],
+        window_size: Size,
+    ) {
+        let Options {
+            font, text_zoom, ..
+        } = *world.borrow::<UniqueView<Options>>();
+        let layout = Layout::new(
+            Extent::Cells(self.inner_width as u32 + 4),
+            Extent::Cells(8 + self.menu.items().len() as u32),
+        )
+        .fitted_height(0)
+        .with_min_size(Size { w: 0, h: 9 });
+
+        if grids.is_empty() {
+            // Use a bogus size; the layout will resize the grid below.
+            grids.push(TileGrid::new(Size { w: 1, h: 1 }, tilesets, font as usize));
+            grids[0].view.clear_color = None;
+        }
+
+        grids[0].set_tileset(tilesets, font as usize);
+        layout.apply(
+            &mut grids[0],
+            tilesets,
+            text_zoom,
+            Area::window(window_size),
+        );
     }
 
-    pub fn update(
+    fn update(
         &mut self,
         world: &World,
         inputs: &mut InputBuffer,
         grids: &[TileGrid<GameSym>],
         pop_result: &Option<ModeResult>,
     ) -> (ModeControl, ModeUpdate) {
-        if self.items.is_empty() {
-            world.borrow::<UniqueViewMut<Messages>>().add(format!(
-                "You have no items in your inventory to {}.",
-                self.action.name().to_lowercase(),
-            ));
+        if self.menu.items().is_empty() {
+            let msg = {
+                let locale = world.borrow::<UniqueView<Locale>>();
+
+                locale.format(
+                    "msg.no_items_to",
+                    &[("action", &locale.get(self.action.name_key()).to_lowercase())],
+                )
+            };
+            world
+                .borrow::<UniqueViewMut<Messages>>()
+                .add_as(MsgCategory::Warning, msg);
 
             (
-                ModeControl::Pop(InventoryShortcutModeResult::Cancelled.into()),
+                ModeControl::Pop(Box::new(InventoryShortcutModeResult::Cancelled)),
                 ModeUpdate::Immediate,
             )
         } else if let Some(result) = pop_result {
-            match result {
-                ModeResult::TargetModeResult(result) => match result {
+            if let Some(result) = result.downcast_ref::<TargetModeResult>() {
+                match result {
                     TargetModeResult::AppQuit => (
-                        ModeControl::Pop(InventoryShortcutModeResult::AppQuit.into()),
+                        ModeControl::Pop(Box::new(InventoryShortcutModeResult::AppQuit)),
                         ModeUpdate::Immediate,
                     ),
                     TargetModeResult::Cancelled => (ModeControl::Stay, ModeUpdate::WaitForEvent),
-                    TargetModeResult::Target { x, y } => (
-                        ModeControl::Pop(
-                            InventoryShortcutModeResult::UseItem(
-                                self.items[self.selection as usize],
+                    TargetModeResult::Target { x, y } => match self.selected_item() {
+                        Some(item_id) => (
+                            ModeControl::Pop(Box::new(InventoryShortcutModeResult::UseItem(
+                                item_id,
                                 Some((*x, *y)),
-                            )
-                            .into(),
+                            ))),
+                            ModeUpdate::Immediate,
                         ),
-                        ModeUpdate::Immediate,
-                    ),
-                },
-                _ => unreachable!(),
+                        None => unreachable!(),
+                    },
+                }
+            } else {
+                unreachable!()
             }
         } else {
             inputs.prepare_input();
 
             if let Some(InputEvent::AppQuit) = inputs.get_input() {
                 return (
-                    ModeControl::Pop(InventoryShortcutModeResult::AppQuit.into()),
+                    ModeControl::Pop(Box::new(InventoryShortcutModeResult::AppQuit)),
                     ModeUpdate::Immediate,
                 );
             } else if let Some(InputEvent::Press(keycode)) = inputs.get_input() {
-                match gamekey::from_keycode(keycode, inputs.get_mods(KeyMods::SHIFT)) {
-                    GameKey::Up => match self.subsection {
-                        SubSection::Items => {
-                            if self.selection > 0 {
-                                self.selection -= 1;
-                            } else {
-                                self.subsection = SubSection::Cancel;
-                            }
-                        }
-                        SubSection::Cancel => {
-                            self.subsection = SubSection::Items;
-                            self.selection = self.items.len() as i32 - 1;
-                        }
-                    },
-                    GameKey::Down => match self.subsection {
-                        SubSection::Items => {
-                            if self.selection < self.items.len() as i32 - 1 {
-                                self.selection += 1;
-                            } else {
-                                self.subsection = SubSection::Cancel;
-                            }
-                        }
-                        SubSection::Cancel => {
-                            self.subsection = SubSection::Items;
-                            self.selection = 0;
+                let key = gamekey::from_keycode(keycode, inputs.get_mods(KeyMods::SHIFT));
+
+                if let Some(nav) = gamekey::menu_nav(&key) {
+                    let page_height = grids.first().map_or(1, Self::item_list_height);
+
+                    self.menu.navigate(nav, page_height);
+                } else {
+                    match key {
+                        GameKey::Cancel => {
+                            return (
+                                ModeControl::Pop(Box::new(InventoryShortcutModeResult::Cancelled)),
+                                ModeUpdate::Immediate,
+                            )
                         }
-                    },
-                    GameKey::PageUp => {
-                        if matches!(self.subsection, SubSection::Items) {
-                            if let Some(grid) = grids.get(0) {
-                                self.selection = self
-                                    .selection
-                                    .saturating_sub(Self::item_list_height(grid))
-                                    .max(0);
+                        GameKey::Wait => {
+                            let selection = self.menu.selection();
+
+                            if let (Some(marked), false) = (&mut self.marked, self.menu.in_footer())
+                            {
+                                let mark = &mut marked[selection];
+
+                                *mark = !*mark;
+                                if selection + 1 < self.menu.items().len() {
+                                    self.menu.select(selection + 1);
+                                }
                             }
                         }
-                    }
-                    GameKey::PageDown => {
-                        if matches!(self.subsection, SubSection::Items) {
-                            if let Some(grid) = grids.get(0) {
-                                let max_selection = (self.items.len() as i32 - 1).max(0);
-
-                                self.selection = self
-                                    .selection
-                                    .saturating_add(Self::item_list_height(grid))
-                                    .min(max_selection);
+                        GameKey::SelectAll => {
+                            if let Some(marked) = &mut self.marked {
+                                let mark = !marked.iter().all(|m| *m);
+
+                                marked.iter_mut().for_each(|m| *m = mark);
                             }
                         }
-                    }
-                    GameKey::Home => {
-                        if matches!(self.subsection, SubSection::Items) {
-                            self.selection = 0;
-                        }
-                    }
-                    GameKey::End => {
-                        if matches!(self.subsection, SubSection::Items) {
-                            self.selection = (self.items.len() as i32 - 1).max(0);
-                        }
-                    }
-                    GameKey::Cancel => {
-                        return (
-                            ModeControl::Pop(InventoryShortcutModeResult::Cancelled.into()),
-                            ModeUpdate::Immediate,
-                        )
-                    }
-                    GameKey::Confirm => return self.confirm_action(world, inputs),
-                    key => {
-                        if let Some(action) = InventoryAction::from_key(key) {
-                            if action == self.action && matches!(self.subsection, SubSection::Items)
-                            {
-                                return self.confirm_action(world, inputs);
+                        GameKey::Confirm => return self.confirm_action(world, inputs),
+                        key => {
+                            if let Some(action) = InventoryAction::from_key(key) {
+                                if action == self.action && !self.menu.in_footer() {
+                                    return self.confirm_action(world, inputs);
+                                }
                             }
                         }
                     }
@@ -292,9 +328,14 @@ impl InventoryShortcutMode {
                         InventoryAction::DropItem => {
                             &mut menu_memory[MenuMemory::INVENTORY_SHORTCUT_DROP]
                         }
+                        InventoryAction::OpenContainer
+                        | InventoryAction::Favorite
+                        | InventoryAction::Unfavorite
+                        | InventoryAction::Lock
+                        | InventoryAction::Unlock => unreachable!(),
                     };
 
-                    *menu_memory = self.selection;
+                    *menu_memory = self.menu.selection() as i32;
                 }
             }
 
@@ -302,7 +343,7 @@ impl InventoryShortcutMode {
         }
     }
 
-    pub fn draw(&self, world: &World, grids: &mut [TileGrid<GameSym>], active: bool) {
+    fn draw(&mut self, world: &World, grids: &mut [TileGrid<GameSym>], active: bool) {
         let grid = &mut grids[0];
         let width = grid.width();
         let height = grid.height();
@@ -314,70 +355,65 @@ impl InventoryShortcutMode {
 
         grid.draw_box((0, 0), (width, height), fg, bg);
         grid.print_color((2, 0), &self.title, true, Color::YELLOW, bg);
-        grid.print((2, 2), &self.prompt);
-
-        let list_height = Self::item_list_height(grid);
-        let list_offset = (self.selection - (list_height - 1) / 2)
-            .min(self.items.len() as i32 - list_height)
-            .max(0);
-
-        if self.items.len() as i32 > list_height {
-            grid.draw_bar(
+        if self.marked.is_some() {
+            grid.print_color(
+                (2, height as i32 - 1),
+                world.borrow::<UniqueView<Locale>>().get(MARK_HINT),
                 true,
-                (width as i32 - 1, 4),
-                list_height,
-                list_offset,
-                list_height,
-                self.items.len() as i32,
-                fg,
+                Color::GRAY,
                 bg,
             );
         }
+        grid.print((2, 2), &self.prompt);
+
+        let list_height = Self::item_list_height(grid);
 
         {
-            let names = world.borrow::<View<Name>>();
             let renderables = world.borrow::<View<Renderable>>();
+            let options = world.borrow::<UniqueView<Options>>();
+            let marked = &self.marked;
 
-            for (i, item_id) in self
-                .items
-                .iter()
-                .enumerate()
-                .skip(list_offset as usize)
-                .take(list_height as usize)
-            {
-                let render = renderables.get(*item_id);
-
-                grid.put_sym_color(
-                    (2, 4 + i as i32 - list_offset),
-                    render.sym,
-                    render.fg,
-                    render.bg,
-                );
-
-                grid.print_color(
-                    (4, 4 + i as i32 - list_offset),
-                    &names.get(*item_id).0,
-                    true,
-                    fg,
-                    if matches!(self.subsection, SubSection::Items) && i as i32 == self.selection {
-                        selected_bg
+            self.menu.draw_list(
+                grid,
+                Position { x: 2, y: 4 },
+                list_height,
+                fg,
+                bg,
+                selected_bg,
+                |grid, pos, i, item, item_bg| {
+                    let x = if let Some(marked) = marked {
+                        if marked[i] {
+                            grid.print_color(pos, "+", true, Color::GREEN, bg);
+                        } else {
+                            grid.print_color(pos, "-", true, Color::GRAY, bg);
+                        }
+                        pos.x + 2
                     } else {
-                        bg
-                    },
-                );
-            }
+                        pos.x
+                    };
+
+                    if let Some(item_id) = item.value {
+                        render::put_renderable(
+                            grid,
+                            (x, pos.y),
+                            renderables.get(item_id),
+                            &options,
+                        );
+                        grid.print_color((x + 2, pos.y), &item.label, true, fg, item_bg);
+                    }
+                },
+            );
         }
 
-        grid.print_color(
-            (4, height as i32 - 3),
-            CANCEL,
-            true,
-            fg,
-            if matches!(self.subsection, SubSection::Cancel) {
-                selected_bg
-            } else {
-                bg
+        self.menu.draw_footer(
+            grid,
+            Position {
+                x: 4,
+                y: height as i32 - 3,
             },
+            fg,
+            bg,
+            selected_bg,
         );
     }
 }
@@ -0,0 +1,323 @@
+use shipyard::{EntityId, Get, UniqueView, View, World};
+
+use crate::{
+    components::{
+        AreaOfEffect, CombatBonus, Consumable, Container, Description, Durability, EquipSlot,
+        Equipment, InflictsDamage, InflictsSleep, Inventory, Name, Nutrition, ProvidesHealing,
+        Ranged, Renderable, Repairs, ScriptedUse, Victory, Weight,
+    },
+    gamekey::{self, GameKey},
+    gamesym::GameSym,
+    locale::Locale,
+    message,
+    player::PlayerId,
+    render,
+    ui::{self, Options},
+};
+use ruggrogue::{
+    util::{Color, Size},
+    InputBuffer, InputEvent, KeyMods, TileGrid, Tileset,
+};
+
+use super::{Mode, ModeControl, ModeResult, ModeUpdate};
+
+const TEXT_WIDTH: usize = 40;
+
+pub enum ItemDetailModeResult {
+    AppQuit,
+    Done,
+}
+
+pub struct ItemDetailMode {
+    item_id: EntityId,
+    lines: Vec<Vec<(char, Color)>>,
+    scroll_row: i32,
+}
+
+fn push_text(lines: &mut Vec<Vec<(char, Color)>>, text: &str, fg: Color) {
+    for line in message::wrap_markup(text, TEXT_WIDTH) {
+        lines.push(
+            line.into_iter()
+                .map(|(ch, color)| (ch, color.unwrap_or(fg)))
+                .collect(),
+        );
+    }
+}
+
+/// Describe how an equipment bonus compares to that of what's currently equipped in its slot.
+fn compare_bonus(locale: &Locale, stat_key: &str, value: f32, current: Option<f32>) -> String {
+    let bonus = locale.format(stat_key, &[("value", &format!("{:+.1}", value))]);
+
+    match current {
+        Some(current) => {
+            let diff = value - current;
+            let color = if diff > 0.0 {
+                "green"
+            } else if diff < 0.0 {
+                "red"
+            } else {
+                "gray"
+            };
+
+            format!(
+                "{} {{{}}}{}{{/}}",
+                bonus,
+                color,
+                locale.format(
+                    "item_detail.vs_equipped",
+                    &[("diff", &format!("{:+.1}", diff))]
+                )
+            )
+        }
+        None => bonus,
+    }
+}
+
+/// Show the details of a single item: its description and everything it does.
+impl ItemDetailMode {
+    pub fn new(world: &World, item_id: EntityId) -> Self {
+        let locale = world.borrow::<UniqueView<Locale>>();
+        let mut lines = Vec::new();
+
+        if let Ok(desc) = world.borrow::<View<Description>>().try_get(item_id) {
+            push_text(&mut lines, &desc.text(&locale), Color::WHITE);
+            lines.push(Vec::new());
+        }
+
+        let mut stats = Vec::new();
+
+        if let (Ok(slot), Ok(bonus)) = (
+            world.borrow::<View<EquipSlot>>().try_get(item_id),
+            world.borrow::<View<CombatBonus>>().try_get(item_id),
+        ) {
+            let player_id = world.borrow::<UniqueView<PlayerId>>();
+            let equipment = world.borrow::<View<Equipment>>();
+            let combat_bonuses = world.borrow::<View<CombatBonus>>();
+            let equipped = equipment
+                .try_get(player_id.0)
+                .ok()
+                .and_then(|e| match slot {
+                    EquipSlot::Weapon => e.weapon,
+                    EquipSlot::Armor => e.armor,
+                });
+            let current = equipped
+                .filter(|id| *id != item_id)
+                .and_then(|id| combat_bonuses.try_get(id).ok());
+
+            stats.push(
+                locale
+                    .get(match slot {
+                        EquipSlot::Weapon => "item_detail.slot_weapon",
+                        EquipSlot::Armor => "item_detail.slot_armor",
+                    })
+                    .to_string(),
+            );
+            if bonus.attack != 0.0 || matches!(slot, EquipSlot::Weapon) {
+                stats.push(compare_bonus(
+                    &locale,
+                    "item_detail.attack",
+                    bonus.attack,
+                    current.map(|c| c.attack),
+                ));
+            }
+            if bonus.defense != 0.0 || matches!(slot, EquipSlot::Armor) {
+                stats.push(compare_bonus(
+                    &locale,
+                    "item_detail.defense",
+                    bonus.defense,
+                    current.map(|c| c.defense),
+                ));
+            }
+            if world.borrow::<UniqueView<Options>>().durability {
+                if let Ok(durability) = world.borrow::<View<Durability>>().try_get(item_id) {
+                    stats.push(locale.format(
+                        "item_detail.durability",
+                        &[("current", &durability.current), ("max", &durability.max)],
+                    ));
+                }
+            }
+            if equipped == Some(item_id) {
+                stats.push(format!(
+                    "{{yellow}}{}{{/}}",
+                    locale.get("item_detail.equipped")
+                ));
+            }
+        }
+
+        if let Ok(healing) = world.borrow::<View<ProvidesHealing>>().try_get(item_id) {
+            stats.push(locale.format("item_detail.heals", &[("amount", &healing.heal_amount)]));
+        }
+        if let Ok(damage) = world.borrow::<View<InflictsDamage>>().try_get(item_id) {
+            stats.push(locale.format("item_detail.damage", &[("damage", &damage.damage)]));
+        }
+        if world.borrow::<View<InflictsSleep>>().contains(item_id) {
+            stats.push(locale.get("item_detail.sleep").to_string());
+        }
+        if let Ok(nutrition) = world.borrow::<View<Nutrition>>().try_get(item_id) {
+            stats.push(locale.format("item_detail.nutrition", &[("amount", &nutrition.0)]));
+        }
+        if let Ok(repairs) = world.borrow::<View<Repairs>>().try_get(item_id) {
+            stats.push(
+                locale
+                    .get(match repairs.0 {
+                        EquipSlot::Weapon => "item_detail.repairs_weapon",
+                        EquipSlot::Armor => "item_detail.repairs_armor",
+                    })
+                    .to_string(),
+            );
+        }
+        if world.borrow::<View<ScriptedUse>>().contains(item_id) {
+            stats.push(locale.get("item_detail.scripted").to_string());
+        }
+        if let Ok(ranged) = world.borrow::<View<Ranged>>().try_get(item_id) {
+            stats.push(locale.format("item_detail.range", &[("range", &ranged.range)]));
+        }
+        if let Ok(aoe) = world.borrow::<View<AreaOfEffect>>().try_get(item_id) {
+            stats.push(locale.format("item_detail.radius", &[("radius", &aoe.radius)]));
+        }
+        if world.borrow::<View<Consumable>>().contains(item_id) {
+            stats.push(locale.get("item_detail.consumable").to_string());
+        }
+        if let Ok(container) = world.borrow::<View<Container>>().try_get(item_id) {
+            let inside = world
+                .borrow::<View<Inventory>>()
+                .try_get(item_id)
+                .map_or(0, |inv| inv.items.len());
+
+            stats.push(locale.format(
+                "item_detail.container",
+                &[("capacity", &container.capacity), ("inside", &inside)],
+            ));
+        }
+        if let Ok(weight) = world.borrow::<View<Weight>>().try_get(item_id) {
+            stats.push(locale.format("item_detail.weight", &[("weight", &weight.0)]));
+        }
+        if world.borrow::<View<Victory>>().contains(item_id) {
+            stats.push(format!(
+                "{{yellow}}{}{{/}}",
+                locale.get("item_detail.victory")
+            ));
+        }
+
+        for stat in stats {
+            push_text(&mut lines, &stat, Color::GRAY);
+        }
+
+        // Drop the blank line after the description if there were no stats to show.
+        if matches!(lines.last(), Some(line) if line.is_empty()) {
+            lines.pop();
+        }
+
+        Self {
+            item_id,
+            lines,
+            scroll_row: 0,
+        }
+    }
+}
+
+impl Mode<World, GameSym> for ItemDetailMode {
+    fn prepare_grids(
+        &mut self,
+        world: &World,
+        grids: &mut Vec<TileGrid<GameSym>>,
+        tilesets: &[Tileset<GameSym>],
+        window_size: Size,
+    ) {
+        let Options {
+            font, text_zoom, ..
+        } = *world.borrow::<UniqueView<Options>>();
+        let name_width = world
+            .borrow::<View<Name>>()
+            .get(self.item_id)
+            .0
+            .chars()
+            .count()
+            + 2;
+        let new_size = Size {
+            w: TEXT_WIDTH.max(name_width) as u32 + 4,
+            h: self.lines.len() as u32 + 6,
+        };
+
+        if !grids.is_empty() {
+            grids[0].resize(new_size);
+        } else {
+            grids.push(TileGrid::new(new_size, tilesets, font as usize));
+            grids[0].view.clear_color = None;
+        }
+
+        grids[0].set_tileset(tilesets, font as usize);
+        grids[0].view_centered(tilesets, text_zoom, (0, 0).into(), window_size);
+        grids[0].view.zoom = text_zoom;
+    }
+
+    fn update(
+        &mut self,
+        _world: &World,
+        inputs: &mut InputBuffer,
+        grids: &[TileGrid<GameSym>],
+        _pop_result: &Option<ModeResult>,
+    ) -> (ModeControl, ModeUpdate) {
+        inputs.prepare_input();
+
+        if let Some(InputEvent::AppQuit) = inputs.get_input() {
+            return (
+                ModeControl::Pop(Box::new(ItemDetailModeResult::AppQuit)),
+                ModeUpdate::Immediate,
+            );
+        } else if let Some(InputEvent::Press(keycode)) = inputs.get_input() {
+            let key = gamekey::from_keycode(keycode, inputs.get_mods(KeyMods::SHIFT));
+            if matches!(key, GameKey::Confirm | GameKey::Cancel) {
+                inputs.clear_input();
+                return (
+                    ModeControl::Pop(Box::new(ItemDetailModeResult::Done)),
+                    ModeUpdate::Immediate,
+                );
+            } else if let Some(grid) = grids.first() {
+                // Scroll a row at a time if the details don't fit on screen.
+                let (first_row, last_row) = grid.view_visible_rows();
+                let half = (last_row - first_row) / 2;
+                let max_scroll = (grid.height() as i32 - 1 - half).max(half);
+
+                match key {
+                    GameKey::Up => {
+                        self.scroll_row = (self.scroll_row.min(max_scroll) - 1).max(half);
+                    }
+                    GameKey::Down => {
+                        self.scroll_row = (self.scroll_row.max(half) + 1).min(max_scroll);
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        (ModeControl::Stay, ModeUpdate::WaitForEvent)
+    }
+
+    fn draw(&mut self, world: &World, grids: &mut [TileGrid<GameSym>], active: bool) {
+        let grid = &mut grids[0];
+        let fg = Color::WHITE;
+        let bg = Color::BLACK;
+
+        grid.view.color_mod = if active { Color::WHITE } else { Color::GRAY };
+
+        grid.draw_box((0, 0), (grid.width(), grid.height()), fg, bg);
+
+        world.run(
+            |options: UniqueView<Options>, names: View<Name>, renderables: View<Renderable>| {
+                let render = renderables.get(self.item_id);
+
+                render::put_renderable(grid, (2, 2), render, &options);
+                grid.print_color((4, 2), &names.get(self.item_id).0, true, fg, bg);
+            },
+        );
+
+        for (y, line) in self.lines.iter().enumerate() {
+            for (x, (ch, color)) in line.iter().enumerate() {
+                grid.put_char_color((2 + x as i32, 4 + y as i32), *ch, *color, bg);
+            }
+        }
+
+        ui::scroll_menu_grid(grid, self.scroll_row, fg, bg);
+    }
+}
@@ -0,0 +1,196 @@
+use shipyard::{UniqueView, World};
+
+use crate::{
+    gamekey::{self, GameKey},
+    gamesym::GameSym,
+    journal::{Journal, JournalEntry},
+    locale::Locale,
+    ui::{self, Options},
+};
+use ruggrogue::{
+    util::{Color, Size},
+    InputBuffer, InputEvent, KeyMods, TileGrid, Tileset,
+};
+
+use super::{Mode, ModeControl, ModeResult, ModeUpdate};
+
+const TEXT_WIDTH: usize = 56;
+
+pub enum JournalModeResult {
+    AppQuit,
+    Done,
+}
+
+pub struct JournalMode {
+    lines: Vec<(String, Color)>,
+    scroll_row: i32,
+}
+
+fn push_entry(lines: &mut Vec<(String, Color)>, locale: &Locale, entry: &JournalEntry) {
+    for (i, line) in ruggrogue::word_wrap(&entry.text, TEXT_WIDTH - 2).enumerate() {
+        let bullet = if i == 0 { "* " } else { "  " };
+        lines.push((format!("{}{}", bullet, line), Color::WHITE));
+    }
+
+    let origin = locale.format(
+        "journal.from",
+        &[("giver", &entry.giver), ("depth", &entry.depth)],
+    );
+    let origin = if let Some(completed_depth) = entry.completed_depth {
+        locale.format(
+            "journal.completed",
+            &[("origin", &origin), ("depth", &completed_depth)],
+        )
+    } else {
+        origin
+    };
+    lines.push((format!("  {}", origin), Color::GRAY));
+
+    if let Some(reward) = entry.reward {
+        let status = if entry.reward_claimed {
+            "journal.claimed"
+        } else {
+            "journal.pending"
+        };
+        lines.push((
+            format!(
+                "  {}",
+                locale.format(
+                    "journal.reward",
+                    &[
+                        ("reward", &locale.name(reward.name())),
+                        ("status", &locale.get(status)),
+                    ],
+                )
+            ),
+            Color::GRAY,
+        ));
+    }
+}
+
+/// Show the quests of the current game, split into active and completed ones.
+impl JournalMode {
+    pub fn new(world: &World) -> Self {
+        let journal = world.borrow::<UniqueView<Journal>>();
+        let locale = world.borrow::<UniqueView<Locale>>();
+        let none = format!("  {}", locale.get("journal.none"));
+        let mut lines = Vec::new();
+
+        lines.push((locale.get("journal.active").to_string(), Color::YELLOW));
+        if journal.active().next().is_none() {
+            lines.push((none.clone(), Color::GRAY));
+        }
+        for entry in journal.active() {
+            push_entry(&mut lines, &locale, entry);
+        }
+
+        lines.push((String::new(), Color::WHITE));
+
+        lines.push((locale.get("journal.finished").to_string(), Color::YELLOW));
+        if journal.completed().next().is_none() {
+            lines.push((none, Color::GRAY));
+        }
+        for entry in journal.completed() {
+            push_entry(&mut lines, &locale, entry);
+        }
+
+        Self {
+            lines,
+            scroll_row: 0,
+        }
+    }
+}
+
+impl Mode<World, GameSym> for JournalMode {
+    fn prepare_grids(
+        &mut self,
+        world: &World,
+        grids: &mut Vec<TileGrid<GameSym>>,
+        tilesets: &[Tileset<GameSym>],
+        window_size: Size,
+    ) {
+        let Options {
+            font, text_zoom, ..
+        } = *world.borrow::<UniqueView<Options>>();
+        let new_size = Size {
+            w: TEXT_WIDTH as u32 + 4,
+            h: self.lines.len() as u32 + 4,
+        };
+
+        if !grids.is_empty() {
+            grids[0].resize(new_size);
+        } else {
+            grids.push(TileGrid::new(new_size, tilesets, font as usize));
+            grids[0].view.clear_color = None;
+        }
+
+        grids[0].set_tileset(tilesets, font as usize);
+        grids[0].view_centered(tilesets, text_zoom, (0, 0).into(), window_size);
+        grids[0].view.zoom = text_zoom;
+    }
+
+    fn update(
+        &mut self,
+        _world: &World,
+        inputs: &mut InputBuffer,
+        grids: &[TileGrid<GameSym>],
+        _pop_result: &Option<ModeResult>,
+    ) -> (ModeControl, ModeUpdate) {
+        inputs.prepare_input();
+
+        if let Some(InputEvent::AppQuit) = inputs.get_input() {
+            return (
+                ModeControl::Pop(Box::new(JournalModeResult::AppQuit)),
+                ModeUpdate::Immediate,
+            );
+        } else if let Some(InputEvent::Press(keycode)) = inputs.get_input() {
+            let key = gamekey::from_keycode(keycode, inputs.get_mods(KeyMods::SHIFT));
+            if matches!(key, GameKey::Confirm | GameKey::Cancel | GameKey::Journal) {
+                inputs.clear_input();
+                return (
+                    ModeControl::Pop(Box::new(JournalModeResult::Done)),
+                    ModeUpdate::Immediate,
+                );
+            } else if let Some(grid) = grids.first() {
+                // Scroll a row at a time if the journal doesn't fit on screen.
+                let (first_row, last_row) = grid.view_visible_rows();
+                let half = (last_row - first_row) / 2;
+                let max_scroll = (grid.height() as i32 - 1 - half).max(half);
+
+                match key {
+                    GameKey::Up => {
+                        self.scroll_row = (self.scroll_row.min(max_scroll) - 1).max(half);
+                    }
+                    GameKey::Down => {
+                        self.scroll_row = (self.scroll_row.max(half) + 1).min(max_scroll);
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        (ModeControl::Stay, ModeUpdate::WaitForEvent)
+    }
+
+    fn draw(&mut self, world: &World, grids: &mut [TileGrid<GameSym>], active: bool) {
+        let grid = &mut grids[0];
+        let bg = Color::BLACK;
+
+        grid.view.color_mod = if active { Color::WHITE } else { Color::GRAY };
+
+        grid.draw_box((0, 0), (grid.width(), grid.height()), Color::WHITE, bg);
+        grid.print_color(
+            (2, 0),
+            world.borrow::<UniqueView<Locale>>().get("journal.title"),
+            true,
+            Color::YELLOW,
+            bg,
+        );
+
+        for (y, (line, fg)) in self.lines.iter().enumerate() {
+            grid.print_color((2, 2 + y as i32), line, true, *fg, bg);
+        }
+
+        ui::scroll_menu_grid(grid, self.scroll_row, Color::WHITE, bg);
+    }
+}
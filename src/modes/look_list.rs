@@ -0,0 +1,315 @@
+use shipyard::{Get, UniqueView, View, World};
+
+use crate::{
+    components::{Coord, FieldOfView, Item, Monster, Name},
+    gamekey::{self, GameKey},
+    gamesym::GameSym,
+    locale::Locale,
+    map::{Map, Tile},
+    player::PlayerId,
+    ui::{self, Options},
+};
+use ruggrogue::{
+    util::{Color, Position, Size},
+    InputBuffer, InputEvent, KeyMods, TileGrid, Tileset,
+};
+
+use super::{Mode, ModeControl, ModeResult, ModeUpdate};
+
+const TITLE: &str = "look.title";
+const NOTHING: &str = "look.nothing";
+const CANCEL: &str = "menu.cancel";
+
+pub enum LookListModeResult {
+    AppQuit,
+    Cancelled,
+    Selected(Position),
+}
+
+enum SubSection {
+    Entries,
+    Cancel,
+}
+
+struct Entry {
+    /// Entries at the same distance are listed in order of rank, so monsters come first.
+    rank: u8,
+    pos: Position,
+    text: String,
+    fg: Color,
+}
+
+pub struct LookListMode {
+    entries: Vec<Entry>,
+    subsection: SubSection,
+    selection: i32,
+}
+
+/// Locale key of the compass direction from one position towards another.
+fn direction_key(dx: i32, dy: i32) -> &'static str {
+    match (dx.signum(), dy.signum()) {
+        (0, -1) => "dir.north",
+        (1, -1) => "dir.northeast",
+        (1, 0) => "dir.east",
+        (1, 1) => "dir.southeast",
+        (0, 1) => "dir.south",
+        (-1, 1) => "dir.southwest",
+        (-1, 0) => "dir.west",
+        (-1, -1) => "dir.northwest",
+        _ => "look.here",
+    }
+}
+
+/// Describe how far away and in which direction a position is from the player.
+fn describe_offset(locale: &Locale, dx: i32, dy: i32) -> String {
+    let distance = dx.abs().max(dy.abs());
+
+    if distance == 0 {
+        locale.get("look.here").to_string()
+    } else {
+        format!("{} {}", distance, locale.get(direction_key(dx, dy)))
+    }
+}
+
+/// A list of everything the player can currently see, described in words, so that the map can be
+/// explored without having to make sense of it visually.
+impl LookListMode {
+    pub fn new(world: &World) -> Self {
+        let map = world.borrow::<UniqueView<Map>>();
+        let player_id = world.borrow::<UniqueView<PlayerId>>();
+        let coords = world.borrow::<View<Coord>>();
+        let fovs = world.borrow::<View<FieldOfView>>();
+        let items = world.borrow::<View<Item>>();
+        let monsters = world.borrow::<View<Monster>>();
+        let names = world.borrow::<View<Name>>();
+        let locale = world.borrow::<UniqueView<Locale>>();
+        let player_pos = coords.get(player_id.0).0;
+        let mut entries = Vec::new();
+
+        for (x, y) in fovs.get(player_id.0).iter() {
+            let (dx, dy) = (x - player_pos.x, y - player_pos.y);
+
+            for id in map.iter_entities_at(x, y) {
+                if id == player_id.0 {
+                    continue;
+                }
+                if let Ok(name) = names.try_get(id) {
+                    let (rank, kind, fg) = if monsters.contains(id) {
+                        (0, "look.monster", Color::RED)
+                    } else if items.contains(id) {
+                        (2, "look.item", Color::LIGHT_GRAY)
+                    } else {
+                        (1, "look.other", Color::CYAN)
+                    };
+
+                    entries.push(Entry {
+                        rank,
+                        pos: (x, y).into(),
+                        text: locale.format(
+                            "look.entry",
+                            &[
+                                ("kind", &locale.get(kind)),
+                                ("name", &name.0),
+                                ("where", &describe_offset(&locale, dx, dy)),
+                            ],
+                        ),
+                        fg,
+                    });
+                }
+            }
+
+            if matches!(map.get_tile(x, y), Tile::DownStairs) {
+                entries.push(Entry {
+                    rank: 3,
+                    pos: (x, y).into(),
+                    text: locale.format(
+                        "look.down_stairs",
+                        &[("where", &describe_offset(&locale, dx, dy))],
+                    ),
+                    fg: Color::YELLOW,
+                });
+            }
+        }
+
+        // Nearest first.
+        entries.sort_by_key(|e| {
+            (
+                (e.pos.x - player_pos.x)
+                    .abs()
+                    .max((e.pos.y - player_pos.y).abs()),
+                e.rank,
+            )
+        });
+
+        Self {
+            subsection: if entries.is_empty() {
+                SubSection::Cancel
+            } else {
+                SubSection::Entries
+            },
+            entries,
+            selection: 0,
+        }
+    }
+}
+
+impl Mode<World, GameSym> for LookListMode {
+    fn prepare_grids(
+        &mut self,
+        world: &World,
+        grids: &mut Vec<TileGrid<GameSym>>,
+        tilesets: &[Tileset<GameSym>],
+        window_size: Size,
+    ) {
+        let Options {
+            font, text_zoom, ..
+        } = *world.borrow::<UniqueView<Options>>();
+        let locale = world.borrow::<UniqueView<Locale>>();
+        let inner_width = self
+            .entries
+            .iter()
+            .map(|e| e.text.chars().count())
+            .max()
+            .unwrap_or(0)
+            .max(locale.get(NOTHING).chars().count())
+            .max(locale.get(TITLE).chars().count());
+        let new_size = Size {
+            w: inner_width as u32 + 4,
+            h: 6 + self.entries.len().max(1) as u32,
+        };
+
+        if !grids.is_empty() {
+            grids[0].resize(new_size);
+        } else {
+            grids.push(TileGrid::new(new_size, tilesets, font as usize));
+            grids[0].view.clear_color = None;
+        }
+
+        grids[0].set_tileset(tilesets, font as usize);
+        grids[0].view_centered(tilesets, text_zoom, (0, 0).into(), window_size);
+        grids[0].view.zoom = text_zoom;
+    }
+
+    fn update(
+        &mut self,
+        _world: &World,
+        inputs: &mut InputBuffer,
+        _grids: &[TileGrid<GameSym>],
+        _pop_result: &Option<ModeResult>,
+    ) -> (ModeControl, ModeUpdate) {
+        inputs.prepare_input();
+
+        if let Some(InputEvent::AppQuit) = inputs.get_input() {
+            return (
+                ModeControl::Pop(Box::new(LookListModeResult::AppQuit)),
+                ModeUpdate::Immediate,
+            );
+        } else if let Some(InputEvent::Press(keycode)) = inputs.get_input() {
+            let len = self.entries.len() as i32;
+
+            match gamekey::from_keycode(keycode, inputs.get_mods(KeyMods::SHIFT)) {
+                GameKey::Up => match self.subsection {
+                    SubSection::Entries if self.selection > 0 => self.selection -= 1,
+                    SubSection::Entries => self.subsection = SubSection::Cancel,
+                    SubSection::Cancel if len > 0 => {
+                        self.subsection = SubSection::Entries;
+                        self.selection = len - 1;
+                    }
+                    SubSection::Cancel => {}
+                },
+                GameKey::Down => match self.subsection {
+                    SubSection::Entries if self.selection < len - 1 => self.selection += 1,
+                    SubSection::Entries => self.subsection = SubSection::Cancel,
+                    SubSection::Cancel if len > 0 => {
+                        self.subsection = SubSection::Entries;
+                        self.selection = 0;
+                    }
+                    SubSection::Cancel => {}
+                },
+                GameKey::Home => {
+                    if matches!(self.subsection, SubSection::Entries) {
+                        self.selection = 0;
+                    }
+                }
+                GameKey::End => {
+                    if matches!(self.subsection, SubSection::Entries) {
+                        self.selection = (len - 1).max(0);
+                    }
+                }
+                GameKey::Confirm => {
+                    let result = match self.subsection {
+                        SubSection::Entries => {
+                            LookListModeResult::Selected(self.entries[self.selection as usize].pos)
+                        }
+                        SubSection::Cancel => LookListModeResult::Cancelled,
+                    };
+
+                    return (ModeControl::Pop(Box::new(result)), ModeUpdate::Immediate);
+                }
+                GameKey::Cancel | GameKey::LookList => {
+                    return (
+                        ModeControl::Pop(Box::new(LookListModeResult::Cancelled)),
+                        ModeUpdate::Immediate,
+                    );
+                }
+                _ => {}
+            }
+        }
+
+        (ModeControl::Stay, ModeUpdate::WaitForEvent)
+    }
+
+    fn draw(&mut self, world: &World, grids: &mut [TileGrid<GameSym>], active: bool) {
+        let grid = &mut grids[0];
+        let fg = Color::WHITE;
+        let bg = Color::BLACK;
+
+        grid.view.color_mod = if active { Color::WHITE } else { Color::GRAY };
+
+        grid.draw_box((0, 0), (grid.width(), grid.height()), fg, bg);
+        let locale = world.borrow::<UniqueView<Locale>>();
+
+        grid.print_color((2, 0), locale.get(TITLE), true, Color::YELLOW, bg);
+
+        if self.entries.is_empty() {
+            grid.print_color((2, 2), locale.get(NOTHING), true, Color::GRAY, bg);
+        }
+
+        for (i, entry) in self.entries.iter().enumerate() {
+            grid.print_color(
+                (2, 2 + i as i32),
+                &entry.text,
+                true,
+                entry.fg,
+                if matches!(self.subsection, SubSection::Entries) && i as i32 == self.selection {
+                    ui::SELECTED_BG
+                } else {
+                    bg
+                },
+            );
+        }
+
+        let cancel_y = grid.height() as i32 - 3;
+        grid.print_color(
+            (4, cancel_y),
+            locale.get(CANCEL),
+            true,
+            fg,
+            if matches!(self.subsection, SubSection::Cancel) {
+                ui::SELECTED_BG
+            } else {
+                bg
+            },
+        );
+
+        ui::scroll_menu_grid(
+            grid,
+            match self.subsection {
+                SubSection::Entries => 2 + self.selection,
+                SubSection::Cancel => cancel_y,
+            },
+            fg,
+            bg,
+        );
+    }
+}
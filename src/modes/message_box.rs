@@ -3,14 +3,14 @@ use shipyard::{UniqueView, World};
 use crate::{
     gamekey::{self, GameKey},
     gamesym::GameSym,
-    ui::Options,
+    ui::{self, Options},
 };
 use ruggrogue::{
     util::{Color, Size},
     InputBuffer, InputEvent, KeyMods, TileGrid, Tileset,
 };
 
-use super::{ModeControl, ModeResult, ModeUpdate};
+use super::{Mode, ModeControl, ModeResult, ModeUpdate};
 
 pub enum MessageBoxModeResult {
     AppQuit,
@@ -20,6 +20,7 @@ pub enum MessageBoxModeResult {
 pub struct MessageBoxMode {
     msg: Vec<String>,
     inner_width: u32,
+    scroll_row: i32,
 }
 
 /// Show a multi-line message box.
@@ -27,11 +28,17 @@ impl MessageBoxMode {
     pub fn new(msg: Vec<String>) -> Self {
         let inner_width = msg.iter().map(|m| m.chars().count()).max().unwrap_or(0) as u32;
 
-        Self { msg, inner_width }
+        Self {
+            msg,
+            inner_width,
+            scroll_row: 0,
+        }
     }
+}
 
-    pub fn prepare_grids(
-        &self,
+impl Mode<World, GameSym> for MessageBoxMode {
+    fn prepare_grids(
+        &mut self,
         world: &World,
         grids: &mut Vec<TileGrid<GameSym>>,
         tilesets: &[Tileset<GameSym>],
@@ -57,18 +64,18 @@ impl MessageBoxMode {
         grids[0].view.zoom = text_zoom;
     }
 
-    pub fn update(
+    fn update(
         &mut self,
         _world: &World,
         inputs: &mut InputBuffer,
-        _grids: &[TileGrid<GameSym>],
+        grids: &[TileGrid<GameSym>],
         _pop_result: &Option<ModeResult>,
     ) -> (ModeControl, ModeUpdate) {
         inputs.prepare_input();
 
         if let Some(InputEvent::AppQuit) = inputs.get_input() {
             return (
-                ModeControl::Pop(MessageBoxModeResult::AppQuit.into()),
+                ModeControl::Pop(Box::new(MessageBoxModeResult::AppQuit)),
                 ModeUpdate::Immediate,
             );
         } else if let Some(InputEvent::Press(keycode)) = inputs.get_input() {
@@ -76,16 +83,31 @@ impl MessageBoxMode {
             if matches!(key, GameKey::Confirm | GameKey::Cancel) {
                 inputs.clear_input();
                 return (
-                    ModeControl::Pop(MessageBoxModeResult::Done.into()),
+                    ModeControl::Pop(Box::new(MessageBoxModeResult::Done)),
                     ModeUpdate::Immediate,
                 );
+            } else if let Some(grid) = grids.first() {
+                // Scroll a row at a time if the message box doesn't fit on screen.
+                let (first_row, last_row) = grid.view_visible_rows();
+                let half = (last_row - first_row) / 2;
+                let max_scroll = (grid.height() as i32 - 1 - half).max(half);
+
+                match key {
+                    GameKey::Up => {
+                        self.scroll_row = (self.scroll_row.min(max_scroll) - 1).max(half);
+                    }
+                    GameKey::Down => {
+                        self.scroll_row = (self.scroll_row.max(half) + 1).min(max_scroll);
+                    }
+                    _ => {}
+                }
             }
         }
 
         (ModeControl::Stay, ModeUpdate::WaitForEvent)
     }
 
-    pub fn draw(&self, _world: &World, grids: &mut [TileGrid<GameSym>], active: bool) {
+    fn draw(&mut self, _world: &World, grids: &mut [TileGrid<GameSym>], active: bool) {
         let grid = &mut grids[0];
 
         grid.view.color_mod = if active { Color::WHITE } else { Color::GRAY };
@@ -100,5 +122,7 @@ impl MessageBoxMode {
         for (y, msg) in self.msg.iter().enumerate() {
             grid.print((2, 2 + y as i32), msg);
         }
+
+        ui::scroll_menu_grid(grid, self.scroll_row, Color::WHITE, Color::BLACK);
     }
 }
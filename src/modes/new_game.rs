@@ -0,0 +1,383 @@
+use shipyard::{UniqueView, UniqueViewMut, World};
+
+use crate::{
+    config,
+    experience::{DifficultyPreset, DifficultySettings},
+    gamekey::{self, GameKey},
+    gamesym::GameSym,
+    locale::Locale,
+    ui::{self, Options},
+};
+use ruggrogue::{
+    util::{Color, Size},
+    InputBuffer, InputEvent, KeyMods, TileGrid, Tileset,
+};
+
+use super::{error_dialog::ErrorDialogModeResult, Mode, ModeControl, ModeResult, ModeUpdate};
+
+const TITLE: &str = "new_game.title";
+const START: &str = "new_game.start";
+const CANCEL: &str = "new_game.cancel";
+
+const LABELS: [&str; 6] = [
+    "new_game.difficulty",
+    "new_game.monster_levels",
+    "new_game.spawn_density",
+    "new_game.hunger_rate",
+    "new_game.regen_rate",
+    "new_game.starting_pet",
+];
+
+const PET_DOG: &str = "new_game.pet.dog";
+const PET_NONE: &str = "new_game.pet.none";
+
+/// Width of the widest row label in the current language.
+fn label_width(locale: &Locale) -> usize {
+    LABELS
+        .iter()
+        .map(|l| locale.get(l).chars().count())
+        .max()
+        .unwrap_or(0)
+}
+
+/// Widest value that any row can show in the current language, e.g. "Normal" or "300%".
+fn value_width(locale: &Locale) -> usize {
+    DifficultyPreset::ALL
+        .iter()
+        .map(|p| p.name_key())
+        .chain([PET_DOG, PET_NONE])
+        .map(|key| locale.get(key).chars().count())
+        .fold(4, usize::max)
+}
+
+pub enum NewGameModeResult {
+    AppQuit,
+    Cancelled,
+    Start,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Selection {
+    Preset,
+    MonsterLevels,
+    SpawnDensity,
+    HungerRate,
+    RegenRate,
+    StartingPet,
+    Start,
+    Cancel,
+}
+
+impl Selection {
+    const ROWS: [Selection; 7] = [
+        Selection::Preset,
+        Selection::MonsterLevels,
+        Selection::SpawnDensity,
+        Selection::HungerRate,
+        Selection::RegenRate,
+        Selection::StartingPet,
+        Selection::Start,
+    ];
+
+    /// True for rows that are set by the difficulty preset.
+    fn is_difficulty_value(&self) -> bool {
+        matches!(
+            self,
+            Selection::MonsterLevels
+                | Selection::SpawnDensity
+                | Selection::HungerRate
+                | Selection::RegenRate
+        )
+    }
+
+    fn row(&self) -> usize {
+        match self {
+            Selection::Cancel => Self::ROWS.len() - 1,
+            s => Self::ROWS.iter().position(|r| r == s).unwrap_or(0),
+        }
+    }
+}
+
+pub struct NewGameMode {
+    settings: DifficultySettings,
+    starting_pet: bool,
+    selection: Selection,
+}
+
+fn adjust_rate(rate: u32, forward: bool) -> u32 {
+    let (min, max) = DifficultySettings::RATE_RANGE;
+
+    if forward {
+        (rate + DifficultySettings::RATE_STEP).min(max)
+    } else {
+        rate.saturating_sub(DifficultySettings::RATE_STEP).max(min)
+    }
+}
+
+/// Pick how hard a new game should be before starting it, either from a preset or custom values,
+/// and whether to bring a pet along.
+impl NewGameMode {
+    pub fn new(world: &World) -> Self {
+        let options = world.borrow::<UniqueView<Options>>();
+
+        Self {
+            settings: options.difficulty,
+            starting_pet: options.starting_pet,
+            selection: Selection::Start,
+        }
+    }
+
+    /// Cycle the value of the selected row, switching to custom settings for anything but the
+    /// preset itself.
+    fn adjust(&mut self, forward: bool) {
+        let settings = &mut self.settings;
+
+        match self.selection {
+            Selection::Preset => {
+                let all = DifficultyPreset::ALL;
+                let pos = all.iter().position(|p| *p == settings.preset).unwrap_or(0);
+                let new_pos = if forward {
+                    (pos + 1).min(all.len() - 1)
+                } else {
+                    pos.saturating_sub(1)
+                };
+
+                // Custom settings start from wherever the previous preset left off.
+                if matches!(all[new_pos], DifficultyPreset::Custom) {
+                    settings.preset = DifficultyPreset::Custom;
+                } else {
+                    *settings = DifficultySettings::from_preset(all[new_pos]);
+                }
+                return;
+            }
+            Selection::MonsterLevels => {
+                let (min, max) = DifficultySettings::MONSTER_LEVELS_RANGE;
+                let delta = if forward { 1 } else { -1 };
+
+                settings.monster_levels = (settings.monster_levels + delta).max(min).min(max);
+            }
+            Selection::SpawnDensity => {
+                settings.spawn_density = adjust_rate(settings.spawn_density, forward)
+            }
+            Selection::HungerRate => {
+                settings.hunger_rate = adjust_rate(settings.hunger_rate, forward)
+            }
+            Selection::RegenRate => settings.regen_rate = adjust_rate(settings.regen_rate, forward),
+            Selection::StartingPet => {
+                self.starting_pet = forward;
+                return;
+            }
+            Selection::Start | Selection::Cancel => return,
+        }
+
+        settings.preset = DifficultyPreset::Custom;
+    }
+}
+
+/// Start the game once the chosen settings have been saved for next time, reporting it if they
+/// couldn't be.
+fn start(world: &World) -> (ModeControl, ModeUpdate) {
+    match config::save_options(world) {
+        Ok(()) => (
+            ModeControl::Pop(Box::new(NewGameModeResult::Start)),
+            ModeUpdate::Immediate,
+        ),
+        Err(e) => (ModeControl::Report(e), ModeUpdate::Immediate),
+    }
+}
+
+impl Mode<World, GameSym> for NewGameMode {
+    fn prepare_grids(
+        &mut self,
+        world: &World,
+        grids: &mut Vec<TileGrid<GameSym>>,
+        tilesets: &[Tileset<GameSym>],
+        window_size: Size,
+    ) {
+        let Options {
+            font, text_zoom, ..
+        } = *world.borrow::<UniqueView<Options>>();
+        let locale = world.borrow::<UniqueView<Locale>>();
+        let new_size = Size {
+            w: (label_width(&locale) + value_width(&locale) + 11) as u32,
+            h: LABELS.len() as u32 + 7,
+        };
+
+        if !grids.is_empty() {
+            grids[0].resize(new_size);
+        } else {
+            grids.push(TileGrid::new(new_size, tilesets, font as usize));
+            grids[0].view.clear_color = None;
+        }
+
+        grids[0].set_tileset(tilesets, font as usize);
+        grids[0].view_centered(tilesets, text_zoom, (0, 0).into(), window_size);
+        grids[0].view.zoom = text_zoom;
+    }
+
+    fn update(
+        &mut self,
+        world: &World,
+        inputs: &mut InputBuffer,
+        _grids: &[TileGrid<GameSym>],
+        pop_result: &Option<ModeResult>,
+    ) -> (ModeControl, ModeUpdate) {
+        // The only error reported from here is failing to save the options.
+        if let Some(result) = pop_result
+            .as_ref()
+            .and_then(|result| result.downcast_ref::<ErrorDialogModeResult>())
+        {
+            return match result {
+                ErrorDialogModeResult::AppQuit => (
+                    ModeControl::Pop(Box::new(NewGameModeResult::AppQuit)),
+                    ModeUpdate::Immediate,
+                ),
+                ErrorDialogModeResult::Retry => start(world),
+                ErrorDialogModeResult::Ignore => (
+                    ModeControl::Pop(Box::new(NewGameModeResult::Start)),
+                    ModeUpdate::Immediate,
+                ),
+            };
+        }
+
+        inputs.prepare_input();
+
+        if let Some(InputEvent::AppQuit) = inputs.get_input() {
+            return (
+                ModeControl::Pop(Box::new(NewGameModeResult::AppQuit)),
+                ModeUpdate::Immediate,
+            );
+        } else if let Some(InputEvent::Press(keycode)) = inputs.get_input() {
+            let rows = Selection::ROWS.len();
+
+            match gamekey::from_keycode(keycode, inputs.get_mods(KeyMods::SHIFT)) {
+                GameKey::Up => {
+                    self.selection = Selection::ROWS[(self.selection.row() + rows - 1) % rows];
+                }
+                GameKey::Down => {
+                    self.selection = Selection::ROWS[(self.selection.row() + 1) % rows];
+                }
+                GameKey::Left => match self.selection {
+                    Selection::Cancel => self.selection = Selection::Start,
+                    _ => self.adjust(false),
+                },
+                GameKey::Right => match self.selection {
+                    Selection::Start => self.selection = Selection::Cancel,
+                    _ => self.adjust(true),
+                },
+                GameKey::Confirm => match self.selection {
+                    Selection::Cancel => {
+                        return (
+                            ModeControl::Pop(Box::new(NewGameModeResult::Cancelled)),
+                            ModeUpdate::Immediate,
+                        );
+                    }
+                    _ => {
+                        // Remember the chosen difficulty and pet for next time.
+                        {
+                            let mut options = world.borrow::<UniqueViewMut<Options>>();
+                            options.difficulty = self.settings;
+                            options.starting_pet = self.starting_pet;
+                        }
+                        return start(world);
+                    }
+                },
+                GameKey::Cancel => {
+                    return (
+                        ModeControl::Pop(Box::new(NewGameModeResult::Cancelled)),
+                        ModeUpdate::Immediate,
+                    );
+                }
+                _ => {}
+            }
+        }
+
+        (ModeControl::Stay, ModeUpdate::WaitForEvent)
+    }
+
+    fn draw(&mut self, world: &World, grids: &mut [TileGrid<GameSym>], active: bool) {
+        let grid = &mut grids[0];
+        let locale = world.borrow::<UniqueView<Locale>>();
+        let fg = Color::WHITE;
+        let bg = Color::BLACK;
+        let settings = &self.settings;
+        let label_width = label_width(&locale);
+        let value_width = value_width(&locale);
+        let values = [
+            locale.get(settings.preset.name_key()).to_string(),
+            format!("{:+}", settings.monster_levels),
+            format!("{}%", settings.spawn_density),
+            format!("{}%", settings.hunger_rate),
+            format!("{}%", settings.regen_rate),
+            locale
+                .get(if self.starting_pet { PET_DOG } else { PET_NONE })
+                .to_string(),
+        ];
+
+        grid.view.color_mod = if active { Color::WHITE } else { Color::GRAY };
+
+        grid.draw_box((0, 0), (grid.width(), grid.height()), fg, bg);
+        grid.print_color((2, 0), locale.get(TITLE), true, Color::YELLOW, bg);
+
+        for (i, (label, value)) in LABELS.iter().zip(values.iter()).enumerate() {
+            // Leave gaps around the values set by the preset.
+            let y = match i {
+                0 => 2,
+                _ if Selection::ROWS[i].is_difficulty_value() => 3 + i as i32,
+                _ => 4 + i as i32,
+            };
+            let left_x = 3 + label_width as i32;
+            let value_x = 3 + left_x;
+            let right_x = 1 + value_x + value_width as i32;
+            let selected = self.selection.row() == i;
+
+            let label = locale.get(label);
+
+            grid.print((2 + (label_width - label.chars().count()) as i32, y), label);
+            grid.print_color((left_x, y), "<<", true, fg, bg);
+            grid.print_color(
+                (value_x, y),
+                value,
+                true,
+                if Selection::ROWS[i].is_difficulty_value()
+                    && !matches!(settings.preset, DifficultyPreset::Custom)
+                {
+                    Color::LIGHT_GRAY
+                } else {
+                    fg
+                },
+                if selected { ui::SELECTED_BG } else { bg },
+            );
+            grid.print_color((right_x, y), ">>", true, fg, bg);
+        }
+
+        let start = locale.get(START);
+        let cancel = locale.get(CANCEL);
+        let buttons_y = grid.height() as i32 - 2;
+        let start_x =
+            (grid.width() as i32 - (start.chars().count() + cancel.chars().count() + 2) as i32) / 2;
+
+        grid.print_color(
+            (start_x, buttons_y),
+            start,
+            true,
+            fg,
+            if matches!(self.selection, Selection::Start) {
+                ui::SELECTED_BG
+            } else {
+                bg
+            },
+        );
+        grid.print_color(
+            (start_x + start.chars().count() as i32 + 2, buttons_y),
+            cancel,
+            true,
+            fg,
+            if matches!(self.selection, Selection::Cancel) {
+                ui::SELECTED_BG
+            } else {
+                bg
+            },
+        );
+    }
+}
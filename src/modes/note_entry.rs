@@ -0,0 +1,189 @@
+use sdl2::keyboard::Keycode;
+use shipyard::{UniqueView, UniqueViewMut, World};
+
+use crate::{
+    gamekey::{self, GameKey},
+    gamesym::GameSym,
+    locale::Locale,
+    map::Map,
+    message::{Messages, MsgCategory},
+    notes,
+    ui::{self, Options},
+    TurnCount,
+};
+use ruggrogue::{
+    util::{Color, Size},
+    InputBuffer, InputEvent, KeyMods, TileGrid, Tileset,
+};
+
+use super::{Mode, ModeControl, ModeResult, ModeUpdate};
+
+const NOTE_FIELD_WIDTH: usize = 40;
+const MAX_NOTE_LEN: usize = 200;
+
+pub enum NoteEntryModeResult {
+    AppQuit,
+    Done,
+}
+
+pub struct NoteEntryMode {
+    note: String,
+}
+
+/// A text box for jotting down a note about the current moment of the game, which is appended to
+/// the notes file when confirmed.
+impl NoteEntryMode {
+    pub fn new() -> Self {
+        Self {
+            note: String::new(),
+        }
+    }
+}
+
+impl Mode<World, GameSym> for NoteEntryMode {
+    fn prepare_grids(
+        &mut self,
+        world: &World,
+        grids: &mut Vec<TileGrid<GameSym>>,
+        tilesets: &[Tileset<GameSym>],
+        window_size: Size,
+    ) {
+        let Options {
+            font, text_zoom, ..
+        } = *world.borrow::<UniqueView<Options>>();
+        let hint_width = world
+            .borrow::<UniqueView<Locale>>()
+            .get("note.hint")
+            .chars()
+            .count();
+        let new_grid_size = Size {
+            w: 4 + NOTE_FIELD_WIDTH.max(hint_width) as u32 + 2,
+            h: 9,
+        };
+
+        if !grids.is_empty() {
+            grids[0].resize(new_grid_size);
+        } else {
+            grids.push(TileGrid::new(new_grid_size, tilesets, font as usize));
+            grids[0].view.clear_color = None;
+        }
+
+        grids[0].set_tileset(tilesets, font as usize);
+        grids[0].view_centered(tilesets, text_zoom, (0, 0).into(), window_size);
+        grids[0].view.zoom = text_zoom;
+    }
+
+    fn update(
+        &mut self,
+        world: &World,
+        inputs: &mut InputBuffer,
+        _grids: &[TileGrid<GameSym>],
+        _pop_result: &Option<ModeResult>,
+    ) -> (ModeControl, ModeUpdate) {
+        inputs.prepare_input();
+
+        match inputs.get_input() {
+            Some(InputEvent::AppQuit) => {
+                return (
+                    ModeControl::Pop(Box::new(NoteEntryModeResult::AppQuit)),
+                    ModeUpdate::Immediate,
+                );
+            }
+            Some(InputEvent::Text(c))
+                if !c.is_control() && self.note.chars().count() < MAX_NOTE_LEN =>
+            {
+                self.note.push(c);
+            }
+            Some(InputEvent::Press(Keycode::Backspace)) => {
+                self.note.pop();
+            }
+            Some(InputEvent::Press(keycode)) => {
+                match gamekey::from_keycode(keycode, inputs.get_mods(KeyMods::SHIFT)) {
+                    GameKey::Confirm => {
+                        let note = self.note.trim();
+
+                        if !note.is_empty() {
+                            let locale = world.borrow::<UniqueView<Locale>>();
+                            let mut msgs = world.borrow::<UniqueViewMut<Messages>>();
+
+                            if let Err(e) = notes::append_note(world, note) {
+                                eprintln!("Warning: notes::append_note: {}", e);
+                                msgs.add_as(
+                                    MsgCategory::Warning,
+                                    locale.get("msg.note_failed").into(),
+                                );
+                            } else {
+                                msgs.add(locale.get("msg.note_saved").into());
+                            }
+                        }
+
+                        return (
+                            ModeControl::Pop(Box::new(NoteEntryModeResult::Done)),
+                            ModeUpdate::Immediate,
+                        );
+                    }
+                    GameKey::Cancel => {
+                        return (
+                            ModeControl::Pop(Box::new(NoteEntryModeResult::Done)),
+                            ModeUpdate::Immediate,
+                        );
+                    }
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+
+        (ModeControl::Stay, ModeUpdate::WaitForEvent)
+    }
+
+    fn draw(&mut self, world: &World, grids: &mut [TileGrid<GameSym>], active: bool) {
+        let grid = &mut grids[0];
+        let fg = Color::WHITE;
+        let bg = Color::BLACK;
+        let locale = world.borrow::<UniqueView<Locale>>();
+        let depth = world.borrow::<UniqueView<Map>>().depth;
+        let turn = world.borrow::<UniqueView<TurnCount>>().0;
+        let note_len = self.note.chars().count();
+        let visible_note = self
+            .note
+            .chars()
+            .skip(note_len.saturating_sub(NOTE_FIELD_WIDTH - 1))
+            .collect::<String>();
+
+        grid.view.color_mod = if active { Color::WHITE } else { Color::GRAY };
+
+        grid.draw_box((0, 0), (grid.width(), grid.height()), fg, bg);
+        grid.print_color((2, 0), locale.get("note.title"), true, Color::YELLOW, bg);
+        grid.print_color(
+            (2, 2),
+            &locale.format("note.when", &[("depth", &depth), ("turn", &turn)]),
+            true,
+            Color::GRAY,
+            bg,
+        );
+
+        grid.print_color(
+            (2, 4),
+            &" ".repeat(NOTE_FIELD_WIDTH + 2),
+            true,
+            fg,
+            ui::SELECTED_BG,
+        );
+        grid.print_color((3, 4), &visible_note, true, fg, ui::SELECTED_BG);
+        grid.put_char_color(
+            (3 + visible_note.chars().count() as i32, 4),
+            '_',
+            Color::YELLOW,
+            ui::SELECTED_BG,
+        );
+
+        grid.print_color((2, 6), locale.get("note.hint"), true, Color::GRAY, bg);
+
+        ui::scroll_menu_grid(grid, 4, fg, bg);
+    }
+
+    fn wants_text(&self) -> bool {
+        true
+    }
+}
@@ -1,33 +1,222 @@
 use shipyard::{UniqueView, UniqueViewMut, World};
 
 use crate::{
+    chunked::FogStyle,
+    config,
     gamekey::{self, GameKey},
     gamesym::GameSym,
+    hints,
+    locale::{LanguageList, Locale},
+    message::MsgCategory,
+    spawn,
+    tilesets::TilesetList,
     ui::{self, Options},
 };
 use ruggrogue::{
     util::{Color, Size},
-    InputBuffer, InputEvent, KeyMods, TileGrid, Tileset,
+    Error, InputBuffer, InputEvent, KeyMods, Menu, MenuItem, TileGrid, Tileset,
 };
 
 use super::{
+    error_dialog::ErrorDialogModeResult,
     yes_no_dialog::{YesNoDialogMode, YesNoDialogModeResult},
-    ModeControl, ModeResult, ModeUpdate,
+    Mode, ModeControl, ModeResult, ModeUpdate,
 };
 
-const TILESET_LABEL: &str = "  Tileset:";
-const FONT_LABEL: &str = "     Font:";
-const NUM_FONTS: u32 = 2;
-const TILESET_NAMES: [&str; 3] = ["GohuFont", "Terminal", "Urizen"];
+// Menu text is looked up in the locale catalog using these keys.
+const TITLE: &str = "options.title";
+const TILESET_LABEL: &str = "options.tileset";
+const FONT_LABEL: &str = "options.font";
 const UNKNOWN_TILESET_NAME: &str = "???";
-const MAP_ZOOM_LABEL: &str = " Map zoom:";
-const TEXT_ZOOM_LABEL: &str = "Text zoom:";
+const MAP_ZOOM_LABEL: &str = "options.map_zoom";
+const MAP_ZOOM_FIT: &str = "options.map_zoom.fit";
+const TEXT_ZOOM_LABEL: &str = "options.text_zoom";
 const ZOOM_1X_ON: &str = "[1x]";
 const ZOOM_1X_OFF: &str = " 1x ";
 const ZOOM_2X_ON: &str = "[2x]";
 const ZOOM_2X_OFF: &str = " 2x ";
-const QUIT: &str = "[ Save and exit ]";
-const BACK: &str = "[ Back ]";
+const COLORS_LABEL: &str = "options.colors";
+const COLORS_CHOICES: (&str, &str) = ("options.colors.default", "options.colors.safe");
+const GLYPHS_LABEL: &str = "options.glyphs";
+const GLYPHS_CHOICES: (&str, &str) = ("options.glyphs.default", "options.glyphs.distinct");
+const CONTRAST_LABEL: &str = "options.contrast";
+const CONTRAST_CHOICES: (&str, &str) = ("options.contrast.normal", "options.contrast.high");
+const FOG_LABEL: &str = "options.fog";
+const POPUPS_LABEL: &str = "options.popups";
+const POPUPS_CHOICES: (&str, &str) = ("options.popups.hide", "options.popups.show");
+const TRANSITIONS_LABEL: &str = "options.transitions";
+const TRANSITIONS_CHOICES: (&str, &str) = ("options.transitions.off", "options.transitions.on");
+const AUTOSAVE_LABEL: &str = "options.autosave";
+const AUTOSAVE_OFF: &str = "options.autosave.off";
+const AUTOSAVE_EVERY: &str = "options.autosave.every";
+const FOCUS_LABEL: &str = "options.unfocused";
+const FOCUS_CHOICES: (&str, &str) = ("options.unfocused.keep_going", "options.unfocused.pause");
+const ITEM_TURNS_LABEL: &str = "options.item_turns";
+const ITEM_TURNS_CHOICES: (&str, &str) = ("options.item_turns.once", "options.item_turns.each");
+const DURABILITY_LABEL: &str = "options.durability";
+const DURABILITY_CHOICES: (&str, &str) = ("options.durability.off", "options.durability.on");
+const LANGUAGE_LABEL: &str = "options.language";
+const MSG_DOCK_LABEL: &str = "options.msg_dock";
+const MSG_DOCK_CHOICES: (&str, &str) = ("options.msg_dock.side", "options.msg_dock.bottom");
+const MSG_LINES_LABEL: &str = "options.msg_lines";
+const MSG_LINES_AUTO: &str = "options.msg_lines.auto";
+const TOUCH_LABEL: &str = "options.touch";
+const TOUCH_CHOICES: (&str, &str) = ("options.touch.hide", "options.touch.show");
+const RECORD_LABEL: &str = "options.record";
+const RECORD_CHOICES: (&str, &str) = ("options.record.off", "options.record.on");
+const HINTS_LABEL: &str = "options.hints";
+const HINTS_CHOICES: (&str, &str) = ("options.hints.off", "options.hints.on");
+const RESET_HINTS: &str = "options.reset_hints";
+const RESET_HINTS_DONE: &str = "options.reset_hints.done";
+const MSGS_HEADER: &str = "options.messages";
+const MSGS_CHOICES: (&str, &str) = ("options.messages.hide", "options.messages.show");
+const QUIT: &str = "options.save_and_exit";
+const BACK: &str = "options.back";
+const CONFIRM_QUIT: &str = "options.confirm_quit";
+
+const LABELS: [&str; 20] = [
+    TILESET_LABEL,
+    FONT_LABEL,
+    MAP_ZOOM_LABEL,
+    TEXT_ZOOM_LABEL,
+    COLORS_LABEL,
+    GLYPHS_LABEL,
+    CONTRAST_LABEL,
+    FOG_LABEL,
+    POPUPS_LABEL,
+    TRANSITIONS_LABEL,
+    AUTOSAVE_LABEL,
+    FOCUS_LABEL,
+    ITEM_TURNS_LABEL,
+    DURABILITY_LABEL,
+    LANGUAGE_LABEL,
+    MSG_DOCK_LABEL,
+    MSG_LINES_LABEL,
+    TOUCH_LABEL,
+    RECORD_LABEL,
+    HINTS_LABEL,
+];
+
+/// Width of the widest row label in the current language, so that they can be lined up.
+fn label_width(locale: &Locale) -> usize {
+    LABELS
+        .iter()
+        .map(|key| locale.get(key).chars().count())
+        .chain(
+            MsgCategory::ALL
+                .iter()
+                .map(|c| locale.get(c.name_key()).chars().count() + 1),
+        )
+        .max()
+        .unwrap_or(0)
+}
+
+/// A row label right-aligned to the given width.
+fn label(locale: &Locale, key: &str, width: usize) -> String {
+    format!("{:>1$}", locale.get(key), width)
+}
+
+fn choices<'a>(locale: &'a Locale, keys: (&'a str, &'a str)) -> (&'a str, &'a str) {
+    (locale.get(keys.0), locale.get(keys.1))
+}
+
+fn choices_len(locale: &Locale, keys: (&str, &str)) -> usize {
+    let (a, b) = choices(locale, keys);
+
+    a.chars().count() + b.chars().count()
+}
+
+/// Map zoom choices are "fit" at index 0, followed by each fixed zoom level.
+fn map_zoom_text(locale: &Locale, index: u32) -> String {
+    if index == 0 {
+        locale.get(MAP_ZOOM_FIT).to_string()
+    } else {
+        format!("{}x", index)
+    }
+}
+
+fn map_zoom_value_width(locale: &Locale) -> usize {
+    (0..=ui::MAX_MAP_ZOOM)
+        .map(|i| map_zoom_text(locale, i).chars().count())
+        .max()
+        .unwrap_or(0)
+}
+
+fn map_zoom_index(options: &Options) -> u32 {
+    if options.map_zoom_fit {
+        0
+    } else {
+        options.map_zoom
+    }
+}
+
+fn set_map_zoom_index(options: &mut Options, index: u32) {
+    options.map_zoom_fit = index == 0;
+    if index > 0 {
+        options.map_zoom = index;
+    }
+}
+
+fn autosave_text(locale: &Locale, autosave_turns: u32) -> String {
+    if autosave_turns == 0 {
+        locale.get(AUTOSAVE_OFF).to_string()
+    } else {
+        locale.format(AUTOSAVE_EVERY, &[("turns", &autosave_turns)])
+    }
+}
+
+fn autosave_value_width(locale: &Locale) -> usize {
+    ui::AUTOSAVE_TURNS_CHOICES
+        .iter()
+        .map(|t| autosave_text(locale, *t).chars().count())
+        .max()
+        .unwrap_or(0)
+}
+
+fn autosave_index(autosave_turns: u32) -> usize {
+    ui::AUTOSAVE_TURNS_CHOICES
+        .iter()
+        .position(|t| *t == autosave_turns)
+        .unwrap_or(0)
+}
+
+fn fog_value_width(locale: &Locale) -> usize {
+    FogStyle::ALL
+        .iter()
+        .map(|f| locale.get(f.name_key()).chars().count())
+        .max()
+        .unwrap_or(0)
+}
+
+fn fog_index(fog_style: FogStyle) -> usize {
+    FogStyle::ALL
+        .iter()
+        .position(|f| *f == fog_style)
+        .unwrap_or(0)
+}
+
+fn msg_lines_text(locale: &Locale, msg_lines: u32) -> String {
+    if msg_lines == 0 {
+        locale.get(MSG_LINES_AUTO).to_string()
+    } else {
+        msg_lines.to_string()
+    }
+}
+
+fn msg_lines_value_width(locale: &Locale) -> usize {
+    ui::MSG_LINES_CHOICES
+        .iter()
+        .map(|l| msg_lines_text(locale, *l).chars().count())
+        .max()
+        .unwrap_or(0)
+}
+
+fn msg_lines_index(msg_lines: u32) -> usize {
+    ui::MSG_LINES_CHOICES
+        .iter()
+        .position(|l| *l == msg_lines)
+        .unwrap_or(0)
+}
 
 pub enum OptionsMenuModeResult {
     AppQuit,
@@ -35,17 +224,50 @@ pub enum OptionsMenuModeResult {
     ReallyQuit,
 }
 
+#[derive(Clone, Copy, PartialEq)]
 enum Selection {
     Tileset,
     Font,
     MapZoom,
     TextZoom,
+    Colors,
+    Glyphs,
+    Contrast,
+    Fog,
+    PopUps,
+    Transitions,
+    Autosave,
+    PauseOnFocusLoss,
+    ItemTurns,
+    Durability,
+    Language,
+    MsgDock,
+    MsgLines,
+    TouchControls,
+    RecordSessions,
+    Hints,
+    ResetHints,
+    Messages(MsgCategory),
     Quit,
 }
 
+/// Something that failed and was reported to the player, who can choose to try it again.
+enum Retry {
+    /// Loading the strings of the newly chosen language.
+    Language,
+    /// Writing the profile after forgetting the hints that have been shown.
+    ResetHints,
+    /// Saving the options as the menu closes, which is held off until it's dealt with.
+    Close(ModeControl),
+}
+
 pub struct OptionsMenuMode {
     prompt_to_save: bool,
-    selection: Selection,
+    /// Rows of the menu, labelled with the locale keys of their labels.
+    menu: Menu<Selection>,
+    /// Set once hints have been reset, to let the player know that it worked.
+    hints_reset: bool,
+    retry: Option<Retry>,
 }
 
 /// A menu of general game options that the player can choose amongst.
@@ -53,79 +275,149 @@ impl OptionsMenuMode {
     pub fn new(prompt_to_save: bool) -> Self {
         Self {
             prompt_to_save,
-            selection: Selection::Tileset,
+            menu: Menu::new(
+                [
+                    Selection::Tileset,
+                    Selection::Font,
+                    Selection::MapZoom,
+                    Selection::TextZoom,
+                    Selection::Colors,
+                    Selection::Glyphs,
+                    Selection::Contrast,
+                    Selection::Fog,
+                    Selection::PopUps,
+                    Selection::Transitions,
+                    Selection::Autosave,
+                    Selection::PauseOnFocusLoss,
+                    Selection::ItemTurns,
+                    Selection::Durability,
+                    Selection::Language,
+                    Selection::MsgDock,
+                    Selection::MsgLines,
+                    Selection::TouchControls,
+                    Selection::RecordSessions,
+                    Selection::Hints,
+                ]
+                .iter()
+                .zip(LABELS.iter())
+                .map(|(selection, label)| MenuItem::new(*selection, *label))
+                .chain(std::iter::once(MenuItem::new(
+                    Selection::ResetHints,
+                    RESET_HINTS,
+                )))
+                .chain(
+                    MsgCategory::ALL
+                        .iter()
+                        .map(|c| MenuItem::new(Selection::Messages(*c), c.name_key())),
+                )
+                .collect(),
+            )
+            .with_footer(vec![MenuItem::new(
+                Selection::Quit,
+                if prompt_to_save { QUIT } else { BACK },
+            )]),
+            hints_reset: false,
+            retry: None,
         }
     }
 
-    pub fn prepare_grids(
-        &self,
-        world: &World,
-        grids: &mut Vec<TileGrid<GameSym>>,
-        tilesets: &[Tileset<GameSym>],
-        window_size: Size,
-    ) {
-        let tileset_width = 7
-            + TILESET_LABEL.len()
-            + TILESET_NAMES
-                .iter()
-                .map(|n| n.len())
-                .max()
-                .unwrap_or_else(|| UNKNOWN_TILESET_NAME.len());
-        let font_width = 7
-            + FONT_LABEL.len()
-            + TILESET_NAMES
-                .iter()
-                .take(NUM_FONTS as usize)
-                .map(|n| n.len())
-                .max()
-                .unwrap_or_else(|| UNKNOWN_TILESET_NAME.len());
-        let map_zoom_width = 2 + MAP_ZOOM_LABEL.len() + ZOOM_1X_ON.len() + ZOOM_2X_ON.len();
-        let text_zoom_width = 2 + TEXT_ZOOM_LABEL.len() + ZOOM_1X_ON.len() + ZOOM_2X_ON.len();
-        let new_grid_size = Size {
-            w: 4 + tileset_width
-                .max(font_width)
-                .max(map_zoom_width)
-                .max(text_zoom_width)
-                .max(QUIT.len()) as u32,
-            h: 10,
-        };
-        let Options {
-            font, text_zoom, ..
-        } = *world.borrow::<UniqueView<Options>>();
+    /// The row that the cursor is on.
+    fn selection(&self) -> Selection {
+        self.menu
+            .selected()
+            .map_or(Selection::Quit, |item| item.value)
+    }
 
-        if !grids.is_empty() {
-            grids[0].resize(new_grid_size);
-        } else {
-            grids.push(TileGrid::new(new_grid_size, tilesets, font as usize));
-            grids[0].view.clear_color = None;
+    /// Grid row of the selected entry, leaving room for the header above the message categories
+    /// and a gap above the quit button.
+    fn selected_row(&self) -> i32 {
+        let selection = self.menu.selection() as i32;
+
+        match self.selection() {
+            Selection::Messages(_) => 4 + selection,
+            Selection::Quit => 5 + selection,
+            _ => 2 + selection,
         }
+    }
 
-        grids[0].set_tileset(tilesets, font as usize);
-        grids[0].view_centered(tilesets, text_zoom, (0, 0).into(), window_size);
-        grids[0].view.zoom = text_zoom;
+    /// Switch to the strings of the chosen language, reporting it if any of them couldn't be
+    /// loaded.
+    fn change_language(&mut self, world: &World) -> Option<(ModeControl, ModeUpdate)> {
+        let language_list = world.borrow::<UniqueView<LanguageList>>();
+        let language = world.borrow::<UniqueView<Options>>().language;
+
+        match Locale::try_load(&language_list, language) {
+            Ok(locale) => {
+                *world.borrow::<UniqueViewMut<Locale>>() = locale;
+                spawn::translate_names(world);
+                None
+            }
+            Err(e) => {
+                // Make do with whatever strings could be loaded in the meantime.
+                *world.borrow::<UniqueViewMut<Locale>>() = Locale::load(&language_list, language);
+                spawn::translate_names(world);
+                self.retry = Some(Retry::Language);
+                Some((
+                    ModeControl::Report(Error::new("Failed to change language", e)),
+                    ModeUpdate::Immediate,
+                ))
+            }
+        }
     }
 
-    pub fn update(
+    /// Forget the hints that have been shown, reporting it if that couldn't be saved.
+    fn reset_hints(&mut self, world: &World) -> (ModeControl, ModeUpdate) {
+        match hints::reset(world) {
+            Ok(()) => {
+                self.hints_reset = true;
+                (ModeControl::Stay, ModeUpdate::Immediate)
+            }
+            Err(e) => {
+                self.retry = Some(Retry::ResetHints);
+                (ModeControl::Report(e), ModeUpdate::Immediate)
+            }
+        }
+    }
+
+    /// Save the options as the menu closes, reporting it instead of closing if they couldn't be
+    /// saved.
+    fn close(
+        &mut self,
+        world: &World,
+        control: ModeControl,
+        update: ModeUpdate,
+    ) -> (ModeControl, ModeUpdate) {
+        match config::save_options(world) {
+            Ok(()) => (control, update),
+            Err(e) => {
+                self.retry = Some(Retry::Close(control));
+                (ModeControl::Report(e), ModeUpdate::Immediate)
+            }
+        }
+    }
+
+    fn update_menu(
         &mut self,
         world: &World,
         inputs: &mut InputBuffer,
-        _grids: &[TileGrid<GameSym>],
+        grids: &[TileGrid<GameSym>],
         pop_result: &Option<ModeResult>,
     ) -> (ModeControl, ModeUpdate) {
         if let Some(result) = pop_result {
-            return match result {
-                ModeResult::YesNoDialogModeResult(result) => match result {
+            return if let Some(result) = result.downcast_ref::<YesNoDialogModeResult>() {
+                match result {
                     YesNoDialogModeResult::AppQuit => (
-                        ModeControl::Pop(OptionsMenuModeResult::AppQuit.into()),
+                        ModeControl::Pop(Box::new(OptionsMenuModeResult::AppQuit)),
                         ModeUpdate::Immediate,
                     ),
                     YesNoDialogModeResult::Yes => (
-                        ModeControl::Pop(OptionsMenuModeResult::ReallyQuit.into()),
+                        ModeControl::Pop(Box::new(OptionsMenuModeResult::ReallyQuit)),
                         ModeUpdate::Immediate,
                     ),
                     YesNoDialogModeResult::No => (ModeControl::Stay, ModeUpdate::WaitForEvent),
-                },
-                _ => unreachable!(),
+                }
+            } else {
+                unreachable!()
             };
         }
 
@@ -133,16 +425,26 @@ impl OptionsMenuMode {
 
         if let Some(InputEvent::AppQuit) = inputs.get_input() {
             return (
-                ModeControl::Pop(OptionsMenuModeResult::AppQuit.into()),
+                ModeControl::Pop(Box::new(OptionsMenuModeResult::AppQuit)),
                 ModeUpdate::Immediate,
             );
         } else if let Some(InputEvent::Press(keycode)) = inputs.get_input() {
             let mut options = world.borrow::<UniqueViewMut<Options>>();
+            let tileset_list = world.borrow::<UniqueView<TilesetList>>();
+            let language_list = world.borrow::<UniqueView<LanguageList>>();
             let gkey = gamekey::from_keycode(keycode, inputs.get_mods(KeyMods::SHIFT));
 
-            match (&self.selection, gkey) {
-                (Selection::Tileset, GameKey::Up) => self.selection = Selection::Quit,
-                (Selection::Tileset, GameKey::Down) => self.selection = Selection::Font,
+            if let Some(nav) = gamekey::menu_nav(&gkey) {
+                let page_height = grids.first().map_or(1, |grid| {
+                    let (first_row, last_row) = grid.view_visible_rows();
+                    last_row - first_row + 1
+                });
+
+                self.menu.navigate(nav, page_height);
+                return (ModeControl::Stay, ModeUpdate::WaitForEvent);
+            }
+
+            match (self.selection(), gkey) {
                 (Selection::Tileset, GameKey::Left) => {
                     if options.tileset > 0 {
                         options.tileset -= 1;
@@ -151,45 +453,47 @@ impl OptionsMenuMode {
                     }
                 }
                 (Selection::Tileset, GameKey::Right) => {
-                    if options.tileset as usize + 1 < TILESET_NAMES.len() {
+                    if options.tileset as usize + 1 < tileset_list.len() {
                         options.tileset += 1;
                         inputs.clear_input();
                         return (ModeControl::Stay, ModeUpdate::Immediate);
                     }
                 }
 
-                (Selection::Font, GameKey::Up) => self.selection = Selection::Tileset,
-                (Selection::Font, GameKey::Down) => self.selection = Selection::MapZoom,
                 (Selection::Font, GameKey::Left) => {
-                    if options.font > 0 {
-                        options.font -= 1;
+                    if let Some(prev_font) = tileset_list.prev_font(options.font) {
+                        options.font = prev_font;
                         inputs.clear_input();
                         return (ModeControl::Stay, ModeUpdate::Immediate);
                     }
                 }
                 (Selection::Font, GameKey::Right) => {
-                    if options.font + 1 < NUM_FONTS {
-                        options.font += 1;
+                    if let Some(next_font) = tileset_list.next_font(options.font) {
+                        options.font = next_font;
                         inputs.clear_input();
                         return (ModeControl::Stay, ModeUpdate::Immediate);
                     }
                 }
 
-                (Selection::MapZoom, GameKey::Up) => self.selection = Selection::Font,
-                (Selection::MapZoom, GameKey::Down) => self.selection = Selection::TextZoom,
                 (Selection::MapZoom, GameKey::Left) => {
-                    options.map_zoom = 1;
-                    inputs.clear_input();
-                    return (ModeControl::Stay, ModeUpdate::Immediate);
+                    let index = map_zoom_index(&options);
+
+                    if index > 0 {
+                        set_map_zoom_index(&mut options, index - 1);
+                        inputs.clear_input();
+                        return (ModeControl::Stay, ModeUpdate::Immediate);
+                    }
                 }
                 (Selection::MapZoom, GameKey::Right) => {
-                    options.map_zoom = 2;
-                    inputs.clear_input();
-                    return (ModeControl::Stay, ModeUpdate::Immediate);
+                    let index = map_zoom_index(&options);
+
+                    if index < ui::MAX_MAP_ZOOM {
+                        set_map_zoom_index(&mut options, index + 1);
+                        inputs.clear_input();
+                        return (ModeControl::Stay, ModeUpdate::Immediate);
+                    }
                 }
 
-                (Selection::TextZoom, GameKey::Up) => self.selection = Selection::MapZoom,
-                (Selection::TextZoom, GameKey::Down) => self.selection = Selection::Quit,
                 (Selection::TextZoom, GameKey::Left) => {
                     options.text_zoom = 1;
                     inputs.clear_input();
@@ -201,21 +505,239 @@ impl OptionsMenuMode {
                     return (ModeControl::Stay, ModeUpdate::Immediate);
                 }
 
-                (Selection::Quit, GameKey::Up) => self.selection = Selection::TextZoom,
-                (Selection::Quit, GameKey::Down) => self.selection = Selection::Tileset,
+                (Selection::Colors, GameKey::Left) => {
+                    options.colorblind = false;
+                    inputs.clear_input();
+                    return (ModeControl::Stay, ModeUpdate::Immediate);
+                }
+                (Selection::Colors, GameKey::Right) => {
+                    options.colorblind = true;
+                    inputs.clear_input();
+                    return (ModeControl::Stay, ModeUpdate::Immediate);
+                }
+
+                (Selection::Glyphs, GameKey::Left) => {
+                    options.distinct_glyphs = false;
+                    inputs.clear_input();
+                    return (ModeControl::Stay, ModeUpdate::Immediate);
+                }
+                (Selection::Glyphs, GameKey::Right) => {
+                    options.distinct_glyphs = true;
+                    inputs.clear_input();
+                    return (ModeControl::Stay, ModeUpdate::Immediate);
+                }
+
+                (Selection::Contrast, GameKey::Left) => {
+                    options.high_contrast = false;
+                    inputs.clear_input();
+                    return (ModeControl::Stay, ModeUpdate::Immediate);
+                }
+                (Selection::Contrast, GameKey::Right) => {
+                    options.high_contrast = true;
+                    inputs.clear_input();
+                    return (ModeControl::Stay, ModeUpdate::Immediate);
+                }
+
+                (Selection::Fog, GameKey::Left) => {
+                    let index = fog_index(options.fog_style);
+
+                    if index > 0 {
+                        options.fog_style = FogStyle::ALL[index - 1];
+                        inputs.clear_input();
+                        return (ModeControl::Stay, ModeUpdate::Immediate);
+                    }
+                }
+                (Selection::Fog, GameKey::Right) => {
+                    let index = fog_index(options.fog_style);
+
+                    if index + 1 < FogStyle::ALL.len() {
+                        options.fog_style = FogStyle::ALL[index + 1];
+                        inputs.clear_input();
+                        return (ModeControl::Stay, ModeUpdate::Immediate);
+                    }
+                }
+
+                (Selection::PopUps, GameKey::Left) => {
+                    options.popup_numbers = false;
+                    inputs.clear_input();
+                    return (ModeControl::Stay, ModeUpdate::Immediate);
+                }
+                (Selection::PopUps, GameKey::Right) => {
+                    options.popup_numbers = true;
+                    inputs.clear_input();
+                    return (ModeControl::Stay, ModeUpdate::Immediate);
+                }
+
+                (Selection::Transitions, GameKey::Left) => {
+                    options.transitions = false;
+                    inputs.clear_input();
+                    return (ModeControl::Stay, ModeUpdate::Immediate);
+                }
+                (Selection::Transitions, GameKey::Right) => {
+                    options.transitions = true;
+                    inputs.clear_input();
+                    return (ModeControl::Stay, ModeUpdate::Immediate);
+                }
+
+                (Selection::Autosave, GameKey::Left) => {
+                    let index = autosave_index(options.autosave_turns);
+
+                    if index > 0 {
+                        options.autosave_turns = ui::AUTOSAVE_TURNS_CHOICES[index - 1];
+                        inputs.clear_input();
+                        return (ModeControl::Stay, ModeUpdate::Immediate);
+                    }
+                }
+                (Selection::Autosave, GameKey::Right) => {
+                    let index = autosave_index(options.autosave_turns);
+
+                    if index + 1 < ui::AUTOSAVE_TURNS_CHOICES.len() {
+                        options.autosave_turns = ui::AUTOSAVE_TURNS_CHOICES[index + 1];
+                        inputs.clear_input();
+                        return (ModeControl::Stay, ModeUpdate::Immediate);
+                    }
+                }
+
+                (Selection::PauseOnFocusLoss, GameKey::Left) => {
+                    options.pause_on_focus_loss = false;
+                    inputs.clear_input();
+                    return (ModeControl::Stay, ModeUpdate::Immediate);
+                }
+                (Selection::PauseOnFocusLoss, GameKey::Right) => {
+                    options.pause_on_focus_loss = true;
+                    inputs.clear_input();
+                    return (ModeControl::Stay, ModeUpdate::Immediate);
+                }
+
+                (Selection::ItemTurns, GameKey::Left) => {
+                    options.turn_per_item = false;
+                    inputs.clear_input();
+                    return (ModeControl::Stay, ModeUpdate::Immediate);
+                }
+                (Selection::ItemTurns, GameKey::Right) => {
+                    options.turn_per_item = true;
+                    inputs.clear_input();
+                    return (ModeControl::Stay, ModeUpdate::Immediate);
+                }
+
+                (Selection::Durability, GameKey::Left) => {
+                    options.durability = false;
+                    inputs.clear_input();
+                    return (ModeControl::Stay, ModeUpdate::Immediate);
+                }
+                (Selection::Durability, GameKey::Right) => {
+                    options.durability = true;
+                    inputs.clear_input();
+                    return (ModeControl::Stay, ModeUpdate::Immediate);
+                }
+
+                (Selection::Language, GameKey::Left) => {
+                    if options.language > 0 {
+                        options.language -= 1;
+                        inputs.clear_input();
+                        return (ModeControl::Stay, ModeUpdate::Immediate);
+                    }
+                }
+                (Selection::Language, GameKey::Right) => {
+                    if options.language as usize + 1 < language_list.len() {
+                        options.language += 1;
+                        inputs.clear_input();
+                        return (ModeControl::Stay, ModeUpdate::Immediate);
+                    }
+                }
+
+                (Selection::MsgDock, GameKey::Left) => {
+                    options.msg_dock_bottom = false;
+                    inputs.clear_input();
+                    return (ModeControl::Stay, ModeUpdate::Immediate);
+                }
+                (Selection::MsgDock, GameKey::Right) => {
+                    options.msg_dock_bottom = true;
+                    inputs.clear_input();
+                    return (ModeControl::Stay, ModeUpdate::Immediate);
+                }
+
+                (Selection::MsgLines, GameKey::Left) => {
+                    let index = msg_lines_index(options.msg_lines);
+
+                    if index > 0 {
+                        options.msg_lines = ui::MSG_LINES_CHOICES[index - 1];
+                        inputs.clear_input();
+                        return (ModeControl::Stay, ModeUpdate::Immediate);
+                    }
+                }
+                (Selection::MsgLines, GameKey::Right) => {
+                    let index = msg_lines_index(options.msg_lines);
+
+                    if index + 1 < ui::MSG_LINES_CHOICES.len() {
+                        options.msg_lines = ui::MSG_LINES_CHOICES[index + 1];
+                        inputs.clear_input();
+                        return (ModeControl::Stay, ModeUpdate::Immediate);
+                    }
+                }
+
+                (Selection::TouchControls, GameKey::Left) => {
+                    options.touch_controls = false;
+                    inputs.clear_input();
+                    return (ModeControl::Stay, ModeUpdate::Immediate);
+                }
+                (Selection::TouchControls, GameKey::Right) => {
+                    options.touch_controls = true;
+                    inputs.clear_input();
+                    return (ModeControl::Stay, ModeUpdate::Immediate);
+                }
+
+                (Selection::RecordSessions, GameKey::Left) => {
+                    options.record_sessions = false;
+                    inputs.clear_input();
+                    return (ModeControl::Stay, ModeUpdate::Immediate);
+                }
+                (Selection::RecordSessions, GameKey::Right) => {
+                    options.record_sessions = true;
+                    inputs.clear_input();
+                    return (ModeControl::Stay, ModeUpdate::Immediate);
+                }
+
+                (Selection::Hints, GameKey::Left) => {
+                    options.hints = false;
+                    inputs.clear_input();
+                    return (ModeControl::Stay, ModeUpdate::Immediate);
+                }
+                (Selection::Hints, GameKey::Right) => {
+                    options.hints = true;
+                    inputs.clear_input();
+                    return (ModeControl::Stay, ModeUpdate::Immediate);
+                }
+
+                (Selection::ResetHints, GameKey::Confirm) => {
+                    inputs.clear_input();
+                    return self.reset_hints(world);
+                }
+
+                (Selection::Messages(category), GameKey::Left) => {
+                    options.msg_filter.set(category, false);
+                    inputs.clear_input();
+                    return (ModeControl::Stay, ModeUpdate::Immediate);
+                }
+                (Selection::Messages(category), GameKey::Right) => {
+                    options.msg_filter.set(category, true);
+                    inputs.clear_input();
+                    return (ModeControl::Stay, ModeUpdate::Immediate);
+                }
+
                 (Selection::Quit, GameKey::Confirm) => {
                     inputs.clear_input();
                     return (
                         if self.prompt_to_save {
-                            ModeControl::Push(
-                                YesNoDialogMode::new(
-                                    "Save and return to title screen?".into(),
-                                    false,
-                                )
-                                .into(),
-                            )
+                            ModeControl::Push(Box::new(YesNoDialogMode::new(
+                                world
+                                    .borrow::<UniqueView<Locale>>()
+                                    .get(CONFIRM_QUIT)
+                                    .into(),
+                                false,
+                            )))
                         } else {
-                            ModeControl::Pop(OptionsMenuModeResult::Closed.into())
+                            ModeControl::Pop(Box::new(OptionsMenuModeResult::Closed))
                         },
                         ModeUpdate::Immediate,
                     );
@@ -223,7 +745,7 @@ impl OptionsMenuMode {
 
                 (_, GameKey::Cancel) => {
                     return (
-                        ModeControl::Pop(OptionsMenuModeResult::Closed.into()),
+                        ModeControl::Pop(Box::new(OptionsMenuModeResult::Closed)),
                         ModeUpdate::Immediate,
                     )
                 }
@@ -234,129 +756,73 @@ impl OptionsMenuMode {
         (ModeControl::Stay, ModeUpdate::WaitForEvent)
     }
 
-    fn draw_tileset(
-        &self,
-        world: &World,
+    /// Draw a row with a label and a value that can be cycled through with "<<" and ">>".
+    #[allow(clippy::too_many_arguments)]
+    fn draw_cycle(
         grid: &mut TileGrid<GameSym>,
+        y: i32,
+        label: &str,
+        value: &str,
+        value_width: usize,
+        has_prev: bool,
+        has_next: bool,
+        selected: bool,
         fg: Color,
         bg: Color,
         selected_bg: Color,
     ) {
-        let tileset_left_x = 3 + TILESET_LABEL.len() as i32;
-        let tileset_name_x = 3 + tileset_left_x;
-        let tileset_right_x = 1
-            + tileset_name_x
-            + TILESET_NAMES
-                .iter()
-                .map(|n| n.len())
-                .max()
-                .unwrap_or_else(|| UNKNOWN_TILESET_NAME.len()) as i32;
-        let tileset_y = 2;
-        let tileset = world.borrow::<UniqueView<Options>>().tileset;
+        let left_x = 3 + label.chars().count() as i32;
+        let value_x = 3 + left_x;
+        let right_x = 1 + value_x + value_width as i32;
 
-        grid.print((2, tileset_y), TILESET_LABEL);
-        if tileset > 0 {
-            grid.print_color((tileset_left_x, tileset_y), "<<", true, fg, bg);
+        grid.print((2, y), label);
+        if has_prev {
+            grid.print_color((left_x, y), "<<", true, fg, bg);
         }
         grid.print_color(
-            (tileset_name_x, tileset_y),
-            TILESET_NAMES
-                .get(tileset as usize)
-                .unwrap_or(&UNKNOWN_TILESET_NAME),
+            (value_x, y),
+            value,
             true,
             fg,
-            if matches!(self.selection, Selection::Tileset) {
-                selected_bg
-            } else {
-                bg
-            },
+            if selected { selected_bg } else { bg },
         );
-        if tileset as usize + 1 < TILESET_NAMES.len() {
-            grid.print_color((tileset_right_x, tileset_y), ">>", true, fg, bg);
+        if has_next {
+            grid.print_color((right_x, y), ">>", true, fg, bg);
         }
     }
 
-    fn draw_font(
-        &self,
-        world: &World,
+    #[allow(clippy::too_many_arguments)]
+    fn draw_zoom(
         grid: &mut TileGrid<GameSym>,
+        y: i32,
+        label: &str,
+        zoom: u32,
+        selected: bool,
         fg: Color,
         bg: Color,
         selected_bg: Color,
     ) {
-        let font_left_x = 3 + FONT_LABEL.len() as i32;
-        let font_name_x = 3 + font_left_x;
-        let font_right_x = 1
-            + font_name_x
-            + TILESET_NAMES
-                .iter()
-                .map(|n| n.len())
-                .max()
-                .unwrap_or_else(|| UNKNOWN_TILESET_NAME.len()) as i32;
-        let font_y = 3;
-        let font = world.borrow::<UniqueView<Options>>().font;
+        let zoom_1x_x = 3 + label.chars().count() as i32;
+        let zoom_2x_x = 1 + zoom_1x_x + ZOOM_1X_OFF.len() as i32;
 
-        grid.print((2, font_y), FONT_LABEL);
-        if font > 0 {
-            grid.print_color((font_left_x, font_y), "<<", true, fg, bg);
-        }
+        grid.print((2, y), label);
         grid.print_color(
-            (font_name_x, font_y),
-            TILESET_NAMES
-                .get(font as usize)
-                .unwrap_or(&UNKNOWN_TILESET_NAME),
+            (zoom_1x_x, y),
+            if zoom == 1 { ZOOM_1X_ON } else { ZOOM_1X_OFF },
             true,
             fg,
-            if matches!(self.selection, Selection::Font) {
+            if zoom == 1 && selected {
                 selected_bg
             } else {
                 bg
             },
         );
-        if font + 1 < NUM_FONTS {
-            grid.print_color((font_right_x, font_y), ">>", true, fg, bg);
-        }
-    }
-
-    fn draw_map_zoom(
-        &self,
-        world: &World,
-        grid: &mut TileGrid<GameSym>,
-        fg: Color,
-        bg: Color,
-        selected_bg: Color,
-    ) {
-        let map_zoom_1x_x = 3 + MAP_ZOOM_LABEL.len() as i32;
-        let map_zoom_2x_x = 4 + (MAP_ZOOM_LABEL.len() + ZOOM_1X_OFF.len()) as i32;
-        let map_zoom_y = 4;
-        let map_zoom = world.borrow::<UniqueView<Options>>().map_zoom;
-
-        grid.print((2, map_zoom_y), MAP_ZOOM_LABEL);
         grid.print_color(
-            (map_zoom_1x_x, map_zoom_y),
-            if map_zoom == 1 {
-                ZOOM_1X_ON
-            } else {
-                ZOOM_1X_OFF
-            },
-            true,
-            fg,
-            if map_zoom == 1 && matches!(self.selection, Selection::MapZoom) {
-                selected_bg
-            } else {
-                bg
-            },
-        );
-        grid.print_color(
-            (map_zoom_2x_x, map_zoom_y),
-            if map_zoom == 2 {
-                ZOOM_2X_ON
-            } else {
-                ZOOM_2X_OFF
-            },
+            (zoom_2x_x, y),
+            if zoom == 2 { ZOOM_2X_ON } else { ZOOM_2X_OFF },
             true,
             fg,
-            if map_zoom == 2 && matches!(self.selection, Selection::MapZoom) {
+            if zoom == 2 && selected {
                 selected_bg
             } else {
                 bg
@@ -364,78 +830,535 @@ impl OptionsMenuMode {
         );
     }
 
-    fn draw_text_zoom(
-        &self,
-        world: &World,
+    /// Draw a row with a label and a pair of choices, the second of which is picked if `value` is
+    /// true.
+    #[allow(clippy::too_many_arguments)]
+    fn draw_toggle(
         grid: &mut TileGrid<GameSym>,
+        y: i32,
+        label: &str,
+        choices: (&str, &str),
+        value: bool,
+        selected: bool,
         fg: Color,
         bg: Color,
         selected_bg: Color,
     ) {
-        let text_zoom_1x_x = 3 + TEXT_ZOOM_LABEL.len() as i32;
-        let text_zoom_2x_x = 4 + (TEXT_ZOOM_LABEL.len() + ZOOM_1X_OFF.len()) as i32;
-        let text_zoom_y = 5;
-        let text_zoom = world.borrow::<UniqueView<Options>>().text_zoom;
+        let off_x = 3 + label.chars().count() as i32;
+        let on_x = 4 + (label.chars().count() + choices.0.chars().count()) as i32 + 2;
 
-        grid.print((2, text_zoom_y), TEXT_ZOOM_LABEL);
+        grid.print((2, y), label);
         grid.print_color(
-            (text_zoom_1x_x, text_zoom_y),
-            if text_zoom == 1 {
-                ZOOM_1X_ON
+            (off_x, y),
+            &if value {
+                format!(" {} ", choices.0)
             } else {
-                ZOOM_1X_OFF
+                format!("[{}]", choices.0)
             },
             true,
             fg,
-            if text_zoom == 1 && matches!(self.selection, Selection::TextZoom) {
-                selected_bg
-            } else {
-                bg
-            },
+            if !value && selected { selected_bg } else { bg },
         );
         grid.print_color(
-            (text_zoom_2x_x, text_zoom_y),
-            if text_zoom == 2 {
-                ZOOM_2X_ON
+            (on_x, y),
+            &if value {
+                format!("[{}]", choices.1)
             } else {
-                ZOOM_2X_OFF
+                format!(" {} ", choices.1)
             },
             true,
             fg,
-            if text_zoom == 2 && matches!(self.selection, Selection::TextZoom) {
-                selected_bg
-            } else {
-                bg
-            },
+            if value && selected { selected_bg } else { bg },
         );
     }
+}
+
+impl Mode<World, GameSym> for OptionsMenuMode {
+    fn prepare_grids(
+        &mut self,
+        world: &World,
+        grids: &mut Vec<TileGrid<GameSym>>,
+        tilesets: &[Tileset<GameSym>],
+        window_size: Size,
+    ) {
+        let tileset_list = world.borrow::<UniqueView<TilesetList>>();
+        let language_list = world.borrow::<UniqueView<LanguageList>>();
+        let locale = world.borrow::<UniqueView<Locale>>();
+        let label_width = label_width(&locale);
+        let tileset_width = 7
+            + label_width
+            + tileset_list
+                .0
+                .iter()
+                .map(|e| e.name.chars().count())
+                .max()
+                .unwrap_or_else(|| UNKNOWN_TILESET_NAME.len());
+        let font_width = 7
+            + label_width
+            + tileset_list
+                .0
+                .iter()
+                .filter(|e| e.font)
+                .map(|e| e.name.chars().count())
+                .max()
+                .unwrap_or_else(|| UNKNOWN_TILESET_NAME.len());
+        let map_zoom_width = 7 + label_width + map_zoom_value_width(&locale);
+        let zoom_width = 2 + label_width + ZOOM_1X_ON.len() + ZOOM_2X_ON.len();
+        let colors_width = 6 + label_width + choices_len(&locale, COLORS_CHOICES);
+        let glyphs_width = 6 + label_width + choices_len(&locale, GLYPHS_CHOICES);
+        let contrast_width = 6 + label_width + choices_len(&locale, CONTRAST_CHOICES);
+        let fog_width = 7 + label_width + fog_value_width(&locale);
+        let popups_width = 6 + label_width + choices_len(&locale, POPUPS_CHOICES);
+        let transitions_width = 6 + label_width + choices_len(&locale, TRANSITIONS_CHOICES);
+        let autosave_width = 7 + label_width + autosave_value_width(&locale);
+        let focus_width = 6 + label_width + choices_len(&locale, FOCUS_CHOICES);
+        let item_turns_width = 6 + label_width + choices_len(&locale, ITEM_TURNS_CHOICES);
+        let durability_width = 6 + label_width + choices_len(&locale, DURABILITY_CHOICES);
+        let language_width = 7
+            + label_width
+            + language_list
+                .0
+                .iter()
+                .map(|e| e.name.chars().count())
+                .max()
+                .unwrap_or(0);
+        let msg_dock_width = 6 + label_width + choices_len(&locale, MSG_DOCK_CHOICES);
+        let msg_lines_width = 7 + label_width + msg_lines_value_width(&locale);
+        let touch_width = 6 + label_width + choices_len(&locale, TOUCH_CHOICES);
+        let record_width = 6 + label_width + choices_len(&locale, RECORD_CHOICES);
+        let hints_width = 6 + label_width + choices_len(&locale, HINTS_CHOICES);
+        let msgs_width = 6 + label_width + choices_len(&locale, MSGS_CHOICES);
+        let new_grid_size = Size {
+            w: 4 + tileset_width
+                .max(font_width)
+                .max(map_zoom_width)
+                .max(zoom_width)
+                .max(colors_width)
+                .max(glyphs_width)
+                .max(contrast_width)
+                .max(fog_width)
+                .max(popups_width)
+                .max(transitions_width)
+                .max(autosave_width)
+                .max(focus_width)
+                .max(item_turns_width)
+                .max(durability_width)
+                .max(language_width)
+                .max(msg_dock_width)
+                .max(msg_lines_width)
+                .max(touch_width)
+                .max(record_width)
+                .max(hints_width)
+                .max(locale.get(RESET_HINTS).chars().count())
+                .max(locale.get(RESET_HINTS_DONE).chars().count())
+                .max(msgs_width)
+                .max(locale.get(MSGS_HEADER).chars().count())
+                .max(locale.get(QUIT).chars().count()) as u32,
+            h: 29 + MsgCategory::ALL.len() as u32,
+        };
+        let Options {
+            font, text_zoom, ..
+        } = *world.borrow::<UniqueView<Options>>();
 
-    pub fn draw(&self, world: &World, grids: &mut [TileGrid<GameSym>], active: bool) {
+        if !grids.is_empty() {
+            grids[0].resize(new_grid_size);
+        } else {
+            grids.push(TileGrid::new(new_grid_size, tilesets, font as usize));
+            grids[0].view.clear_color = None;
+        }
+
+        grids[0].set_tileset(tilesets, font as usize);
+        grids[0].view_centered(tilesets, text_zoom, (0, 0).into(), window_size);
+        grids[0].view.zoom = text_zoom;
+    }
+
+    fn update(
+        &mut self,
+        world: &World,
+        inputs: &mut InputBuffer,
+        grids: &[TileGrid<GameSym>],
+        pop_result: &Option<ModeResult>,
+    ) -> (ModeControl, ModeUpdate) {
+        if let Some(result) = pop_result
+            .as_ref()
+            .and_then(|result| result.downcast_ref::<ErrorDialogModeResult>())
+        {
+            return match (result, self.retry.take()) {
+                (ErrorDialogModeResult::AppQuit, _) => (
+                    ModeControl::Pop(Box::new(OptionsMenuModeResult::AppQuit)),
+                    ModeUpdate::Immediate,
+                ),
+                (ErrorDialogModeResult::Retry, Some(Retry::Language)) => self
+                    .change_language(world)
+                    .unwrap_or((ModeControl::Stay, ModeUpdate::WaitForEvent)),
+                (ErrorDialogModeResult::Retry, Some(Retry::ResetHints)) => self.reset_hints(world),
+                (ErrorDialogModeResult::Retry, Some(Retry::Close(control))) => {
+                    self.close(world, control, ModeUpdate::Immediate)
+                }
+                (ErrorDialogModeResult::Ignore, Some(Retry::Close(control))) => {
+                    (control, ModeUpdate::Immediate)
+                }
+                (ErrorDialogModeResult::Retry, None) | (ErrorDialogModeResult::Ignore, _) => {
+                    (ModeControl::Stay, ModeUpdate::WaitForEvent)
+                }
+            };
+        }
+
+        let language = world.borrow::<UniqueView<Options>>().language;
+        let (control, update) = self.update_menu(world, inputs, grids, pop_result);
+        let new_language = world.borrow::<UniqueView<Options>>().language;
+
+        if new_language != language {
+            if let Some(report) = self.change_language(world) {
+                return report;
+            }
+        }
+
+        // Persist options whenever the menu is closed, however it's closed.
+        if matches!(control, ModeControl::Pop(_)) {
+            return self.close(world, control, update);
+        }
+
+        (control, update)
+    }
+
+    fn draw(&mut self, world: &World, grids: &mut [TileGrid<GameSym>], active: bool) {
         let grid = &mut grids[0];
         let fg = Color::WHITE;
         let bg = Color::BLACK;
         let selected_bg = ui::SELECTED_BG;
+        let locale = world.borrow::<UniqueView<Locale>>();
+        let options = world.borrow::<UniqueView<Options>>();
+        let tileset_list = world.borrow::<UniqueView<TilesetList>>();
+        let language_list = world.borrow::<UniqueView<LanguageList>>();
+        let label_width = label_width(&locale);
+
+        let quit_y = 26 + MsgCategory::ALL.len() as i32;
 
         grid.view.color_mod = if active { Color::WHITE } else { Color::GRAY };
 
         grid.draw_box((0, 0), (grid.width(), grid.height()), fg, bg);
-        grid.print_color((2, 0), "< Options >", true, Color::YELLOW, bg);
+        grid.print_color(
+            (2, 0),
+            &format!("< {} >", locale.get(TITLE)),
+            true,
+            Color::YELLOW,
+            bg,
+        );
+
+        Self::draw_cycle(
+            grid,
+            2,
+            &label(&locale, TILESET_LABEL, label_width),
+            tileset_list
+                .name(options.tileset)
+                .unwrap_or(UNKNOWN_TILESET_NAME),
+            tileset_list
+                .0
+                .iter()
+                .map(|e| e.name.chars().count())
+                .max()
+                .unwrap_or_else(|| UNKNOWN_TILESET_NAME.len()),
+            options.tileset > 0,
+            options.tileset as usize + 1 < tileset_list.len(),
+            self.selection() == Selection::Tileset,
+            fg,
+            bg,
+            selected_bg,
+        );
+        Self::draw_cycle(
+            grid,
+            3,
+            &label(&locale, FONT_LABEL, label_width),
+            tileset_list
+                .name(options.font)
+                .unwrap_or(UNKNOWN_TILESET_NAME),
+            tileset_list
+                .0
+                .iter()
+                .filter(|e| e.font)
+                .map(|e| e.name.chars().count())
+                .max()
+                .unwrap_or_else(|| UNKNOWN_TILESET_NAME.len()),
+            tileset_list.prev_font(options.font).is_some(),
+            tileset_list.next_font(options.font).is_some(),
+            self.selection() == Selection::Font,
+            fg,
+            bg,
+            selected_bg,
+        );
+        let map_zoom_index = map_zoom_index(&options);
+
+        Self::draw_cycle(
+            grid,
+            4,
+            &label(&locale, MAP_ZOOM_LABEL, label_width),
+            &map_zoom_text(&locale, map_zoom_index),
+            map_zoom_value_width(&locale),
+            map_zoom_index > 0,
+            map_zoom_index < ui::MAX_MAP_ZOOM,
+            self.selection() == Selection::MapZoom,
+            fg,
+            bg,
+            selected_bg,
+        );
+        Self::draw_zoom(
+            grid,
+            5,
+            &label(&locale, TEXT_ZOOM_LABEL, label_width),
+            options.text_zoom,
+            self.selection() == Selection::TextZoom,
+            fg,
+            bg,
+            selected_bg,
+        );
+        Self::draw_toggle(
+            grid,
+            6,
+            &label(&locale, COLORS_LABEL, label_width),
+            choices(&locale, COLORS_CHOICES),
+            options.colorblind,
+            self.selection() == Selection::Colors,
+            fg,
+            bg,
+            selected_bg,
+        );
+        Self::draw_toggle(
+            grid,
+            7,
+            &label(&locale, GLYPHS_LABEL, label_width),
+            choices(&locale, GLYPHS_CHOICES),
+            options.distinct_glyphs,
+            self.selection() == Selection::Glyphs,
+            fg,
+            bg,
+            selected_bg,
+        );
+        Self::draw_toggle(
+            grid,
+            8,
+            &label(&locale, CONTRAST_LABEL, label_width),
+            choices(&locale, CONTRAST_CHOICES),
+            options.high_contrast,
+            self.selection() == Selection::Contrast,
+            fg,
+            bg,
+            selected_bg,
+        );
 
-        self.draw_tileset(world, grid, fg, bg, selected_bg);
-        self.draw_font(world, grid, fg, bg, selected_bg);
-        self.draw_map_zoom(world, grid, fg, bg, selected_bg);
-        self.draw_text_zoom(world, grid, fg, bg, selected_bg);
+        let fog_index = fog_index(options.fog_style);
+
+        Self::draw_cycle(
+            grid,
+            9,
+            &label(&locale, FOG_LABEL, label_width),
+            locale.get(options.fog_style.name_key()),
+            fog_value_width(&locale),
+            fog_index > 0,
+            fog_index + 1 < FogStyle::ALL.len(),
+            self.selection() == Selection::Fog,
+            fg,
+            bg,
+            selected_bg,
+        );
+        Self::draw_toggle(
+            grid,
+            10,
+            &label(&locale, POPUPS_LABEL, label_width),
+            choices(&locale, POPUPS_CHOICES),
+            options.popup_numbers,
+            self.selection() == Selection::PopUps,
+            fg,
+            bg,
+            selected_bg,
+        );
+
+        Self::draw_toggle(
+            grid,
+            11,
+            &label(&locale, TRANSITIONS_LABEL, label_width),
+            choices(&locale, TRANSITIONS_CHOICES),
+            options.transitions,
+            self.selection() == Selection::Transitions,
+            fg,
+            bg,
+            selected_bg,
+        );
+
+        let autosave_index = autosave_index(options.autosave_turns);
+
+        Self::draw_cycle(
+            grid,
+            12,
+            &label(&locale, AUTOSAVE_LABEL, label_width),
+            &autosave_text(&locale, options.autosave_turns),
+            autosave_value_width(&locale),
+            autosave_index > 0,
+            autosave_index + 1 < ui::AUTOSAVE_TURNS_CHOICES.len(),
+            self.selection() == Selection::Autosave,
+            fg,
+            bg,
+            selected_bg,
+        );
+        Self::draw_toggle(
+            grid,
+            13,
+            &label(&locale, FOCUS_LABEL, label_width),
+            choices(&locale, FOCUS_CHOICES),
+            options.pause_on_focus_loss,
+            self.selection() == Selection::PauseOnFocusLoss,
+            fg,
+            bg,
+            selected_bg,
+        );
+        Self::draw_toggle(
+            grid,
+            14,
+            &label(&locale, ITEM_TURNS_LABEL, label_width),
+            choices(&locale, ITEM_TURNS_CHOICES),
+            options.turn_per_item,
+            self.selection() == Selection::ItemTurns,
+            fg,
+            bg,
+            selected_bg,
+        );
+        Self::draw_toggle(
+            grid,
+            15,
+            &label(&locale, DURABILITY_LABEL, label_width),
+            choices(&locale, DURABILITY_CHOICES),
+            options.durability,
+            self.selection() == Selection::Durability,
+            fg,
+            bg,
+            selected_bg,
+        );
+        Self::draw_cycle(
+            grid,
+            16,
+            &label(&locale, LANGUAGE_LABEL, label_width),
+            language_list.name(options.language).unwrap_or_default(),
+            language_list
+                .0
+                .iter()
+                .map(|e| e.name.chars().count())
+                .max()
+                .unwrap_or(0),
+            options.language > 0,
+            options.language as usize + 1 < language_list.len(),
+            self.selection() == Selection::Language,
+            fg,
+            bg,
+            selected_bg,
+        );
+
+        Self::draw_toggle(
+            grid,
+            17,
+            &label(&locale, MSG_DOCK_LABEL, label_width),
+            choices(&locale, MSG_DOCK_CHOICES),
+            options.msg_dock_bottom,
+            self.selection() == Selection::MsgDock,
+            fg,
+            bg,
+            selected_bg,
+        );
+
+        let msg_lines_index = msg_lines_index(options.msg_lines);
+
+        Self::draw_cycle(
+            grid,
+            18,
+            &label(&locale, MSG_LINES_LABEL, label_width),
+            &msg_lines_text(&locale, options.msg_lines),
+            msg_lines_value_width(&locale),
+            msg_lines_index > 0,
+            msg_lines_index + 1 < ui::MSG_LINES_CHOICES.len(),
+            self.selection() == Selection::MsgLines,
+            fg,
+            bg,
+            selected_bg,
+        );
+
+        Self::draw_toggle(
+            grid,
+            19,
+            &label(&locale, TOUCH_LABEL, label_width),
+            choices(&locale, TOUCH_CHOICES),
+            options.touch_controls,
+            self.selection() == Selection::TouchControls,
+            fg,
+            bg,
+            selected_bg,
+        );
+
+        Self::draw_toggle(
+            grid,
+            20,
+            &label(&locale, RECORD_LABEL, label_width),
+            choices(&locale, RECORD_CHOICES),
+            options.record_sessions,
+            self.selection() == Selection::RecordSessions,
+            fg,
+            bg,
+            selected_bg,
+        );
+
+        Self::draw_toggle(
+            grid,
+            21,
+            &label(&locale, HINTS_LABEL, label_width),
+            choices(&locale, HINTS_CHOICES),
+            options.hints,
+            self.selection() == Selection::Hints,
+            fg,
+            bg,
+            selected_bg,
+        );
+
+        grid.print_color(
+            (2, 22),
+            locale.get(if self.hints_reset {
+                RESET_HINTS_DONE
+            } else {
+                RESET_HINTS
+            }),
+            true,
+            fg,
+            if self.selection() == Selection::ResetHints {
+                selected_bg
+            } else {
+                bg
+            },
+        );
+
+        grid.print_color((2, 24), locale.get(MSGS_HEADER), true, Color::YELLOW, bg);
+        for (i, category) in MsgCategory::ALL.iter().enumerate() {
+            Self::draw_toggle(
+                grid,
+                25 + i as i32,
+                &format!("{:>1$}:", locale.get(category.name_key()), label_width - 1),
+                choices(&locale, MSGS_CHOICES),
+                options.msg_filter.shows(*category),
+                self.selection() == Selection::Messages(*category),
+                fg,
+                bg,
+                selected_bg,
+            );
+        }
 
         grid.print_color(
-            (2, 7),
-            if self.prompt_to_save { QUIT } else { BACK },
+            (2, quit_y),
+            locale.get(if self.prompt_to_save { QUIT } else { BACK }),
             true,
             fg,
-            if matches!(self.selection, Selection::Quit) {
+            if self.menu.in_footer() {
                 selected_bg
             } else {
                 bg
             },
         );
+
+        ui::scroll_menu_grid(grid, self.selected_row(), fg, bg);
     }
 }
@@ -4,28 +4,31 @@ use crate::{
     components::{Coord, Item, Name, Renderable},
     gamekey::{self, GameKey},
     gamesym::GameSym,
-    item::PickUpHint,
+    hints::{self, Hint},
+    locale::Locale,
     map::Map,
     menu_memory::MenuMemory,
-    message::Messages,
+    message::{Messages, MsgCategory},
     player::PlayerId,
+    render,
     ui::{self, Options},
 };
 use ruggrogue::{
     util::{Color, Size},
-    InputBuffer, InputEvent, KeyMods, TileGrid, Tileset,
+    Area, Extent, InputBuffer, InputEvent, KeyMods, Layout, TileGrid, Tileset,
 };
 
-use super::{ModeControl, ModeResult, ModeUpdate};
+use super::{Mode, ModeControl, ModeResult, ModeUpdate};
 
-const TITLE: &str = "< Get Items >";
-const PROMPT: &str = "Pick up which item?";
-const CANCEL: &str = "[ Cancel ]";
+const TITLE: &str = "pick_up.title";
+const PROMPT: &str = "pick_up.prompt";
+const CANCEL: &str = "menu.cancel";
+const MARK_HINT: &str = "menu.mark_hint";
 
 pub enum PickUpMenuModeResult {
     AppQuit,
     Cancelled,
-    PickedItem(EntityId),
+    PickedItems(Vec<EntityId>),
 }
 
 enum SubSection {
@@ -35,12 +38,13 @@ enum SubSection {
 
 pub struct PickUpMenuMode {
     items: Vec<EntityId>,
+    marked: Vec<bool>,
     width: i32,
     subsection: SubSection,
     selection: i32,
 }
 
-/// Show a list of items that player is on top of and let them choose one to pick up.
+/// Show a list of items that player is on top of and let them choose one or more to pick up.
 impl PickUpMenuMode {
     pub fn new(world: &World) -> Self {
         let map = world.borrow::<UniqueView<Map>>();
@@ -49,18 +53,24 @@ impl PickUpMenuMode {
         let coords = world.borrow::<View<Coord>>();
         let items = world.borrow::<View<Item>>();
         let names = world.borrow::<View<Name>>();
+        let locale = world.borrow::<UniqueView<Locale>>();
         let player_coord = coords.get(player_id.0);
         let items = map
             .iter_entities_at(player_coord.0.x, player_coord.0.y)
             .filter(|id| items.contains(*id))
             .collect::<Vec<_>>();
-        let width = TITLE.len().max(PROMPT.len()).max(CANCEL.len()).max(
-            items
-                .iter()
-                .map(|it| names.get(*it).0.len() + 2)
-                .max()
-                .unwrap_or(2),
-        );
+        let width = [TITLE, PROMPT, CANCEL, MARK_HINT]
+            .iter()
+            .map(|key| locale.get(key).chars().count())
+            .max()
+            .unwrap_or(0)
+            .max(
+                items
+                    .iter()
+                    .map(|it| names.get(*it).0.chars().count() + 4)
+                    .max()
+                    .unwrap_or(2),
+            );
         let selection = if items.is_empty() {
             0
         } else if player_coord.0 != menu_memory.pick_up_pos {
@@ -72,6 +82,7 @@ impl PickUpMenuMode {
         };
 
         Self {
+            marked: vec![false; items.len()],
             items,
             width: width as i32,
             subsection: SubSection::Items,
@@ -79,8 +90,29 @@ impl PickUpMenuMode {
         }
     }
 
-    pub fn prepare_grids(
-        &self,
+    /// The height of the item list as an i32 for convenience.
+    fn item_list_height(grid: &TileGrid<GameSym>) -> i32 {
+        grid.height().saturating_sub(9).max(1).min(i32::MAX as u32) as i32
+    }
+
+    /// The marked items, or the item under the cursor if none are marked.
+    fn chosen_items(&self) -> Vec<EntityId> {
+        if self.marked.iter().any(|m| *m) {
+            self.items
+                .iter()
+                .zip(self.marked.iter())
+                .filter(|(_, m)| **m)
+                .map(|(id, _)| *id)
+                .collect()
+        } else {
+            vec![self.items[self.selection as usize]]
+        }
+    }
+}
+
+impl Mode<World, GameSym> for PickUpMenuMode {
+    fn prepare_grids(
+        &mut self,
         world: &World,
         grids: &mut Vec<TileGrid<GameSym>>,
         tilesets: &[Tileset<GameSym>],
@@ -89,32 +121,30 @@ impl PickUpMenuMode {
         let Options {
             font, text_zoom, ..
         } = *world.borrow::<UniqueView<Options>>();
-        let tileset = &tilesets.get(font as usize).unwrap_or(&tilesets[0]);
-        let new_grid_size = Size {
-            w: self.width as u32 + 4,
-            h: (8 + self.items.len() as u32)
-                .min(window_size.h / (tileset.tile_height() * text_zoom))
-                .max(9),
-        };
+        let layout = Layout::new(
+            Extent::Cells(self.width as u32 + 4),
+            Extent::Cells(9 + self.items.len() as u32),
+        )
+        .fitted_height(0)
+        .with_min_size(Size { w: 0, h: 10 });
 
-        if !grids.is_empty() {
-            grids[0].resize(new_grid_size);
-        } else {
-            grids.push(TileGrid::new(new_grid_size, tilesets, font as usize));
+        if grids.is_empty() {
+            // Use a bogus size; the layout will resize the grid below.
+            grids.push(TileGrid::new(Size { w: 1, h: 1 }, tilesets, font as usize));
             grids[0].view.clear_color = None;
+            grids[0].view.bg_alpha = ui::OVERLAY_BG_ALPHA;
         }
 
         grids[0].set_tileset(tilesets, font as usize);
-        grids[0].view_centered(tilesets, text_zoom, (0, 0).into(), window_size);
-        grids[0].view.zoom = text_zoom;
-    }
-
-    /// The height of the item list as an i32 for convenience.
-    fn item_list_height(grid: &TileGrid<GameSym>) -> i32 {
-        grid.height().saturating_sub(8).max(1).min(i32::MAX as u32) as i32
+        layout.apply(
+            &mut grids[0],
+            tilesets,
+            text_zoom,
+            Area::window(window_size),
+        );
     }
 
-    pub fn update(
+    fn update(
         &mut self,
         world: &World,
         inputs: &mut InputBuffer,
@@ -122,12 +152,17 @@ impl PickUpMenuMode {
         _pop_result: &Option<ModeResult>,
     ) -> (ModeControl, ModeUpdate) {
         if self.items.is_empty() {
-            world.run(|mut msgs: UniqueViewMut<Messages>| {
-                msgs.add("There are no items to pick up here.".into());
-            });
+            world.run(
+                |locale: UniqueView<Locale>, mut msgs: UniqueViewMut<Messages>| {
+                    msgs.add_as(
+                        MsgCategory::Warning,
+                        locale.get("msg.no_items_to_pick_up").into(),
+                    );
+                },
+            );
 
             (
-                ModeControl::Pop(PickUpMenuModeResult::Cancelled.into()),
+                ModeControl::Pop(Box::new(PickUpMenuModeResult::Cancelled)),
                 ModeUpdate::Immediate,
             )
         } else {
@@ -135,7 +170,7 @@ impl PickUpMenuMode {
 
             if let Some(InputEvent::AppQuit) = inputs.get_input() {
                 return (
-                    ModeControl::Pop(PickUpMenuModeResult::AppQuit.into()),
+                    ModeControl::Pop(Box::new(PickUpMenuModeResult::AppQuit)),
                     ModeUpdate::Immediate,
                 );
             } else if let Some(InputEvent::Press(keycode)) = inputs.get_input() {
@@ -200,24 +235,37 @@ impl PickUpMenuMode {
                     }
                     GameKey::Cancel => {
                         return (
-                            ModeControl::Pop(PickUpMenuModeResult::Cancelled.into()),
+                            ModeControl::Pop(Box::new(PickUpMenuModeResult::Cancelled)),
                             ModeUpdate::Immediate,
                         )
                     }
+                    GameKey::Wait => {
+                        if matches!(self.subsection, SubSection::Items) {
+                            let marked = &mut self.marked[self.selection as usize];
+
+                            *marked = !*marked;
+                            if self.selection < self.items.len() as i32 - 1 {
+                                self.selection += 1;
+                            }
+                        }
+                    }
+                    GameKey::SelectAll => {
+                        let mark = !self.marked.iter().all(|m| *m);
+
+                        self.marked.iter_mut().for_each(|m| *m = mark);
+                    }
                     GameKey::Confirm | GameKey::PickUp => {
                         let result = match self.subsection {
                             SubSection::Items => {
                                 // Item picked up, so don't repeat the pick up key hint.
-                                world.borrow::<UniqueViewMut<PickUpHint>>().0 = false;
+                                hints::mark_shown(world, Hint::PickUp);
 
-                                PickUpMenuModeResult::PickedItem(
-                                    self.items[self.selection as usize],
-                                )
+                                PickUpMenuModeResult::PickedItems(self.chosen_items())
                             }
                             SubSection::Cancel => PickUpMenuModeResult::Cancelled,
                         };
 
-                        return (ModeControl::Pop(result.into()), ModeUpdate::Immediate);
+                        return (ModeControl::Pop(Box::new(result)), ModeUpdate::Immediate);
                     }
                     _ => {}
                 }
@@ -229,7 +277,7 @@ impl PickUpMenuMode {
         }
     }
 
-    pub fn draw(&self, world: &World, grids: &mut [TileGrid<GameSym>], active: bool) {
+    fn draw(&mut self, world: &World, grids: &mut [TileGrid<GameSym>], active: bool) {
         let grid = &mut grids[0];
         let width = grid.width();
         let height = grid.height();
@@ -240,8 +288,17 @@ impl PickUpMenuMode {
         grid.view.color_mod = if active { Color::WHITE } else { Color::GRAY };
 
         grid.draw_box((0, 0), (width, height), fg, bg);
-        grid.print_color((2, 0), TITLE, true, Color::YELLOW, bg);
-        grid.print((2, 2), PROMPT);
+        let locale = world.borrow::<UniqueView<Locale>>();
+
+        grid.print_color((2, 0), locale.get(TITLE), true, Color::YELLOW, bg);
+        grid.print_color(
+            (2, height as i32 - 1),
+            locale.get(MARK_HINT),
+            true,
+            Color::GRAY,
+            bg,
+        );
+        grid.print((2, 2), locale.get(PROMPT));
 
         let list_height = Self::item_list_height(grid);
         let list_offset = (self.selection - (list_height - 1) / 2)
@@ -261,40 +318,54 @@ impl PickUpMenuMode {
             );
         }
 
-        world.run(|names: View<Name>, renderables: View<Renderable>| {
-            for (i, item_id) in self
-                .items
-                .iter()
-                .enumerate()
-                .skip(list_offset as usize)
-                .take(list_height as usize)
-            {
-                let render = renderables.get(*item_id);
-
-                grid.put_sym_color(
-                    (2, 4 + i as i32 - list_offset),
-                    render.sym,
-                    render.fg,
-                    render.bg,
-                );
+        world.run(
+            |options: UniqueView<Options>, names: View<Name>, renderables: View<Renderable>| {
+                for (i, item_id) in self
+                    .items
+                    .iter()
+                    .enumerate()
+                    .skip(list_offset as usize)
+                    .take(list_height as usize)
+                {
+                    let render = renderables.get(*item_id);
+                    let y = 4 + i as i32 - list_offset;
 
-                grid.print_color(
-                    (4, 4 + i as i32 - list_offset),
-                    &names.get(*item_id).0,
-                    true,
-                    fg,
-                    if matches!(self.subsection, SubSection::Items) && i as i32 == self.selection {
-                        selected_bg
+                    if self.marked[i] {
+                        grid.print_color((2, y), "+", true, Color::GREEN, bg);
                     } else {
-                        bg
-                    },
-                );
+                        grid.print_color((2, y), "-", true, Color::GRAY, bg);
+                    }
+                    render::put_renderable(grid, (4, y), render, &options);
+
+                    grid.print_color(
+                        (6, y),
+                        &names.get(*item_id).0,
+                        true,
+                        fg,
+                        if matches!(self.subsection, SubSection::Items)
+                            && i as i32 == self.selection
+                        {
+                            selected_bg
+                        } else {
+                            bg
+                        },
+                    );
+                }
+            },
+        );
+
+        // Compare highlighted equipment against what's currently equipped.
+        if matches!(self.subsection, SubSection::Items) {
+            if let Some((text, color)) =
+                ui::equip_comparison_text(world, self.items[self.selection as usize])
+            {
+                grid.print_color((2, height as i32 - 5), &text, true, color, bg);
             }
-        });
+        }
 
         grid.print_color(
             (4, height as i32 - 3),
-            CANCEL,
+            locale.get(CANCEL),
             true,
             fg,
             if matches!(self.subsection, SubSection::Cancel) {
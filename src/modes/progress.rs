@@ -0,0 +1,138 @@
+use shipyard::{UniqueView, World};
+
+use crate::{
+    gamesym::GameSym,
+    locale::Locale,
+    saveload::{GameLoader, GameSaver},
+    ui::Options,
+};
+use ruggrogue::{
+    util::{Color, Size},
+    InputBuffer, TileGrid, Tileset,
+};
+
+use super::{Mode, ModeControl, ModeResult, ModeUpdate};
+
+/// Lines of the save file to load each frame.
+const LOAD_LINES_PER_FRAME: usize = 250;
+
+/// Uniques and component storages to save each frame.
+const SAVE_SECTIONS_PER_FRAME: usize = 8;
+
+const BOX_WIDTH: u32 = 40;
+
+pub enum ProgressModeResult {
+    Done,
+    Failed(String),
+}
+
+enum Task {
+    Load(Box<GameLoader>),
+    Save(GameSaver),
+}
+
+pub struct ProgressMode {
+    task: Task,
+}
+
+/// Load or save the game a bit at a time each frame, showing how far along it is.
+///
+/// Any input that arrives in the meantime is left for the modes underneath to deal with once this
+/// mode is done.
+impl ProgressMode {
+    pub fn load(loader: GameLoader) -> Self {
+        Self {
+            task: Task::Load(Box::new(loader)),
+        }
+    }
+
+    pub fn save(saver: GameSaver) -> Self {
+        Self {
+            task: Task::Save(saver),
+        }
+    }
+}
+
+impl Mode<World, GameSym> for ProgressMode {
+    fn prepare_grids(
+        &mut self,
+        world: &World,
+        grids: &mut Vec<TileGrid<GameSym>>,
+        tilesets: &[Tileset<GameSym>],
+        window_size: Size,
+    ) {
+        let Options {
+            font, text_zoom, ..
+        } = *world.borrow::<UniqueView<Options>>();
+        let new_size = Size { w: BOX_WIDTH, h: 5 };
+
+        if !grids.is_empty() {
+            grids[0].resize(new_size);
+        } else {
+            grids.push(TileGrid::new(new_size, tilesets, font as usize));
+            grids[0].view.clear_color = None;
+        }
+
+        grids[0].set_tileset(tilesets, font as usize);
+        grids[0].view_centered(tilesets, text_zoom, (0, 0).into(), window_size);
+        grids[0].view.zoom = text_zoom;
+    }
+
+    fn update(
+        &mut self,
+        world: &World,
+        _inputs: &mut InputBuffer,
+        _grids: &[TileGrid<GameSym>],
+        _pop_result: &Option<ModeResult>,
+    ) -> (ModeControl, ModeUpdate) {
+        let result = match &mut self.task {
+            Task::Load(loader) => loader.load_lines(world, LOAD_LINES_PER_FRAME),
+            Task::Save(saver) => saver.save_sections(world, SAVE_SECTIONS_PER_FRAME),
+        };
+
+        match result {
+            Ok(false) => (ModeControl::Stay, ModeUpdate::Update),
+            Ok(true) => (
+                ModeControl::Pop(Box::new(ProgressModeResult::Done)),
+                ModeUpdate::Immediate,
+            ),
+            Err(e) => (
+                ModeControl::Pop(Box::new(ProgressModeResult::Failed(format!("{}", e)))),
+                ModeUpdate::Immediate,
+            ),
+        }
+    }
+
+    fn draw(&mut self, world: &World, grids: &mut [TileGrid<GameSym>], _active: bool) {
+        let grid = &mut grids[0];
+        let fg = Color::WHITE;
+        let bg = Color::BLACK;
+        let (title, (done, total)) = match &self.task {
+            Task::Load(loader) => ("progress.loading", loader.progress()),
+            Task::Save(saver) => ("progress.saving", saver.progress()),
+        };
+        let title = world.borrow::<UniqueView<Locale>>().get(title).to_string();
+        // Scale down to keep large save files within the range of the bar.
+        let per_mille = (done.min(total) * 1000)
+            .checked_div(total)
+            .map_or(1000, |per_mille| per_mille as i32);
+
+        grid.draw_box((0, 0), (grid.width(), grid.height()), fg, bg);
+        grid.print_color((2, 0), &title, true, Color::YELLOW, bg);
+        grid.draw_bar(
+            false,
+            (2, 2),
+            grid.width() as i32 - 4,
+            0,
+            per_mille,
+            1000,
+            Color::WHITE,
+            None,
+        );
+    }
+
+    /// The game world is only partly loaded while loading, so it shouldn't be drawn.
+    fn draw_behind(&self) -> bool {
+        matches!(self.task, Task::Save(_))
+    }
+}
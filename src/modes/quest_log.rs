@@ -0,0 +1,183 @@
+use shipyard::{UniqueView, World};
+
+use crate::{
+    gamekey::{self, GameKey},
+    gamesym::GameSym,
+    locale::Locale,
+    quest::{Quest, QuestState, Quests},
+    ui::{self, Options},
+};
+use ruggrogue::{
+    util::{Color, Size},
+    InputBuffer, InputEvent, KeyMods, TileGrid, Tileset,
+};
+
+use super::{Mode, ModeControl, ModeResult, ModeUpdate};
+
+const TEXT_WIDTH: usize = 56;
+
+pub enum QuestLogModeResult {
+    AppQuit,
+    Done,
+}
+
+pub struct QuestLogMode {
+    lines: Vec<(String, Color)>,
+    scroll_row: i32,
+}
+
+fn push_quest(lines: &mut Vec<(String, Color)>, locale: &Locale, quest: &Quest) {
+    for (i, line) in ruggrogue::word_wrap(&quest.describe(locale), TEXT_WIDTH - 2).enumerate() {
+        let bullet = if i == 0 { "* " } else { "  " };
+        lines.push((format!("{}{}", bullet, line), Color::WHITE));
+    }
+
+    let status = match quest.state {
+        QuestState::Active => "quest_log.depth",
+        QuestState::Completed => "quest_log.completed",
+        QuestState::Failed => "quest_log.abandoned",
+    };
+    lines.push((
+        format!("  {}", locale.format(status, &[("depth", &quest.depth)])),
+        Color::GRAY,
+    ));
+
+    let reward = match quest.reward {
+        Some(reward) => locale.format(
+            "quest_log.reward_exp_item",
+            &[
+                ("exp", &quest.reward_exp),
+                ("item", &locale.name(reward.name())),
+            ],
+        ),
+        None => locale.format("quest_log.reward_exp", &[("exp", &quest.reward_exp)]),
+    };
+    lines.push((format!("  {}", reward), Color::GRAY));
+}
+
+/// Show the procedural quests of the current game, split into active and finished ones.
+impl QuestLogMode {
+    pub fn new(world: &World) -> Self {
+        let quests = world.borrow::<UniqueView<Quests>>();
+        let locale = world.borrow::<UniqueView<Locale>>();
+        let none = format!("  {}", locale.get("quest_log.none"));
+        let mut lines = Vec::new();
+
+        lines.push((locale.get("quest_log.active").to_string(), Color::YELLOW));
+        if quests.active().next().is_none() {
+            lines.push((none.clone(), Color::GRAY));
+        }
+        for quest in quests.active() {
+            push_quest(&mut lines, &locale, quest);
+        }
+
+        lines.push((String::new(), Color::WHITE));
+
+        lines.push((locale.get("quest_log.finished").to_string(), Color::YELLOW));
+        if quests.finished().next().is_none() {
+            lines.push((none, Color::GRAY));
+        }
+        for quest in quests.finished() {
+            push_quest(&mut lines, &locale, quest);
+        }
+
+        Self {
+            lines,
+            scroll_row: 0,
+        }
+    }
+}
+
+impl Mode<World, GameSym> for QuestLogMode {
+    fn prepare_grids(
+        &mut self,
+        world: &World,
+        grids: &mut Vec<TileGrid<GameSym>>,
+        tilesets: &[Tileset<GameSym>],
+        window_size: Size,
+    ) {
+        let Options {
+            font, text_zoom, ..
+        } = *world.borrow::<UniqueView<Options>>();
+        let new_size = Size {
+            w: TEXT_WIDTH as u32 + 4,
+            h: self.lines.len() as u32 + 4,
+        };
+
+        if !grids.is_empty() {
+            grids[0].resize(new_size);
+        } else {
+            grids.push(TileGrid::new(new_size, tilesets, font as usize));
+            grids[0].view.clear_color = None;
+        }
+
+        grids[0].set_tileset(tilesets, font as usize);
+        grids[0].view_centered(tilesets, text_zoom, (0, 0).into(), window_size);
+        grids[0].view.zoom = text_zoom;
+    }
+
+    fn update(
+        &mut self,
+        _world: &World,
+        inputs: &mut InputBuffer,
+        grids: &[TileGrid<GameSym>],
+        _pop_result: &Option<ModeResult>,
+    ) -> (ModeControl, ModeUpdate) {
+        inputs.prepare_input();
+
+        if let Some(InputEvent::AppQuit) = inputs.get_input() {
+            return (
+                ModeControl::Pop(Box::new(QuestLogModeResult::AppQuit)),
+                ModeUpdate::Immediate,
+            );
+        } else if let Some(InputEvent::Press(keycode)) = inputs.get_input() {
+            let key = gamekey::from_keycode(keycode, inputs.get_mods(KeyMods::SHIFT));
+            if matches!(key, GameKey::Confirm | GameKey::Cancel | GameKey::QuestLog) {
+                inputs.clear_input();
+                return (
+                    ModeControl::Pop(Box::new(QuestLogModeResult::Done)),
+                    ModeUpdate::Immediate,
+                );
+            } else if let Some(grid) = grids.first() {
+                // Scroll a row at a time if the quest log doesn't fit on screen.
+                let (first_row, last_row) = grid.view_visible_rows();
+                let half = (last_row - first_row) / 2;
+                let max_scroll = (grid.height() as i32 - 1 - half).max(half);
+
+                match key {
+                    GameKey::Up => {
+                        self.scroll_row = (self.scroll_row.min(max_scroll) - 1).max(half);
+                    }
+                    GameKey::Down => {
+                        self.scroll_row = (self.scroll_row.max(half) + 1).min(max_scroll);
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        (ModeControl::Stay, ModeUpdate::WaitForEvent)
+    }
+
+    fn draw(&mut self, world: &World, grids: &mut [TileGrid<GameSym>], active: bool) {
+        let grid = &mut grids[0];
+        let bg = Color::BLACK;
+
+        grid.view.color_mod = if active { Color::WHITE } else { Color::GRAY };
+
+        grid.draw_box((0, 0), (grid.width(), grid.height()), Color::WHITE, bg);
+        grid.print_color(
+            (2, 0),
+            world.borrow::<UniqueView<Locale>>().get("quest_log.title"),
+            true,
+            Color::YELLOW,
+            bg,
+        );
+
+        for (y, (line, fg)) in self.lines.iter().enumerate() {
+            grid.print_color((2, 2 + y as i32), line, true, *fg, bg);
+        }
+
+        ui::scroll_menu_grid(grid, self.scroll_row, Color::WHITE, bg);
+    }
+}
@@ -0,0 +1,223 @@
+use shipyard::{UniqueView, World};
+use std::cmp::Reverse;
+
+use crate::{
+    gamekey::{self, GameKey},
+    gamesym::GameSym,
+    locale::Locale,
+    stats::{self, Profile},
+    ui::{self, Options},
+};
+use ruggrogue::{
+    util::{Color, Size},
+    InputBuffer, InputEvent, KeyMods, TileGrid, Tileset,
+};
+
+use super::{Mode, ModeControl, ModeResult, ModeUpdate};
+
+pub enum StatsModeResult {
+    AppQuit,
+    Done,
+}
+
+/// A line of the statistics listing; headers have no value.
+struct Row {
+    label: String,
+    value: Option<String>,
+}
+
+impl Row {
+    fn header(label: &str) -> Self {
+        Self {
+            label: label.to_string(),
+            value: None,
+        }
+    }
+
+    fn stat<T: ToString>(label: &str, value: T) -> Self {
+        Self {
+            label: label.to_string(),
+            value: Some(value.to_string()),
+        }
+    }
+}
+
+pub struct StatsMode {
+    title: String,
+    rows: Vec<Row>,
+    inner_width: u32,
+    scroll_row: i32,
+}
+
+/// Show lifetime statistics gathered across all runs.
+impl StatsMode {
+    pub fn new(world: &World) -> Self {
+        let locale = world.borrow::<UniqueView<Locale>>();
+        let Profile {
+            runs,
+            wins,
+            turns_played,
+            fastest_win,
+            kills,
+            deaths,
+            ..
+        } = stats::current_profile(world);
+        let mut rows = vec![
+            Row::stat(locale.get("stats.runs"), runs),
+            Row::stat(locale.get("stats.wins"), wins),
+            Row::stat(locale.get("stats.turns_played"), turns_played),
+            Row::stat(
+                locale.get("stats.fastest_win"),
+                fastest_win.map_or_else(
+                    || "--".to_string(),
+                    |t| locale.format("stats.turns", &[("turns", &t)]),
+                ),
+            ),
+        ];
+        let mut kills = kills.into_iter().collect::<Vec<_>>();
+        let mut deaths = deaths.into_iter().collect::<Vec<_>>();
+
+        // Most frequent first.
+        kills.sort_by_key(|(_, count)| Reverse(*count));
+        deaths.sort_by_key(|(_, count)| Reverse(*count));
+
+        rows.push(Row::header(""));
+        rows.push(Row::header(locale.get("stats.kills")));
+        if kills.is_empty() {
+            rows.push(Row::stat(
+                &format!("  {}", locale.get("stats.none_yet")),
+                "",
+            ));
+        }
+        for (name, count) in kills {
+            rows.push(Row::stat(&format!("  {}", name), count));
+        }
+
+        rows.push(Row::header(""));
+        rows.push(Row::header(locale.get("stats.deaths")));
+        if deaths.is_empty() {
+            rows.push(Row::stat(
+                &format!("  {}", locale.get("stats.none_yet")),
+                "",
+            ));
+        }
+        for (cause, count) in deaths {
+            rows.push(Row::stat(&format!("  {}", locale.name(&cause)), count));
+        }
+
+        let inner_width = rows
+            .iter()
+            .map(|r| {
+                r.label.chars().count() + r.value.as_ref().map_or(0, |v| 2 + v.chars().count())
+            })
+            .max()
+            .unwrap_or(0);
+        let title = locale.get("stats.title").to_string();
+        let inner_width = inner_width.max(title.chars().count()) as u32;
+
+        Self {
+            title,
+            rows,
+            inner_width,
+            scroll_row: 0,
+        }
+    }
+}
+
+impl Mode<World, GameSym> for StatsMode {
+    fn prepare_grids(
+        &mut self,
+        world: &World,
+        grids: &mut Vec<TileGrid<GameSym>>,
+        tilesets: &[Tileset<GameSym>],
+        window_size: Size,
+    ) {
+        let Options {
+            font, text_zoom, ..
+        } = *world.borrow::<UniqueView<Options>>();
+        let new_size = Size {
+            w: self.inner_width + 4,
+            h: self.rows.len() as u32 + 4,
+        };
+
+        if !grids.is_empty() {
+            grids[0].resize(new_size);
+        } else {
+            grids.push(TileGrid::new(new_size, tilesets, font as usize));
+            grids[0].view.clear_color = None;
+        }
+
+        grids[0].set_tileset(tilesets, font as usize);
+        grids[0].view_centered(tilesets, text_zoom, (0, 0).into(), window_size);
+        grids[0].view.zoom = text_zoom;
+    }
+
+    fn update(
+        &mut self,
+        _world: &World,
+        inputs: &mut InputBuffer,
+        grids: &[TileGrid<GameSym>],
+        _pop_result: &Option<ModeResult>,
+    ) -> (ModeControl, ModeUpdate) {
+        inputs.prepare_input();
+
+        if let Some(InputEvent::AppQuit) = inputs.get_input() {
+            return (
+                ModeControl::Pop(Box::new(StatsModeResult::AppQuit)),
+                ModeUpdate::Immediate,
+            );
+        } else if let Some(InputEvent::Press(keycode)) = inputs.get_input() {
+            let key = gamekey::from_keycode(keycode, inputs.get_mods(KeyMods::SHIFT));
+            if matches!(key, GameKey::Confirm | GameKey::Cancel) {
+                inputs.clear_input();
+                return (
+                    ModeControl::Pop(Box::new(StatsModeResult::Done)),
+                    ModeUpdate::Immediate,
+                );
+            } else if let Some(grid) = grids.first() {
+                // Scroll a row at a time if the listing doesn't fit on screen.
+                let (first_row, last_row) = grid.view_visible_rows();
+                let half = (last_row - first_row) / 2;
+                let max_scroll = (grid.height() as i32 - 1 - half).max(half);
+
+                match key {
+                    GameKey::Up => {
+                        self.scroll_row = (self.scroll_row.min(max_scroll) - 1).max(half);
+                    }
+                    GameKey::Down => {
+                        self.scroll_row = (self.scroll_row.max(half) + 1).min(max_scroll);
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        (ModeControl::Stay, ModeUpdate::WaitForEvent)
+    }
+
+    fn draw(&mut self, _world: &World, grids: &mut [TileGrid<GameSym>], active: bool) {
+        let grid = &mut grids[0];
+        let fg = Color::WHITE;
+        let bg = Color::BLACK;
+
+        grid.view.color_mod = if active { Color::WHITE } else { Color::GRAY };
+
+        grid.draw_box((0, 0), (grid.width(), grid.height()), fg, bg);
+        grid.print_color((2, 0), &self.title, true, Color::YELLOW, bg);
+
+        for (i, row) in self.rows.iter().enumerate() {
+            let y = 2 + i as i32;
+
+            if let Some(value) = &row.value {
+                let value_x = grid.width() as i32 - 2 - value.chars().count() as i32;
+
+                grid.print_color((2, y), &row.label, true, Color::LIGHT_GRAY, bg);
+                grid.print_color((value_x, y), value, true, Color::YELLOW, bg);
+            } else {
+                grid.print_color((2, y), &row.label, true, fg, bg);
+            }
+        }
+
+        ui::scroll_menu_grid(grid, self.scroll_row, fg, bg);
+    }
+}
@@ -6,6 +6,7 @@ use crate::{
     components::{Coord, FieldOfView, Monster},
     gamekey::{self, GameKey},
     gamesym::GameSym,
+    locale::Locale,
     map::Map,
     player::PlayerId,
     render, ui,
@@ -17,7 +18,7 @@ use ruggrogue::{
 
 use super::{
     yes_no_dialog::{YesNoDialogMode, YesNoDialogModeResult},
-    ModeControl, ModeResult, ModeUpdate,
+    Mode, ModeControl, ModeResult, ModeUpdate,
 };
 
 pub enum TargetModeResult {
@@ -89,8 +90,10 @@ impl TargetMode {
             warn_self,
         }
     }
+}
 
-    pub fn prepare_grids(
+impl Mode<World, GameSym> for TargetMode {
+    fn prepare_grids(
         &mut self,
         world: &World,
         grids: &mut Vec<TileGrid<GameSym>>,
@@ -112,33 +115,31 @@ impl TargetMode {
         self.old_msg_frame_size.h = grids[ui::MSG_FRAME_GRID].height();
     }
 
-    pub fn update(
+    fn update(
         &mut self,
-        _world: &World,
+        world: &World,
         inputs: &mut InputBuffer,
         _grids: &[TileGrid<GameSym>],
         pop_result: &Option<ModeResult>,
     ) -> (ModeControl, ModeUpdate) {
         if let Some(result) = pop_result {
-            return match result {
-                ModeResult::YesNoDialogModeResult(result) => match result {
+            return if let Some(result) = result.downcast_ref::<YesNoDialogModeResult>() {
+                match result {
                     YesNoDialogModeResult::AppQuit => (
-                        ModeControl::Pop(TargetModeResult::AppQuit.into()),
+                        ModeControl::Pop(Box::new(TargetModeResult::AppQuit)),
                         ModeUpdate::Immediate,
                     ),
                     YesNoDialogModeResult::Yes => (
-                        ModeControl::Pop(
-                            TargetModeResult::Target {
-                                x: self.cursor.0,
-                                y: self.cursor.1,
-                            }
-                            .into(),
-                        ),
+                        ModeControl::Pop(Box::new(TargetModeResult::Target {
+                            x: self.cursor.0,
+                            y: self.cursor.1,
+                        })),
                         ModeUpdate::Immediate,
                     ),
                     YesNoDialogModeResult::No => (ModeControl::Stay, ModeUpdate::WaitForEvent),
-                },
-                _ => (ModeControl::Stay, ModeUpdate::WaitForEvent),
+                }
+            } else {
+                (ModeControl::Stay, ModeUpdate::WaitForEvent)
             };
         }
 
@@ -146,7 +147,7 @@ impl TargetMode {
 
         if let Some(InputEvent::AppQuit) = inputs.get_input() {
             return (
-                ModeControl::Pop(TargetModeResult::AppQuit.into()),
+                ModeControl::Pop(Box::new(TargetModeResult::AppQuit)),
                 ModeUpdate::Immediate,
             );
         } else if let Some(InputEvent::Press(keycode)) = inputs.get_input() {
@@ -195,7 +196,7 @@ impl TargetMode {
                 }
                 GameKey::Cancel => {
                     return (
-                        ModeControl::Pop(TargetModeResult::Cancelled.into()),
+                        ModeControl::Pop(Box::new(TargetModeResult::Cancelled)),
                         ModeUpdate::Immediate,
                     )
                 }
@@ -205,28 +206,22 @@ impl TargetMode {
                             && dist2(self.cursor, self.center) <= self.radius * (self.radius + 1)
                         {
                             inputs.clear_input();
-                            ModeControl::Push(
-                                YesNoDialogMode::new(
-                                    format!(
-                                        "Really {} yourself?",
-                                        if self.cursor == self.center {
-                                            "target"
-                                        } else {
-                                            "include"
-                                        },
-                                    ),
-                                    false,
-                                )
-                                .into(),
-                            )
+                            ModeControl::Push(Box::new(YesNoDialogMode::new(
+                                world
+                                    .borrow::<UniqueView<Locale>>()
+                                    .get(if self.cursor == self.center {
+                                        "target.confirm_self"
+                                    } else {
+                                        "target.confirm_include_self"
+                                    })
+                                    .to_string(),
+                                false,
+                            )))
                         } else {
-                            ModeControl::Pop(
-                                TargetModeResult::Target {
-                                    x: self.cursor.0,
-                                    y: self.cursor.1,
-                                }
-                                .into(),
-                            )
+                            ModeControl::Pop(Box::new(TargetModeResult::Target {
+                                x: self.cursor.0,
+                                y: self.cursor.1,
+                            }))
                         };
 
                         return (result, ModeUpdate::Immediate);
@@ -250,10 +245,15 @@ impl TargetMode {
             }
         }
 
-        (ModeControl::Stay, ModeUpdate::WaitForEvent)
+        // Keep drawing map chunks that didn't fit in previous frames.
+        if self.chunked_map_grid.has_pending_chunks() {
+            (ModeControl::Stay, ModeUpdate::Update)
+        } else {
+            (ModeControl::Stay, ModeUpdate::WaitForEvent)
+        }
     }
 
-    pub fn draw(&mut self, world: &World, grids: &mut [TileGrid<GameSym>], active: bool) {
+    fn draw(&mut self, world: &World, grids: &mut [TileGrid<GameSym>], active: bool) {
         let (map_grid, grids) = grids.split_first_mut().unwrap(); // ui::MAP_GRID
         let (status_grid, grids) = grids.split_first_mut().unwrap(); // ui::STATUS_GRID
         let (item_grid, grids) = grids.split_first_mut().unwrap(); // ui::ITEM_GRID
@@ -315,6 +315,8 @@ impl TargetMode {
             map_grid.recolor_pos(pos, None, Color::MAGENTA);
         }
 
+        let locale = world.borrow::<UniqueView<Locale>>();
+
         // Describe the location that the cursor is positioned at.
         let cursor_desc = if self.valid.contains(&self.cursor) {
             world
@@ -322,11 +324,11 @@ impl TargetMode {
                 .describe_pos(world, self.cursor.0, self.cursor.1, true, false, false)
                 .0
         } else {
-            "Out of range".to_string()
+            locale.get("target.out_of_range").to_string()
         };
 
         if self.redraw_msg_frame_grid {
-            ui::draw_msg_frame(msg_frame_grid, false);
+            ui::draw_msg_frame(world, msg_frame_grid, false);
         }
 
         msg_grid.clear();
@@ -335,10 +337,14 @@ impl TargetMode {
             status_grid,
             item_grid,
             msg_grid,
-            Some(&format!(
-                "Pick target for {}: {}",
-                self.for_what, cursor_desc
+            Some(&locale.format(
+                "target.prompt",
+                &[("what", &self.for_what), ("where", &cursor_desc)],
             )),
         );
     }
+
+    fn draw_behind(&self) -> bool {
+        false
+    }
 }
@@ -1,36 +1,51 @@
 use shipyard::{AllStoragesViewMut, Get, UniqueView, UniqueViewMut, View, ViewMut, World};
 
 use crate::{
+    attract, bones, chest,
+    chunked::ChunkedMapGrid,
     components::{CombatStats, Experience, FieldOfView},
+    config,
     experience::{self, Difficulty},
     gamekey::{self, GameKey},
     gamesym::GameSym,
-    item::PickUpHint,
+    item,
+    journal::Journal,
+    locale::{LanguageList, Locale},
     map::{self, Map},
     menu_memory::MenuMemory,
     message::Messages,
+    pet,
     player::{self, PlayerAlive, PlayerId},
-    saveload, spawn,
+    quest::{self, Quests},
+    recording::Recorder,
+    render, saveload, spawn,
+    stats::RunStats,
+    tilesets::TilesetList,
+    tutorial::{self, Tutorial},
     ui::{self, Options},
     vision, BaseEquipmentLevel, GameSeed, TurnCount, Wins,
 };
 use ruggrogue::{
-    util::{Color, Size},
-    InputBuffer, InputEvent, KeyMods, TileGrid, Tileset,
+    util::{Color, Position, Size},
+    InputBuffer, InputEvent, KeyMods, Menu, MenuItem, TileGrid, Tileset,
 };
 
 use super::{
     dungeon::DungeonMode,
     message_box::{MessageBoxMode, MessageBoxModeResult},
+    new_game::{NewGameMode, NewGameModeResult},
     options_menu::{OptionsMenuMode, OptionsMenuModeResult},
+    progress::{ProgressMode, ProgressModeResult},
+    stats::{StatsMode, StatsModeResult},
     yes_no_dialog::{YesNoDialogMode, YesNoDialogModeResult},
-    ModeControl, ModeResult, ModeUpdate,
+    Mode, ModeControl, ModeResult, ModeUpdate,
 };
 
-const LOGO_GRID: usize = 0;
-const VERSION_GRID: usize = 1;
-const SOURCE_GRID: usize = 2;
-const MENU_GRID: usize = 3;
+const MAP_GRID: usize = 0;
+const LOGO_GRID: usize = 1;
+const VERSION_GRID: usize = 2;
+const SOURCE_GRID: usize = 3;
+const MENU_GRID: usize = 4;
 
 const SOURCE_STR: &str = "tung.github.io/ruggrogue/";
 const VERSION_STR: &str = "v1.0.1";
@@ -58,25 +73,45 @@ pub enum TitleModeResult {
 pub enum TitleAction {
     NewGame,
     LoadGame,
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+    ExportSave,
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+    ImportSave,
+    Tutorial,
+    Stats,
     Options,
     #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
     Quit,
 }
 
 impl TitleAction {
-    fn label(&self) -> &'static str {
+    fn label_key(&self) -> &'static str {
         match self {
-            TitleAction::NewGame => "New Game",
-            TitleAction::LoadGame => "Load Game",
-            TitleAction::Options => "Options",
-            TitleAction::Quit => "Quit",
+            TitleAction::NewGame => "title.new_game",
+            TitleAction::LoadGame => "title.load_game",
+            TitleAction::ExportSave => "title.export_save",
+            TitleAction::ImportSave => "title.import_save",
+            TitleAction::Tutorial => "title.tutorial",
+            TitleAction::Stats => "title.stats",
+            TitleAction::Options => "title.options",
+            TitleAction::Quit => "title.quit",
         }
     }
+
+    fn menu_item(self, locale: &Locale) -> MenuItem<TitleAction> {
+        let label = locale.get(self.label_key());
+
+        MenuItem::new(self, label)
+    }
 }
 
-const ALL_TITLE_ACTIONS: [TitleAction; 4] = [
+const ALL_TITLE_ACTIONS: [TitleAction; 8] = [
     TitleAction::NewGame,
     TitleAction::LoadGame,
+    TitleAction::ExportSave,
+    TitleAction::ImportSave,
+    TitleAction::Tutorial,
+    TitleAction::Stats,
     TitleAction::Options,
     TitleAction::Quit,
 ];
@@ -88,6 +123,8 @@ fn print_game_seed(game_seed: UniqueView<GameSeed>) {
 pub fn new_game_setup(world: &World, new_game_plus: bool) {
     world.borrow::<UniqueViewMut<MenuMemory>>().reset();
     world.borrow::<UniqueViewMut<Messages>>().reset();
+    world.borrow::<UniqueViewMut<Journal>>().reset();
+    world.borrow::<UniqueViewMut<Quests>>().reset();
     world.borrow::<UniqueViewMut<Map>>().clear();
     world.borrow::<UniqueViewMut<PlayerAlive>>().0 = true;
 
@@ -106,19 +143,26 @@ pub fn new_game_setup(world: &World, new_game_plus: bool) {
 
         let player_id = world.borrow::<UniqueView<PlayerId>>().0;
 
+        // Carry equipment over, but leave consumables behind.
+        let discarded = item::discard_consumables(world, player_id);
+
         // Restore player to full health.
         if let Ok(stats) = (&mut world.borrow::<ViewMut<CombatStats>>()).try_get(player_id) {
             stats.hp = stats.max_hp;
         }
 
-        // Mark field of view as dirty.
+        // The field of view has to be calculated again for the new map.
         if let Ok(fov) = (&mut world.borrow::<ViewMut<FieldOfView>>()).try_get(player_id) {
-            fov.dirty = true;
+            fov.invalidate();
         }
 
-        world
-            .borrow::<UniqueViewMut<Messages>>()
-            .add("Welcome back to RuggRogue!".into());
+        let locale = world.borrow::<UniqueView<Locale>>();
+        let mut msgs = world.borrow::<UniqueViewMut<Messages>>();
+
+        msgs.add(locale.get("title.welcome_back").into());
+        if discarded > 0 {
+            msgs.add(locale.get("title.supplies_left_behind").into());
+        }
     } else {
         world.run(print_game_seed);
 
@@ -136,30 +180,56 @@ pub fn new_game_setup(world: &World, new_game_plus: bool) {
         let new_player_id = world.run(spawn::spawn_player);
         world.borrow::<UniqueViewMut<PlayerId>>().0 = new_player_id;
 
-        // Show the hint for the pick up item key.
-        world.borrow::<UniqueViewMut<PickUpHint>>().0 = true;
+        // Only a New Game Plus carries the storage chest over.
+        chest::despawn_chests(world);
 
-        world
-            .borrow::<UniqueViewMut<Messages>>()
-            .add("Welcome to RuggRogue!".into());
+        let welcome = world
+            .borrow::<UniqueView<Locale>>()
+            .get("title.welcome")
+            .into();
+        world.borrow::<UniqueViewMut<Messages>>().add(welcome);
     }
 
-    // Replace old difficulty tracker with a fresh one.
+    // Start tracking stats for this run.
+    {
+        let turn_count = world.borrow::<UniqueView<TurnCount>>().0;
+        world.borrow::<UniqueViewMut<RunStats>>().reset(turn_count);
+    }
+
+    // Replace old difficulty tracker with a fresh one, keeping difficulty settings in New Game
+    // Plus and picking up the ones chosen for a new game otherwise.
     {
         let difficulty_id = world.borrow::<UniqueView<Difficulty>>().id;
+        let settings = if new_game_plus {
+            world.borrow::<UniqueView<Difficulty>>().settings
+        } else {
+            world.borrow::<UniqueView<Options>>().difficulty
+        };
         spawn::despawn_entity(&mut world.borrow::<AllStoragesViewMut>(), difficulty_id);
-        let new_difficulty = Difficulty::new(world.run(spawn::spawn_difficulty));
+        let mut new_difficulty = Difficulty::new(world.run(spawn::spawn_difficulty));
+        new_difficulty.settings = settings;
         world
             .borrow::<UniqueViewMut<Difficulty>>()
             .replace(new_difficulty);
     }
 
-    if let Some(victory_pos) = world.run(map::generate_rooms_and_corridors) {
+    let remains = if let Some(victory_pos) = world.run(map::generate_rooms_and_corridors) {
         spawn::spawn_present(world, victory_pos);
-    }
+        None
+    } else {
+        bones::take_bones(world)
+    };
     world.run(player::add_coords_to_players);
     world.run(map::place_player_in_first_room);
+    chest::place_chest(world);
+    if world.borrow::<UniqueView<Options>>().starting_pet {
+        pet::spawn_starting_pet(world);
+    }
     spawn::fill_rooms_with_spawns(world);
+    if let Some(remains) = remains {
+        spawn::spawn_remains(world, &remains);
+    }
+    quest::offer_depth_quests(world);
     world.run(experience::calc_exp_for_next_depth);
     world.run(vision::recalculate_fields_of_view);
 
@@ -168,7 +238,10 @@ pub fn new_game_setup(world: &World, new_game_plus: bool) {
 
 pub fn post_game_cleanup(world: &World, reset_seed: bool) {
     world.run(player::remove_coords_from_players);
+    world.run(chest::remove_coords_from_chests);
     world.run(spawn::despawn_coord_entities);
+    world.borrow::<UniqueViewMut<Recorder>>().stop();
+    world.borrow::<UniqueViewMut<Tutorial>>().finish();
 
     if reset_seed {
         // Ensure the next game uses a new seed.
@@ -177,15 +250,15 @@ pub fn post_game_cleanup(world: &World, reset_seed: bool) {
 }
 
 pub struct TitleMode {
-    actions: Vec<TitleAction>,
-    menu_width: u32,
+    chunked_map_grid: ChunkedMapGrid,
+    menu: Menu<TitleAction>,
     menu_height: u32,
-    selection: usize,
+    confirm_import: bool,
 }
 
 /// Show the title screen of the game with a menu that leads into the game proper.
 impl TitleMode {
-    pub fn new() -> Self {
+    pub fn new(world: &World) -> Self {
         let mut actions = vec![TitleAction::NewGame];
 
         // There's no obvious way to get Emscripten to load the IndexedDB filesystem in time to
@@ -195,303 +268,359 @@ impl TitleMode {
             actions.push(TitleAction::LoadGame);
         }
 
+        // Export files can't be reached from outside the browser, so don't offer them there.
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            if saveload::save_file_exists() {
+                actions.push(TitleAction::ExportSave);
+            }
+            actions.push(TitleAction::ImportSave);
+        }
+
+        actions.push(TitleAction::Tutorial);
+        actions.push(TitleAction::Stats);
         actions.push(TitleAction::Options);
 
         #[cfg(not(target_arch = "wasm32"))]
         actions.push(TitleAction::Quit);
 
-        let selection = if saveload::save_file_exists() {
+        let locale = world.borrow::<UniqueView<Locale>>();
+        let mut menu = Menu::new(
             actions
-                .iter()
-                .position(|a| matches!(*a, TitleAction::LoadGame))
-                .unwrap_or(0)
-        } else {
-            0
-        };
+                .into_iter()
+                .map(|action| action.menu_item(&locale))
+                .collect(),
+        );
+
+        if saveload::save_file_exists() {
+            menu.select_where(|a| matches!(*a, TitleAction::LoadGame));
+        }
 
         Self {
-            actions,
-            menu_width: ALL_TITLE_ACTIONS
-                .iter()
-                .map(|a| a.label().len())
-                .max()
-                .unwrap_or(0) as u32,
+            chunked_map_grid: ChunkedMapGrid::new(),
+            menu,
             menu_height: ALL_TITLE_ACTIONS.len() as u32,
-            selection,
+            confirm_import: false,
         }
     }
 
-    pub fn prepare_grids(
-        &self,
-        world: &World,
-        grids: &mut Vec<TileGrid<GameSym>>,
-        tilesets: &[Tileset<GameSym>],
-        window_size: Size,
-    ) {
-        let Options {
-            font, text_zoom, ..
-        } = *world.borrow::<UniqueView<Options>>();
-        let tileset = &tilesets.get(font as usize).unwrap_or(&tilesets[0]);
-
-        let new_logo_size = Size {
-            w: LOGO_STR
-                .lines()
-                .map(str::chars)
-                .map(Iterator::count)
-                .max()
-                .unwrap_or(1) as u32,
-            h: LOGO_STR.chars().filter(|c| *c == '\n').count() as u32,
-        };
-        let new_version_size = Size {
-            w: VERSION_STR.len() as u32,
-            h: 1,
-        };
-        let new_source_size = Size {
-            w: SOURCE_STR.len() as u32,
-            h: 1,
-        };
-        let new_menu_size = Size {
-            w: self.menu_width,
-            h: self.menu_height,
-        };
-
-        if !grids.is_empty() {
-            grids[LOGO_GRID].resize(new_logo_size);
-            grids[VERSION_GRID].resize(new_version_size);
-            grids[SOURCE_GRID].resize(new_source_size);
-            grids[MENU_GRID].resize(new_menu_size);
-        } else {
-            grids.push(TileGrid::new(new_logo_size, tilesets, font as usize));
-            grids.push(TileGrid::new(new_version_size, tilesets, font as usize));
-            grids.push(TileGrid::new(new_source_size, tilesets, font as usize));
-            grids.push(TileGrid::new(new_menu_size, tilesets, font as usize));
-            grids[LOGO_GRID].view.clear_color = None;
-            grids[VERSION_GRID].view.clear_color = None;
-            grids[SOURCE_GRID].view.clear_color = None;
-            grids[MENU_GRID].view.clear_color = None;
-        }
+    /// Show a message box with the given heading and an error wrapped underneath it.
+    fn error_box(heading: &str, e: Box<dyn std::error::Error>) -> (ModeControl, ModeUpdate) {
+        let mut msg = vec![heading.to_string(), "".to_string()];
 
-        let (logo_grid, grids) = grids.split_first_mut().unwrap(); // LOGO_GRID
-        let (version_grid, grids) = grids.split_first_mut().unwrap(); // VERSION_GRID
-        let (source_grid, grids) = grids.split_first_mut().unwrap(); // SOURCE_GRID
-        let (menu_grid, _) = grids.split_first_mut().unwrap(); // MENU_GRID
+        msg.extend(ruggrogue::word_wrap(&format!("{}", e), 78).map(String::from));
 
-        // Set fonts.
-        logo_grid.set_tileset(tilesets, font as usize);
-        version_grid.set_tileset(tilesets, font as usize);
-        source_grid.set_tileset(tilesets, font as usize);
-        menu_grid.set_tileset(tilesets, font as usize);
+        (
+            ModeControl::Push(Box::new(MessageBoxMode::new(msg))),
+            ModeUpdate::Immediate,
+        )
+    }
 
-        let combined_px_height =
-            (new_logo_size.h + new_menu_size.h) * tileset.tile_height() * text_zoom;
+    fn export_save(world: &World) -> (ModeControl, ModeUpdate) {
+        let locale = world.borrow::<UniqueView<Locale>>();
+
+        match saveload::export_save() {
+            Ok(_) => (
+                ModeControl::Push(Box::new(MessageBoxMode::new(vec![locale.format(
+                    "title.exported",
+                    &[("file", &saveload::export_file().display())],
+                )]))),
+                ModeUpdate::Immediate,
+            ),
+            Err(e) => Self::error_box(locale.get("title.export_failed"), e),
+        }
+    }
 
-        // Logo goes in the center top third.
-        logo_grid.view.size.w = new_logo_size.w * tileset.tile_width() * text_zoom;
-        logo_grid.view.size.h = new_logo_size.h * tileset.tile_height() * text_zoom;
-        logo_grid.view.pos.x = (window_size.w - logo_grid.view.size.w) as i32 / 2;
-        logo_grid.view.pos.y = (window_size.h.saturating_sub(combined_px_height) / 3) as i32;
+    fn import_save(&mut self, world: &World) -> (ModeControl, ModeUpdate) {
+        if let Err(e) = saveload::import_save() {
+            let locale = world.borrow::<UniqueView<Locale>>();
 
-        // Menu goes in the left-center bottom third.
-        menu_grid.view.size.w = new_menu_size.w * tileset.tile_width() * text_zoom;
-        menu_grid.view.size.h = new_menu_size.h * tileset.tile_height() * text_zoom;
-        menu_grid.view.pos.x = (window_size.w / 2 - menu_grid.view.size.w) as i32;
-        menu_grid.view.pos.y =
-            (logo_grid.view.size.h + window_size.h.saturating_sub(combined_px_height) * 2 / 3)
-                .min(window_size.h.saturating_sub(menu_grid.view.size.h)) as i32;
+            return Self::error_box(locale.get("title.import_failed"), e);
+        }
 
-        // Version goes in the bottom left corner of the screen.
-        version_grid.view.size.w = new_version_size.w * tileset.tile_width() * text_zoom;
-        version_grid.view.size.h = new_version_size.h * tileset.tile_height() * text_zoom;
-        version_grid.view.pos.x = 0;
-        version_grid.view.pos.y = (window_size.h - version_grid.view.size.h) as i32;
+        // Pick up any options that came with the save.
+        {
+            let tileset_list = world.borrow::<UniqueView<TilesetList>>();
+            let language_list = world.borrow::<UniqueView<LanguageList>>();
+            let mut options = world.borrow::<UniqueViewMut<Options>>();
+            *options = config::load_options(&tileset_list, &language_list, *options);
+            *world.borrow::<UniqueViewMut<Locale>>() =
+                Locale::load(&language_list, options.language);
+        }
 
-        // Source goes in the bottom right corner of the screen.
-        source_grid.view.size.w = new_source_size.w * tileset.tile_width() * text_zoom;
-        source_grid.view.size.h = new_source_size.h * tileset.tile_height() * text_zoom;
-        source_grid.view.pos.x = (window_size.w - source_grid.view.size.w) as i32;
-        source_grid.view.pos.y = (window_size.h - source_grid.view.size.h) as i32;
+        // Offer to load or export the imported save.
+        let locale = world.borrow::<UniqueView<Locale>>();
 
-        // Set all grids to current text zoom.
-        logo_grid.view.zoom = text_zoom;
-        version_grid.view.zoom = text_zoom;
-        source_grid.view.zoom = text_zoom;
-        menu_grid.view.zoom = text_zoom;
+        if !self
+            .menu
+            .items()
+            .iter()
+            .any(|item| matches!(item.value, TitleAction::LoadGame))
+        {
+            self.menu
+                .insert_item(1, TitleAction::LoadGame.menu_item(&locale));
+        }
+        if !self
+            .menu
+            .items()
+            .iter()
+            .any(|item| matches!(item.value, TitleAction::ExportSave))
+        {
+            self.menu
+                .insert_item(2, TitleAction::ExportSave.menu_item(&locale));
+        }
+        self.menu.select(1);
+
+        (
+            ModeControl::Push(Box::new(MessageBoxMode::new(vec![locale.format(
+                "title.imported",
+                &[("file", &saveload::export_file().display())],
+            )]))),
+            ModeUpdate::Immediate,
+        )
     }
 
-    pub fn update(
+    fn update_menu(
         &mut self,
         world: &World,
         inputs: &mut InputBuffer,
-        _grids: &[TileGrid<GameSym>],
         pop_result: &Option<ModeResult>,
     ) -> (ModeControl, ModeUpdate) {
         if let Some(result) = pop_result {
-            return match result {
-                ModeResult::MessageBoxModeResult(result) => match result {
+            return if let Some(result) = result.downcast_ref::<MessageBoxModeResult>() {
+                match result {
                     MessageBoxModeResult::AppQuit => (
-                        ModeControl::Pop(TitleModeResult::AppQuit.into()),
+                        ModeControl::Pop(Box::new(TitleModeResult::AppQuit)),
                         ModeUpdate::Immediate,
                     ),
                     MessageBoxModeResult::Done => (ModeControl::Stay, ModeUpdate::WaitForEvent),
-                },
-                ModeResult::OptionsMenuModeResult(result) => match result {
+                }
+            } else if let Some(result) = result.downcast_ref::<ProgressModeResult>() {
+                match result {
+                    ProgressModeResult::Done => {
+                        world.run(print_game_seed);
+
+                        inputs.clear_input();
+                        (
+                            ModeControl::Switch(Box::new(DungeonMode::new())),
+                            ModeUpdate::Immediate,
+                        )
+                    }
+                    ProgressModeResult::Failed(e) => Self::error_box(
+                        world
+                            .borrow::<UniqueView<Locale>>()
+                            .get("title.load_failed"),
+                        e.clone().into(),
+                    ),
+                }
+            } else if let Some(result) = result.downcast_ref::<NewGameModeResult>() {
+                match result {
+                    NewGameModeResult::AppQuit => (
+                        ModeControl::Pop(Box::new(TitleModeResult::AppQuit)),
+                        ModeUpdate::Immediate,
+                    ),
+                    NewGameModeResult::Cancelled => (ModeControl::Stay, ModeUpdate::WaitForEvent),
+                    NewGameModeResult::Start => {
+                        attract::stop(world);
+                        new_game_setup(world, false);
+                        inputs.clear_input();
+                        (
+                            ModeControl::Switch(Box::new(DungeonMode::new())),
+                            ModeUpdate::Immediate,
+                        )
+                    }
+                }
+            } else if let Some(result) = result.downcast_ref::<StatsModeResult>() {
+                match result {
+                    StatsModeResult::AppQuit => (
+                        ModeControl::Pop(Box::new(TitleModeResult::AppQuit)),
+                        ModeUpdate::Immediate,
+                    ),
+                    StatsModeResult::Done => (ModeControl::Stay, ModeUpdate::WaitForEvent),
+                }
+            } else if let Some(result) = result.downcast_ref::<OptionsMenuModeResult>() {
+                match result {
                     OptionsMenuModeResult::AppQuit => (
-                        ModeControl::Pop(TitleModeResult::AppQuit.into()),
+                        ModeControl::Pop(Box::new(TitleModeResult::AppQuit)),
                         ModeUpdate::Immediate,
                     ),
-                    OptionsMenuModeResult::Closed => (ModeControl::Stay, ModeUpdate::WaitForEvent),
+                    OptionsMenuModeResult::Closed => {
+                        // The language may have been changed in the options menu.
+                        let locale = world.borrow::<UniqueView<Locale>>();
+                        self.menu
+                            .relabel(|action| locale.get(action.label_key()).to_string());
+                        (ModeControl::Stay, ModeUpdate::WaitForEvent)
+                    }
                     OptionsMenuModeResult::ReallyQuit => unreachable!(),
-                },
-                ModeResult::YesNoDialogModeResult(result) => match result {
+                }
+            } else if let Some(result) = result.downcast_ref::<YesNoDialogModeResult>() {
+                match result {
                     YesNoDialogModeResult::AppQuit => (
-                        ModeControl::Pop(TitleModeResult::AppQuit.into()),
+                        ModeControl::Pop(Box::new(TitleModeResult::AppQuit)),
                         ModeUpdate::Immediate,
                     ),
+                    YesNoDialogModeResult::Yes if self.confirm_import => {
+                        self.confirm_import = false;
+                        inputs.clear_input();
+                        self.import_save(world)
+                    }
                     YesNoDialogModeResult::Yes => {
                         saveload::delete_save_file();
 
-                        // Remove the load game option.
-                        self.actions
-                            .retain(|a| !matches!(*a, TitleAction::LoadGame));
-
-                        // Adjust selection if needed.
-                        if let Some(pos) = self
-                            .actions
-                            .iter()
-                            .position(|a| matches!(*a, TitleAction::NewGame))
-                        {
-                            self.selection = pos;
-                        } else {
-                            self.selection =
-                                self.selection.min(self.actions.len().saturating_sub(1));
-                        }
+                        // Remove the load game and export save options.
+                        self.menu.retain_items(|item| {
+                            !matches!(item.value, TitleAction::LoadGame | TitleAction::ExportSave)
+                        });
+                        self.menu
+                            .select_where(|a| matches!(*a, TitleAction::NewGame));
 
                         inputs.clear_input();
                         (ModeControl::Stay, ModeUpdate::Immediate)
                     }
-                    YesNoDialogModeResult::No => (ModeControl::Stay, ModeUpdate::WaitForEvent),
-                },
-                _ => unreachable!(),
+                    YesNoDialogModeResult::No => {
+                        self.confirm_import = false;
+                        (ModeControl::Stay, ModeUpdate::WaitForEvent)
+                    }
+                }
+            } else {
+                unreachable!()
             };
         }
 
         inputs.prepare_input();
 
+        let locale = world.borrow::<UniqueView<Locale>>();
+
         match inputs.get_input() {
             Some(InputEvent::AppQuit) => {
                 return (
-                    ModeControl::Pop(TitleModeResult::AppQuit.into()),
+                    ModeControl::Pop(Box::new(TitleModeResult::AppQuit)),
                     ModeUpdate::Immediate,
                 );
             }
 
             Some(InputEvent::Press(keycode)) => {
-                match gamekey::from_keycode(keycode, inputs.get_mods(KeyMods::SHIFT)) {
-                    GameKey::Up => {
-                        if self.selection > 0 {
-                            self.selection -= 1;
-                        } else {
-                            self.selection = self.actions.len().saturating_sub(1);
-                        }
-                    }
-                    GameKey::Down => {
-                        if self.selection < self.actions.len().saturating_sub(1) {
-                            self.selection += 1;
-                        } else {
-                            self.selection = 0;
+                let key = gamekey::from_keycode(keycode, inputs.get_mods(KeyMods::SHIFT));
+
+                match key {
+                    GameKey::Up | GameKey::Down => {
+                        if let Some(nav) = gamekey::menu_nav(&key) {
+                            self.menu.navigate(nav, self.menu_height as i32);
                         }
                     }
                     GameKey::Cancel => {
-                        if let Some(quit_pos) = self
-                            .actions
-                            .iter()
-                            .position(|a| matches!(*a, TitleAction::Quit))
-                        {
-                            self.selection = quit_pos;
-                        }
+                        self.menu.select_where(|a| matches!(*a, TitleAction::Quit));
                     }
                     GameKey::Confirm => {
-                        assert!(self.selection < self.actions.len());
+                        let action = match self.menu.selected() {
+                            Some(item) => &item.value,
+                            None => unreachable!(),
+                        };
 
-                        match self.actions[self.selection] {
+                        match action {
                             TitleAction::NewGame => {
                                 if saveload::save_file_exists() {
                                     inputs.clear_input();
                                     return (
-                                        ModeControl::Push(
-                                            YesNoDialogMode::new(
-                                                "Save data already exists.  Delete it?".into(),
-                                                false,
-                                            )
-                                            .into(),
-                                        ),
+                                        ModeControl::Push(Box::new(YesNoDialogMode::new(
+                                            locale.get("title.confirm_delete").into(),
+                                            false,
+                                        ))),
                                         ModeUpdate::Immediate,
                                     );
                                 } else {
-                                    new_game_setup(world, false);
                                     inputs.clear_input();
                                     return (
-                                        ModeControl::Switch(DungeonMode::new().into()),
+                                        ModeControl::Push(Box::new(NewGameMode::new(world))),
                                         ModeUpdate::Immediate,
                                     );
                                 }
                             }
                             TitleAction::LoadGame => {
                                 if saveload::save_file_exists() {
-                                    match saveload::load_game(world) {
-                                        Ok(_) => {
-                                            world.run(print_game_seed);
-
-                                            // Don't show pick up key hint to returning players.
-                                            world.borrow::<UniqueViewMut<PickUpHint>>().0 = false;
-
-                                            inputs.clear_input();
+                                    attract::stop(world);
+                                    inputs.clear_input();
+                                    match saveload::GameLoader::new() {
+                                        Ok(loader) => {
                                             return (
-                                                ModeControl::Switch(DungeonMode::new().into()),
+                                                ModeControl::Push(Box::new(ProgressMode::load(
+                                                    loader,
+                                                ))),
                                                 ModeUpdate::Immediate,
                                             );
                                         }
                                         Err(e) => {
-                                            let mut msg = vec![
-                                                "Failed to load game:".to_string(),
-                                                "".to_string(),
-                                            ];
-
-                                            msg.extend(
-                                                ruggrogue::word_wrap(&format!("{}", e), 78)
-                                                    .map(String::from),
-                                            );
-
-                                            inputs.clear_input();
-                                            return (
-                                                ModeControl::Push(MessageBoxMode::new(msg).into()),
-                                                ModeUpdate::Immediate,
+                                            return Self::error_box(
+                                                locale.get("title.load_failed"),
+                                                e,
                                             );
                                         }
                                     }
                                 } else {
                                     inputs.clear_input();
                                     return (
-                                        ModeControl::Push(
-                                            MessageBoxMode::new(vec![
-                                                "No save file found.".to_string()
-                                            ])
-                                            .into(),
-                                        ),
+                                        ModeControl::Push(Box::new(MessageBoxMode::new(vec![
+                                            locale.get("title.no_save").to_string(),
+                                        ]))),
+                                        ModeUpdate::Immediate,
+                                    );
+                                }
+                            }
+                            TitleAction::ExportSave => {
+                                inputs.clear_input();
+                                return Self::export_save(world);
+                            }
+                            TitleAction::ImportSave => {
+                                inputs.clear_input();
+                                if !saveload::export_file().exists() {
+                                    return (
+                                        ModeControl::Push(Box::new(MessageBoxMode::new(vec![
+                                            locale.format(
+                                                "title.nothing_to_import",
+                                                &[("file", &saveload::export_file().display())],
+                                            ),
+                                        ]))),
+                                        ModeUpdate::Immediate,
+                                    );
+                                } else if saveload::save_file_exists() {
+                                    self.confirm_import = true;
+                                    return (
+                                        ModeControl::Push(Box::new(YesNoDialogMode::new(
+                                            locale.get("title.confirm_replace").into(),
+                                            false,
+                                        ))),
                                         ModeUpdate::Immediate,
                                     );
+                                } else {
+                                    return self.import_save(world);
                                 }
                             }
+                            TitleAction::Tutorial => {
+                                attract::stop(world);
+                                tutorial::setup(world);
+                                inputs.clear_input();
+                                return (
+                                    ModeControl::Switch(Box::new(DungeonMode::new())),
+                                    ModeUpdate::Immediate,
+                                );
+                            }
+                            TitleAction::Stats => {
+                                inputs.clear_input();
+                                return (
+                                    ModeControl::Push(Box::new(StatsMode::new(world))),
+                                    ModeUpdate::Immediate,
+                                );
+                            }
                             TitleAction::Options => {
                                 inputs.clear_input();
                                 return (
-                                    ModeControl::Push(OptionsMenuMode::new(false).into()),
+                                    ModeControl::Push(Box::new(OptionsMenuMode::new(false))),
                                     ModeUpdate::Immediate,
                                 );
                             }
                             TitleAction::Quit => {
                                 return (
-                                    ModeControl::Pop(TitleModeResult::AppQuit.into()),
+                                    ModeControl::Pop(Box::new(TitleModeResult::AppQuit)),
                                     ModeUpdate::Immediate,
                                 );
                             }
@@ -506,8 +635,156 @@ impl TitleMode {
 
         (ModeControl::Stay, ModeUpdate::WaitForEvent)
     }
+}
 
-    pub fn draw(&self, _world: &World, grids: &mut [TileGrid<GameSym>], active: bool) {
+impl Mode<World, GameSym> for TitleMode {
+    fn prepare_grids(
+        &mut self,
+        world: &World,
+        grids: &mut Vec<TileGrid<GameSym>>,
+        tilesets: &[Tileset<GameSym>],
+        window_size: Size,
+    ) {
+        let Options {
+            font, text_zoom, ..
+        } = *world.borrow::<UniqueView<Options>>();
+        let tileset = &tilesets.get(font as usize).unwrap_or(&tilesets[0]);
+
+        let new_logo_size = Size {
+            w: LOGO_STR
+                .lines()
+                .map(str::chars)
+                .map(Iterator::count)
+                .max()
+                .unwrap_or(1) as u32,
+            h: LOGO_STR.chars().filter(|c| *c == '\n').count() as u32,
+        };
+        let new_version_size = Size {
+            w: VERSION_STR.len() as u32,
+            h: 1,
+        };
+        let new_source_size = Size {
+            w: SOURCE_STR.len() as u32,
+            h: 1,
+        };
+        let locale = world.borrow::<UniqueView<Locale>>();
+        let new_menu_size = Size {
+            w: ALL_TITLE_ACTIONS
+                .iter()
+                .map(|a| locale.get(a.label_key()).chars().count())
+                .max()
+                .unwrap_or(0) as u32,
+            h: self.menu_height,
+        };
+
+        if !grids.is_empty() {
+            // MAP_GRID resizing is handled by ChunkedMapGrid::prepare_grid below.
+            grids[LOGO_GRID].resize(new_logo_size);
+            grids[VERSION_GRID].resize(new_version_size);
+            grids[SOURCE_GRID].resize(new_source_size);
+            grids[MENU_GRID].resize(new_menu_size);
+        } else {
+            // Use a bogus size for MAP_GRID; ChunkedMapGrid::prepare_grid will resize it below.
+            grids.push(TileGrid::new(Size { w: 1, h: 1 }, tilesets, 0));
+            grids.push(TileGrid::new(new_logo_size, tilesets, font as usize));
+            grids.push(TileGrid::new(new_version_size, tilesets, font as usize));
+            grids.push(TileGrid::new(new_source_size, tilesets, font as usize));
+            grids.push(TileGrid::new(new_menu_size, tilesets, font as usize));
+            grids[LOGO_GRID].view.clear_color = None;
+            grids[VERSION_GRID].view.clear_color = None;
+            grids[SOURCE_GRID].view.clear_color = None;
+            grids[MENU_GRID].view.clear_color = None;
+        }
+
+        // The wandering bot's level fills the whole screen behind everything else.
+        self.chunked_map_grid.prepare_grid(
+            world,
+            &mut grids[MAP_GRID],
+            tilesets,
+            Position { x: 0, y: 0 },
+            window_size,
+        );
+
+        let (_, grids) = grids.split_first_mut().unwrap(); // MAP_GRID
+        let (logo_grid, grids) = grids.split_first_mut().unwrap(); // LOGO_GRID
+        let (version_grid, grids) = grids.split_first_mut().unwrap(); // VERSION_GRID
+        let (source_grid, grids) = grids.split_first_mut().unwrap(); // SOURCE_GRID
+        let (menu_grid, _) = grids.split_first_mut().unwrap(); // MENU_GRID
+
+        // Set fonts.
+        logo_grid.set_tileset(tilesets, font as usize);
+        version_grid.set_tileset(tilesets, font as usize);
+        source_grid.set_tileset(tilesets, font as usize);
+        menu_grid.set_tileset(tilesets, font as usize);
+
+        let combined_px_height =
+            (new_logo_size.h + new_menu_size.h) * tileset.tile_height() * text_zoom;
+
+        // Logo goes in the center top third.
+        logo_grid.view.size.w = new_logo_size.w * tileset.tile_width() * text_zoom;
+        logo_grid.view.size.h = new_logo_size.h * tileset.tile_height() * text_zoom;
+        logo_grid.view.pos.x = (window_size.w - logo_grid.view.size.w) as i32 / 2;
+        logo_grid.view.pos.y = (window_size.h.saturating_sub(combined_px_height) / 3) as i32;
+
+        // Menu goes in the left-center bottom third.
+        menu_grid.view.size.w = new_menu_size.w * tileset.tile_width() * text_zoom;
+        menu_grid.view.size.h = new_menu_size.h * tileset.tile_height() * text_zoom;
+        menu_grid.view.pos.x = (window_size.w / 2 - menu_grid.view.size.w) as i32;
+        menu_grid.view.pos.y =
+            (logo_grid.view.size.h + window_size.h.saturating_sub(combined_px_height) * 2 / 3)
+                .min(window_size.h.saturating_sub(menu_grid.view.size.h)) as i32;
+
+        // Version goes in the bottom left corner of the screen.
+        version_grid.view.size.w = new_version_size.w * tileset.tile_width() * text_zoom;
+        version_grid.view.size.h = new_version_size.h * tileset.tile_height() * text_zoom;
+        version_grid.view.pos.x = 0;
+        version_grid.view.pos.y = (window_size.h - version_grid.view.size.h) as i32;
+
+        // Source goes in the bottom right corner of the screen.
+        source_grid.view.size.w = new_source_size.w * tileset.tile_width() * text_zoom;
+        source_grid.view.size.h = new_source_size.h * tileset.tile_height() * text_zoom;
+        source_grid.view.pos.x = (window_size.w - source_grid.view.size.w) as i32;
+        source_grid.view.pos.y = (window_size.h - source_grid.view.size.h) as i32;
+
+        // Set all grids to current text zoom.
+        logo_grid.view.zoom = text_zoom;
+        version_grid.view.zoom = text_zoom;
+        source_grid.view.zoom = text_zoom;
+        menu_grid.view.zoom = text_zoom;
+    }
+
+    fn update(
+        &mut self,
+        world: &World,
+        inputs: &mut InputBuffer,
+        _grids: &[TileGrid<GameSym>],
+        pop_result: &Option<ModeResult>,
+    ) -> (ModeControl, ModeUpdate) {
+        let loaded = pop_result.as_ref().is_some_and(|result| {
+            matches!(
+                result.downcast_ref::<ProgressModeResult>(),
+                Some(ProgressModeResult::Done)
+            )
+        });
+
+        // Keep the bot wandering behind the menu, unless a game was just loaded to replace it.
+        if !loaded {
+            attract::start(world);
+            if attract::tick(world) {
+                self.chunked_map_grid.mark_all_dirty();
+            }
+        }
+
+        match self.update_menu(world, inputs, pop_result) {
+            (ModeControl::Stay, ModeUpdate::WaitForEvent) => {
+                (ModeControl::Stay, ModeUpdate::Update)
+            }
+            result => result,
+        }
+    }
+
+    fn draw(&mut self, world: &World, grids: &mut [TileGrid<GameSym>], active: bool) {
+        let (map_grid, grids) = grids.split_first_mut().unwrap(); // MAP_GRID
         let (logo_grid, grids) = grids.split_first_mut().unwrap(); // LOGO_GRID
         let (version_grid, grids) = grids.split_first_mut().unwrap(); // VERSION_GRID
         let (source_grid, grids) = grids.split_first_mut().unwrap(); // SOURCE_GRID
@@ -517,17 +794,22 @@ impl TitleMode {
         let selected_bg = ui::SELECTED_BG;
 
         if active {
+            map_grid.view.color_mod = Color::GRAY;
             logo_grid.view.color_mod = Color::WHITE;
             version_grid.view.color_mod = Color::WHITE;
             source_grid.view.color_mod = Color::WHITE;
             menu_grid.view.color_mod = Color::WHITE;
         } else {
+            map_grid.view.color_mod = Color::GRAY.blend(Color::BLACK, 128);
             logo_grid.view.color_mod = Color::GRAY;
             version_grid.view.color_mod = Color::GRAY;
             source_grid.view.color_mod = Color::GRAY;
             menu_grid.view.color_mod = Color::GRAY;
         }
 
+        self.chunked_map_grid.draw(world, map_grid);
+        render::draw_renderables(&self.chunked_map_grid, world, map_grid);
+
         for (i, logo_line) in LOGO_STR.lines().enumerate() {
             logo_grid.print_color((0, i as i32), logo_line, true, Color::ORANGE, bg);
         }
@@ -537,14 +819,17 @@ impl TitleMode {
         source_grid.print_color((0, 0), SOURCE_STR, true, Color::GRAY, bg);
 
         menu_grid.clear();
-        for (i, action) in self.actions.iter().enumerate() {
-            menu_grid.print_color(
-                (0, i as i32),
-                action.label(),
-                true,
-                fg,
-                if i == self.selection { selected_bg } else { bg },
-            );
-        }
+        self.menu.draw_labels(
+            menu_grid,
+            Position { x: 0, y: 0 },
+            self.menu.items().len() as i32,
+            fg,
+            bg,
+            selected_bg,
+        );
+    }
+
+    fn draw_behind(&self) -> bool {
+        false
     }
 }
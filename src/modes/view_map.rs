@@ -1,26 +1,45 @@
-use shipyard::{Get, UniqueView, UniqueViewMut, View, World};
+use shipyard::{Get, IntoIter, UniqueView, UniqueViewMut, View, World};
 
 use crate::{
-    chunked::{Camera, ChunkedMapGrid},
-    components::Coord,
+    chunked::{Camera, ChunkedMapGrid, FogStyle},
+    components::{Coord, FieldOfView, Item, Monster},
     gamekey::{self, GameKey},
     gamesym::GameSym,
-    map::Map,
+    locale::Locale,
+    map::{Map, Tile},
+    message::{Messages, MsgCategory},
     player::PlayerId,
-    render, ui,
+    render,
+    ui::{self, Options},
 };
 use ruggrogue::{
     util::{Color, Position, Size},
-    InputBuffer, InputEvent, KeyMods, TileGrid, Tileset,
+    Anchor, Area, Extent, InputBuffer, InputEvent, KeyMods, Layout, TileGrid, Tileset,
 };
 
-use super::{ModeControl, ModeResult, ModeUpdate};
+use super::{
+    look_list::{LookListMode, LookListModeResult},
+    Mode, ModeControl, ModeResult, ModeUpdate,
+};
 
 const SHIFT_STEP: i32 = 5;
 
+const OVERVIEW_GRID: usize = ui::MSG_GRID + 1;
+const LEGEND_GRID: usize = ui::MSG_GRID + 2;
+
+/// What the colors of the overview stand for, besides the map tiles themselves.
+const LEGEND: [(&str, Color); 5] = [
+    ("overview.you", Color::WHITE),
+    ("overview.monsters", Color::RED),
+    ("overview.items", Color::CYAN),
+    ("overview.stairs", Color::YELLOW),
+    ("overview.cursor", Color::MAGENTA),
+];
+
 pub enum ViewMapModeResult {
     AppQuit,
     Done,
+    TravelTo((i32, i32)),
 }
 
 pub struct ViewMapMode {
@@ -29,6 +48,12 @@ pub struct ViewMapMode {
     redraw_msg_frame_grid: bool,
     center: Position,
     range: i32,
+    /// Show the list of everything in view as soon as the mode starts.
+    open_look_list: bool,
+    /// Leave view mode altogether if the list shown at the start is cancelled.
+    exit_on_list_cancel: bool,
+    /// Show the whole level at once instead of the map around the cursor.
+    overview: bool,
 }
 
 fn reset_camera(
@@ -48,10 +73,34 @@ impl ViewMapMode {
             redraw_msg_frame_grid: true,
             center: world.borrow::<UniqueView<Camera>>().0,
             range: 80,
+            open_look_list: false,
+            exit_on_list_cancel: false,
+            overview: false,
         }
     }
 
-    pub fn prepare_grids(
+    /// Start with the list of everything in view, so the cursor can be sent straight to one.
+    pub fn with_look_list(world: &World) -> Self {
+        Self {
+            open_look_list: true,
+            exit_on_list_cancel: true,
+            ..Self::new(world)
+        }
+    }
+
+    /// Move the cursor to a map position, redrawing where it was.
+    fn move_cursor_to(&mut self, world: &World, pos: Position) {
+        let mut camera = world.borrow::<UniqueViewMut<Camera>>();
+
+        self.chunked_map_grid
+            .mark_dirty(camera.0, Size { w: 1, h: 1 });
+        camera.0 = pos;
+        self.chunked_map_grid.mark_dirty(pos, Size { w: 1, h: 1 });
+    }
+}
+
+impl Mode<World, GameSym> for ViewMapMode {
+    fn prepare_grids(
         &mut self,
         world: &World,
         grids: &mut Vec<TileGrid<GameSym>>,
@@ -71,22 +120,60 @@ impl ViewMapMode {
             || grids[ui::MSG_FRAME_GRID].height() != self.old_msg_frame_size.h;
         self.old_msg_frame_size.w = grids[ui::MSG_FRAME_GRID].width();
         self.old_msg_frame_size.h = grids[ui::MSG_FRAME_GRID].height();
+
+        prepare_overview_grids(world, grids, tilesets);
     }
 
-    pub fn update(
+    fn update(
         &mut self,
         world: &World,
         inputs: &mut InputBuffer,
         _grids: &[TileGrid<GameSym>],
-        _pop_result: &Option<ModeResult>,
+        pop_result: &Option<ModeResult>,
     ) -> (ModeControl, ModeUpdate) {
+        if let Some(result) = pop_result
+            .as_ref()
+            .and_then(|result| result.downcast_ref::<LookListModeResult>())
+        {
+            match result {
+                LookListModeResult::AppQuit => {
+                    world.run(reset_camera);
+                    return (
+                        ModeControl::Pop(Box::new(ViewMapModeResult::AppQuit)),
+                        ModeUpdate::Immediate,
+                    );
+                }
+                LookListModeResult::Cancelled => {
+                    if self.exit_on_list_cancel {
+                        world.run(reset_camera);
+                        return (
+                            ModeControl::Pop(Box::new(ViewMapModeResult::Done)),
+                            ModeUpdate::Immediate,
+                        );
+                    }
+                }
+                LookListModeResult::Selected(pos) => self.move_cursor_to(world, *pos),
+            }
+
+            self.exit_on_list_cancel = false;
+        }
+
+        if self.open_look_list {
+            self.open_look_list = false;
+            inputs.clear_input();
+            return (
+                ModeControl::Push(Box::new(LookListMode::new(world))),
+                ModeUpdate::Immediate,
+            );
+        }
+
         inputs.prepare_input();
 
         if let Some(InputEvent::AppQuit) = inputs.get_input() {
             world.run(reset_camera);
 
             return (
-                ModeControl::Pop(ViewMapModeResult::AppQuit.into()),
+                ModeControl::Pop(Box::new(ViewMapModeResult::AppQuit)),
                 ModeUpdate::Immediate,
             );
         } else if let Some(InputEvent::Press(keycode)) = inputs.get_input() {
@@ -127,10 +214,59 @@ impl ViewMapMode {
                     move_x = player_pos.x - camera.0.x;
                     move_y = player_pos.y - camera.0.y;
                 }
-                GameKey::Confirm | GameKey::Cancel | GameKey::ViewMap => {
+                GameKey::ZoomOut => self.overview = true,
+                GameKey::ZoomIn => self.overview = false,
+                GameKey::LookList => {
+                    inputs.clear_input();
+                    return (
+                        ModeControl::Push(Box::new(LookListMode::new(world))),
+                        ModeUpdate::Immediate,
+                    );
+                }
+                GameKey::Confirm => {
+                    let player_pos = {
+                        let player_id = world.borrow::<UniqueView<PlayerId>>();
+                        let coords = world.borrow::<View<Coord>>();
+                        coords.get(player_id.0).0
+                    };
+                    let dest = world.borrow::<UniqueView<Camera>>().0;
+
+                    // Travel to the tile under the cursor, or just leave if it's the player's.
+                    if dest == player_pos {
+                        world.run(reset_camera);
+                        return (
+                            ModeControl::Pop(Box::new(ViewMapModeResult::Done)),
+                            ModeUpdate::Immediate,
+                        );
+                    }
+
+                    let known = {
+                        let map = world.borrow::<UniqueView<Map>>();
+
+                        !map.wall_or_oob(dest.x, dest.y)
+                            && map.seen.get_bit(dest.x, dest.y)
+                            && !map.get_tile(dest.x, dest.y).is_hazard()
+                            && !matches!(map.get_tile(dest.x, dest.y), Tile::LockedDoor)
+                    };
+
+                    if known {
+                        world.run(reset_camera);
+                        return (
+                            ModeControl::Pop(Box::new(ViewMapModeResult::TravelTo(dest.into()))),
+                            ModeUpdate::Immediate,
+                        );
+                    }
+
+                    world.run(
+                        |locale: UniqueView<Locale>, mut msgs: UniqueViewMut<Messages>| {
+                            msgs.add_as(MsgCategory::Warning, locale.get("msg.no_route").into())
+                        },
+                    );
+                }
+                GameKey::Cancel | GameKey::ViewMap => {
                     world.run(reset_camera);
                     return (
-                        ModeControl::Pop(ViewMapModeResult::Done.into()),
+                        ModeControl::Pop(Box::new(ViewMapModeResult::Done)),
                         ModeUpdate::Immediate,
                     );
                 }
@@ -138,13 +274,14 @@ impl ViewMapMode {
             }
 
             if move_x != 0 || move_y != 0 {
-                let min_x = self.center.x - self.range;
-                let max_x = self.center.x + self.range;
-                let min_y = self.center.y - self.range;
-                let max_y = self.center.y + self.range;
+                let playable = world.borrow::<UniqueView<Map>>().playable_rect();
+                let min_x = (self.center.x - self.range).max(playable.x1);
+                let max_x = (self.center.x + self.range).min(playable.x2);
+                let min_y = (self.center.y - self.range).max(playable.y1);
+                let max_y = (self.center.y + self.range).min(playable.y2);
                 let mut camera = world.borrow::<UniqueViewMut<Camera>>();
 
-                // Keep the camera within range of the center.
+                // Keep the camera within range of the center and inside the playable map.
                 if move_x < 0 && camera.0.x + move_x < min_x {
                     move_x = min_x - camera.0.x;
                     move_y = move_y.signum() * move_y.abs().min(move_x.abs());
@@ -176,15 +313,22 @@ impl ViewMapMode {
             }
         }
 
-        (ModeControl::Stay, ModeUpdate::WaitForEvent)
+        // Keep drawing map chunks that didn't fit in previous frames.
+        if self.chunked_map_grid.has_pending_chunks() {
+            (ModeControl::Stay, ModeUpdate::Update)
+        } else {
+            (ModeControl::Stay, ModeUpdate::WaitForEvent)
+        }
     }
 
-    pub fn draw(&mut self, world: &World, grids: &mut [TileGrid<GameSym>], active: bool) {
+    fn draw(&mut self, world: &World, grids: &mut [TileGrid<GameSym>], active: bool) {
         let (map_grid, grids) = grids.split_first_mut().unwrap(); // ui::MAP_GRID
         let (status_grid, grids) = grids.split_first_mut().unwrap(); // ui::STATUS_GRID
         let (item_grid, grids) = grids.split_first_mut().unwrap(); // ui::ITEM_GRID
         let (msg_frame_grid, grids) = grids.split_first_mut().unwrap(); // ui::MSG_FRAME_GRID
-        let (msg_grid, _) = grids.split_first_mut().unwrap(); // ui::MSG_GRID
+        let (msg_grid, grids) = grids.split_first_mut().unwrap(); // ui::MSG_GRID
+        let (overview_grid, grids) = grids.split_first_mut().unwrap(); // OVERVIEW_GRID
+        let (legend_grid, _) = grids.split_first_mut().unwrap(); // LEGEND_GRID
 
         if active {
             map_grid.view.color_mod = Color::WHITE;
@@ -192,12 +336,25 @@ impl ViewMapMode {
             item_grid.view.color_mod = Color::WHITE;
             msg_frame_grid.view.color_mod = Color::WHITE;
             msg_grid.view.color_mod = Color::WHITE;
+            overview_grid.view.color_mod = Color::WHITE;
+            legend_grid.view.color_mod = Color::WHITE;
         } else {
             map_grid.view.color_mod = Color::GRAY;
             status_grid.view.color_mod = Color::GRAY;
             item_grid.view.color_mod = Color::GRAY;
             msg_frame_grid.view.color_mod = Color::GRAY;
             msg_grid.view.color_mod = Color::GRAY;
+            overview_grid.view.color_mod = Color::GRAY;
+            legend_grid.view.color_mod = Color::GRAY;
+        }
+
+        map_grid.view.visible = !self.overview;
+        overview_grid.view.visible = self.overview;
+        legend_grid.view.visible = self.overview;
+
+        if self.overview {
+            draw_overview(world, overview_grid);
+            draw_legend(world, legend_grid);
         }
 
         self.chunked_map_grid.draw(world, map_grid);
@@ -220,7 +377,7 @@ impl ViewMapMode {
         let (desc, recalled) = map.describe_pos(world, camera.0.x, camera.0.y, false, false, false);
 
         if self.redraw_msg_frame_grid {
-            ui::draw_msg_frame(msg_frame_grid, true);
+            ui::draw_msg_frame(world, msg_frame_grid, true);
         }
 
         msg_grid.clear();
@@ -229,13 +386,179 @@ impl ViewMapMode {
             status_grid,
             item_grid,
             msg_grid,
-            Some(&format!(
-                "You {} [{:+},{:+}]: {}",
-                if recalled { "recall" } else { "see" },
-                camera.0.x - player_pos.x,
-                camera.0.y - player_pos.y,
-                desc,
+            Some(&world.borrow::<UniqueView<Locale>>().format(
+                if recalled {
+                    "overview.recall"
+                } else {
+                    "overview.see"
+                },
+                &[
+                    ("dx", &format!("{:+}", camera.0.x - player_pos.x)),
+                    ("dy", &format!("{:+}", camera.0.y - player_pos.y)),
+                    ("what", &desc),
+                ],
             )),
         );
     }
+
+    fn draw_behind(&self) -> bool {
+        false
+    }
+}
+
+/// Pixels per map tile drawn into the overview texture and the zoom that scales them up to the
+/// largest size at which the whole map fits in an area of the given size.
+///
+/// Tiles are drawn as 2-by-2 blocks when there's room for it and single pixels otherwise, so the
+/// texture stays tiny no matter how big the area is.
+fn overview_scale(map_size: Size, area_size: Size) -> (u32, u32) {
+    let fit = (area_size.w / map_size.w.max(1))
+        .min(area_size.h / map_size.h.max(1))
+        .max(1);
+
+    if fit >= 2 {
+        (2, fit / 2)
+    } else {
+        (1, 1)
+    }
+}
+
+/// Set up the grids of the overview and its legend over the area of the map grid.
+fn prepare_overview_grids(
+    world: &World,
+    grids: &mut Vec<TileGrid<GameSym>>,
+    tilesets: &[Tileset<GameSym>],
+) {
+    let Options {
+        font, text_zoom, ..
+    } = *world.borrow::<UniqueView<Options>>();
+    let map_size = {
+        let map = world.borrow::<UniqueView<Map>>();
+
+        Size {
+            w: map.width as u32,
+            h: map.height as u32,
+        }
+    };
+    let legend_size = {
+        let locale = world.borrow::<UniqueView<Locale>>();
+        let title_w = locale.get("overview.title").chars().count() as u32 + 8;
+        let label_w = LEGEND
+            .iter()
+            .map(|(key, _)| locale.get(key).chars().count() as u32 + 6)
+            .max()
+            .unwrap_or(0);
+
+        Size {
+            w: title_w.max(label_w),
+            h: LEGEND.len() as u32 + 2,
+        }
+    };
+
+    if grids.len() <= OVERVIEW_GRID {
+        grids.push(TileGrid::new(map_size, tilesets, font as usize));
+        grids[OVERVIEW_GRID].view.clear_color = Some(Color::BLACK);
+
+        grids.push(TileGrid::new(legend_size, tilesets, font as usize));
+        grids[LEGEND_GRID].view.clear_color = None;
+    }
+
+    let map_area = Area::new(grids[ui::MAP_GRID].view.pos, grids[ui::MAP_GRID].view.size);
+    let (block_px, zoom) = overview_scale(map_size, map_area.size);
+    let overview_grid = &mut grids[OVERVIEW_GRID];
+
+    overview_grid.resize(map_size);
+    overview_grid.set_tileset(tilesets, font as usize);
+    overview_grid.set_block_size(
+        tilesets,
+        Some(Size {
+            w: block_px,
+            h: block_px,
+        }),
+    );
+    overview_grid.view_centered(tilesets, zoom, map_area.pos, map_area.size);
+    overview_grid.view.zoom = zoom;
+
+    grids[LEGEND_GRID].set_tileset(tilesets, font as usize);
+    Layout::new(Extent::Cells(legend_size.w), Extent::Cells(legend_size.h))
+        .with_anchor(Anchor::TopLeft)
+        .apply(&mut grids[LEGEND_GRID], tilesets, text_zoom, map_area);
+}
+
+/// Draw the whole level onto the overview grid, one cell per map tile.
+///
+/// Known tiles are drawn in the color of their symbols, while the player, monsters, items, stairs
+/// and the cursor are picked out in the colors of the [LEGEND].
+fn draw_overview(world: &World, grid: &mut TileGrid<GameSym>) {
+    let (map, options, camera, player_id, coords, fovs, items, monsters) = world.borrow::<(
+        UniqueView<Map>,
+        UniqueView<Options>,
+        UniqueView<Camera>,
+        UniqueView<PlayerId>,
+        View<Coord>,
+        View<FieldOfView>,
+        View<Item>,
+        View<Monster>,
+    )>();
+    let player_fov = fovs.get(player_id.0);
+    let [you, monster, item, stairs, cursor] =
+        LEGEND.map(|(_, color)| render::palette_color(&options, color));
+    let mut put = |pos: (i32, i32), color: Color| {
+        grid.put_char_color_raw(pos, ' ', Color::WHITE, color);
+    };
+
+    for (x, y, tile) in map.iter_bounds(0, 0, map.width - 1, map.height - 1) {
+        let in_view = player_fov.get((x, y));
+        let color = match tile.filter(|_| in_view || options.fog_style != FogStyle::Hidden) {
+            Some(_) if matches!(map.get_tile(x, y), Tile::DownStairs) => stairs,
+            Some(_) if !in_view && map.remembered_at(x, y).is_some() => item,
+            Some((_, color)) if in_view => render::palette_color(&options, color),
+            Some((_, color)) => options
+                .fog_style
+                .apply(render::palette_color(&options, color))
+                .unwrap_or(Color::BLACK),
+            None => Color::BLACK,
+        };
+
+        put((x, y), color);
+    }
+
+    for (coord, _) in (&coords, &items).iter() {
+        if player_fov.get(coord.0.into()) {
+            put(coord.0.into(), item);
+        }
+    }
+
+    for (coord, _) in (&coords, &monsters).iter() {
+        if player_fov.get(coord.0.into()) {
+            put(coord.0.into(), monster);
+        }
+    }
+
+    put(coords.get(player_id.0).0.into(), you);
+    put(camera.0.into(), cursor);
+}
+
+/// Draw a box explaining the colors of the overview.
+fn draw_legend(world: &World, grid: &mut TileGrid<GameSym>) {
+    let locale = world.borrow::<UniqueView<Locale>>();
+    let options = world.borrow::<UniqueView<Options>>();
+    let fg = Color::WHITE;
+    let bg = Color::BLACK;
+
+    grid.draw_box((0, 0), (grid.width(), grid.height()), fg, bg);
+    grid.print_color(
+        (2, 0),
+        &format!("< {} >", locale.get("overview.title")),
+        true,
+        Color::YELLOW,
+        bg,
+    );
+
+    for (i, (key, color)) in LEGEND.iter().enumerate() {
+        let y = 1 + i as i32;
+
+        grid.put_char_color((2, y), '■', render::palette_color(&options, *color), bg);
+        grid.print_color((4, y), locale.get(key), true, fg, bg);
+    }
 }
@@ -3,6 +3,7 @@ use shipyard::{UniqueView, World};
 use crate::{
     gamekey::{self, GameKey},
     gamesym::GameSym,
+    locale::Locale,
     ui::{self, Options},
 };
 use ruggrogue::{
@@ -10,10 +11,7 @@ use ruggrogue::{
     InputBuffer, InputEvent, KeyMods, TileGrid, Tileset,
 };
 
-use super::{ModeControl, ModeResult, ModeUpdate};
-
-const YES_STR: &str = "[ Yes ]";
-const NO_STR: &str = "[ No ]";
+use super::{Mode, ModeControl, ModeResult, ModeUpdate};
 
 pub enum YesNoDialogModeResult {
     AppQuit,
@@ -44,9 +42,11 @@ impl YesNoDialogMode {
             yes_selected: yes_default,
         }
     }
+}
 
-    pub fn prepare_grids(
-        &self,
+impl Mode<World, GameSym> for YesNoDialogMode {
+    fn prepare_grids(
+        &mut self,
         world: &World,
         grids: &mut Vec<TileGrid<GameSym>>,
         tilesets: &[Tileset<GameSym>],
@@ -55,8 +55,11 @@ impl YesNoDialogMode {
         let Options {
             font, text_zoom, ..
         } = *world.borrow::<UniqueView<Options>>();
+        let locale = world.borrow::<UniqueView<Locale>>();
+        let buttons_width =
+            locale.get("yes_no.yes").chars().count() + locale.get("yes_no.no").chars().count() + 2;
         let new_grid_size = Size {
-            w: 4 + self.prompt.len().max(YES_STR.len() + NO_STR.len() + 2) as u32,
+            w: 4 + self.prompt.chars().count().max(buttons_width) as u32,
             h: 7,
         };
 
@@ -72,7 +75,7 @@ impl YesNoDialogMode {
         grids[0].view.zoom = text_zoom;
     }
 
-    pub fn update(
+    fn update(
         &mut self,
         _world: &World,
         inputs: &mut InputBuffer,
@@ -83,7 +86,7 @@ impl YesNoDialogMode {
 
         if let Some(InputEvent::AppQuit) = inputs.get_input() {
             return (
-                ModeControl::Pop(YesNoDialogModeResult::AppQuit.into()),
+                ModeControl::Pop(Box::new(YesNoDialogModeResult::AppQuit)),
                 ModeUpdate::Immediate,
             );
         } else if let Some(InputEvent::Press(keycode)) = inputs.get_input() {
@@ -92,13 +95,13 @@ impl YesNoDialogMode {
                 GameKey::Right => self.yes_selected = false,
                 GameKey::Confirm => {
                     return (
-                        ModeControl::Pop(YesNoDialogModeResult::from(self.yes_selected).into()),
+                        ModeControl::Pop(Box::new(YesNoDialogModeResult::from(self.yes_selected))),
                         ModeUpdate::Immediate,
                     )
                 }
                 GameKey::Cancel => {
                     return (
-                        ModeControl::Pop(YesNoDialogModeResult::No.into()),
+                        ModeControl::Pop(Box::new(YesNoDialogModeResult::No)),
                         ModeUpdate::Immediate,
                     )
                 }
@@ -109,10 +112,14 @@ impl YesNoDialogMode {
         (ModeControl::Stay, ModeUpdate::WaitForEvent)
     }
 
-    pub fn draw(&self, _world: &World, grids: &mut [TileGrid<GameSym>], active: bool) {
+    fn draw(&mut self, world: &World, grids: &mut [TileGrid<GameSym>], active: bool) {
         let grid = &mut grids[0];
-        let yes_x = grid.width() as i32 - (YES_STR.len() + NO_STR.len() + 4) as i32;
-        let no_x = grid.width() as i32 - NO_STR.len() as i32 - 2;
+        let locale = world.borrow::<UniqueView<Locale>>();
+        let yes_str = locale.get("yes_no.yes");
+        let no_str = locale.get("yes_no.no");
+        let yes_x =
+            grid.width() as i32 - (yes_str.chars().count() + no_str.chars().count() + 4) as i32;
+        let no_x = grid.width() as i32 - no_str.chars().count() as i32 - 2;
         let fg = Color::WHITE;
         let bg = Color::BLACK;
         let selected_bg = ui::SELECTED_BG;
@@ -124,17 +131,19 @@ impl YesNoDialogMode {
 
         grid.print_color(
             (yes_x, 4),
-            YES_STR,
+            yes_str,
             true,
             fg,
             if self.yes_selected { selected_bg } else { bg },
         );
         grid.print_color(
             (no_x, 4),
-            NO_STR,
+            no_str,
             true,
             fg,
             if !self.yes_selected { selected_bg } else { bg },
         );
+
+        ui::scroll_menu_grid(grid, 4, fg, bg);
     }
 }
@@ -1,16 +1,36 @@
+use rand::Rng;
+use ruggrogue::{DijkstraMap, PathBuffers, PathableMap};
 use shipyard::{
     EntitiesView, EntityId, Get, IntoIter, Shiperator, UniqueView, UniqueViewMut, View, ViewMut,
     World,
 };
-use std::{cmp::Reverse, collections::BinaryHeap};
+use std::{cmp::Reverse, collections::BinaryHeap, mem};
 
 use crate::{
-    components::{BlocksTile, Coord, FieldOfView, Monster},
-    damage, item,
+    components::{
+        Afraid, Asleep, BlocksTile, CombatStats, Confused, Coord, FieldOfView, LastSeenPlayer,
+        Monster, NameSource,
+    },
+    damage, faction, item,
+    locale::Locale,
     map::Map,
-    player::{self, PlayerId},
+    message::{Messages, MsgCategory},
+    player::PlayerId,
+    rng::RngStream,
+    script::{self, Scripts},
+    sound, status, GameSeed, TurnCount,
 };
 
+/// Most positions a monster will consider when looking for a path to the player.
+const PATH_BUDGET: u32 = 500;
+
+/// Turns a monster keeps heading to where it last saw the player before giving up.
+const LAST_SEEN_TURNS: i32 = 8;
+
+/// How strongly frightened monsters prefer getting far away over getting away quickly; see
+/// [DijkstraMap::fleeing].
+const FLEE_PERCENT: i32 = 120;
+
 pub struct MonsterTurns(BinaryHeap<(Reverse<i32>, EntityId)>);
 
 impl MonsterTurns {
@@ -25,6 +45,10 @@ impl Default for MonsterTurns {
     }
 }
 
+/// Path search storage shared by all monsters so that each search doesn't have to allocate.
+#[derive(Default)]
+pub struct MonsterPaths(PathBuffers);
+
 pub fn enqueue_monster_turns(
     mut monster_turns: UniqueViewMut<MonsterTurns>,
     player_id: UniqueView<PlayerId>,
@@ -41,43 +65,498 @@ pub fn enqueue_monster_turns(
     }
 }
 
-fn do_turn_for_one_monster(world: &World, monster: EntityId) {
-    if item::is_asleep(world, monster) {
-        item::handle_sleep_turn(world, monster);
-    } else if player::can_see_player(world, monster) {
-        let mut map = world.borrow::<UniqueViewMut<Map>>();
-        let player_id = world.borrow::<UniqueView<PlayerId>>();
-        let (player_pos, pos): ((i32, i32), (i32, i32)) = {
-            let coords = world.borrow::<View<Coord>>();
-            (
-                coords.get(player_id.0).0.into(),
-                coords.get(monster).0.into(),
-            )
+/// Give the scripted ability of a monster, if it has one, a chance to trigger against a target that
+/// it just hit.
+fn try_monster_ability(world: &World, monster: EntityId, target: EntityId) {
+    let script = {
+        let scripts = world.borrow::<UniqueView<Scripts>>();
+        let ability =
+            match scripts.ability_for(world.borrow::<View<NameSource>>().get(monster).key()) {
+                Some(ability) => ability,
+                None => return,
+            };
+        let mut rng = {
+            let mut fork = world
+                .borrow::<UniqueView<GameSeed>>()
+                .fork(RngStream::MonsterAbility);
+            fork.mix_u64(world.borrow::<UniqueView<TurnCount>>().0);
+            if let Ok(coord) = world.borrow::<View<Coord>>().try_get(monster) {
+                fork.mix_i32(coord.0.x).mix_i32(coord.0.y);
+            }
+            fork.rng()
         };
 
-        if let Some(step) = ruggrogue::find_path(&*map, pos, player_pos, 4, true).nth(1) {
-            if step == player_pos {
-                damage::melee_attack(world, monster, player_id.0);
+        if !rng.gen_ratio(ability.chance.min(100), 100) {
+            return;
+        }
+
+        ability.on_hit.clone()
+    };
+
+    script::run_script(world, &script, monster, target);
+}
+
+/// Find the first step of a path from one position towards another.
+pub fn first_step_towards(
+    world: &World,
+    map: &Map,
+    from: (i32, i32),
+    to: (i32, i32),
+) -> Option<(i32, i32)> {
+    let mut monster_paths = world.borrow::<UniqueViewMut<MonsterPaths>>();
+    let buffers = mem::take(&mut monster_paths.0);
+    let mut path = ruggrogue::find_path_reusing(map, from, to, 4, true, Some(PATH_BUDGET), buffers);
+    let step = path.nth(1);
+
+    monster_paths.0 = path.into_buffers();
+    step
+}
+
+/// Find a step that takes a monster further away from a foe.
+fn flee_step(map: &Map, pos: (i32, i32), foe_pos: (i32, i32)) -> Option<(i32, i32)> {
+    DijkstraMap::new(map, std::iter::once(foe_pos), None)
+        .fleeing(map, FLEE_PERCENT)
+        .downhill(pos)
+}
+
+/// Move a monster to an adjacent position, or attack what's there instead.
+///
+/// Only foes are attacked unless `hit_anyone` is true, e.g. for a confused monster that can't tell
+/// friend from foe.  If `audible` is true, the player hears the monster's footsteps if it's out of
+/// sight.
+fn monster_step(
+    world: &World,
+    monster: EntityId,
+    step: (i32, i32),
+    hit_anyone: bool,
+    audible: bool,
+) {
+    let mut map = world.borrow::<UniqueViewMut<Map>>();
+    let player_id = world.borrow::<UniqueView<PlayerId>>();
+    let (player_pos, pos): ((i32, i32), (i32, i32)) = {
+        let coords = world.borrow::<View<Coord>>();
+        (
+            coords.get(player_id.0).0.into(),
+            coords.get(monster).0.into(),
+        )
+    };
+    let target = {
+        let combat_stats = world.borrow::<View<CombatStats>>();
+        map.iter_entities_at(step.0, step.1)
+            .find(|id| *id != monster && combat_stats.contains(*id))
+    };
+
+    if let Some(target) = target {
+        if hit_anyone || faction::is_hostile(world, monster, target) {
+            drop(map);
+            if damage::melee_attack(world, monster, target) {
+                try_monster_ability(world, monster, target);
+            }
+        }
+    } else if !map.is_blocked(step.0, step.1) {
+        let blocks = world.borrow::<View<BlocksTile>>();
+        let mut coords = world.borrow::<ViewMut<Coord>>();
+        let mut fovs = world.borrow::<ViewMut<FieldOfView>>();
+
+        map.move_entity(monster, pos, step, blocks.contains(monster));
+        (&mut coords).get(monster).0 = step.into();
+        (&mut fovs).get(monster).dirty = true;
+
+        if audible {
+            let player_fov = fovs.get(player_id.0);
+            let locale = world.borrow::<UniqueView<Locale>>();
+
+            if let Some(cue) = sound::cue(&locale, player_pos, player_fov, step, "sound.footsteps")
+            {
+                world
+                    .borrow::<UniqueViewMut<Messages>>()
+                    .add_as(MsgCategory::Warning, cue);
+            }
+        }
+    }
+}
+
+/// What a monster knows about its surroundings at the start of the monsters' turns.
+struct Perception {
+    monster: EntityId,
+    pos: (i32, i32),
+    asleep: bool,
+    confused: bool,
+    afraid: bool,
+    /// The closest foe that the monster can see, and where it is.
+    foe: Option<(EntityId, (i32, i32))>,
+    /// True if the monster would fight the player, so it remembers where it last saw them.
+    hunts_player: bool,
+}
+
+/// What a monster has decided to do with its turn.
+enum Intent {
+    /// Sleep through the turn, possibly waking up.
+    Sleep,
+    /// Stumble onto an adjacent position, attacking anyone there.
+    Stumble((i32, i32)),
+    /// Get away from a foe at the given position.
+    Flee((i32, i32)),
+    /// Head towards a position, attacking any foe in the way.  The flag is set on the first step a
+    /// monster takes after the player's trail once it loses sight of them, which is the only one
+    /// the player can hear, so that monsters closing in out of sight don't flood the messages.
+    Approach((i32, i32), bool),
+    Idle,
+}
+
+/// Gather what each monster knows, looking over each storage once for all of them instead of once
+/// per monster.
+fn perceive(world: &World, monsters: &[EntityId]) -> Vec<Perception> {
+    let foes = faction::nearest_visible_foes(world, monsters);
+    let player_id = world.borrow::<UniqueView<PlayerId>>().0;
+    let player_faction = faction::faction_of(world, player_id);
+    let monster_factions = monsters
+        .iter()
+        .map(|&monster| faction::faction_of(world, monster))
+        .collect::<Vec<_>>();
+    let (asleeps, afraids, confuseds, coords) =
+        world.borrow::<(View<Asleep>, View<Afraid>, View<Confused>, View<Coord>)>();
+
+    monsters
+        .iter()
+        .zip(foes)
+        .zip(monster_factions)
+        .map(|((&monster, foe), faction)| Perception {
+            monster,
+            pos: coords.get(monster).0.into(),
+            asleep: asleeps.contains(monster),
+            confused: confuseds.contains(monster),
+            afraid: afraids.contains(monster),
+            foe: foe.map(|foe| (foe, coords.get(foe).0.into())),
+            hunts_player: matches!(
+                (faction, player_faction),
+                (Some(a), Some(b)) if a != b
+            ),
+        })
+        .collect()
+}
+
+/// Decide what each monster will do based on what it perceives: chase the closest foe it can see,
+/// otherwise head to where it last saw the player until it gets there or gives up.
+fn decide(world: &World, perceptions: &[Perception]) -> Vec<Intent> {
+    let entities = world.borrow::<EntitiesView>();
+    let player_id = world.borrow::<UniqueView<PlayerId>>().0;
+    let mut last_seens = world.borrow::<ViewMut<LastSeenPlayer>>();
+
+    perceptions
+        .iter()
+        .map(|p| {
+            if p.asleep {
+                return Intent::Sleep;
+            }
+
+            let stumble = if p.confused {
+                status::stumble(world, p.monster)
             } else {
-                let blocks = world.borrow::<View<BlocksTile>>();
-                let mut coords = world.borrow::<ViewMut<Coord>>();
-                let mut fovs = world.borrow::<ViewMut<FieldOfView>>();
+                None
+            };
+
+            if let Some((dx, dy)) = stumble {
+                Intent::Stumble((p.pos.0 + dx, p.pos.1 + dy))
+            } else if p.afraid {
+                p.foe
+                    .map_or(Intent::Idle, |(_, foe_pos)| Intent::Flee(foe_pos))
+            } else if let Some((foe, foe_pos)) = p.foe {
+                if foe == player_id {
+                    entities.add_component(
+                        &mut last_seens,
+                        LastSeenPlayer {
+                            pos: foe_pos.into(),
+                            turns_left: LAST_SEEN_TURNS,
+                        },
+                        p.monster,
+                    );
+                }
+
+                Intent::Approach(foe_pos, false)
+            } else if !p.hunts_player {
+                last_seens.remove(p.monster);
+                Intent::Idle
+            } else if let Ok(last_seen) = (&mut last_seens).try_get(p.monster) {
+                last_seen.turns_left -= 1;
+
+                if last_seen.turns_left >= 0 && last_seen.pos != p.pos.into() {
+                    Intent::Approach(
+                        last_seen.pos.into(),
+                        last_seen.turns_left == LAST_SEEN_TURNS - 1,
+                    )
+                } else {
+                    last_seens.remove(p.monster);
+                    Intent::Idle
+                }
+            } else {
+                Intent::Idle
+            }
+        })
+        .collect()
+}
+
+/// Carry out what each monster decided to do, one monster at a time in turn order, since each
+/// action can change the map for the monsters after it.
+fn act(world: &World, perceptions: &[Perception], intents: Vec<Intent>) {
+    for (p, intent) in perceptions.iter().zip(intents) {
+        if !world.borrow::<EntitiesView>().is_alive(p.monster) {
+            continue;
+        }
+
+        match intent {
+            Intent::Sleep => item::handle_sleep_turn(world, p.monster),
+            Intent::Stumble(step) => monster_step(world, p.monster, step, true, false),
+            Intent::Flee(foe_pos) => {
+                let step = flee_step(&world.borrow::<UniqueView<Map>>(), p.pos, foe_pos);
+
+                // Frightened monsters won't fight even if fleeing leads right past a foe.
+                if let Some(step) = step.filter(|step| *step != foe_pos) {
+                    monster_step(world, p.monster, step, false, false);
+                }
+            }
+            Intent::Approach(goal, audible) => {
+                let step =
+                    first_step_towards(world, &world.borrow::<UniqueView<Map>>(), p.pos, goal);
 
-                map.move_entity(monster, pos, step, blocks.contains(monster));
-                (&mut coords).get(monster).0 = step.into();
-                (&mut fovs).get(monster).dirty = true;
+                if let Some(step) = step {
+                    monster_step(world, p.monster, step, false, audible);
+                } else {
+                    // Nowhere to go, so stop looking.
+                    world.borrow::<ViewMut<LastSeenPlayer>>().remove(p.monster);
+                }
             }
+            Intent::Idle => {}
         }
     }
 }
 
+/// Give every monster queued up by [enqueue_monster_turns] its turn.
+///
+/// Monsters take their turns in batches: first they all perceive their surroundings, then they all
+/// decide what to do, and only then do they act one at a time.  Monsters decide based on where
+/// everyone was at the start of the monsters' turns, but find their paths and pick their targets
+/// when they act, so they never walk into a monster that moved before them.
 pub fn do_monster_turns(world: &World) {
-    let (entities, mut monster_turns) =
-        world.borrow::<(EntitiesView, UniqueViewMut<MonsterTurns>)>();
+    let monsters = {
+        let (entities, mut monster_turns) =
+            world.borrow::<(EntitiesView, UniqueViewMut<MonsterTurns>)>();
+        let mut monsters = Vec::with_capacity(monster_turns.0.len());
+
+        while let Some((_, monster)) = monster_turns.0.pop() {
+            if entities.is_alive(monster) {
+                monsters.push(monster);
+            }
+        }
+
+        monsters
+    };
+    let perceptions = perceive(world, &monsters);
+    let intents = decide(world, &perceptions);
+
+    act(world, &perceptions, intents);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        components::Faction,
+        event::GameEvents,
+        locale::{self, Locale},
+        player::{self, PlayerAlive},
+        spawn,
+        spawn_tables::{self, SpawnTables},
+        ui::Options,
+        vision,
+    };
+    use std::time::{Duration, Instant};
+
+    /// About as many monsters as a crowded level deep into new game plus, and then some.
+    const MONSTERS: usize = 600;
+    const TURNS: u32 = 20;
+
+    /// An open arena with the player in the middle and monsters packed all around them.
+    fn crowded_world(monsters: usize) -> World {
+        let world = World::new();
+        let language_list = locale::discover_languages();
+        let center = (40, 25);
+
+        world.add_unique(Locale::load(&language_list, 0));
+        world.add_unique(
+            serde_json::from_str::<Options>(
+                r#"{ "tileset": 0, "font": 0, "map_zoom": 1, "text_zoom": 1 }"#,
+            )
+            .unwrap(),
+        );
+        world.add_unique(GameSeed(1));
+        world.add_unique(TurnCount(1));
+        world.add_unique(GameEvents::default());
+        world.add_unique(Messages::new(100));
+        world.add_unique(Map::new(80, 50));
+        world.add_unique(PlayerId(world.run(spawn::spawn_player)));
+        world.add_unique(PlayerAlive(true));
+        world.add_unique(MonsterTurns::new());
+        world.add_unique(MonsterPaths::default());
+        world.add_unique(script::load_scripts());
+        world.add_unique(spawn_tables::load_spawn_tables().unwrap());
+        world.run(player::add_coords_to_players);
+        world.run(
+            |mut map: UniqueViewMut<Map>,
+             player_id: UniqueView<PlayerId>,
+             mut coords: ViewMut<Coord>| {
+                map.place_entity(player_id.0, center, false);
+                (&mut coords).get(player_id.0).0 = center.into();
+            },
+        );
+
+        let name = world
+            .borrow::<UniqueView<SpawnTables>>()
+            .monsters
+            .last()
+            .unwrap()
+            .name
+            .clone();
+        let spots = (1..49)
+            .step_by(2)
+            .flat_map(|y| (1..79).step_by(2).map(move |x| (x, y)))
+            .filter(|&pos| pos != center)
+            .take(monsters);
+
+        for pos in spots {
+            spawn::spawn_summon(&world, pos, &name, Faction::Monsters);
+        }
+        assert_eq!(world.borrow::<View<Monster>>().iter().count(), monsters);
+
+        world
+    }
+
+    /// Find the closest visible foe of a single entity the way monsters did before they perceived
+    /// their surroundings in a batch.
+    fn nearest_visible_foe_unbatched(world: &World, who: EntityId) -> Option<EntityId> {
+        let candidates = {
+            let combat_stats = world.borrow::<View<CombatStats>>();
+            let coords = world.borrow::<View<Coord>>();
+            let fovs = world.borrow::<View<FieldOfView>>();
+            let (who_coord, who_fov) = (coords.get(who), fovs.get(who));
+            let mut candidates = (&coords, &combat_stats)
+                .iter()
+                .with_id()
+                .into_iter()
+                .filter(|(id, (coord, _))| *id != who && who_fov.get(coord.0.into()))
+                .map(|(id, (coord, _))| (coord.dist(who_coord), id))
+                .collect::<Vec<_>>();
+
+            candidates.sort_by_key(|(dist, _)| *dist);
+            candidates
+        };
+
+        candidates
+            .into_iter()
+            .map(|(_, id)| id)
+            .find(|&id| faction::is_hostile(world, who, id))
+    }
+
+    /// How far each monster is from its foe, since equally close foes may be found in any order.
+    fn foe_dists(
+        world: &World,
+        monsters: &[EntityId],
+        foes: &[Option<EntityId>],
+    ) -> Vec<Option<i32>> {
+        let coords = world.borrow::<View<Coord>>();
+
+        monsters
+            .iter()
+            .zip(foes)
+            .map(|(&monster, foe)| foe.map(|foe| coords.get(foe).dist(coords.get(monster))))
+            .collect()
+    }
 
-    while let Some((_, monster)) = monster_turns.0.pop() {
-        if entities.is_alive(monster) {
-            do_turn_for_one_monster(world, monster);
+    fn queued_monsters(world: &World) -> Vec<EntityId> {
+        world.run(vision::recalculate_fields_of_view);
+        world.run(enqueue_monster_turns);
+
+        let mut monster_turns = world.borrow::<UniqueViewMut<MonsterTurns>>();
+
+        mem::take(&mut monster_turns.0)
+            .into_sorted_vec()
+            .into_iter()
+            .map(|(_, id)| id)
+            .collect()
+    }
+
+    #[test]
+    fn batched_foes_match_unbatched() {
+        let world = crowded_world(100);
+
+        // Charm a few monsters so that monsters have foes other than the player.
+        let monsters = queued_monsters(&world);
+        for &monster in monsters.iter().step_by(7) {
+            world.borrow::<EntitiesView>().add_component(
+                &mut world.borrow::<ViewMut<Faction>>(),
+                Faction::Player,
+                monster,
+            );
         }
+
+        let batched = faction::nearest_visible_foes(&world, &monsters);
+        let unbatched = monsters
+            .iter()
+            .map(|&monster| nearest_visible_foe_unbatched(&world, monster))
+            .collect::<Vec<_>>();
+
+        assert!(batched.iter().any(Option::is_some));
+        assert_eq!(
+            foe_dists(&world, &monsters, &batched),
+            foe_dists(&world, &monsters, &unbatched),
+        );
+    }
+
+    /// Run with `cargo test --release -- --ignored --nocapture monster_turns_benchmark`.
+    #[test]
+    #[ignore]
+    fn monster_turns_benchmark() {
+        let world = crowded_world(MONSTERS);
+        let mut batched = Duration::ZERO;
+        let mut unbatched = Duration::ZERO;
+        let mut turns = Duration::ZERO;
+
+        for _ in 0..TURNS {
+            let monsters = queued_monsters(&world);
+
+            let start = Instant::now();
+            let foes = faction::nearest_visible_foes(&world, &monsters);
+            batched += start.elapsed();
+
+            let start = Instant::now();
+            let old_foes = monsters
+                .iter()
+                .map(|&monster| nearest_visible_foe_unbatched(&world, monster))
+                .collect::<Vec<_>>();
+            unbatched += start.elapsed();
+
+            assert_eq!(
+                foe_dists(&world, &monsters, &foes),
+                foe_dists(&world, &monsters, &old_foes),
+            );
+
+            world.run(enqueue_monster_turns);
+            let start = Instant::now();
+            do_monster_turns(&world);
+            turns += start.elapsed();
+
+            *world.borrow::<UniqueViewMut<GameEvents>>() = GameEvents::default();
+            world.borrow::<UniqueViewMut<TurnCount>>().0 += 1;
+        }
+
+        eprintln!(
+            "{} monsters over {} turns: finding foes took {:?} per turn batched versus {:?} one \
+             monster at a time; whole monster turns took {:?} per turn",
+            MONSTERS,
+            TURNS,
+            batched / TURNS,
+            unbatched / TURNS,
+            turns / TURNS,
+        );
     }
 }
@@ -0,0 +1,24 @@
+use shipyard::{UniqueView, World};
+use std::{fs::OpenOptions, io::Write};
+
+use crate::{map::Map, paths, GameSeed, TurnCount};
+
+pub(crate) const NOTES_FILENAME: &str = "notes.txt";
+
+/// Append a note to the end of the notes file, prefixed with the game seed, current dungeon depth
+/// and turn count so it can be matched up with the moment it was written.
+pub fn append_note(world: &World, note: &str) -> std::io::Result<()> {
+    let seed = world.borrow::<UniqueView<GameSeed>>().0;
+    let depth = world.borrow::<UniqueView<Map>>().depth;
+    let turn = world.borrow::<UniqueView<TurnCount>>().0;
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(paths::data_file(NOTES_FILENAME))?;
+
+    writeln!(
+        file,
+        "[seed {}, depth {}, turn {}] {}",
+        seed, depth, turn, note
+    )
+}
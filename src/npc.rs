@@ -0,0 +1,151 @@
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, fs};
+
+use crate::gamesym::GameSym;
+
+const NPCS_PATH: &str = "assets/npcs.json";
+
+/// Items that an NPC can hand over as part of a dialog.
+#[derive(Clone, Copy, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GiftItem {
+    Ration,
+    HealthPotion,
+    MagicMissileScroll,
+    FireballScroll,
+    SleepScroll,
+    Bag,
+    Pickaxe,
+    Whetstone,
+    RepairKit,
+}
+
+impl GiftItem {
+    pub const ALL: [GiftItem; 9] = [
+        GiftItem::Ration,
+        GiftItem::HealthPotion,
+        GiftItem::MagicMissileScroll,
+        GiftItem::FireballScroll,
+        GiftItem::SleepScroll,
+        GiftItem::Bag,
+        GiftItem::Pickaxe,
+        GiftItem::Whetstone,
+        GiftItem::RepairKit,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            GiftItem::Ration => "Ration",
+            GiftItem::HealthPotion => "Health Potion",
+            GiftItem::MagicMissileScroll => "Magic Missile Scroll",
+            GiftItem::FireballScroll => "Fireball Scroll",
+            GiftItem::SleepScroll => "Sleep Scroll",
+            GiftItem::Bag => "Bag",
+            GiftItem::Pickaxe => "Pickaxe",
+            GiftItem::Whetstone => "Whetstone",
+            GiftItem::RepairKit => "Repair Kit",
+        }
+    }
+}
+
+/// Something that happens when the player picks a dialog choice.
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DialogEffect {
+    /// Show a hint in the message log.
+    Hint { text: String },
+    /// Hand over an item, at most once per NPC.
+    Item { item: GiftItem },
+    /// Hand out a quest, recorded in the journal under the given ID.
+    Quest {
+        id: String,
+        text: String,
+        #[serde(default)]
+        reward: Option<GiftItem>,
+    },
+    /// Complete an active quest and hand over its reward.
+    CompleteQuest { id: String },
+}
+
+#[derive(Deserialize)]
+pub struct DialogChoice {
+    pub label: String,
+    /// Node to continue to, or end the dialog if absent.
+    #[serde(default)]
+    pub next: Option<String>,
+    #[serde(default)]
+    pub effect: Option<DialogEffect>,
+}
+
+#[derive(Deserialize)]
+pub struct DialogNode {
+    pub text: String,
+    pub choices: Vec<DialogChoice>,
+}
+
+/// A friendly NPC and its dialog tree, as defined in the NPCs file.
+#[derive(Deserialize)]
+pub struct NpcDef {
+    pub id: String,
+    pub name: String,
+    pub sym: GameSym,
+    pub fg: (u8, u8, u8),
+    #[serde(default = "default_min_depth")]
+    pub min_depth: i32,
+    pub start: String,
+    pub nodes: HashMap<String, DialogNode>,
+}
+
+fn default_min_depth() -> i32 {
+    1
+}
+
+/// All NPC definitions known to the game.
+pub struct NpcDefs(pub Vec<NpcDef>);
+
+impl NpcDefs {
+    pub fn get(&self, id: &str) -> Option<&NpcDef> {
+        self.0.iter().find(|d| d.id == id)
+    }
+}
+
+/// Load NPC definitions from the NPCs file, if it exists.
+///
+/// Definitions whose start node or choices lead to missing nodes are skipped with a warning.
+pub fn load_npc_defs() -> NpcDefs {
+    let defs = match fs::read_to_string(NPCS_PATH) {
+        Ok(contents) => match serde_json::from_str::<Vec<NpcDef>>(&contents) {
+            Ok(defs) => defs,
+            Err(e) => {
+                eprintln!("Warning: Failed to parse {}: {}", NPCS_PATH, e);
+                Vec::new()
+            }
+        },
+        Err(e) => {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                eprintln!("Warning: Failed to read {}: {}", NPCS_PATH, e);
+            }
+            Vec::new()
+        }
+    };
+
+    NpcDefs(
+        defs.into_iter()
+            .filter(|def| {
+                let valid = def.nodes.contains_key(&def.start)
+                    && def.nodes.values().all(|node| {
+                        node.choices.iter().all(|choice| match &choice.next {
+                            Some(next) => def.nodes.contains_key(next),
+                            None => true,
+                        })
+                    });
+
+                if !valid {
+                    eprintln!("Warning: NPC '{}' has a dangling dialog node", def.id);
+                }
+
+                valid
+            })
+            .collect(),
+    )
+}
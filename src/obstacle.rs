@@ -0,0 +1,196 @@
+use shipyard::{
+    AllStoragesViewMut, EntityId, Get, IntoIter, UniqueView, UniqueViewMut, View, ViewMut, World,
+};
+
+use crate::{
+    components::{Boulder, Coord, Diggable, Digger, FieldOfView, Inventory, Key, Name},
+    locale::Locale,
+    map::{Map, Tile},
+    message::Messages,
+    spawn,
+};
+
+/// Find a boulder at the given position.
+pub fn boulder_at(world: &World, pos: (i32, i32)) -> Option<EntityId> {
+    let map = world.borrow::<UniqueView<Map>>();
+    let boulders = world.borrow::<View<Boulder>>();
+
+    let boulder = map
+        .iter_entities_at(pos.0, pos.1)
+        .find(|id| boulders.contains(*id));
+
+    boulder
+}
+
+/// Find a cracked wall at the given position.
+pub fn diggable_at(world: &World, pos: (i32, i32)) -> Option<EntityId> {
+    let map = world.borrow::<UniqueView<Map>>();
+    let diggables = world.borrow::<View<Diggable>>();
+
+    let wall = map
+        .iter_entities_at(pos.0, pos.1)
+        .find(|id| diggables.contains(*id));
+
+    wall
+}
+
+/// True if the given entity carries something to dig with.
+pub fn carries_digger(world: &World, who: EntityId) -> bool {
+    let diggers = world.borrow::<View<Digger>>();
+    let inventories = world.borrow::<View<Inventory>>();
+
+    match inventories.try_get(who) {
+        Ok(inventory) => inventory.items.iter().any(|id| diggers.contains(*id)),
+        Err(_) => false,
+    }
+}
+
+/// Push a boulder one step in the given direction, returning false if something is in the way.
+///
+/// Boulders pushed into deep water or chasms fill them in, while lava swallows them whole.  They
+/// can't be pushed onto the stairs down.
+pub fn push_boulder(world: &World, boulder: EntityId, (dx, dy): (i32, i32)) -> bool {
+    let from: (i32, i32) = world.borrow::<View<Coord>>().get(boulder).0.into();
+    let to = (from.0 + dx, from.1 + dy);
+    let msg_key = {
+        let mut map = world.borrow::<UniqueViewMut<Map>>();
+
+        // Boulders never cover the way down.
+        if map.wall_or_oob(to.0, to.1)
+            || map.is_impassable(to.0, to.1)
+            || matches!(map.get_tile(to.0, to.1), Tile::DownStairs)
+        {
+            return false;
+        }
+
+        match *map.get_tile(to.0, to.1) {
+            Tile::DeepWater => {
+                map.change_tile(to.0, to.1, Tile::ShallowWater);
+                Some("msg.boulder_fills_water")
+            }
+            Tile::Chasm => {
+                map.change_tile(to.0, to.1, Tile::Floor);
+                Some("msg.boulder_fills_chasm")
+            }
+            Tile::Lava => Some("msg.boulder_sinks"),
+            _ => {
+                map.move_entity(boulder, from, to, true);
+                (&mut world.borrow::<ViewMut<Coord>>()).get(boulder).0 = to.into();
+                None
+            }
+        }
+    };
+
+    if let Some(msg_key) = msg_key {
+        world.run(
+            |locale: UniqueView<Locale>, mut msgs: UniqueViewMut<Messages>, names: View<Name>| {
+                msgs.add(locale.format(msg_key, &[("what", &names.get(boulder).0)]));
+            },
+        );
+        world
+            .borrow::<UniqueViewMut<Map>>()
+            .remove_entity(boulder, from, true);
+        spawn::despawn_entity(&mut world.borrow::<AllStoragesViewMut>(), boulder);
+    }
+
+    true
+}
+
+/// Dig out a cracked wall, leaving floor behind.
+pub fn dig(world: &World, digger: EntityId, wall: EntityId) {
+    let pos: (i32, i32) = world.borrow::<View<Coord>>().get(wall).0.into();
+
+    world.run(
+        |locale: UniqueView<Locale>, mut msgs: UniqueViewMut<Messages>, names: View<Name>| {
+            msgs.add(locale.format(
+                "msg.digs",
+                &[("who", &names.get(digger).0), ("what", &names.get(wall).0)],
+            ));
+        },
+    );
+
+    {
+        let mut map = world.borrow::<UniqueViewMut<Map>>();
+
+        map.remove_entity(wall, pos, false);
+        map.change_tile(pos.0, pos.1, Tile::Floor);
+    }
+    spawn::despawn_entity(&mut world.borrow::<AllStoragesViewMut>(), wall);
+
+    invalidate_fovs_near(world, pos);
+}
+
+/// Find a key that the given entity carries.
+pub fn carried_key(world: &World, who: EntityId) -> Option<EntityId> {
+    let inventories = world.borrow::<View<Inventory>>();
+    let keys = world.borrow::<View<Key>>();
+
+    match inventories.try_get(who) {
+        Ok(inventory) => inventory
+            .items
+            .iter()
+            .copied()
+            .find(|id| keys.contains(*id)),
+        Err(_) => None,
+    }
+}
+
+/// Unlock the door at the given position with a key, using the key up.
+pub fn unlock_with_key(world: &World, who: EntityId, key: EntityId, pos: (i32, i32)) {
+    world.run(
+        |locale: UniqueView<Locale>, mut msgs: UniqueViewMut<Messages>, names: View<Name>| {
+            msgs.add(locale.format(
+                "msg.unlocks",
+                &[("who", &names.get(who).0), ("item", &names.get(key).0)],
+            ));
+        },
+    );
+
+    if let Ok(inventory) = (&mut world.borrow::<ViewMut<Inventory>>()).try_get(who) {
+        inventory.items.retain(|id| *id != key);
+    }
+    spawn::despawn_entity(&mut world.borrow::<AllStoragesViewMut>(), key);
+    unlock_door(world, pos);
+}
+
+/// Unlock every locked door within the given distance of a position, returning how many there were.
+pub fn unlock_doors_near(world: &World, pos: (i32, i32), radius: i32) -> usize {
+    let doors = {
+        let map = world.borrow::<UniqueView<Map>>();
+        let mut doors = Vec::new();
+
+        for y in (pos.1 - radius).max(0)..=(pos.1 + radius).min(map.height - 1) {
+            for x in (pos.0 - radius).max(0)..=(pos.0 + radius).min(map.width - 1) {
+                if matches!(map.get_tile(x, y), Tile::LockedDoor) {
+                    doors.push((x, y));
+                }
+            }
+        }
+
+        doors
+    };
+
+    for &door in &doors {
+        unlock_door(world, door);
+    }
+
+    doors.len()
+}
+
+fn unlock_door(world: &World, pos: (i32, i32)) {
+    world
+        .borrow::<UniqueViewMut<Map>>()
+        .change_tile(pos.0, pos.1, Tile::Floor);
+    invalidate_fovs_near(world, pos);
+}
+
+/// Make anything that could see the given position see again, now that it no longer blocks sight.
+fn invalidate_fovs_near(world: &World, pos: (i32, i32)) {
+    world.run(|coords: View<Coord>, mut fovs: ViewMut<FieldOfView>| {
+        for (coord, fov) in (&coords, &mut fovs).iter() {
+            if coord.dist(&Coord(pos.into())) <= fov.range {
+                fov.invalidate();
+            }
+        }
+    });
+}
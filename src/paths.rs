@@ -0,0 +1,140 @@
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+use crate::{bones, config, notes, saveload, stats};
+
+/// Files that belong in the data directory.
+const DATA_FILES: &[&str] = &[
+    saveload::SAVE_FILENAME,
+    saveload::EXPORT_FILENAME,
+    stats::STATS_FILENAME,
+    bones::BONES_FILENAME,
+    notes::NOTES_FILENAME,
+];
+
+/// Files that belong in the config directory.
+const CONFIG_FILES: &[&str] = &[config::CONFIG_FILENAME];
+
+struct Dirs {
+    data: PathBuf,
+    config: PathBuf,
+}
+
+static DIRS: OnceLock<Dirs> = OnceLock::new();
+
+fn home_dir() -> Option<PathBuf> {
+    env::var_os("HOME")
+        .filter(|home| !home.is_empty())
+        .map(PathBuf::from)
+}
+
+/// An absolute path from an environment variable, ignoring it if it's unset, empty or relative,
+/// as the XDG base directory spec asks.
+fn env_dir(var: &str) -> Option<PathBuf> {
+    env::var_os(var)
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+}
+
+fn find_dirs() -> Dirs {
+    if cfg!(target_os = "emscripten") {
+        // The web build keeps everything in a directory backed by IndexedDB.
+        let dir = PathBuf::from("/ruggrogue");
+
+        return Dirs {
+            data: dir.clone(),
+            config: dir,
+        };
+    }
+
+    let (data, config) = if cfg!(windows) {
+        let dir = env_dir("APPDATA").map(|dir| dir.join("RuggRogue"));
+        (dir.clone(), dir)
+    } else if cfg!(target_os = "macos") {
+        let dir = home_dir().map(|home| home.join("Library/Application Support/RuggRogue"));
+        (dir.clone(), dir)
+    } else {
+        (
+            env_dir("XDG_DATA_HOME")
+                .or_else(|| home_dir().map(|home| home.join(".local/share")))
+                .map(|dir| dir.join("ruggrogue")),
+            env_dir("XDG_CONFIG_HOME")
+                .or_else(|| home_dir().map(|home| home.join(".config")))
+                .map(|dir| dir.join("ruggrogue")),
+        )
+    };
+
+    // Fall back to the current working directory, which is where files used to be kept.
+    Dirs {
+        data: data.unwrap_or_else(|| PathBuf::from(".")),
+        config: config.unwrap_or_else(|| PathBuf::from(".")),
+    }
+}
+
+fn dirs() -> &'static Dirs {
+    DIRS.get_or_init(find_dirs)
+}
+
+/// Path of a file kept in the data directory, e.g. the save file.
+pub fn data_file(name: &str) -> PathBuf {
+    dirs().data.join(name)
+}
+
+/// Path of a file kept in the config directory.
+pub fn config_file(name: &str) -> PathBuf {
+    dirs().config.join(name)
+}
+
+/// Move a file from where it used to be kept, renaming it if possible and copying it otherwise,
+/// e.g. if it's on a different file system.
+fn migrate_file(old_path: &Path, new_path: &Path) -> io::Result<()> {
+    if fs::rename(old_path, new_path).is_err() {
+        fs::copy(old_path, new_path)?;
+        fs::remove_file(old_path)?;
+    }
+
+    Ok(())
+}
+
+/// Create the data and config directories, and move over any files left in the current working
+/// directory by older versions of the game.
+///
+/// Files already in the new directories are never overwritten.
+pub fn init() {
+    if cfg!(target_os = "emscripten") {
+        return;
+    }
+
+    let Dirs { data, config } = dirs();
+
+    for (dir, names) in [(data, DATA_FILES), (config, CONFIG_FILES)] {
+        if let Err(e) = fs::create_dir_all(dir) {
+            eprintln!("Warning: Failed to create {}: {}", dir.display(), e);
+            continue;
+        }
+
+        if dir == Path::new(".") {
+            continue;
+        }
+
+        for name in names {
+            let old_path = Path::new(name);
+            let new_path = dir.join(name);
+
+            if old_path.is_file() && !new_path.exists() {
+                match migrate_file(old_path, &new_path) {
+                    Ok(()) => eprintln!("Moved {} to {}", name, new_path.display()),
+                    Err(e) => eprintln!(
+                        "Warning: Failed to move {} to {}: {}",
+                        name,
+                        new_path.display(),
+                        e
+                    ),
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,254 @@
+use shipyard::{
+    AllStoragesViewMut, EntitiesView, EntityId, Get, IntoIter, Shiperator, UniqueView,
+    UniqueViewMut, View, ViewMut, World,
+};
+
+use crate::{
+    components::{BlocksTile, CombatStats, Coord, FieldOfView, Name, Nutrition, Pet, Stomach},
+    damage, faction, hunger, item,
+    locale::Locale,
+    map::{Map, Tile},
+    message::Messages,
+    monster,
+    player::PlayerId,
+    spawn, status,
+};
+use ruggrogue::PathableMap;
+
+/// Pets wander no further than this from the player before heading back to them.
+const FOLLOW_DIST: i32 = 2;
+
+/// Find the free floor tile in the first room that is closest to the player.
+fn free_pos_near_player(world: &World) -> Option<(i32, i32)> {
+    let map = world.borrow::<UniqueView<Map>>();
+    let player_id = world.borrow::<UniqueView<PlayerId>>();
+    let coords = world.borrow::<View<Coord>>();
+    let player_coord = coords.get(player_id.0);
+
+    map.rooms.first().and_then(|room| {
+        room.iter_xy()
+            .filter(|&(x, y)| {
+                matches!(map.get_tile(x, y), Tile::Floor)
+                    && map.iter_entities_at(x, y).next().is_none()
+            })
+            .min_by_key(|&pos| Coord(pos.into()).dist(player_coord))
+    })
+}
+
+/// Spawn a pet for a new game next to the player.
+pub fn spawn_starting_pet(world: &World) {
+    if let Some(pos) = free_pos_near_player(world) {
+        spawn::spawn_pet(world, pos);
+    }
+}
+
+/// Take the coordinates off of all pets so that they survive the current map being despawned,
+/// returning the pets so that they can be put back with [place_pets_near_player].
+pub fn remove_coords_from_pets(mut coords: ViewMut<Coord>, pets: View<Pet>) -> Vec<EntityId> {
+    let pet_ids = pets.iter().with_id().map(|(id, _)| id).collect::<Vec<_>>();
+
+    for &id in pet_ids.iter() {
+        coords.remove(id);
+    }
+
+    pet_ids
+}
+
+/// Put pets back on the map next to the player after descending.
+///
+/// A pet that can't fit into the first room is left behind for good.
+pub fn place_pets_near_player(world: &World, pet_ids: &[EntityId]) {
+    for &pet_id in pet_ids {
+        if let Some(pos) = free_pos_near_player(world) {
+            let entities = world.borrow::<EntitiesView>();
+            let mut coords = world.borrow::<ViewMut<Coord>>();
+            let mut fovs = world.borrow::<ViewMut<FieldOfView>>();
+
+            world
+                .borrow::<UniqueViewMut<Map>>()
+                .place_entity(pet_id, pos, true);
+            entities.add_component(&mut coords, Coord(pos.into()), pet_id);
+            if let Ok(fov) = (&mut fovs).try_get(pet_id) {
+                fov.invalidate();
+            }
+        } else {
+            spawn::despawn_entity(&mut world.borrow::<AllStoragesViewMut>(), pet_id);
+        }
+    }
+}
+
+/// Find a foe of a pet next to it.
+fn adjacent_foe(world: &World, pet: EntityId, pos: (i32, i32)) -> Option<EntityId> {
+    let neighbors = {
+        let map = world.borrow::<UniqueView<Map>>();
+        let combat_stats = world.borrow::<View<CombatStats>>();
+        let mut neighbors = Vec::new();
+
+        for dy in -1..=1 {
+            for dx in -1..=1 {
+                if dx != 0 || dy != 0 {
+                    neighbors.extend(
+                        map.iter_entities_at(pos.0 + dx, pos.1 + dy)
+                            .filter(|id| combat_stats.contains(*id)),
+                    );
+                }
+            }
+        }
+
+        neighbors
+    };
+
+    neighbors
+        .into_iter()
+        .find(|&id| faction::is_hostile(world, pet, id))
+}
+
+/// Find the closest food lying on the floor that a pet can see, along with its position.
+fn nearest_visible_food(world: &World, pet: EntityId) -> Option<(EntityId, (i32, i32))> {
+    let coords = world.borrow::<View<Coord>>();
+    let fovs = world.borrow::<View<FieldOfView>>();
+    let nutritions = world.borrow::<View<Nutrition>>();
+    let pet_coord = coords.get(pet);
+    let pet_fov = fovs.get(pet);
+
+    (&nutritions, &coords)
+        .iter()
+        .with_id()
+        .into_iter()
+        .filter(|(_, (_, coord))| pet_fov.get(coord.0.into()))
+        .min_by_key(|(_, (_, coord))| coord.dist(pet_coord))
+        .map(|(id, (_, coord))| (id, coord.0.into()))
+}
+
+/// Have a pet eat food that it's standing on.
+fn pet_eat(world: &World, pet: EntityId, food: EntityId, pos: (i32, i32)) {
+    {
+        let locale = world.borrow::<UniqueView<Locale>>();
+        let mut msgs = world.borrow::<UniqueViewMut<Messages>>();
+        let player_id = world.borrow::<UniqueView<PlayerId>>();
+        let fovs = world.borrow::<View<FieldOfView>>();
+        let names = world.borrow::<View<Name>>();
+        let nutritions = world.borrow::<View<Nutrition>>();
+        let mut stomachs = world.borrow::<ViewMut<Stomach>>();
+
+        if let Ok(stomach) = (&mut stomachs).try_get(pet) {
+            stomach.fullness =
+                (stomach.fullness + nutritions.get(food).0).min(stomach.max_fullness);
+        }
+
+        if fovs.get(player_id.0).get(pos) {
+            msgs.add(locale.format(
+                "msg.pet_eats",
+                &[("who", &names.get(pet).0), ("item", &names.get(food).0)],
+            ));
+        }
+
+        let blocks = world.borrow::<View<BlocksTile>>().contains(food);
+        world
+            .borrow::<UniqueViewMut<Map>>()
+            .remove_entity(food, pos, blocks);
+    }
+
+    spawn::despawn_entity(&mut world.borrow::<AllStoragesViewMut>(), food);
+}
+
+/// Move a pet to an adjacent position, or attack a foe there instead.
+fn pet_step(world: &World, pet: EntityId, step: (i32, i32)) {
+    let mut map = world.borrow::<UniqueViewMut<Map>>();
+    let pos: (i32, i32) = world.borrow::<View<Coord>>().get(pet).0.into();
+    let occupant = {
+        let combat_stats = world.borrow::<View<CombatStats>>();
+        map.iter_entities_at(step.0, step.1)
+            .find(|id| *id != pet && combat_stats.contains(*id))
+    };
+
+    if let Some(occupant) = occupant {
+        // Pets never attack the player or their allies, even when stumbling about.
+        if faction::is_hostile(world, pet, occupant) {
+            drop(map);
+            damage::melee_attack(world, pet, occupant);
+        }
+    } else if !map.is_blocked(step.0, step.1) {
+        let mut coords = world.borrow::<ViewMut<Coord>>();
+        let mut fovs = world.borrow::<ViewMut<FieldOfView>>();
+
+        map.move_entity(pet, pos, step, true);
+        (&mut coords).get(pet).0 = step.into();
+        (&mut fovs).get(pet).dirty = true;
+    }
+}
+
+fn do_turn_for_one_pet(world: &World, pet: EntityId) {
+    if item::is_asleep(world, pet) {
+        item::handle_sleep_turn(world, pet);
+        return;
+    }
+
+    let pos: (i32, i32) = world.borrow::<View<Coord>>().get(pet).0.into();
+    let stumble = if status::is_confused(world, pet) {
+        status::stumble(world, pet)
+    } else {
+        None
+    };
+
+    if let Some((dx, dy)) = stumble {
+        pet_step(world, pet, (pos.0 + dx, pos.1 + dy));
+        return;
+    }
+
+    let food = if hunger::is_hungry(world, pet) {
+        nearest_visible_food(world, pet)
+    } else {
+        None
+    };
+
+    if let Some((food_id, food_pos)) = food.filter(|(_, food_pos)| *food_pos == pos) {
+        pet_eat(world, pet, food_id, food_pos);
+        return;
+    }
+
+    // Frightened pets stop fighting, but still stay close to the player.
+    if !status::is_afraid(world, pet) {
+        if let Some(target) = adjacent_foe(world, pet, pos) {
+            damage::melee_attack(world, pet, target);
+            return;
+        }
+    }
+
+    let goal = food.map(|(_, food_pos)| food_pos).or_else(|| {
+        let player_id = world.borrow::<UniqueView<PlayerId>>();
+        let coords = world.borrow::<View<Coord>>();
+        let player_coord = coords.get(player_id.0);
+
+        if coords.get(pet).dist(player_coord) > FOLLOW_DIST {
+            Some(player_coord.0.into())
+        } else {
+            None
+        }
+    });
+
+    if let Some(goal) = goal {
+        let step =
+            monster::first_step_towards(world, &world.borrow::<UniqueView<Map>>(), pos, goal);
+
+        if let Some(step) = step {
+            pet_step(world, pet, step);
+        }
+    }
+}
+
+/// Pets follow the player, fight monsters next to them and eat food they find when hungry.
+pub fn do_pet_turns(world: &World) {
+    let pet_ids = world
+        .borrow::<View<Pet>>()
+        .iter()
+        .with_id()
+        .map(|(id, _)| id)
+        .collect::<Vec<_>>();
+
+    for pet_id in pet_ids {
+        if world.borrow::<EntitiesView>().is_alive(pet_id) {
+            do_turn_for_one_pet(world, pet_id);
+        }
+    }
+}
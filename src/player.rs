@@ -6,16 +6,26 @@ use shipyard::{
 };
 
 use crate::{
-    components::{CombatStats, Coord, FieldOfView, Item, Monster, Name, Player},
-    damage, experience,
+    bones, chest,
+    components::{
+        BlocksTile, CombatStats, Coord, EquipSlot, Equipment, FieldOfView, Friendly, InLoadout,
+        Inventory, Item, Monster, Name, Npc, Player, Tally,
+    },
+    damage, encumbrance,
+    event::{self, GameEvent},
+    experience, faction, feeling,
     gamekey::{self, GameKey},
+    hints::{Hint, Hints},
     hunger::{self, CanRegenResult},
-    item::{self, PickUpHint},
+    inspect, item,
+    locale::Locale,
     map::{self, Map, Tile},
-    message::Messages,
-    spawn, vision,
+    message::{Messages, MsgCategory},
+    obstacle, pet, quest, spawn, status,
+    ui::Options,
+    vision,
 };
-use ruggrogue::{util::Position, InputBuffer, InputEvent, KeyMods, PathableMap};
+use ruggrogue::{util::Position, InputBuffer, InputEvent, KeyMods};
 
 #[derive(Deserialize, Serialize)]
 pub struct PlayerId(pub EntityId);
@@ -35,6 +45,7 @@ enum AutoRunType {
     RestInPlace,
     Corridor,
     Straight { expect_wall: AutoRunWallSide },
+    Travel { dest: (i32, i32) },
 }
 
 pub struct AutoRun {
@@ -43,6 +54,18 @@ pub struct AutoRun {
     run_type: AutoRunType,
 }
 
+#[derive(Clone, Copy)]
+pub enum ItemBatchKind {
+    PickUp,
+    Drop,
+}
+
+/// Items waiting to be picked up or dropped one per turn.
+pub struct ItemBatch {
+    kind: ItemBatchKind,
+    items: Vec<EntityId>,
+}
+
 pub enum PlayerInputResult {
     AppQuit,
     NoResult,
@@ -50,10 +73,26 @@ pub enum PlayerInputResult {
     TryDescend,
     ShowOptionsMenu,
     ViewMap,
+    LookList,
+    InspectMonster(EntityId),
+    PickInspectTarget,
+    ZoomMap(i32),
     ShowPickUpMenu,
     ShowInventory,
     ShowInventoryShortcut(GameKey),
     ShowEquipmentShortcut(GameKey),
+    TakeNote,
+    ShowRecentMessages,
+    ShowJournal,
+    ShowQuestLog,
+    ShowBestiary,
+    ShowInteractMenu,
+    DoAgain,
+    ToggleMacroRecording,
+    PlayMacro,
+    TalkTo(EntityId),
+    OpenChest(EntityId),
+    FallDown,
 }
 
 pub fn player_is_auto_running(player_id: UniqueView<PlayerId>, players: View<Player>) -> bool {
@@ -64,6 +103,14 @@ pub fn player_stop_auto_run(player_id: UniqueView<PlayerId>, mut players: ViewMu
     (&mut players).get(player_id.0).auto_run = None;
 }
 
+pub fn player_has_item_batch(player_id: UniqueView<PlayerId>, players: View<Player>) -> bool {
+    players.get(player_id.0).item_batch.is_some()
+}
+
+pub fn player_stop_item_batch(player_id: UniqueView<PlayerId>, mut players: ViewMut<Player>) {
+    (&mut players).get(player_id.0).item_batch = None;
+}
+
 pub fn player_sees_foes(
     map: UniqueView<Map>,
     player_id: UniqueView<PlayerId>,
@@ -88,6 +135,32 @@ pub fn can_see_player(world: &World, who: EntityId) -> bool {
     }
 }
 
+/// The map as far as the player knows it, so that travel routes stick to explored tiles.
+struct KnownMap<'a>(&'a Map);
+
+impl ruggrogue::BoundedMap for KnownMap<'_> {
+    fn bounds(&self) -> (i32, i32, i32, i32) {
+        ruggrogue::BoundedMap::bounds(self.0)
+    }
+}
+
+impl ruggrogue::PathableMap for KnownMap<'_> {
+    fn is_blocked(&self, x: i32, y: i32) -> bool {
+        !self.0.seen.get_bit(x, y) || ruggrogue::PathableMap::is_blocked(self.0, x, y)
+    }
+}
+
+/// Direction of the next step along the shortest known route from the player to `dest`, or `None`
+/// if the player is already there or there's no such route.
+fn travel_step(world: &World, dest: (i32, i32)) -> Option<(i32, i32)> {
+    let map = world.borrow::<UniqueView<Map>>();
+    let player_id = world.borrow::<UniqueView<PlayerId>>();
+    let (px, py): (i32, i32) = world.borrow::<View<Coord>>().get(player_id.0).0.into();
+    let step = ruggrogue::find_path(&KnownMap(&map), (px, py), dest, 0, false).nth(1);
+
+    step.map(|(x, y)| (x - px, y - py))
+}
+
 /// Calculate a 2-by-2 matrix to rotate any `(dx, dy)` to only face `(+dx, 0)` or `(+dx, +dy)`.
 ///
 /// Returns `real_x_from_x`, `real_x_from_y`, `real_y_from_x` and `real_y_from_y` in that order.
@@ -513,6 +586,21 @@ fn auto_run_next_step(world: &World) -> Option<(i32, i32)> {
                     None
                 }
             }
+            AutoRunType::Travel { dest } => {
+                let step = travel_step(world, dest);
+
+                if let Some(new_dir) = step {
+                    world.run(
+                        |player_id: UniqueView<PlayerId>, mut players: ViewMut<Player>| {
+                            let player = (&mut players).get(player_id.0);
+                            if let Some(ar) = &mut player.auto_run {
+                                ar.dir = new_dir;
+                            }
+                        },
+                    );
+                }
+                step
+            }
             AutoRunType::Straight { expect_wall } => {
                 if let Some(actual_wall) = auto_run_straight_check(world, dx, dy) {
                     // Ensure whatever wall we expect is still there.
@@ -531,11 +619,292 @@ fn auto_run_next_step(world: &World) -> Option<(i32, i32)> {
     }
 }
 
+/// Trade places between the player and an adjacent friendly.
+fn swap_player_with(world: &World, friendly_id: EntityId) {
+    let (locale, mut msgs, mut map, player_id, blocks, mut coords, mut fovs, names) = world
+        .borrow::<(
+            UniqueView<Locale>,
+            UniqueViewMut<Messages>,
+            UniqueViewMut<Map>,
+            UniqueView<PlayerId>,
+            View<BlocksTile>,
+            ViewMut<Coord>,
+            ViewMut<FieldOfView>,
+            View<Name>,
+        )>();
+    let player_pos = coords.get(player_id.0).0;
+    let friendly_pos = coords.get(friendly_id).0;
+
+    map.swap_entities(
+        (player_id.0, player_pos.into(), blocks.contains(player_id.0)),
+        (
+            friendly_id,
+            friendly_pos.into(),
+            blocks.contains(friendly_id),
+        ),
+    );
+    (&mut coords).get(player_id.0).0 = friendly_pos;
+    (&mut coords).get(friendly_id).0 = player_pos;
+    (&mut fovs).get(player_id.0).dirty = true;
+    if let Ok(fov) = (&mut fovs).try_get(friendly_id) {
+        fov.dirty = true;
+    }
+
+    msgs.add(locale.format(
+        "msg.swaps_places",
+        &[
+            ("who", &names.get(player_id.0).0),
+            ("other", &names.get(friendly_id).0),
+        ],
+    ));
+}
+
 pub fn try_move_player(world: &World, dx: i32, dy: i32, start_run: bool) -> PlayerInputResult {
+    let player_id = world.borrow::<UniqueView<PlayerId>>().0;
+    let confused = status::is_confused(world, player_id);
+
+    if start_run && confused {
+        world.run(
+            |locale: UniqueView<Locale>, mut msgs: UniqueViewMut<Messages>| {
+                msgs.add_as(
+                    MsgCategory::Warning,
+                    locale.get("msg.cannot_run_confused").into(),
+                )
+            },
+        );
+        return PlayerInputResult::NoResult;
+    }
+
     if start_run && world.run(player_sees_foes) {
-        world.run(|mut msgs: UniqueViewMut<Messages>| {
-            msgs.add("You cannot run while foes are near.".into())
+        world.run(
+            |locale: UniqueView<Locale>, mut msgs: UniqueViewMut<Messages>| {
+                msgs.add_as(MsgCategory::Warning, locale.get("msg.cannot_run").into())
+            },
+        );
+        return PlayerInputResult::NoResult;
+    }
+
+    // Confused moves go astray half of the time.
+    let stumble = if confused {
+        status::stumble(world, player_id)
+    } else {
+        None
+    };
+    let (dx, dy) = match stumble {
+        Some(stumble) => {
+            world.run(player_stop_auto_run);
+            world.run(
+                |locale: UniqueView<Locale>,
+                 mut msgs: UniqueViewMut<Messages>,
+                 names: View<Name>| {
+                    msgs.add_as(
+                        MsgCategory::Combat,
+                        locale.format("msg.stumbles", &[("who", &names.get(player_id).0)]),
+                    );
+                },
+            );
+            stumble
+        }
+        None => (dx, dy),
+    };
+
+    // A frightened player refuses to attack, and doesn't lose a turn over it.
+    if status::is_afraid(world, player_id) {
+        let afraid_of = world
+            .run(
+                |map: UniqueView<Map>, combat_stats: View<CombatStats>, coords: View<Coord>| {
+                    let Coord(pos) = coords.get(player_id);
+
+                    map.iter_entities_at(pos.x + dx, pos.y + dy)
+                        .find(|e| combat_stats.contains(*e))
+                },
+            )
+            .filter(|&e| faction::is_hostile(world, player_id, e));
+
+        if let Some(monster) = afraid_of {
+            world.run(player_stop_auto_run);
+            world.run(
+                |locale: UniqueView<Locale>,
+                 mut msgs: UniqueViewMut<Messages>,
+                 names: View<Name>| {
+                    msgs.add_as(
+                        MsgCategory::Warning,
+                        locale.format(
+                            "msg.too_afraid",
+                            &[
+                                ("who", &names.get(player_id).0),
+                                ("target", &names.get(monster).0),
+                            ],
+                        ),
+                    );
+                },
+            );
+            return PlayerInputResult::NoResult;
+        }
+    }
+
+    // Bumping into a friendly NPC starts a conversation instead of a move or an attack.
+    let talk_target = world.run(
+        |map: UniqueView<Map>,
+         player_id: UniqueView<PlayerId>,
+         coords: View<Coord>,
+         npcs: View<Npc>| {
+            let Coord(pos) = coords.get(player_id.0);
+
+            map.iter_entities_at(pos.x + dx, pos.y + dy)
+                .find(|e| npcs.contains(*e))
+        },
+    );
+
+    if let Some(npc_id) = talk_target {
+        world.run(player_stop_auto_run);
+        quest::turn_in_fetch_quests(world, npc_id);
+        return PlayerInputResult::TalkTo(npc_id);
+    }
+
+    // Bumping into any other friendly or an ally, such as a pet or a charmed monster, trades places
+    // with it, unless it's asleep.
+    let displaced = world
+        .run(
+            |map: UniqueView<Map>,
+             coords: View<Coord>,
+             combat_stats: View<CombatStats>,
+             friendlies: View<Friendly>| {
+                let Coord(pos) = coords.get(player_id);
+
+                map.iter_entities_at(pos.x + dx, pos.y + dy)
+                    .find(|e| friendlies.contains(*e) || combat_stats.contains(*e))
+            },
+        )
+        .filter(|&e| {
+            world.borrow::<View<Friendly>>().contains(e)
+                || faction::faction_of(world, e) == faction::faction_of(world, player_id)
         });
+
+    if let Some(friendly_id) = displaced {
+        world.run(player_stop_auto_run);
+        return if item::is_asleep(world, friendly_id) {
+            world.run(
+                |locale: UniqueView<Locale>,
+                 mut msgs: UniqueViewMut<Messages>,
+                 names: View<Name>| {
+                    msgs.add_as(
+                        MsgCategory::Warning,
+                        locale.format("msg.wont_budge", &[("who", &names.get(friendly_id).0)]),
+                    );
+                },
+            );
+            PlayerInputResult::NoResult
+        } else {
+            swap_player_with(world, friendly_id);
+            encumbrance::strain_from_move(world, player_id);
+            PlayerInputResult::TurnDone
+        };
+    }
+
+    // Walking into a boulder pushes it out of the way, and walking into a cracked wall digs through
+    // it with a pickaxe.
+    let target = world.run(|coords: View<Coord>| {
+        let Coord(pos) = coords.get(player_id);
+        (pos.x + dx, pos.y + dy)
+    });
+
+    if let Some(wall_id) = obstacle::diggable_at(world, target) {
+        world.run(player_stop_auto_run);
+        return if obstacle::carries_digger(world, player_id) {
+            obstacle::dig(world, player_id, wall_id);
+            PlayerInputResult::TurnDone
+        } else {
+            world.run(
+                |locale: UniqueView<Locale>,
+                 mut msgs: UniqueViewMut<Messages>,
+                 names: View<Name>| {
+                    msgs.add_as(
+                        MsgCategory::Warning,
+                        locale.format(
+                            "msg.needs_pickaxe",
+                            &[
+                                ("who", &names.get(player_id).0),
+                                ("what", &names.get(wall_id).0),
+                            ],
+                        ),
+                    );
+                },
+            );
+            PlayerInputResult::NoResult
+        };
+    }
+
+    let locked_door = {
+        let map = world.borrow::<UniqueView<Map>>();
+        target.0 >= 0
+            && target.1 >= 0
+            && target.0 < map.width
+            && target.1 < map.height
+            && matches!(map.get_tile(target.0, target.1), Tile::LockedDoor)
+    };
+
+    if locked_door {
+        world.run(player_stop_auto_run);
+        return if let Some(key_id) = obstacle::carried_key(world, player_id) {
+            obstacle::unlock_with_key(world, player_id, key_id, target);
+            PlayerInputResult::TurnDone
+        } else {
+            world.run(
+                |locale: UniqueView<Locale>, mut msgs: UniqueViewMut<Messages>| {
+                    msgs.add_as(MsgCategory::Warning, locale.get("msg.door_locked").into());
+                },
+            );
+            PlayerInputResult::NoResult
+        };
+    }
+
+    // Walking into the storage chest or a grave opens it.
+    if let Some(chest_id) =
+        chest::chest_at(world, target).or_else(|| bones::grave_at(world, target))
+    {
+        world.run(player_stop_auto_run);
+        return PlayerInputResult::OpenChest(chest_id);
+    }
+
+    if let Some(boulder_id) = obstacle::boulder_at(world, target) {
+        world.run(player_stop_auto_run);
+        if !obstacle::push_boulder(world, boulder_id, (dx, dy)) {
+            world.run(
+                |locale: UniqueView<Locale>,
+                 mut msgs: UniqueViewMut<Messages>,
+                 names: View<Name>| {
+                    msgs.add_as(
+                        MsgCategory::Warning,
+                        locale.format("msg.wont_budge", &[("who", &names.get(boulder_id).0)]),
+                    );
+                },
+            );
+            return PlayerInputResult::NoResult;
+        }
+    }
+
+    // Swimming through deep water means carrying little enough to stay afloat.
+    let into_deep_water = world.run(|map: UniqueView<Map>, coords: View<Coord>| {
+        let Coord(pos) = coords.get(player_id);
+
+        matches!(map.get_tile(pos.x + dx, pos.y + dy), Tile::DeepWater)
+            && map
+                .iter_entities_at(pos.x + dx, pos.y + dy)
+                .next()
+                .is_none()
+    });
+
+    if into_deep_water && encumbrance::is_burdened(world, player_id) {
+        world.run(player_stop_auto_run);
+        world.run(
+            |locale: UniqueView<Locale>, mut msgs: UniqueViewMut<Messages>, names: View<Name>| {
+                msgs.add_as(
+                    MsgCategory::Warning,
+                    locale.format("msg.too_heavy_to_swim", &[("who", &names.get(player_id).0)]),
+                );
+            },
+        );
         return PlayerInputResult::NoResult;
     }
 
@@ -561,7 +930,7 @@ pub fn try_move_player(world: &World, dx: i32, dy: i32, start_run: bool) -> Play
                     if let Some(melee_target) = melee_target {
                         melee_queue.push((id, melee_target));
                         took_time = true;
-                    } else if !map.is_blocked(new_x, new_y) {
+                    } else if !map.is_impassable(new_x, new_y) {
                         map.move_entity(id, coord.0.into(), (new_x, new_y), false);
                         coord.0 = (new_x, new_y).into();
                         fov.dirty = true;
@@ -579,6 +948,20 @@ pub fn try_move_player(world: &World, dx: i32, dy: i32, start_run: bool) -> Play
         damage::melee_attack(world, attacker, defender);
     }
 
+    if moved {
+        encumbrance::strain_from_move(world, player_id);
+
+        let over_chasm = world.run(|map: UniqueView<Map>, coords: View<Coord>| {
+            let Coord(pos) = coords.get(player_id);
+            matches!(map.get_tile(pos.x, pos.y), Tile::Chasm)
+        });
+
+        if over_chasm {
+            world.run(player_stop_auto_run);
+            return PlayerInputResult::FallDown;
+        }
+    }
+
     if start_run && moved {
         if auto_run_corridor_check(world, dx, dy).is_some() {
             // Start corridor auto run.
@@ -612,29 +995,220 @@ pub fn try_move_player(world: &World, dx: i32, dy: i32, start_run: bool) -> Play
     }
 }
 
+/// Start auto-walking the player along the shortest explored route to `dest`, taking the first
+/// step right away.  Travel stops for the same things as auto-running does.
+pub fn player_travel_to(world: &World, dest: (i32, i32)) -> PlayerInputResult {
+    let player_id = world.borrow::<UniqueView<PlayerId>>().0;
+    let warning = if status::is_confused(world, player_id) {
+        Some("msg.cannot_run_confused")
+    } else if world.run(player_sees_foes) {
+        Some("msg.cannot_run")
+    } else {
+        None
+    };
+
+    if let Some(warning) = warning {
+        world.run(
+            |locale: UniqueView<Locale>, mut msgs: UniqueViewMut<Messages>| {
+                msgs.add_as(MsgCategory::Warning, locale.get(warning).into())
+            },
+        );
+        return PlayerInputResult::NoResult;
+    }
+
+    if let Some((dx, dy)) = travel_step(world, dest) {
+        world.run(|mut players: ViewMut<Player>| {
+            (&mut players).get(player_id).auto_run = Some(AutoRun {
+                limit: 400,
+                dir: (dx, dy),
+                run_type: AutoRunType::Travel { dest },
+            });
+        });
+        try_move_player(world, dx, dy, false)
+    } else {
+        world.run(
+            |locale: UniqueView<Locale>, mut msgs: UniqueViewMut<Messages>| {
+                msgs.add_as(MsgCategory::Warning, locale.get("msg.no_route").into())
+            },
+        );
+        PlayerInputResult::NoResult
+    }
+}
+
+/// Try to descend if the player is on the down stairs, otherwise travel to them if they've been
+/// seen.
+fn player_seek_stairs(world: &World) -> PlayerInputResult {
+    let (on_stairs, stairs_pos) = {
+        let map = world.borrow::<UniqueView<Map>>();
+        let player_id = world.borrow::<UniqueView<PlayerId>>();
+        let pos = world.borrow::<View<Coord>>().get(player_id.0).0;
+        let stairs_pos = (0..map.height)
+            .flat_map(|y| (0..map.width).map(move |x| (x, y)))
+            .find(|&(x, y)| {
+                map.seen.get_bit(x, y) && matches!(map.get_tile(x, y), Tile::DownStairs)
+            });
+
+        (
+            matches!(map.get_tile(pos.x, pos.y), Tile::DownStairs),
+            stairs_pos,
+        )
+    };
+
+    if on_stairs {
+        PlayerInputResult::TryDescend
+    } else if let Some(stairs_pos) = stairs_pos {
+        player_travel_to(world, stairs_pos)
+    } else {
+        world.run(
+            |locale: UniqueView<Locale>, mut msgs: UniqueViewMut<Messages>| {
+                msgs.add_as(
+                    MsgCategory::Warning,
+                    locale.get("msg.stairs_not_found").into(),
+                )
+            },
+        );
+        PlayerInputResult::NoResult
+    }
+}
+
+/// Remember what the player has equipped as their current equipment set, then switch to the other
+/// set, equipping whichever of its items the player still carries.  Switching to a set that has
+/// never been filled keeps the current equipment and takes no time.
+fn player_swap_loadout(world: &World) -> PlayerInputResult {
+    let player_id = world.borrow::<UniqueView<PlayerId>>().0;
+    let active = world.borrow::<View<Player>>().get(player_id).active_loadout;
+    let other = 1 - active;
+    let (weapon, armor) = {
+        let equipments = world.borrow::<View<Equipment>>();
+        let equipment = equipments.get(player_id);
+
+        (equipment.weapon, equipment.armor)
+    };
+    let held = world
+        .borrow::<View<Inventory>>()
+        .get(player_id)
+        .items
+        .iter()
+        .copied()
+        .chain(weapon)
+        .chain(armor)
+        .collect::<Vec<_>>();
+    let targets = {
+        let (entities, mut in_loadouts) = world.borrow::<(EntitiesView, ViewMut<InLoadout>)>();
+
+        for id in held.iter() {
+            if let Ok(in_loadout) = (&mut in_loadouts).try_get(*id) {
+                in_loadout.0[active] = false;
+            }
+        }
+        for id in weapon.iter().chain(armor.iter()) {
+            if let Ok(in_loadout) = (&mut in_loadouts).try_get(*id) {
+                in_loadout.0[active] = true;
+            } else {
+                let mut sets = [false; 2];
+
+                sets[active] = true;
+                entities.add_component(&mut in_loadouts, InLoadout(sets), *id);
+            }
+        }
+
+        held.iter()
+            .filter(|id| matches!(in_loadouts.try_get(**id), Ok(l) if l.0[other]))
+            .copied()
+            .collect::<Vec<_>>()
+    };
+
+    (&mut world.borrow::<ViewMut<Player>>())
+        .get(player_id)
+        .active_loadout = other;
+
+    let set = (other + 1).to_string();
+
+    if targets.is_empty() {
+        world.run(
+            |locale: UniqueView<Locale>, mut msgs: UniqueViewMut<Messages>| {
+                msgs.add_as(
+                    MsgCategory::System,
+                    locale.format("msg.loadout_new", &[("set", &set)]),
+                )
+            },
+        );
+        return PlayerInputResult::NoResult;
+    }
+
+    let mut changed = false;
+
+    for (equipped, slot) in [(weapon, EquipSlot::Weapon), (armor, EquipSlot::Armor)].iter() {
+        let target = targets.iter().copied().find(|id| {
+            matches!(
+                (world.borrow::<View<EquipSlot>>().try_get(*id), slot),
+                (Ok(EquipSlot::Weapon), EquipSlot::Weapon)
+                    | (Ok(EquipSlot::Armor), EquipSlot::Armor)
+            )
+        });
+
+        match (*equipped, target) {
+            (_, Some(target)) if *equipped != Some(target) => {
+                item::equip_item(world, player_id, target);
+                changed = true;
+            }
+            (Some(equipped), None) => {
+                item::remove_equipment(world, player_id, equipped);
+                changed = true;
+            }
+            _ => {}
+        }
+    }
+
+    world.run(
+        |locale: UniqueView<Locale>, mut msgs: UniqueViewMut<Messages>| {
+            msgs.add_as(
+                MsgCategory::Item,
+                locale.format("msg.loadout_swapped", &[("set", &set)]),
+            )
+        },
+    );
+
+    if changed {
+        PlayerInputResult::TurnDone
+    } else {
+        PlayerInputResult::NoResult
+    }
+}
+
 fn wait_player(world: &World, rest_in_place: bool) -> PlayerInputResult {
     let foes_seen = world.run(player_sees_foes);
     let (player_id, mut players) = world.borrow::<(UniqueView<PlayerId>, ViewMut<Player>)>();
     let player_can_regen = hunger::can_regen(world, player_id.0);
+    let locale = world.borrow::<UniqueView<Locale>>();
     let mut msgs = world.borrow::<UniqueViewMut<Messages>>();
 
     if rest_in_place {
         if foes_seen {
-            msgs.add("You cannot rest while foes are near.".into());
+            msgs.add_as(
+                MsgCategory::Warning,
+                locale.get("msg.cannot_rest_foes").into(),
+            );
             return PlayerInputResult::NoResult;
         } else if !matches!(player_can_regen, CanRegenResult::CanRegen) {
             match player_can_regen {
                 CanRegenResult::CanRegen => unreachable!(),
-                CanRegenResult::NoRegen => msgs.add("You cannot rest to heal.".into()),
-                CanRegenResult::FullyRested => msgs.add("You are already fully rested.".into()),
-                CanRegenResult::TooHungry => msgs.add("You are too hungry to rest.".into()),
+                CanRegenResult::NoRegen => {
+                    msgs.add_as(MsgCategory::Warning, locale.get("msg.cannot_rest").into())
+                }
+                CanRegenResult::FullyRested => {
+                    msgs.add_as(MsgCategory::Warning, locale.get("msg.fully_rested").into())
+                }
+                CanRegenResult::TooHungry => {
+                    msgs.add_as(MsgCategory::Warning, locale.get("msg.too_hungry").into())
+                }
             }
             return PlayerInputResult::NoResult;
         }
 
         // Rest in place if requested.
         if matches!(player_can_regen, CanRegenResult::CanRegen) {
-            msgs.add("You tend to your wounds.".into());
+            msgs.add(locale.get("msg.tend_wounds").into());
             (&mut players).get(player_id.0).auto_run = Some(AutoRun {
                 limit: 400,
                 dir: (0, 0),
@@ -667,6 +1241,7 @@ pub fn remove_coords_from_players(mut coords: ViewMut<Coord>, players: View<Play
 }
 
 pub fn player_try_descend(
+    locale: UniqueView<Locale>,
     map: UniqueView<Map>,
     mut msgs: UniqueViewMut<Messages>,
     player_id: UniqueView<PlayerId>,
@@ -680,13 +1255,16 @@ pub fn player_try_descend(
     ) {
         true
     } else {
-        msgs.add("There is no way down here.".into());
+        msgs.add_as(MsgCategory::Warning, locale.get("msg.no_way_down").into());
         false
     }
 }
 
 pub fn player_do_descend(world: &World) {
+    quest::abandon_depth_quests(world);
     world.run(remove_coords_from_players);
+    let pet_ids = world.run(pet::remove_coords_from_pets);
+    world.run(chest::remove_coords_from_chests);
     world.run(spawn::despawn_coord_entities);
     world.run(add_coords_to_players);
 
@@ -694,64 +1272,169 @@ pub fn player_do_descend(world: &World) {
         map.clear();
         map.depth += 1;
     });
-    if let Some(victory_pos) = world.run(map::generate_rooms_and_corridors) {
+    let remains = if let Some(victory_pos) = world.run(map::generate_rooms_and_corridors) {
         spawn::spawn_present(world, victory_pos);
-    }
+        None
+    } else {
+        bones::take_bones(world)
+    };
     world.run(map::place_player_in_first_room);
+    chest::place_chest(world);
+    pet::place_pets_near_player(world, &pet_ids);
 
     world.run(experience::redeem_exp_for_next_depth);
     world.run(experience::gain_levels);
     spawn::fill_rooms_with_spawns(world);
+    if let Some(remains) = remains {
+        spawn::spawn_remains(world, &remains);
+    }
+    quest::offer_depth_quests(world);
     world.run(experience::calc_exp_for_next_depth);
 
     world.run(|mut fovs: ViewMut<FieldOfView>, players: View<Player>| {
         for (fov, _) in (&mut fovs, &players).iter() {
-            fov.dirty = true;
+            fov.invalidate();
         }
     });
     world.run(vision::recalculate_fields_of_view);
 
+    let depth = world.borrow::<UniqueView<Map>>().depth;
+    event::emit(world, GameEvent::LevelChanged { depth });
+
     world.run(
-        |map: UniqueView<Map>,
+        |locale: UniqueView<Locale>,
+         map: UniqueView<Map>,
          mut msgs: UniqueViewMut<Messages>,
          player_id: UniqueView<PlayerId>,
          names: View<Name>| {
-            msgs.add(format!(
-                "{} descends to depth {}.",
-                names.get(player_id.0).0,
-                map.depth,
+            msgs.add(locale.format(
+                "msg.descends",
+                &[("who", &names.get(player_id.0).0), ("depth", &map.depth)],
             ));
         },
     );
+    feeling::announce_level_feeling(world);
+}
+
+/// Drop the player down a chasm to the next depth.
+///
+/// The landing hurts, but never enough to kill.
+pub fn player_fall_down(world: &World) {
+    world.run(
+        |locale: UniqueView<Locale>,
+         mut msgs: UniqueViewMut<Messages>,
+         player_id: UniqueView<PlayerId>,
+         names: View<Name>| {
+            msgs.add_as(
+                MsgCategory::Warning,
+                locale.format("msg.falls", &[("who", &names.get(player_id.0).0)]),
+            );
+        },
+    );
+
+    player_do_descend(world);
+
+    world.run(
+        |player_id: UniqueView<PlayerId>,
+         mut combat_stats: ViewMut<CombatStats>,
+         mut tallies: ViewMut<Tally>| {
+            let stats = (&mut combat_stats).get(player_id.0);
+            let damage = (stats.max_hp / 10).min(stats.hp - 1).max(0);
+
+            stats.hp -= damage;
+            if let Ok(tally) = (&mut tallies).try_get(player_id.0) {
+                tally.damage_taken += damage as u64;
+            }
+        },
+    );
 }
 
-pub fn player_pick_up_item(world: &World, item_id: EntityId) {
+/// Pick up or drop the given items right away, announcing each one that moves.
+fn player_move_items(world: &World, kind: ItemBatchKind, item_ids: &[EntityId]) {
     let player_id = world.run(|player_id: UniqueView<PlayerId>| player_id.0);
 
-    item::remove_item_from_map(world, item_id);
-    item::add_item_to_inventory(world, player_id, item_id);
-    world.run(|mut msgs: UniqueViewMut<Messages>, names: View<Name>| {
-        msgs.add(format!(
-            "{} picks up {}.",
-            names.get(player_id).0,
-            names.get(item_id).0
-        ));
-    });
+    let was_burdened = encumbrance::is_burdened(world, player_id);
+
+    let (moved, msg_key) = match kind {
+        ItemBatchKind::PickUp => (
+            item::pick_up_items(world, player_id, item_ids),
+            "msg.picks_up",
+        ),
+        ItemBatchKind::Drop => (item::drop_items(world, player_id, item_ids), "msg.drops"),
+    };
+
+    world.run(
+        |locale: UniqueView<Locale>, mut msgs: UniqueViewMut<Messages>, names: View<Name>| {
+            for item_id in moved {
+                msgs.add_as(
+                    MsgCategory::Item,
+                    locale.format(
+                        msg_key,
+                        &[
+                            ("who", &names.get(player_id).0),
+                            ("item", &names.get(item_id).0),
+                        ],
+                    ),
+                );
+            }
+        },
+    );
+    encumbrance::report_player_burden_change(world, was_burdened);
+}
+
+/// Pick up or drop several items, either all in one turn or one per turn depending on options.
+fn player_start_item_batch(world: &World, kind: ItemBatchKind, item_ids: &[EntityId]) {
+    if world.borrow::<UniqueView<Options>>().turn_per_item && item_ids.len() > 1 {
+        player_move_items(world, kind, &item_ids[..1]);
+        world.run(
+            |player_id: UniqueView<PlayerId>, mut players: ViewMut<Player>| {
+                (&mut players).get(player_id.0).item_batch = Some(ItemBatch {
+                    kind,
+                    items: item_ids[1..].to_vec(),
+                });
+            },
+        );
+    } else {
+        player_move_items(world, kind, item_ids);
+    }
+}
+
+pub fn player_pick_up_items(world: &World, item_ids: &[EntityId]) {
+    player_start_item_batch(world, ItemBatchKind::PickUp, item_ids);
 }
 
 pub fn player_drop_item(world: &World, item_id: EntityId) {
-    let player_id = world.run(|player_id: UniqueView<PlayerId>| player_id.0);
-    let player_pos: (i32, i32) = world.run(|coords: View<Coord>| coords.get(player_id).0.into());
-
-    item::remove_item_from_inventory(world, player_id, item_id);
-    item::add_item_to_map(world, item_id, player_pos);
-    world.run(|mut msgs: UniqueViewMut<Messages>, names: View<Name>| {
-        msgs.add(format!(
-            "{} drops {}.",
-            names.get(player_id).0,
-            names.get(item_id).0
-        ));
-    });
+    player_move_items(world, ItemBatchKind::Drop, &[item_id]);
+}
+
+pub fn player_drop_items(world: &World, item_ids: &[EntityId]) {
+    player_start_item_batch(world, ItemBatchKind::Drop, item_ids);
+}
+
+/// Pick up or drop the next item of a batch started in an earlier turn.
+fn player_continue_item_batch(world: &World) -> PlayerInputResult {
+    let next = world.run(
+        |player_id: UniqueView<PlayerId>, mut players: ViewMut<Player>| {
+            let player = (&mut players).get(player_id.0);
+            let next = player
+                .item_batch
+                .as_mut()
+                .map(|batch| (batch.kind, batch.items.remove(0)));
+
+            if matches!(&player.item_batch, Some(batch) if batch.items.is_empty()) {
+                player.item_batch = None;
+            }
+
+            next
+        },
+    );
+
+    if let Some((kind, item_id)) = next {
+        player_move_items(world, kind, &[item_id]);
+        PlayerInputResult::TurnDone
+    } else {
+        PlayerInputResult::NoResult
+    }
 }
 
 /// Describe contents of the tile the player is on.
@@ -763,7 +1446,9 @@ pub fn describe_player_pos(world: &World) {
     };
     let map = world.borrow::<UniqueView<Map>>();
     let more_than_player = map.iter_entities_at(x, y).nth(1).is_some();
-    let pick_up_hint = world.borrow::<UniqueView<PickUpHint>>().0
+    let pick_up_hint = world
+        .borrow::<UniqueView<Hints>>()
+        .wants(&world.borrow::<UniqueView<Options>>(), Hint::PickUp)
         && map
             .iter_entities_at(x, y)
             .any(|id| world.borrow::<View<Item>>().contains(id));
@@ -773,17 +1458,30 @@ pub fn describe_player_pos(world: &World) {
         let (desc, recalled) = map.describe_pos(world, x, y, false, true, true);
         let downstairs = matches!(tile, Tile::DownStairs) && map.depth == 1;
 
-        world.borrow::<UniqueViewMut<Messages>>().add(format!(
-            "You {} {} here.{}",
-            if recalled { "recall" } else { "see" },
-            desc,
-            match (pick_up_hint, downstairs) {
-                (true, true) => " (Press 'g' to pick up, 'Enter' to descend.)",
-                (true, false) => " (Press 'g' to pick up.)",
-                (false, true) => " (Press 'Enter' to descend.)",
-                _ => "",
-            },
-        ));
+        let locale = world.borrow::<UniqueView<Locale>>();
+
+        world.borrow::<UniqueViewMut<Messages>>().add_as(
+            MsgCategory::Item,
+            locale.format(
+                if recalled {
+                    "msg.recall_here"
+                } else {
+                    "msg.see_here"
+                },
+                &[
+                    ("what", &desc),
+                    (
+                        "hint",
+                        &match (pick_up_hint, downstairs) {
+                            (true, true) => locale.get("hint.pick_up_descend"),
+                            (true, false) => locale.get("hint.pick_up"),
+                            (false, true) => locale.get("hint.descend"),
+                            _ => "",
+                        },
+                    ),
+                ],
+            ),
+        );
     }
 }
 
@@ -792,6 +1490,16 @@ pub fn player_input(world: &World, inputs: &mut InputBuffer) -> PlayerInputResul
 
     inputs.prepare_input();
 
+    if let Some(InputEvent::FocusLost) = inputs.get_input() {
+        return if world.borrow::<UniqueView<Options>>().pause_on_focus_loss {
+            world.run(player_stop_auto_run);
+            world.run(player_stop_item_batch);
+            PlayerInputResult::ShowOptionsMenu
+        } else {
+            PlayerInputResult::NoResult
+        };
+    }
+
     if item::is_asleep(world, player_id.0) {
         if let Some(InputEvent::AppQuit) = inputs.get_input() {
             PlayerInputResult::AppQuit
@@ -806,9 +1514,19 @@ pub fn player_input(world: &World, inputs: &mut InputBuffer) -> PlayerInputResul
             match key {
                 GameKey::Cancel => PlayerInputResult::ShowOptionsMenu,
                 _ => {
-                    world.run(|mut msgs: UniqueViewMut<Messages>, names: View<Name>| {
-                        msgs.add(format!("{} is sleeping.", names.get(player_id.0).0));
-                    });
+                    world.run(
+                        |locale: UniqueView<Locale>,
+                         mut msgs: UniqueViewMut<Messages>,
+                         names: View<Name>| {
+                            msgs.add_as(
+                                MsgCategory::Combat,
+                                locale.format(
+                                    "msg.is_sleeping",
+                                    &[("who", &names.get(player_id.0).0)],
+                                ),
+                            );
+                        },
+                    );
                     item::handle_sleep_turn(world, player_id.0);
                     PlayerInputResult::TurnDone
                 }
@@ -854,6 +1572,18 @@ pub fn player_input(world: &World, inputs: &mut InputBuffer) -> PlayerInputResul
                 PlayerInputResult::NoResult
             }
         }
+    } else if world.run(player_has_item_batch) {
+        if matches!(inputs.get_input(), Some(InputEvent::AppQuit)) {
+            world.run(player_stop_item_batch);
+            PlayerInputResult::AppQuit
+        } else if matches!(inputs.get_input(), Some(InputEvent::Press(_)))
+            || world.run(player_sees_foes)
+        {
+            world.run(player_stop_item_batch);
+            PlayerInputResult::NoResult
+        } else {
+            player_continue_item_batch(world)
+        }
     } else if let Some(InputEvent::AppQuit) = inputs.get_input() {
         PlayerInputResult::AppQuit
     } else if let Some(InputEvent::Press(keycode)) = inputs.get_input() {
@@ -876,13 +1606,28 @@ pub fn player_input(world: &World, inputs: &mut InputBuffer) -> PlayerInputResul
             GameKey::Wait => wait_player(world, shift),
             GameKey::Cancel => PlayerInputResult::ShowOptionsMenu,
             GameKey::ViewMap => PlayerInputResult::ViewMap,
-            GameKey::Descend | GameKey::Confirm => PlayerInputResult::TryDescend,
+            GameKey::LookList => PlayerInputResult::LookList,
+            GameKey::Inspect => inspect::player_inspect(world),
+            GameKey::ZoomIn => PlayerInputResult::ZoomMap(1),
+            GameKey::ZoomOut => PlayerInputResult::ZoomMap(-1),
+            GameKey::Descend => player_seek_stairs(world),
+            GameKey::Confirm => PlayerInputResult::TryDescend,
             GameKey::PickUp => PlayerInputResult::ShowPickUpMenu,
             GameKey::Inventory => PlayerInputResult::ShowInventory,
             key @ GameKey::UseItem | key @ GameKey::EquipItem | key @ GameKey::DropItem => {
                 PlayerInputResult::ShowInventoryShortcut(key)
             }
             key @ GameKey::RemoveItem => PlayerInputResult::ShowEquipmentShortcut(key),
+            GameKey::TakeNote => PlayerInputResult::TakeNote,
+            GameKey::RecentMessages => PlayerInputResult::ShowRecentMessages,
+            GameKey::Journal => PlayerInputResult::ShowJournal,
+            GameKey::QuestLog => PlayerInputResult::ShowQuestLog,
+            GameKey::Bestiary => PlayerInputResult::ShowBestiary,
+            GameKey::Interact => PlayerInputResult::ShowInteractMenu,
+            GameKey::DoAgain => PlayerInputResult::DoAgain,
+            GameKey::RecordMacro => PlayerInputResult::ToggleMacroRecording,
+            GameKey::PlayMacro => PlayerInputResult::PlayMacro,
+            GameKey::SwapLoadout => player_swap_loadout(world),
             _ => PlayerInputResult::NoResult,
         }
     } else {
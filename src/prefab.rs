@@ -0,0 +1,370 @@
+use rand::{seq::SliceRandom, Rng};
+use serde::Deserialize;
+use std::fs;
+
+use crate::{
+    bitgrid::BitGrid,
+    map::{Map, Rect, Tile},
+};
+
+const PREFABS_PATH: &str = "assets/prefabs.json";
+
+/// Attempts made to fit a chosen prefab into a room before giving up on it.
+const STAMP_ATTEMPTS: usize = 10;
+
+/// What a prefab is for, which decides how often it shows up.
+#[derive(Clone, Copy, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum PrefabKind {
+    /// An oddly-shaped room with nothing special in it.
+    Room,
+    /// A room with loot that's guaranteed to be there, usually behind something awkward.
+    Vault,
+    /// Loot used as bait amongst hazards, with monsters lying in wait.
+    Trap,
+}
+
+impl PrefabKind {
+    /// Most prefabs of this kind that can be placed on one level, and the odds of placing each.
+    fn placements(self) -> (usize, u32, u32) {
+        match self {
+            PrefabKind::Room => (2, 1, 2),
+            PrefabKind::Vault => (1, 1, 4),
+            PrefabKind::Trap => (1, 1, 5),
+        }
+    }
+}
+
+/// Something to spawn at a spot of a stamped prefab once the level has been generated.
+#[derive(Clone, Copy)]
+pub enum PrefabMark {
+    /// Gold or better-than-usual equipment.
+    Treasure,
+    /// A monster waiting for the player to show up.
+    Lurker,
+}
+
+/// A hand-authored room layout that can be stamped into levels, as defined in the prefabs file.
+///
+/// Each row of the layout is a string, using these characters:
+///
+/// - `#` wall
+/// - `.` floor
+/// - `~` shallow water
+/// - `w` deep water
+/// - `l` lava
+/// - `:` chasm
+/// - `$` floor with treasure
+/// - `M` floor with a lurking monster
+#[derive(Deserialize)]
+pub struct Prefab {
+    pub name: String,
+    pub kind: PrefabKind,
+    #[serde(default = "default_min_depth")]
+    pub min_depth: i32,
+    #[serde(default = "default_weight")]
+    pub weight: u32,
+    pub layout: Vec<String>,
+}
+
+fn default_min_depth() -> i32 {
+    1
+}
+
+fn default_weight() -> u32 {
+    1
+}
+
+fn parse_cell(c: char) -> Option<(Tile, Option<PrefabMark>)> {
+    match c {
+        '#' => Some((Tile::Wall, None)),
+        '.' => Some((Tile::Floor, None)),
+        '~' => Some((Tile::ShallowWater, None)),
+        'w' => Some((Tile::DeepWater, None)),
+        'l' => Some((Tile::Lava, None)),
+        ':' => Some((Tile::Chasm, None)),
+        '$' => Some((Tile::Floor, Some(PrefabMark::Treasure))),
+        'M' => Some((Tile::Floor, Some(PrefabMark::Lurker))),
+        _ => None,
+    }
+}
+
+/// True for tiles that can be walked through safely, for the sake of checking that stamping a
+/// prefab doesn't cut anything off.
+fn is_open(tile: Tile) -> bool {
+    matches!(tile, Tile::Floor | Tile::ShallowWater | Tile::DownStairs)
+}
+
+/// Count the open cells of a `w` by `h` grid that can't be reached by stepping across open cells
+/// from any of the given starting cells.
+fn count_unreached<F>(w: i32, h: i32, starts: &[(i32, i32)], open: F) -> usize
+where
+    F: Fn(i32, i32) -> bool,
+{
+    let mut unreached = BitGrid::new(w, h);
+    let mut reached = BitGrid::new(w, h);
+
+    for y in 0..h {
+        for x in 0..w {
+            unreached.set_bit(x, y, open(x, y));
+        }
+    }
+    for &(x, y) in starts {
+        reached.flood_fill(x, y, false, &open);
+    }
+    unreached.difference_with(&reached);
+
+    unreached.count_ones()
+}
+
+/// A prefab layout turned into tiles, possibly rotated and mirrored.
+struct Stamp {
+    w: i32,
+    h: i32,
+    cells: Vec<(Tile, Option<PrefabMark>)>,
+}
+
+impl Stamp {
+    fn new(layout: &[String]) -> Result<Self, String> {
+        let h = layout.len() as i32;
+        let w = layout.first().map_or(0, |row| row.chars().count()) as i32;
+
+        if w == 0 || h == 0 {
+            return Err("layout is empty".to_string());
+        }
+
+        let mut cells = Vec::with_capacity((w * h) as usize);
+
+        for (y, row) in layout.iter().enumerate() {
+            if row.chars().count() as i32 != w {
+                return Err(format!("layout row {} is not {} wide", y + 1, w));
+            }
+            for c in row.chars() {
+                match parse_cell(c) {
+                    Some(cell) => cells.push(cell),
+                    None => return Err(format!("layout has unknown character '{}'", c)),
+                }
+            }
+        }
+
+        Ok(Self { w, h, cells })
+    }
+
+    fn get(&self, x: i32, y: i32) -> (Tile, Option<PrefabMark>) {
+        self.cells[(y * self.w + x) as usize]
+    }
+
+    /// Rotate a quarter turn clockwise.
+    fn rotated(&self) -> Self {
+        let mut cells = Vec::with_capacity(self.cells.len());
+
+        for y in 0..self.w {
+            for x in 0..self.h {
+                cells.push(self.get(y, self.h - 1 - x));
+            }
+        }
+
+        Self {
+            w: self.h,
+            h: self.w,
+            cells,
+        }
+    }
+
+    /// Flip left to right.
+    fn mirrored(&self) -> Self {
+        let mut cells = Vec::with_capacity(self.cells.len());
+
+        for y in 0..self.h {
+            for x in 0..self.w {
+                cells.push(self.get(self.w - 1 - x, y));
+            }
+        }
+
+        Self {
+            w: self.w,
+            h: self.h,
+            cells,
+        }
+    }
+
+    /// Check that the layout can be entered from its edges and that none of its open cells are cut
+    /// off from the rest of it.
+    fn validate_connectivity(&self) -> Result<(), String> {
+        let open = |x: i32, y: i32| is_open(self.get(x, y).0);
+        let edges = (0..self.h)
+            .flat_map(|y| (0..self.w).map(move |x| (x, y)))
+            .filter(|&(x, y)| x == 0 || y == 0 || x == self.w - 1 || y == self.h - 1)
+            .collect::<Vec<_>>();
+
+        if !edges.iter().any(|&(x, y)| open(x, y)) {
+            return Err("layout has no way in from its edges".to_string());
+        }
+
+        if count_unreached(self.w, self.h, &edges, open) > 0 {
+            return Err("layout has open cells that can't be reached from its edges".to_string());
+        }
+
+        Ok(())
+    }
+}
+
+/// Prefabs loaded from the prefabs file.
+#[derive(Default)]
+pub struct Prefabs(Vec<Prefab>);
+
+/// Load prefabs from the prefabs file, if it exists.
+///
+/// Prefabs with malformed layouts, or layouts with open cells that can't be reached from their
+/// edges, are skipped with a warning.
+pub fn load_prefabs() -> Prefabs {
+    let prefabs = match fs::read_to_string(PREFABS_PATH) {
+        Ok(contents) => match serde_json::from_str::<Vec<Prefab>>(&contents) {
+            Ok(prefabs) => prefabs,
+            Err(e) => {
+                eprintln!("Warning: Failed to parse {}: {}", PREFABS_PATH, e);
+                Vec::new()
+            }
+        },
+        Err(e) => {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                eprintln!("Warning: Failed to read {}: {}", PREFABS_PATH, e);
+            }
+            Vec::new()
+        }
+    };
+
+    Prefabs(
+        prefabs
+            .into_iter()
+            .filter(|prefab| {
+                match Stamp::new(&prefab.layout).and_then(|s| s.validate_connectivity()) {
+                    Ok(()) => true,
+                    Err(e) => {
+                        eprintln!("Warning: Skipping prefab '{}': {}", prefab.name, e);
+                        false
+                    }
+                }
+            })
+            .collect(),
+    )
+}
+
+/// True if every open tile on the map can be reached from every other one.
+fn map_is_connected(map: &Map) -> bool {
+    let open = |x: i32, y: i32| is_open(*map.get_tile(x, y));
+    let start = (0..map.height)
+        .flat_map(|y| (0..map.width).map(move |x| (x, y)))
+        .find(|&(x, y)| open(x, y));
+    let start = match start {
+        Some(start) => start,
+        None => return true,
+    };
+
+    count_unreached(map.width, map.height, &[start], open) == 0
+}
+
+/// Try to stamp a prefab somewhere inside a room, undoing it if it would cut off any part of the
+/// map.  Returns true if the prefab was placed.
+fn stamp_in_room<R: Rng>(map: &mut Map, rng: &mut R, stamp: &Stamp, room: &Rect) -> bool {
+    let room_w = room.x2 - room.x1 + 1;
+    let room_h = room.y2 - room.y1 + 1;
+
+    if stamp.w > room_w || stamp.h > room_h {
+        return false;
+    }
+
+    let x = rng.gen_range(room.x1..=room.x2 - stamp.w + 1);
+    let y = rng.gen_range(room.y1..=room.y2 - stamp.h + 1);
+    let area = Rect::new(x, y, stamp.w, stamp.h);
+    let old_tiles = area
+        .iter_xy()
+        .map(|(tx, ty)| *map.get_tile(tx, ty))
+        .collect::<Vec<_>>();
+
+    for (tx, ty) in area.iter_xy() {
+        map.set_tile(tx, ty, stamp.get(tx - x, ty - y).0);
+    }
+
+    if map_is_connected(map) {
+        for (tx, ty) in area.iter_xy() {
+            if let Some(mark) = stamp.get(tx - x, ty - y).1 {
+                map.prefab_marks.push(((tx, ty), mark));
+            }
+        }
+        true
+    } else {
+        for ((tx, ty), tile) in area.iter_xy().zip(old_tiles) {
+            map.set_tile(tx, ty, tile);
+        }
+        false
+    }
+}
+
+/// Stamp prefabs into rooms of the map, randomly rotated and mirrored.
+///
+/// Only rooms that are still plain floor are used, and the first and last rooms are left alone to
+/// keep the arrival point and the way down clear.  A prefab is only kept if every open tile of the
+/// map can still be reached afterwards.  Prefabs with chasms are left out unless `allow_chasms` is
+/// set.
+pub fn add_prefabs<R: Rng>(map: &mut Map, rng: &mut R, prefabs: &Prefabs, allow_chasms: bool) {
+    let mut free_rooms = map
+        .rooms
+        .iter()
+        .skip(1)
+        .take(map.rooms.len().saturating_sub(2))
+        .filter(|room| {
+            room.iter_xy()
+                .all(|(x, y)| matches!(map.get_tile(x, y), Tile::Floor))
+        })
+        .copied()
+        .collect::<Vec<_>>();
+
+    for kind in [PrefabKind::Vault, PrefabKind::Trap, PrefabKind::Room]
+        .iter()
+        .copied()
+    {
+        let (most, numerator, denominator) = kind.placements();
+
+        for _ in 0..most {
+            if !rng.gen_ratio(numerator, denominator) {
+                continue;
+            }
+
+            let candidates = prefabs
+                .0
+                .iter()
+                .filter(|p| p.kind == kind && p.min_depth <= map.depth)
+                .filter(|p| allow_chasms || !p.layout.iter().any(|row| row.contains(':')))
+                .collect::<Vec<_>>();
+            let prefab = match candidates.choose_weighted(rng, |p| p.weight) {
+                Ok(prefab) => prefab,
+                Err(_) => break,
+            };
+            let mut stamp = match Stamp::new(&prefab.layout) {
+                Ok(stamp) => stamp,
+                Err(_) => break,
+            };
+
+            for _ in 0..rng.gen_range(0..4) {
+                stamp = stamp.rotated();
+            }
+            if rng.gen() {
+                stamp = stamp.mirrored();
+            }
+
+            for _ in 0..STAMP_ATTEMPTS {
+                if free_rooms.is_empty() {
+                    return;
+                }
+
+                let i = rng.gen_range(0..free_rooms.len());
+
+                if stamp_in_room(map, rng, &stamp, &free_rooms[i]) {
+                    free_rooms.swap_remove(i);
+                    break;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,463 @@
+use rand::{
+    seq::{IteratorRandom, SliceRandom},
+    Rng,
+};
+use serde::{Deserialize, Serialize};
+use shipyard::{
+    AllStoragesViewMut, EntityId, Get, IntoIter, Shiperator, UniqueView, UniqueViewMut, View,
+    ViewMut, World,
+};
+use std::collections::HashMap;
+
+use crate::{
+    components::{
+        Coord, Experience, GivesExperience, Inventory, Monster, Name, NameSource, Npc, QuestItem,
+    },
+    experience,
+    locale::Locale,
+    map::{Map, Tile},
+    message::{Messages, MsgCategory},
+    npc::GiftItem,
+    player::PlayerId,
+    rng::RngStream,
+    spawn, GameSeed,
+};
+
+/// Things an NPC might have lost somewhere on its level.
+const LOST_ITEMS: &[&str] = &[
+    "Silver Locket",
+    "Music Box",
+    "Family Ring",
+    "Old Compass",
+    "Lucky Coin",
+    "Worn Diary",
+];
+
+/// Items that can be handed out for completing a quest.
+const REWARD_ITEMS: &[GiftItem] = &[
+    GiftItem::Ration,
+    GiftItem::HealthPotion,
+    GiftItem::MagicMissileScroll,
+    GiftItem::FireballScroll,
+    GiftItem::SleepScroll,
+];
+
+/// What has to be done to complete a quest.
+#[derive(Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum QuestGoal {
+    /// Slay a number of one kind of monster on the depth the quest was given.
+    Kill {
+        monster: String,
+        needed: u32,
+        killed: u32,
+    },
+    /// Find an item on the depth the quest was given and bring it back to an NPC.
+    Fetch {
+        item: String,
+        /// ID of the definition of the NPC that wants the item back.
+        npc: String,
+        giver: String,
+    },
+}
+
+#[derive(Clone, Copy, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QuestState {
+    Active,
+    Completed,
+    Failed,
+}
+
+/// A procedurally generated quest tied to a single depth.
+#[derive(Deserialize, Serialize)]
+pub struct Quest {
+    pub id: u32,
+    pub goal: QuestGoal,
+    /// Dungeon depth where the quest was given, and where it has to be completed.
+    pub depth: i32,
+    pub state: QuestState,
+    pub reward_exp: u64,
+    pub reward: Option<GiftItem>,
+}
+
+impl Quest {
+    /// A short description of the quest for the quest log.
+    pub fn describe(&self, locale: &Locale) -> String {
+        match &self.goal {
+            QuestGoal::Kill {
+                monster,
+                needed,
+                killed,
+            } => format!(
+                "{} ({}/{})",
+                locale.format(
+                    "quest.kill",
+                    &[("count", needed), ("monster", &locale.name(monster))],
+                ),
+                killed,
+                needed
+            ),
+            QuestGoal::Fetch { item, giver, .. } => locale.format(
+                "quest.fetch",
+                &[("item", &locale.name(item)), ("giver", giver)],
+            ),
+        }
+    }
+}
+
+/// Procedural quests of the current game.
+#[derive(Default, Deserialize, Serialize)]
+pub struct Quests {
+    quests: Vec<Quest>,
+    next_id: u32,
+}
+
+impl Quests {
+    pub fn replace(&mut self, replacement: Self) {
+        self.quests = replacement.quests;
+        self.next_id = replacement.next_id;
+    }
+
+    pub fn reset(&mut self) {
+        self.quests.clear();
+        self.next_id = 0;
+    }
+
+    fn add(
+        &mut self,
+        goal: QuestGoal,
+        depth: i32,
+        reward_exp: u64,
+        reward: Option<GiftItem>,
+    ) -> u32 {
+        let id = self.next_id;
+
+        self.next_id += 1;
+        self.quests.push(Quest {
+            id,
+            goal,
+            depth,
+            state: QuestState::Active,
+            reward_exp,
+            reward,
+        });
+
+        id
+    }
+
+    fn get_mut(&mut self, id: u32) -> Option<&mut Quest> {
+        self.quests.iter_mut().find(|q| q.id == id)
+    }
+
+    pub fn active(&self) -> impl Iterator<Item = &Quest> {
+        self.quests
+            .iter()
+            .filter(|q| matches!(q.state, QuestState::Active))
+    }
+
+    pub fn finished(&self) -> impl Iterator<Item = &Quest> {
+        self.quests
+            .iter()
+            .filter(|q| !matches!(q.state, QuestState::Active))
+    }
+}
+
+/// Hand out new quests for the level that was just generated.
+///
+/// A kill quest may be given for a kind of monster that appears more than once, and an NPC on the
+/// level may ask for a lost item that is then hidden in another room.
+pub fn offer_depth_quests(world: &World) {
+    let depth = world.borrow::<UniqueView<Map>>().depth;
+    let mut rng = world
+        .borrow::<UniqueView<GameSeed>>()
+        .fork(RngStream::Quests)
+        .mix_i32(depth)
+        .rng();
+
+    if rng.gen_ratio(1, 2) {
+        let target = {
+            let coords = world.borrow::<View<Coord>>();
+            let gives_exps = world.borrow::<View<GivesExperience>>();
+            let monsters = world.borrow::<View<Monster>>();
+            let name_sources = world.borrow::<View<NameSource>>();
+            let mut counts: HashMap<&str, (u32, u64)> = HashMap::new();
+
+            for (id, _) in (&monsters, &coords).iter().with_id() {
+                let exp = gives_exps.try_get(id).map_or(0, |e| e.0);
+                let count = counts.entry(name_sources.get(id).key()).or_insert((0, 0));
+
+                count.0 += 1;
+                count.1 = count.1.max(exp);
+            }
+
+            // Sort for a stable choice regardless of hash order.
+            let mut candidates = counts
+                .into_iter()
+                .filter(|(_, (count, _))| *count >= 2)
+                .map(|(name, (count, exp))| (name.to_string(), count, exp))
+                .collect::<Vec<_>>();
+            candidates.sort();
+            candidates.into_iter().choose(&mut rng)
+        };
+
+        if let Some((monster, count, exp)) = target {
+            let needed = rng.gen_range(2..=count.min(4));
+            let reward = REWARD_ITEMS.choose(&mut rng).copied();
+            let reward_exp = exp * needed as u64 / 2;
+
+            world.borrow::<UniqueViewMut<Quests>>().add(
+                QuestGoal::Kill {
+                    monster: monster.clone(),
+                    needed,
+                    killed: 0,
+                },
+                depth,
+                reward_exp,
+                reward,
+            );
+            world.run(
+                |locale: UniqueView<Locale>, mut msgs: UniqueViewMut<Messages>| {
+                    let monster_name = locale.name(&monster);
+
+                    msgs.add(locale.format(
+                        "msg.quest_kill",
+                        &[("count", &needed), ("monster", &monster_name)],
+                    ));
+                },
+            );
+        }
+    }
+
+    let giver = world.run(|npcs: View<Npc>, coords: View<Coord>, names: View<Name>| {
+        (&npcs, &coords)
+            .iter()
+            .with_id()
+            .map(|(id, (npc, coord))| {
+                let pos: (i32, i32) = coord.0.into();
+                (npc.def_id.clone(), names.get(id).0.clone(), pos)
+            })
+            .next()
+    });
+
+    if let Some((npc, giver, npc_pos)) = giver {
+        if !rng.gen_ratio(1, 2) {
+            return;
+        }
+
+        let pos = {
+            let map = world.borrow::<UniqueView<Map>>();
+
+            map.rooms
+                .iter()
+                .skip(1)
+                .filter(|room| !room.contains(npc_pos.0, npc_pos.1))
+                .choose(&mut rng)
+                .and_then(|room| {
+                    room.iter_xy()
+                        .filter(|&(x, y)| {
+                            matches!(map.get_tile(x, y), Tile::Floor)
+                                && map.iter_entities_at(x, y).next().is_none()
+                        })
+                        .choose(&mut rng)
+                })
+        };
+
+        if let Some(pos) = pos {
+            let item = LOST_ITEMS.choose(&mut rng).unwrap().to_string();
+            let item_name = world.borrow::<UniqueView<Locale>>().name(&item);
+            let reward = REWARD_ITEMS.choose(&mut rng).copied();
+            let reward_exp = experience::calc_monster_exp(depth);
+            let id = world.borrow::<UniqueViewMut<Quests>>().add(
+                QuestGoal::Fetch {
+                    item: item.clone(),
+                    npc,
+                    giver: giver.clone(),
+                },
+                depth,
+                reward_exp,
+                reward,
+            );
+
+            spawn::spawn_quest_item(world, pos, NameSource::Plain(item), id);
+            world.run(
+                |locale: UniqueView<Locale>, mut msgs: UniqueViewMut<Messages>| {
+                    msgs.add(locale.format(
+                        "msg.quest_fetch",
+                        &[("giver", &giver), ("item", &item_name)],
+                    ));
+                },
+            );
+        }
+    }
+}
+
+/// Fail every quest still active on the current depth, since it's about to be left behind.
+pub fn abandon_depth_quests(world: &World) {
+    let depth = world.borrow::<UniqueView<Map>>().depth;
+    let mut quests = world.borrow::<UniqueViewMut<Quests>>();
+    let mut abandoned = false;
+
+    for quest in quests.quests.iter_mut() {
+        if quest.depth == depth && matches!(quest.state, QuestState::Active) {
+            quest.state = QuestState::Failed;
+            abandoned = true;
+        }
+    }
+
+    if abandoned {
+        let locale = world.borrow::<UniqueView<Locale>>();
+
+        world.borrow::<UniqueViewMut<Messages>>().add_as(
+            MsgCategory::Warning,
+            locale.get("msg.quests_abandoned").into(),
+        );
+    }
+}
+
+/// Count a monster slain by the player towards any kill quests for its kind.
+pub fn record_kill(world: &World, monster_name: &str) {
+    let depth = world.borrow::<UniqueView<Map>>().depth;
+    let mut done = Vec::new();
+
+    {
+        let locale = world.borrow::<UniqueView<Locale>>();
+        let mut msgs = world.borrow::<UniqueViewMut<Messages>>();
+        let mut quests = world.borrow::<UniqueViewMut<Quests>>();
+
+        for quest in quests.quests.iter_mut() {
+            if quest.depth != depth || !matches!(quest.state, QuestState::Active) {
+                continue;
+            }
+
+            if let QuestGoal::Kill {
+                monster,
+                needed,
+                killed,
+            } = &mut quest.goal
+            {
+                if monster == monster_name {
+                    *killed += 1;
+                    if *killed >= *needed {
+                        done.push(quest.id);
+                    } else {
+                        msgs.add(locale.format(
+                            "msg.quest_progress",
+                            &[
+                                ("killed", killed),
+                                ("count", needed),
+                                ("monster", &locale.name(monster)),
+                            ],
+                        ));
+                    }
+                }
+            }
+        }
+    }
+
+    for id in done {
+        complete_quest(world, id);
+    }
+}
+
+/// Hand over any lost items that the given NPC asked the player to find.
+pub fn turn_in_fetch_quests(world: &World, npc_id: EntityId) {
+    let player_id = world.borrow::<UniqueView<PlayerId>>().0;
+    let handed_over = {
+        let quests = world.borrow::<UniqueView<Quests>>();
+        let inventories = world.borrow::<View<Inventory>>();
+        let npcs = world.borrow::<View<Npc>>();
+        let quest_items = world.borrow::<View<QuestItem>>();
+        let def_id = &npcs.get(npc_id).def_id;
+
+        inventories
+            .get(player_id)
+            .items
+            .iter()
+            .filter_map(|&item_id| {
+                let quest_id = quest_items.try_get(item_id).ok()?.0;
+                let wanted = quests.active().any(|q| {
+                    q.id == quest_id
+                        && matches!(&q.goal, QuestGoal::Fetch { npc, .. } if npc == def_id)
+                });
+
+                if wanted {
+                    Some((item_id, quest_id))
+                } else {
+                    None
+                }
+            })
+            .collect::<Vec<_>>()
+    };
+
+    for (item_id, quest_id) in handed_over {
+        world.run(
+            |locale: UniqueView<Locale>, mut msgs: UniqueViewMut<Messages>, names: View<Name>| {
+                msgs.add_as(
+                    MsgCategory::Item,
+                    locale.format(
+                        "msg.hands_over",
+                        &[
+                            ("who", &names.get(player_id).0),
+                            ("item", &names.get(item_id).0),
+                            ("npc", &names.get(npc_id).0),
+                        ],
+                    ),
+                );
+            },
+        );
+        (&mut world.borrow::<ViewMut<Inventory>>())
+            .get(player_id)
+            .items
+            .retain(|&id| id != item_id);
+        spawn::despawn_entity(&mut world.borrow::<AllStoragesViewMut>(), item_id);
+        complete_quest(world, quest_id);
+    }
+}
+
+/// Mark a quest as completed and grant its experience and item rewards to the player.
+fn complete_quest(world: &World, id: u32) {
+    let (reward_exp, reward) = match world.borrow::<UniqueViewMut<Quests>>().get_mut(id) {
+        Some(quest) => {
+            quest.state = QuestState::Completed;
+            (quest.reward_exp, quest.reward)
+        }
+        None => return,
+    };
+    let player_id = world.borrow::<UniqueView<PlayerId>>().0;
+
+    if let Ok(exp) = (&mut world.borrow::<ViewMut<Experience>>()).try_get(player_id) {
+        exp.exp += reward_exp;
+    }
+
+    world.run(
+        |locale: UniqueView<Locale>, mut msgs: UniqueViewMut<Messages>, names: View<Name>| {
+            msgs.add_as(
+                MsgCategory::Item,
+                locale.format(
+                    "msg.quest_complete",
+                    &[("who", &names.get(player_id).0), ("exp", &reward_exp)],
+                ),
+            );
+            if let Some(reward) = reward {
+                msgs.add_as(
+                    MsgCategory::Item,
+                    locale.format(
+                        "msg.quest_reward",
+                        &[
+                            ("who", &names.get(player_id).0),
+                            ("item", &locale.name(reward.name())),
+                        ],
+                    ),
+                );
+            }
+        },
+    );
+
+    if let Some(reward) = reward {
+        let pos: (i32, i32) = world.borrow::<View<Coord>>().get(player_id).0.into();
+        spawn::spawn_gift(world, pos, reward);
+    }
+
+    world.run(experience::gain_levels);
+}
@@ -0,0 +1,209 @@
+use serde::Serialize;
+use shipyard::{Get, UniqueView, UniqueViewMut, View, World};
+use std::{
+    fmt::Write as _,
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::Path,
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    components::{CombatStats, Coord, FieldOfView},
+    map::Map,
+    player::PlayerId,
+    screenshot,
+    ui::Options,
+    TurnCount,
+};
+
+/// Directory that session recordings are written to.
+const RECORDING_DIR: &str = "recordings";
+
+/// Terminal size of recordings; the map takes up every row except the last, which shows status.
+const WIDTH: i32 = 80;
+const HEIGHT: i32 = 24;
+
+/// Pauses between turns longer than this many seconds are shortened when played back.
+const IDLE_TIME_LIMIT: f64 = 2.0;
+
+/// First line of an asciicast v2 file.
+#[derive(Serialize)]
+struct Header {
+    version: u32,
+    width: i32,
+    height: i32,
+    timestamp: u64,
+    idle_time_limit: f64,
+    title: &'static str,
+}
+
+/// Writes the map around the player each turn to an asciicast v2 file, which can be played back in
+/// a terminal, e.g. with `asciinema play`.
+///
+/// Recording starts with the first turn taken while the record option is on and ends with the
+/// game.  Each game gets a file of its own.
+pub struct Recorder {
+    writer: Option<BufWriter<File>>,
+    start: Instant,
+    /// Set when a recording couldn't be started, to avoid trying again every turn.
+    failed: bool,
+}
+
+impl Recorder {
+    pub fn new() -> Self {
+        Self {
+            writer: None,
+            start: Instant::now(),
+            failed: false,
+        }
+    }
+
+    fn begin(&mut self) -> io::Result<()> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let path = Path::new(RECORDING_DIR).join(format!("ruggrogue-{}.cast", timestamp));
+        let header = Header {
+            version: 2,
+            width: WIDTH,
+            height: HEIGHT,
+            timestamp,
+            idle_time_limit: IDLE_TIME_LIMIT,
+            title: "RuggRogue",
+        };
+
+        fs::create_dir_all(RECORDING_DIR)?;
+
+        let mut writer = BufWriter::new(File::create(path)?);
+
+        writeln!(writer, "{}", serde_json::to_string(&header)?)?;
+        // Start the first frame with a cleared screen.
+        writeln!(writer, "{}", serde_json::to_string(&(0.0, "o", "\x1b[2J"))?)?;
+
+        self.writer = Some(writer);
+        self.start = Instant::now();
+
+        Ok(())
+    }
+
+    fn write_frame(&mut self, frame: &str) -> io::Result<()> {
+        if let Some(writer) = &mut self.writer {
+            let elapsed = self.start.elapsed().as_secs_f64();
+
+            writeln!(writer, "{}", serde_json::to_string(&(elapsed, "o", frame))?)?;
+        }
+
+        Ok(())
+    }
+
+    /// Finish the current recording, if any.
+    pub fn stop(&mut self) {
+        if let Some(mut writer) = self.writer.take() {
+            if let Err(e) = writer.flush() {
+                eprintln!("Warning: recording::stop: {}", e);
+            }
+        }
+        self.failed = false;
+    }
+}
+
+/// Draw the map around the player and a status line as text with ANSI color codes, starting from
+/// the top-left of the terminal.
+fn draw_frame(world: &World) -> String {
+    let cells = screenshot::level_cells(world, false);
+    let (map, options, player_id, combat_stats, coords, fovs) = world.borrow::<(
+        UniqueView<Map>,
+        UniqueView<Options>,
+        UniqueView<PlayerId>,
+        View<CombatStats>,
+        View<Coord>,
+        View<FieldOfView>,
+    )>();
+    let player_pos = coords.get(player_id.0).0;
+    let fov = fovs.get(player_id.0);
+    let left = player_pos.x - WIDTH / 2;
+    let top = player_pos.y - (HEIGHT - 1) / 2;
+    let mut frame = String::from("\x1b[H");
+
+    for y in top..top + HEIGHT - 1 {
+        let mut last_fg = None;
+
+        for x in left..left + WIDTH {
+            let cell = if x >= 0 && y >= 0 && x < map.width && y < map.height {
+                cells[(y * map.width + x) as usize].and_then(|(sym, fg)| {
+                    if fov.get((x, y)) {
+                        Some((sym, fg))
+                    } else {
+                        options.fog_style.apply(fg).map(|fg| (sym, fg))
+                    }
+                })
+            } else {
+                None
+            };
+
+            match cell {
+                Some((sym, fg)) => {
+                    if last_fg != Some(fg) {
+                        write!(frame, "\x1b[38;2;{};{};{}m", fg.r, fg.g, fg.b).unwrap();
+                        last_fg = Some(fg);
+                    }
+                    frame.push(screenshot::text_char(sym));
+                }
+                None => frame.push(' '),
+            }
+        }
+
+        frame.push_str("\x1b[0m\r\n");
+    }
+
+    let stats = combat_stats.get(player_id.0);
+    let status = format!(
+        "Depth: {}  HP: {}/{}  Turn: {}",
+        map.depth,
+        stats.hp,
+        stats.max_hp,
+        world.borrow::<UniqueView<TurnCount>>().0,
+    );
+
+    write!(frame, "{:<1$}", status, WIDTH as usize).unwrap();
+
+    frame
+}
+
+/// Record a frame of the current turn if recording is turned on, starting a new recording if
+/// needed.
+///
+/// Recordings aren't made in the web version, since the files couldn't be reached there.
+pub fn record_turn(world: &World) {
+    if cfg!(target_os = "emscripten") {
+        return;
+    }
+
+    let mut recorder = world.borrow::<UniqueViewMut<Recorder>>();
+
+    if !world.borrow::<UniqueView<Options>>().record_sessions {
+        recorder.stop();
+        return;
+    }
+
+    if recorder.writer.is_none() {
+        if recorder.failed {
+            return;
+        }
+        if let Err(e) = recorder.begin() {
+            eprintln!("Warning: recording::record_turn: {}", e);
+            recorder.failed = true;
+            return;
+        }
+    }
+
+    let frame = draw_frame(world);
+
+    if let Err(e) = recorder.write_frame(&frame) {
+        eprintln!("Warning: recording::record_turn: {}", e);
+        recorder.stop();
+        recorder.failed = true;
+    }
+}
@@ -1,22 +1,220 @@
-use shipyard::{Get, IntoIter, UniqueView, View, World};
+use shipyard::{Get, IntoIter, Shiperator, UniqueView, View, World};
 
 use crate::{
     chunked::ChunkedMapGrid,
-    components::{Coord, FieldOfView, RenderOnFloor, RenderOnMap, Renderable},
+    components::{
+        Asleep, Charmed, CombatStats, Confused, Coord, FieldOfView, Monster, RenderOnFloor,
+        RenderOnMap, Renderable,
+    },
     gamesym::GameSym,
+    map::Map,
     player::PlayerId,
+    ui::Options,
 };
-use ruggrogue::TileGrid;
+use ruggrogue::{
+    util::{Color, Position},
+    TileGrid,
+};
+
+/// Colors from the Okabe-Ito palette that remain distinct under common color vision deficiencies.
+const SAFE_VERMILLION: Color = Color {
+    r: 213,
+    g: 94,
+    b: 0,
+};
+const SAFE_ORANGE: Color = Color {
+    r: 230,
+    g: 159,
+    b: 0,
+};
+const SAFE_YELLOW: Color = Color {
+    r: 240,
+    g: 228,
+    b: 66,
+};
+const SAFE_BLUISH_GREEN: Color = Color {
+    r: 0,
+    g: 158,
+    b: 115,
+};
+const SAFE_SKY_BLUE: Color = Color {
+    r: 86,
+    g: 180,
+    b: 233,
+};
+const SAFE_BLUE: Color = Color {
+    r: 0,
+    g: 114,
+    b: 178,
+};
+const SAFE_REDDISH_PURPLE: Color = Color {
+    r: 204,
+    g: 121,
+    b: 167,
+};
+
+/// Background color that injured monsters are tinted towards as they lose hit points.
+const INJURY_TINT: Color = Color { r: 160, g: 0, b: 0 };
+
+/// Remap a color to the nearest hue of a colorblind-safe palette, keeping its brightness.
+///
+/// Grays and near-grays are left alone.
+fn colorblind_safe(color: Color) -> Color {
+    let (r, g, b) = (color.r as i32, color.g as i32, color.b as i32);
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let chroma = max - min;
+
+    if chroma < 48 {
+        return color;
+    }
+
+    let hue = if max == r {
+        (60 * (g - b) / chroma).rem_euclid(360)
+    } else if max == g {
+        60 * (b - r) / chroma + 120
+    } else {
+        60 * (r - g) / chroma + 240
+    };
+    let safe = match hue {
+        0..=20 => SAFE_VERMILLION,
+        21..=45 => SAFE_ORANGE,
+        46..=70 => SAFE_YELLOW,
+        71..=170 => SAFE_BLUISH_GREEN,
+        171..=210 => SAFE_SKY_BLUE,
+        211..=260 => SAFE_BLUE,
+        261..=335 => SAFE_REDDISH_PURPLE,
+        _ => SAFE_VERMILLION,
+    };
+    let safe_max = (safe.r as i32).max(safe.g as i32).max(safe.b as i32);
+    let scale = |c: u8| (c as i32 * max / safe_max).min(255) as u8;
+
+    Color {
+        r: scale(safe.r),
+        g: scale(safe.g),
+        b: scale(safe.b),
+    }
+}
+
+/// Adjust a color for display according to accessibility options.
+pub fn palette_color(options: &Options, color: Color) -> Color {
+    if options.colorblind {
+        colorblind_safe(color)
+    } else {
+        color
+    }
+}
+
+/// Draw a renderable onto a grid at the given position, respecting accessibility options.
+pub fn put_renderable<P: Into<Position>>(
+    grid: &mut TileGrid<GameSym>,
+    pos: P,
+    render: &Renderable,
+    options: &Options,
+) {
+    let fg = palette_color(options, render.fg);
+    let bg = palette_color(options, render.bg);
+
+    match render.sym.distinct_char() {
+        Some(ch) if options.distinct_glyphs => grid.put_char_color(pos, ch, fg, bg),
+        _ => grid.put_sym_color(pos, render.sym, fg, bg),
+    }
+}
+
+/// Blend a background color towards the injury tint by the fraction of hit points lost.
+fn injury_tinted(bg: Color, stats: &CombatStats) -> Color {
+    if stats.max_hp <= 0 || stats.hp >= stats.max_hp {
+        return bg;
+    }
+
+    let lost = 1.0 - stats.hp.max(0) as f32 / stats.max_hp as f32;
+    let blend = |from: u8, to: u8| (from as f32 + (to as f32 - from as f32) * lost).round() as u8;
+
+    Color {
+        r: blend(bg.r, INJURY_TINT.r),
+        g: blend(bg.g, INJURY_TINT.g),
+        b: blend(bg.b, INJURY_TINT.b),
+    }
+}
+
+/// Mark visible monsters that are asleep with a 'z', those that are confused with a '?', those that
+/// are charmed with a '+' and those that have spotted the player with a '!' in the tile just above
+/// them, if that tile is visible and otherwise unoccupied.
+///
+/// The marks are erased along with the rest of the player's field of view when the map chunks
+/// around it are redrawn at the end of each turn.
+fn draw_awareness_marks(
+    chunked_map_grid: &ChunkedMapGrid,
+    world: &World,
+    grid: &mut TileGrid<GameSym>,
+) {
+    let (map, options, player_id, asleeps, charmeds, confuseds, coords, fovs, monsters) = world
+        .borrow::<(
+            UniqueView<Map>,
+            UniqueView<Options>,
+            UniqueView<PlayerId>,
+            View<Asleep>,
+            View<Charmed>,
+            View<Confused>,
+            View<Coord>,
+            View<FieldOfView>,
+            View<Monster>,
+        )>();
+    let player_fov = fovs.get(player_id.0);
+    let player_pos = coords.get(player_id.0).0;
+
+    for (id, (coord, _)) in (&coords, &monsters).iter().with_id() {
+        let above = (coord.0.x, coord.0.y - 1);
+
+        if !player_fov.get(coord.0.into())
+            || !player_fov.get(above)
+            || map.iter_entities_at(above.0, above.1).next().is_some()
+        {
+            continue;
+        }
+
+        let mark = if asleeps.contains(id) {
+            Some(('z', Color::CYAN))
+        } else if confuseds.contains(id) {
+            Some(('?', Color::MAGENTA))
+        } else if charmeds.contains(id) {
+            Some(('+', Color::PINK))
+        } else if matches!(fovs.try_get(id), Ok(fov) if fov.get(player_pos.into())) {
+            Some(('!', Color::YELLOW))
+        } else {
+            None
+        };
+
+        if let Some((ch, fg)) = mark {
+            if let Some(pos) = chunked_map_grid.map_to_grid_pos(world, above.into()) {
+                grid.put_char_color(pos, ch, palette_color(&options, fg), Color::BLACK);
+            }
+        }
+    }
+}
 
 pub fn draw_renderables(
     chunked_map_grid: &ChunkedMapGrid,
     world: &World,
     grid: &mut TileGrid<GameSym>,
 ) {
-    let (player_id, coords, fovs, render_on_floors, render_on_maps, renderables) = world.borrow::<(
+    let (
+        options,
+        player_id,
+        combat_stats,
+        coords,
+        fovs,
+        monsters,
+        render_on_floors,
+        render_on_maps,
+        renderables,
+    ) = world.borrow::<(
+        UniqueView<Options>,
         UniqueView<PlayerId>,
+        View<CombatStats>,
         View<Coord>,
         View<FieldOfView>,
+        View<Monster>,
         View<RenderOnFloor>,
         View<RenderOnMap>,
         View<Renderable>,
@@ -28,17 +226,29 @@ pub fn draw_renderables(
     for (coord, render, _) in (&coords, &renderables, &render_on_floors).iter() {
         if fov.get(coord.0.into()) {
             if let Some(pos) = chunked_map_grid.map_to_grid_pos(world, coord.0) {
-                grid.put_sym_color(pos, render.sym, render.fg, render.bg);
+                put_renderable(grid, pos, render, &options);
             }
         }
     }
 
-    // Draw normal map entities.
-    for (coord, render, _) in (&coords, &renderables, &render_on_maps).iter() {
+    // Draw normal map entities, tinting injured monsters.
+    for (id, (coord, render, _)) in (&coords, &renderables, &render_on_maps).iter().with_id() {
         if fov.get(coord.0.into()) {
             if let Some(pos) = chunked_map_grid.map_to_grid_pos(world, coord.0) {
-                grid.put_sym_color(pos, render.sym, render.fg, render.bg);
+                match combat_stats.try_get(id) {
+                    Ok(stats) if monsters.contains(id) => {
+                        let tinted = Renderable {
+                            bg: injury_tinted(render.bg, stats),
+                            ..*render
+                        };
+
+                        put_renderable(grid, pos, &tinted, &options);
+                    }
+                    _ => put_renderable(grid, pos, render, &options),
+                }
             }
         }
     }
+
+    draw_awareness_marks(chunked_map_grid, world, grid);
 }
@@ -0,0 +1,115 @@
+use rand::SeedableRng;
+use std::hash::Hasher;
+use wyhash::WyHash;
+
+use crate::{magicnum, GameSeed};
+
+/// Random number generator used for everything that should follow from the game seed.
+pub type GameRng = rand_xoshiro::Xoshiro128PlusPlus;
+
+/// Independent random number streams forked off of the game seed.
+///
+/// Each stream is seeded separately, so drawing more or fewer numbers from one stream never
+/// changes what another stream produces.  New random features should get a stream of their own.
+#[derive(Clone, Copy)]
+pub enum RngStream {
+    /// Map layout generation.
+    MapGen,
+    /// The outline of the playable area of each level.
+    LevelShape,
+    /// The style of corridors on each level and the paths of winding ones.
+    Corridors,
+    /// Pools of water, lava, chasms and vaults added after the map layout is generated.
+    Terrain,
+    /// Filling rooms with monsters and items.
+    Spawns,
+    /// Periodically guaranteed weapon spawns.
+    GuaranteedWeapon,
+    /// Periodically guaranteed armor spawns.
+    GuaranteedArmor,
+    /// Friendly NPC placement.
+    Npc,
+    /// Placement of harmless characters without dialog, such as prisoners.
+    Friendly,
+    /// Placement of boulders and cracked walls.
+    Obstacles,
+    /// Vault loot and which monster carries the key.
+    Vault,
+    /// Placement of prefab rooms, vaults and traps.
+    Prefabs,
+    /// Loot and monsters placed in prefabs.
+    PrefabContents,
+    /// Procedural quests handed out on arrival at a new depth.
+    Quests,
+    /// Placement of the remains of earlier games.
+    Bones,
+    /// Melee attack rolls.
+    Combat,
+    /// Triggering of scripted monster abilities.
+    MonsterAbility,
+    /// Monster drop tables.
+    Loot,
+    /// Stumbling about while confused.
+    Confusion,
+}
+
+impl RngStream {
+    fn magicnum(self) -> u64 {
+        match self {
+            RngStream::MapGen => magicnum::GENERATE_ROOMS_AND_CORRIDORS,
+            RngStream::LevelShape => magicnum::GENERATE_LEVEL_SHAPE,
+            RngStream::Corridors => magicnum::GENERATE_CORRIDORS,
+            RngStream::Terrain => magicnum::GENERATE_TERRAIN,
+            RngStream::Spawns => magicnum::FILL_ROOM_WITH_SPAWNS,
+            RngStream::GuaranteedWeapon => magicnum::SPAWN_GUARANTEED_WEAPON,
+            RngStream::GuaranteedArmor => magicnum::SPAWN_GUARANTEED_ARMOR,
+            RngStream::Npc => magicnum::SPAWN_NPC,
+            RngStream::Friendly => magicnum::SPAWN_FRIENDLY,
+            RngStream::Obstacles => magicnum::SPAWN_OBSTACLES,
+            RngStream::Vault => magicnum::SPAWN_VAULT,
+            RngStream::Prefabs => magicnum::PLACE_PREFABS,
+            RngStream::PrefabContents => magicnum::SPAWN_PREFAB_CONTENTS,
+            RngStream::Quests => magicnum::GENERATE_QUESTS,
+            RngStream::Bones => magicnum::TAKE_BONES,
+            RngStream::Combat => magicnum::MELEE_ATTACK,
+            RngStream::MonsterAbility => magicnum::MONSTER_ABILITY,
+            RngStream::Loot => magicnum::MONSTER_DROPS,
+            RngStream::Confusion => magicnum::CONFUSION,
+        }
+    }
+}
+
+/// A stream forked off of the game seed, which can be narrowed further by mixing in values such
+/// as the current depth or turn before creating an RNG from it.
+pub struct RngFork(WyHash);
+
+impl RngFork {
+    pub fn mix_u64(&mut self, value: u64) -> &mut Self {
+        self.0.write_u64(value);
+        self
+    }
+
+    pub fn mix_u32(&mut self, value: u32) -> &mut Self {
+        self.0.write_u32(value);
+        self
+    }
+
+    pub fn mix_i32(&mut self, value: i32) -> &mut Self {
+        self.0.write_i32(value);
+        self
+    }
+
+    /// Create an RNG seeded with the stream and everything mixed into it so far.
+    pub fn rng(&self) -> GameRng {
+        GameRng::seed_from_u64(self.0.finish())
+    }
+}
+
+impl GameSeed {
+    /// Fork a random number stream off of the game seed.
+    pub fn fork(&self, stream: RngStream) -> RngFork {
+        let mut hasher = WyHash::with_seed(stream.magicnum());
+        hasher.write_u64(self.0);
+        RngFork(hasher)
+    }
+}
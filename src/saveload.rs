@@ -9,33 +9,66 @@ use std::{
     error, fmt,
     fs::{self, File},
     io::{BufRead, BufReader, BufWriter, Write},
-    path::Path,
+    path::{Path, PathBuf},
 };
 
 #[cfg(target_os = "emscripten")]
 use crate::ruggrogue_sync_idbfs;
 use crate::{
     components::*,
+    config,
     experience::Difficulty,
+    gamesym::{self, GameSym},
+    journal::Journal,
+    locale::{self, Locale},
     map::Map,
     message::Messages,
+    paths,
     player::{PlayerAlive, PlayerId},
-    spawn, BaseEquipmentLevel, GameSeed, TurnCount, Wins,
+    quest::Quests,
+    spawn,
+    stats::RunStats,
+    tutorial::Tutorial,
+    BaseEquipmentLevel, GameSeed, TurnCount, Wins,
 };
+use ruggrogue::{DynSym, SymbolRegistry};
 
-#[cfg(target_os = "emscripten")]
-const SAVE_FILENAME: &str = "/ruggrogue/savegame.txt";
+pub(crate) const SAVE_FILENAME: &str = "savegame.txt";
+
+/// Saves are written here first and then renamed over the save file, so an interrupted write
+/// never leaves a half-written save behind.
+const SAVE_TEMP_FILENAME: &str = "savegame.txt.tmp";
+
+/// File that a save is exported to and imported from.
+pub(crate) const EXPORT_FILENAME: &str = "ruggrogue-export.json";
 
-#[cfg(not(target_os = "emscripten"))]
-const SAVE_FILENAME: &str = "savegame.txt";
+/// Version of the export file layout; bump this when [SaveExport] changes incompatibly.
+const EXPORT_FORMAT: u32 = 1;
+
+/// A save file bundled with the options that it was played with, for moving a run between
+/// machines or attaching it to a bug report.
+#[derive(Deserialize, Serialize)]
+struct SaveExport {
+    format: u32,
+    game_version: String,
+    savegame: String,
+    #[serde(default)]
+    config: Option<String>,
+}
 
 type BoxedError = Box<dyn error::Error>;
 
+/// Names of the dynamic symbols registered when the game was saved, in ID order, so that
+/// [GameSym::Dyn] IDs can be matched up with whatever is registered when it's loaded.
+#[derive(Deserialize, Serialize)]
+struct SymbolNames(Vec<String>);
+
 /// Game-specific errors that can occur when loading a save file.
 #[derive(Debug)]
 pub enum LoadError {
     DuplicateComponent(usize, &'static str),
     DuplicateUnique(usize, &'static str),
+    IncompatibleExport(String),
     MissingUnique(&'static str),
     UnknownId(EntityId),
     UnrecognizedLine(usize),
@@ -50,6 +83,7 @@ impl fmt::Display for LoadError {
             Self::DuplicateUnique(line_num, which) => {
                 write!(f, "line {}: duplicate {} unique", line_num, which)
             }
+            Self::IncompatibleExport(why) => write!(f, "incompatible export: {}", why),
             Self::MissingUnique(which) => write!(f, "missing {} unique", which),
             Self::UnknownId(id) => write!(f, "unknown entity ID {:?}", *id),
             Self::UnrecognizedLine(line_num) => write!(f, "line {}: unrecognized line", line_num),
@@ -60,12 +94,18 @@ impl fmt::Display for LoadError {
 impl error::Error for LoadError {}
 
 pub fn save_file_exists() -> bool {
-    Path::new(SAVE_FILENAME).exists()
+    paths::data_file(SAVE_FILENAME).exists()
+}
+
+/// Path of the file that a save is exported to and imported from.
+#[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+pub fn export_file() -> PathBuf {
+    paths::data_file(EXPORT_FILENAME)
 }
 
 pub fn delete_save_file() {
     if save_file_exists() {
-        if let Err(e) = fs::remove_file(SAVE_FILENAME) {
+        if let Err(e) = fs::remove_file(paths::data_file(SAVE_FILENAME)) {
             eprintln!("Warning: saveload::delete_save_file: {}", e);
         }
     }
@@ -91,6 +131,20 @@ macro_rules! save_unique {
     };
 }
 
+/// Save the names of the dynamic symbols in the [SymbolRegistry] of the world as a unique.
+fn save_symbol_names<W: Write>(world: &World, mut writer: &mut W) -> Result<(), BoxedError> {
+    let registry = world.borrow::<UniqueView<SymbolRegistry>>();
+    let names = (0..)
+        .map_while(|id| registry.name(DynSym::from_id(id)))
+        .map(String::from)
+        .collect();
+
+    write!(writer, "*\tSymbolNames\t")?;
+    SymbolNames(names).serialize(&mut Serializer::new(&mut writer))?;
+    writer.write_all(b"\n")?;
+    Ok(())
+}
+
 /// Save components of a storage as an entity ID, a tab, its type, a tab and its serialized data,
 /// one per line.
 fn save_named_storage<W, T>(world: &World, mut writer: &mut W, name: &str) -> Result<(), BoxedError>
@@ -114,50 +168,206 @@ macro_rules! save_storage {
     };
 }
 
-/// Save all data in uniques and component storages to the save file.
+/// Writes one part of the save file: a unique or all of the components of a storage.
+type SaveSection = fn(&World, &mut BufWriter<File>) -> Result<(), BoxedError>;
+
+/// Everything that goes into a save file, in the order that it's written.
+const SAVE_SECTIONS: &[SaveSection] = &[
+    |world, writer| save_symbol_names(world, writer),
+    |world, writer| save_unique!(GameSeed, world, writer),
+    |world, writer| save_unique!(TurnCount, world, writer),
+    |world, writer| save_unique!(Wins, world, writer),
+    |world, writer| save_unique!(BaseEquipmentLevel, world, writer),
+    |world, writer| save_unique!(Difficulty, world, writer),
+    |world, writer| save_unique!(Messages, world, writer),
+    |world, writer| save_unique!(Journal, world, writer),
+    |world, writer| save_unique!(Quests, world, writer),
+    |world, writer| save_unique!(RunStats, world, writer),
+    |world, writer| save_unique!(PlayerAlive, world, writer),
+    |world, writer| save_unique!(PlayerId, world, writer),
+    |world, writer| save_unique!(Map, world, writer),
+    |world, writer| save_storage!(Acquired, world, writer),
+    |world, writer| save_storage!(Afraid, world, writer),
+    |world, writer| save_storage!(AreaOfEffect, world, writer),
+    |world, writer| save_storage!(Asleep, world, writer),
+    |world, writer| save_storage!(BlocksTile, world, writer),
+    |world, writer| save_storage!(Boulder, world, writer),
+    |world, writer| save_storage!(Charmed, world, writer),
+    |world, writer| save_storage!(Chest, world, writer),
+    |world, writer| save_storage!(CombatBonus, world, writer),
+    |world, writer| save_storage!(CombatStats, world, writer),
+    |world, writer| save_storage!(Confused, world, writer),
+    |world, writer| save_storage!(Consumable, world, writer),
+    |world, writer| save_storage!(Container, world, writer),
+    |world, writer| save_storage!(Coord, world, writer),
+    |world, writer| save_storage!(DamageLog, world, writer),
+    |world, writer| save_storage!(Description, world, writer),
+    |world, writer| save_storage!(Diggable, world, writer),
+    |world, writer| save_storage!(Digger, world, writer),
+    |world, writer| save_storage!(Durability, world, writer),
+    |world, writer| save_storage!(EquipSlot, world, writer),
+    |world, writer| save_storage!(Equipment, world, writer),
+    |world, writer| save_storage!(Experience, world, writer),
+    |world, writer| save_storage!(Faction, world, writer),
+    |world, writer| save_storage!(Favorite, world, writer),
+    |world, writer| save_storage!(FieldOfView, world, writer),
+    |world, writer| save_storage!(Friendly, world, writer),
+    |world, writer| save_storage!(GivesExperience, world, writer),
+    |world, writer| save_storage!(Gravestone, world, writer),
+    |world, writer| save_storage!(InLoadout, world, writer),
+    |world, writer| save_storage!(InflictsDamage, world, writer),
+    |world, writer| save_storage!(InflictsSleep, world, writer),
+    |world, writer| save_storage!(Inventory, world, writer),
+    |world, writer| save_storage!(Item, world, writer),
+    |world, writer| save_storage!(Key, world, writer),
+    |world, writer| save_storage!(KeyCarrier, world, writer),
+    |world, writer| save_storage!(LastSeenPlayer, world, writer),
+    |world, writer| save_storage!(Locked, world, writer),
+    |world, writer| save_storage!(Monster, world, writer),
+    |world, writer| save_storage!(Name, world, writer),
+    |world, writer| save_storage!(NameSource, world, writer),
+    |world, writer| save_storage!(Npc, world, writer),
+    |world, writer| save_storage!(Nutrition, world, writer),
+    |world, writer| save_storage!(Pet, world, writer),
+    |world, writer| save_storage!(Player, world, writer),
+    |world, writer| save_storage!(ProvidesHealing, world, writer),
+    |world, writer| save_storage!(QuestItem, world, writer),
+    |world, writer| save_storage!(Ranged, world, writer),
+    |world, writer| save_storage!(RenderOnFloor, world, writer),
+    |world, writer| save_storage!(RenderOnMap, world, writer),
+    |world, writer| save_storage!(Renderable, world, writer),
+    |world, writer| save_storage!(Repairs, world, writer),
+    |world, writer| save_storage!(ScriptedUse, world, writer),
+    |world, writer| save_storage!(Stomach, world, writer),
+    |world, writer| save_storage!(Tally, world, writer),
+    |world, writer| save_storage!(Victory, world, writer),
+    |world, writer| save_storage!(Weight, world, writer),
+];
+
+/// Saves the game a few sections at a time, so that the game can keep drawing while it saves.
+///
+/// Sections are written to a temporary file that only replaces the save file once every section
+/// has been written.
+pub struct GameSaver {
+    writer: Option<BufWriter<File>>,
+    next_section: usize,
+}
+
+impl GameSaver {
+    /// Start saving the game, or return `None` if the game shouldn't be saved.
+    ///
+    /// The tutorial is never saved, so that it can't replace a game in progress.
+    pub fn new(world: &World) -> Result<Option<Self>, BoxedError> {
+        if world.borrow::<UniqueView<Tutorial>>().is_active() {
+            return Ok(None);
+        }
+
+        Ok(Some(Self {
+            writer: Some(BufWriter::new(File::create(paths::data_file(
+                SAVE_TEMP_FILENAME,
+            ))?)),
+            next_section: 0,
+        }))
+    }
+
+    /// How many sections have been saved out of how many in total.
+    pub fn progress(&self) -> (u64, u64) {
+        (self.next_section as u64, SAVE_SECTIONS.len() as u64)
+    }
+
+    /// Write up to `max_sections` more sections of the save file, returning true once every
+    /// section has been written and the save file has been replaced.
+    pub fn save_sections(
+        &mut self,
+        world: &World,
+        max_sections: usize,
+    ) -> Result<bool, BoxedError> {
+        let writer = match &mut self.writer {
+            Some(writer) => writer,
+            None => return Ok(true),
+        };
+        let end = self
+            .next_section
+            .saturating_add(max_sections)
+            .min(SAVE_SECTIONS.len());
+
+        for save_section in &SAVE_SECTIONS[self.next_section..end] {
+            save_section(world, writer)?;
+        }
+        self.next_section = end;
+
+        if end < SAVE_SECTIONS.len() {
+            return Ok(false);
+        }
+
+        if let Some(mut writer) = self.writer.take() {
+            writer.flush()?;
+            writer.into_inner()?.sync_all()?;
+            fs::rename(
+                paths::data_file(SAVE_TEMP_FILENAME),
+                paths::data_file(SAVE_FILENAME),
+            )?;
+
+            #[cfg(target_os = "emscripten")]
+            unsafe {
+                ruggrogue_sync_idbfs();
+            }
+        }
+
+        Ok(true)
+    }
+}
+
+/// Save all data in uniques and component storages to the save file in one go.
+///
+/// The tutorial is never saved, so that it can't replace a game in progress.
 pub fn save_game(world: &World) -> Result<(), BoxedError> {
-    let mut writer = BufWriter::new(File::create(SAVE_FILENAME)?);
-
-    save_unique!(GameSeed, world, &mut writer)?;
-    save_unique!(TurnCount, world, &mut writer)?;
-    save_unique!(Wins, world, &mut writer)?;
-    save_unique!(BaseEquipmentLevel, world, &mut writer)?;
-    save_unique!(Difficulty, world, &mut writer)?;
-    save_unique!(Messages, world, &mut writer)?;
-    save_unique!(PlayerAlive, world, &mut writer)?;
-    save_unique!(PlayerId, world, &mut writer)?;
-    save_unique!(Map, world, &mut writer)?;
-
-    save_storage!(AreaOfEffect, world, &mut writer)?;
-    save_storage!(Asleep, world, &mut writer)?;
-    save_storage!(BlocksTile, world, &mut writer)?;
-    save_storage!(CombatBonus, world, &mut writer)?;
-    save_storage!(CombatStats, world, &mut writer)?;
-    save_storage!(Consumable, world, &mut writer)?;
-    save_storage!(Coord, world, &mut writer)?;
-    save_storage!(EquipSlot, world, &mut writer)?;
-    save_storage!(Equipment, world, &mut writer)?;
-    save_storage!(Experience, world, &mut writer)?;
-    save_storage!(FieldOfView, world, &mut writer)?;
-    save_storage!(GivesExperience, world, &mut writer)?;
-    save_storage!(InflictsDamage, world, &mut writer)?;
-    save_storage!(InflictsSleep, world, &mut writer)?;
-    save_storage!(Inventory, world, &mut writer)?;
-    save_storage!(Item, world, &mut writer)?;
-    save_storage!(Monster, world, &mut writer)?;
-    save_storage!(Name, world, &mut writer)?;
-    save_storage!(Nutrition, world, &mut writer)?;
-    save_storage!(Player, world, &mut writer)?;
-    save_storage!(ProvidesHealing, world, &mut writer)?;
-    save_storage!(Ranged, world, &mut writer)?;
-    save_storage!(RenderOnFloor, world, &mut writer)?;
-    save_storage!(RenderOnMap, world, &mut writer)?;
-    save_storage!(Renderable, world, &mut writer)?;
-    save_storage!(Stomach, world, &mut writer)?;
-    save_storage!(Tally, world, &mut writer)?;
-    save_storage!(Victory, world, &mut writer)?;
-
-    writer.flush()?;
+    if let Some(mut saver) = GameSaver::new(world)? {
+        saver.save_sections(world, usize::MAX)?;
+    }
+
+    Ok(())
+}
+
+/// Bundle the save file and config file into a single export file.
+#[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+pub fn export_save() -> Result<(), BoxedError> {
+    let export = SaveExport {
+        format: EXPORT_FORMAT,
+        game_version: env!("CARGO_PKG_VERSION").to_string(),
+        savegame: fs::read_to_string(paths::data_file(SAVE_FILENAME))?,
+        config: fs::read_to_string(paths::config_file(config::CONFIG_FILENAME)).ok(),
+    };
+
+    fs::write(export_file(), serde_json::to_string(&export)?)?;
+
+    Ok(())
+}
+
+/// Replace the save file and config file with the contents of the export file, after checking
+/// that it was exported by a compatible version of the game.
+#[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+pub fn import_save() -> Result<(), BoxedError> {
+    let export: SaveExport = serde_json::from_str(&fs::read_to_string(export_file())?)?;
+
+    if export.format != EXPORT_FORMAT {
+        return Err(Box::new(LoadError::IncompatibleExport(format!(
+            "format {}, expected {}",
+            export.format, EXPORT_FORMAT
+        ))));
+    }
+    if export.game_version != env!("CARGO_PKG_VERSION") {
+        return Err(Box::new(LoadError::IncompatibleExport(format!(
+            "game version {}, expected {}",
+            export.game_version,
+            env!("CARGO_PKG_VERSION")
+        ))));
+    }
+
+    fs::write(paths::data_file(SAVE_FILENAME), export.savegame)?;
+    if let Some(config) = export.config {
+        fs::write(paths::config_file(config::CONFIG_FILENAME), config)?;
+    }
 
     #[cfg(target_os = "emscripten")]
     unsafe {
@@ -265,7 +475,26 @@ macro_rules! deserialize_component {
     };
 }
 
-/// Load data out of the save file, with a list of entities to be despawned afterwards.
+/// Uniques loaded out of a save file, waiting to be checked and committed to the world.
+#[derive(Default)]
+struct LoadedUniques {
+    game_seed: Option<GameSeed>,
+    turn_count: Option<TurnCount>,
+    wins: Option<Wins>,
+    base_equipment_level: Option<BaseEquipmentLevel>,
+    difficulty: Option<Difficulty>,
+    messages: Option<Messages>,
+    journal: Option<Journal>,
+    quests: Option<Quests>,
+    run_stats: Option<RunStats>,
+    player_alive: Option<PlayerAlive>,
+    player_id: Option<PlayerId>,
+    map: Option<Map>,
+    symbol_names: Option<SymbolNames>,
+}
+
+/// Loads a save file a few lines at a time, so that the game can keep drawing while a large save
+/// is loaded.
 ///
 /// Loading saved data involves loading and interpreting data line by line; uniques are loaded to
 /// temporary space, while components are added to the entities in the world that are created if
@@ -276,22 +505,90 @@ macro_rules! deserialize_component {
 /// which would normally leave a partially-loaded group of entities behind.  To prevent this,
 /// freshly-created entities are added to the `despawn_ids` list, which is only cleared and
 /// replaced with old existing entities after final validation of the loaded data.
-fn load_save_file(world: &World, despawn_ids: &mut Vec<EntityId>) -> Result<(), BoxedError> {
-    let mut game_seed: Option<GameSeed> = None;
-    let mut turn_count: Option<TurnCount> = None;
-    let mut wins: Option<Wins> = None;
-    let mut base_equipment_level: Option<BaseEquipmentLevel> = None;
-    let mut difficulty: Option<Difficulty> = None;
-    let mut messages: Option<Messages> = None;
-    let mut player_alive: Option<PlayerAlive> = None;
-    let mut player_id: Option<PlayerId> = None;
-    let mut map: Option<Map> = None;
-    let mut old_to_new_ids: HashMap<EntityId, EntityId> = HashMap::new();
-    let reader = BufReader::new(File::open(SAVE_FILENAME)?);
-
-    for (line_num, line_bytes) in reader.lines().enumerate() {
-        let line_num = line_num + 1;
-        let line = line_bytes?;
+pub struct GameLoader {
+    reader: BufReader<File>,
+    line: String,
+    line_num: usize,
+    bytes_read: u64,
+    total_bytes: u64,
+    uniques: LoadedUniques,
+    old_to_new_ids: HashMap<EntityId, EntityId>,
+    despawn_ids: Vec<EntityId>,
+}
+
+impl GameLoader {
+    /// Start loading the save file.
+    pub fn new() -> Result<Self, BoxedError> {
+        Self::open(paths::data_file(SAVE_FILENAME))
+    }
+
+    fn open<P: AsRef<Path>>(path: P) -> Result<Self, BoxedError> {
+        let file = File::open(path)?;
+        let total_bytes = file.metadata()?.len();
+
+        Ok(Self {
+            reader: BufReader::new(file),
+            line: String::new(),
+            line_num: 0,
+            bytes_read: 0,
+            total_bytes,
+            uniques: LoadedUniques::default(),
+            old_to_new_ids: HashMap::new(),
+            despawn_ids: Vec::new(),
+        })
+    }
+
+    /// How many bytes of the save file have been loaded out of how many in total.
+    pub fn progress(&self) -> (u64, u64) {
+        (self.bytes_read.min(self.total_bytes), self.total_bytes)
+    }
+
+    /// Load up to `max_lines` more lines of the save file, returning true once the whole file has
+    /// been loaded and committed to the world.
+    ///
+    /// Entities that need despawning are despawned once loading succeeds or fails, after which the
+    /// loader shouldn't be used any more.
+    pub fn load_lines(&mut self, world: &World, max_lines: usize) -> Result<bool, BoxedError> {
+        let result = self.read_lines(world, max_lines);
+
+        if !matches!(result, Ok(false)) {
+            for id in self.despawn_ids.drain(..) {
+                spawn::despawn_entity(&mut world.borrow::<AllStoragesViewMut>(), id);
+            }
+        }
+
+        result
+    }
+
+    fn read_lines(&mut self, world: &World, max_lines: usize) -> Result<bool, BoxedError> {
+        for _ in 0..max_lines {
+            self.line.clear();
+
+            let len = self.reader.read_line(&mut self.line)?;
+
+            if len == 0 {
+                self.commit(world)?;
+                return Ok(true);
+            }
+
+            self.bytes_read += len as u64;
+            self.line_num += 1;
+
+            // Set the line buffer aside while loading the line so it can be reused afterwards.
+            let line = std::mem::take(&mut self.line);
+            let line_text = line.strip_suffix('\n').unwrap_or(&line);
+            let result = self.load_line(world, line_text.strip_suffix('\r').unwrap_or(line_text));
+
+            self.line = line;
+            result?;
+        }
+
+        Ok(false)
+    }
+
+    fn load_line(&mut self, world: &World, line: &str) -> Result<(), BoxedError> {
+        let uniques = &mut self.uniques;
+        let line_num = self.line_num;
 
         // A line starting with an asterisk should hold data for a unique.
         if let Some(maybe_unique) = line
@@ -301,22 +598,36 @@ fn load_save_file(world: &World, despawn_ids: &mut Vec<EntityId>) -> Result<(),
             let maybe_unique = maybe_unique.trim_start();
 
             // Try parsing the line as a unique.
-            if deserialize_unique!(GameSeed, maybe_unique, line_num, &mut game_seed)?
-                || deserialize_unique!(TurnCount, maybe_unique, line_num, &mut turn_count)?
-                || deserialize_unique!(Wins, maybe_unique, line_num, &mut wins)?
+            if deserialize_unique!(GameSeed, maybe_unique, line_num, &mut uniques.game_seed)?
+                || deserialize_unique!(TurnCount, maybe_unique, line_num, &mut uniques.turn_count)?
+                || deserialize_unique!(Wins, maybe_unique, line_num, &mut uniques.wins)?
                 || deserialize_unique!(
                     BaseEquipmentLevel,
                     maybe_unique,
                     line_num,
-                    &mut base_equipment_level
+                    &mut uniques.base_equipment_level
+                )?
+                || deserialize_unique!(Difficulty, maybe_unique, line_num, &mut uniques.difficulty)?
+                || deserialize_unique!(Messages, maybe_unique, line_num, &mut uniques.messages)?
+                || deserialize_unique!(Journal, maybe_unique, line_num, &mut uniques.journal)?
+                || deserialize_unique!(Quests, maybe_unique, line_num, &mut uniques.quests)?
+                || deserialize_unique!(RunStats, maybe_unique, line_num, &mut uniques.run_stats)?
+                || deserialize_unique!(
+                    PlayerAlive,
+                    maybe_unique,
+                    line_num,
+                    &mut uniques.player_alive
+                )?
+                || deserialize_unique!(PlayerId, maybe_unique, line_num, &mut uniques.player_id)?
+                || deserialize_unique!(Map, maybe_unique, line_num, &mut uniques.map)?
+                || deserialize_unique!(
+                    SymbolNames,
+                    maybe_unique,
+                    line_num,
+                    &mut uniques.symbol_names
                 )?
-                || deserialize_unique!(Difficulty, maybe_unique, line_num, &mut difficulty)?
-                || deserialize_unique!(Messages, maybe_unique, line_num, &mut messages)?
-                || deserialize_unique!(PlayerAlive, maybe_unique, line_num, &mut player_alive)?
-                || deserialize_unique!(PlayerId, maybe_unique, line_num, &mut player_id)?
-                || deserialize_unique!(Map, maybe_unique, line_num, &mut map)?
             {
-                continue;
+                return Ok(());
             }
         }
 
@@ -325,156 +636,316 @@ fn load_save_file(world: &World, despawn_ids: &mut Vec<EntityId>) -> Result<(),
             let save_id = EntityId::deserialize(&mut Deserializer::from_str(maybe_id))?;
 
             // Map entity_id into the current world, creating a new entity if needed.
-            let live_id = if let Some(id) = old_to_new_ids.get(&save_id) {
+            let live_id = if let Some(id) = self.old_to_new_ids.get(&save_id) {
                 *id
             } else {
-                // Add new entity to despawn_ids and old_to_new_ids.
+                // Add new entity to despawn_ids and self.old_to_new_ids.
                 let new_id = world.borrow::<EntitiesViewMut>().add_entity((), ());
-                despawn_ids.push(new_id);
-                old_to_new_ids.insert(save_id, new_id);
+                self.despawn_ids.push(new_id);
+                self.old_to_new_ids.insert(save_id, new_id);
                 new_id
             };
 
             let maybe_data = maybe_data.trim_start();
 
             // Try parsing maybe_data and add it to the entity on success.
-            if deserialize_component!(AreaOfEffect, world, maybe_data, line_num, live_id)?
+            if deserialize_component!(Acquired, world, maybe_data, line_num, live_id)?
+                || deserialize_component!(Afraid, world, maybe_data, line_num, live_id)?
+                || deserialize_component!(AreaOfEffect, world, maybe_data, line_num, live_id)?
                 || deserialize_component!(Asleep, world, maybe_data, line_num, live_id)?
                 || deserialize_component!(BlocksTile, world, maybe_data, line_num, live_id)?
+                || deserialize_component!(Boulder, world, maybe_data, line_num, live_id)?
+                || deserialize_component!(Charmed, world, maybe_data, line_num, live_id)?
+                || deserialize_component!(Chest, world, maybe_data, line_num, live_id)?
                 || deserialize_component!(CombatBonus, world, maybe_data, line_num, live_id)?
                 || deserialize_component!(CombatStats, world, maybe_data, line_num, live_id)?
+                || deserialize_component!(Confused, world, maybe_data, line_num, live_id)?
                 || deserialize_component!(Consumable, world, maybe_data, line_num, live_id)?
+                || deserialize_component!(Container, world, maybe_data, line_num, live_id)?
                 || deserialize_component!(Coord, world, maybe_data, line_num, live_id)?
+                || deserialize_component!(DamageLog, world, maybe_data, line_num, live_id)?
+                || deserialize_component!(Description, world, maybe_data, line_num, live_id)?
+                || deserialize_component!(Diggable, world, maybe_data, line_num, live_id)?
+                || deserialize_component!(Digger, world, maybe_data, line_num, live_id)?
+                || deserialize_component!(Durability, world, maybe_data, line_num, live_id)?
                 || deserialize_component!(EquipSlot, world, maybe_data, line_num, live_id)?
                 || deserialize_component!(Equipment, world, maybe_data, line_num, live_id)?
                 || deserialize_component!(Experience, world, maybe_data, line_num, live_id)?
+                || deserialize_component!(Faction, world, maybe_data, line_num, live_id)?
+                || deserialize_component!(Favorite, world, maybe_data, line_num, live_id)?
                 || deserialize_component!(FieldOfView, world, maybe_data, line_num, live_id)?
+                || deserialize_component!(Friendly, world, maybe_data, line_num, live_id)?
                 || deserialize_component!(GivesExperience, world, maybe_data, line_num, live_id)?
+                || deserialize_component!(Gravestone, world, maybe_data, line_num, live_id)?
+                || deserialize_component!(InLoadout, world, maybe_data, line_num, live_id)?
                 || deserialize_component!(InflictsDamage, world, maybe_data, line_num, live_id)?
                 || deserialize_component!(InflictsSleep, world, maybe_data, line_num, live_id)?
                 || deserialize_component!(Inventory, world, maybe_data, line_num, live_id)?
                 || deserialize_component!(Item, world, maybe_data, line_num, live_id)?
+                || deserialize_component!(Key, world, maybe_data, line_num, live_id)?
+                || deserialize_component!(KeyCarrier, world, maybe_data, line_num, live_id)?
+                || deserialize_component!(LastSeenPlayer, world, maybe_data, line_num, live_id)?
+                || deserialize_component!(Locked, world, maybe_data, line_num, live_id)?
                 || deserialize_component!(Monster, world, maybe_data, line_num, live_id)?
                 || deserialize_component!(Name, world, maybe_data, line_num, live_id)?
+                || deserialize_component!(NameSource, world, maybe_data, line_num, live_id)?
+                || deserialize_component!(Npc, world, maybe_data, line_num, live_id)?
                 || deserialize_component!(Nutrition, world, maybe_data, line_num, live_id)?
+                || deserialize_component!(Pet, world, maybe_data, line_num, live_id)?
                 || deserialize_component!(Player, world, maybe_data, line_num, live_id)?
                 || deserialize_component!(ProvidesHealing, world, maybe_data, line_num, live_id)?
+                || deserialize_component!(QuestItem, world, maybe_data, line_num, live_id)?
                 || deserialize_component!(Ranged, world, maybe_data, line_num, live_id)?
                 || deserialize_component!(RenderOnFloor, world, maybe_data, line_num, live_id)?
                 || deserialize_component!(RenderOnMap, world, maybe_data, line_num, live_id)?
                 || deserialize_component!(Renderable, world, maybe_data, line_num, live_id)?
+                || deserialize_component!(Repairs, world, maybe_data, line_num, live_id)?
+                || deserialize_component!(ScriptedUse, world, maybe_data, line_num, live_id)?
                 || deserialize_component!(Stomach, world, maybe_data, line_num, live_id)?
                 || deserialize_component!(Tally, world, maybe_data, line_num, live_id)?
                 || deserialize_component!(Victory, world, maybe_data, line_num, live_id)?
+                || deserialize_component!(Weight, world, maybe_data, line_num, live_id)?
             {
-                continue;
+                return Ok(());
             }
 
             // No other kinds of lines are valid.
             return Err(Box::new(LoadError::UnrecognizedLine(line_num)));
         }
 
-        return Err(Box::new(LoadError::UnrecognizedLine(line_num)));
+        Err(Box::new(LoadError::UnrecognizedLine(line_num)))
     }
 
-    // Check that all uniques are present.
-    let game_seed = game_seed.ok_or(LoadError::MissingUnique("GameSeed"))?;
-    let turn_count = turn_count.ok_or(LoadError::MissingUnique("TurnCount"))?;
-    let wins = wins.ok_or(LoadError::MissingUnique("Wins"))?;
-    let base_equipment_level =
-        base_equipment_level.ok_or(LoadError::MissingUnique("BaseEquipmentLevel"))?;
-    let mut difficulty = difficulty.ok_or(LoadError::MissingUnique("Difficulty"))?;
-    let messages = messages.ok_or(LoadError::MissingUnique("Messages"))?;
-    let player_alive = player_alive.ok_or(LoadError::MissingUnique("PlayerAlive"))?;
-    let mut player_id = player_id.ok_or(LoadError::MissingUnique("PlayerId"))?;
-    let mut map = map.ok_or(LoadError::MissingUnique("Map"))?;
-
-    // Replace old save-internal entity IDs with new loaded entity IDs.
-    difficulty.id = old_to_new_ids
-        .get(&difficulty.id)
-        .copied()
-        .ok_or(LoadError::UnknownId(difficulty.id))?;
-    player_id.0 = old_to_new_ids
-        .get(&player_id.0)
-        .copied()
-        .ok_or(LoadError::UnknownId(player_id.0))?;
-
-    // Ensure that we're only working with freshly-loaded entities below.
-    let new_ids = old_to_new_ids
-        .values()
-        .copied()
-        .collect::<HashSet<EntityId>>();
-
-    // Replace entity IDs in equipment.
-    for (_, equipment) in IntoIter::iter(&mut world.borrow::<ViewMut<Equipment>>())
-        .with_id()
-        .filter(|(id, _)| new_ids.contains(id))
-    {
-        if let Some(weapon) = &mut equipment.weapon {
-            *weapon = old_to_new_ids
-                .get(weapon)
-                .copied()
-                .ok_or(LoadError::UnknownId(*weapon))?;
+    /// Check, fix up and commit everything loaded once the whole save file has been read.
+    fn commit(&mut self, world: &World) -> Result<(), BoxedError> {
+        let LoadedUniques {
+            game_seed,
+            turn_count,
+            wins,
+            base_equipment_level,
+            difficulty,
+            messages,
+            journal,
+            quests,
+            run_stats,
+            player_alive,
+            player_id,
+            map,
+            symbol_names,
+        } = std::mem::take(&mut self.uniques);
+
+        // Check that all uniques are present.
+        let game_seed = game_seed.ok_or(LoadError::MissingUnique("GameSeed"))?;
+        let turn_count = turn_count.ok_or(LoadError::MissingUnique("TurnCount"))?;
+        let wins = wins.ok_or(LoadError::MissingUnique("Wins"))?;
+        let base_equipment_level =
+            base_equipment_level.ok_or(LoadError::MissingUnique("BaseEquipmentLevel"))?;
+        let mut difficulty = difficulty.ok_or(LoadError::MissingUnique("Difficulty"))?;
+        let messages = messages.ok_or(LoadError::MissingUnique("Messages"))?;
+        // Saves from before the journal existed simply have no quests.
+        let journal = journal.unwrap_or_default();
+        // Saves from before procedural quests existed have none in progress.
+        let quests = quests.unwrap_or_default();
+        // Saves from before run stats were tracked count the run from the very first turn.
+        let run_stats = run_stats.unwrap_or_default();
+        let player_alive = player_alive.ok_or(LoadError::MissingUnique("PlayerAlive"))?;
+        let mut player_id = player_id.ok_or(LoadError::MissingUnique("PlayerId"))?;
+        let mut map = map.ok_or(LoadError::MissingUnique("Map"))?;
+
+        // Replace old save-internal entity IDs with new loaded entity IDs.
+        difficulty.id = self
+            .old_to_new_ids
+            .get(&difficulty.id)
+            .copied()
+            .ok_or(LoadError::UnknownId(difficulty.id))?;
+        player_id.0 = self
+            .old_to_new_ids
+            .get(&player_id.0)
+            .copied()
+            .ok_or(LoadError::UnknownId(player_id.0))?;
+
+        // Ensure that we're only working with freshly-loaded entities below.
+        let new_ids = self
+            .old_to_new_ids
+            .values()
+            .copied()
+            .collect::<HashSet<EntityId>>();
+
+        // Replace entity IDs in equipment.
+        for (_, equipment) in IntoIter::iter(&mut world.borrow::<ViewMut<Equipment>>())
+            .with_id()
+            .filter(|(id, _)| new_ids.contains(id))
+        {
+            if let Some(weapon) = &mut equipment.weapon {
+                *weapon = self
+                    .old_to_new_ids
+                    .get(weapon)
+                    .copied()
+                    .ok_or(LoadError::UnknownId(*weapon))?;
+            }
+            if let Some(armor) = &mut equipment.armor {
+                *armor = self
+                    .old_to_new_ids
+                    .get(armor)
+                    .copied()
+                    .ok_or(LoadError::UnknownId(*armor))?;
+            }
         }
-        if let Some(armor) = &mut equipment.armor {
-            *armor = old_to_new_ids
-                .get(armor)
-                .copied()
-                .ok_or(LoadError::UnknownId(*armor))?;
+
+        // Replace entity IDs in inventories.
+        for (_, inventory) in IntoIter::iter(&mut world.borrow::<ViewMut<Inventory>>())
+            .with_id()
+            .filter(|(id, _)| new_ids.contains(id))
+        {
+            for item in inventory.items.iter_mut() {
+                *item = self
+                    .old_to_new_ids
+                    .get(item)
+                    .copied()
+                    .ok_or(LoadError::UnknownId(*item))?;
+            }
         }
-    }
 
-    // Replace entity IDs in inventories.
-    for (_, inventory) in IntoIter::iter(&mut world.borrow::<ViewMut<Inventory>>())
-        .with_id()
-        .filter(|(id, _)| new_ids.contains(id))
-    {
-        for item in inventory.items.iter_mut() {
-            *item = old_to_new_ids
-                .get(item)
-                .copied()
-                .ok_or(LoadError::UnknownId(*item))?;
+        // Replace entity IDs in damage logs, forgetting about anyone who isn't around anymore.
+        for (_, damage_log) in IntoIter::iter(&mut world.borrow::<ViewMut<DamageLog>>())
+            .with_id()
+            .filter(|(id, _)| new_ids.contains(id))
+        {
+            damage_log.0 = damage_log
+                .0
+                .iter()
+                .filter_map(|(id, damage)| {
+                    self.old_to_new_ids.get(id).map(|new_id| (*new_id, *damage))
+                })
+                .collect();
         }
-    }
 
-    // Place all Coord-carrying entities on the map.
-    for (id, coord) in IntoIter::iter(&world.borrow::<View<Coord>>()).with_id() {
-        let blocks_tile = world.borrow::<View<BlocksTile>>().try_get(id).is_ok();
-        map.place_entity(id, coord.0.into(), blocks_tile);
-    }
+        // Match up dynamic symbols by name, since their IDs depend on what was registered when the
+        // game was saved.  Saves from before names were kept use the IDs as they are.
+        if let Some(SymbolNames(names)) = symbol_names {
+            let registry = world.borrow::<UniqueView<SymbolRegistry>>();
+            let dyn_ids = names
+                .iter()
+                .map(|name| match registry.get(name) {
+                    Some(dsym) => dsym.id(),
+                    None => {
+                        eprintln!("Warning: Unknown symbol '{}' in save file", name);
+                        gamesym::UNKNOWN_DYN_ID
+                    }
+                })
+                .collect::<Vec<_>>();
+            let load_sym = |sym| match sym {
+                GameSym::Dyn(id) => GameSym::Dyn(
+                    dyn_ids
+                        .get(id as usize)
+                        .copied()
+                        .unwrap_or(gamesym::UNKNOWN_DYN_ID),
+                ),
+                sym => sym,
+            };
 
-    // Commit loaded entities and mark old existing entities for despawning.
-    despawn_ids.clear();
-    despawn_ids.push(world.borrow::<UniqueView<Difficulty>>().id);
-    despawn_ids.push(world.borrow::<UniqueView<PlayerId>>().0);
+            for (_, renderable) in IntoIter::iter(&mut world.borrow::<ViewMut<Renderable>>())
+                .with_id()
+                .filter(|(id, _)| new_ids.contains(id))
+            {
+                renderable.sym = load_sym(renderable.sym);
+            }
+            map.map_remembered_syms(load_sym);
+        }
 
-    // Commit uniques.
-    world.borrow::<UniqueViewMut<GameSeed>>().0 = game_seed.0;
-    world.borrow::<UniqueViewMut<TurnCount>>().0 = turn_count.0;
-    world.borrow::<UniqueViewMut<Wins>>().0 = wins.0;
-    world.borrow::<UniqueViewMut<BaseEquipmentLevel>>().0 = base_equipment_level.0;
-    world
-        .borrow::<UniqueViewMut<Difficulty>>()
-        .replace(difficulty);
-    world.borrow::<UniqueViewMut<Messages>>().replace(messages);
-    world.borrow::<UniqueViewMut<PlayerAlive>>().0 = player_alive.0;
-    world.borrow::<UniqueViewMut<PlayerId>>().0 = player_id.0;
-    world.borrow::<UniqueViewMut<Map>>().replace(map);
+        // Place all Coord-carrying entities on the map.
+        for (id, coord) in IntoIter::iter(&world.borrow::<View<Coord>>()).with_id() {
+            let blocks_tile = world.borrow::<View<BlocksTile>>().try_get(id).is_ok();
+            map.place_entity(id, coord.0.into(), blocks_tile);
+        }
 
-    Ok(())
+        // Commit loaded entities and mark old existing entities for despawning.
+        self.despawn_ids.clear();
+        self.despawn_ids
+            .push(world.borrow::<UniqueView<Difficulty>>().id);
+        self.despawn_ids
+            .push(world.borrow::<UniqueView<PlayerId>>().0);
+
+        // Commit uniques.
+        world.borrow::<UniqueViewMut<GameSeed>>().0 = game_seed.0;
+        world.borrow::<UniqueViewMut<TurnCount>>().0 = turn_count.0;
+        world.borrow::<UniqueViewMut<Wins>>().0 = wins.0;
+        world.borrow::<UniqueViewMut<BaseEquipmentLevel>>().0 = base_equipment_level.0;
+        world
+            .borrow::<UniqueViewMut<Difficulty>>()
+            .replace(difficulty);
+        world.borrow::<UniqueViewMut<Messages>>().replace(messages);
+        world.borrow::<UniqueViewMut<Journal>>().replace(journal);
+        world.borrow::<UniqueViewMut<Quests>>().replace(quests);
+        world.borrow::<UniqueViewMut<RunStats>>().replace(run_stats);
+        world.borrow::<UniqueViewMut<PlayerAlive>>().0 = player_alive.0;
+        world.borrow::<UniqueViewMut<PlayerId>>().0 = player_id.0;
+        world.borrow::<UniqueViewMut<Map>>().replace(map);
+
+        // Names are saved in whatever language was chosen back then.
+        spawn::translate_names(world);
+
+        Ok(())
+    }
 }
 
-/// Load the game state stored in the save file and despawn entities that need despawning after the
-/// process of loading succeeds or fails.
-pub fn load_game(world: &World) -> Result<(), BoxedError> {
-    let mut delete_ids = Vec::new();
-    let result = load_save_file(world, &mut delete_ids);
+/// Load the given save file into a scratch world and print a report of its contents, so a suspect
+/// save can be checked without starting the game.  Returns true if the save loaded successfully.
+pub fn validate_save(path: &str) -> bool {
+    let world = World::new();
+
+    world.add_unique(Locale::load(&locale::discover_languages(), 0));
+    world.add_unique(gamesym::load_symbol_registry());
+    world.add_unique(GameSeed(0));
+    world.add_unique(TurnCount(0));
+    world.add_unique(Wins(0));
+    world.add_unique(BaseEquipmentLevel(0));
+    world.add_unique(Difficulty::new(world.run(spawn::spawn_difficulty)));
+    world.add_unique(Messages::new(100));
+    world.add_unique(Journal::default());
+    world.add_unique(Quests::default());
+    world.add_unique(RunStats::default());
+    world.add_unique(Map::new(80, 50));
+    world.add_unique(PlayerId(world.run(spawn::spawn_player)));
+    world.add_unique(PlayerAlive(true));
+
+    println!("Save file: {}", path);
+
+    if let Err(e) =
+        GameLoader::open(path).and_then(|mut loader| loader.load_lines(&world, usize::MAX))
+    {
+        println!("Result: invalid");
+        println!("Error: {}", e);
+        return false;
+    }
+
+    let player_id = world.borrow::<UniqueView<PlayerId>>().0;
+    let map = world.borrow::<UniqueView<Map>>();
+    let coords = world.borrow::<View<Coord>>();
+    let monsters = world.borrow::<View<Monster>>();
+    let items = world.borrow::<View<Item>>();
+    let inventory_len = world
+        .borrow::<View<Inventory>>()
+        .try_get(player_id)
+        .map_or(0, |inv| inv.items.len());
 
-    for id in delete_ids {
-        spawn::despawn_entity(&mut world.borrow::<AllStoragesViewMut>(), id);
+    println!("Result: valid");
+    println!("Game seed: {}", world.borrow::<UniqueView<GameSeed>>().0);
+    println!("Turn: {}", world.borrow::<UniqueView<TurnCount>>().0);
+    println!("Wins: {}", world.borrow::<UniqueView<Wins>>().0);
+    println!("Depth: {}", map.depth);
+    println!("Rooms: {}", map.rooms.len());
+    if let Ok(exp) = world.borrow::<View<Experience>>().try_get(player_id) {
+        println!("Player level: {}", exp.level);
+    }
+    if let Ok(stats) = world.borrow::<View<CombatStats>>().try_get(player_id) {
+        println!("Player HP: {}/{}", stats.hp, stats.max_hp);
     }
+    println!("Inventory items: {}", inventory_len);
+    println!("Monsters on map: {}", (&monsters, &coords).iter().count());
+    println!("Items on map: {}", (&items, &coords).iter().count());
 
-    result
+    true
 }
 
 /// Helper module that converts a list of values into a run-length encoded vector of pairs when
@@ -521,6 +992,35 @@ pub mod run_length_encoded {
     }
 }
 
+/// Helper module that converts a HashMap keyed by positions into a list of pairs for Serde, since
+/// JSON only allows strings as keys.
+pub mod pos_map {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use std::collections::HashMap;
+
+    pub fn serialize<S, T>(map: &HashMap<(i32, i32), T>, s: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        T: Serialize,
+    {
+        // Sort the pairs so that the same map always saves the same way.
+        let mut pairs = map.iter().collect::<Vec<_>>();
+
+        pairs.sort_by_key(|((x, y), _)| (*y, *x));
+        pairs.serialize(s)
+    }
+
+    pub fn deserialize<'de, D, T>(d: D) -> Result<HashMap<(i32, i32), T>, D::Error>
+    where
+        D: Deserializer<'de>,
+        T: Deserialize<'de>,
+    {
+        Ok(Vec::<((i32, i32), T)>::deserialize(d)?
+            .into_iter()
+            .collect())
+    }
+}
+
 /// Helper module that converts a BitVec into a run-length encoded list of u8s for Serde.
 pub mod bit_vec {
     use bitvec::prelude::*;
@@ -0,0 +1,241 @@
+use shipyard::{Get, IntoIter, UniqueView, UniqueViewMut, View, World};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    components::{Coord, FieldOfView, RenderOnFloor, RenderOnMap, Renderable},
+    gamekey::{self, GameKey},
+    gamesym::GameSym,
+    locale::Locale,
+    map::Map,
+    message::{Messages, MsgCategory},
+    player::PlayerId,
+    render,
+    ui::Options,
+};
+use ruggrogue::{
+    util::{Color, Size},
+    FrameRequests, InputBuffer, InputEvent, KeyMods, Symbol, TileGrid, Tileset,
+};
+
+/// Directory that screenshots and level exports are written to.
+const SCREENSHOT_DIR: &str = "screenshots";
+
+/// Path in the screenshot directory named after the current time, so that new files don't
+/// overwrite old ones.
+fn timestamped_path(prefix: &str, extension: &str) -> PathBuf {
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0);
+
+    Path::new(SCREENSHOT_DIR).join(format!("{}-{}.{}", prefix, millis, extension))
+}
+
+/// Symbol and color of each tile of the current level in rows from the top-left, or None for
+/// tiles that aren't shown.
+///
+/// Only what the player has explored and can see is shown, unless `full` is true, in which case
+/// the whole level and everything on it is shown.
+pub fn level_cells(world: &World, full: bool) -> Vec<Option<(GameSym, Color)>> {
+    let (map, options, player_id, coords, fovs, render_on_floors, render_on_maps, renderables) =
+        world.borrow::<(
+            UniqueView<Map>,
+            UniqueView<Options>,
+            UniqueView<PlayerId>,
+            View<Coord>,
+            View<FieldOfView>,
+            View<RenderOnFloor>,
+            View<RenderOnMap>,
+            View<Renderable>,
+        )>();
+    let fov = fovs.get(player_id.0);
+    let index = |x: i32, y: i32| (y * map.width + x) as usize;
+    let mut cells = vec![None; (map.width * map.height) as usize];
+
+    for y in 0..map.height {
+        for x in 0..map.width {
+            if map.is_playable(x, y) && (full || map.seen.get_bit(x, y)) {
+                cells[index(x, y)] = Some(match map.remembered_at(x, y) {
+                    Some(remembered) if !full && !fov.get((x, y)) => {
+                        (remembered.sym, remembered.fg)
+                    }
+                    _ => map.tile_appearance(x, y),
+                });
+            }
+        }
+    }
+
+    let mut put = |coord: &Coord, render: &Renderable| {
+        let (x, y) = coord.0.into();
+
+        if x >= 0 && y >= 0 && x < map.width && y < map.height && (full || fov.get((x, y))) {
+            cells[index(x, y)] = Some((render.sym, render::palette_color(&options, render.fg)));
+        }
+    };
+
+    // Entities on the floor go under everything else, just like on screen.
+    for (coord, render, _) in (&coords, &renderables, &render_on_floors).iter() {
+        put(coord, render);
+    }
+    for (coord, render, _) in (&coords, &renderables, &render_on_maps).iter() {
+        put(coord, render);
+    }
+
+    cells
+}
+
+/// Character that stands for a symbol in plain text.
+pub fn text_char(sym: GameSym) -> char {
+    sym.distinct_char().unwrap_or_else(|| sym.text_fallback())
+}
+
+/// Write the cells of a level as lines of text, leaving out trailing spaces.
+fn write_level_text(
+    path: &Path,
+    cells: &[Option<(GameSym, Color)>],
+    width: i32,
+) -> Result<(), String> {
+    let mut text = String::with_capacity(cells.len() + cells.len() / width as usize);
+
+    for row in cells.chunks(width as usize) {
+        let line: String = row
+            .iter()
+            .map(|cell| match cell {
+                Some((sym, _)) => text_char(*sym),
+                None => ' ',
+            })
+            .collect();
+
+        text.push_str(line.trim_end());
+        text.push('\n');
+    }
+
+    fs::write(path, text).map_err(|e| e.to_string())
+}
+
+/// Export the whole current level as a text file and as a PNG image drawn with the current
+/// tileset.
+///
+/// Debug builds export everything on the level; otherwise only what the player has explored.
+fn export_level(
+    world: &World,
+    tilesets: &[Tileset<GameSym>],
+    requests: &mut FrameRequests<GameSym>,
+) {
+    let full = cfg!(debug_assertions);
+    let cells = level_cells(world, full);
+    let (width, height, depth) = {
+        let map = world.borrow::<UniqueView<Map>>();
+        (map.width, map.height, map.depth)
+    };
+    let prefix = format!("level{}", depth);
+    let text_path = timestamped_path(&prefix, "txt");
+    let result = write_level_text(&text_path, &cells, width);
+
+    requests.saved_images.push((text_path, result));
+
+    let tileset = world.borrow::<UniqueView<Options>>().tileset as usize;
+    let mut grid = TileGrid::new(
+        Size {
+            w: width as u32,
+            h: height as u32,
+        },
+        tilesets,
+        tileset,
+    );
+
+    for (i, cell) in cells.iter().enumerate() {
+        let pos = (i as i32 % width, i as i32 / width);
+
+        match cell {
+            Some((sym, fg)) => grid.put_sym_color_raw(pos, *sym, *fg, Color::BLACK),
+            None => grid.put_char_color_raw(pos, ' ', Color::WHITE, Color::BLACK),
+        }
+    }
+
+    requests
+        .grid_images
+        .push((grid, timestamped_path(&prefix, "png")));
+}
+
+/// Report files saved since the last update, as messages if a game is being played.
+fn report_saved(world: &World, in_game: bool, requests: &mut FrameRequests<GameSym>) {
+    if requests.saved_images.is_empty() {
+        return;
+    }
+
+    let locale = world.borrow::<UniqueView<Locale>>();
+    let mut msgs = world.borrow::<UniqueViewMut<Messages>>();
+
+    for (path, result) in requests.saved_images.drain(..) {
+        let path = path.display();
+
+        match result {
+            Ok(()) => {
+                if in_game {
+                    msgs.add(locale.format("msg.screenshot_saved", &[("path", &path)]));
+                }
+            }
+            Err(e) => {
+                eprintln!("Warning: screenshot::report_saved: {}: {}", path, e);
+                if in_game {
+                    msgs.add_as(
+                        MsgCategory::Warning,
+                        locale.format("msg.screenshot_failed", &[("path", &path)]),
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Handle the screenshot and level export keys if nothing else handled them, and report on files
+/// saved since the last update.
+///
+/// Levels can only be exported while a game is being played.
+pub fn update(
+    world: &World,
+    in_game: bool,
+    inputs: &InputBuffer,
+    tilesets: &[Tileset<GameSym>],
+    requests: &mut FrameRequests<GameSym>,
+) {
+    report_saved(world, in_game, requests);
+
+    let gkey = match inputs.get_input() {
+        Some(InputEvent::Press(keycode)) => {
+            gamekey::from_keycode(keycode, inputs.get_mods(KeyMods::SHIFT))
+        }
+        _ => return,
+    };
+
+    if !matches!(gkey, GameKey::Screenshot | GameKey::ExportMap) {
+        return;
+    }
+
+    // Files written in the web version can't be reached by the player.
+    if cfg!(target_os = "emscripten") {
+        if in_game {
+            let locale = world.borrow::<UniqueView<Locale>>();
+            world
+                .borrow::<UniqueViewMut<Messages>>()
+                .add(locale.get("msg.screenshots_unavailable").into());
+        }
+        return;
+    }
+
+    if let Err(e) = fs::create_dir_all(SCREENSHOT_DIR) {
+        eprintln!("Warning: screenshot::update: {}: {}", SCREENSHOT_DIR, e);
+        return;
+    }
+
+    match gkey {
+        GameKey::Screenshot => requests.screenshot = Some(timestamped_path("screenshot", "png")),
+        GameKey::ExportMap if in_game => export_level(world, tilesets, requests),
+        _ => {}
+    }
+}
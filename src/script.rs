@@ -0,0 +1,290 @@
+use serde::Deserialize;
+use shipyard::{EntitiesView, EntityId, Get, UniqueView, UniqueViewMut, View, ViewMut, World};
+use std::{collections::HashMap, fs};
+
+use crate::{
+    components::{
+        Afraid, Asleep, Charmed, CombatStats, Confused, Coord, DamageLog, HurtBy, LastSeenPlayer,
+        Name, Player, Tally,
+    },
+    damage, event, faction,
+    gamesym::GameSym,
+    message::{Messages, MsgCategory},
+    npc::GiftItem,
+    obstacle, spawn,
+};
+
+const SCRIPTS_PATH: &str = "assets/scripts.json";
+
+/// Which entity a script operation acts on.
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScriptSubject {
+    User,
+    Target,
+}
+
+/// A single step of a script.  Scripts can only affect the game through these operations.
+#[derive(Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum ScriptOp {
+    /// Add a message to the log; "{user}" and "{target}" are replaced with their names.
+    Message { text: String },
+    /// Take hit points away, crediting the user for any resulting death.
+    Damage { who: ScriptSubject, amount: i32 },
+    /// Restore hit points, up to the maximum.
+    Heal { who: ScriptSubject, amount: i32 },
+    /// Put to sleep for roughly the given number of turns.
+    Sleep { who: ScriptSubject, turns: i32 },
+    /// Make moves go astray for the given number of turns.
+    Confuse { who: ScriptSubject, turns: i32 },
+    /// Make too afraid to fight for the given number of turns.
+    Frighten { who: ScriptSubject, turns: i32 },
+    /// Make fight for the faction of the other party for the given number of turns; the player
+    /// can't be charmed.
+    Charm { who: ScriptSubject, turns: i32 },
+    /// Call up a monster from the spawn tables by name next to the subject to fight for its
+    /// faction.
+    Summon { who: ScriptSubject, monster: String },
+    /// Drop an item at the feet of the subject.
+    Spawn { who: ScriptSubject, item: GiftItem },
+    /// Unlock every locked door within the given distance of the subject.
+    Knock { who: ScriptSubject, radius: i32 },
+}
+
+/// An item whose effect when used is a script, as defined in the scripts file.
+#[derive(Deserialize)]
+pub struct ScriptedItemDef {
+    pub name: String,
+    pub sym: GameSym,
+    pub fg: (u8, u8, u8),
+    #[serde(default)]
+    pub desc: String,
+    #[serde(default = "default_weight")]
+    pub weight: i32,
+    #[serde(default = "default_min_depth")]
+    pub min_depth: i32,
+    /// Targeting range; the item affects only its user if absent.
+    #[serde(default)]
+    pub range: Option<i32>,
+    #[serde(default)]
+    pub radius: i32,
+    pub on_use: String,
+}
+
+/// A script that a monster has a chance to run against whatever it hits in melee.
+#[derive(Deserialize)]
+pub struct MonsterAbilityDef {
+    pub monster: String,
+    /// Percent chance of the ability triggering on a hit.
+    pub chance: u32,
+    pub on_hit: String,
+}
+
+fn default_min_depth() -> i32 {
+    1
+}
+
+fn default_weight() -> i32 {
+    1
+}
+
+/// Scripts and the content hooked up to them, loaded from the scripts file at startup.
+#[derive(Default, Deserialize)]
+pub struct Scripts {
+    #[serde(default)]
+    scripts: HashMap<String, Vec<ScriptOp>>,
+    #[serde(default)]
+    pub items: Vec<ScriptedItemDef>,
+    #[serde(default)]
+    pub abilities: Vec<MonsterAbilityDef>,
+}
+
+impl Scripts {
+    pub fn ability_for(&self, monster_name: &str) -> Option<&MonsterAbilityDef> {
+        self.abilities.iter().find(|a| a.monster == monster_name)
+    }
+}
+
+/// Load scripts from the scripts file, if it exists.
+///
+/// Items and abilities that refer to missing scripts are skipped with a warning.
+pub fn load_scripts() -> Scripts {
+    let mut scripts = match fs::read_to_string(SCRIPTS_PATH) {
+        Ok(contents) => match serde_json::from_str::<Scripts>(&contents) {
+            Ok(scripts) => scripts,
+            Err(e) => {
+                eprintln!("Warning: Failed to parse {}: {}", SCRIPTS_PATH, e);
+                Scripts::default()
+            }
+        },
+        Err(e) => {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                eprintln!("Warning: Failed to read {}: {}", SCRIPTS_PATH, e);
+            }
+            Scripts::default()
+        }
+    };
+    let known = &scripts.scripts;
+
+    scripts.items.retain(|item| {
+        let valid = known.contains_key(&item.on_use);
+        if !valid {
+            eprintln!(
+                "Warning: Scripted item '{}' uses missing script '{}'",
+                item.name, item.on_use
+            );
+        }
+        valid
+    });
+    scripts.abilities.retain(|ability| {
+        let valid = known.contains_key(&ability.on_hit);
+        if !valid {
+            eprintln!(
+                "Warning: Ability of '{}' uses missing script '{}'",
+                ability.monster, ability.on_hit
+            );
+        }
+        valid
+    });
+
+    scripts
+}
+
+/// Run the named script with the given user and target.  Operations on entities that have already
+/// been killed by an earlier operation are skipped.
+pub fn run_script(world: &World, name: &str, user: EntityId, target: EntityId) {
+    let scripts = world.borrow::<UniqueView<Scripts>>();
+    let ops = match scripts.scripts.get(name) {
+        Some(ops) => ops,
+        None => return,
+    };
+
+    for op in ops {
+        let subject = |who: ScriptSubject| match who {
+            ScriptSubject::User => user,
+            ScriptSubject::Target => target,
+        };
+        let alive = |id: EntityId| matches!(world.borrow::<View<CombatStats>>().try_get(id), Ok(stats) if stats.hp > 0);
+
+        match op {
+            ScriptOp::Message { text } => {
+                let names = world.borrow::<View<Name>>();
+                let name_of = |id: EntityId| names.try_get(id).map_or("", |n| n.0.as_str());
+                let text = text
+                    .replace("{user}", name_of(user))
+                    .replace("{target}", name_of(target));
+
+                world
+                    .borrow::<UniqueViewMut<Messages>>()
+                    .add_as(MsgCategory::Combat, text);
+            }
+            ScriptOp::Damage { who, amount } if alive(subject(*who)) => {
+                let id = subject(*who);
+                let entities = world.borrow::<EntitiesView>();
+                let mut damage_logs = world.borrow::<ViewMut<DamageLog>>();
+                let mut hurt_bys = world.borrow::<ViewMut<HurtBy>>();
+                let mut tallies = world.borrow::<ViewMut<Tally>>();
+
+                (&mut world.borrow::<ViewMut<CombatStats>>()).get(id).hp -= amount;
+                event::emit_damage(world, id, *amount);
+                if id != user {
+                    entities.add_component(&mut hurt_bys, HurtBy::Someone(user), id);
+                    damage::log_damage(&entities, &mut damage_logs, user, id, *amount);
+                    if let Ok(user_tally) = (&mut tallies).try_get(user) {
+                        user_tally.damage_dealt += (*amount).max(0) as u64;
+                    }
+                }
+                if let Ok(tally) = (&mut tallies).try_get(id) {
+                    tally.damage_taken += (*amount).max(0) as u64;
+                }
+            }
+            ScriptOp::Heal { who, amount } if alive(subject(*who)) => {
+                let mut combat_stats = world.borrow::<ViewMut<CombatStats>>();
+                let stats = (&mut combat_stats).get(subject(*who));
+                let healed = (stats.hp + amount).min(stats.max_hp) - stats.hp;
+
+                stats.hp += healed;
+                event::emit_damage(world, subject(*who), -healed);
+            }
+            ScriptOp::Sleep { who, turns } if alive(subject(*who)) => {
+                let id = subject(*who);
+                let last_hp = world.borrow::<View<CombatStats>>().get(id).hp;
+
+                world.borrow::<EntitiesView>().add_component(
+                    &mut world.borrow::<ViewMut<Asleep>>(),
+                    Asleep {
+                        sleepiness: *turns,
+                        last_hp,
+                    },
+                    id,
+                );
+            }
+            ScriptOp::Confuse { who, turns } if alive(subject(*who)) => {
+                world.borrow::<EntitiesView>().add_component(
+                    &mut world.borrow::<ViewMut<Confused>>(),
+                    Confused { turns: *turns },
+                    subject(*who),
+                );
+            }
+            ScriptOp::Frighten { who, turns } if alive(subject(*who)) => {
+                world.borrow::<EntitiesView>().add_component(
+                    &mut world.borrow::<ViewMut<Afraid>>(),
+                    Afraid { turns: *turns },
+                    subject(*who),
+                );
+            }
+            ScriptOp::Charm { who, turns } if alive(subject(*who)) => {
+                let id = subject(*who);
+                let other = if id == user { target } else { user };
+                let faction = faction::faction_of(world, other);
+
+                if let Some(faction) =
+                    faction.filter(|_| !world.borrow::<View<Player>>().contains(id))
+                {
+                    world.borrow::<EntitiesView>().add_component(
+                        &mut world.borrow::<ViewMut<Charmed>>(),
+                        Charmed {
+                            faction,
+                            turns: *turns,
+                        },
+                        id,
+                    );
+                    world.borrow::<ViewMut<LastSeenPlayer>>().remove(id);
+                }
+            }
+            ScriptOp::Summon { who, monster } => {
+                let id = subject(*who);
+                let pos = world
+                    .borrow::<View<Coord>>()
+                    .try_get(id)
+                    .map(|coord| coord.0.into());
+
+                if let (Ok(pos), Some(faction)) = (pos, faction::faction_of(world, id)) {
+                    spawn::spawn_summon(world, pos, monster, faction);
+                }
+            }
+            ScriptOp::Spawn { who, item } => {
+                let pos = world
+                    .borrow::<View<Coord>>()
+                    .try_get(subject(*who))
+                    .map(|coord| coord.0.into());
+
+                if let Ok(pos) = pos {
+                    spawn::spawn_gift(world, pos, *item);
+                }
+            }
+            ScriptOp::Knock { who, radius } => {
+                let pos = world
+                    .borrow::<View<Coord>>()
+                    .try_get(subject(*who))
+                    .map(|coord| coord.0.into());
+
+                if let Ok(pos) = pos {
+                    obstacle::unlock_doors_near(world, pos, *radius);
+                }
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,48 @@
+use crate::{components::FieldOfView, locale::Locale};
+
+/// How far away the player can hear things happen, in tiles.
+const HEARING_RADIUS: i32 = 16;
+
+const DIRECTIONS: [&str; 8] = [
+    "dir.east",
+    "dir.northeast",
+    "dir.north",
+    "dir.northwest",
+    "dir.west",
+    "dir.southwest",
+    "dir.south",
+    "dir.southeast",
+];
+
+/// Locale key of the compass direction of an offset, with negative `dy` being north.
+pub fn direction(dx: i32, dy: i32) -> &'static str {
+    let angle = (-dy as f32).atan2(dx as f32);
+    let sector = (angle / std::f32::consts::FRAC_PI_4).round() as i32;
+
+    DIRECTIONS[sector.rem_euclid(8) as usize]
+}
+
+/// Describe a sound made at `pos` if the player can hear it but can't see where it came from,
+/// e.g. "You hear footsteps to the north."  `what` is the locale key of the sound.
+pub fn cue(
+    locale: &Locale,
+    player_pos: (i32, i32),
+    player_fov: &FieldOfView,
+    pos: (i32, i32),
+    what: &str,
+) -> Option<String> {
+    let dx = pos.0 - player_pos.0;
+    let dy = pos.1 - player_pos.1;
+
+    if player_fov.get(pos) || dx * dx + dy * dy > HEARING_RADIUS * HEARING_RADIUS {
+        None
+    } else {
+        Some(locale.format(
+            "msg.hear",
+            &[
+                ("what", &locale.get(what)),
+                ("direction", &locale.get(direction(dx, dy))),
+            ],
+        ))
+    }
+}
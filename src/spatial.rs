@@ -0,0 +1,322 @@
+use shipyard::EntityId;
+
+/// Slot index of cells with no entities in them.
+const EMPTY: u32 = u32::MAX;
+
+/// Entities sharing a single tile, along with how many of them block it.
+#[derive(Default)]
+struct Slot {
+    block_count: i32,
+    entities: Vec<EntityId>,
+}
+
+/// Which entities are at each position of a map, for quick lookups by position.
+///
+/// Each tile of the map holds an index into a list of slots, and only tiles with entities on them
+/// have a slot.  Slots of tiles that empty out go onto a free list so that their entity lists can
+/// be reused without allocating as entities move around.
+///
+/// Distances are measured the same way as [crate::components::Coord::dist], so a radius of `r`
+/// covers a square `2 * r + 1` tiles across.
+#[derive(Default)]
+pub struct SpatialIndex {
+    width: i32,
+    height: i32,
+    cells: Vec<u32>,
+    slots: Vec<Slot>,
+    free_slots: Vec<u32>,
+}
+
+impl SpatialIndex {
+    pub fn new(width: i32, height: i32) -> Self {
+        let mut index = Self::default();
+
+        index.reset(width, height);
+        index
+    }
+
+    /// True if the index covers a map of the given size.
+    pub fn fits(&self, width: i32, height: i32) -> bool {
+        self.width == width && self.height == height
+    }
+
+    /// Remove all entities and change the size of the area covered by the index.
+    pub fn reset(&mut self, width: i32, height: i32) {
+        self.width = width;
+        self.height = height;
+        self.clear();
+    }
+
+    /// Remove all entities, keeping allocated slots for reuse.
+    pub fn clear(&mut self) {
+        self.cells.clear();
+        self.cells
+            .resize((self.width * self.height).max(0) as usize, EMPTY);
+        self.free_slots.clear();
+        for (i, slot) in self.slots.iter_mut().enumerate() {
+            slot.block_count = 0;
+            slot.entities.clear();
+            self.free_slots.push(i as u32);
+        }
+    }
+
+    fn cell_index(&self, (x, y): (i32, i32)) -> Option<usize> {
+        if x >= 0 && y >= 0 && x < self.width && y < self.height {
+            Some((y * self.width + x) as usize)
+        } else {
+            None
+        }
+    }
+
+    fn slot_at(&self, pos: (i32, i32)) -> Option<&Slot> {
+        self.cell_index(pos)
+            .map(|i| self.cells[i])
+            .filter(|&slot| slot != EMPTY)
+            .map(|slot| &self.slots[slot as usize])
+    }
+
+    pub fn insert(&mut self, entity: EntityId, pos: (i32, i32), blocks: bool) {
+        let cell = self.cell_index(pos).expect("entity placed outside of map");
+
+        if self.cells[cell] == EMPTY {
+            self.cells[cell] = match self.free_slots.pop() {
+                Some(slot) => slot,
+                None => {
+                    self.slots.push(Slot::default());
+                    self.slots.len() as u32 - 1
+                }
+            };
+        }
+
+        let slot = &mut self.slots[self.cells[cell] as usize];
+
+        if blocks {
+            slot.block_count += 1;
+        }
+        slot.entities.push(entity);
+    }
+
+    pub fn remove(&mut self, entity: EntityId, pos: (i32, i32), blocks: bool) {
+        let cell = self.cell_index(pos).expect("entity removed outside of map");
+        let slot_index = self.cells[cell];
+        let slot = &mut self.slots[slot_index as usize];
+        let idx = slot.entities.iter().position(|e| *e == entity).unwrap();
+
+        slot.entities.remove(idx);
+        if blocks {
+            slot.block_count -= 1;
+        }
+
+        if slot.entities.is_empty() {
+            self.cells[cell] = EMPTY;
+            self.free_slots.push(slot_index);
+        }
+    }
+
+    pub fn move_entity(
+        &mut self,
+        entity: EntityId,
+        old_pos: (i32, i32),
+        new_pos: (i32, i32),
+        blocks: bool,
+    ) {
+        let old_cell = self
+            .cell_index(old_pos)
+            .expect("entity moved from outside of map");
+        let new_cell = self
+            .cell_index(new_pos)
+            .expect("entity moved outside of map");
+        let old_slot = self.cells[old_cell];
+
+        // An entity alone on its tile moving onto an empty tile can take its slot along with it.
+        if self.cells[new_cell] == EMPTY && self.slots[old_slot as usize].entities.len() == 1 {
+            assert!(self.slots[old_slot as usize].entities[0] == entity);
+            self.cells[new_cell] = old_slot;
+            self.cells[old_cell] = EMPTY;
+        } else {
+            self.remove(entity, old_pos, blocks);
+            self.insert(entity, new_pos, blocks);
+        }
+    }
+
+    /// True if any entity at the position blocks it.
+    pub fn is_blocked(&self, pos: (i32, i32)) -> bool {
+        self.slot_at(pos).is_some_and(|slot| slot.block_count > 0)
+    }
+
+    pub fn entities_at(&self, pos: (i32, i32)) -> impl Iterator<Item = EntityId> + '_ {
+        self.slot_at(pos)
+            .map(|slot| slot.entities.iter().copied())
+            .into_iter()
+            .flatten()
+    }
+
+    /// Every entity within `radius` tiles of a position along with where it is, row by row.
+    pub fn within_radius(
+        &self,
+        center: (i32, i32),
+        radius: i32,
+    ) -> impl Iterator<Item = (EntityId, (i32, i32))> + '_ {
+        let x1 = (center.0 - radius).max(0);
+        let x2 = (center.0 + radius).min(self.width - 1);
+        let y1 = (center.1 - radius).max(0);
+        let y2 = (center.1 + radius).min(self.height - 1);
+
+        (y1..=y2)
+            .flat_map(move |y| (x1..=x2).map(move |x| (x, y)))
+            .flat_map(move |pos| self.entities_at(pos).map(move |id| (id, pos)))
+    }
+
+    /// The closest entity within `max_radius` tiles of a position that satisfies the predicate,
+    /// along with where it is.
+    ///
+    /// Tiles are searched in rings of increasing distance, so only as much of the map as needed is
+    /// looked at.  Ties between equally close entities go to the first one found in a ring,
+    /// starting from the top-left corner of the ring and going clockwise.
+    pub fn nearest<F>(
+        &self,
+        center: (i32, i32),
+        max_radius: i32,
+        mut pred: F,
+    ) -> Option<(EntityId, (i32, i32))>
+    where
+        F: FnMut(EntityId, (i32, i32)) -> bool,
+    {
+        for r in 0..=max_radius.max(0) {
+            let found = ring(center, r)
+                .flat_map(|pos| self.entities_at(pos).map(move |id| (id, pos)))
+                .find(|&(id, pos)| pred(id, pos));
+
+            if found.is_some() {
+                return found;
+            }
+        }
+
+        None
+    }
+
+    /// Panic if the index has become inconsistent with itself.
+    pub fn check_consistency(&self) {
+        let mut slot_used = vec![false; self.slots.len()];
+
+        assert_eq!(self.cells.len(), (self.width * self.height).max(0) as usize);
+
+        for &slot in self.cells.iter().filter(|&&slot| slot != EMPTY) {
+            let used = &mut slot_used[slot as usize];
+
+            assert!(!*used, "slot {} is shared by multiple tiles", slot);
+            *used = true;
+
+            let slot = &self.slots[slot as usize];
+
+            assert!(!slot.entities.is_empty(), "tile has an empty slot");
+            assert!(slot.block_count >= 0 && slot.block_count as usize <= slot.entities.len());
+        }
+
+        for &slot in &self.free_slots {
+            assert!(
+                !slot_used[slot as usize],
+                "slot {} is both used and free",
+                slot
+            );
+            assert!(self.slots[slot as usize].entities.is_empty());
+            slot_used[slot as usize] = true;
+        }
+
+        assert!(slot_used.iter().all(|used| *used), "slot leaked");
+    }
+}
+
+/// Positions exactly `r` tiles away from the center, clockwise from the top-left corner.
+fn ring(center: (i32, i32), r: i32) -> impl Iterator<Item = (i32, i32)> {
+    let (cx, cy) = center;
+    let side = 2 * r;
+    let steps = if r == 0 { 1 } else { 4 * side };
+
+    (0..steps).map(move |i| {
+        if r == 0 {
+            center
+        } else if i < side {
+            (cx - r + i, cy - r)
+        } else if i < 2 * side {
+            (cx + r, cy - r + (i - side))
+        } else if i < 3 * side {
+            (cx + r - (i - 2 * side), cy + r)
+        } else {
+            (cx - r, cy + r - (i - 3 * side))
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shipyard::{EntitiesViewMut, World};
+
+    fn new_ids(n: usize) -> Vec<EntityId> {
+        let world = World::new();
+        let mut entities = world.borrow::<EntitiesViewMut>();
+
+        (0..n).map(|_| entities.add_entity((), ())).collect()
+    }
+
+    #[test]
+    fn slots_are_reused_as_entities_move() {
+        let ids = new_ids(3);
+        let mut index = SpatialIndex::new(10, 10);
+
+        index.insert(ids[0], (1, 1), true);
+        index.insert(ids[1], (1, 1), false);
+        index.insert(ids[2], (5, 5), true);
+        index.check_consistency();
+        assert!(index.is_blocked((1, 1)));
+
+        index.move_entity(ids[0], (1, 1), (2, 1), true);
+        index.move_entity(ids[2], (5, 5), (6, 5), true);
+        index.check_consistency();
+        assert!(!index.is_blocked((1, 1)));
+        assert_eq!(index.entities_at((1, 1)).collect::<Vec<_>>(), vec![ids[1]]);
+        assert_eq!(index.entities_at((6, 5)).collect::<Vec<_>>(), vec![ids[2]]);
+
+        index.remove(ids[1], (1, 1), false);
+        index.remove(ids[0], (2, 1), true);
+        index.check_consistency();
+        assert_eq!(index.slots.len(), 3);
+        assert_eq!(index.free_slots.len(), 2);
+
+        index.insert(ids[0], (9, 9), false);
+        index.check_consistency();
+        assert_eq!(index.slots.len(), 3);
+    }
+
+    #[test]
+    fn radius_and_nearest_queries() {
+        let ids = new_ids(3);
+        let mut index = SpatialIndex::new(20, 20);
+
+        index.insert(ids[0], (10, 10), true);
+        index.insert(ids[1], (13, 8), true);
+        index.insert(ids[2], (0, 0), true);
+
+        let mut near = index
+            .within_radius((11, 10), 2)
+            .map(|(id, _)| id)
+            .collect::<Vec<_>>();
+        near.sort();
+        assert_eq!(near, vec![ids[0], ids[1]]);
+
+        assert_eq!(
+            index.nearest((12, 9), 5, |_, _| true),
+            Some((ids[1], (13, 8)))
+        );
+        assert_eq!(
+            index.nearest((12, 9), 5, |id, _| id != ids[1]),
+            Some((ids[0], (10, 10)))
+        );
+        assert_eq!(index.nearest((12, 9), 5, |id, _| id == ids[2]), None);
+        assert_eq!(
+            index.nearest((1, 1), 1, |_, _| true),
+            Some((ids[2], (0, 0)))
+        );
+    }
+}
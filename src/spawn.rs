@@ -1,80 +1,35 @@
 use rand::{
     seq::{IteratorRandom, SliceRandom},
-    Rng, SeedableRng,
+    Rng,
 };
-use rand_xoshiro::Xoshiro128PlusPlus as GameRng;
 use shipyard::{
     AllStoragesViewMut, EntitiesView, EntitiesViewMut, EntityId, Get, IntoIter, Shiperator,
     UniqueView, UniqueViewMut, View, ViewMut, World,
 };
-use std::hash::Hasher;
-use wyhash::WyHash;
 
 use crate::{
+    bones::Remains,
     components::*,
+    durability,
     experience::{self, Difficulty},
     gamesym::GameSym,
-    magicnum,
-    map::{Map, Rect},
-    BaseEquipmentLevel, GameSeed, Wins,
+    item,
+    locale::Locale,
+    map::{Map, Rect, Tile},
+    npc::{GiftItem, NpcDef, NpcDefs},
+    prefab::PrefabMark,
+    rng::RngStream,
+    script::Scripts,
+    spawn_tables::{DropTable, FriendlyEntry, QualityTier, SpawnEntry, SpawnTables},
+    ui::Options,
+    BaseEquipmentLevel, GameSeed, TurnCount, Wins,
 };
-use ruggrogue::util::Color;
+use ruggrogue::{util::Color, PathableMap};
 
 const EQUIPMENT_SPAWN_PERIOD: u32 = 4;
 
-const MONSTERS: [(GameSym, &str, (u8, u8, u8)); 25] = [
-    (GameSym::Blob, "Blob", (89, 162, 191)),
-    (GameSym::Bat, "Bat", (128, 128, 128)),
-    (GameSym::Crab, "Crab", (255, 0, 0)),
-    (GameSym::Snake, "Snake", (0, 153, 0)),
-    (GameSym::Goblin, "Goblin", (34, 187, 59)),
-    (GameSym::Kobold, "Kobold", (122, 181, 73)),
-    (GameSym::Gnome, "Gnome", (134, 204, 199)),
-    (GameSym::Orc, "Orc", (202, 100, 39)),
-    (GameSym::Unicorn, "Unicorn", (255, 150, 255)),
-    (GameSym::Pirate, "Pirate", (0, 134, 255)),
-    (GameSym::Lizardman, "Lizardman", (89, 153, 175)),
-    (GameSym::Ghost, "Ghost", (254, 255, 255)),
-    (GameSym::Skeleton, "Skeleton", (222, 211, 195)),
-    (GameSym::Ogre, "Ogre", (202, 101, 39)),
-    (GameSym::Naga, "Naga", (211, 205, 137)),
-    (GameSym::Warlock, "Warlock", (168, 44, 234)),
-    (GameSym::Demon, "Demon", (218, 0, 0)),
-    (GameSym::Sentinel, "Sentinel", (168, 44, 234)),
-    (GameSym::Robber, "Robber", (82, 84, 255)),
-    (GameSym::SkateboardKid, "Skateboard Kid", (255, 127, 0)),
-    (GameSym::Jellybean, "Jellybean", (192, 96, 192)),
-    (GameSym::Alien, "Alien", (65, 168, 58)),
-    (GameSym::Dweller, "Dweller", (58, 149, 140)),
-    (GameSym::LittleHelper, "Little Helper", (0, 153, 0)),
-    (GameSym::BigHelper, "Big Helper", (255, 99, 99)),
-];
-
-const WEAPONS: [(GameSym, &str, (u8, u8, u8)); 10] = [
-    (GameSym::Knife, "Knife", (165, 165, 165)),
-    (GameSym::Club, "Club", (137, 88, 38)),
-    (GameSym::Hatchet, "Hatchet", (165, 165, 165)),
-    (GameSym::Spear, "Spear", (137, 88, 38)),
-    (GameSym::Rapier, "Rapier", (198, 159, 39)),
-    (GameSym::Saber, "Saber", (165, 165, 165)),
-    (GameSym::Longsword, "Longsword", (165, 165, 165)),
-    (GameSym::Crowbar, "Crowbar", (255, 127, 0)),
-    (GameSym::Tonfa, "Tonfa", (82, 84, 255)),
-    (GameSym::BeamSword, "Beam Sword", (255, 255, 0)),
-];
-
-const ARMORS: [(GameSym, &str, (u8, u8, u8)); 10] = [
-    (GameSym::Jerkin, "Jerkin", (170, 97, 32)),
-    (GameSym::Coat, "Coat", (170, 97, 32)),
-    (GameSym::WoodenShield, "Wooden Shield", (191, 92, 0)),
-    (GameSym::TowerShield, "Tower Shield", (165, 165, 165)),
-    (GameSym::KiteShield, "Kite Shield", (165, 165, 165)),
-    (GameSym::StuddedArmor, "Studded Armor", (170, 97, 32)),
-    (GameSym::Hauberk, "Hauberk", (165, 165, 165)),
-    (GameSym::Platemail, "Platemail", (165, 165, 165)),
-    (GameSym::ArmyHelmet, "Army Helmet", (77, 120, 78)),
-    (GameSym::FlakJacket, "Flak Jacket", (77, 120, 78)),
-];
+/// Most items that the storage chest can hold.
+const CHEST_CAPACITY: usize = 20;
 
 /// Spawn an entity whose purpose is to track the total amount of experience points that could
 /// theoretically be gained in the game in order to increase difficulty over time.
@@ -96,12 +51,13 @@ pub fn spawn_difficulty(mut entities: EntitiesViewMut, mut exps: ViewMut<Experie
 /// after this.
 pub fn spawn_player(
     mut entities: EntitiesViewMut,
+    locale: UniqueView<Locale>,
     mut combat_stats: ViewMut<CombatStats>,
     mut equipments: ViewMut<Equipment>,
     mut exps: ViewMut<Experience>,
     mut fovs: ViewMut<FieldOfView>,
     mut inventories: ViewMut<Inventory>,
-    mut names: ViewMut<Name>,
+    (mut names, mut name_sources): (ViewMut<Name>, ViewMut<NameSource>),
     mut players: ViewMut<Player>,
     (mut render_on_maps, mut renderables, mut stomachs, mut tallies): (
         ViewMut<RenderOnMap>,
@@ -123,7 +79,11 @@ pub fn spawn_player(
             &mut stomachs,
         ),
         (
-            Player { auto_run: None },
+            Player {
+                auto_run: None,
+                item_batch: None,
+                active_loadout: 0,
+            },
             CombatStats {
                 max_hp: experience::calc_player_max_hp(1),
                 hp: experience::calc_player_max_hp(1),
@@ -138,7 +98,7 @@ pub fn spawn_player(
             },
             FieldOfView::new(8),
             Inventory { items: Vec::new() },
-            Name("Player".into()),
+            Name(make_name(&locale, &NameSource::Plain("Player".into()))),
             RenderOnMap {},
             Renderable {
                 sym: GameSym::Player,
@@ -149,17 +109,19 @@ pub fn spawn_player(
                 fullness: 1500,
                 max_fullness: 1500,
                 sub_hp: 0,
+                strain: 0,
             },
         ),
     );
 
     entities.add_component(
-        (&mut equipments, &mut tallies),
+        (&mut equipments, &mut name_sources, &mut tallies),
         (
             Equipment {
                 weapon: None,
                 armor: None,
             },
+            NameSource::Plain("Player".into()),
             Tally {
                 damage_dealt: 0,
                 damage_taken: 0,
@@ -172,62 +134,106 @@ pub fn spawn_player(
     id
 }
 
-pub fn spawn_present(world: &World, pos: (i32, i32)) {
-    let mut map = world.borrow::<UniqueViewMut<Map>>();
-    let mut entities = world.borrow::<EntitiesViewMut>();
-    let mut coords = world.borrow::<ViewMut<Coord>>();
-    let mut items = world.borrow::<ViewMut<Item>>();
-    let mut names = world.borrow::<ViewMut<Name>>();
-    let mut render_on_floors = world.borrow::<ViewMut<RenderOnFloor>>();
-    let mut renderables = world.borrow::<ViewMut<Renderable>>();
-    let mut victories = world.borrow::<ViewMut<Victory>>();
-    let present_id = entities.add_entity(
+/// Spawn a pet dog for the player at the given position.
+pub fn spawn_pet(world: &World, pos: (i32, i32)) -> EntityId {
+    let pet_id = world.borrow::<EntitiesViewMut>().add_entity(
         (
-            &mut items,
-            &mut coords,
-            &mut names,
-            &mut render_on_floors,
-            &mut renderables,
-            &mut victories,
+            &mut world.borrow::<ViewMut<Pet>>(),
+            &mut world.borrow::<ViewMut<Friendly>>(),
+            &mut world.borrow::<ViewMut<BlocksTile>>(),
+            &mut world.borrow::<ViewMut<CombatStats>>(),
+            &mut world.borrow::<ViewMut<Coord>>(),
+            &mut world.borrow::<ViewMut<Experience>>(),
+            &mut world.borrow::<ViewMut<FieldOfView>>(),
+            &mut world.borrow::<ViewMut<RenderOnMap>>(),
+            &mut world.borrow::<ViewMut<Renderable>>(),
         ),
         (
-            Item {},
+            Pet {},
+            Friendly {},
+            BlocksTile {},
+            CombatStats {
+                max_hp: experience::calc_monster_max_hp(1),
+                hp: experience::calc_monster_max_hp(1),
+                attack: experience::calc_monster_attack(1),
+                defense: experience::calc_monster_defense(1),
+            },
             Coord(pos.into()),
-            Name("Present".into()),
-            RenderOnFloor {},
+            Experience {
+                level: 1,
+                exp: 0,
+                next: 50,
+                base: 0,
+            },
+            FieldOfView::new(6),
+            RenderOnMap {},
             Renderable {
-                sym: GameSym::Present,
-                fg: Color::YELLOW,
+                sym: GameSym::Dog,
+                fg: Color::ORANGE,
                 bg: Color::BLACK,
             },
-            Victory {},
         ),
     );
 
-    map.place_entity(present_id, pos, false);
+    add_name(world, pet_id, NameSource::Plain("Dog".into()));
+    world.borrow::<EntitiesViewMut>().add_component(
+        &mut world.borrow::<ViewMut<Stomach>>(),
+        Stomach {
+            fullness: 1000,
+            max_fullness: 1000,
+            sub_hp: 0,
+            strain: 0,
+        },
+        pet_id,
+    );
+
+    world
+        .borrow::<UniqueViewMut<Map>>()
+        .place_entity(pet_id, pos, true);
+
+    pet_id
 }
 
-fn spawn_item(world: &World, pos: (i32, i32), name: String, sym: GameSym, fg: Color) -> EntityId {
-    world.run(
+pub fn spawn_present(world: &World, pos: (i32, i32)) {
+    let present_id = spawn_item(
+        world,
+        pos,
+        NameSource::Plain("Present".into()),
+        GameSym::Present,
+        Color::YELLOW,
+    );
+
+    world.borrow::<EntitiesView>().add_component(
+        &mut world.borrow::<ViewMut<Victory>>(),
+        Victory {},
+        present_id,
+    );
+}
+
+fn spawn_item(
+    world: &World,
+    pos: (i32, i32),
+    name: NameSource,
+    sym: GameSym,
+    fg: Color,
+) -> EntityId {
+    let item_id = world.run(
         |mut map: UniqueViewMut<Map>,
          mut entities: EntitiesViewMut,
          mut coords: ViewMut<Coord>,
          mut items: ViewMut<Item>,
-         mut names: ViewMut<Name>,
          mut render_on_floors: ViewMut<RenderOnFloor>,
          mut renderables: ViewMut<Renderable>| {
             let item_id = entities.add_entity(
                 (
                     &mut items,
                     &mut coords,
-                    &mut names,
                     &mut render_on_floors,
                     &mut renderables,
                 ),
                 (
                     Item {},
                     Coord(pos.into()),
-                    Name(name),
                     RenderOnFloor {},
                     Renderable {
                         sym,
@@ -241,11 +247,133 @@ fn spawn_item(world: &World, pos: (i32, i32), name: String, sym: GameSym, fg: Co
 
             item_id
         },
-    )
+    );
+
+    add_name(world, item_id, name);
+
+    item_id
+}
+
+/// The name of something in the chosen language.
+fn make_name(locale: &Locale, source: &NameSource) -> String {
+    match source {
+        NameSource::Plain(name) => locale.name(name),
+        NameSource::Equipment {
+            bonus,
+            quality,
+            name,
+        } => {
+            let bonus = format!("{:+}", bonus);
+            let name = locale.name(name);
+
+            if quality.is_empty() {
+                locale.format("name.equipment", &[("bonus", &bonus), ("name", &name)])
+            } else {
+                locale.format(
+                    "name.equipment_quality",
+                    &[
+                        ("bonus", &bonus),
+                        ("quality", &locale.name(quality)),
+                        ("name", &name),
+                    ],
+                )
+            }
+        }
+        NameSource::Grave(who) => locale.format("name.grave_of", &[("who", &locale.name(who))]),
+        NameSource::Ghost(who) => locale.format("name.ghost_of", &[("who", &locale.name(who))]),
+        NameSource::Gold(amount) => locale.format(
+            if *amount == 1 {
+                "name.gold_coin"
+            } else {
+                "name.gold_coins"
+            },
+            &[("count", amount)],
+        ),
+    }
+}
+
+/// Name an entity in the chosen language, keeping what the name was made from for when the
+/// language changes.
+fn add_name(world: &World, id: EntityId, source: NameSource) {
+    let name = make_name(&world.borrow::<UniqueView<Locale>>(), &source);
+
+    world.borrow::<EntitiesView>().add_component(
+        (
+            &mut world.borrow::<ViewMut<Name>>(),
+            &mut world.borrow::<ViewMut<NameSource>>(),
+        ),
+        (Name(name), source),
+        id,
+    );
+}
+
+/// Name every entity again in the chosen language, e.g. after it changes or a game is loaded.
+///
+/// Entities from older save files without a [NameSource] keep the name they have.
+pub fn translate_names(world: &World) {
+    let locale = world.borrow::<UniqueView<Locale>>();
+    let entities = world.borrow::<EntitiesView>();
+    let mut names = world.borrow::<ViewMut<Name>>();
+    let mut sources = world.borrow::<ViewMut<NameSource>>();
+    let unsourced = names
+        .iter()
+        .with_id()
+        .filter(|(id, _)| !sources.contains(*id))
+        .map(|(id, name)| (id, NameSource::Plain(name.0.clone())))
+        .collect::<Vec<_>>();
+
+    for (id, source) in unsourced {
+        entities.add_component(&mut sources, source, id);
+    }
+
+    for (name, source) in (&mut names, &sources).iter() {
+        name.0 = make_name(&locale, source);
+    }
+}
+
+/// Give an item a weight that counts towards the carrying capacity of whoever holds it.
+fn weigh_item(world: &World, item_id: EntityId, weight: i32) {
+    world.borrow::<EntitiesView>().add_component(
+        &mut world.borrow::<ViewMut<Weight>>(),
+        Weight(weight),
+        item_id,
+    );
+}
+
+/// Give a piece of equipment its full durability.
+fn add_durability(world: &World, item_id: EntityId) {
+    world.borrow::<EntitiesView>().add_component(
+        &mut world.borrow::<ViewMut<Durability>>(),
+        Durability {
+            current: durability::MAX_DURABILITY,
+            max: durability::MAX_DURABILITY,
+        },
+        item_id,
+    );
+}
+
+/// Give an item or monster flavor text to be shown when it's examined, either a `desc.*` key of the
+/// locale catalog or text from a data file.
+fn describe_entity(world: &World, id: EntityId, desc: &str) {
+    if !desc.is_empty() {
+        world.borrow::<EntitiesView>().add_component(
+            &mut world.borrow::<ViewMut<Description>>(),
+            Description::Text(desc.to_string()),
+            id,
+        );
+    }
 }
 
 fn spawn_ration(world: &World, pos: (i32, i32)) {
-    let item_id = spawn_item(world, pos, "Ration".into(), GameSym::Ration, Color::BROWN);
+    let item_id = spawn_item(
+        world,
+        pos,
+        NameSource::Plain("Ration".into()),
+        GameSym::Ration,
+        Color::BROWN,
+    );
+    describe_entity(world, item_id, "desc.ration");
+    weigh_item(world, item_id, 3);
     let (entities, mut consumables, mut nutritions) =
         world.borrow::<(EntitiesView, ViewMut<Consumable>, ViewMut<Nutrition>)>();
 
@@ -260,10 +388,12 @@ fn spawn_health_potion(world: &World, pos: (i32, i32)) {
     let item_id = spawn_item(
         world,
         pos,
-        "Health Potion".into(),
+        NameSource::Plain("Health Potion".into()),
         GameSym::HealthPotion,
         Color::MAGENTA,
     );
+    describe_entity(world, item_id, "desc.health_potion");
+    weigh_item(world, item_id, 1);
     let (entities, mut consumables, mut provides_healings) =
         world.borrow::<(EntitiesView, ViewMut<Consumable>, ViewMut<ProvidesHealing>)>();
 
@@ -278,10 +408,12 @@ fn spawn_magic_missile_scroll(world: &World, pos: (i32, i32)) {
     let item_id = spawn_item(
         world,
         pos,
-        "Magic Missile Scroll".into(),
+        NameSource::Plain("Magic Missile Scroll".into()),
         GameSym::MagicMissileScroll,
         Color::CYAN,
     );
+    describe_entity(world, item_id, "desc.magic_missile_scroll");
+    weigh_item(world, item_id, 1);
     let (entities, mut consumables, mut inflicts_damages, mut rangeds) = world.borrow::<(
         EntitiesView,
         ViewMut<Consumable>,
@@ -304,10 +436,12 @@ fn spawn_fireball_scroll(world: &World, pos: (i32, i32)) {
     let item_id = spawn_item(
         world,
         pos,
-        "Fireball Scroll".into(),
+        NameSource::Plain("Fireball Scroll".into()),
         GameSym::FireballScroll,
         Color::ORANGE,
     );
+    describe_entity(world, item_id, "desc.fireball_scroll");
+    weigh_item(world, item_id, 1);
     let (entities, mut aoes, mut consumables, mut inflicts_damages, mut rangeds) = world.borrow::<(
         EntitiesView,
         ViewMut<AreaOfEffect>,
@@ -337,10 +471,12 @@ fn spawn_sleep_scroll(world: &World, pos: (i32, i32)) {
     let item_id = spawn_item(
         world,
         pos,
-        "Sleep Scroll".into(),
+        NameSource::Plain("Sleep Scroll".into()),
         GameSym::SleepScroll,
         Color::PINK,
     );
+    describe_entity(world, item_id, "desc.sleep_scroll");
+    weigh_item(world, item_id, 1);
     let (entities, mut aoes, mut consumables, mut inflicts_sleeps, mut rangeds) = world.borrow::<(
         EntitiesView,
         ViewMut<AreaOfEffect>,
@@ -366,24 +502,225 @@ fn spawn_sleep_scroll(world: &World, pos: (i32, i32)) {
     );
 }
 
-fn rescale_level<R: Rng>(level: f32, scale: usize, rng: &mut R) -> usize {
-    let monsters_range = MONSTERS.len().saturating_sub(1).max(1) as f32;
+fn spawn_bag(world: &World, pos: (i32, i32)) {
+    let item_id = spawn_item(
+        world,
+        pos,
+        NameSource::Plain("Bag".into()),
+        GameSym::Bag,
+        Color::BROWN,
+    );
+    describe_entity(world, item_id, "desc.bag");
+    weigh_item(world, item_id, 2);
+    let (entities, mut containers, mut inventories) =
+        world.borrow::<(EntitiesView, ViewMut<Container>, ViewMut<Inventory>)>();
+
+    entities.add_component(
+        (&mut containers, &mut inventories),
+        (Container { capacity: 10 }, Inventory { items: Vec::new() }),
+        item_id,
+    );
+}
+
+fn spawn_pickaxe(world: &World, pos: (i32, i32)) {
+    let item_id = spawn_item(
+        world,
+        pos,
+        NameSource::Plain("Pickaxe".into()),
+        GameSym::Pickaxe,
+        Color::GRAY,
+    );
+    describe_entity(world, item_id, "desc.pickaxe");
+    weigh_item(world, item_id, 6);
+    world.borrow::<EntitiesView>().add_component(
+        &mut world.borrow::<ViewMut<Digger>>(),
+        Digger {},
+        item_id,
+    );
+}
+
+fn spawn_repair_item(
+    world: &World,
+    pos: (i32, i32),
+    name: &str,
+    sym: GameSym,
+    desc: &str,
+    slot: EquipSlot,
+) {
+    let item_id = spawn_item(world, pos, NameSource::Plain(name.into()), sym, Color::GRAY);
+    describe_entity(world, item_id, desc);
+    weigh_item(world, item_id, 1);
+    let (entities, mut consumables, mut repairs) =
+        world.borrow::<(EntitiesView, ViewMut<Consumable>, ViewMut<Repairs>)>();
+
+    entities.add_component(
+        (&mut consumables, &mut repairs),
+        (Consumable {}, Repairs(slot)),
+        item_id,
+    );
+}
+
+pub fn spawn_key(world: &World, pos: (i32, i32)) {
+    let item_id = spawn_item(
+        world,
+        pos,
+        NameSource::Plain("Key".into()),
+        GameSym::Key,
+        Color::YELLOW,
+    );
+    describe_entity(world, item_id, "desc.key");
+    world.borrow::<EntitiesView>().add_component(
+        &mut world.borrow::<ViewMut<Key>>(),
+        Key {},
+        item_id,
+    );
+}
+
+/// Spawn a lost item that an NPC asked the player to find in the quest with the given ID.
+pub fn spawn_quest_item(world: &World, pos: (i32, i32), name: NameSource, quest_id: u32) {
+    let item_id = spawn_item(world, pos, name, GameSym::Trinket, Color::PINK);
+    describe_entity(world, item_id, "desc.quest_item");
+    weigh_item(world, item_id, 1);
+    world.borrow::<EntitiesView>().add_component(
+        &mut world.borrow::<ViewMut<QuestItem>>(),
+        QuestItem(quest_id),
+        item_id,
+    );
+}
+
+/// Spawn an item that an NPC hands over.
+pub fn spawn_gift(world: &World, pos: (i32, i32), gift: GiftItem) {
+    match gift {
+        GiftItem::Ration => spawn_ration(world, pos),
+        GiftItem::HealthPotion => spawn_health_potion(world, pos),
+        GiftItem::MagicMissileScroll => spawn_magic_missile_scroll(world, pos),
+        GiftItem::FireballScroll => spawn_fireball_scroll(world, pos),
+        GiftItem::SleepScroll => spawn_sleep_scroll(world, pos),
+        GiftItem::Bag => spawn_bag(world, pos),
+        GiftItem::Pickaxe => spawn_pickaxe(world, pos),
+        GiftItem::Whetstone => spawn_repair_item(
+            world,
+            pos,
+            GiftItem::Whetstone.name(),
+            GameSym::Whetstone,
+            "desc.whetstone",
+            EquipSlot::Weapon,
+        ),
+        GiftItem::RepairKit => spawn_repair_item(
+            world,
+            pos,
+            GiftItem::RepairKit.name(),
+            GameSym::RepairKit,
+            "desc.repair_kit",
+            EquipSlot::Armor,
+        ),
+    }
+}
+
+/// Spawn a random item from the scripts file that's allowed at the current depth, returning false
+/// if there aren't any.
+fn spawn_random_scripted_item<R: Rng>(world: &World, rng: &mut R, pos: (i32, i32)) -> bool {
+    let depth = world.borrow::<UniqueView<Map>>().depth;
+    let scripts = world.borrow::<UniqueView<Scripts>>();
+    let def = match scripts
+        .items
+        .iter()
+        .filter(|def| def.min_depth <= depth)
+        .choose(rng)
+    {
+        Some(def) => def,
+        None => return false,
+    };
+    let item_id = spawn_item(
+        world,
+        pos,
+        NameSource::Plain(def.name.clone()),
+        def.sym,
+        def.fg.into(),
+    );
+    describe_entity(world, item_id, &def.desc);
+    weigh_item(world, item_id, def.weight);
+    let (entities, mut aoes, mut consumables, mut rangeds, mut scripted_uses) = world.borrow::<(
+        EntitiesView,
+        ViewMut<AreaOfEffect>,
+        ViewMut<Consumable>,
+        ViewMut<Ranged>,
+        ViewMut<ScriptedUse>,
+    )>();
+
+    entities.add_component(
+        (&mut consumables, &mut scripted_uses),
+        (Consumable {}, ScriptedUse(def.on_use.clone())),
+        item_id,
+    );
+    if let Some(range) = def.range {
+        entities.add_component(&mut rangeds, Ranged { range }, item_id);
+    }
+    if def.radius > 0 {
+        entities.add_component(&mut aoes, AreaOfEffect { radius: def.radius }, item_id);
+    }
+
+    true
+}
+
+/// Pick an entry out of an equipment table, rescaling the level to the size of the table relative
+/// to the monster table.
+fn pick_by_level<'a, R: Rng>(
+    tables: &SpawnTables,
+    table: &'a [SpawnEntry],
+    level: f32,
+    rng: &mut R,
+) -> &'a SpawnEntry {
+    let monsters_range = tables.monsters.len().saturating_sub(1).max(1) as f32;
+    let scale = table.len().saturating_sub(1);
     let rescaled = ((level - 1.0) / monsters_range).clamp(0.0, 1.0) * scale as f32;
 
-    experience::f32_round_random(rescaled, rng) as usize
+    &table[(experience::f32_round_random(rescaled, rng) as usize).min(scale)]
+}
+
+/// Name a piece of equipment after its total bonus and quality, if it has one worth naming.
+fn equipment_name(total_bonus: i32, quality: Option<&QualityTier>, name: &str) -> NameSource {
+    NameSource::Equipment {
+        bonus: total_bonus,
+        quality: quality.map_or_else(String::new, |q| q.name.clone()),
+        name: name.to_string(),
+    }
 }
 
 fn spawn_weapon<R: Rng>(world: &World, rng: &mut R, pos: (i32, i32), level: f32, bonus: i32) {
-    let (sym, name, rgb) = WEAPONS[rescale_level(level, WEAPONS.len().saturating_sub(1), rng)];
+    spawn_weapon_of_quality(world, rng, pos, level, bonus, None);
+}
+
+fn spawn_weapon_of_quality<R: Rng>(
+    world: &World,
+    rng: &mut R,
+    pos: (i32, i32),
+    level: f32,
+    bonus: i32,
+    quality: Option<&QualityTier>,
+) {
+    let tables = world.borrow::<UniqueView<SpawnTables>>();
+    let SpawnEntry {
+        sym,
+        name,
+        fg,
+        desc,
+        weight,
+        ..
+    } = pick_by_level(&tables, &tables.weapons, level, rng);
     let level = experience::f32_round_random(level, rng);
     let base_equipment_level = world.borrow::<UniqueView<BaseEquipmentLevel>>().0;
+    let bonus = bonus + quality.map_or(0, |q| q.bonus);
     let item_id = spawn_item(
         world,
         pos,
-        format!("{:+} {}", level + bonus + base_equipment_level, name),
-        sym,
-        rgb.into(),
+        equipment_name(level + bonus + base_equipment_level, quality, name),
+        *sym,
+        (*fg).into(),
     );
+    describe_entity(world, item_id, desc);
+    weigh_item(world, item_id, *weight);
+    add_durability(world, item_id);
     let (entities, mut combat_bonuses, mut equip_slots) =
         world.borrow::<(EntitiesView, ViewMut<CombatBonus>, ViewMut<EquipSlot>)>();
 
@@ -401,16 +738,39 @@ fn spawn_weapon<R: Rng>(world: &World, rng: &mut R, pos: (i32, i32), level: f32,
 }
 
 fn spawn_armor<R: Rng>(world: &World, rng: &mut R, pos: (i32, i32), level: f32, bonus: i32) {
-    let (sym, name, rgb) = ARMORS[rescale_level(level, ARMORS.len().saturating_sub(1), rng)];
+    spawn_armor_of_quality(world, rng, pos, level, bonus, None);
+}
+
+fn spawn_armor_of_quality<R: Rng>(
+    world: &World,
+    rng: &mut R,
+    pos: (i32, i32),
+    level: f32,
+    bonus: i32,
+    quality: Option<&QualityTier>,
+) {
+    let tables = world.borrow::<UniqueView<SpawnTables>>();
+    let SpawnEntry {
+        sym,
+        name,
+        fg,
+        desc,
+        weight,
+        ..
+    } = pick_by_level(&tables, &tables.armors, level, rng);
     let level = experience::f32_round_random(level, rng);
     let base_equipment_level = world.borrow::<UniqueView<BaseEquipmentLevel>>().0;
+    let bonus = bonus + quality.map_or(0, |q| q.bonus);
     let item_id = spawn_item(
         world,
         pos,
-        format!("{:+} {}", level + bonus + base_equipment_level, name),
-        sym,
-        rgb.into(),
+        equipment_name(level + bonus + base_equipment_level, quality, name),
+        *sym,
+        (*fg).into(),
     );
+    describe_entity(world, item_id, desc);
+    weigh_item(world, item_id, *weight);
+    add_durability(world, item_id);
     let (entities, mut combat_bonuses, mut equip_slots) =
         world.borrow::<(EntitiesView, ViewMut<CombatBonus>, ViewMut<EquipSlot>)>();
 
@@ -427,7 +787,14 @@ fn spawn_armor<R: Rng>(world: &World, rng: &mut R, pos: (i32, i32), level: f32,
     );
 }
 
-fn spawn_monster(world: &World, pos: (i32, i32), level: i32, sym: GameSym, name: &str, fg: Color) {
+fn spawn_monster(
+    world: &World,
+    pos: (i32, i32),
+    level: i32,
+    sym: GameSym,
+    name: NameSource,
+    fg: Color,
+) -> EntityId {
     let monster_id = world.borrow::<EntitiesViewMut>().add_entity(
         (
             &mut world.borrow::<ViewMut<Monster>>(),
@@ -436,7 +803,6 @@ fn spawn_monster(world: &World, pos: (i32, i32), level: i32, sym: GameSym, name:
             &mut world.borrow::<ViewMut<Coord>>(),
             &mut world.borrow::<ViewMut<FieldOfView>>(),
             &mut world.borrow::<ViewMut<GivesExperience>>(),
-            &mut world.borrow::<ViewMut<Name>>(),
             &mut world.borrow::<ViewMut<RenderOnMap>>(),
             &mut world.borrow::<ViewMut<Renderable>>(),
         ),
@@ -452,7 +818,6 @@ fn spawn_monster(world: &World, pos: (i32, i32), level: i32, sym: GameSym, name:
             Coord(pos.into()),
             FieldOfView::new(8),
             GivesExperience(experience::calc_monster_exp(level)),
-            Name(name.into()),
             RenderOnMap {},
             Renderable {
                 sym,
@@ -461,10 +826,586 @@ fn spawn_monster(world: &World, pos: (i32, i32), level: i32, sym: GameSym, name:
             },
         ),
     );
+    add_name(world, monster_id, name);
 
     world
         .borrow::<UniqueViewMut<Map>>()
         .place_entity(monster_id, pos, true);
+
+    monster_id
+}
+
+/// Spawn a monster from the spawn tables next to a position to fight for the given faction.
+///
+/// Summoned monsters give no experience, so summoners can't be farmed for it.
+pub fn spawn_summon(world: &World, near: (i32, i32), name: &str, faction: Faction) {
+    let entry = {
+        let tables = world.borrow::<UniqueView<SpawnTables>>();
+
+        tables
+            .monsters
+            .iter()
+            .position(|m| m.name == name)
+            .map(|i| {
+                let entry = &tables.monsters[i];
+                (i as i32 + 1, entry.sym, entry.fg, entry.desc.clone())
+            })
+    };
+    let (level, sym, fg, desc) = match entry {
+        Some(entry) => entry,
+        None => {
+            eprintln!("Warning: Cannot summon unknown monster '{}'", name);
+            return;
+        }
+    };
+    let pos = {
+        let map = world.borrow::<UniqueView<Map>>();
+
+        (-1..=1)
+            .flat_map(|dy| (-1..=1).map(move |dx| (near.0 + dx, near.1 + dy)))
+            .find(|&(x, y)| {
+                matches!(map.get_tile(x, y), Tile::Floor)
+                    && map.iter_entities_at(x, y).next().is_none()
+            })
+    };
+
+    if let Some(pos) = pos {
+        let summon_id = spawn_monster(
+            world,
+            pos,
+            level,
+            sym,
+            NameSource::Plain(name.into()),
+            fg.into(),
+        );
+
+        describe_entity(world, summon_id, &desc);
+
+        world.borrow::<ViewMut<GivesExperience>>().remove(summon_id);
+        world.borrow::<EntitiesViewMut>().add_component(
+            &mut world.borrow::<ViewMut<Faction>>(),
+            faction,
+            summon_id,
+        );
+    }
+}
+
+fn spawn_npc(world: &World, pos: (i32, i32), def: &NpcDef) {
+    let npc_id = world.borrow::<EntitiesViewMut>().add_entity(
+        (
+            &mut world.borrow::<ViewMut<Npc>>(),
+            &mut world.borrow::<ViewMut<BlocksTile>>(),
+            &mut world.borrow::<ViewMut<Coord>>(),
+            &mut world.borrow::<ViewMut<RenderOnMap>>(),
+            &mut world.borrow::<ViewMut<Renderable>>(),
+        ),
+        (
+            Npc {
+                def_id: def.id.clone(),
+                gifted: false,
+            },
+            BlocksTile {},
+            Coord(pos.into()),
+            RenderOnMap {},
+            Renderable {
+                sym: def.sym,
+                fg: def.fg.into(),
+                bg: Color::BLACK,
+            },
+        ),
+    );
+    add_name(world, npc_id, NameSource::Plain(def.name.clone()));
+
+    world
+        .borrow::<UniqueViewMut<Map>>()
+        .place_entity(npc_id, pos, true);
+}
+
+/// The open floor tile nearest to the given position, which may be the position itself.
+fn nearest_open_floor(map: &Map, pos: (i32, i32)) -> Option<(i32, i32)> {
+    let (x, y) = pos;
+
+    (0..map.height)
+        .flat_map(|ty| (0..map.width).map(move |tx| (tx, ty)))
+        .filter(|&(tx, ty)| matches!(map.get_tile(tx, ty), Tile::Floor) && !map.is_blocked(tx, ty))
+        .min_by_key(|&(tx, ty)| (tx - x).abs().max((ty - y).abs()))
+}
+
+/// Spawn the grave of a dead player from an earlier game, burying everything they carried in it.
+fn spawn_grave(world: &World, pos: (i32, i32), remains: &Remains) {
+    let grave_id = world.borrow::<EntitiesViewMut>().add_entity(
+        (
+            &mut world.borrow::<ViewMut<Gravestone>>(),
+            &mut world.borrow::<ViewMut<BlocksTile>>(),
+            &mut world.borrow::<ViewMut<Container>>(),
+            &mut world.borrow::<ViewMut<Coord>>(),
+            &mut world.borrow::<ViewMut<Inventory>>(),
+            &mut world.borrow::<ViewMut<RenderOnMap>>(),
+            &mut world.borrow::<ViewMut<Renderable>>(),
+        ),
+        (
+            Gravestone {},
+            BlocksTile {},
+            Container {
+                capacity: (remains.items.len() + remains.pack.len()).max(1),
+            },
+            Coord(pos.into()),
+            Inventory { items: Vec::new() },
+            RenderOnMap {},
+            Renderable {
+                sym: GameSym::Gravestone,
+                fg: Color::GRAY,
+                bg: Color::BLACK,
+            },
+        ),
+    );
+    add_name(world, grave_id, NameSource::Grave(remains.name.clone()));
+    world.borrow::<EntitiesView>().add_component(
+        &mut world.borrow::<ViewMut<Description>>(),
+        Description::Grave {
+            who: remains.name.clone(),
+            level: remains.level,
+        },
+        grave_id,
+    );
+
+    world
+        .borrow::<UniqueViewMut<Map>>()
+        .place_entity(grave_id, pos, true);
+
+    // Spawn the buried items on the grave's tile, then move them into the grave.
+    let items_here = |world: &World| {
+        let map = world.borrow::<UniqueView<Map>>();
+        let items = world.borrow::<View<Item>>();
+
+        map.iter_entities_at(pos.0, pos.1)
+            .filter(|id| items.contains(*id))
+            .collect::<Vec<EntityId>>()
+    };
+    let existing = items_here(world);
+
+    for bones_item in remains.items.iter() {
+        let item_id = spawn_item(
+            world,
+            pos,
+            bones_item
+                .source
+                .clone()
+                .unwrap_or_else(|| NameSource::Plain(bones_item.name.clone())),
+            bones_item.sym,
+            bones_item.fg.into(),
+        );
+        weigh_item(world, item_id, bones_item.weight);
+        let (entities, mut combat_bonuses, mut equip_slots) =
+            world.borrow::<(EntitiesView, ViewMut<CombatBonus>, ViewMut<EquipSlot>)>();
+
+        entities.add_component(
+            (&mut combat_bonuses, &mut equip_slots),
+            (
+                CombatBonus {
+                    attack: bones_item.attack,
+                    defense: bones_item.defense,
+                },
+                if bones_item.weapon {
+                    EquipSlot::Weapon
+                } else {
+                    EquipSlot::Armor
+                },
+            ),
+            item_id,
+        );
+    }
+    for gift in remains.pack.iter() {
+        spawn_gift(world, pos, *gift);
+    }
+
+    for item_id in items_here(world) {
+        if !existing.contains(&item_id) {
+            item::remove_item_from_map(world, item_id);
+            item::add_item_to_inventory(world, grave_id, item_id);
+        }
+    }
+}
+
+/// Spawn the grave of a dead player from an earlier game where they died, along with their ghost.
+pub fn spawn_remains(world: &World, remains: &Remains) {
+    // Put the grave where the player died, or the nearest open floor if something is in the way.
+    let grave_pos = nearest_open_floor(&world.borrow::<UniqueView<Map>>(), remains.pos);
+
+    if let Some(grave_pos) = grave_pos {
+        spawn_grave(world, grave_pos, remains);
+    }
+
+    // The ghost lingers as close to the grave as it can.
+    let ghost_pos = nearest_open_floor(
+        &world.borrow::<UniqueView<Map>>(),
+        grave_pos.unwrap_or(remains.pos),
+    );
+
+    if let Some(ghost_pos) = ghost_pos {
+        spawn_monster(
+            world,
+            ghost_pos,
+            remains.level + 2,
+            GameSym::Ghost,
+            NameSource::Ghost(remains.name.clone()),
+            Color::PURPLE,
+        );
+    }
+}
+
+/// Rarely place a friendly NPC on an empty floor tile outside of the first room.
+fn spawn_random_npc(world: &World) {
+    let mut rng = world
+        .borrow::<UniqueView<GameSeed>>()
+        .fork(RngStream::Npc)
+        .mix_i32(world.borrow::<UniqueView<Map>>().depth)
+        .rng();
+
+    if !rng.gen_ratio(1, 5) {
+        return;
+    }
+
+    let npc_defs = world.borrow::<UniqueView<NpcDefs>>();
+    let depth = world.borrow::<UniqueView<Map>>().depth;
+    let def = match npc_defs
+        .0
+        .iter()
+        .filter(|d| d.min_depth <= depth)
+        .choose(&mut rng)
+    {
+        Some(def) => def,
+        None => return,
+    };
+    let pos = {
+        let map = world.borrow::<UniqueView<Map>>();
+
+        map.rooms.iter().skip(1).choose(&mut rng).and_then(|room| {
+            room.iter_xy()
+                .filter(|&(x, y)| {
+                    matches!(map.get_tile(x, y), Tile::Floor)
+                        && map.iter_entities_at(x, y).next().is_none()
+                })
+                .choose(&mut rng)
+        })
+    };
+
+    if let Some(pos) = pos {
+        spawn_npc(world, pos, def);
+    }
+}
+
+fn spawn_friendly(world: &World, pos: (i32, i32), entry: &FriendlyEntry) {
+    let friendly_id = world.borrow::<EntitiesViewMut>().add_entity(
+        (
+            &mut world.borrow::<ViewMut<Friendly>>(),
+            &mut world.borrow::<ViewMut<BlocksTile>>(),
+            &mut world.borrow::<ViewMut<Coord>>(),
+            &mut world.borrow::<ViewMut<RenderOnMap>>(),
+            &mut world.borrow::<ViewMut<Renderable>>(),
+        ),
+        (
+            Friendly {},
+            BlocksTile {},
+            Coord(pos.into()),
+            RenderOnMap {},
+            Renderable {
+                sym: entry.sym,
+                fg: entry.fg.into(),
+                bg: Color::BLACK,
+            },
+        ),
+    );
+    add_name(world, friendly_id, NameSource::Plain(entry.name.clone()));
+
+    world
+        .borrow::<UniqueViewMut<Map>>()
+        .place_entity(friendly_id, pos, true);
+}
+
+/// Sometimes place a friendly from the spawn tables on an empty floor tile outside of the first
+/// room.
+fn spawn_random_friendly(world: &World) {
+    let mut rng = world
+        .borrow::<UniqueView<GameSeed>>()
+        .fork(RngStream::Friendly)
+        .mix_i32(world.borrow::<UniqueView<Map>>().depth)
+        .rng();
+    let tables = world.borrow::<UniqueView<SpawnTables>>();
+
+    if !rng.gen_ratio(tables.friendly_chance.min(100), 100) {
+        return;
+    }
+
+    let depth = world.borrow::<UniqueView<Map>>().depth;
+    let entry = match tables
+        .friendlies
+        .iter()
+        .filter(|f| f.min_depth <= depth)
+        .choose(&mut rng)
+    {
+        Some(entry) => entry,
+        None => return,
+    };
+    let pos = {
+        let map = world.borrow::<UniqueView<Map>>();
+
+        map.rooms.iter().skip(1).choose(&mut rng).and_then(|room| {
+            room.iter_xy()
+                .filter(|&(x, y)| {
+                    matches!(map.get_tile(x, y), Tile::Floor)
+                        && map.iter_entities_at(x, y).next().is_none()
+                })
+                .choose(&mut rng)
+        })
+    };
+
+    if let Some(pos) = pos {
+        spawn_friendly(world, pos, entry);
+    }
+}
+
+/// Fill the vault on the current level with gold and better-than-usual equipment, and hand its key
+/// to a random monster elsewhere on the level, or leave it lying in a room if there are none.
+fn spawn_vault_contents(world: &World) {
+    let vault = match world.borrow::<UniqueView<Map>>().vault {
+        Some(vault) => vault,
+        None => return,
+    };
+    let mut rng = world
+        .borrow::<UniqueView<GameSeed>>()
+        .fork(RngStream::Vault)
+        .mix_i32(world.borrow::<UniqueView<Map>>().depth)
+        .rng();
+    let level = {
+        let difficulty = world.borrow::<UniqueView<Difficulty>>();
+        let exps = world.borrow::<View<Experience>>();
+        difficulty.as_f32(&exps)
+    };
+    let num = rng.gen_range(2..=3);
+
+    for pos in vault.iter_xy().choose_multiple(&mut rng, num) {
+        // Vault equipment is a good deal better than what lies around elsewhere.
+        let bonus = rng.gen_range(3..=5);
+
+        match rng.gen_range(0..3) {
+            0 => spawn_weapon(world, &mut rng, pos, level, bonus),
+            1 => spawn_armor(world, &mut rng, pos, level, bonus),
+            _ => spawn_gold(world, pos, rng.gen_range(20..=60)),
+        }
+    }
+
+    let carrier = world
+        .borrow::<View<Monster>>()
+        .iter()
+        .with_id()
+        .into_iter()
+        .map(|(id, _)| id)
+        .choose(&mut rng);
+
+    if let Some(carrier) = carrier {
+        world.borrow::<EntitiesView>().add_component(
+            &mut world.borrow::<ViewMut<KeyCarrier>>(),
+            KeyCarrier {},
+            carrier,
+        );
+    } else {
+        let pos = {
+            let map = world.borrow::<UniqueView<Map>>();
+
+            map.rooms.iter().skip(1).choose(&mut rng).and_then(|room| {
+                room.iter_xy()
+                    .filter(|&(x, y)| matches!(map.get_tile(x, y), Tile::Floor))
+                    .choose(&mut rng)
+            })
+        };
+
+        if let Some(pos) = pos {
+            spawn_key(world, pos);
+        }
+    }
+}
+
+/// Spawn the treasure and lurking monsters marked in prefabs stamped into the current level.
+fn spawn_prefab_contents(world: &World) {
+    let marks = world.borrow::<UniqueView<Map>>().prefab_marks.clone();
+
+    if marks.is_empty() {
+        return;
+    }
+
+    let mut rng = world
+        .borrow::<UniqueView<GameSeed>>()
+        .fork(RngStream::PrefabContents)
+        .mix_i32(world.borrow::<UniqueView<Map>>().depth)
+        .rng();
+    let level = {
+        let difficulty = world.borrow::<UniqueView<Difficulty>>();
+        let exps = world.borrow::<View<Experience>>();
+        difficulty.as_f32(&exps)
+    };
+
+    for (pos, mark) in marks {
+        match mark {
+            PrefabMark::Treasure => {
+                // Not quite as good as what's locked away in a proper vault.
+                let bonus = rng.gen_range(2..=4);
+
+                match rng.gen_range(0..3) {
+                    0 => spawn_weapon(world, &mut rng, pos, level, bonus),
+                    1 => spawn_armor(world, &mut rng, pos, level, bonus),
+                    _ => spawn_gold(world, pos, rng.gen_range(10..=40)),
+                }
+            }
+            PrefabMark::Lurker => spawn_random_monster_at(world, &mut rng, pos),
+        }
+    }
+}
+
+fn spawn_boulder(world: &World, pos: (i32, i32)) {
+    let boulder_id = world.borrow::<EntitiesViewMut>().add_entity(
+        (
+            &mut world.borrow::<ViewMut<Boulder>>(),
+            &mut world.borrow::<ViewMut<BlocksTile>>(),
+            &mut world.borrow::<ViewMut<Coord>>(),
+            &mut world.borrow::<ViewMut<RenderOnMap>>(),
+            &mut world.borrow::<ViewMut<Renderable>>(),
+        ),
+        (
+            Boulder {},
+            BlocksTile {},
+            Coord(pos.into()),
+            RenderOnMap {},
+            Renderable {
+                sym: GameSym::Boulder,
+                fg: Color::LIGHT_GRAY,
+                bg: Color::BLACK,
+            },
+        ),
+    );
+    add_name(world, boulder_id, NameSource::Plain("Boulder".into()));
+
+    world
+        .borrow::<UniqueViewMut<Map>>()
+        .place_entity(boulder_id, pos, true);
+}
+
+/// Spawn an empty storage chest off of the map, to be placed with [crate::chest::place_chest].
+pub fn spawn_chest(world: &World) -> EntityId {
+    let chest_id = world.borrow::<EntitiesViewMut>().add_entity(
+        (
+            &mut world.borrow::<ViewMut<Chest>>(),
+            &mut world.borrow::<ViewMut<BlocksTile>>(),
+            &mut world.borrow::<ViewMut<Container>>(),
+            &mut world.borrow::<ViewMut<Inventory>>(),
+            &mut world.borrow::<ViewMut<RenderOnMap>>(),
+            &mut world.borrow::<ViewMut<Renderable>>(),
+        ),
+        (
+            Chest {},
+            BlocksTile {},
+            Container {
+                capacity: CHEST_CAPACITY,
+            },
+            Inventory { items: Vec::new() },
+            RenderOnMap {},
+            Renderable {
+                sym: GameSym::Chest,
+                fg: Color::BROWN,
+                bg: Color::BLACK,
+            },
+        ),
+    );
+
+    add_name(world, chest_id, NameSource::Plain("Storage Chest".into()));
+
+    chest_id
+}
+
+/// Spawn a cracked wall over a wall tile, which already blocks movement on its own.
+fn spawn_cracked_wall(world: &World, pos: (i32, i32)) {
+    let wall_id = world.borrow::<EntitiesViewMut>().add_entity(
+        (
+            &mut world.borrow::<ViewMut<Diggable>>(),
+            &mut world.borrow::<ViewMut<Coord>>(),
+            &mut world.borrow::<ViewMut<RenderOnMap>>(),
+            &mut world.borrow::<ViewMut<Renderable>>(),
+        ),
+        (
+            Diggable {},
+            Coord(pos.into()),
+            RenderOnMap {},
+            Renderable {
+                sym: GameSym::CrackedWall,
+                fg: Color::BROWN,
+                bg: Color::BLACK,
+            },
+        ),
+    );
+    add_name(world, wall_id, NameSource::Plain("Cracked Wall".into()));
+
+    world
+        .borrow::<UniqueViewMut<Map>>()
+        .place_entity(wall_id, pos, false);
+}
+
+/// Scatter boulders around rooms outside of the first, and crack a few walls that have floor on
+/// either side of them so that digging them out makes a shortcut.
+///
+/// Rooms with deep water or chasms are more likely to get a boulder to fill them in with.
+fn spawn_obstacles(world: &World) {
+    let mut rng = world
+        .borrow::<UniqueView<GameSeed>>()
+        .fork(RngStream::Obstacles)
+        .mix_i32(world.borrow::<UniqueView<Map>>().depth)
+        .rng();
+    let (boulder_spots, wall_spots) = {
+        let map = world.borrow::<UniqueView<Map>>();
+        let mut boulder_spots = Vec::new();
+
+        for room in map.rooms.iter().skip(1) {
+            let has_pool = room
+                .iter_xy()
+                .any(|(x, y)| matches!(map.get_tile(x, y), Tile::DeepWater | Tile::Chasm));
+
+            if rng.gen_ratio(1, if has_pool { 2 } else { 8 }) {
+                let pos = room
+                    .iter_xy()
+                    .filter(|&(x, y)| {
+                        matches!(map.get_tile(x, y), Tile::Floor)
+                            && map.iter_entities_at(x, y).next().is_none()
+                    })
+                    .choose(&mut rng);
+
+                boulder_spots.extend(pos);
+            }
+        }
+
+        let open = |x: i32, y: i32| !map.wall_or_oob(x, y);
+        let thin_walls = (0..map.height)
+            .flat_map(|y| (0..map.width).map(move |x| (x, y)))
+            .filter(|&(x, y)| {
+                matches!(map.get_tile(x, y), Tile::Wall)
+                    && ((open(x - 1, y) && open(x + 1, y)) || (open(x, y - 1) && open(x, y + 1)))
+            })
+            .collect::<Vec<_>>();
+        let num = rng.gen_range(0..=2);
+
+        (
+            boulder_spots,
+            thin_walls
+                .choose_multiple(&mut rng, num)
+                .copied()
+                .collect::<Vec<_>>(),
+        )
+    };
+
+    for pos in boulder_spots {
+        spawn_boulder(world, pos);
+    }
+    for pos in wall_spots {
+        spawn_cracked_wall(world, pos);
+    }
 }
 
 fn spawn_random_monster_at<R: Rng>(world: &World, rng: &mut R, pos: (i32, i32)) {
@@ -479,15 +1420,47 @@ fn spawn_random_monster_at<R: Rng>(world: &World, rng: &mut R, pos: (i32, i32))
             level = rng.gen_range(1i32..level);
         }
     }
-    let (sym, name, fg) = MONSTERS[(level.max(1) as usize)
-        .min(MONSTERS.len())
+    level = (level
+        + world
+            .borrow::<UniqueView<Difficulty>>()
+            .settings
+            .monster_levels)
+        .max(1);
+    let tables = world.borrow::<UniqueView<SpawnTables>>();
+    let SpawnEntry {
+        sym,
+        name,
+        fg,
+        desc,
+        faction,
+        ..
+    } = &tables.monsters[(level.max(1) as usize)
+        .min(tables.monsters.len())
         .saturating_sub(1)];
+    let monster_id = spawn_monster(
+        world,
+        pos,
+        level,
+        *sym,
+        NameSource::Plain(name.clone()),
+        (*fg).into(),
+    );
+
+    describe_entity(world, monster_id, desc);
 
-    spawn_monster(world, pos, level, sym, name, fg.into());
+    if *faction != Faction::Monsters {
+        world.borrow::<EntitiesViewMut>().add_component(
+            &mut world.borrow::<ViewMut<Faction>>(),
+            *faction,
+            monster_id,
+        );
+    }
 }
 
 fn spawn_random_item_at<R: Rng>(world: &World, rng: &mut R, pos: (i32, i32)) {
-    if rng.gen_ratio(1, 11) {
+    let equipment_odds = world.borrow::<UniqueView<SpawnTables>>().equipment_odds;
+
+    if rng.gen_ratio(1, equipment_odds) {
         // Spawn weapon or armor.
         let level = {
             let difficulty = world.borrow::<UniqueView<Difficulty>>();
@@ -503,22 +1476,110 @@ fn spawn_random_item_at<R: Rng>(world: &World, rng: &mut R, pos: (i32, i32)) {
             spawn_armor(world, rng, pos, level, bonus);
         }
     } else {
-        // Spawn an item.
-        type ItemFn = fn(&World, (i32, i32));
-        let choice: Result<&(u32, ItemFn), _> = [
-            (3, spawn_health_potion as _),
-            (3, spawn_magic_missile_scroll as _),
-            (2, spawn_fireball_scroll as _),
-            (2, spawn_sleep_scroll as _),
-        ]
-        .choose_weighted(rng, |&(weight, _)| weight);
-
-        if let Ok((_, item_fn)) = choice {
-            item_fn(world, pos);
+        spawn_random_consumable_at(world, rng, pos);
+    }
+}
+
+fn spawn_random_consumable_at<R: Rng>(world: &World, rng: &mut R, pos: (i32, i32)) {
+    // Occasionally spawn an item from the scripts file instead of a built-in one.
+    if rng.gen_ratio(1, 8) && spawn_random_scripted_item(world, rng, pos) {
+        return;
+    }
+
+    // Repair items are no use if equipment never wears out.
+    let durability = world.borrow::<UniqueView<Options>>().durability;
+    let choice = world
+        .borrow::<UniqueView<SpawnTables>>()
+        .items
+        .choose_weighted(rng, |w| {
+            if !durability && matches!(w.item, GiftItem::Whetstone | GiftItem::RepairKit) {
+                0
+            } else {
+                w.weight
+            }
+        })
+        .map(|w| w.item);
+
+    if let Ok(item) = choice {
+        spawn_gift(world, pos, item);
+    }
+}
+
+fn spawn_gold(world: &World, pos: (i32, i32), amount: u32) {
+    let item_id = spawn_item(
+        world,
+        pos,
+        NameSource::Gold(amount),
+        GameSym::Gold,
+        Color::YELLOW,
+    );
+    describe_entity(world, item_id, "desc.gold");
+    weigh_item(world, item_id, 0);
+}
+
+/// Roll the drop table of a monster that just died, placing whatever it drops where it died.
+///
+/// Monsters without a drop table of their own use the default drops from the spawn tables.
+pub fn spawn_monster_drops(world: &World, monster_name: &str, pos: (i32, i32)) {
+    let mut rng = world
+        .borrow::<UniqueView<GameSeed>>()
+        .fork(RngStream::Loot)
+        .mix_u64(world.borrow::<UniqueView<TurnCount>>().0)
+        .mix_i32(pos.0)
+        .mix_i32(pos.1)
+        .rng();
+    let drops = {
+        let tables = world.borrow::<UniqueView<SpawnTables>>();
+        tables
+            .monsters
+            .iter()
+            .find(|m| m.name == monster_name)
+            .and_then(|m| m.drops)
+            .unwrap_or(tables.default_drops)
+    };
+    let DropTable {
+        gold_chance,
+        gold,
+        consumable_chance,
+        equipment_chance,
+    } = drops;
+
+    if rng.gen_range(0..100) < gold_chance {
+        spawn_gold(world, pos, rng.gen_range(gold.0..=gold.1).max(1));
+    }
+
+    if rng.gen_range(0..100) < consumable_chance {
+        spawn_random_consumable_at(world, &mut rng, pos);
+    }
+
+    if rng.gen_range(0..100) < equipment_chance {
+        let level = {
+            let difficulty = world.borrow::<UniqueView<Difficulty>>();
+            let exps = world.borrow::<View<Experience>>();
+            difficulty.as_f32(&exps)
+        };
+        let tables = world.borrow::<UniqueView<SpawnTables>>();
+        let quality = tables
+            .quality_tiers
+            .choose_weighted(&mut rng, |q| q.weight)
+            .ok();
+
+        if rng.gen() {
+            spawn_weapon_of_quality(world, &mut rng, pos, level, 0, quality);
+        } else {
+            spawn_armor_of_quality(world, &mut rng, pos, level, 0, quality);
         }
     }
 }
 
+/// True for hazards, and walls that prefabs put inside of rooms.
+fn is_unfit_for_spawn(world: &World, pos: (i32, i32)) -> bool {
+    let map = world.borrow::<UniqueView<Map>>();
+    let tile = map.get_tile(pos.0, pos.1);
+
+    tile.is_hazard() || matches!(tile, Tile::Wall)
+}
+
 fn fill_room_with_spawns<R: Rng>(world: &World, rng: &mut R, room: &Rect) {
     let depth = world.borrow::<UniqueView<Map>>().depth;
     let wins = world.borrow::<UniqueView<Wins>>().0.min(i32::MAX as u32) as i32;
@@ -527,15 +1588,24 @@ fn fill_room_with_spawns<R: Rng>(world: &World, rng: &mut R, room: &Rect) {
         let num = rng.gen_range(1i32..2i32 + wins);
 
         for pos in room.iter_xy().choose_multiple(rng, num as usize) {
-            spawn_random_item_at(world, rng, pos);
+            if !is_unfit_for_spawn(world, pos) {
+                spawn_random_item_at(world, rng, pos);
+            }
         }
     }
 
-    if rng.gen_ratio(1, 2) {
+    // Rooms are more likely to hold monsters with each win, scaled by spawn density.
+    let spawn_density = world
+        .borrow::<UniqueView<Difficulty>>()
+        .settings
+        .spawn_density;
+    if rng.gen_ratio(((2 + wins).min(4) as u32 * spawn_density).min(400), 400) {
         let num = rng.gen_range(1i32..1 + wins + ((depth + 1) / 2).max(1).min(3));
 
         for pos in room.iter_xy().choose_multiple(rng, num as usize) {
-            spawn_random_monster_at(world, rng, pos);
+            if !is_unfit_for_spawn(world, pos) {
+                spawn_random_monster_at(world, rng, pos);
+            }
         }
     }
 }
@@ -546,7 +1616,11 @@ fn pick_random_pos_in_room<R: Rng>(world: &World, rng: &mut R) -> Option<(i32, i
 
     map.rooms.choose(rng).and_then(|room| {
         room.iter_xy()
-            .filter(|&(x, y)| !map.iter_entities_at(x, y).any(|id| items.contains(id)))
+            .filter(|&(x, y)| {
+                !map.get_tile(x, y).is_hazard()
+                    && !map.wall_or_oob(x, y)
+                    && !map.iter_entities_at(x, y).any(|id| items.contains(id))
+            })
             .choose(rng)
     })
 }
@@ -583,15 +1657,15 @@ fn spawn_guaranteed_equipment<R: Rng>(world: &World, rng: &mut R) {
     // Spawn a weapon and an armor once every so many levels, using separate RNGs for stable,
     // isolated random numbers to decide the exact levels.
     let depth_period_base = depth as u32 / EQUIPMENT_SPAWN_PERIOD * EQUIPMENT_SPAWN_PERIOD;
-    let game_seed = world.borrow::<UniqueView<GameSeed>>().0;
+    let game_seed = world.borrow::<UniqueView<GameSeed>>();
 
     let mut periodic_weapon_rng = {
         // Offset the weapon spawn period based on the low bytes of the game seed.
-        let offset = (game_seed & 0xffffffff) as u32 % EQUIPMENT_SPAWN_PERIOD;
-        let mut hasher = WyHash::with_seed(magicnum::SPAWN_GUARANTEED_WEAPON);
-        hasher.write_u64(game_seed);
-        hasher.write_u32((depth as u32 + offset) / EQUIPMENT_SPAWN_PERIOD);
-        GameRng::seed_from_u64(hasher.finish())
+        let offset = (game_seed.0 & 0xffffffff) as u32 % EQUIPMENT_SPAWN_PERIOD;
+        game_seed
+            .fork(RngStream::GuaranteedWeapon)
+            .mix_u32((depth as u32 + offset) / EQUIPMENT_SPAWN_PERIOD)
+            .rng()
     };
 
     // Pick a random number in a range one-short of the period to guarantee a "gap" level, to make
@@ -612,11 +1686,11 @@ fn spawn_guaranteed_equipment<R: Rng>(world: &World, rng: &mut R) {
 
     let mut periodic_armor_rng = {
         // Offset the armor spawn period based on the high bytes of the game seed.
-        let offset = ((game_seed >> 32) & 0xffffffff) as u32 % EQUIPMENT_SPAWN_PERIOD;
-        let mut hasher = WyHash::with_seed(magicnum::SPAWN_GUARANTEED_ARMOR);
-        hasher.write_u64(game_seed);
-        hasher.write_u32((depth as u32 + offset) / EQUIPMENT_SPAWN_PERIOD);
-        GameRng::seed_from_u64(hasher.finish())
+        let offset = ((game_seed.0 >> 32) & 0xffffffff) as u32 % EQUIPMENT_SPAWN_PERIOD;
+        game_seed
+            .fork(RngStream::GuaranteedArmor)
+            .mix_u32((depth as u32 + offset) / EQUIPMENT_SPAWN_PERIOD)
+            .rng()
     };
 
     // Random number one-short of the period, for the same reason as the weapon spawn.
@@ -644,12 +1718,11 @@ fn spawn_guaranteed_ration<R: Rng>(world: &World, rng: &mut R) {
 }
 
 pub fn fill_rooms_with_spawns(world: &World) {
-    let mut rng = {
-        let mut hasher = WyHash::with_seed(magicnum::FILL_ROOM_WITH_SPAWNS);
-        hasher.write_u64(world.borrow::<UniqueView<GameSeed>>().0);
-        hasher.write_i32(world.borrow::<UniqueView<Map>>().depth);
-        GameRng::seed_from_u64(hasher.finish())
-    };
+    let mut rng = world
+        .borrow::<UniqueView<GameSeed>>()
+        .fork(RngStream::Spawns)
+        .mix_i32(world.borrow::<UniqueView<Map>>().depth)
+        .rng();
 
     spawn_guaranteed_equipment(world, &mut rng);
 
@@ -666,9 +1739,44 @@ pub fn fill_rooms_with_spawns(world: &World) {
     }
 
     spawn_guaranteed_ration(world, &mut rng);
+    spawn_random_npc(world);
+    spawn_random_friendly(world);
+    spawn_vault_contents(world);
+    spawn_prefab_contents(world);
+    spawn_obstacles(world);
 }
 
-/// Despawn an entity, including all associated entities like equipment and inventory.
+/// Spawn the fixed contents of the tutorial level: a weapon and a magic missile scroll to pick up,
+/// and the weakest monster at each of the given positions.
+pub fn spawn_tutorial_contents(
+    world: &World,
+    weapon_pos: (i32, i32),
+    scroll_pos: (i32, i32),
+    monster_positions: &[(i32, i32)],
+) {
+    let mut rng = world
+        .borrow::<UniqueView<GameSeed>>()
+        .fork(RngStream::Spawns)
+        .rng();
+
+    spawn_weapon(world, &mut rng, weapon_pos, 1.0, 1);
+    spawn_magic_missile_scroll(world, scroll_pos);
+
+    for &pos in monster_positions {
+        let (sym, name, fg, desc) = {
+            let tables = world.borrow::<UniqueView<SpawnTables>>();
+            let entry = &tables.monsters[0];
+
+            (entry.sym, entry.name.clone(), entry.fg, entry.desc.clone())
+        };
+        let monster_id = spawn_monster(world, pos, 1, sym, NameSource::Plain(name), fg.into());
+
+        describe_entity(world, monster_id, &desc);
+    }
+}
+
+/// Despawn an entity, including all associated entities like equipment and inventory, and the
+/// contents of any containers among them.
 pub fn despawn_entity(all_storages: &mut AllStoragesViewMut, id: EntityId) {
     let mut extra_despawn_ids = Vec::new();
 
@@ -690,7 +1798,7 @@ pub fn despawn_entity(all_storages: &mut AllStoragesViewMut, id: EntityId) {
     }
 
     for extra_id in extra_despawn_ids {
-        all_storages.delete(extra_id);
+        despawn_entity(all_storages, extra_id);
     }
 
     all_storages.delete(id);
@@ -709,3 +1817,91 @@ pub fn despawn_coord_entities(mut all_storages: AllStoragesViewMut) {
         despawn_entity(&mut all_storages, id);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        locale::{self, Locale},
+        map,
+        message::Messages,
+        npc, player,
+        player::PlayerId,
+        prefab,
+        quest::Quests,
+        script, spawn_tables,
+    };
+
+    const SEEDS: u64 = 200;
+    const DEPTHS: i32 = 8;
+
+    /// A world with just enough in it to generate and fill levels, like a fresh game.
+    fn new_world(seed: u64) -> World {
+        let world = World::new();
+        let language_list = locale::discover_languages();
+
+        world.add_unique(Locale::load(&language_list, 0));
+        world.add_unique(
+            serde_json::from_str::<Options>(
+                r#"{ "tileset": 0, "font": 0, "map_zoom": 1, "text_zoom": 1 }"#,
+            )
+            .unwrap(),
+        );
+        world.add_unique(GameSeed(seed));
+        world.add_unique(TurnCount(1));
+        world.add_unique(Wins(0));
+        world.add_unique(BaseEquipmentLevel(0));
+        world.add_unique(Difficulty::new(world.run(spawn_difficulty)));
+        world.add_unique(Messages::new(100));
+        world.add_unique(Quests::default());
+        world.add_unique(Map::new(80, 50));
+        world.add_unique(PlayerId(world.run(spawn_player)));
+        world.add_unique(npc::load_npc_defs());
+        world.add_unique(script::load_scripts());
+        world.add_unique(prefab::load_prefabs());
+        world.add_unique(spawn_tables::load_spawn_tables().unwrap());
+        world.run(player::add_coords_to_players);
+        world
+    }
+
+    #[test]
+    fn no_entities_spawn_inside_walls() {
+        for seed in 0..SEEDS {
+            let world = new_world(seed);
+
+            for depth in 1..=DEPTHS {
+                world.run(player::remove_coords_from_players);
+                world.run(despawn_coord_entities);
+                world.run(player::add_coords_to_players);
+                world.run(|mut map: UniqueViewMut<Map>| {
+                    map.clear();
+                    map.depth = depth;
+                });
+                world.run(map::generate_rooms_and_corridors);
+                world.run(map::place_player_in_first_room);
+                fill_rooms_with_spawns(&world);
+
+                // Cracked walls are the only things meant to be found inside of walls.
+                world.run(
+                    |map: UniqueView<Map>, coords: View<Coord>, diggables: View<Diggable>| {
+                        for (id, coord) in coords.iter().with_id() {
+                            let (x, y) = (coord.0.x, coord.0.y);
+
+                            assert!(
+                                diggables.contains(id)
+                                    || !map.wall_or_oob(x, y)
+                                        && !matches!(map.get_tile(x, y), Tile::LockedDoor),
+                                "seed {} depth {}: entity spawned in {} at ({}, {})",
+                                seed,
+                                depth,
+                                map.get_tile(x, y),
+                                x,
+                                y,
+                            );
+                        }
+                    },
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,175 @@
+use serde::Deserialize;
+use std::fs;
+
+use crate::{components::Faction, gamesym::GameSym, npc::GiftItem};
+
+pub const SPAWN_TABLES_PATH: &str = "assets/spawn_tables.json";
+
+/// A kind of monster, weapon or armor.  Tables list these from weakest to strongest.
+#[derive(Deserialize)]
+pub struct SpawnEntry {
+    pub sym: GameSym,
+    pub name: String,
+    pub fg: (u8, u8, u8),
+    /// Flavor text shown when examining weapons and armor, and in the bestiary for monsters.
+    #[serde(default)]
+    pub desc: String,
+    /// Weight of weapons and armor; unused for monsters.
+    #[serde(default)]
+    pub weight: i32,
+    /// What a monster drops when it dies, instead of the default drops; unused for weapons and
+    /// armor.
+    #[serde(default)]
+    pub drops: Option<DropTable>,
+    /// Which side a monster fights on; unused for weapons and armor.
+    #[serde(default)]
+    pub faction: Faction,
+}
+
+/// A harmless character without dialog, such as a prisoner, that the player can swap places with.
+#[derive(Deserialize)]
+pub struct FriendlyEntry {
+    pub sym: GameSym,
+    pub name: String,
+    pub fg: (u8, u8, u8),
+    #[serde(default = "default_min_depth")]
+    pub min_depth: i32,
+}
+
+fn default_min_depth() -> i32 {
+    1
+}
+
+/// What a monster might drop when it dies, with chances given as percentages.
+#[derive(Clone, Copy, Default, Deserialize)]
+pub struct DropTable {
+    #[serde(default)]
+    pub gold_chance: u32,
+    /// Least and most gold dropped.
+    #[serde(default)]
+    pub gold: (u32, u32),
+    #[serde(default)]
+    pub consumable_chance: u32,
+    #[serde(default)]
+    pub equipment_chance: u32,
+}
+
+impl DropTable {
+    fn validate(&self, what: &str, errors: &mut Vec<String>) {
+        for (field, chance) in [
+            ("gold_chance", self.gold_chance),
+            ("consumable_chance", self.consumable_chance),
+            ("equipment_chance", self.equipment_chance),
+        ]
+        .iter()
+        {
+            if *chance > 100 {
+                errors.push(format!("{} '{}' must be at most 100", what, field));
+            }
+        }
+        if self.gold.0 > self.gold.1 {
+            errors.push(format!("{} 'gold' minimum is more than its maximum", what));
+        }
+    }
+}
+
+/// A grade of dropped equipment that adds to its bonus and is named in front of it.
+#[derive(Deserialize)]
+pub struct QualityTier {
+    pub name: String,
+    pub bonus: i32,
+    pub weight: u32,
+}
+
+/// How likely an item is to be picked when a random non-equipment item is spawned.
+#[derive(Deserialize)]
+pub struct ItemWeight {
+    pub item: GiftItem,
+    pub weight: u32,
+}
+
+/// What can spawn in the dungeon and how often, as defined in the spawn tables file.
+#[derive(Deserialize)]
+pub struct SpawnTables {
+    pub monsters: Vec<SpawnEntry>,
+    pub weapons: Vec<SpawnEntry>,
+    pub armors: Vec<SpawnEntry>,
+    /// One in this many random item spawns is a weapon or armor instead.
+    pub equipment_odds: u32,
+    pub items: Vec<ItemWeight>,
+    /// Drops for monsters that don't list their own.
+    #[serde(default)]
+    pub default_drops: DropTable,
+    /// Grades of equipment dropped by monsters; the name of an ungraded tier should be empty.
+    #[serde(default)]
+    pub quality_tiers: Vec<QualityTier>,
+    #[serde(default)]
+    pub friendlies: Vec<FriendlyEntry>,
+    /// Percent chance of a friendly appearing on each level.
+    #[serde(default)]
+    pub friendly_chance: u32,
+}
+
+impl SpawnTables {
+    /// List everything wrong with the spawn tables that would prevent spawning from working.
+    fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+
+        for (table, entries) in [
+            ("monsters", &self.monsters),
+            ("weapons", &self.weapons),
+            ("armors", &self.armors),
+        ]
+        .iter()
+        {
+            if entries.is_empty() {
+                errors.push(format!("'{}' has no entries", table));
+            }
+            for (i, entry) in entries.iter().enumerate() {
+                if entry.name.trim().is_empty() {
+                    errors.push(format!("'{}' entry {} has an empty name", table, i + 1));
+                }
+            }
+        }
+
+        if self.equipment_odds == 0 {
+            errors.push("'equipment_odds' must be at least 1".to_string());
+        }
+
+        if self.items.iter().all(|w| w.weight == 0) {
+            errors.push("'items' needs at least one entry with a non-zero weight".to_string());
+        }
+
+        for (i, entry) in self.friendlies.iter().enumerate() {
+            if entry.name.trim().is_empty() {
+                errors.push(format!("'friendlies' entry {} has an empty name", i + 1));
+            }
+        }
+        if self.friendly_chance > 100 {
+            errors.push("'friendly_chance' must be at most 100".to_string());
+        }
+
+        self.default_drops.validate("'default_drops'", &mut errors);
+        for (i, entry) in self.monsters.iter().enumerate() {
+            if let Some(drops) = &entry.drops {
+                drops.validate(&format!("'monsters' entry {} drops", i + 1), &mut errors);
+            }
+        }
+
+        errors
+    }
+}
+
+/// Load and validate the spawn tables file, returning all of the problems found with it if it
+/// can't be used.
+pub fn load_spawn_tables() -> Result<SpawnTables, Vec<String>> {
+    let contents = fs::read_to_string(SPAWN_TABLES_PATH).map_err(|e| vec![e.to_string()])?;
+    let tables = serde_json::from_str::<SpawnTables>(&contents).map_err(|e| vec![e.to_string()])?;
+    let errors = tables.validate();
+
+    if errors.is_empty() {
+        Ok(tables)
+    } else {
+        Err(errors)
+    }
+}
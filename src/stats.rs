@@ -0,0 +1,294 @@
+use serde::{Deserialize, Serialize};
+use shipyard::{EntityId, Get, UniqueView, UniqueViewMut, View, World};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs, mem,
+};
+
+#[cfg(target_os = "emscripten")]
+use crate::ruggrogue_sync_idbfs;
+use crate::{
+    components::{FieldOfView, HurtBy, Monster, NameSource},
+    hints::Hint,
+    map::Map,
+    paths,
+    player::PlayerId,
+    TurnCount,
+};
+use ruggrogue::Error;
+
+pub(crate) const STATS_FILENAME: &str = "stats.json";
+
+/// Lifetime statistics across all runs, kept in the stats file.
+#[derive(Clone, Default, Deserialize, Serialize)]
+pub struct Profile {
+    #[serde(default)]
+    pub runs: u32,
+    #[serde(default)]
+    pub wins: u32,
+    #[serde(default)]
+    pub turns_played: u64,
+    /// Fewest turns taken by a winning run.
+    #[serde(default)]
+    pub fastest_win: Option<u64>,
+    /// Kills by the name of the monster killed.
+    #[serde(default)]
+    pub kills: BTreeMap<String, u64>,
+    /// Deaths by what caused them.
+    #[serde(default)]
+    pub deaths: BTreeMap<String, u32>,
+    /// Names of monsters that the player has laid eyes on, for the bestiary.
+    #[serde(default)]
+    pub seen: BTreeSet<String>,
+    /// Hints that have been shown to the player.
+    #[serde(default)]
+    pub hints_shown: BTreeSet<Hint>,
+}
+
+/// A profile whose changes couldn't be written to the stats file, held on to so that writing it
+/// can be tried again without losing anything recorded in the meantime.
+#[derive(Default)]
+pub struct UnsavedProfile {
+    profile: Option<Profile>,
+    /// Set when writing the profile fails, until [check_profile_saved] reports it.
+    unreported: bool,
+}
+
+/// Statistics of the current run that haven't been added to the profile yet.
+///
+/// A run ends when the player wins or is defeated; New Game Plus starts a new run.
+#[derive(Default, Deserialize, Serialize)]
+pub struct RunStats {
+    start_turn: u64,
+    kills: BTreeMap<String, u64>,
+    #[serde(default)]
+    seen: BTreeSet<String>,
+}
+
+impl RunStats {
+    /// Start tracking a new run beginning on the given turn.
+    pub fn reset(&mut self, start_turn: u64) {
+        self.start_turn = start_turn;
+        self.kills.clear();
+        self.seen.clear();
+    }
+
+    pub fn replace(&mut self, replacement: Self) {
+        *self = replacement;
+    }
+
+    pub fn add_kill(&mut self, name: &str) {
+        *self.kills.entry(name.to_string()).or_insert(0) += 1;
+    }
+
+    pub fn kills_of(&self, name: &str) -> u64 {
+        self.kills.get(name).copied().unwrap_or(0)
+    }
+
+    pub fn has_seen(&self, name: &str) -> bool {
+        self.seen.contains(name)
+    }
+}
+
+/// Note down every monster in the player's field of view as seen for the bestiary.
+pub fn record_seen_monsters(
+    map: UniqueView<Map>,
+    player_id: UniqueView<PlayerId>,
+    mut run_stats: UniqueViewMut<RunStats>,
+    fovs: View<FieldOfView>,
+    monsters: View<Monster>,
+    name_sources: View<NameSource>,
+) {
+    for (x, y) in fovs.get(player_id.0).iter() {
+        for id in map.iter_entities_at(x, y) {
+            if monsters.contains(id) && !run_stats.seen.contains(name_sources.get(id).key()) {
+                run_stats
+                    .seen
+                    .insert(name_sources.get(id).key().to_string());
+            }
+        }
+    }
+}
+
+/// Load the profile from the stats file, or an empty one if there isn't one yet.
+pub fn load_profile() -> Profile {
+    let path = paths::data_file(STATS_FILENAME);
+
+    match fs::read_to_string(&path) {
+        Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+            eprintln!("Warning: Failed to parse {}: {}", path.display(), e);
+            Profile::default()
+        }),
+        Err(e) => {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                eprintln!("Warning: Failed to read {}: {}", path.display(), e);
+            }
+            Profile::default()
+        }
+    }
+}
+
+/// The profile as it stands, including any changes that have yet to be written to the stats file.
+pub fn current_profile(world: &World) -> Profile {
+    world
+        .borrow::<UniqueView<UnsavedProfile>>()
+        .profile
+        .clone()
+        .unwrap_or_else(load_profile)
+}
+
+/// Take the profile to make changes to, starting from any unsaved changes.
+fn take_profile(unsaved: &mut UnsavedProfile) -> Profile {
+    unsaved.profile.take().unwrap_or_else(load_profile)
+}
+
+fn write_profile(profile: &Profile) -> Result<(), Error> {
+    let contents = serde_json::to_string_pretty(profile)
+        .map_err(|e| Error::new("Failed to serialize stats", e))?;
+    let path = paths::data_file(STATS_FILENAME);
+
+    fs::write(&path, contents)
+        .map_err(|e| Error::new(format!("Failed to write {}", path.display()), e))?;
+
+    #[cfg(target_os = "emscripten")]
+    unsafe {
+        ruggrogue_sync_idbfs();
+    }
+
+    Ok(())
+}
+
+/// Write the profile to the stats file, holding on to it if that fails.
+///
+/// Profile changes are recorded deep inside of turns where there's no mode to report to, so
+/// failures are reported by [check_profile_saved] instead.
+fn save_profile(unsaved: &mut UnsavedProfile, profile: Profile) {
+    if write_profile(&profile).is_err() {
+        unsaved.profile = Some(profile);
+        unsaved.unreported = true;
+    }
+}
+
+/// Try writing a profile whose changes couldn't be written before, returning the error if it still
+/// can't be written.
+pub fn save_unsaved_profile(world: &World) -> Result<(), Error> {
+    let mut unsaved = world.borrow::<UniqueViewMut<UnsavedProfile>>();
+
+    if let Some(profile) = &unsaved.profile {
+        write_profile(profile)?;
+        unsaved.profile = None;
+    }
+
+    Ok(())
+}
+
+/// Returns the error to report if writing the profile has failed since this was last called and
+/// still fails when tried again.
+///
+/// A failure that the player chose to ignore isn't reported again, but the changes are still held
+/// on to and written along with the next ones.
+pub fn check_profile_saved(world: &World) -> Result<(), Error> {
+    if mem::take(&mut world.borrow::<UniqueViewMut<UnsavedProfile>>().unreported) {
+        save_unsaved_profile(world)
+    } else {
+        Ok(())
+    }
+}
+
+/// Describe what defeated the given entity.
+///
+/// The description stays the same in every language since it keys the death tally of the profile;
+/// pass it through [crate::locale::Locale::name] to show it.
+pub fn defeat_cause(
+    id: EntityId,
+    hurt_bys: &View<HurtBy>,
+    name_sources: &View<NameSource>,
+) -> String {
+    match hurt_bys.try_get(id) {
+        Ok(HurtBy::Someone(hurter)) => {
+            if *hurter == id {
+                "an overinflated ego".to_string()
+            } else {
+                name_sources.get(*hurter).key().to_string()
+            }
+        }
+        Ok(HurtBy::Starvation) => "starvation".to_string(),
+        Ok(HurtBy::Burning) => "lava".to_string(),
+        Err(_) => "perfectly natural causes".to_string(),
+    }
+}
+
+/// Add the current run to the profile, returning how many turns it took, and start a new run.
+fn end_run(profile: &mut Profile, run_stats: &mut RunStats, turn_count: u64) -> u64 {
+    let run_turns = turn_count.saturating_sub(run_stats.start_turn);
+
+    profile.runs += 1;
+    profile.turns_played += run_turns;
+    for (name, kills) in run_stats.kills.iter() {
+        *profile.kills.entry(name.clone()).or_insert(0) += kills;
+    }
+    profile.seen.extend(run_stats.seen.iter().cloned());
+    run_stats.reset(turn_count);
+
+    run_turns
+}
+
+/// Record the end of a run in which the player was defeated.
+pub fn record_defeat(
+    turn_count: UniqueView<TurnCount>,
+    player_id: UniqueView<PlayerId>,
+    mut run_stats: UniqueViewMut<RunStats>,
+    hurt_bys: View<HurtBy>,
+    name_sources: View<NameSource>,
+    mut unsaved: UniqueViewMut<UnsavedProfile>,
+) {
+    let mut profile = take_profile(&mut unsaved);
+
+    end_run(&mut profile, &mut run_stats, turn_count.0);
+    *profile
+        .deaths
+        .entry(defeat_cause(player_id.0, &hurt_bys, &name_sources))
+        .or_insert(0) += 1;
+    save_profile(&mut unsaved, profile);
+}
+
+/// Remember that a hint has been shown, so that later games don't show it again.
+pub fn record_hint_shown(world: &World, hint: Hint) {
+    let mut unsaved = world.borrow::<UniqueViewMut<UnsavedProfile>>();
+    let was_unsaved = unsaved.profile.is_some();
+    let mut profile = take_profile(&mut unsaved);
+
+    if profile.hints_shown.insert(hint) {
+        save_profile(&mut unsaved, profile);
+    } else if was_unsaved {
+        unsaved.profile = Some(profile);
+    }
+}
+
+/// Forget every hint that has been shown, so that they'll be shown again.
+pub fn forget_hints_shown(world: &World) {
+    let mut unsaved = world.borrow::<UniqueViewMut<UnsavedProfile>>();
+    let was_unsaved = unsaved.profile.is_some();
+    let mut profile = take_profile(&mut unsaved);
+
+    if !profile.hints_shown.is_empty() {
+        profile.hints_shown.clear();
+        save_profile(&mut unsaved, profile);
+    } else if was_unsaved {
+        unsaved.profile = Some(profile);
+    }
+}
+
+/// Record the end of a run in which the player won.
+pub fn record_victory(
+    turn_count: UniqueView<TurnCount>,
+    mut run_stats: UniqueViewMut<RunStats>,
+    mut unsaved: UniqueViewMut<UnsavedProfile>,
+) {
+    let mut profile = take_profile(&mut unsaved);
+    let run_turns = end_run(&mut profile, &mut run_stats, turn_count.0);
+
+    profile.wins += 1;
+    profile.fastest_win = Some(profile.fastest_win.map_or(run_turns, |t| t.min(run_turns)));
+    save_profile(&mut unsaved, profile);
+}
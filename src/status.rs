@@ -0,0 +1,118 @@
+use rand::Rng;
+use shipyard::{
+    EntityId, Get, IntoIter, Shiperator, UniqueView, UniqueViewMut, View, ViewMut, World,
+};
+
+use crate::{
+    components::{Afraid, Charmed, Confused, Coord, FieldOfView, Name},
+    locale::Locale,
+    message::{Messages, MsgCategory},
+    player::PlayerId,
+    rng::RngStream,
+    GameSeed, TurnCount,
+};
+
+/// The eight directions that an entity can move in.
+const DIRECTIONS: [(i32, i32); 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+
+pub fn is_confused(world: &World, who: EntityId) -> bool {
+    world.borrow::<View<Confused>>().contains(who)
+}
+
+pub fn is_afraid(world: &World, who: EntityId) -> bool {
+    world.borrow::<View<Afraid>>().contains(who)
+}
+
+/// Roll for whether a confused entity stumbles this turn, returning the random direction it
+/// stumbles in if it does, instead of what it meant to do.
+pub fn stumble(world: &World, who: EntityId) -> Option<(i32, i32)> {
+    let mut rng = {
+        let mut fork = world
+            .borrow::<UniqueView<GameSeed>>()
+            .fork(RngStream::Confusion);
+        fork.mix_u64(world.borrow::<UniqueView<TurnCount>>().0);
+        if let Ok(coord) = world.borrow::<View<Coord>>().try_get(who) {
+            fork.mix_i32(coord.0.x).mix_i32(coord.0.y);
+        }
+        fork.rng()
+    };
+
+    if rng.gen_ratio(1, 2) {
+        Some(DIRECTIONS[rng.gen_range(0..DIRECTIONS.len())])
+    } else {
+        None
+    }
+}
+
+/// Count down confusion, fear and charms, announcing when they wear off for the player or anything
+/// the player can see.
+pub fn tick_statuses(
+    locale: UniqueView<Locale>,
+    mut msgs: UniqueViewMut<Messages>,
+    player_id: UniqueView<PlayerId>,
+    coords: View<Coord>,
+    fovs: View<FieldOfView>,
+    names: View<Name>,
+    mut confuseds: ViewMut<Confused>,
+    mut afraids: ViewMut<Afraid>,
+    mut charmeds: ViewMut<Charmed>,
+) {
+    let player_fov = fovs.get(player_id.0);
+    let noticed = |id: EntityId| {
+        id == player_id.0
+            || matches!(coords.try_get(id), Ok(coord) if player_fov.get(coord.0.into()))
+    };
+    let mut announce = |id: EntityId, msg_key: &str| {
+        if noticed(id) {
+            msgs.add_as(
+                MsgCategory::Combat,
+                locale.format(msg_key, &[("who", &names.get(id).0)]),
+            );
+        }
+    };
+
+    let mut unconfused = Vec::new();
+    for (id, confused) in (&mut confuseds).iter().with_id() {
+        confused.turns -= 1;
+        if confused.turns <= 0 {
+            unconfused.push(id);
+        }
+    }
+    for id in unconfused {
+        confuseds.remove(id);
+        announce(id, "msg.no_longer_confused");
+    }
+
+    let mut unafraid = Vec::new();
+    for (id, afraid) in (&mut afraids).iter().with_id() {
+        afraid.turns -= 1;
+        if afraid.turns <= 0 {
+            unafraid.push(id);
+        }
+    }
+    for id in unafraid {
+        afraids.remove(id);
+        announce(id, "msg.no_longer_afraid");
+    }
+
+    let mut uncharmed = Vec::new();
+    for (id, charmed) in (&mut charmeds).iter().with_id() {
+        charmed.turns -= 1;
+        if charmed.turns <= 0 {
+            uncharmed.push(id);
+        }
+    }
+    for id in uncharmed {
+        charmeds.remove(id);
+        announce(id, "msg.no_longer_charmed");
+    }
+}
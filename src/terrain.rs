@@ -0,0 +1,74 @@
+use shipyard::{
+    EntitiesView, EntityId, Get, IntoIter, Shiperator, UniqueView, UniqueViewMut, View, ViewMut,
+    World,
+};
+
+use crate::{
+    components::{CombatStats, Coord, FieldOfView, HurtBy, Name, Player, Tally},
+    event,
+    locale::Locale,
+    map::{Map, Tile},
+    message::{Messages, MsgCategory},
+    player::PlayerId,
+};
+
+/// Standing in lava burns away this fraction of maximum hit points each turn.
+const LAVA_BURN_DIVISOR: i32 = 5;
+
+/// Burn everything with hit points that is standing in lava.
+pub fn tick_terrain(world: &World) {
+    let burning = {
+        let map = world.borrow::<UniqueView<Map>>();
+        let combat_stats = world.borrow::<View<CombatStats>>();
+        let coords = world.borrow::<View<Coord>>();
+
+        (&combat_stats, &coords)
+            .iter()
+            .with_id()
+            .into_iter()
+            .filter(|(_, (stats, coord))| {
+                stats.hp > 0 && matches!(map.get_tile(coord.0.x, coord.0.y), Tile::Lava)
+            })
+            .map(|(id, (stats, _))| (id, (stats.max_hp / LAVA_BURN_DIVISOR).max(1)))
+            .collect::<Vec<(EntityId, i32)>>()
+    };
+
+    for (id, amount) in burning {
+        {
+            let locale = world.borrow::<UniqueView<Locale>>();
+            let mut msgs = world.borrow::<UniqueViewMut<Messages>>();
+            let player_id = world.borrow::<UniqueView<PlayerId>>();
+            let entities = world.borrow::<EntitiesView>();
+            let mut combat_stats = world.borrow::<ViewMut<CombatStats>>();
+            let coords = world.borrow::<View<Coord>>();
+            let fovs = world.borrow::<View<FieldOfView>>();
+            let mut hurt_bys = world.borrow::<ViewMut<HurtBy>>();
+            let names = world.borrow::<View<Name>>();
+            let mut players = world.borrow::<ViewMut<Player>>();
+            let mut tallies = world.borrow::<ViewMut<Tally>>();
+
+            (&mut combat_stats).get(id).hp -= amount;
+            entities.add_component(&mut hurt_bys, HurtBy::Burning, id);
+            if let Ok(tally) = (&mut tallies).try_get(id) {
+                tally.damage_taken += amount as u64;
+            }
+
+            // Stop auto-run when burning.
+            if let Ok(player) = (&mut players).try_get(id) {
+                player.auto_run = None;
+            }
+
+            if id == player_id.0 || fovs.get(player_id.0).get(coords.get(id).0.into()) {
+                msgs.add_as(
+                    MsgCategory::Combat,
+                    locale.format(
+                        "msg.burns",
+                        &[("who", &names.get(id).0), ("damage", &amount)],
+                    ),
+                );
+            }
+        }
+
+        event::emit_damage(world, id, amount);
+    }
+}
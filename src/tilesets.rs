@@ -0,0 +1,203 @@
+use serde::Deserialize;
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::gamesym::{self, GameSym};
+use ruggrogue::TilesetInfo;
+
+const ASSETS_DIR: &str = "assets";
+const TILESETS_DIR: &str = "assets/tilesets";
+
+/// Named glyph layouts that a tileset descriptor can refer to for its font map.
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum FontMapKind {
+    CodePage437,
+    Urizen,
+}
+
+/// Named symbol layouts that a tileset descriptor can refer to for its symbol map.
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum SymbolMapKind {
+    None,
+    Urizen,
+}
+
+/// JSON descriptor for a tileset found in the tilesets directory.
+#[derive(Deserialize)]
+struct TilesetDescriptor {
+    name: String,
+    /// Path to the tile image, relative to the assets directory.
+    image: PathBuf,
+    tile_size: (u32, u32),
+    #[serde(default)]
+    tile_start: (i32, i32),
+    #[serde(default)]
+    tile_gap: (u32, u32),
+    font_map: FontMapKind,
+    #[serde(default = "default_symbol_map")]
+    symbol_map: SymbolMapKind,
+    /// True if the tileset is suitable for drawing user interface text.
+    #[serde(default)]
+    font: bool,
+    /// Tile positions of named symbols registered in the symbols file.
+    #[serde(default)]
+    symbols: HashMap<String, (i32, i32)>,
+}
+
+fn default_symbol_map() -> SymbolMapKind {
+    SymbolMapKind::None
+}
+
+impl TilesetDescriptor {
+    fn into_tileset_info(self) -> TilesetInfo<GameSym> {
+        TilesetInfo::<GameSym> {
+            image_path: Path::new(ASSETS_DIR).join(self.image),
+            tile_size: self.tile_size.into(),
+            tile_start: self.tile_start.into(),
+            tile_gap: self.tile_gap.into(),
+            font_map: match self.font_map {
+                FontMapKind::CodePage437 => TilesetInfo::<GameSym>::map_code_page_437(),
+                FontMapKind::Urizen => gamesym::urizen_font_map(),
+            },
+            symbol_map: match self.symbol_map {
+                SymbolMapKind::None => HashMap::new(),
+                SymbolMapKind::Urizen => gamesym::urizen_symbol_map(),
+            },
+            dyn_symbol_map: self.symbols,
+        }
+    }
+}
+
+pub struct TilesetEntry {
+    pub name: String,
+    pub font: bool,
+}
+
+/// Names and roles of tilesets available to the game, in the same order as the tilesets that were
+/// handed over to [ruggrogue::run].
+pub struct TilesetList(pub Vec<TilesetEntry>);
+
+impl TilesetList {
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn name(&self, index: u32) -> Option<&str> {
+        self.0.get(index as usize).map(|e| e.name.as_str())
+    }
+
+    pub fn is_font(&self, index: u32) -> bool {
+        matches!(self.0.get(index as usize), Some(e) if e.font)
+    }
+
+    /// Index of the nearest font before the given index, if any.
+    pub fn prev_font(&self, index: u32) -> Option<u32> {
+        (0..index.min(self.len() as u32))
+            .rev()
+            .find(|i| self.is_font(*i))
+    }
+
+    /// Index of the nearest font after the given index, if any.
+    pub fn next_font(&self, index: u32) -> Option<u32> {
+        (index + 1..self.len() as u32).find(|i| self.is_font(*i))
+    }
+
+    /// The first tileset that isn't a font, for showing the map by default.
+    pub fn default_tileset(&self) -> u32 {
+        self.0.iter().position(|e| !e.font).unwrap_or(0) as u32
+    }
+
+    /// The first font, for showing text by default.
+    pub fn default_font(&self) -> u32 {
+        self.0.iter().position(|e| e.font).unwrap_or(0) as u32
+    }
+}
+
+/// Tilesets to fall back on if none could be found in the tilesets directory.
+fn builtin_tilesets() -> (TilesetList, Vec<TilesetInfo<GameSym>>) {
+    let list = TilesetList(vec![
+        TilesetEntry {
+            name: "GohuFont".into(),
+            font: true,
+        },
+        TilesetEntry {
+            name: "Terminal".into(),
+            font: true,
+        },
+        TilesetEntry {
+            name: "Urizen".into(),
+            font: false,
+        },
+    ]);
+    let infos = vec![
+        TilesetInfo::<GameSym> {
+            image_path: PathBuf::from("assets/gohufont-8x14.png"),
+            tile_size: (8, 14).into(),
+            tile_start: (0, 0).into(),
+            tile_gap: (0, 0).into(),
+            font_map: TilesetInfo::<GameSym>::map_code_page_437(),
+            symbol_map: HashMap::new(),
+            dyn_symbol_map: HashMap::new(),
+        },
+        TilesetInfo::<GameSym> {
+            image_path: PathBuf::from("assets/terminal-8x8.png"),
+            tile_size: (8, 8).into(),
+            tile_start: (0, 0).into(),
+            tile_gap: (0, 0).into(),
+            font_map: TilesetInfo::<GameSym>::map_code_page_437(),
+            symbol_map: HashMap::new(),
+            dyn_symbol_map: HashMap::new(),
+        },
+        gamesym::urizen_tileset_info(),
+    ];
+
+    (list, infos)
+}
+
+/// Scan the tilesets directory for JSON tileset descriptors, sorted by file name.
+///
+/// Falls back to built-in tilesets if none could be loaded.
+pub fn discover_tilesets() -> (TilesetList, Vec<TilesetInfo<GameSym>>) {
+    let mut paths = match fs::read_dir(TILESETS_DIR) {
+        Ok(read_dir) => read_dir
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|p| matches!(p.extension().and_then(|e| e.to_str()), Some("json")))
+            .collect::<Vec<_>>(),
+        Err(e) => {
+            eprintln!("Warning: Failed to read {}: {}", TILESETS_DIR, e);
+            Vec::new()
+        }
+    };
+    let mut list = TilesetList(Vec::new());
+    let mut infos = Vec::new();
+
+    paths.sort();
+
+    for path in paths {
+        let descriptor = fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|s| serde_json::from_str::<TilesetDescriptor>(&s).map_err(|e| e.to_string()));
+
+        match descriptor {
+            Ok(descriptor) => {
+                list.0.push(TilesetEntry {
+                    name: descriptor.name.clone(),
+                    font: descriptor.font,
+                });
+                infos.push(descriptor.into_tileset_info());
+            }
+            Err(e) => eprintln!("Warning: Failed to load {}: {}", path.display(), e),
+        }
+    }
+
+    if infos.is_empty() {
+        builtin_tilesets()
+    } else {
+        (list, infos)
+    }
+}
@@ -0,0 +1,156 @@
+use sdl2::keyboard::Keycode;
+use shipyard::{UniqueView, UniqueViewMut, World};
+
+use crate::{
+    config,
+    gamesym::GameSym,
+    locale::Locale,
+    ui::{self, Options},
+};
+use ruggrogue::{
+    util::{Color, Position, Size},
+    Error, InputBuffer, InputEvent, TileGrid, Tileset,
+};
+
+/// A button of the on-screen touch controls that stands in for a key press.
+struct TouchButton {
+    pos: (i32, i32),
+    size: (i32, i32),
+    /// Locale key of the label, or the label itself for arrows and other symbols.
+    label: &'static str,
+    key: Keycode,
+}
+
+impl TouchButton {
+    const fn new(pos: (i32, i32), size: (i32, i32), label: &'static str, key: Keycode) -> Self {
+        Self {
+            pos,
+            size,
+            label,
+            key,
+        }
+    }
+
+    fn contains(&self, cell: Position) -> bool {
+        cell.x >= self.pos.0
+            && cell.y >= self.pos.1
+            && cell.x < self.pos.0 + self.size.0
+            && cell.y < self.pos.1 + self.size.1
+    }
+}
+
+/// Cell size of the grid that the touch controls are drawn on.
+const TOUCH_GRID_SIZE: Size = Size { w: 21, h: 9 };
+
+/// A directional pad on the left, with action buttons to its right.  Directions use the keypad
+/// keys so that they work in menus as well as on the map.
+const BUTTONS: [TouchButton; 15] = [
+    TouchButton::new((0, 0), (3, 3), "\\", Keycode::Kp7),
+    TouchButton::new((3, 0), (3, 3), "↑", Keycode::Kp8),
+    TouchButton::new((6, 0), (3, 3), "/", Keycode::Kp9),
+    TouchButton::new((0, 3), (3, 3), "←", Keycode::Kp4),
+    TouchButton::new((3, 3), (3, 3), "·", Keycode::Kp5),
+    TouchButton::new((6, 3), (3, 3), "→", Keycode::Kp6),
+    TouchButton::new((0, 6), (3, 3), "/", Keycode::Kp1),
+    TouchButton::new((3, 6), (3, 3), "↓", Keycode::Kp2),
+    TouchButton::new((6, 6), (3, 3), "\\", Keycode::Kp3),
+    TouchButton::new((10, 0), (5, 3), "touch.get", Keycode::G),
+    TouchButton::new((16, 0), (5, 3), "touch.act", Keycode::F),
+    TouchButton::new((10, 3), (5, 3), "touch.inv", Keycode::I),
+    TouchButton::new((16, 3), (5, 3), ">", Keycode::Greater),
+    TouchButton::new((10, 6), (5, 3), "touch.ok", Keycode::Return),
+    TouchButton::new((16, 6), (5, 3), "touch.esc", Keycode::Escape),
+];
+
+/// Prepare the touch controls grid to sit in the bottom-left corner of the window, at double text
+/// size so that the buttons are big enough to hit with a finger.
+pub fn prepare_touch_grid(
+    world: &World,
+    grids: &mut Vec<TileGrid<GameSym>>,
+    tilesets: &[Tileset<GameSym>],
+    window_size: Size,
+) {
+    let Options {
+        font, text_zoom, ..
+    } = *world.borrow::<UniqueView<Options>>();
+
+    if !grids.is_empty() {
+        grids[0].resize(TOUCH_GRID_SIZE);
+    } else {
+        grids.push(TileGrid::new(TOUCH_GRID_SIZE, tilesets, font as usize));
+        grids[0].view.clear_color = None;
+        grids[0].view.bg_alpha = ui::OVERLAY_BG_ALPHA;
+    }
+
+    let grid = &mut grids[0];
+    let zoom = text_zoom * 2;
+
+    grid.set_tileset(tilesets, font as usize);
+    grid.view.zoom = zoom;
+    grid.view.size = Size {
+        w: TOUCH_GRID_SIZE.w * tilesets[font as usize].tile_width() * zoom,
+        h: TOUCH_GRID_SIZE.h * tilesets[font as usize].tile_height() * zoom,
+    };
+    grid.view.pos = Position {
+        x: 0,
+        y: (window_size.h as i32 - grid.view.size.h as i32).max(0),
+    };
+}
+
+/// Draw the touch controls, hiding them entirely if they're turned off.
+pub fn draw_touch_controls(world: &World, grid: &mut TileGrid<GameSym>) {
+    let fg = Color::WHITE;
+    let bg = Color::BLACK;
+    let locale = world.borrow::<UniqueView<Locale>>();
+
+    grid.view.visible = world.borrow::<UniqueView<Options>>().touch_controls;
+
+    grid.clear();
+    for button in BUTTONS.iter() {
+        grid.draw_box(
+            button.pos,
+            (button.size.0 as u32, button.size.1 as u32),
+            Color::GRAY,
+            bg,
+        );
+        let label = locale.get(button.label);
+
+        grid.print_color(
+            (
+                button.pos.0 + (button.size.0 - label.chars().count() as i32) / 2,
+                button.pos.1 + button.size.1 / 2,
+            ),
+            label,
+            false,
+            fg,
+            bg,
+        );
+    }
+}
+
+/// Turn a tap on the touch controls into the key press that its button stands for.
+///
+/// The first tap made with a finger turns the touch controls on if they're off, and is otherwise
+/// ignored so that it doesn't land on a button that the player couldn't see yet.  Returns an
+/// error if the touch controls were turned on but the options couldn't be saved.
+pub fn handle_tap(
+    world: &World,
+    inputs: &mut InputBuffer,
+    touch_grid: Option<&TileGrid<GameSym>>,
+) -> Result<(), Error> {
+    if let Some(InputEvent::Tap { pos, touch }) = inputs.get_input() {
+        if !world.borrow::<UniqueView<Options>>().touch_controls {
+            if touch {
+                world.borrow::<UniqueViewMut<Options>>().touch_controls = true;
+                inputs.clear_input();
+                return config::save_options(world);
+            }
+        } else if let Some(cell) = touch_grid.and_then(|grid| grid.view_cell_at(pos)) {
+            if let Some(button) = BUTTONS.iter().find(|b| b.contains(cell)) {
+                inputs.replace_input(InputEvent::Press(button.key));
+            }
+        }
+    }
+
+    Ok(())
+}
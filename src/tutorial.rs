@@ -0,0 +1,261 @@
+use shipyard::{
+    AllStoragesViewMut, Get, IntoIter, Shiperator, UniqueView, UniqueViewMut, View, ViewMut, World,
+};
+
+use crate::{
+    chest,
+    components::{Consumable, Coord, EquipSlot, Equipment, InflictsDamage, Inventory, Monster},
+    experience::{self, Difficulty},
+    journal::Journal,
+    locale::Locale,
+    map::{Map, Rect, Tile},
+    menu_memory::MenuMemory,
+    message::Messages,
+    player::{self, PlayerAlive, PlayerId},
+    quest::Quests,
+    spawn,
+    stats::RunStats,
+    vision, BaseEquipmentLevel, TurnCount, Wins,
+};
+
+/// The tutorial level: '#' is wall, '.' is floor, '>' is the way down, '@' is where the player
+/// starts, ')' is a weapon, '?' is a magic missile scroll and 'g' is a weak monster.
+const LAYOUT: [&str; 7] = [
+    "################################################",
+    "#.......#...........#..........................#",
+    "#.......#...........#..........................#",
+    "#...@.......................g............g..>..#",
+    "#.......#...........#..........................#",
+    "#.......#....)...?..#..........................#",
+    "################################################",
+];
+
+/// Rooms of the tutorial level as (x, y, width, height), matching the layout.
+const ROOMS: [(i32, i32, i32, i32); 3] = [(1, 1, 7, 5), (9, 1, 11, 5), (21, 1, 26, 5)];
+
+/// The steps of the tutorial, in the order the player is walked through them.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Step {
+    Move,
+    PickUp,
+    Equip,
+    Fight,
+    UseScroll,
+    Descend,
+}
+
+impl Step {
+    fn next(self) -> Option<Step> {
+        match self {
+            Step::Move => Some(Step::PickUp),
+            Step::PickUp => Some(Step::Equip),
+            Step::Equip => Some(Step::Fight),
+            Step::Fight => Some(Step::UseScroll),
+            Step::UseScroll => Some(Step::Descend),
+            Step::Descend => None,
+        }
+    }
+
+    fn prompt_key(self) -> &'static str {
+        match self {
+            Step::Move => "tutorial.move",
+            Step::PickUp => "tutorial.pick_up",
+            Step::Equip => "tutorial.equip",
+            Step::Fight => "tutorial.fight",
+            Step::UseScroll => "tutorial.use_scroll",
+            Step::Descend => "tutorial.descend",
+        }
+    }
+
+    /// True if the player has done what this step asks of them.
+    fn is_done(self, world: &World, start_pos: (i32, i32)) -> bool {
+        let player_id = world.borrow::<UniqueView<PlayerId>>().0;
+
+        match self {
+            Step::Move => world.borrow::<View<Coord>>().get(player_id).0 != start_pos.into(),
+            Step::PickUp => {
+                let (inventories, equip_slots) =
+                    world.borrow::<(View<Inventory>, View<EquipSlot>)>();
+
+                inventories
+                    .get(player_id)
+                    .items
+                    .iter()
+                    .any(|&id| matches!(equip_slots.try_get(id), Ok(EquipSlot::Weapon)))
+            }
+            Step::Equip => world
+                .borrow::<View<Equipment>>()
+                .get(player_id)
+                .weapon
+                .is_some(),
+            Step::Fight => monsters_left(world) < 2,
+            Step::UseScroll => {
+                let (consumables, inflicts_damages) =
+                    world.borrow::<(View<Consumable>, View<InflictsDamage>)>();
+
+                monsters_left(world) == 0 || (&consumables, &inflicts_damages).iter().count() == 0
+            }
+            // The way down is watched for by the dungeon mode instead.
+            Step::Descend => false,
+        }
+    }
+}
+
+fn monsters_left(world: &World) -> usize {
+    let (coords, monsters) = world.borrow::<(View<Coord>, View<Monster>)>();
+
+    (&coords, &monsters).iter().count()
+}
+
+/// State of the tutorial, which is only active while a tutorial game is being played.
+pub struct Tutorial {
+    step: Option<Step>,
+    start_pos: (i32, i32),
+}
+
+impl Tutorial {
+    pub fn new() -> Self {
+        Self {
+            step: None,
+            start_pos: (0, 0),
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.step.is_some()
+    }
+
+    /// End the tutorial, if one is being played.
+    pub fn finish(&mut self) {
+        self.step = None;
+    }
+}
+
+/// Set up the tutorial level with a fresh player in place of a new game.
+///
+/// The tutorial is never saved and doesn't count towards the player's statistics or leave bones.
+pub fn setup(world: &World) {
+    world.borrow::<UniqueViewMut<MenuMemory>>().reset();
+    world.borrow::<UniqueViewMut<Messages>>().reset();
+    world.borrow::<UniqueViewMut<Journal>>().reset();
+    world.borrow::<UniqueViewMut<Quests>>().reset();
+    world.borrow::<UniqueViewMut<PlayerAlive>>().0 = true;
+    world.borrow::<UniqueViewMut<Wins>>().0 = 0;
+    world.borrow::<UniqueViewMut<BaseEquipmentLevel>>().0 = 0;
+    world.borrow::<UniqueViewMut<TurnCount>>().0 = 1;
+
+    // Replace the old player with a fresh one.
+    {
+        let player_id = world.borrow::<UniqueView<PlayerId>>().0;
+        spawn::despawn_entity(&mut world.borrow::<AllStoragesViewMut>(), player_id);
+        let new_player_id = world.run(spawn::spawn_player);
+        world.borrow::<UniqueViewMut<PlayerId>>().0 = new_player_id;
+    }
+
+    chest::despawn_chests(world);
+    world.borrow::<UniqueViewMut<RunStats>>().reset(1);
+
+    // Replace the old difficulty tracker with a fresh one at the default settings.
+    {
+        let difficulty_id = world.borrow::<UniqueView<Difficulty>>().id;
+        spawn::despawn_entity(&mut world.borrow::<AllStoragesViewMut>(), difficulty_id);
+        let new_difficulty = Difficulty::new(world.run(spawn::spawn_difficulty));
+        world
+            .borrow::<UniqueViewMut<Difficulty>>()
+            .replace(new_difficulty);
+    }
+
+    let mut start_pos = (0, 0);
+    let mut weapon_pos = (0, 0);
+    let mut scroll_pos = (0, 0);
+    let mut monster_positions = Vec::new();
+
+    {
+        let mut map = world.borrow::<UniqueViewMut<Map>>();
+
+        map.resize(LAYOUT[0].len() as i32, LAYOUT.len() as i32);
+        map.depth = 1;
+
+        for (y, line) in LAYOUT.iter().enumerate() {
+            for (x, c) in line.chars().enumerate() {
+                let pos = (x as i32, y as i32);
+                let tile = match c {
+                    '#' => Tile::Wall,
+                    '>' => Tile::DownStairs,
+                    _ => Tile::Floor,
+                };
+
+                match c {
+                    '@' => start_pos = pos,
+                    ')' => weapon_pos = pos,
+                    '?' => scroll_pos = pos,
+                    'g' => monster_positions.push(pos),
+                    _ => {}
+                }
+
+                map.set_tile(pos.0, pos.1, tile);
+            }
+        }
+
+        map.rooms = ROOMS
+            .iter()
+            .map(|&(x, y, w, h)| Rect::new(x, y, w, h))
+            .collect();
+    }
+
+    world.run(player::add_coords_to_players);
+    {
+        let player_id = world.borrow::<UniqueView<PlayerId>>().0;
+        let mut map = world.borrow::<UniqueViewMut<Map>>();
+        let mut coords = world.borrow::<ViewMut<Coord>>();
+
+        map.place_entity(player_id, start_pos, false);
+        (&mut coords).get(player_id).0 = start_pos.into();
+    }
+    spawn::spawn_tutorial_contents(world, weapon_pos, scroll_pos, &monster_positions);
+    world.run(experience::calc_exp_for_next_depth);
+    world.run(vision::recalculate_fields_of_view);
+
+    {
+        let mut tutorial = world.borrow::<UniqueViewMut<Tutorial>>();
+
+        tutorial.step = Some(Step::Move);
+        tutorial.start_pos = start_pos;
+    }
+
+    let locale = world.borrow::<UniqueView<Locale>>();
+    let mut msgs = world.borrow::<UniqueViewMut<Messages>>();
+
+    msgs.add(locale.get("tutorial.welcome").into());
+    msgs.add(locale.get(Step::Move.prompt_key()).into());
+}
+
+/// Move the tutorial on past every step the player has done, prompting them for the next one.
+pub fn advance(world: &World) {
+    let (mut step, start_pos) = {
+        let tutorial = world.borrow::<UniqueView<Tutorial>>();
+
+        match tutorial.step {
+            Some(step) => (step, tutorial.start_pos),
+            None => return,
+        }
+    };
+    let old_step = step;
+
+    while step.is_done(world, start_pos) {
+        match step.next() {
+            Some(next) => step = next,
+            None => break,
+        }
+    }
+
+    if step != old_step {
+        world.borrow::<UniqueViewMut<Tutorial>>().step = Some(step);
+
+        let locale = world.borrow::<UniqueView<Locale>>();
+
+        world
+            .borrow::<UniqueViewMut<Messages>>()
+            .add(locale.get(step.prompt_key()).into());
+    }
+}
@@ -1,31 +1,137 @@
-use shipyard::{Get, UniqueView, View, World};
+use serde::{Deserialize, Serialize};
+use shipyard::{EntityId, Get, UniqueView, View, World};
 
 use crate::{
-    chunked::ChunkedMapGrid,
-    components::{CombatStats, Equipment, Experience, Inventory, Name, Renderable},
+    chunked::{ChunkedMapGrid, FogStyle},
+    components::{CombatStats, EquipSlot, Equipment, Experience, Inventory, Name, Renderable},
+    encumbrance,
+    experience::DifficultySettings,
     gamesym::GameSym,
-    hunger,
+    hunger, item,
+    locale::Locale,
     map::Map,
-    message::Messages,
+    message::{self, Messages, MsgFilter},
     player::PlayerId,
-    TurnCount,
+    render, TurnCount, Wins,
 };
 use ruggrogue::{
     util::{Color, Position, Size},
     Symbol, TileGrid, Tileset,
 };
 
+/// Background opacity of menus shown directly over the dungeon, letting the map show through dimly.
+pub const OVERLAY_BG_ALPHA: u8 = 208;
+
 pub const SELECTED_BG: Color = Color {
     r: 0,
     g: 128,
     b: 255,
 };
 
+#[derive(Clone, Copy, Deserialize, Serialize)]
 pub struct Options {
     pub tileset: u32,
     pub font: u32,
     pub map_zoom: u32,
     pub text_zoom: u32,
+    /// Pick the largest map zoom that still shows the whole field of view, ignoring map_zoom.
+    #[serde(default)]
+    pub map_zoom_fit: bool,
+    /// Remap colors to a palette that is easier to tell apart with color vision deficiencies.
+    #[serde(default)]
+    pub colorblind: bool,
+    /// Draw symbols that differ only in color with distinct glyphs instead.
+    #[serde(default)]
+    pub distinct_glyphs: bool,
+    /// Draw messages with brighter colors.
+    #[serde(default)]
+    pub high_contrast: bool,
+    /// How known parts of the map outside of the field of view are drawn.
+    #[serde(default)]
+    pub fog_style: FogStyle,
+    /// Show damage and healing numbers floating up from entities on the map.
+    #[serde(default = "default_popup_numbers")]
+    pub popup_numbers: bool,
+    /// Animate menus and screens as they open and close.
+    #[serde(default = "default_transitions")]
+    pub transitions: bool,
+    /// Categories of messages to show in the sidebar.
+    #[serde(default)]
+    pub msg_filter: MsgFilter,
+    /// Show the message panel along the bottom of the window instead of in the sidebar.
+    #[serde(default)]
+    pub msg_dock_bottom: bool,
+    /// Lines of messages to show in the message panel, or zero to pick automatically.
+    #[serde(default)]
+    pub msg_lines: u32,
+    /// Save the game every this many turns, or never if zero.
+    #[serde(default = "default_autosave_turns")]
+    pub autosave_turns: u32,
+    /// Open the options menu when the game window loses focus.
+    #[serde(default)]
+    pub pause_on_focus_loss: bool,
+    /// Spend a turn on each item when picking up or dropping several at once.
+    #[serde(default)]
+    pub turn_per_item: bool,
+    /// Wear down weapons and armor with use until they break.
+    #[serde(default = "default_durability")]
+    pub durability: bool,
+    /// Index into the list of languages that locale catalogs were found for.  The config file
+    /// keeps the language code instead, since the index shifts as catalogs are added; older
+    /// config files that still have the index are read as-is.
+    #[serde(default, skip_serializing)]
+    pub language: u32,
+    /// Difficulty last picked when starting a new game.
+    #[serde(default)]
+    pub difficulty: DifficultySettings,
+    /// Start new games with a pet dog at the player's side.
+    #[serde(default)]
+    pub starting_pet: bool,
+    /// Show on-screen touch controls over the map.
+    #[serde(default)]
+    pub touch_controls: bool,
+    /// Record each turn of play to a file that can be played back in a terminal.
+    #[serde(default)]
+    pub record_sessions: bool,
+    /// Give tips about how to play the first time they become useful.
+    #[serde(default = "default_hints")]
+    pub hints: bool,
+    /// Synchronize drawing with the refresh rate of the display; takes effect on restart.
+    #[serde(default)]
+    pub vsync: bool,
+}
+
+/// Largest map zoom that can be picked, either manually or by fitting the view.
+pub const MAX_MAP_ZOOM: u32 = 4;
+
+/// Message panel line counts that can be picked in the options menu, where zero picks
+/// automatically.
+pub const MSG_LINES_CHOICES: [u32; 6] = [0, 4, 6, 8, 12, 16];
+
+/// Lines of messages shown in a message panel docked at the bottom if picked automatically.
+const AUTO_BOTTOM_MSG_LINES: u32 = 6;
+
+/// Autosave intervals that can be picked in the options menu.
+pub const AUTOSAVE_TURNS_CHOICES: [u32; 5] = [0, 50, 100, 200, 500];
+
+pub fn default_autosave_turns() -> u32 {
+    100
+}
+
+pub fn default_popup_numbers() -> bool {
+    true
+}
+
+pub fn default_transitions() -> bool {
+    true
+}
+
+pub fn default_durability() -> bool {
+    true
+}
+
+pub fn default_hints() -> bool {
+    true
 }
 
 pub const MAP_GRID: usize = 0;
@@ -36,6 +142,10 @@ pub const MSG_GRID: usize = 4;
 
 fn draw_status<Y: Symbol>(world: &World, grid: &mut TileGrid<Y>) {
     let player_id = world.borrow::<UniqueView<PlayerId>>();
+    let locale = world.borrow::<UniqueView<Locale>>();
+    let label = |grid: &mut TileGrid<Y>, y: i32, key: &str| {
+        grid.print_color((2, y), locale.get(key), true, Color::LIGHT_GRAY, None);
+    };
 
     // Draw the box one tile higher than the grid so it runs off the bottom.
     grid.draw_box(
@@ -55,15 +165,14 @@ fn draw_status<Y: Symbol>(world: &World, grid: &mut TileGrid<Y>) {
         grid.put_char((3 + player_name.len() as i32, 0), ' ');
     }
 
-    grid.print_color(
-        (grid.width() as i32 - 17, 0),
-        " [esc] Options ",
-        true,
-        None,
-        None,
-    );
-    for x in 0..3 {
-        grid.recolor_pos((grid.width() as i32 - 15 + x, 0), Color::YELLOW, None);
+    {
+        let options_label = locale.get("ui.options");
+        let options_x = grid.width() as i32 - 2 - options_label.chars().count() as i32;
+
+        grid.print_color((options_x, 0), options_label, true, None, None);
+        for x in 0..3 {
+            grid.recolor_pos((options_x + 2 + x, 0), Color::YELLOW, None);
+        }
     }
 
     // Level and experience.
@@ -71,10 +180,10 @@ fn draw_status<Y: Symbol>(world: &World, grid: &mut TileGrid<Y>) {
         let exps = world.borrow::<View<Experience>>();
         let player_exp = exps.get(player_id.0);
 
-        grid.print_color((2, 1), "Level:", true, Color::LIGHT_GRAY, None);
+        label(grid, 1, "ui.level");
         grid.print((14, 1), &format!("{}", player_exp.level));
 
-        grid.print_color((2, 2), "Experience:", true, Color::LIGHT_GRAY, None);
+        label(grid, 2, "ui.experience");
         grid.draw_bar(
             false,
             (14, 2),
@@ -85,9 +194,10 @@ fn draw_status<Y: Symbol>(world: &World, grid: &mut TileGrid<Y>) {
             Color::PURPLE,
             None,
         );
+        // Show progress towards the next level; experience beyond it carries over on level up.
         grid.print_color(
             (14, 2),
-            &format!("{}", player_exp.base + player_exp.exp),
+            &format!("{} / {}", player_exp.exp, player_exp.next),
             false,
             Color::WHITE,
             None,
@@ -99,7 +209,7 @@ fn draw_status<Y: Symbol>(world: &World, grid: &mut TileGrid<Y>) {
         let combat_stats = world.borrow::<View<CombatStats>>();
         let player_stats = combat_stats.get(player_id.0);
 
-        grid.print_color((2, 3), "Health:", true, Color::LIGHT_GRAY, None);
+        label(grid, 3, "ui.health");
         grid.draw_bar(
             false,
             (14, 3),
@@ -118,10 +228,10 @@ fn draw_status<Y: Symbol>(world: &World, grid: &mut TileGrid<Y>) {
             None,
         );
 
-        grid.print_color((2, 4), "Attack:", true, Color::LIGHT_GRAY, None);
+        label(grid, 4, "ui.attack");
         grid.print((14, 4), &format!("{:+.0}", player_stats.attack.round()));
 
-        grid.print_color((2, 5), "Defense:", true, Color::LIGHT_GRAY, None);
+        label(grid, 5, "ui.defense");
         grid.print((14, 5), &format!("{:+.0}", player_stats.defense.round()));
     }
 
@@ -129,19 +239,44 @@ fn draw_status<Y: Symbol>(world: &World, grid: &mut TileGrid<Y>) {
     {
         let (hunger_label, hunger_fg, hunger_bg) = world.run(hunger::player_hunger_label);
 
-        grid.print_color((2, 6), "Hunger:", true, Color::LIGHT_GRAY, None);
-        grid.print_color((14, 6), hunger_label, true, hunger_fg, hunger_bg);
+        label(grid, 6, "ui.hunger");
+        grid.print_color(
+            (14, 6),
+            locale.get(hunger_label),
+            true,
+            hunger_fg,
+            hunger_bg,
+        );
+
+        if encumbrance::is_burdened(world, player_id.0) {
+            grid.print_color(
+                (26, 6),
+                locale.get("ui.burdened"),
+                true,
+                Color::ORANGE,
+                None,
+            );
+        }
     }
 
     // Depth
-    grid.print_color((2, 7), "Depth:", true, Color::LIGHT_GRAY, None);
+    label(grid, 7, "ui.depth");
     grid.print(
         (14, 7),
         &format!("{}", world.borrow::<UniqueView<Map>>().depth),
     );
 
+    // New Game Plus level
+    {
+        let wins = world.borrow::<UniqueView<Wins>>().0;
+
+        if wins > 0 {
+            grid.print_color((26, 7), &format!("NG+{}", wins), true, Color::GREEN, None);
+        }
+    }
+
     // Turn
-    grid.print_color((2, 8), "Turn:", true, Color::LIGHT_GRAY, None);
+    label(grid, 8, "ui.turn");
     grid.print(
         (14, 8),
         &format!("{}", world.borrow::<UniqueView<TurnCount>>().0),
@@ -150,6 +285,7 @@ fn draw_status<Y: Symbol>(world: &World, grid: &mut TileGrid<Y>) {
 
 fn draw_item_info(world: &World, grid: &mut TileGrid<GameSym>) {
     let player_id = world.borrow::<UniqueView<PlayerId>>();
+    let locale = world.borrow::<UniqueView<Locale>>();
 
     // Draw the box one tile higher than the grid so it runs off the bottom.
     grid.draw_box(
@@ -168,10 +304,13 @@ fn draw_item_info(world: &World, grid: &mut TileGrid<GameSym>) {
 
         grid.print(
             (2, 0),
-            &format!(
-                " [i] Inventory ({} {}) ",
-                item_count,
-                if item_count == 1 { "item" } else { "items" },
+            &locale.format(
+                if item_count == 1 {
+                    "ui.inventory_item"
+                } else {
+                    "ui.inventory_items"
+                },
+                &[("count", &item_count)],
             ),
         );
         grid.recolor_pos((4, 0), Color::YELLOW, None);
@@ -182,62 +321,90 @@ fn draw_item_info(world: &World, grid: &mut TileGrid<GameSym>) {
         let equipments = world.borrow::<View<Equipment>>();
         let names = world.borrow::<View<Name>>();
         let renderables = world.borrow::<View<Renderable>>();
+        let options = world.borrow::<UniqueView<Options>>();
         let player_equipment = equipments.get(player_id.0);
-
-        grid.print_color((2, 1), "Weapon:", true, Color::LIGHT_GRAY, None);
+        let weapon_label = locale.get("inventory.weapon");
+        let armor_label = locale.get("inventory.armor");
+        // Line the equipment up just past the wider of the two labels.
+        let equip_x = 3 + weapon_label
+            .chars()
+            .count()
+            .max(armor_label.chars().count()) as i32;
+
+        grid.print_color((2, 1), weapon_label, true, Color::LIGHT_GRAY, None);
         if let Some(weapon) = player_equipment.weapon {
             let x = if let Ok(render) = renderables.try_get(weapon) {
-                grid.put_sym_color((10, 1), render.sym, render.fg, render.bg);
-                12
+                render::put_renderable(grid, (equip_x, 1), render, &options);
+                equip_x + 2
             } else {
-                10
+                equip_x
             };
             grid.print((x, 1), &names.get(weapon).0);
         } else {
-            grid.print_color((10, 1), "-- nothing --", true, Color::GRAY, None);
+            grid.print_color(
+                (equip_x, 1),
+                locale.get("inventory.nothing"),
+                true,
+                Color::GRAY,
+                None,
+            );
         }
 
-        grid.print_color((2, 2), "Armor:", true, Color::LIGHT_GRAY, None);
+        grid.print_color((2, 2), armor_label, true, Color::LIGHT_GRAY, None);
         if let Some(armor) = player_equipment.armor {
             let x = if let Ok(render) = renderables.try_get(armor) {
-                grid.put_sym_color((10, 2), render.sym, render.fg, render.bg);
-                12
+                render::put_renderable(grid, (equip_x, 2), render, &options);
+                equip_x + 2
             } else {
-                10
+                equip_x
             };
             grid.print((x, 2), &names.get(armor).0);
         } else {
-            grid.print_color((10, 2), "-- nothing --", true, Color::GRAY, None);
+            grid.print_color(
+                (equip_x, 2),
+                locale.get("inventory.nothing"),
+                true,
+                Color::GRAY,
+                None,
+            );
         }
     }
 }
 
-pub fn draw_msg_frame<Y: Symbol>(msg_frame_grid: &mut TileGrid<Y>, view_mode: bool) {
+pub fn draw_msg_frame<Y: Symbol>(world: &World, msg_frame_grid: &mut TileGrid<Y>, view_mode: bool) {
     msg_frame_grid.draw_box(
         (0, 0),
         (msg_frame_grid.width(), msg_frame_grid.height()),
         Color::GRAY,
         Color::BLACK,
     );
-    msg_frame_grid.put_char_color((0, 0), '├', None, None);
-    msg_frame_grid.put_char_color((msg_frame_grid.width() as i32 - 1, 0), '┤', None, None);
+    // Join up with the box of the item grid above when in the sidebar.
+    if !world.borrow::<UniqueView<Options>>().msg_dock_bottom {
+        msg_frame_grid.put_char_color((0, 0), '├', None, None);
+        msg_frame_grid.put_char_color((msg_frame_grid.width() as i32 - 1, 0), '┤', None, None);
+    }
+
+    let locale = world.borrow::<UniqueView<Locale>>();
 
     msg_frame_grid.print_color(
         (2, 0),
-        " Messages ",
+        locale.get("ui.messages"),
         true,
         if view_mode { Color::GRAY } else { Color::WHITE },
         Color::BLACK,
     );
 
+    let view_mode_label = locale.get("ui.view_mode");
+    let view_mode_x = msg_frame_grid.width() as i32 - 2 - view_mode_label.chars().count() as i32;
+
     msg_frame_grid.print_color(
-        (msg_frame_grid.width() as i32 - 17, 0),
-        " [v] View Mode ",
+        (view_mode_x, 0),
+        view_mode_label,
         true,
         if view_mode { Color::WHITE } else { Color::GRAY },
         Color::BLACK,
     );
-    msg_frame_grid.recolor_pos((msg_frame_grid.width() as i32 - 15, 0), Color::YELLOW, None);
+    msg_frame_grid.recolor_pos((view_mode_x + 2, 0), Color::YELLOW, None);
 }
 
 fn draw_messages<Y>(world: &World, grid: &mut TileGrid<Y>, active: bool, min_y: i32, max_y: i32)
@@ -248,31 +415,63 @@ where
     let width = grid.width().saturating_sub(2).max(1) as usize;
     let mut y = min_y;
     let mut skip_y = min_y;
-    let (fg, highlight_fg) = if active {
-        (Color::GRAY, Color::WHITE)
-    } else {
-        (Color::DARK_GRAY, Color::GRAY)
+    let options = world.borrow::<UniqueView<Options>>();
+    // Brightness of messages out of 4, for (normal, highlighted) messages.
+    let (fg_quarters, highlight_fg_quarters) = match (active, options.high_contrast) {
+        (true, false) => (2, 4),
+        (true, true) => (3, 4),
+        (false, false) => (1, 2),
+        (false, true) => (2, 3),
     };
+    let mut prev_blank = false;
 
-    for (message, highlighted) in messages.rev_iter() {
+    for (message, category, highlighted) in messages.rev_iter() {
         if y > max_y {
             break;
         }
 
         if message.is_empty() {
-            y += 1;
+            // Avoid runs of blank lines left behind by hidden messages.
+            if !prev_blank {
+                y += 1;
+                prev_blank = true;
+            }
             continue;
         }
 
-        let msg_fg = if highlighted { highlight_fg } else { fg };
+        if !options.msg_filter.shows(category) {
+            continue;
+        }
+
+        prev_blank = false;
+
+        let quarters = if highlighted {
+            highlight_fg_quarters
+        } else {
+            fg_quarters
+        };
+        let msg_color = |color: Color| {
+            let c = render::palette_color(&options, color);
+
+            Color {
+                r: (c.r as u32 * quarters / 4) as u8,
+                g: (c.g as u32 * quarters / 4) as u8,
+                b: (c.b as u32 * quarters / 4) as u8,
+            }
+        };
+        let msg_fg = msg_color(category.color());
 
         grid.put_char_color((0, y), '>', msg_fg, None);
-        for line in ruggrogue::word_wrap(message, width) {
+        for line in message::wrap_markup(&message, width) {
             if skip_y > 0 {
                 skip_y -= 1;
                 continue;
             }
-            grid.print_color((2, y), line, true, msg_fg, None);
+
+            for (x, (ch, color)) in line.into_iter().enumerate() {
+                let fg = color.map_or(msg_fg, msg_color);
+                grid.put_char_color((2 + x as i32, y), ch, fg, None);
+            }
             y += 1;
             if y > max_y {
                 break;
@@ -313,6 +512,8 @@ pub fn prepare_main_grids<Y: Symbol>(
         tileset: map_tileset_index,
         font: ui_tileset_index,
         text_zoom,
+        msg_dock_bottom,
+        msg_lines,
         ..
     } = *world.borrow::<UniqueView<Options>>();
     let ui_tileset = &tilesets
@@ -320,19 +521,40 @@ pub fn prepare_main_grids<Y: Symbol>(
         .unwrap_or(&tilesets[0]);
 
     let sidebar_w = 36;
-    let sidebar_px_w = sidebar_w * ui_tileset.tile_width() * text_zoom;
+    let tile_px_w = ui_tileset.tile_width() * text_zoom;
+    let tile_px_h = ui_tileset.tile_height() * text_zoom;
+    let sidebar_px_w = sidebar_w * tile_px_w;
+    let window_rows = window_size.h / tile_px_h;
 
     let new_status_size = Size {
         w: sidebar_w,
         h: 10,
     };
     let new_item_size = Size { w: sidebar_w, h: 4 };
-    let new_msg_frame_size = Size {
-        w: sidebar_w,
-        h: (window_size.h / (ui_tileset.tile_height() * text_zoom))
-            .saturating_sub(new_status_size.h + new_item_size.h)
-            .max(4),
+    let sidebar_rows_left = window_rows.saturating_sub(new_status_size.h + new_item_size.h);
+    let new_msg_frame_size = if msg_dock_bottom {
+        let msg_lines = if msg_lines == 0 {
+            AUTO_BOTTOM_MSG_LINES
+        } else {
+            msg_lines
+        };
+
+        Size {
+            w: (window_size.w / tile_px_w).max(sidebar_w),
+            h: (msg_lines + 2).min(sidebar_rows_left).max(3),
+        }
+    } else if msg_lines == 0 {
+        Size {
+            w: sidebar_w,
+            h: sidebar_rows_left.max(4),
+        }
+    } else {
+        Size {
+            w: sidebar_w,
+            h: (msg_lines + 2).min(sidebar_rows_left).max(4),
+        }
     };
+    let msg_frame_px_h = new_msg_frame_size.h * tile_px_h;
     let new_msg_size = Size {
         w: new_msg_frame_size.w.saturating_sub(2).max(1),
         h: new_msg_frame_size.h.saturating_sub(2).max(1).min(100),
@@ -388,7 +610,11 @@ pub fn prepare_main_grids<Y: Symbol>(
         Position { x: 0, y: 0 },
         Size {
             w: window_size.w.saturating_sub(sidebar_px_w).max(1),
-            h: window_size.h,
+            h: if msg_dock_bottom {
+                window_size.h.saturating_sub(msg_frame_px_h).max(1)
+            } else {
+                window_size.h
+            },
         },
     );
 
@@ -415,13 +641,20 @@ pub fn prepare_main_grids<Y: Symbol>(
     grids[ITEM_GRID].view.zoom = text_zoom;
 
     grids[MSG_FRAME_GRID].set_tileset(tilesets, ui_tileset_index as usize);
-    grids[MSG_FRAME_GRID].view.pos = Position {
-        x: window_size.w as i32 - sidebar_px_w as i32,
-        y: grids[ITEM_GRID].view.pos.y + grids[ITEM_GRID].view.size.h as i32,
+    grids[MSG_FRAME_GRID].view.pos = if msg_dock_bottom {
+        Position {
+            x: 0,
+            y: window_size.h as i32 - msg_frame_px_h as i32,
+        }
+    } else {
+        Position {
+            x: window_size.w as i32 - sidebar_px_w as i32,
+            y: grids[ITEM_GRID].view.pos.y + grids[ITEM_GRID].view.size.h as i32,
+        }
     };
     grids[MSG_FRAME_GRID].view.size = Size {
-        w: sidebar_px_w,
-        h: grids[MSG_FRAME_GRID].height() * ui_tileset.tile_height() * text_zoom,
+        w: grids[MSG_FRAME_GRID].width() * tile_px_w,
+        h: msg_frame_px_h,
     };
     grids[MSG_FRAME_GRID].view.zoom = text_zoom;
 
@@ -436,3 +669,60 @@ pub fn prepare_main_grids<Y: Symbol>(
     };
     grids[MSG_GRID].view.zoom = text_zoom;
 }
+
+/// A short readout of how an equippable item compares to what the player has equipped, colored by
+/// whether it would be an improvement.
+pub fn equip_comparison_text(world: &World, item_id: EntityId) -> Option<(String, Color)> {
+    let player_id = world.borrow::<UniqueView<PlayerId>>().0;
+    let item::EquipComparison {
+        slot,
+        delta,
+        equipped,
+    } = item::compare_to_equipped(world, player_id, item_id)?;
+    let locale = world.borrow::<UniqueView<Locale>>();
+    let stat = match slot {
+        EquipSlot::Weapon => "ui.compare_attack",
+        EquipSlot::Armor => "ui.compare_defense",
+    };
+    let versus = equipped.map_or_else(
+        || locale.get("game_over.nothing").to_string(),
+        |id| world.borrow::<View<Name>>().get(id).0.clone(),
+    );
+    let color = if delta > 0.0 {
+        Color::GREEN
+    } else if delta < 0.0 {
+        Color::RED
+    } else {
+        Color::GRAY
+    };
+
+    Some((
+        locale.format(
+            stat,
+            &[("delta", &format!("{:+.1}", delta)), ("versus", &versus)],
+        ),
+        color,
+    ))
+}
+
+/// Scroll a menu grid that is too tall to fit on screen so that the given row can be seen, and draw
+/// a scroll bar along its right edge to show that there's more to see.
+pub fn scroll_menu_grid<Y: Symbol>(grid: &mut TileGrid<Y>, row: i32, fg: Color, bg: Color) {
+    grid.view_scroll_to_row(row);
+
+    let (first_row, last_row) = grid.view_visible_rows();
+    let visible_rows = last_row - first_row + 1;
+
+    if visible_rows < grid.height() as i32 {
+        grid.draw_bar(
+            true,
+            (grid.width() as i32 - 1, first_row),
+            visible_rows,
+            first_row,
+            visible_rows,
+            grid.height() as i32,
+            fg,
+            bg,
+        );
+    }
+}
@@ -1,37 +1,221 @@
-use shipyard::{IntoIter, Shiperator, UniqueViewMut, View, ViewMut};
+use shipyard::{Get, IntoIter, Shiperator, UniqueViewMut, View, ViewMut};
 
 use crate::{
-    components::{Coord, FieldOfView, Player},
-    map::{Map, Tile},
+    components::{Coord, FieldOfView, Item, Player, Renderable},
+    map::{Map, Remembered, TileFlags},
 };
-use ruggrogue::FovShape;
+use ruggrogue::{FovAlgorithm, FovBuffers, FovShape};
 
 pub fn recalculate_fields_of_view(
     mut map: UniqueViewMut<Map>,
     coords: View<Coord>,
     mut fovs: ViewMut<FieldOfView>,
+    items: View<Item>,
     players: View<Player>,
+    renderables: View<Renderable>,
 ) {
+    // Share calculation buffers between all fields of view.
+    let mut buffers = FovBuffers::default();
+
     for (id, (coord, mut fov)) in (&coords, &mut fovs).iter().with_id() {
-        if fov.dirty {
+        if fov.needs_recalculation(coord.0.into()) {
             fov.center = coord.0.into();
             fov.tiles.zero_out_bits();
 
-            // Update field of view.
-            for (x, y, symmetric) in
-                ruggrogue::field_of_view(&*map, coord.0.into(), fov.range, FovShape::CirclePlus)
-            {
-                if symmetric || matches!(map.get_tile(x, y), &Tile::Wall) {
+            // Update field of view, keeping it symmetric so that anything in view can see back.
+            let mut fov_iter = ruggrogue::field_of_view_reusing(
+                &*map,
+                coord.0.into(),
+                fov.range,
+                FovShape::CirclePlus,
+                buffers,
+            )
+            .algorithm(FovAlgorithm::Symmetric);
+
+            for (x, y, _) in &mut fov_iter {
+                if map.is_playable(x, y) {
                     fov.set((x, y), true);
                 }
             }
 
-            fov.dirty = false;
+            buffers = fov_iter.into_buffers();
+            fov.mark_calculated();
 
-            // Update map seen tiles if this field of view belongs to a player.
+            // Update map seen tiles if this field of view belongs to a player, remembering the
+            // items seen lying about and forgetting any that are gone.
             if players.contains(id) {
                 fov.mark_seen(&mut map.seen);
+
+                for (x, y) in fov.iter() {
+                    let top_item = map
+                        .iter_entities_at(x, y)
+                        .filter(|&e| items.contains(e))
+                        .filter_map(|e| renderables.try_get(e).ok())
+                        .last()
+                        .map(|render| Remembered {
+                            sym: render.sym,
+                            fg: render.fg,
+                        });
+
+                    map.set_flags(x, y, TileFlags::ITEM_SEEN, top_item.is_some());
+                    map.remember(x, y, top_item);
+                }
             }
+        } else {
+            // Nothing that the tiles depend on has changed.
+            fov.dirty = false;
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{map, prefab, GameSeed};
+    use ruggrogue::{BoundedMap, ViewableField};
+    use std::{
+        collections::HashSet,
+        time::{Duration, Instant},
+    };
+
+    const SEEDS: u64 = 6;
+    const DEPTHS: [i32; 2] = [1, 5];
+    const RANGE: i32 = 8;
+
+    /// Every transparent tile that an entity could be standing on.
+    fn open_tiles(map: &Map) -> Vec<(i32, i32)> {
+        let (x1, y1, x2, y2) = map.bounds();
+
+        (y1..=y2)
+            .flat_map(|y| (x1..=x2).map(move |x| (x, y)))
+            .filter(|&(x, y)| map.is_playable(x, y) && !map.is_opaque(x, y))
+            .collect()
+    }
+
+    fn check_symmetry(algorithm: FovAlgorithm, name: &str) {
+        let prefabs = prefab::load_prefabs();
+
+        for seed in 0..SEEDS {
+            for &depth in DEPTHS.iter() {
+                let mut map = Map::new(80, 50);
+
+                map.depth = depth;
+                map::generate_map(&mut map, &GameSeed(seed), &prefabs, true);
+
+                let tiles = open_tiles(&map);
+                let seen = tiles
+                    .iter()
+                    .map(|&pos| {
+                        ruggrogue::field_of_view(&map, pos, RANGE, FovShape::CirclePlus)
+                            .algorithm(algorithm)
+                            .map(|(x, y, _)| (x, y))
+                            .collect::<HashSet<_>>()
+                    })
+                    .collect::<Vec<_>>();
+
+                for (a, a_seen) in tiles.iter().zip(seen.iter()) {
+                    for (b, b_seen) in tiles.iter().zip(seen.iter()) {
+                        assert_eq!(
+                            a_seen.contains(b),
+                            b_seen.contains(a),
+                            "{} seed {} depth {}: {:?} and {:?} don't see each other alike",
+                            name,
+                            seed,
+                            depth,
+                            a,
+                            b,
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn symmetric_fov_is_symmetric() {
+        check_symmetry(FovAlgorithm::Symmetric, "symmetric");
+    }
+
+    #[test]
+    fn permissive_fov_is_symmetric() {
+        check_symmetry(FovAlgorithm::Permissive, "permissive");
+    }
+
+    #[test]
+    fn digital_fov_is_symmetric() {
+        check_symmetry(FovAlgorithm::Digital, "digital");
+    }
+
+    /// Run with `cargo test --release -- --ignored --nocapture fov_benchmark`.
+    #[test]
+    #[ignore]
+    fn fov_benchmark() {
+        let prefabs = prefab::load_prefabs();
+        let mut map = Map::new(80, 50);
+
+        map.depth = DEPTHS[1];
+        map::generate_map(&mut map, &GameSeed(0), &prefabs, true);
+
+        let tiles = open_tiles(&map);
+        // Roughly the part of the map that fits on screen around a player in the middle of it.
+        let clip = (20, 13, 59, 36);
+        let in_clip =
+            |&(x, y): &(i32, i32)| x >= clip.0 && y >= clip.1 && x <= clip.2 && y <= clip.3;
+        let mut fresh = Duration::ZERO;
+        let mut reused = Duration::ZERO;
+        let mut clipped = Duration::ZERO;
+        let mut buffers = FovBuffers::default();
+
+        for &pos in tiles.iter() {
+            let start = Instant::now();
+            let fresh_seen = ruggrogue::field_of_view(&map, pos, RANGE, FovShape::CirclePlus)
+                .algorithm(FovAlgorithm::Symmetric)
+                .map(|(x, y, _)| (x, y))
+                .collect::<HashSet<_>>();
+            fresh += start.elapsed();
+
+            let start = Instant::now();
+            let mut fov_iter =
+                ruggrogue::field_of_view_reusing(&map, pos, RANGE, FovShape::CirclePlus, buffers)
+                    .algorithm(FovAlgorithm::Symmetric);
+            let reused_seen = (&mut fov_iter)
+                .map(|(x, y, _)| (x, y))
+                .collect::<HashSet<_>>();
+            buffers = fov_iter.into_buffers();
+            reused += start.elapsed();
+
+            let start = Instant::now();
+            let mut fov_iter =
+                ruggrogue::field_of_view_reusing(&map, pos, RANGE, FovShape::CirclePlus, buffers)
+                    .algorithm(FovAlgorithm::Symmetric)
+                    .clip(clip);
+            let clipped_seen = (&mut fov_iter)
+                .map(|(x, y, _)| (x, y))
+                .collect::<HashSet<_>>();
+            buffers = fov_iter.into_buffers();
+            clipped += start.elapsed();
+
+            assert_eq!(fresh_seen, reused_seen);
+            assert_eq!(
+                fresh_seen
+                    .into_iter()
+                    .filter(in_clip)
+                    .collect::<HashSet<_>>(),
+                clipped_seen,
+            );
+        }
+
+        let num_fovs = tiles.len() as u32;
+
+        eprintln!(
+            "{} fields of view of range {}: {:?} each with fresh buffers, {:?} each with reused \
+             buffers and {:?} each clipped to {:?}",
+            num_fovs,
+            RANGE,
+            fresh / num_fovs,
+            reused / num_fovs,
+            clipped / num_fovs,
+            clip,
+        );
+    }
+}